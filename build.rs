@@ -5,12 +5,25 @@
 //! 2. The .NET SDK is available
 //!
 //! If the .NET SDK isn't available, it provides helpful instructions.
+//!
+//! With the `bundled` feature, a prebuilt library is downloaded and
+//! checksum-verified instead of being built. With the `embed` feature, the
+//! library directory is then packed into the crate.
 
 use std::env;
 use std::path::PathBuf;
 use std::process::Command;
 
 fn main() {
+    provide_native_library();
+
+    #[cfg(feature = "embed")]
+    embed::pack(current_rid(), native_lib_name());
+}
+
+/// Find, fetch or build the native library for the current platform
+#[allow(clippy::too_many_lines)] // Linear sequence of build steps
+fn provide_native_library() {
     // Set rerun triggers for .NET source files
     println!("cargo:rerun-if-changed=dotnet/src/");
     println!("cargo:rerun-if-changed=dotnet/KqlLanguageFfi.csproj");
@@ -50,6 +63,12 @@ fn main() {
         return;
     }
 
+    // Fetch the prebuilt library instead of building it
+    #[cfg(feature = "bundled")]
+    if bundled::provide(rid, lib_name) {
+        return;
+    }
+
     // Native library doesn't exist - try to build it
     println!("cargo:warning=Native library not found, attempting to build...");
 
@@ -216,8 +235,7 @@ fn is_dotnet_available() -> bool {
     Command::new("dotnet")
         .arg("--version")
         .output()
-        .map(|output| output.status.success())
-        .unwrap_or(false)
+        .is_ok_and(|output| output.status.success())
 }
 
 /// Print instructions for installing .NET SDK
@@ -239,7 +257,7 @@ fn print_dotnet_instructions(rid: &str, lib_name: &str) {
     println!("cargo:warning=2. Set KQL_LANGUAGE_TOOLS_PATH to a pre-built library:");
     println!("cargo:warning=   export KQL_LANGUAGE_TOOLS_PATH=/path/to/{lib_name}");
     println!("cargo:warning=");
-    println!("cargo:warning=3. Download pre-built binaries from releases (if available)");
+    println!("cargo:warning=3. Enable the `bundled` feature to download a prebuilt library");
     println!("cargo:warning=");
     println!("cargo:warning=Target platform: {rid} ({lib_name})");
     println!("cargo:warning======================================================");
@@ -255,3 +273,201 @@ fn print_manual_build_instructions(rid: &str) {
     println!("cargo:warning=Or use the shell script (macOS/Linux):");
     println!("cargo:warning=  cd dotnet && ./build.sh {rid}");
 }
+
+/// Prebuilt native library download (`bundled` feature)
+#[cfg(feature = "bundled")]
+mod bundled {
+    use sha2::{Digest, Sha256};
+    use std::env;
+    use std::fmt::Write;
+    use std::io::Read;
+    use std::path::PathBuf;
+
+    /// Overrides the archive URL, e.g. for a mirror
+    const URL_ENV: &str = "KQL_LANGUAGE_TOOLS_BUNDLED_URL";
+
+    /// Overrides the expected SHA-256 of the archive
+    const SHA256_ENV: &str = "KQL_LANGUAGE_TOOLS_BUNDLED_SHA256";
+
+    /// Pinned checksums, in `sha256sum` format
+    const CHECKSUMS: &str = include_str!("dotnet/bundled-checksums.txt");
+
+    /// Fetch the library and point the crate at it, returning whether that worked
+    pub fn provide(rid: &str, lib_name: &str) -> bool {
+        match fetch(rid, lib_name) {
+            Ok(dir) => {
+                println!(
+                    "cargo:rustc-env=KQL_LANGUAGE_TOOLS_BUNDLED_DIR={}",
+                    dir.display()
+                );
+                true
+            }
+            Err(e) => {
+                println!("cargo:warning=Failed to fetch bundled native library: {e}");
+                false
+            }
+        }
+    }
+
+    /// Download, verify and unpack the archive for `rid` into `OUT_DIR`
+    ///
+    /// Returns the directory holding the library.
+    fn fetch(rid: &str, lib_name: &str) -> Result<PathBuf, String> {
+        println!("cargo:rerun-if-env-changed={URL_ENV}");
+        println!("cargo:rerun-if-env-changed={SHA256_ENV}");
+        println!("cargo:rerun-if-changed=dotnet/bundled-checksums.txt");
+
+        let out_dir = PathBuf::from(env::var("OUT_DIR").map_err(|e| e.to_string())?);
+        let dir = out_dir.join("native").join(rid);
+        if dir.join(lib_name).exists() {
+            return Ok(dir);
+        }
+
+        let archive = format!("kql-language-tools-native-{rid}.tar.gz");
+        let expected = env::var(SHA256_ENV)
+            .ok()
+            .or_else(|| pinned_checksum(&archive))
+            .ok_or_else(|| format!("no checksum pinned for {archive}"))?;
+        let url = env::var(URL_ENV).unwrap_or_else(|_| {
+            format!(
+                "https://github.com/dolly-parseton/kql-language-tools/releases/download/v{}/{archive}",
+                env!("CARGO_PKG_VERSION")
+            )
+        });
+
+        println!("cargo:warning=Downloading prebuilt native library from {url}");
+        let mut bytes = Vec::new();
+        ureq::get(&url)
+            .call()
+            .map_err(|e| e.to_string())?
+            .into_reader()
+            .read_to_end(&mut bytes)
+            .map_err(|e| e.to_string())?;
+
+        let actual = Sha256::digest(&bytes)
+            .iter()
+            .fold(String::new(), |mut hex, b| {
+                let _ = write!(hex, "{b:02x}");
+                hex
+            });
+        if !actual.eq_ignore_ascii_case(expected.trim()) {
+            return Err(format!(
+                "checksum mismatch for {archive}: expected {expected}, got {actual}"
+            ));
+        }
+
+        let unpacked = tar::Archive::new(flate2::read::GzDecoder::new(bytes.as_slice()))
+            .unpack(&dir)
+            .map_err(|e| e.to_string())
+            .and_then(|()| {
+                if dir.join(lib_name).exists() {
+                    Ok(())
+                } else {
+                    Err(format!("{archive} does not contain {lib_name}"))
+                }
+            });
+        if let Err(e) = unpacked {
+            let _ = std::fs::remove_dir_all(&dir);
+            return Err(e);
+        }
+        println!("cargo:warning=Native library unpacked to {}", dir.display());
+        Ok(dir)
+    }
+
+    /// Look up the pinned checksum of an archive
+    fn pinned_checksum(archive: &str) -> Option<String> {
+        CHECKSUMS
+            .lines()
+            .filter(|line| !line.starts_with('#'))
+            .find_map(|line| {
+                let (hash, name) = line.split_once(char::is_whitespace)?;
+                (name.trim().trim_start_matches('*') == archive).then(|| hash.to_string())
+            })
+    }
+}
+
+/// Native library embedding (`embed` feature)
+#[cfg(feature = "embed")]
+mod embed {
+    use std::env;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+
+    /// Pack the native library directory into `OUT_DIR` for `include_bytes!`
+    ///
+    /// Each file is stored as a little-endian `u32` name length, the
+    /// `/`-separated relative path, a `u64` data length and the data. When
+    /// no library is found the archive is empty and loading fails at runtime.
+    pub fn pack(rid: &str, lib_name: &str) {
+        let out_dir = PathBuf::from(env::var("OUT_DIR").unwrap());
+        let manifest_dir = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap());
+
+        println!("cargo:rerun-if-env-changed=KQL_LANGUAGE_TOOLS_PATH");
+        let mut candidates = Vec::new();
+        if let Ok(path) = env::var("KQL_LANGUAGE_TOOLS_PATH") {
+            let path = PathBuf::from(path);
+            if path.is_file() {
+                candidates.extend(path.parent().map(Path::to_path_buf));
+            } else {
+                candidates.push(path);
+            }
+        }
+        candidates.push(out_dir.join("native").join(rid));
+        candidates.push(manifest_dir.join("dotnet").join("native").join(rid));
+
+        let mut archive = Vec::new();
+        match candidates.iter().find(|dir| dir.join(lib_name).is_file()) {
+            Some(dir) => {
+                println!("cargo:rerun-if-changed={}", dir.display());
+                if let Err(e) = append_dir(&mut archive, dir, "") {
+                    println!("cargo:warning=Failed to embed native library: {e}");
+                    archive.clear();
+                } else {
+                    println!(
+                        "cargo:warning=Embedding native library from {}",
+                        dir.display()
+                    );
+                }
+            }
+            None => println!("cargo:warning=No native library found to embed"),
+        }
+
+        let path = out_dir.join("embedded-native.bin");
+        fs::write(&path, &archive).unwrap();
+        println!(
+            "cargo:rustc-env=KQL_LANGUAGE_TOOLS_EMBEDDED={}",
+            path.display()
+        );
+        println!(
+            "cargo:rustc-env=KQL_LANGUAGE_TOOLS_EMBEDDED_HASH={:016x}",
+            fnv1a(&archive)
+        );
+    }
+
+    /// Append every file under `dir` to the archive, in a stable order
+    fn append_dir(archive: &mut Vec<u8>, dir: &Path, prefix: &str) -> std::io::Result<()> {
+        let mut entries = fs::read_dir(dir)?.collect::<Result<Vec<_>, _>>()?;
+        entries.sort_by_key(fs::DirEntry::file_name);
+        for entry in entries {
+            let name = format!("{prefix}{}", entry.file_name().to_string_lossy());
+            if entry.file_type()?.is_dir() {
+                append_dir(archive, &entry.path(), &format!("{name}/"))?;
+                continue;
+            }
+            let data = fs::read(entry.path())?;
+            let name_len = u32::try_from(name.len()).expect("file name fits in u32");
+            archive.extend_from_slice(&name_len.to_le_bytes());
+            archive.extend_from_slice(name.as_bytes());
+            archive.extend_from_slice(&(data.len() as u64).to_le_bytes());
+            archive.extend_from_slice(&data);
+        }
+        Ok(())
+    }
+
+    /// FNV-1a hash of the archive, naming its cache directory
+    fn fnv1a(bytes: &[u8]) -> u64 {
+        bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &b| {
+            (hash ^ u64::from(b)).wrapping_mul(0x0100_0000_01b3)
+        })
+    }
+}
@@ -24,6 +24,19 @@ patch_runtime_config() {
     fi
 }
 
+# --package also writes native/kql-language-tools-native-<rid>.tar.gz for the
+# `bundled` feature and prints its line for bundled-checksums.txt
+PACKAGE=0
+ARGS=()
+for arg in "$@"; do
+    if [ "$arg" = "--package" ]; then
+        PACKAGE=1
+    else
+        ARGS+=("$arg")
+    fi
+done
+set -- "${ARGS[@]}"
+
 # Default to current platform if no argument provided
 if [ -z "$1" ]; then
     # Detect current platform
@@ -111,6 +124,18 @@ for rid in "${RIDS[@]}"; do
         patch_runtime_config "native/$rid/KqlLanguageFfi.runtimeconfig.json"
 
         echo "Success: native/$rid/"
+
+        if [ "$PACKAGE" = 1 ]; then
+            ARCHIVE="native/kql-language-tools-native-$rid.tar.gz"
+            tar -czf "$ARCHIVE" -C "native/$rid" .
+            if command -v sha256sum > /dev/null; then
+                CHECKSUM=$(sha256sum "$ARCHIVE" | cut -d' ' -f1)
+            else
+                CHECKSUM=$(shasum -a 256 "$ARCHIVE" | cut -d' ' -f1)
+            fi
+            echo "Packaged: $ARCHIVE"
+            echo "Checksum: $CHECKSUM  $(basename "$ARCHIVE")"
+        fi
     else
         echo "Failed to build for $rid"
         exit 1
@@ -0,0 +1,105 @@
+using Kusto.Language;
+using Kusto.Language.Symbols;
+using Kusto.Language.Syntax;
+
+namespace KqlLanguageFfi;
+
+/// <summary>
+/// Referenced-entity analysis service.
+/// Collects the schema tables, columns, and functions a query references,
+/// plus clusters and databases named through cluster() and database().
+/// </summary>
+public static class AnalysisService
+{
+    /// <summary>
+    /// Analyze the entities referenced by a query.
+    /// </summary>
+    /// <param name="query">The KQL query</param>
+    /// <param name="schema">Schema definition the query runs against</param>
+    /// <param name="options">Analysis options (dialect, ...)</param>
+    /// <returns>Referenced entities, each list sorted and de-duplicated</returns>
+    public static QueryAnalysisResult Analyze(string query, SchemaDefinition schema, ValidationOptions options)
+    {
+        var globals = ValidationService.BuildGlobalState(schema, options);
+        var code = KustoCode.ParseAndAnalyze(query, globals);
+
+        // Map schema symbols back to their definitions
+        var columnTables = new Dictionary<ColumnSymbol, string>();
+        foreach (var table in globals.Database.Tables)
+        {
+            foreach (var column in table.Columns)
+                columnTables[column] = table.Name;
+        }
+        var schemaFunctions = new HashSet<string>(
+            (schema.Functions ?? Enumerable.Empty<FunctionDefinition>()).Select(f => f.Name));
+
+        var tables = new SortedSet<string>(StringComparer.Ordinal);
+        var columns = new SortedDictionary<string, SortedSet<string>>(StringComparer.Ordinal);
+        var functions = new SortedSet<string>(StringComparer.Ordinal);
+        var clusters = new SortedSet<string>(StringComparer.Ordinal);
+        var databases = new SortedSet<string>(StringComparer.Ordinal);
+
+        foreach (var node in code.Syntax.GetDescendants<SyntaxNode>())
+        {
+            switch (node)
+            {
+                case FunctionCallExpression call when IsEntityCall(call, "cluster", out var cluster):
+                    clusters.Add(cluster);
+                    break;
+
+                case FunctionCallExpression call when IsEntityCall(call, "database", out var database):
+                    databases.Add(database);
+                    break;
+
+                case FunctionCallExpression call:
+                    if (call.ReferencedSymbol is FunctionSymbol function && schemaFunctions.Contains(function.Name))
+                        functions.Add(function.Name);
+                    break;
+
+                case NameReference reference:
+                    switch (reference.ReferencedSymbol)
+                    {
+                        case TableSymbol table when globals.Database.Tables.Contains(table):
+                            tables.Add(table.Name);
+                            break;
+
+                        case ColumnSymbol column when columnTables.TryGetValue(column, out var tableName):
+                            if (!columns.TryGetValue(tableName, out var names))
+                                columns[tableName] = names = new SortedSet<string>(StringComparer.Ordinal);
+                            names.Add(column.Name);
+                            break;
+
+                        case FunctionSymbol function when schemaFunctions.Contains(function.Name):
+                            functions.Add(function.Name);
+                            break;
+                    }
+                    break;
+            }
+        }
+
+        return new QueryAnalysisResult
+        {
+            Tables = tables.ToList(),
+            Columns = columns.ToDictionary(kv => kv.Key, kv => kv.Value.ToList()),
+            Functions = functions.ToList(),
+            Clusters = clusters.ToList(),
+            Databases = databases.ToList()
+        };
+    }
+
+    /// <summary>
+    /// Check for a call such as cluster('name') with a string literal argument.
+    /// </summary>
+    private static bool IsEntityCall(FunctionCallExpression call, string function, out string name)
+    {
+        name = "";
+        if (call.Name.SimpleName != function || call.ArgumentList.Expressions.Count != 1)
+            return false;
+
+        if (call.ArgumentList.Expressions[0].Element is not LiteralExpression { LiteralValue: string value })
+            return false;
+
+        name = value;
+        return true;
+    }
+}
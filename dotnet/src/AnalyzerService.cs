@@ -0,0 +1,42 @@
+using Kusto.Language;
+using Kusto.Language.Editor;
+
+namespace KqlLanguageFfi;
+
+/// <summary>
+/// Best-practice analysis service.
+/// Runs the Kusto.Language built-in analyzers, which flag queries that are
+/// valid but slow or fragile (e.g. <c>contains</c> where <c>has</c> would do).
+/// </summary>
+public static class AnalyzerService
+{
+    /// <summary>
+    /// Category reported for every analyzer diagnostic.
+    /// </summary>
+    private const string AnalyzerCategory = "Analyzer";
+
+    /// <summary>
+    /// Get analyzer diagnostics for a query.
+    /// </summary>
+    /// <param name="query">The KQL query</param>
+    /// <param name="schema">Schema definition the query runs against</param>
+    /// <param name="options">Analysis options (dialect, ...)</param>
+    /// <returns>Analyzer diagnostics, ordered by position</returns>
+    public static List<Diagnostic> GetAnalyzerDiagnostics(string query, SchemaDefinition schema, ValidationOptions options)
+    {
+        var globals = ValidationService.BuildGlobalState(schema, options);
+        var code = KustoCode.ParseAndAnalyze(query, globals);
+
+        var diagnostics = new KustoCodeService(code)
+            .GetAnalyzerDiagnostics(waitForAnalysis: true)
+            .OrderBy(d => d.Start)
+            .ToList();
+
+        var result = ValidationService.CreateResult(query, diagnostics).Diagnostics;
+        foreach (var diagnostic in result)
+        {
+            diagnostic.Category = AnalyzerCategory;
+        }
+        return result;
+    }
+}
@@ -0,0 +1,61 @@
+using System.Collections.Concurrent;
+
+namespace KqlLanguageFfi;
+
+/// <summary>
+/// Registry of cancellation sources created by the caller and referenced by id.
+/// Options carrying a cancellation id make validation observe the source's token,
+/// so a caller that gives up on a call can stop its analysis from another thread.
+/// </summary>
+public static class CancellationRegistry
+{
+    private static readonly ConcurrentDictionary<int, CancellationTokenSource> Sources = new();
+    private static int _nextId;
+
+    /// <summary>
+    /// Create a cancellation source.
+    /// </summary>
+    /// <returns>A positive cancellation id</returns>
+    public static int Create()
+    {
+        var id = Interlocked.Increment(ref _nextId);
+        Sources[id] = new CancellationTokenSource();
+        return id;
+    }
+
+    /// <summary>
+    /// Cancel a source. Calls observing it stop at their next cancellation check.
+    /// </summary>
+    /// <returns>True if the id was registered</returns>
+    public static bool Cancel(int id)
+    {
+        if (!Sources.TryGetValue(id, out var source))
+            return false;
+
+        source.Cancel();
+        return true;
+    }
+
+    /// <summary>
+    /// Release a source.
+    /// </summary>
+    /// <returns>True if the id was registered</returns>
+    public static bool Release(int id)
+    {
+        if (!Sources.TryRemove(id, out var source))
+            return false;
+
+        source.Dispose();
+        return true;
+    }
+
+    /// <summary>
+    /// Get the token for an id, or CancellationToken.None if it is absent or unknown.
+    /// </summary>
+    public static CancellationToken Token(int? id)
+    {
+        return id is int value && Sources.TryGetValue(value, out var source)
+            ? source.Token
+            : CancellationToken.None;
+    }
+}
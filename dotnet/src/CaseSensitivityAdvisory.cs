@@ -0,0 +1,118 @@
+using System.Text.RegularExpressions;
+using Kusto.Language;
+using Kusto.Language.Syntax;
+
+namespace KqlLanguageFfi;
+
+/// <summary>
+/// Suggests case-sensitive string operators when the compared literal is
+/// exact-case, since case-insensitive matching is slower.
+/// </summary>
+public static class CaseSensitivityAdvisory
+{
+    /// <summary>
+    /// Case-insensitive operator used with an exact-case literal.
+    /// </summary>
+    public const string CodeCaseSensitiveVariant = "CS001";
+
+    /// <summary>
+    /// Case-insensitive operators and their case-sensitive variants.
+    /// </summary>
+    private static readonly Dictionary<string, string> Variants = new(StringComparer.OrdinalIgnoreCase)
+    {
+        ["=~"] = "==",
+        ["!~"] = "!=",
+        ["in~"] = "in",
+        ["!in~"] = "!in",
+        ["has"] = "has_cs",
+        ["!has"] = "!has_cs",
+        ["contains"] = "contains_cs",
+        ["!contains"] = "!contains_cs",
+        ["startswith"] = "startswith_cs",
+        ["!startswith"] = "!startswith_cs",
+        ["endswith"] = "endswith_cs",
+        ["!endswith"] = "!endswith_cs",
+        ["hasprefix"] = "hasprefix_cs",
+        ["!hasprefix"] = "!hasprefix_cs",
+        ["hassuffix"] = "hassuffix_cs",
+        ["!hassuffix"] = "!hassuffix_cs",
+    };
+
+    private static readonly Regex Guid = new(
+        "^[{(]?[0-9a-fA-F]{8}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{12}[)}]?$",
+        RegexOptions.Compiled);
+
+    // MD5, SHA-1, SHA-256, SHA-512
+    private static readonly Regex Hash = new(
+        "^(?:[0-9a-f]{32}|[0-9a-f]{40}|[0-9a-f]{64}|[0-9a-f]{128})$",
+        RegexOptions.Compiled | RegexOptions.IgnoreCase);
+
+    /// <summary>
+    /// Run the advisory over a parsed query, appending hints and recording their quick fixes.
+    /// </summary>
+    public static List<Kusto.Language.Diagnostic> Apply(
+        KustoCode code,
+        List<Kusto.Language.Diagnostic> diagnostics,
+        Dictionary<Kusto.Language.Diagnostic, List<FixDefinition>> fixes)
+    {
+        var result = new List<Kusto.Language.Diagnostic>(diagnostics);
+
+        foreach (var binary in code.Syntax.GetDescendants<BinaryExpression>())
+        {
+            if (binary.Right is LiteralExpression literal && IsExactCase(literal))
+            {
+                Suggest(binary.Operator, result, fixes);
+            }
+        }
+
+        foreach (var inExpression in code.Syntax.GetDescendants<InExpression>())
+        {
+            var values = inExpression.Right.Expressions;
+            if (values.Count > 0 && values.All(v => v.Element is LiteralExpression literal && IsExactCase(literal)))
+            {
+                Suggest(inExpression.Operator, result, fixes);
+            }
+        }
+
+        return result.OrderBy(d => d.Start).ToList();
+    }
+
+    private static void Suggest(
+        SyntaxToken op,
+        List<Kusto.Language.Diagnostic> result,
+        Dictionary<Kusto.Language.Diagnostic, List<FixDefinition>> fixes)
+    {
+        if (!Variants.TryGetValue(op.Text, out var replacement))
+            return;
+
+        var message = $"The literal is exact-case; '{replacement}' is faster than '{op.Text}'";
+        var diagnostic = new Kusto.Language.Diagnostic(
+                CodeCaseSensitiveVariant, DiagnosticCategory.Performance, DiagnosticSeverity.Suggestion, message, message)
+            .WithLocation(op.TextStart, op.Text.Length);
+
+        result.Add(diagnostic);
+        fixes[diagnostic] = new List<FixDefinition>
+        {
+            new()
+            {
+                Title = $"Use '{replacement}'",
+                Edits = new List<TextEditDefinition>
+                {
+                    new() { Start = op.TextStart, End = op.TextStart + op.Text.Length, NewText = replacement }
+                }
+            }
+        };
+    }
+
+    /// <summary>
+    /// A literal is exact-case when case cannot matter (no letters, e.g. IP
+    /// addresses) or is conventionally fixed (GUIDs, hex hashes).
+    /// </summary>
+    private static bool IsExactCase(LiteralExpression literal)
+    {
+        if (literal.LiteralValue is not string value || value.Length == 0)
+            return false;
+
+        return !value.Any(char.IsLetter) || Guid.IsMatch(value) || Hash.IsMatch(value);
+    }
+}
@@ -0,0 +1,57 @@
+using System.Formats.Cbor;
+using System.Text.Json;
+
+namespace KqlLanguageFfi;
+
+/// <summary>
+/// Converts serialized JSON results to CBOR with the same structure.
+/// Numbers that fit an integer are written as CBOR integers.
+/// </summary>
+internal static class CborTranscoder
+{
+    public static byte[] FromJson(byte[] json)
+    {
+        var reader = new Utf8JsonReader(json);
+        var writer = new CborWriter(CborConformanceMode.Lax, convertIndefiniteLengthEncodings: false);
+
+        while (reader.Read())
+        {
+            switch (reader.TokenType)
+            {
+                case JsonTokenType.StartObject:
+                    writer.WriteStartMap(null);
+                    break;
+                case JsonTokenType.EndObject:
+                    writer.WriteEndMap();
+                    break;
+                case JsonTokenType.StartArray:
+                    writer.WriteStartArray(null);
+                    break;
+                case JsonTokenType.EndArray:
+                    writer.WriteEndArray();
+                    break;
+                case JsonTokenType.PropertyName:
+                case JsonTokenType.String:
+                    writer.WriteTextString(reader.GetString()!);
+                    break;
+                case JsonTokenType.Number:
+                    if (reader.TryGetInt64(out var integer))
+                        writer.WriteInt64(integer);
+                    else
+                        writer.WriteDouble(reader.GetDouble());
+                    break;
+                case JsonTokenType.True:
+                    writer.WriteBoolean(true);
+                    break;
+                case JsonTokenType.False:
+                    writer.WriteBoolean(false);
+                    break;
+                case JsonTokenType.Null:
+                    writer.WriteNull();
+                    break;
+            }
+        }
+
+        return writer.Encode();
+    }
+}
@@ -10,18 +10,112 @@ namespace KqlLanguageFfi;
 /// </summary>
 public static class ClassificationService
 {
+    /// <summary>
+    /// Last query analyzed for range classification on this thread, so
+    /// classifying one query range by range parses it once. Held weakly so a
+    /// large query's tree is not kept alive once the caller is done with it.
+    /// </summary>
+    [ThreadStatic]
+    private static WeakReference<KustoCode>? _lastRangeCode;
+
     /// <summary>
     /// Get syntax classifications for a KQL query.
     /// </summary>
     /// <param name="query">The KQL query to classify</param>
     /// <returns>Classification result with spans for each token</returns>
     public static ClassificationResult GetClassifications(string query)
+    {
+        return GetClassifications(query, GlobalState.Default);
+    }
+
+    /// <summary>
+    /// Get schema-aware classifications for a KQL query.
+    /// Names that resolve to tables, columns, and functions in the schema are
+    /// classified as such instead of as plain identifiers.
+    /// </summary>
+    /// <param name="query">The KQL query to classify</param>
+    /// <param name="schema">Schema definition to resolve names against</param>
+    /// <param name="options">Analysis options (dialect, ...)</param>
+    /// <returns>Classification result with spans for each token</returns>
+    public static ClassificationResult GetSemanticClassifications(
+        string query,
+        SchemaDefinition schema,
+        ValidationOptions options)
+    {
+        try
+        {
+            return GetClassifications(query, ValidationService.BuildGlobalState(schema, options));
+        }
+        catch (Exception)
+        {
+            // On error, return empty result (let validation catch errors)
+            return new ClassificationResult();
+        }
+    }
+
+    /// <summary>
+    /// Get syntax classifications for the tokens starting within a range.
+    /// </summary>
+    /// <param name="query">The KQL query to classify</param>
+    /// <param name="start">Start of the range (character offset)</param>
+    /// <param name="end">End of the range (character offset, exclusive)</param>
+    /// <returns>Classification result with spans for each token starting in the range</returns>
+    public static ClassificationResult GetClassificationsInRange(string query, int start, int end)
+    {
+        try
+        {
+            if (_lastRangeCode == null || !_lastRangeCode.TryGetTarget(out var code) || code.Text != query)
+            {
+                code = KustoCode.ParseAndAnalyze(query, GlobalState.Default);
+                _lastRangeCode = new WeakReference<KustoCode>(code);
+            }
+
+            var spans = new List<ClassifiedSpan>();
+            ClassifyNode(code.Syntax, spans, start, end);
+
+            return new ClassificationResult { Spans = spans };
+        }
+        catch (Exception)
+        {
+            // On error, return empty result (let validation catch errors)
+            return new ClassificationResult();
+        }
+    }
+
+    /// <summary>
+    /// Get syntax classifications for a KQL query in batches.
+    /// The query is parsed once and its tokens classified in order, so the
+    /// batches are sorted and only the current one is held.
+    /// </summary>
+    /// <param name="query">The KQL query to classify</param>
+    /// <param name="batchSpans">Maximum number of spans per batch</param>
+    /// <returns>The batches, in query order</returns>
+    public static IEnumerable<ClassificationResult> GetClassificationBatches(string query, int batchSpans)
+    {
+        var code = KustoCode.ParseAndAnalyze(query, GlobalState.Default);
+        var spans = new List<ClassifiedSpan>();
+
+        for (var token = code.Syntax.GetFirstToken(); token != null; token = token.GetNextToken())
+        {
+            ClassifyToken(token, spans);
+            if (spans.Count >= batchSpans)
+            {
+                yield return new ClassificationResult { Spans = spans };
+                spans = new List<ClassifiedSpan>();
+            }
+        }
+
+        if (spans.Count > 0)
+            yield return new ClassificationResult { Spans = spans };
+    }
+
+    private static ClassificationResult GetClassifications(string query, GlobalState globals)
     {
         try
         {
             // Parse AND analyze the query for semantic information
             // This gives us access to ReferencedSymbol which tells us exactly what each identifier is
-            var code = KustoCode.ParseAndAnalyze(query);
+            var code = KustoCode.ParseAndAnalyze(query, globals);
             var spans = new List<ClassifiedSpan>();
 
             // Walk the syntax tree and classify each token using semantic info
@@ -40,6 +134,15 @@ public static class ClassificationService
     /// Recursively classify nodes in the syntax tree.
     /// </summary>
     private static void ClassifyNode(SyntaxNode node, List<ClassifiedSpan> spans)
+    {
+        ClassifyNode(node, spans, 0, int.MaxValue);
+    }
+
+    /// <summary>
+    /// Recursively classify the tokens starting within a range, skipping
+    /// nodes that lie outside it.
+    /// </summary>
+    private static void ClassifyNode(SyntaxNode node, List<ClassifiedSpan> spans, int start, int end)
     {
         // Process tokens at this node
         for (int i = 0; i < node.ChildCount; i++)
@@ -47,11 +150,12 @@ public static class ClassificationService
             var child = node.GetChild(i);
             if (child is SyntaxToken token)
             {
-                ClassifyToken(token, spans);
+                if (token.TextStart >= start && token.TextStart < end)
+                    ClassifyToken(token, spans);
             }
-            else if (child is SyntaxNode childNode)
+            else if (child is SyntaxNode childNode && childNode.End > start && childNode.TextStart < end)
             {
-                ClassifyNode(childNode, spans);
+                ClassifyNode(childNode, spans, start, end);
             }
         }
     }
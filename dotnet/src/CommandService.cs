@@ -0,0 +1,39 @@
+using Kusto.Language;
+
+namespace KqlLanguageFfi;
+
+/// <summary>
+/// Checks that text validated as a control command actually is one.
+/// Kusto.Language parses commands (text starting with '.') and queries alike,
+/// so without this check a query would pass command validation.
+/// </summary>
+public static class CommandService
+{
+    /// <summary>
+    /// Text is a query, not a control command.
+    /// </summary>
+    public const string CodeNotACommand = "KC001";
+
+    /// <summary>
+    /// Run the command check over parsed text, appending an error if it is not a command.
+    /// </summary>
+    public static List<Kusto.Language.Diagnostic> Apply(
+        KustoCode code,
+        List<Kusto.Language.Diagnostic> diagnostics)
+    {
+        if (code.Kind == CodeKinds.Command)
+            return diagnostics;
+
+        var first = code.Syntax.GetFirstToken();
+        var start = first?.TextStart ?? 0;
+        var length = first?.Text.Length ?? 0;
+
+        var result = new List<Kusto.Language.Diagnostic>(diagnostics);
+        var message = "Expected a control command (starting with '.')";
+        result.Add(new Kusto.Language.Diagnostic(
+                CodeNotACommand, DiagnosticCategory.Correctness, DiagnosticSeverity.Error, message, message)
+            .WithLocation(start, length));
+
+        return result.OrderBy(d => d.Start).ToList();
+    }
+}
@@ -1,6 +1,7 @@
 using Kusto.Language;
 using Kusto.Language.Editor;
 using Kusto.Language.Symbols;
+using Kusto.Language.Syntax;
 
 namespace KqlLanguageFfi;
 
@@ -16,19 +17,72 @@ public static class CompletionService
     /// <param name="query">The KQL query</param>
     /// <param name="cursorPosition">Cursor position (0-based character offset)</param>
     /// <param name="schema">Optional schema for context-aware completions</param>
+    /// <param name="options">Optional filtering options, applied before serialization</param>
     /// <returns>Completion result with items</returns>
-    public static CompletionResult GetCompletions(string query, int cursorPosition, SchemaDefinition? schema = null)
+    public static CompletionResult GetCompletions(
+        string query,
+        int cursorPosition,
+        SchemaDefinition? schema = null,
+        CompletionOptions? options = null)
     {
+        // Build globals with schema if provided
+        GlobalState globals;
         try
         {
-            // Build globals with schema if provided
-            GlobalState globals = schema != null
+            globals = schema != null
                 ? ValidationService.BuildGlobalState(schema)
                 : GlobalState.Default;
+        }
+        catch (Exception)
+        {
+            // On error, return empty result
+            return new CompletionResult();
+        }
 
+        return GetCompletions(query, cursorPosition, globals, options);
+    }
+
+    /// <summary>
+    /// Get completion items at the specified cursor position using prebuilt globals.
+    /// </summary>
+    /// <param name="query">The KQL query</param>
+    /// <param name="cursorPosition">Cursor position (0-based character offset)</param>
+    /// <param name="globals">Globals to resolve symbols against</param>
+    /// <param name="options">Optional filtering options, applied before serialization</param>
+    /// <returns>Completion result with items</returns>
+    public static CompletionResult GetCompletions(
+        string query,
+        int cursorPosition,
+        GlobalState globals,
+        CompletionOptions? options = null)
+    {
+        try
+        {
             // Create CodeScript from query string with globals
-            var script = CodeScript.From(query, globals);
+            return GetCompletions(CodeScript.From(query, globals), cursorPosition, options);
+        }
+        catch (Exception)
+        {
+            // On error, return empty result
+            return new CompletionResult();
+        }
+    }
 
+    /// <summary>
+    /// Get completion items at the specified cursor position of an existing script.
+    /// Blocks the script has already analyzed are reused.
+    /// </summary>
+    /// <param name="script">The script to complete in</param>
+    /// <param name="cursorPosition">Cursor position (0-based character offset)</param>
+    /// <param name="options">Optional filtering options, applied before serialization</param>
+    /// <returns>Completion result with items</returns>
+    public static CompletionResult GetCompletions(
+        CodeScript script,
+        int cursorPosition,
+        CompletionOptions? options = null)
+    {
+        try
+        {
             // Get the CodeBlock at the cursor position
             var block = script.GetBlockAtPosition(cursorPosition);
 
@@ -38,8 +92,22 @@ public static class CompletionService
             var items = new List<CompletionItemResponse>();
             int sortOrder = 0;
 
+            // Analyzed only when let bindings or an expected type need it
+            var code = new Lazy<KustoCode>(() => KustoCode.ParseAndAnalyze(block.Text, script.Globals));
+            var expected = GetExpectedType(block, code, completionInfo.EditStart - block.Start);
+
+            // Ranking, matching and expected types need every item before max_items is applied
+            var ranking = options != null && (options.Ranked || options.Matching != null);
+            var reordering = ranking || expected != null;
+
             foreach (var item in completionInfo.Items)
             {
+                if (options != null && !Includes(options, item.Kind))
+                    continue;
+
+                if (!reordering && options?.MaxItems is int maxItems && items.Count >= maxItems)
+                    break;
+
                 int editStart = completionInfo.EditStart;
 
                 // Use MatchText for insertion if available (e.g., "ago" for label "ago(timespan)")
@@ -62,7 +130,29 @@ public static class CompletionService
                 });
             }
 
-            return new CompletionResult { Items = items };
+            AddLetDetails(items, code, cursorPosition - block.Start);
+
+            if (expected != null)
+            {
+                MarkExpectedType(items, script.Globals, expected.Value.Type, expected.Value.Columns);
+                items = items.OrderBy(i => i.MatchesExpectedType ? 0 : 1).ToList();
+                for (int i = 0; i < items.Count; i++)
+                    items[i].SortOrder = i;
+            }
+
+            if (ranking)
+                items = Rank(items, script.Text.Substring(0, Math.Min(cursorPosition, script.Text.Length)), options!);
+            else if (options?.MaxItems is int maxItems && items.Count > maxItems)
+                items = items.Take(maxItems).ToList();
+
+            var context = new CompletionContextResult
+            {
+                Trigger = GetTrigger(block, completionInfo.EditStart),
+                EditStart = completionInfo.EditStart,
+                EditEnd = completionInfo.EditStart + completionInfo.EditLength
+            };
+
+            return new CompletionResult { Items = items, Context = context, ExpectedType = expected?.Type };
         }
         catch (Exception)
         {
@@ -71,6 +161,337 @@ public static class CompletionService
         }
     }
 
+    /// <summary>
+    /// Set the detail of let-bound items to their inferred type: a scalar
+    /// type name, "(column: type, ...)" for tables, or a function's signature.
+    /// Only let statements before the cursor are in scope.
+    /// </summary>
+    private static void AddLetDetails(List<CompletionItemResponse> items, Lazy<KustoCode> code, int position)
+    {
+        if (!items.Any(i => i.Kind == "LetVariable"))
+            return;
+
+        var types = new Dictionary<string, string>();
+        foreach (var statement in code.Value.Syntax.GetDescendants<LetStatement>(l => l.End <= position))
+        {
+            var type = statement.Name.ReferencedSymbol switch
+            {
+                FunctionSymbol function => function.Signatures.Select(s => FormatSignature(function.Name, s)).FirstOrDefault(),
+                VariableSymbol { Type: TableSymbol table } => FormatColumns(table),
+                VariableSymbol variable => ResultSchemaService.GetTypeName(variable.Type),
+                _ => null
+            };
+            if (type != null)
+                types[statement.Name.SimpleName] = type;
+        }
+
+        foreach (var item in items.Where(i => i.Kind == "LetVariable"))
+        {
+            if (types.TryGetValue(GetSymbolName(item.Label), out var type))
+                item.Detail = type;
+        }
+    }
+
+    private static readonly string[] ComparisonOperators = { "==", "!=", "<>", "<=", ">=", "=~", "!~", "<", ">" };
+
+    /// <summary>
+    /// The type of the left-hand side when completing the right-hand side of
+    /// a comparison, as in "where Timestamp > ", with the columns in scope.
+    /// </summary>
+    private static (string Type, IReadOnlyList<ColumnSymbol> Columns)? GetExpectedType(
+        CodeBlock block, Lazy<KustoCode> code, int position)
+    {
+        // Check the text first so other completions are not analyzed
+        var before = block.Text.Substring(0, Math.Min(position, block.Text.Length)).TrimEnd();
+        if (!ComparisonOperators.Any(before.EndsWith))
+            return null;
+
+        var token = code.Value.Syntax.GetTokenAt(position);
+        if (token != null && token.TextStart >= position)
+            token = token.GetPreviousToken();
+        if (token?.Parent is not BinaryExpression binary || binary.Operator != token)
+            return null;
+        if (binary.Left.ResultType is not ScalarSymbol scalar || scalar == ErrorSymbol.Instance)
+            return null;
+
+        // Columns of the table the enclosing operator reads
+        IReadOnlyList<ColumnSymbol> columns = Array.Empty<ColumnSymbol>();
+        for (SyntaxNode? node = binary; node != null; node = node.Parent)
+        {
+            if (node is QueryOperator && node.Parent is PipeExpression { Expression.ResultType: TableSymbol table })
+            {
+                columns = table.Columns;
+                break;
+            }
+        }
+
+        return (ResultSchemaService.GetTypeName(scalar), columns);
+    }
+
+    /// <summary>
+    /// Mark the columns, functions and let bindings whose type is the expected type.
+    /// </summary>
+    private static void MarkExpectedType(
+        List<CompletionItemResponse> items, GlobalState globals, string type, IReadOnlyList<ColumnSymbol> columns)
+    {
+        foreach (var item in items)
+        {
+            var name = GetSymbolName(item.Label);
+            item.MatchesExpectedType = item.Kind switch
+            {
+                "Column" => columns.Any(c => c.Name == name && ResultSchemaService.GetTypeName(c.Type) == type),
+                "Function" => (globals.GetFunction(name) ?? globals.Database.GetFunction(name)) is FunctionSymbol function
+                    && function.Signatures.Any(s => s.ReturnKind == ReturnTypeKind.Declared
+                        && ResultSchemaService.GetTypeName(s.ReturnType) == type),
+                "LetVariable" => item.Detail == type,
+                _ => false
+            };
+        }
+    }
+
+    /// <summary>
+    /// Format a table's columns as "(column: type, ...)".
+    /// </summary>
+    private static string FormatColumns(TableSymbol table)
+    {
+        var columns = table.Columns.Select(c => $"{c.Name}: {ResultSchemaService.GetTypeName(c.Type)}");
+        return $"({string.Join(", ", columns)})";
+    }
+
+    /// <summary>
+    /// Describe what precedes the text being completed, from the token
+    /// before it and the innermost enclosing function call or by clause.
+    /// </summary>
+    private static string GetTrigger(CodeBlock block, int editStart)
+    {
+        var code = KustoCode.Parse(block.Text);
+        var position = editStart - block.Start;
+        var token = code.Syntax.GetTokenAt(position);
+        if (token != null && token.TextStart >= position)
+            token = token.GetPreviousToken();
+        if (token == null)
+            return "Other";
+
+        if (token.Text == "|")
+            return "AfterPipe";
+        if (token.Text == ".")
+            return "AfterDot";
+
+        for (var node = token.Parent; node != null; node = node.Parent)
+        {
+            if (node is ExpressionList { Parent: FunctionCallExpression })
+                return "FunctionArguments";
+            if (node is SummarizeByClause)
+                return "ByClause";
+        }
+
+        return token.Kind == SyntaxKind.ByKeyword ? "ByClause" : "Other";
+    }
+
+    /// <summary>
+    /// Fill in the documentation and signature of a completion item.
+    /// Completion lists leave these out to stay fast; clients resolve the
+    /// item they are showing. Items that do not name a function, table or
+    /// column are returned unchanged.
+    /// </summary>
+    /// <param name="item">The item, as returned by GetCompletions</param>
+    /// <param name="globals">Globals the item's symbol is looked up in</param>
+    /// <returns>The item with Documentation (markdown) and Detail set</returns>
+    public static CompletionItemResponse Resolve(CompletionItemResponse item, GlobalState globals)
+    {
+        var name = GetSymbolName(item.Label);
+        switch (item.Kind)
+        {
+            case "Function":
+            case "AggregateFunction":
+                var function = (item.Kind == "AggregateFunction" ? globals.GetAggregate(name) : null)
+                    ?? globals.GetFunction(name)
+                    ?? globals.Database.GetFunction(name);
+                if (function != null)
+                {
+                    var signatures = function.Signatures.Select(s => FormatSignature(function.Name, s)).ToList();
+                    item.Detail = signatures.FirstOrDefault() ?? item.Detail;
+                    item.Documentation = FormatDocumentation(string.Join("\n", signatures), function.Description);
+                }
+                break;
+
+            case "Table":
+                var table = globals.Database.GetTable(name);
+                if (table != null)
+                {
+                    item.Documentation = FormatDocumentation(table.Name + FormatColumns(table), table.Description);
+                }
+                break;
+
+            case "Column":
+                var column = globals.Database.Tables
+                    .SelectMany(t => t.Columns)
+                    .FirstOrDefault(c => c.Name == name);
+                if (column != null)
+                {
+                    var type = ResultSchemaService.GetTypeName(column.Type);
+                    item.Detail = type;
+                    item.Documentation = FormatDocumentation($"{column.Name}: {type}", column.Description);
+                }
+                break;
+        }
+
+        return item;
+    }
+
+    /// <summary>
+    /// Symbol name of a completion label: "ago()" names ago and
+    /// "['My Column']" names My Column.
+    /// </summary>
+    private static string GetSymbolName(string label)
+    {
+        var paren = label.IndexOf('(');
+        var name = paren > 0 ? label.Substring(0, paren) : label;
+        if (name.Length >= 4 && name.StartsWith("[") && name.EndsWith("]"))
+            name = name.Substring(2, name.Length - 4);
+        return name;
+    }
+
+    /// <summary>
+    /// Format a signature as "name(param: type, [optional: type])".
+    /// </summary>
+    private static string FormatSignature(string name, Signature signature)
+    {
+        var parameters = signature.Parameters.Select(p =>
+        {
+            var type = p.DeclaredTypes.Count > 0
+                ? string.Join(" | ", p.DeclaredTypes.Select(t => t.Name))
+                : p.TypeKind.ToString().ToLowerInvariant();
+            return p.IsOptional ? $"[{p.Name}: {type}]" : $"{p.Name}: {type}";
+        });
+        return $"{name}({string.Join(", ", parameters)})";
+    }
+
+    /// <summary>
+    /// Markdown with a KQL code block followed by the description, if any.
+    /// </summary>
+    private static string FormatDocumentation(string code, string? description)
+    {
+        var markdown = $"```kql\n{code}\n```";
+        return string.IsNullOrEmpty(description) ? markdown : $"{markdown}\n\n{description}";
+    }
+
+    /// <summary>
+    /// Check whether a completion kind passes the filtering options.
+    /// </summary>
+    private static bool Includes(CompletionOptions options, CompletionKind kind)
+    {
+        if (options.Kinds is { Count: > 0 } kinds && !kinds.Contains(MapCompletionKind(kind)))
+            return false;
+
+        return kind switch
+        {
+            CompletionKind.Table or CompletionKind.Column or CompletionKind.Database
+                or CompletionKind.Cluster or CompletionKind.DatabaseFunction => true,
+            _ when options.OnlySchemaSymbols => false,
+            CompletionKind.Punctuation => options.IncludePunctuation,
+            CompletionKind.Keyword or CompletionKind.Syntax => options.IncludeKeywords,
+            _ => true
+        };
+    }
+
+    /// <summary>
+    /// Match items against the partially typed word before the cursor, rank
+    /// them and apply max_items. Mirrors CompletionOptions::apply in Rust:
+    /// better matches first, then names before keywords (unless an operator
+    /// is expected, as after a pipe), then Kusto's order.
+    /// </summary>
+    private static List<CompletionItemResponse> Rank(
+        List<CompletionItemResponse> items,
+        string beforeCursor,
+        CompletionOptions options)
+    {
+        var wordStart = beforeCursor.Length;
+        while (wordStart > 0 && (char.IsLetterOrDigit(beforeCursor[wordStart - 1]) || beforeCursor[wordStart - 1] == '_'))
+            wordStart--;
+        var word = beforeCursor.Substring(wordStart).ToLowerInvariant();
+        var beforeWord = beforeCursor.Substring(0, wordStart).TrimEnd();
+        var operatorExpected = beforeWord.Length == 0 || beforeWord.EndsWith("|");
+        var fuzzy = options.Matching != "Prefix";
+
+        var matched = items
+            .Select(item => (Item: item, Score: MatchScore(GetFilterText(item.Label), word, fuzzy)))
+            .Where(m => options.Matching == null || m.Score != null);
+        if (options.Ranked)
+        {
+            matched = matched
+                .OrderBy(m => m.Score ?? int.MaxValue)
+                .ThenBy(m => m.Item.MatchesExpectedType ? 0 : 1)
+                .ThenBy(m => KindRank(m.Item.Kind, operatorExpected))
+                .ThenBy(m => m.Item.SortOrder);
+        }
+
+        var result = matched.Select(m => m.Item);
+        if (options.MaxItems is int maxItems)
+            result = result.Take(maxItems);
+
+        var ranked = result.ToList();
+        if (options.Ranked)
+        {
+            for (int i = 0; i < ranked.Count; i++)
+                ranked[i].SortOrder = i;
+        }
+        return ranked;
+    }
+
+    /// <summary>
+    /// Case-folded label with bracket quoting removed, as in CompletionItem::filter_text.
+    /// </summary>
+    private static string GetFilterText(string label)
+    {
+        if (label.Length >= 4 && (label.StartsWith("['") && label.EndsWith("']") || label.StartsWith("[\"") && label.EndsWith("\"]")))
+            label = label.Substring(2, label.Length - 4);
+        return label.ToLowerInvariant();
+    }
+
+    /// <summary>
+    /// 0 for an exact match, 1 for a prefix, 2 for a fuzzy match (the word's
+    /// characters in order), null for no match.
+    /// </summary>
+    private static int? MatchScore(string filterText, string word, bool fuzzy)
+    {
+        if (filterText == word)
+            return 0;
+        if (filterText.StartsWith(word, StringComparison.Ordinal))
+            return 1;
+        if (!fuzzy)
+            return null;
+
+        int next = 0;
+        foreach (var c in word)
+        {
+            next = filterText.IndexOf(c, next);
+            if (next < 0)
+                return null;
+            next++;
+        }
+        return 2;
+    }
+
+    /// <summary>
+    /// Rank of a kind: names first, keywords first only where an operator is
+    /// expected, punctuation last.
+    /// </summary>
+    private static int KindRank(string kind, bool operatorExpected)
+    {
+        return kind switch
+        {
+            "Keyword" when operatorExpected => 0,
+            "Column" => 1,
+            "LetVariable" or "Variable" or "Parameter" => 2,
+            "Function" or "AggregateFunction" => 3,
+            "Table" or "Database" or "Cluster" => 4,
+            "Keyword" or "Operator" or "Type" => 5,
+            "Punctuation" => 7,
+            _ => 6
+        };
+    }
+
     /// <summary>
     /// Map Kusto completion kind to our string representation.
     /// </summary>
@@ -84,13 +505,13 @@ public static class CompletionService
             CompletionKind.Example => "Other",
             CompletionKind.Table => "Table",
             CompletionKind.Column => "Column",
-            CompletionKind.Variable => "Variable",
+            CompletionKind.Variable => "LetVariable",
             CompletionKind.Parameter => "Parameter",
             CompletionKind.Database => "Database",
             CompletionKind.Cluster => "Cluster",
             CompletionKind.AggregateFunction => "AggregateFunction",
             CompletionKind.BuiltInFunction => "Function",
-            CompletionKind.LocalFunction => "Function",
+            CompletionKind.LocalFunction => "LetVariable",
             CompletionKind.DatabaseFunction => "Function",
             CompletionKind.Unknown => "Other",
             _ => "Other"
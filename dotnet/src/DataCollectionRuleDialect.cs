@@ -0,0 +1,167 @@
+using Kusto.Language;
+using Kusto.Language.Symbols;
+using Kusto.Language.Syntax;
+
+namespace KqlLanguageFfi;
+
+/// <summary>
+/// Data Collection Rule transformation dialect.
+/// Restricts transformKql queries to the single 'source' input table; the
+/// operator/function subset supported by Azure Monitor ingestion-time
+/// transformations is enforced through a built-in feature profile.
+/// </summary>
+public static class DataCollectionRuleDialect
+{
+    /// <summary>
+    /// Query reads from a table other than 'source'.
+    /// </summary>
+    public const string CodeInvalidSource = "DCR002";
+
+    /// <summary>
+    /// Statement not supported in transformations.
+    /// </summary>
+    public const string CodeUnsupportedStatement = "DCR004";
+
+    /// <summary>
+    /// The only table a transformation may read.
+    /// </summary>
+    public const string SourceTableName = "source";
+
+    /// <summary>
+    /// Operators and functions supported in transformations.
+    /// </summary>
+    public static readonly FeatureProfileDefinition Profile = new()
+    {
+        Name = "DataCollectionRule",
+        Operators = new HashSet<string>
+        {
+            "where", "extend", "project", "project-away", "project-rename", "parse", "print"
+        },
+        Plugins = new HashSet<string>(),
+        Functions = SupportedFunctions
+    };
+
+    private static HashSet<string> SupportedFunctions => new()
+    {
+        // Bitwise
+        "binary_and", "binary_or", "binary_not", "binary_shift_left", "binary_shift_right", "binary_xor",
+        // Conversion
+        "tobool", "toboolean", "todatetime", "todouble", "toreal", "tostring", "totimespan", "toint", "tolong", "toguid",
+        // Date and time
+        "ago", "datetime_add", "datetime_diff", "datetime_part", "dayofmonth", "dayofweek", "dayofyear",
+        "endofday", "endofmonth", "endofweek", "endofyear", "getmonth", "getyear", "hourofday",
+        "make_datetime", "make_timespan", "now", "startofday", "startofmonth", "startofweek", "startofyear",
+        "weekofyear", "unixtime_seconds_todatetime", "unixtime_milliseconds_todatetime",
+        // Dynamic and arrays
+        "array_concat", "array_length", "pack_array", "pack", "bag_pack", "parse_json", "todynamic", "parse_xml", "zip",
+        // Mathematical
+        "abs", "bin", "floor", "ceiling", "exp", "exp10", "exp2", "isfinite", "isinf", "isnan",
+        "log", "log10", "log2", "pow", "round", "sign",
+        // Conditional
+        "case", "iif", "iff", "max_of", "min_of",
+        // String
+        "base64_encodestring", "base64_encode_tostring", "base64_decodestring", "base64_decode_tostring",
+        "countof", "extract", "extract_all", "indexof", "isempty", "isnotempty", "replace", "replace_string",
+        "replace_regex", "split", "strcat", "strcat_delim", "strlen", "substring", "tolower", "toupper",
+        "trim", "trim_start", "trim_end", "hash_sha256",
+        // Type
+        "isnotnull", "isnull",
+        // Transformation-specific
+        "geo_location", "parse_cef_dictionary", "columnifexists"
+    };
+
+    /// <summary>
+    /// Run DCR restriction checks over an already parsed (and possibly analyzed) query.
+    /// </summary>
+    public static List<Kusto.Language.Diagnostic> Apply(KustoCode code, List<Kusto.Language.Diagnostic> diagnostics)
+    {
+        var result = FeatureProfileService.Apply(code, Profile, diagnostics);
+
+        CheckStatements(code, result);
+
+        // Semantic check: any resolved table reference must be 'source'
+        foreach (var nameRef in code.Syntax.GetDescendants<NameReference>(n => n.ReferencedSymbol is TableSymbol))
+        {
+            if (!IsSourceName(nameRef.SimpleName))
+            {
+                result.Add(MakeDiagnostic(
+                    CodeInvalidSource,
+                    $"DCR transformations can only read from the '{SourceTableName}' table, not '{nameRef.SimpleName}'",
+                    nameRef));
+            }
+        }
+
+        return result
+            .GroupBy(d => (d.Code, d.Start, d.End))
+            .Select(g => g.First())
+            .OrderBy(d => d.Start)
+            .ToList();
+    }
+
+    /// <summary>
+    /// Only let statements and a single query expression reading from 'source' are allowed.
+    /// </summary>
+    private static void CheckStatements(KustoCode code, List<Kusto.Language.Diagnostic> result)
+    {
+        if (code.Syntax is not QueryBlock block)
+            return;
+
+        var queryCount = 0;
+        foreach (var element in block.Statements)
+        {
+            switch (element.Element)
+            {
+                case LetStatement:
+                    break;
+
+                case ExpressionStatement statement:
+                    queryCount++;
+                    if (queryCount > 1)
+                    {
+                        result.Add(MakeDiagnostic(
+                            CodeUnsupportedStatement,
+                            "DCR transformations must contain a single query",
+                            statement));
+                    }
+
+                    var source = GetPipelineSource(statement.Expression);
+                    if (source is NameReference nameRef && !IsSourceName(nameRef.SimpleName))
+                    {
+                        result.Add(MakeDiagnostic(
+                            CodeInvalidSource,
+                            $"DCR transformations can only read from the '{SourceTableName}' table, not '{nameRef.SimpleName}'",
+                            nameRef));
+                    }
+                    break;
+
+                default:
+                    result.Add(MakeDiagnostic(
+                        CodeUnsupportedStatement,
+                        "Only 'let' statements and a single query are supported in DCR transformations",
+                        element.Element));
+                    break;
+            }
+        }
+    }
+
+    private static Expression GetPipelineSource(Expression expression)
+    {
+        var current = expression;
+        while (current is PipeExpression pipe)
+        {
+            current = pipe.Expression;
+        }
+        return current;
+    }
+
+    private static bool IsSourceName(string name)
+    {
+        return string.Equals(name, SourceTableName, StringComparison.Ordinal);
+    }
+
+    private static Kusto.Language.Diagnostic MakeDiagnostic(string code, string message, SyntaxElement location)
+    {
+        return new Kusto.Language.Diagnostic(code, DiagnosticCategory.Correctness, DiagnosticSeverity.Error, message, message)
+            .WithLocation(location.TextStart, location.End - location.TextStart);
+    }
+}
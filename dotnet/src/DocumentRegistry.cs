@@ -0,0 +1,185 @@
+using System.Collections.Concurrent;
+using Kusto.Language;
+using Kusto.Language.Editor;
+
+namespace KqlLanguageFfi;
+
+/// <summary>
+/// Registry of open documents referenced by id.
+/// Each document keeps its parsed blocks between edits so only the blocks an
+/// edit touches are parsed and analyzed again.
+/// </summary>
+public static class DocumentRegistry
+{
+    private static readonly ConcurrentDictionary<int, KqlDocument> Documents = new();
+    private static int _nextId;
+
+    /// <summary>
+    /// Open a document.
+    /// </summary>
+    /// <returns>A positive document id</returns>
+    public static int Open(KqlDocument document)
+    {
+        var id = Interlocked.Increment(ref _nextId);
+        Documents[id] = document;
+        return id;
+    }
+
+    /// <summary>
+    /// Close an open document.
+    /// </summary>
+    /// <returns>True if the id was open</returns>
+    public static bool Close(int id)
+    {
+        return Documents.TryRemove(id, out _);
+    }
+
+    /// <summary>
+    /// Look up an open document.
+    /// </summary>
+    public static bool TryGet(int id, out KqlDocument document)
+    {
+        return Documents.TryGetValue(id, out document!);
+    }
+}
+
+/// <summary>
+/// An open document: its text as a CodeScript plus validation results cached per block.
+/// </summary>
+public sealed class KqlDocument
+{
+    private readonly object _lock = new();
+    private readonly SchemaDefinition? _schema;
+    private readonly GlobalState? _globals;
+    private readonly ValidationOptions _options;
+    private CodeScript _script;
+    private Dictionary<string, ValidationResult> _results = new();
+
+    /// <summary>
+    /// Open a document, splitting it into blocks.
+    /// </summary>
+    /// <param name="text">Initial document text</param>
+    /// <param name="schema">Optional registered schema; without one only syntax is checked</param>
+    /// <param name="options">Analysis options (dialect, ...)</param>
+    public KqlDocument(string text, RegisteredSchema? schema, ValidationOptions options)
+    {
+        _schema = schema?.Definition;
+        _globals = schema?.GetGlobals(options);
+        _options = options;
+        _script = CodeScript.From(text, _globals ?? GlobalState.Default);
+    }
+
+    /// <summary>
+    /// Replace the characters in [start, end) with new text.
+    /// </summary>
+    /// <returns>False if the range is outside the document</returns>
+    public bool Edit(int start, int end, string newText)
+    {
+        lock (_lock)
+        {
+            var text = _script.Text;
+            if (start < 0 || end < start || end > text.Length)
+                return false;
+
+            // WithText keeps blocks whose text did not change
+            _script = _script.WithText(text.Substring(0, start) + newText + text.Substring(end));
+            return true;
+        }
+    }
+
+    /// <summary>
+    /// Validate the document, reusing results for blocks unchanged since the last call.
+    /// </summary>
+    public ValidationResult GetDiagnostics()
+    {
+        lock (_lock)
+        {
+            var text = _script.Text;
+            var results = new Dictionary<string, ValidationResult>();
+            var diagnostics = new List<Diagnostic>();
+
+            foreach (var block in _script.Blocks)
+            {
+                if (!_results.TryGetValue(block.Text, out var result))
+                {
+                    result = Validate(block.Text);
+                }
+                results[block.Text] = result;
+
+                foreach (var diagnostic in result.Diagnostics)
+                {
+                    diagnostics.Add(Shift(text, diagnostic, block.Start));
+                }
+            }
+
+            _results = results;
+            return new ValidationResult
+            {
+                Valid = diagnostics.All(d => d.Severity != "Error"),
+                Diagnostics = diagnostics
+            };
+        }
+    }
+
+    /// <summary>
+    /// Get completion items at a cursor position.
+    /// </summary>
+    public CompletionResult GetCompletions(int cursorPosition)
+    {
+        lock (_lock)
+        {
+            return CompletionService.GetCompletions(_script, cursorPosition);
+        }
+    }
+
+    private ValidationResult Validate(string blockText)
+    {
+        try
+        {
+            var code = _globals != null
+                ? KustoCode.ParseAndAnalyze(blockText, _globals)
+                : KustoCode.Parse(blockText);
+
+            return ValidationService.Validate(code, _schema, _globals != null, _options);
+        }
+        catch (Exception ex)
+        {
+            return ValidationService.ExceptionResult(ex);
+        }
+    }
+
+    /// <summary>
+    /// Move a block-relative diagnostic to its position in the document.
+    /// </summary>
+    private static Diagnostic Shift(string text, Diagnostic diagnostic, int offset)
+    {
+        var (line, column) = ValidationService.GetLineAndColumn(text, diagnostic.Start + offset);
+        return new Diagnostic
+        {
+            Message = diagnostic.Message,
+            Severity = diagnostic.Severity,
+            Start = diagnostic.Start + offset,
+            End = diagnostic.End + offset,
+            Line = line,
+            Column = column,
+            Code = diagnostic.Code,
+            Category = diagnostic.Category,
+            Fixes = diagnostic.Fixes?.Select(fix => new FixDefinition
+            {
+                Title = fix.Title,
+                Edits = fix.Edits.Select(edit => new TextEditDefinition
+                {
+                    Start = edit.Start + offset,
+                    End = edit.End + offset,
+                    NewText = edit.NewText
+                }).ToList()
+            }).ToList(),
+            RelatedSpans = diagnostic.RelatedSpans?.Select(related => new RelatedInfoDefinition
+            {
+                Message = related.Message,
+                Start = related.Start + offset,
+                End = related.End + offset
+            }).ToList()
+        };
+    }
+}
@@ -0,0 +1,202 @@
+using Kusto.Language;
+using Kusto.Language.Symbols;
+using Kusto.Language.Syntax;
+
+namespace KqlLanguageFfi;
+
+/// <summary>
+/// Checks property accesses on dynamic columns against the JSON paths
+/// observed in sample events or declared as the column's fields
+/// (e.g. parse_json(Properties).user.name).
+/// </summary>
+public static class DynamicSampleService
+{
+    /// <summary>
+    /// Property path never seen in the column's sample events.
+    /// </summary>
+    public const string CodeUnseenPath = "DS001";
+
+    /// <summary>
+    /// Property path not among the column's declared fields.
+    /// </summary>
+    public const string CodeUndeclaredField = "DS002";
+
+    private static readonly HashSet<string> ParseFunctions = new(StringComparer.OrdinalIgnoreCase)
+    {
+        "parse_json", "todynamic", "toobject"
+    };
+
+    /// <summary>
+    /// Run the sample-shape and declared-field checks over an analyzed query, appending any warnings.
+    /// </summary>
+    public static List<Kusto.Language.Diagnostic> Apply(
+        KustoCode code,
+        SchemaDefinition schema,
+        List<Kusto.Language.Diagnostic> diagnostics)
+    {
+        var samplePaths = GetSamplePaths(code.Globals, schema);
+        if (samplePaths.Count == 0)
+            return diagnostics;
+
+        var result = new List<Kusto.Language.Diagnostic>(diagnostics);
+
+        // Outermost access chains only: T.a.b is checked once, not also as T.a
+        foreach (var access in code.Syntax.GetDescendants<Expression>(e => IsAccess(e) && !IsAccessTarget(e)))
+        {
+            var segments = new List<(string Segment, SyntaxNode Selector)>();
+            var root = Unwind(access, segments);
+
+            if (GetColumn(root) is not ColumnSymbol column || !samplePaths.TryGetValue(column, out var known))
+                continue;
+            var (paths, declared) = known;
+
+            var path = "";
+            foreach (var (segment, selector) in segments)
+            {
+                path = segment == "[]" ? path + segment : path.Length == 0 ? segment : $"{path}.{segment}";
+                if (!paths.Contains(path))
+                {
+                    var message = declared
+                        ? $"Path '{column.Name}.{path}' is not a declared field"
+                        : $"Path '{column.Name}.{path}' was never seen in sample events";
+                    result.Add(new Kusto.Language.Diagnostic(
+                            declared ? CodeUndeclaredField : CodeUnseenPath,
+                            DiagnosticCategory.Correctness, DiagnosticSeverity.Warning, message, message)
+                        .WithLocation(selector.TextStart, selector.End - selector.TextStart));
+                    break;
+                }
+            }
+        }
+
+        return result.OrderBy(d => d.Start).ToList();
+    }
+
+    /// <summary>
+    /// Map the schema's column symbols to the paths observed or declared for
+    /// them, and whether any were declared.
+    /// </summary>
+    private static Dictionary<ColumnSymbol, (HashSet<string> Paths, bool Declared)> GetSamplePaths(
+        GlobalState globals,
+        SchemaDefinition schema)
+    {
+        var result = new Dictionary<ColumnSymbol, (HashSet<string>, bool)>();
+
+        foreach (var tableDef in schema.Tables ?? Enumerable.Empty<TableDefinition>())
+        {
+            var columnDefs = (tableDef.Columns ?? Enumerable.Empty<ColumnDefinition>())
+                .Where(c => c.SamplePaths is { Count: > 0 } || c.Fields is { Count: > 0 })
+                .ToList();
+            if (columnDefs.Count == 0)
+                continue;
+
+            if (globals.Database.GetTable(tableDef.Name) is not TableSymbol table)
+                continue;
+
+            foreach (var columnDef in columnDefs)
+            {
+                if (table.TryGetColumn(columnDef.Name, out var column))
+                {
+                    var paths = new HashSet<string>(
+                        columnDef.SamplePaths ?? Enumerable.Empty<string>(), StringComparer.Ordinal);
+                    foreach (var field in columnDef.Fields?.Keys ?? Enumerable.Empty<string>())
+                    {
+                        // A declared 'a.b[].c' also declares 'a', 'a.b' and 'a.b[]'
+                        for (var end = 0; end < field.Length; end++)
+                        {
+                            if (field[end] == '.' || (field[end] == '[' && end > 0))
+                                paths.Add(field.Substring(0, end));
+                        }
+                        paths.Add(field);
+                    }
+                    result[column] = (paths, columnDef.Fields is { Count: > 0 });
+                }
+            }
+        }
+
+        return result;
+    }
+
+    private static bool IsAccess(SyntaxNode node)
+    {
+        return node is PathExpression || node is ElementExpression;
+    }
+
+    /// <summary>
+    /// Check whether an access is itself the target of an enclosing access.
+    /// </summary>
+    private static bool IsAccessTarget(Expression expression)
+    {
+        return expression.Parent switch
+        {
+            PathExpression path => path.Expression == expression,
+            ElementExpression element => element.Expression == expression,
+            _ => false
+        };
+    }
+
+    /// <summary>
+    /// Collect the segments of an access chain in source order and return its root expression.
+    /// Segments after a non-literal element selector are not collected.
+    /// </summary>
+    private static Expression Unwind(Expression access, List<(string, SyntaxNode)> segments)
+    {
+        var current = access;
+        while (true)
+        {
+            switch (current)
+            {
+                case PathExpression path:
+                    var name = path.Selector switch
+                    {
+                        NameReference reference => reference.SimpleName,
+                        BracketedExpression { Expression: LiteralExpression literal } => literal.LiteralValue as string,
+                        _ => null
+                    };
+                    if (name == null)
+                        segments.Clear();
+                    else
+                        segments.Insert(0, (name, path.Selector));
+                    current = path.Expression;
+                    break;
+
+                case ElementExpression element:
+                    var segment = element.Selector.Expression is LiteralExpression { LiteralValue: var value }
+                        ? value switch
+                        {
+                            string key => key,
+                            int or long => "[]",
+                            _ => null
+                        }
+                        : null;
+                    if (segment == null)
+                        segments.Clear();
+                    else
+                        segments.Insert(0, (segment, element.Selector));
+                    current = element.Expression;
+                    break;
+
+                case ParenthesizedExpression parenthesized:
+                    current = parenthesized.Expression;
+                    break;
+
+                default:
+                    return current;
+            }
+        }
+    }
+
+    /// <summary>
+    /// Get the column a chain root refers to, directly or via parse_json(column).
+    /// </summary>
+    private static ColumnSymbol? GetColumn(Expression root)
+    {
+        if (root is FunctionCallExpression call
+            && ParseFunctions.Contains(call.Name.SimpleName)
+            && call.ArgumentList.Expressions.Count == 1)
+        {
+            root = call.ArgumentList.Expressions[0].Element;
+        }
+
+        return root is NameReference reference ? reference.ReferencedSymbol as ColumnSymbol : null;
+    }
+}
@@ -0,0 +1,93 @@
+using System.Diagnostics.CodeAnalysis;
+using Kusto.Language;
+using Kusto.Language.Syntax;
+
+namespace KqlLanguageFfi;
+
+/// <summary>
+/// Flags functions, plugins, and operators that are newer than the
+/// engine feature level validation is pinned to.
+/// Unverified (built-in catalog) entries are warnings; verified ones are errors.
+/// </summary>
+public static class EngineFeatureService
+{
+    /// <summary>
+    /// Feature introduced after the pinned engine feature level.
+    /// </summary>
+    public const string CodeFeatureUnavailable = "EL001";
+
+    /// <summary>
+    /// Run the engine pin checks over a parsed query, appending any violations.
+    /// </summary>
+    public static List<Kusto.Language.Diagnostic> Apply(
+        KustoCode code,
+        EnginePinDefinition pin,
+        List<Kusto.Language.Diagnostic> diagnostics)
+    {
+        var result = new List<Kusto.Language.Diagnostic>(diagnostics);
+        var unavailable = (pin.Unavailable ?? new List<FeatureAvailabilityDefinition>())
+            .GroupBy(f => f.Kind)
+            .ToDictionary(
+                g => g.Key,
+                g => g.ToDictionary(f => f.Name, StringComparer.OrdinalIgnoreCase));
+
+        foreach (var op in code.Syntax.GetDescendants<QueryOperator>())
+        {
+            var keyword = op.GetFirstToken();
+            if (TryFind(unavailable, "Operator", keyword.Text, out var feature))
+            {
+                result.Add(MakeDiagnostic(pin, $"The '{keyword.Text}' operator", feature, keyword));
+            }
+
+            if (op is EvaluateOperator evaluate
+                && TryFind(unavailable, "Plugin", evaluate.FunctionCall.Name.SimpleName, out feature))
+            {
+                result.Add(MakeDiagnostic(
+                    pin,
+                    $"The '{evaluate.FunctionCall.Name.SimpleName}' plugin",
+                    feature,
+                    evaluate.FunctionCall.Name));
+            }
+        }
+
+        // Locally declared functions shadow built-ins of the same name
+        var localNames = code.Syntax.GetDescendants<LetStatement>()
+            .Select(l => l.Name.SimpleName)
+            .ToHashSet(StringComparer.OrdinalIgnoreCase);
+
+        foreach (var call in code.Syntax.GetDescendants<FunctionCallExpression>(c => c.Parent is not EvaluateOperator))
+        {
+            var name = call.Name.SimpleName;
+            if (!localNames.Contains(name) && TryFind(unavailable, "Function", name, out var feature))
+            {
+                result.Add(MakeDiagnostic(pin, $"The function '{name}'", feature, call.Name));
+            }
+        }
+
+        return result.OrderBy(d => d.Start).ToList();
+    }
+
+    private static bool TryFind(
+        Dictionary<string, Dictionary<string, FeatureAvailabilityDefinition>> unavailable,
+        string kind,
+        string name,
+        [NotNullWhen(true)] out FeatureAvailabilityDefinition? feature)
+    {
+        feature = null;
+        return unavailable.TryGetValue(kind, out var byName) && byName.TryGetValue(name, out feature);
+    }
+
+    private static Kusto.Language.Diagnostic MakeDiagnostic(
+        EnginePinDefinition pin,
+        string subject,
+        FeatureAvailabilityDefinition feature,
+        SyntaxElement location)
+    {
+        var message = feature.Verified
+            ? $"{subject} requires engine feature level {feature.Introduced} (validation pinned to {pin.Level})"
+            : $"{subject} may require engine feature level {feature.Introduced} (validation pinned to {pin.Level}; catalog date unverified)";
+        var severity = feature.Verified ? DiagnosticSeverity.Error : DiagnosticSeverity.Warning;
+        return new Kusto.Language.Diagnostic(CodeFeatureUnavailable, DiagnosticCategory.Correctness, severity, message, message)
+            .WithLocation(location.TextStart, location.End - location.TextStart);
+    }
+}
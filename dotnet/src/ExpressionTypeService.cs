@@ -0,0 +1,76 @@
+using Kusto.Language;
+using Kusto.Language.Symbols;
+using Kusto.Language.Syntax;
+
+namespace KqlLanguageFfi;
+
+/// <summary>
+/// Expression type service.
+/// Reports the type semantic analysis infers for the expression at a position.
+/// </summary>
+public static class ExpressionTypeService
+{
+    /// <summary>
+    /// Get the type of the innermost expression at a position.
+    /// </summary>
+    /// <param name="query">The KQL query</param>
+    /// <param name="position">Position (0-based character offset)</param>
+    /// <param name="schema">Schema definition the query runs against</param>
+    /// <param name="options">Analysis options (dialect, ...)</param>
+    /// <returns>The expression type, or null if there is no typed expression at the position</returns>
+    public static ExpressionTypeResult? GetExpressionType(string query, int position, SchemaDefinition schema, ValidationOptions options)
+    {
+        var globals = ValidationService.BuildGlobalState(schema, options);
+        var code = KustoCode.ParseAndAnalyze(query, globals);
+
+        // A position just past the end of a token still refers to it
+        var expression = GetExpressionAt(code, position) ?? GetExpressionAt(code, position - 1);
+        if (expression == null)
+            return null;
+
+        var result = new ExpressionTypeResult { Start = expression.TextStart, End = expression.End };
+        switch (expression.ResultType)
+        {
+            case TableSymbol table:
+                result.Kind = "Tabular";
+                result.Columns = table.Columns
+                    .Select(c => new ColumnDefinition { Name = c.Name, DataType = ResultSchemaService.GetTypeName(c.Type) })
+                    .ToList();
+                break;
+            case ScalarSymbol scalar:
+                result.Kind = "Scalar";
+                result.Name = ResultSchemaService.GetTypeName(scalar);
+                break;
+            default:
+                result.Kind = expression.ResultType.Kind.ToString();
+                break;
+        }
+
+        return result;
+    }
+
+    /// <summary>
+    /// Find the innermost expression with a known type containing a position.
+    /// </summary>
+    private static Expression? GetExpressionAt(KustoCode code, int position)
+    {
+        if (position < 0)
+            return null;
+
+        var token = code.Syntax.GetTokenAt(position);
+        if (token == null || token.TextStart > position)
+            return null;
+
+        for (var node = token.Parent; node != null; node = node.Parent)
+        {
+            if (node is Expression expression
+                && expression.ResultType != null
+                && expression.ResultType != ErrorSymbol.Instance)
+            {
+                return expression;
+            }
+        }
+
+        return null;
+    }
+}
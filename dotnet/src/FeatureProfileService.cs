@@ -0,0 +1,131 @@
+using Kusto.Language;
+using Kusto.Language.Syntax;
+
+namespace KqlLanguageFfi;
+
+/// <summary>
+/// Checks queries against restricted feature profiles
+/// (allowed operators, plugins, functions, and maximum pipeline depth).
+/// </summary>
+public static class FeatureProfileService
+{
+    /// <summary>
+    /// Tabular operator not allowed by the profile.
+    /// </summary>
+    public const string CodeOperatorNotAllowed = "FP001";
+
+    /// <summary>
+    /// Evaluate plugin not allowed by the profile.
+    /// </summary>
+    public const string CodePluginNotAllowed = "FP002";
+
+    /// <summary>
+    /// Function not allowed by the profile.
+    /// </summary>
+    public const string CodeFunctionNotAllowed = "FP003";
+
+    /// <summary>
+    /// Pipeline has more operators than the profile allows.
+    /// </summary>
+    public const string CodePipelineTooDeep = "FP004";
+
+    /// <summary>
+    /// Run the profile's checks over a parsed query, appending any violations.
+    /// </summary>
+    public static List<Kusto.Language.Diagnostic> Apply(
+        KustoCode code,
+        FeatureProfileDefinition profile,
+        List<Kusto.Language.Diagnostic> diagnostics)
+    {
+        var result = new List<Kusto.Language.Diagnostic>(diagnostics);
+
+        foreach (var op in code.Syntax.GetDescendants<QueryOperator>())
+        {
+            var keyword = op.GetFirstToken();
+            if (!Allows(profile.Operators, keyword.Text))
+            {
+                result.Add(MakeDiagnostic(
+                    CodeOperatorNotAllowed,
+                    $"The '{keyword.Text}' operator is not allowed by profile '{profile.Name}'",
+                    keyword));
+            }
+
+            if (op is EvaluateOperator evaluate && !Allows(profile.Plugins, evaluate.FunctionCall.Name.SimpleName))
+            {
+                result.Add(MakeDiagnostic(
+                    CodePluginNotAllowed,
+                    $"The '{evaluate.FunctionCall.Name.SimpleName}' plugin is not allowed by profile '{profile.Name}'",
+                    evaluate.FunctionCall.Name));
+            }
+        }
+
+        // Locally declared functions (let f = (...) { ... }) are always allowed
+        var localNames = code.Syntax.GetDescendants<LetStatement>()
+            .Select(l => l.Name.SimpleName)
+            .ToHashSet(StringComparer.OrdinalIgnoreCase);
+
+        foreach (var call in code.Syntax.GetDescendants<FunctionCallExpression>(c => c.Parent is not EvaluateOperator))
+        {
+            var name = call.Name.SimpleName;
+            if (!localNames.Contains(name) && !Allows(profile.Functions, name))
+            {
+                result.Add(MakeDiagnostic(
+                    CodeFunctionNotAllowed,
+                    $"The function '{name}' is not allowed by profile '{profile.Name}'",
+                    call.Name));
+            }
+        }
+
+        if (profile.MaxPipelineDepth is int maxDepth)
+        {
+            foreach (var pipeline in GetPipelines(code.Syntax))
+            {
+                var operators = GetPipelineOperators(pipeline);
+                if (operators.Count > maxDepth)
+                {
+                    result.Add(MakeDiagnostic(
+                        CodePipelineTooDeep,
+                        $"Pipeline has {operators.Count} operators; profile '{profile.Name}' allows at most {maxDepth}",
+                        operators[maxDepth]));
+                }
+            }
+        }
+
+        return result.OrderBy(d => d.Start).ToList();
+    }
+
+    private static bool Allows(HashSet<string>? allowed, string name)
+    {
+        return allowed == null || allowed.Contains(name.ToLowerInvariant());
+    }
+
+    /// <summary>
+    /// Get the outermost pipe expression of every pipeline in the tree.
+    /// </summary>
+    private static IEnumerable<PipeExpression> GetPipelines(SyntaxNode root)
+    {
+        return root.GetDescendants<PipeExpression>(p => !(p.Parent is PipeExpression parent && parent.Expression == p));
+    }
+
+    /// <summary>
+    /// Get the operators of a pipeline in source order.
+    /// </summary>
+    public static List<QueryOperator> GetPipelineOperators(PipeExpression pipeline)
+    {
+        var operators = new List<QueryOperator>();
+        Expression current = pipeline;
+        while (current is PipeExpression pipe)
+        {
+            operators.Add(pipe.Operator);
+            current = pipe.Expression;
+        }
+        operators.Reverse();
+        return operators;
+    }
+
+    private static Kusto.Language.Diagnostic MakeDiagnostic(string code, string message, SyntaxElement location)
+    {
+        return new Kusto.Language.Diagnostic(code, DiagnosticCategory.Correctness, DiagnosticSeverity.Error, message, message)
+            .WithLocation(location.TextStart, location.End - location.TextStart);
+    }
+}
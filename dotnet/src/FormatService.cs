@@ -0,0 +1,68 @@
+using System.Text;
+using Kusto.Language;
+using Kusto.Language.Editor;
+using Kusto.Language.Syntax;
+
+namespace KqlLanguageFfi;
+
+/// <summary>
+/// KQL formatting service.
+/// Wraps the Kusto.Language formatter and optionally normalizes the casing
+/// of query operator names before formatting.
+/// </summary>
+public static class FormatService
+{
+    /// <summary>
+    /// Format a KQL query.
+    /// </summary>
+    /// <param name="query">The KQL query</param>
+    /// <param name="options">Formatting options</param>
+    /// <returns>The formatted query</returns>
+    public static string Format(string query, FormatOptions options)
+    {
+        if (options.OperatorCase == "Lowercase")
+        {
+            query = LowercaseOperators(query);
+        }
+
+        var formattingOptions = FormattingOptions.Default
+            .WithIndentationSize(options.IndentSize)
+            .WithPipeOperatorStyle(options.PipeStyle switch
+            {
+                "Smart" => PlacementStyle.Smart,
+                "Preserve" => PlacementStyle.None,
+                _ => PlacementStyle.NewLine
+            });
+
+        var code = KustoCode.Parse(query);
+        return new KustoCodeService(code).GetFormattedText(formattingOptions).Text;
+    }
+
+    /// <summary>
+    /// Lowercase identifiers following a pipe whose lowercase form is a query operator keyword
+    /// (e.g. "| Where" becomes "| where").
+    /// </summary>
+    private static string LowercaseOperators(string query)
+    {
+        var code = KustoCode.Parse(query);
+        var builder = new StringBuilder(query);
+        SyntaxToken? previous = null;
+
+        for (var token = code.Syntax.GetFirstToken(); token != null; token = token.GetNextToken())
+        {
+            if (previous?.Kind == SyntaxKind.BarToken
+                && token.Kind == SyntaxKind.IdentifierToken
+                && token.Text != token.Text.ToLowerInvariant()
+                && SyntaxFacts.TryGetKind(token.Text.ToLowerInvariant(), out var kind)
+                && kind.GetCategory() == SyntaxCategory.Keyword)
+            {
+                builder.Remove(token.TextStart, token.Text.Length);
+                builder.Insert(token.TextStart, token.Text.ToLowerInvariant());
+            }
+
+            previous = token;
+        }
+
+        return builder.ToString();
+    }
+}
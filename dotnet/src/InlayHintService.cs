@@ -0,0 +1,107 @@
+using Kusto.Language;
+using Kusto.Language.Symbols;
+using Kusto.Language.Syntax;
+
+namespace KqlLanguageFfi;
+
+/// <summary>
+/// Inlay hint service.
+/// Reports the inferred types of scalar let bindings, the parameter names of
+/// function call arguments, and the column count after each pipeline stage.
+/// </summary>
+public static class InlayHintService
+{
+    /// <summary>
+    /// Get the inlay hints of a query within a range.
+    /// </summary>
+    /// <param name="query">The KQL query</param>
+    /// <param name="start">Start of the range (0-based character offset)</param>
+    /// <param name="end">End of the range (0-based character offset)</param>
+    /// <param name="schema">Schema definition the query runs against</param>
+    /// <param name="options">Analysis options (dialect, ...)</param>
+    /// <returns>Hints ordered by position</returns>
+    public static List<InlayHintResult> GetInlayHints(string query, int start, int end, SchemaDefinition schema, ValidationOptions options)
+    {
+        var globals = ValidationService.BuildGlobalState(schema, options);
+        var code = KustoCode.ParseAndAnalyze(query, globals);
+        var hints = new List<InlayHintResult>();
+
+        foreach (var let in code.Syntax.GetDescendants<LetStatement>())
+        {
+            if (let.Expression is not FunctionDeclaration
+                && let.Expression.ResultType is ScalarSymbol scalar
+                && scalar != ErrorSymbol.Instance)
+            {
+                hints.Add(new InlayHintResult
+                {
+                    Position = let.Name.End,
+                    Label = $": {ResultSchemaService.GetTypeName(scalar)}",
+                    Kind = "Type"
+                });
+            }
+        }
+
+        foreach (var call in code.Syntax.GetDescendants<FunctionCallExpression>())
+        {
+            AddParameterHints(call, hints);
+        }
+
+        foreach (var pipe in code.Syntax.GetDescendants<PipeExpression>())
+        {
+            if (pipe.ResultType is TableSymbol table)
+            {
+                var count = table.Columns.Count;
+                hints.Add(new InlayHintResult
+                {
+                    Position = pipe.End,
+                    Label = count == 1 ? "1 column" : $"{count} columns",
+                    Kind = "ColumnCount"
+                });
+            }
+        }
+
+        return hints
+            .Where(h => h.Position >= start && h.Position <= end)
+            .OrderBy(h => h.Position)
+            .ToList();
+    }
+
+    /// <summary>
+    /// Add a hint naming the parameter before each positional argument.
+    /// Single-parameter functions and arguments already named like their
+    /// parameter get none.
+    /// </summary>
+    private static void AddParameterHints(FunctionCallExpression call, List<InlayHintResult> hints)
+    {
+        if (call.ReferencedSymbol is not FunctionSymbol function)
+            return;
+
+        var arguments = call.ArgumentList.Expressions.Select(e => e.Element).ToList();
+        var signature = function.Signatures.FirstOrDefault(s =>
+            s.MinArgumentCount <= arguments.Count && arguments.Count <= s.MaxArgumentCount);
+        if (signature == null || signature.Parameters.Count < 2)
+            return;
+
+        for (var i = 0; i < arguments.Count; i++)
+        {
+            var argument = arguments[i];
+            if (argument is SimpleNamedExpression || argument.Width == 0)
+                continue;
+
+            // Repeatable trailing parameters name every remaining argument
+            var parameter = signature.Parameters[Math.Min(i, signature.Parameters.Count - 1)];
+            if (argument is NameReference name
+                && string.Equals(name.SimpleName, parameter.Name, StringComparison.OrdinalIgnoreCase))
+            {
+                continue;
+            }
+
+            hints.Add(new InlayHintResult
+            {
+                Position = argument.TextStart,
+                Label = $"{parameter.Name}:",
+                Kind = "Parameter"
+            });
+        }
+    }
+}
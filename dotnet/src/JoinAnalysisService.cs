@@ -0,0 +1,205 @@
+using Kusto.Language;
+using Kusto.Language.Symbols;
+using Kusto.Language.Syntax;
+
+namespace KqlLanguageFfi;
+
+/// <summary>
+/// Join and union analysis service.
+/// Lists the joins, lookups and unions of a query with their kinds, inputs
+/// and keys, and warns about keys that are likely to be wrong or slow.
+/// </summary>
+public static class JoinAnalysisService
+{
+    /// <summary>
+    /// Join or lookup key whose sides have different types.
+    /// </summary>
+    public const string CodeKeyTypeMismatch = "JN001";
+
+    /// <summary>
+    /// High-cardinality join key without a shuffle hint.
+    /// </summary>
+    public const string CodeHighCardinalityKey = "JN002";
+
+    /// <summary>
+    /// Key types whose values are nearly all distinct.
+    /// </summary>
+    private static readonly HashSet<string> HighCardinalityTypes = new(StringComparer.Ordinal)
+    {
+        "guid", "datetime", "timespan", "real", "decimal"
+    };
+
+    /// <summary>
+    /// Analyze the joins, lookups and unions of a query.
+    /// </summary>
+    /// <param name="query">The KQL query</param>
+    /// <param name="schema">Schema definition the query runs against</param>
+    /// <param name="options">Analysis options (dialect, ...)</param>
+    /// <returns>The operations in source order, with key warnings</returns>
+    public static JoinAnalysisResult AnalyzeJoins(string query, SchemaDefinition schema, ValidationOptions options)
+    {
+        var globals = ValidationService.BuildGlobalState(schema, options);
+        var code = KustoCode.ParseAndAnalyze(query, globals);
+        var result = new JoinAnalysisResult();
+        var diagnostics = new List<Kusto.Language.Diagnostic>();
+
+        foreach (var op in code.Syntax.GetDescendants<QueryOperator>(
+                     n => n is JoinOperator || n is LookupOperator || n is UnionOperator))
+        {
+            var operation = new JoinOperationResult
+            {
+                Start = op.TextStart,
+                End = op.End
+            };
+
+            // The piped-in input comes first
+            var input = op.Parent is PipeExpression pipe && pipe.Operator == op ? pipe.Expression : null;
+            if (input != null)
+                operation.Sources.Add(GetText(query, input));
+
+            switch (op)
+            {
+                case JoinOperator join:
+                    operation.Operator = "Join";
+                    operation.Kind = GetParameter(query, join.Parameters, "kind") ?? "innerunique";
+                    operation.Sources.Add(GetText(query, join.Expression));
+                    var shuffled = GetParameter(query, join.Parameters, "hint.shufflekey") != null
+                        || string.Equals(GetParameter(query, join.Parameters, "hint.strategy"), "shuffle", StringComparison.OrdinalIgnoreCase);
+                    AddKeys(operation, join.ConditionClause, input, join.Expression, !shuffled, diagnostics);
+                    break;
+
+                case LookupOperator lookup:
+                    operation.Operator = "Lookup";
+                    operation.Kind = GetParameter(query, lookup.Parameters, "kind") ?? "leftouter";
+                    operation.Sources.Add(GetText(query, lookup.Expression));
+                    // Lookups broadcast the right side, so shuffling does not apply
+                    AddKeys(operation, lookup.LookupClause, input, lookup.Expression, false, diagnostics);
+                    break;
+
+                case UnionOperator union:
+                    operation.Operator = "Union";
+                    operation.Kind = GetParameter(query, union.Parameters, "kind") ?? "outer";
+                    foreach (var expression in union.Expressions)
+                        operation.Sources.Add(GetText(query, expression.Element));
+                    break;
+            }
+
+            result.Operations.Add(operation);
+        }
+
+        result.Diagnostics = ValidationService.CreateResult(query, diagnostics.OrderBy(d => d.Start).ToList()).Diagnostics;
+        return result;
+    }
+
+    /// <summary>
+    /// Add the keys of a join or lookup condition, with warnings for suspect keys.
+    /// </summary>
+    private static void AddKeys(
+        JoinOperationResult operation,
+        JoinConditionClause? clause,
+        Expression? left,
+        Expression right,
+        bool checkCardinality,
+        List<Kusto.Language.Diagnostic> diagnostics)
+    {
+        if (clause is not JoinOnClause on)
+            return;
+
+        var leftTable = left?.ResultType as TableSymbol;
+        var rightTable = right.ResultType as TableSymbol;
+
+        foreach (var element in on.Expressions)
+        {
+            var expression = element.Element;
+            string leftName, rightName;
+            switch (expression)
+            {
+                case NameReference name:
+                    leftName = rightName = name.SimpleName;
+                    break;
+
+                case BinaryExpression { Kind: SyntaxKind.EqualExpression } equal
+                    when GetSideColumn(equal.Left) is var (leftSide, leftColumn)
+                        && GetSideColumn(equal.Right) is var (rightSide, rightColumn)
+                        && leftSide != rightSide:
+                    // $right.B == $left.A is the same key as $left.A == $right.B
+                    (leftName, rightName) = leftSide == "$left" ? (leftColumn, rightColumn) : (rightColumn, leftColumn);
+                    break;
+
+                default:
+                    continue;
+            }
+
+            var key = new JoinKeyResult
+            {
+                Left = leftName,
+                Right = rightName,
+                LeftType = GetColumnType(leftTable, leftName),
+                RightType = GetColumnType(rightTable, rightName)
+            };
+            operation.Keys.Add(key);
+
+            if (key.LeftType != null && key.RightType != null && key.LeftType != key.RightType)
+            {
+                diagnostics.Add(MakeDiagnostic(
+                    CodeKeyTypeMismatch,
+                    DiagnosticCategory.Correctness,
+                    $"Join key '{leftName}' is {key.LeftType} but '{rightName}' is {key.RightType}; rows only match after converting every value",
+                    expression));
+            }
+            else if (checkCardinality && key.LeftType != null && HighCardinalityTypes.Contains(key.LeftType))
+            {
+                diagnostics.Add(MakeDiagnostic(
+                    CodeHighCardinalityKey,
+                    DiagnosticCategory.Performance,
+                    $"Join key '{leftName}' is a high-cardinality {key.LeftType}; consider hint.shufflekey={leftName} or hint.strategy=shuffle",
+                    expression));
+            }
+        }
+    }
+
+    /// <summary>
+    /// Get the side and column of a $left.X or $right.X key operand.
+    /// </summary>
+    private static (string Side, string Column)? GetSideColumn(Expression expression)
+    {
+        if (expression is PathExpression { Expression: NameReference side, Selector: NameReference column }
+            && (side.SimpleName == "$left" || side.SimpleName == "$right"))
+        {
+            return (side.SimpleName, column.SimpleName);
+        }
+        return null;
+    }
+
+    private static string? GetColumnType(TableSymbol? table, string name)
+    {
+        return table != null && table.TryGetColumn(name, out var column)
+            ? ResultSchemaService.GetTypeName(column.Type)
+            : null;
+    }
+
+    /// <summary>
+    /// Get the value text of an operator parameter such as kind=inner.
+    /// </summary>
+    private static string? GetParameter(string query, SyntaxList<NamedParameter> parameters, string name)
+    {
+        var parameter = parameters.FirstOrDefault(
+            p => string.Equals(p.Name.SimpleName, name, StringComparison.OrdinalIgnoreCase));
+        return parameter == null ? null : GetText(query, parameter.Expression);
+    }
+
+    private static string GetText(string query, SyntaxNode node)
+    {
+        return query.Substring(node.TextStart, node.End - node.TextStart);
+    }
+
+    private static Kusto.Language.Diagnostic MakeDiagnostic(
+        string code,
+        DiagnosticCategory category,
+        string message,
+        SyntaxNode location)
+    {
+        return new Kusto.Language.Diagnostic(code, category, DiagnosticSeverity.Warning, message, message)
+            .WithLocation(location.TextStart, location.End - location.TextStart);
+    }
+}
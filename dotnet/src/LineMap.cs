@@ -0,0 +1,24 @@
+namespace KqlLanguageFfi;
+
+/// <summary>
+/// Maps character offsets to 1-based line numbers.
+/// </summary>
+internal sealed class LineMap
+{
+    private readonly List<int> _lineStarts = new() { 0 };
+
+    public LineMap(string text)
+    {
+        for (int i = 0; i < text.Length; i++)
+        {
+            if (text[i] == '\n')
+                _lineStarts.Add(i + 1);
+        }
+    }
+
+    public int GetLine(int offset)
+    {
+        var index = _lineStarts.BinarySearch(offset);
+        return index >= 0 ? index + 1 : ~index;
+    }
+}
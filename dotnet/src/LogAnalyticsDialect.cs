@@ -0,0 +1,289 @@
+using Kusto.Language;
+using Kusto.Language.Symbols;
+using Kusto.Language.Syntax;
+
+namespace KqlLanguageFfi;
+
+/// <summary>
+/// Log Analytics dialect support.
+/// Validates the cross-resource scoping functions workspace(), app(), resource()
+/// and adx(), and resolves their tables against the per-scope schemas.
+/// </summary>
+public static class LogAnalyticsDialect
+{
+    /// <summary>
+    /// Scoping function has the wrong number or form of arguments.
+    /// </summary>
+    public const string CodeInvalidScopeArgument = "LA001";
+
+    /// <summary>
+    /// Scoping function used somewhere other than as a table source.
+    /// </summary>
+    public const string CodeInvalidScopePosition = "LA002";
+
+    /// <summary>
+    /// Table not found in a known scope.
+    /// </summary>
+    public const string CodeUnknownScopeTable = "LA003";
+
+    /// <summary>
+    /// adx() source without a remote schema; names are not verified.
+    /// </summary>
+    public const string CodeUnresolvedAdxSource = "LA004";
+
+    private static readonly string[] ScopeFunctionNames = { "workspace", "app", "resource", "adx" };
+
+    /// <summary>
+    /// Build function symbols for the scoping functions.
+    /// Each call resolves to a database containing the matching scope's tables,
+    /// or to an error type (opaque, no further diagnostics) when the scope is unknown.
+    /// </summary>
+    public static IEnumerable<Symbol> GetScopeFunctions(SchemaDefinition schema)
+    {
+        foreach (var name in ScopeFunctionNames)
+        {
+            var scopes = GetScopes(schema, name);
+
+            yield return new FunctionSymbol(
+                name,
+                context => ResolveScope(context, scopes),
+                Tabularity.Tabular,
+                new Parameter("name", ScalarTypes.String));
+        }
+    }
+
+    /// <summary>
+    /// Run dialect checks over an already parsed (and possibly analyzed) query.
+    /// Native diagnostics inside scoping function calls are replaced by our own,
+    /// since the core binder has no knowledge of the argument rules.
+    /// </summary>
+    public static List<Kusto.Language.Diagnostic> Apply(
+        KustoCode code,
+        SchemaDefinition? schema,
+        List<Kusto.Language.Diagnostic> diagnostics)
+    {
+        var added = new List<Kusto.Language.Diagnostic>();
+        var replaced = new List<(int Start, int End)>();
+        var degraded = new List<(int Start, int End)>();
+
+        foreach (var call in code.Syntax.GetDescendants<FunctionCallExpression>(IsScopeCall))
+        {
+            var functionName = call.Name.SimpleName.ToLowerInvariant();
+            replaced.Add((call.TextStart, call.End));
+
+            // Argument form: exactly one non-empty string literal in the expected shape
+            var argument = call.ArgumentList.Expressions.Count == 1
+                ? GetStringLiteral(call.ArgumentList.Expressions[0].Element)
+                : null;
+            var argumentError = argument == null
+                ? $"{functionName}() expects a single string literal argument"
+                : CheckArgumentForm(functionName, argument);
+            if (argumentError != null)
+            {
+                added.Add(MakeDiagnostic(CodeInvalidScopeArgument, argumentError, call.ArgumentList));
+            }
+
+            // Position: must be followed by a table reference
+            if (call.Parent is not PathExpression path || path.Expression != call)
+            {
+                added.Add(MakeDiagnostic(
+                    CodeInvalidScopePosition,
+                    $"{functionName}() must be followed by a table reference, e.g. {functionName}('name').TableName",
+                    call));
+                continue;
+            }
+
+            // Table resolution against the per-scope schema, when one was supplied
+            if (argument == null || schema == null || path.Selector is not NameReference tableRef)
+                continue;
+
+            var scope = FindScope(GetScopes(schema, functionName), argument);
+            if (scope == null)
+            {
+                // Cross-service adx() queries are legal even when we don't know the
+                // remote schema: errors in the pipeline it feeds become warnings.
+                if (functionName == "adx")
+                {
+                    added.Add(MakeDiagnostic(
+                        CodeUnresolvedAdxSource,
+                        $"No schema supplied for adx('{argument}'); table and column names are not verified",
+                        path,
+                        DiagnosticSeverity.Warning));
+                    degraded.Add((path.TextStart, GetPipelineFrom(path).End));
+                }
+                continue;
+            }
+
+            replaced.Add((tableRef.TextStart, tableRef.End));
+            var tableName = tableRef.SimpleName;
+            var found = (scope.Tables ?? Enumerable.Empty<TableDefinition>())
+                .Any(t => string.Equals(t.Name, tableName, StringComparison.OrdinalIgnoreCase));
+            if (!found)
+            {
+                added.Add(MakeDiagnostic(
+                    CodeUnknownScopeTable,
+                    $"The table '{tableName}' does not exist in {functionName}('{argument}')",
+                    tableRef));
+            }
+        }
+
+        var result = diagnostics
+            .Where(d => !replaced.Any(r => d.Start >= r.Start && d.End <= r.End))
+            .Select(d => degraded.Any(r => d.Start >= r.Start && d.End <= r.End) ? ToWarning(d) : d)
+            .ToList();
+        result.AddRange(added);
+        return result.OrderBy(d => d.Start).ToList();
+    }
+
+    /// <summary>
+    /// Check the argument against the forms accepted by each scoping function.
+    /// Returns an error message, or null when the argument is acceptable.
+    /// </summary>
+    private static string? CheckArgumentForm(string functionName, string argument)
+    {
+        if (string.IsNullOrWhiteSpace(argument))
+            return $"{functionName}() argument must not be empty";
+
+        switch (functionName)
+        {
+            case "workspace":
+                if (argument.StartsWith('/') && !ContainsProvider(argument, "/providers/microsoft.operationalinsights/workspaces/"))
+                    return "workspace() resource IDs must refer to a Microsoft.OperationalInsights/workspaces resource";
+                return null;
+
+            case "app":
+                if (argument.StartsWith('/') && !ContainsProvider(argument, "/providers/microsoft.insights/components/"))
+                    return "app() resource IDs must refer to a Microsoft.Insights/components resource";
+                return null;
+
+            case "resource":
+                if (!argument.StartsWith("/subscriptions/", StringComparison.OrdinalIgnoreCase))
+                    return "resource() expects an Azure resource ID, e.g. '/subscriptions/<id>/resourceGroups/<group>'";
+                return null;
+
+            case "adx":
+                var (cluster, database) = SplitAdxArgument(argument);
+                if (string.IsNullOrEmpty(cluster) || string.IsNullOrEmpty(database))
+                    return "adx() expects a cluster and database, e.g. 'https://help.kusto.windows.net/Samples'";
+                return null;
+
+            default:
+                return null;
+        }
+    }
+
+    private static bool ContainsProvider(string resourceId, string provider)
+    {
+        return resourceId.Contains(provider, StringComparison.OrdinalIgnoreCase);
+    }
+
+    /// <summary>
+    /// Split an adx() argument into cluster and database parts.
+    /// </summary>
+    public static (string Cluster, string Database) SplitAdxArgument(string argument)
+    {
+        var text = argument.Trim();
+        var schemeEnd = text.IndexOf("://", StringComparison.Ordinal);
+        if (schemeEnd >= 0)
+            text = text.Substring(schemeEnd + 3);
+
+        var slash = text.IndexOf('/');
+        if (slash < 0)
+            return (text, "");
+
+        return (text.Substring(0, slash), text.Substring(slash + 1).Trim('/'));
+    }
+
+    /// <summary>
+    /// Custom return type for scoping functions: the scope's database, if known.
+    /// </summary>
+    private static TypeSymbol ResolveScope(CustomReturnTypeContext context, List<ScopeDefinition> scopes)
+    {
+        var argument = context.Arguments.Count == 1 ? GetStringLiteral(context.Arguments[0]) : null;
+        var scope = argument != null ? FindScope(scopes, argument) : null;
+        if (scope == null)
+            return ErrorSymbol.Instance;
+
+        return new DatabaseSymbol(scope.Name, ValidationService.BuildTableSymbols(scope.Tables));
+    }
+
+    private static List<ScopeDefinition> GetScopes(SchemaDefinition schema, string functionName)
+    {
+        return (schema.Scopes ?? Enumerable.Empty<ScopeDefinition>())
+            .Where(s => string.Equals(s.Kind, functionName, StringComparison.OrdinalIgnoreCase))
+            .ToList();
+    }
+
+    private static ScopeDefinition? FindScope(List<ScopeDefinition> scopes, string argument)
+    {
+        return scopes.FirstOrDefault(s => ScopeNameMatches(s, argument));
+    }
+
+    /// <summary>
+    /// Compare a scope name with a function argument.
+    /// adx() names match regardless of scheme and short vs fully-qualified cluster name,
+    /// so 'help/Samples' matches 'https://help.kusto.windows.net/Samples'.
+    /// </summary>
+    private static bool ScopeNameMatches(ScopeDefinition scope, string argument)
+    {
+        if (!string.Equals(scope.Kind, "adx", StringComparison.OrdinalIgnoreCase))
+            return string.Equals(scope.Name, argument, StringComparison.OrdinalIgnoreCase);
+
+        var (scopeCluster, scopeDatabase) = SplitAdxArgument(scope.Name);
+        var (argCluster, argDatabase) = SplitAdxArgument(argument);
+        return string.Equals(NormalizeCluster(scopeCluster), NormalizeCluster(argCluster), StringComparison.OrdinalIgnoreCase)
+            && string.Equals(scopeDatabase, argDatabase, StringComparison.OrdinalIgnoreCase);
+    }
+
+    private static string NormalizeCluster(string cluster)
+    {
+        var host = cluster.TrimEnd('/');
+        return host.Contains('.') ? host : $"{host}.kusto.windows.net";
+    }
+
+    /// <summary>
+    /// Find the outermost pipe expression whose leftmost source is the given expression.
+    /// </summary>
+    private static SyntaxNode GetPipelineFrom(SyntaxNode source)
+    {
+        var current = source;
+        while (current.Parent is PipeExpression pipe && pipe.Expression == current)
+        {
+            current = pipe;
+        }
+        return current;
+    }
+
+    private static Kusto.Language.Diagnostic ToWarning(Kusto.Language.Diagnostic diagnostic)
+    {
+        if (diagnostic.Severity != DiagnosticSeverity.Error)
+            return diagnostic;
+
+        return new Kusto.Language.Diagnostic(
+                diagnostic.Code, diagnostic.Category, DiagnosticSeverity.Warning, diagnostic.Description, diagnostic.Message)
+            .WithLocation(diagnostic.Start, diagnostic.Length);
+    }
+
+    private static bool IsScopeCall(FunctionCallExpression call)
+    {
+        var name = call.Name.SimpleName;
+        return ScopeFunctionNames.Any(n => string.Equals(n, name, StringComparison.OrdinalIgnoreCase));
+    }
+
+    private static string? GetStringLiteral(Expression expression)
+    {
+        return expression is LiteralExpression literal && literal.Kind == SyntaxKind.StringLiteralExpression
+            ? literal.LiteralValue as string
+            : null;
+    }
+
+    private static Kusto.Language.Diagnostic MakeDiagnostic(
+        string code,
+        string message,
+        SyntaxElement location,
+        string severity = DiagnosticSeverity.Error)
+    {
+        return new Kusto.Language.Diagnostic(code, DiagnosticCategory.Correctness, severity, message, message)
+            .WithLocation(location.TextStart, location.End - location.TextStart);
+    }
+}
@@ -14,10 +14,45 @@ public static class NativeExports
     [ThreadStatic]
     private static string? _lastError;
 
+    // Thread-local storage for the size the last too-small output needed
+    [ThreadStatic]
+    private static int _lastRequiredSize;
+
+    // Thread-local storage for the last result that did not fit, read with kql_read_result_chunk
+    [ThreadStatic]
+    private static byte[]? _pendingResult;
+
+    // Thread-local serialization format of results, set with kql_set_result_format
+    [ThreadStatic]
+    private static int _resultFormat;
+
     // Error codes matching Rust FFI definitions
     private const int ErrorBufferTooSmall = -1;
     private const int ErrorParseError = -2;
     private const int ErrorInternal = -3;
+    private const int ErrorCancelled = -4;
+
+    // output_max_len asking for a natively allocated result
+    private const int AllocateResult = -1;
+
+    // Bumped when an existing export changes its signature or result shape
+    private const int AbiVersion = 1;
+
+    // Optional features reported by kql_get_capabilities
+    private static readonly string[] Capabilities =
+    {
+        "schema_registry",
+        "documents",
+        "cancellation",
+        "native_results",
+        "result_chunks",
+        "cbor",
+        "completion_ranking"
+    };
+
+    // Result formats matching Rust FFI definitions
+    private const int ResultFormatJson = 0;
+    private const int ResultFormatCbor = 1;
 
     /// <summary>
     /// Initialize the library. Should be called once before any other functions.
@@ -36,6 +71,7 @@ public static class NativeExports
         catch (Exception ex)
         {
             _lastError = $"Initialization failed: {ex}";
+            NativeLog.Write(NativeLog.Error, _lastError);
             return ErrorInternal;
         }
     }
@@ -50,6 +86,37 @@ public static class NativeExports
         // This is here for future use and symmetry with kql_init
     }
 
+    /// <summary>
+    /// Register the callback log messages are sent to, replacing any previous one.
+    /// </summary>
+    /// <returns>0 on success</returns>
+    [UnmanagedCallersOnly(EntryPoint = "kql_set_log_callback")]
+    public static unsafe int SetLogCallback(delegate* unmanaged<int, byte*, int, void> callback)
+    {
+        NativeLog.SetCallback(callback);
+        NativeLog.Write(NativeLog.Debug, "Log callback registered");
+        return 0;
+    }
+
+    /// <summary>
+    /// Get the ABI version of this library. Must match ABI_VERSION in the Rust crate.
+    /// </summary>
+    [UnmanagedCallersOnly(EntryPoint = "kql_get_abi_version")]
+    public static int GetAbiVersion()
+    {
+        return AbiVersion;
+    }
+
+    /// <summary>
+    /// Get the names of the optional features this library supports.
+    /// </summary>
+    /// <returns>Length of JSON array written, or -1 if buffer too small</returns>
+    [UnmanagedCallersOnly(EntryPoint = "kql_get_capabilities")]
+    public static unsafe int GetCapabilities(byte* outputPtr, int outputMaxLen)
+    {
+        return WriteJsonResult(Capabilities, outputPtr, outputMaxLen);
+    }
+
     /// <summary>
     /// Validate KQL query syntax (without schema awareness).
     /// </summary>
@@ -73,8 +140,7 @@ public static class NativeExports
         }
         catch (Exception ex)
         {
-            _lastError = $"ValidateSyntax failed: {ex}";
-            return ErrorInternal;
+            return WriteError(ErrorInternal, $"ValidateSyntax failed: {ex.Message}", ex, outputPtr, outputMaxLen);
         }
     }
 
@@ -100,8 +166,7 @@ public static class NativeExports
             var schema = JsonSerializer.Deserialize<SchemaDefinition>(schemaJson);
             if (schema == null)
             {
-                _lastError = "Failed to parse schema JSON";
-                return ErrorParseError;
+                return WriteError(ErrorParseError, "Failed to parse schema JSON", null, outputPtr, outputMaxLen);
             }
 
             // Validate with schema
@@ -112,13 +177,127 @@ public static class NativeExports
         }
         catch (JsonException ex)
         {
-            _lastError = $"Schema JSON parse error: {ex.Message}";
-            return ErrorParseError;
+            return WriteError(ErrorParseError, $"Schema JSON parse error: {ex.Message}", ex, outputPtr, outputMaxLen);
         }
         catch (Exception ex)
         {
-            _lastError = $"ValidateWithSchema failed: {ex}";
-            return ErrorInternal;
+            return WriteError(ErrorInternal, $"ValidateWithSchema failed: {ex.Message}", ex, outputPtr, outputMaxLen);
+        }
+    }
+
+    /// <summary>
+    /// Validate KQL query with analysis options (dialect) and optional schema.
+    /// </summary>
+    [UnmanagedCallersOnly(EntryPoint = "kql_validate_with_options")]
+    public static unsafe int ValidateWithOptions(
+        byte* queryPtr,
+        int queryLen,
+        byte* schemaPtr,
+        int schemaLen,
+        byte* optionsPtr,
+        int optionsLen,
+        byte* outputPtr,
+        int outputMaxLen)
+    {
+        try
+        {
+            // Convert input bytes to string
+            var query = Encoding.UTF8.GetString(queryPtr, queryLen);
+
+            // Parse schema if provided
+            SchemaDefinition? schema = null;
+            if (schemaPtr != null && schemaLen > 0)
+            {
+                var schemaJson = Encoding.UTF8.GetString(schemaPtr, schemaLen);
+                schema = JsonSerializer.Deserialize<SchemaDefinition>(schemaJson);
+            }
+
+            // Parse options
+            var optionsJson = Encoding.UTF8.GetString(optionsPtr, optionsLen);
+            var options = JsonSerializer.Deserialize<ValidationOptions>(optionsJson);
+            if (options == null)
+            {
+                return WriteError(ErrorParseError, "Failed to parse options JSON", null, outputPtr, outputMaxLen);
+            }
+
+            // Validate
+            var result = ValidationService.Validate(query, schema, options);
+
+            // Serialize result to JSON
+            return WriteJsonResult(result, outputPtr, outputMaxLen);
+        }
+        catch (OperationCanceledException)
+        {
+            return WriteError(ErrorCancelled, "Operation was cancelled", null, outputPtr, outputMaxLen);
+        }
+        catch (JsonException ex)
+        {
+            return WriteError(ErrorParseError, $"Schema/options JSON parse error: {ex.Message}", ex, outputPtr, outputMaxLen);
+        }
+        catch (Exception ex)
+        {
+            return WriteError(ErrorInternal, $"ValidateWithOptions failed: {ex.Message}", ex, outputPtr, outputMaxLen);
+        }
+    }
+
+    /// <summary>
+    /// Validate a batch of KQL queries with shared schema and options.
+    /// Input is a JSON array of query strings; output is a JSON array of results.
+    /// </summary>
+    [UnmanagedCallersOnly(EntryPoint = "kql_validate_batch")]
+    public static unsafe int ValidateBatch(
+        byte* queriesPtr,
+        int queriesLen,
+        byte* schemaPtr,
+        int schemaLen,
+        byte* optionsPtr,
+        int optionsLen,
+        byte* outputPtr,
+        int outputMaxLen)
+    {
+        try
+        {
+            // Parse queries
+            var queriesJson = Encoding.UTF8.GetString(queriesPtr, queriesLen);
+            var queries = JsonSerializer.Deserialize<List<string>>(queriesJson);
+            if (queries == null)
+            {
+                return WriteError(ErrorParseError, "Failed to parse queries JSON", null, outputPtr, outputMaxLen);
+            }
+
+            // Parse schema if provided
+            SchemaDefinition? schema = null;
+            if (schemaPtr != null && schemaLen > 0)
+            {
+                var schemaJson = Encoding.UTF8.GetString(schemaPtr, schemaLen);
+                schema = JsonSerializer.Deserialize<SchemaDefinition>(schemaJson);
+            }
+
+            // Parse options
+            var optionsJson = Encoding.UTF8.GetString(optionsPtr, optionsLen);
+            var options = JsonSerializer.Deserialize<ValidationOptions>(optionsJson);
+            if (options == null)
+            {
+                return WriteError(ErrorParseError, "Failed to parse options JSON", null, outputPtr, outputMaxLen);
+            }
+
+            // Validate
+            var results = ValidationService.ValidateBatch(queries, schema, options);
+
+            // Serialize results to JSON
+            return WriteJsonResult(results, outputPtr, outputMaxLen);
+        }
+        catch (OperationCanceledException)
+        {
+            return WriteError(ErrorCancelled, "Operation was cancelled", null, outputPtr, outputMaxLen);
+        }
+        catch (JsonException ex)
+        {
+            return WriteError(ErrorParseError, $"Queries/schema/options JSON parse error: {ex.Message}", ex, outputPtr, outputMaxLen);
+        }
+        catch (Exception ex)
+        {
+            return WriteError(ErrorInternal, $"ValidateBatch failed: {ex.Message}", ex, outputPtr, outputMaxLen);
         }
     }
 
@@ -145,21 +324,87 @@ public static class NativeExports
         }
         catch (Exception ex)
         {
-            _lastError = $"GetClassifications failed: {ex}";
+            return WriteError(ErrorInternal, $"GetClassifications failed: {ex.Message}", ex, outputPtr, outputMaxLen);
+        }
+    }
+
+    /// <summary>
+    /// Get syntax classifications for the tokens starting within a range.
+    /// </summary>
+    [UnmanagedCallersOnly(EntryPoint = "kql_get_classifications_in_range")]
+    public static unsafe int GetClassificationsInRange(
+        byte* queryPtr,
+        int queryLen,
+        int rangeStart,
+        int rangeEnd,
+        byte* outputPtr,
+        int outputMaxLen)
+    {
+        try
+        {
+            // Convert input bytes to string
+            var query = Encoding.UTF8.GetString(queryPtr, queryLen);
+
+            // Get classifications
+            var result = ClassificationService.GetClassificationsInRange(query, rangeStart, rangeEnd);
+
+            // Serialize result to JSON
+            return WriteJsonResult(result, outputPtr, outputMaxLen);
+        }
+        catch (Exception ex)
+        {
+            return WriteError(ErrorInternal, $"GetClassificationsInRange failed: {ex.Message}", ex, outputPtr, outputMaxLen);
+        }
+    }
+
+    /// <summary>
+    /// Classify a query, passing the spans to a callback in JSON batches.
+    /// A non-zero return from the callback stops classification.
+    /// </summary>
+    /// <returns>0 on success (or when stopped), negative error code on failure</returns>
+    [UnmanagedCallersOnly(EntryPoint = "kql_classify_chunks")]
+    public static unsafe int ClassifyChunks(
+        byte* queryPtr,
+        int queryLen,
+        int batchSpans,
+        delegate* unmanaged<void*, byte*, int, int> callback,
+        void* context)
+    {
+        try
+        {
+            var query = Encoding.UTF8.GetString(queryPtr, queryLen);
+
+            foreach (var batch in ClassificationService.GetClassificationBatches(query, Math.Max(batchSpans, 1)))
+            {
+                var bytes = JsonSerializer.SerializeToUtf8Bytes(batch, JsonOptions.Default);
+                fixed (byte* batchPtr = bytes)
+                {
+                    if (callback(context, batchPtr, bytes.Length) != 0)
+                        return 0;
+                }
+            }
+
+            return 0;
+        }
+        catch (Exception ex)
+        {
+            _lastError = $"ClassifyChunks failed: {ex}";
+            NativeLog.Write(NativeLog.Error, _lastError);
             return ErrorInternal;
         }
     }
 
     /// <summary>
-    /// Get completion items at cursor position.
+    /// Get schema-aware syntax classifications.
     /// </summary>
-    [UnmanagedCallersOnly(EntryPoint = "kql_get_completions")]
-    public static unsafe int GetCompletions(
+    [UnmanagedCallersOnly(EntryPoint = "kql_get_semantic_classifications")]
+    public static unsafe int GetSemanticClassifications(
         byte* queryPtr,
         int queryLen,
-        int cursorPosition,
         byte* schemaPtr,
         int schemaLen,
+        byte* optionsPtr,
+        int optionsLen,
         byte* outputPtr,
         int outputMaxLen)
     {
@@ -168,81 +413,1242 @@ public static class NativeExports
             // Convert input bytes to string
             var query = Encoding.UTF8.GetString(queryPtr, queryLen);
 
-            // Parse schema if provided
-            SchemaDefinition? schema = null;
-            if (schemaPtr != null && schemaLen > 0)
+            // Parse schema and options
+            var schemaJson = Encoding.UTF8.GetString(schemaPtr, schemaLen);
+            var schema = JsonSerializer.Deserialize<SchemaDefinition>(schemaJson);
+            var optionsJson = Encoding.UTF8.GetString(optionsPtr, optionsLen);
+            var options = JsonSerializer.Deserialize<ValidationOptions>(optionsJson);
+            if (schema == null || options == null)
             {
-                var schemaJson = Encoding.UTF8.GetString(schemaPtr, schemaLen);
-                schema = JsonSerializer.Deserialize<SchemaDefinition>(schemaJson);
+                return WriteError(ErrorParseError, "Failed to parse schema/options JSON", null, outputPtr, outputMaxLen);
             }
 
-            // Get completions
-            var result = CompletionService.GetCompletions(query, cursorPosition, schema);
+            // Get classifications
+            var result = ClassificationService.GetSemanticClassifications(query, schema, options);
 
             // Serialize result to JSON
             return WriteJsonResult(result, outputPtr, outputMaxLen);
         }
         catch (JsonException ex)
         {
-            _lastError = $"Schema JSON parse error: {ex.Message}";
-            return ErrorParseError;
+            return WriteError(ErrorParseError, $"Schema/options JSON parse error: {ex.Message}", ex, outputPtr, outputMaxLen);
         }
         catch (Exception ex)
         {
-            _lastError = $"GetCompletions failed: {ex}";
-            return ErrorInternal;
+            return WriteError(ErrorInternal, $"GetSemanticClassifications failed: {ex.Message}", ex, outputPtr, outputMaxLen);
         }
     }
 
     /// <summary>
-    /// Get the last error message.
+    /// Run the Kusto.Language best-practice analyzers.
     /// </summary>
-    [UnmanagedCallersOnly(EntryPoint = "kql_get_last_error")]
-    public static unsafe int GetLastError(byte* outputPtr, int outputMaxLen)
+    [UnmanagedCallersOnly(EntryPoint = "kql_get_analyzer_diagnostics")]
+    public static unsafe int GetAnalyzerDiagnostics(
+        byte* queryPtr,
+        int queryLen,
+        byte* schemaPtr,
+        int schemaLen,
+        byte* optionsPtr,
+        int optionsLen,
+        byte* outputPtr,
+        int outputMaxLen)
     {
-        if (string.IsNullOrEmpty(_lastError))
+        try
         {
-            return 0;
-        }
+            // Convert input bytes to string
+            var query = Encoding.UTF8.GetString(queryPtr, queryLen);
 
-        var bytes = Encoding.UTF8.GetBytes(_lastError);
-        if (bytes.Length > outputMaxLen)
+            // Parse schema and options
+            var schemaJson = Encoding.UTF8.GetString(schemaPtr, schemaLen);
+            var schema = JsonSerializer.Deserialize<SchemaDefinition>(schemaJson);
+            var optionsJson = Encoding.UTF8.GetString(optionsPtr, optionsLen);
+            var options = JsonSerializer.Deserialize<ValidationOptions>(optionsJson);
+            if (schema == null || options == null)
+            {
+                return WriteError(ErrorParseError, "Failed to parse schema/options JSON", null, outputPtr, outputMaxLen);
+            }
+
+            // Run analyzers
+            var result = AnalyzerService.GetAnalyzerDiagnostics(query, schema, options);
+
+            // Serialize result to JSON
+            return WriteJsonResult(result, outputPtr, outputMaxLen);
+        }
+        catch (JsonException ex)
         {
-            return ErrorBufferTooSmall;
+            return WriteError(ErrorParseError, $"Schema/options JSON parse error: {ex.Message}", ex, outputPtr, outputMaxLen);
         }
-
-        fixed (byte* src = bytes)
+        catch (Exception ex)
         {
-            Buffer.MemoryCopy(src, outputPtr, outputMaxLen, bytes.Length);
+            return WriteError(ErrorInternal, $"GetAnalyzerDiagnostics failed: {ex.Message}", ex, outputPtr, outputMaxLen);
         }
-
-        // Clear the error after retrieval
-        var length = bytes.Length;
-        _lastError = null;
-        return length;
     }
 
     /// <summary>
-    /// Write a result object as JSON to the output buffer.
+    /// Get the type of the expression at a position.
+    /// Writes "null" when there is no typed expression at the position.
     /// </summary>
-    private static unsafe int WriteJsonResult<T>(T result, byte* outputPtr, int outputMaxLen)
+    [UnmanagedCallersOnly(EntryPoint = "kql_get_expression_type")]
+    public static unsafe int GetExpressionType(
+        byte* queryPtr,
+        int queryLen,
+        int position,
+        byte* schemaPtr,
+        int schemaLen,
+        byte* optionsPtr,
+        int optionsLen,
+        byte* outputPtr,
+        int outputMaxLen)
     {
-        var json = JsonSerializer.Serialize(result, JsonOptions.Default);
-        var bytes = Encoding.UTF8.GetBytes(json);
+        try
+        {
+            // Convert input bytes to string
+            var query = Encoding.UTF8.GetString(queryPtr, queryLen);
 
-        if (bytes.Length > outputMaxLen)
+            // Parse schema and options
+            var schemaJson = Encoding.UTF8.GetString(schemaPtr, schemaLen);
+            var schema = JsonSerializer.Deserialize<SchemaDefinition>(schemaJson);
+            var optionsJson = Encoding.UTF8.GetString(optionsPtr, optionsLen);
+            var options = JsonSerializer.Deserialize<ValidationOptions>(optionsJson);
+            if (schema == null || options == null)
+            {
+                return WriteError(ErrorParseError, "Failed to parse schema/options JSON", null, outputPtr, outputMaxLen);
+            }
+
+            // Infer expression type
+            var result = ExpressionTypeService.GetExpressionType(query, position, schema, options);
+
+            // Serialize result to JSON
+            return WriteJsonResult(result, outputPtr, outputMaxLen);
+        }
+        catch (JsonException ex)
         {
-            _lastError = $"Output buffer too small: needed {bytes.Length}, got {outputMaxLen}";
-            return ErrorBufferTooSmall;
+            return WriteError(ErrorParseError, $"Schema/options JSON parse error: {ex.Message}", ex, outputPtr, outputMaxLen);
         }
-
-        fixed (byte* src = bytes)
+        catch (Exception ex)
         {
-            Buffer.MemoryCopy(src, outputPtr, outputMaxLen, bytes.Length);
+            return WriteError(ErrorInternal, $"GetExpressionType failed: {ex.Message}", ex, outputPtr, outputMaxLen);
         }
-
-        return bytes.Length;
     }
+
+    /// <summary>
+    /// Fill in the documentation and signature of a completion item.
+    /// Writes the item back with "documentation" and "detail" set.
+    /// </summary>
+    [UnmanagedCallersOnly(EntryPoint = "kql_resolve_completion")]
+    public static unsafe int ResolveCompletion(
+        byte* itemPtr,
+        int itemLen,
+        byte* schemaPtr,
+        int schemaLen,
+        byte* optionsPtr,
+        int optionsLen,
+        byte* outputPtr,
+        int outputMaxLen)
+    {
+        try
+        {
+            // Parse the item
+            var itemJson = Encoding.UTF8.GetString(itemPtr, itemLen);
+            var item = JsonSerializer.Deserialize<CompletionItemResponse>(itemJson);
+
+            // Parse schema if provided, and options
+            var schema = new SchemaDefinition();
+            if (schemaPtr != null && schemaLen > 0)
+            {
+                var schemaJson = Encoding.UTF8.GetString(schemaPtr, schemaLen);
+                schema = JsonSerializer.Deserialize<SchemaDefinition>(schemaJson);
+            }
+            var optionsJson = Encoding.UTF8.GetString(optionsPtr, optionsLen);
+            var options = JsonSerializer.Deserialize<ValidationOptions>(optionsJson);
+            if (item == null || schema == null || options == null)
+            {
+                return WriteError(ErrorParseError, "Failed to parse item/schema/options JSON", null, outputPtr, outputMaxLen);
+            }
+
+            // Look up the item's symbol
+            var globals = ValidationService.BuildGlobalState(schema, options);
+            var result = CompletionService.Resolve(item, globals);
+
+            // Serialize result to JSON
+            return WriteJsonResult(result, outputPtr, outputMaxLen);
+        }
+        catch (JsonException ex)
+        {
+            return WriteError(ErrorParseError, $"Item/schema/options JSON parse error: {ex.Message}", ex, outputPtr, outputMaxLen);
+        }
+        catch (Exception ex)
+        {
+            return WriteError(ErrorInternal, $"ResolveCompletion failed: {ex.Message}", ex, outputPtr, outputMaxLen);
+        }
+    }
+
+    /// <summary>
+    /// Get the declaration of the local name at a cursor position.
+    /// Writes "null" when the cursor is not on a locally declared name.
+    /// </summary>
+    [UnmanagedCallersOnly(EntryPoint = "kql_get_definition")]
+    public static unsafe int GetDefinition(
+        byte* queryPtr,
+        int queryLen,
+        int cursorPosition,
+        byte* outputPtr,
+        int outputMaxLen)
+    {
+        try
+        {
+            // Convert input bytes to string
+            var query = Encoding.UTF8.GetString(queryPtr, queryLen);
+
+            // Find definition
+            var result = NavigationService.GetDefinition(query, cursorPosition);
+
+            // Serialize result to JSON
+            return WriteJsonResult(result, outputPtr, outputMaxLen);
+        }
+        catch (Exception ex)
+        {
+            return WriteError(ErrorInternal, $"GetDefinition failed: {ex.Message}", ex, outputPtr, outputMaxLen);
+        }
+    }
+
+    /// <summary>
+    /// Get document structure (statements, declarations, foldable regions).
+    /// </summary>
+    [UnmanagedCallersOnly(EntryPoint = "kql_get_document_structure")]
+    public static unsafe int GetDocumentStructure(
+        byte* queryPtr,
+        int queryLen,
+        byte* outputPtr,
+        int outputMaxLen)
+    {
+        try
+        {
+            // Convert input bytes to string
+            var query = Encoding.UTF8.GetString(queryPtr, queryLen);
+
+            // Get structure
+            var result = StructureService.GetStructure(query);
+
+            // Serialize result to JSON
+            return WriteJsonResult(result, outputPtr, outputMaxLen);
+        }
+        catch (Exception ex)
+        {
+            return WriteError(ErrorInternal, $"GetDocumentStructure failed: {ex.Message}", ex, outputPtr, outputMaxLen);
+        }
+    }
+
+    /// <summary>
+    /// Get the document outline (let bindings, functions, pipeline stages).
+    /// </summary>
+    [UnmanagedCallersOnly(EntryPoint = "kql_get_document_symbols")]
+    public static unsafe int GetDocumentSymbols(
+        byte* queryPtr,
+        int queryLen,
+        byte* outputPtr,
+        int outputMaxLen)
+    {
+        try
+        {
+            // Convert input bytes to string
+            var query = Encoding.UTF8.GetString(queryPtr, queryLen);
+
+            // Get outline
+            var result = OutlineService.GetDocumentSymbols(query);
+
+            // Serialize result to JSON
+            return WriteJsonResult(result, outputPtr, outputMaxLen);
+        }
+        catch (Exception ex)
+        {
+            return WriteError(ErrorInternal, $"GetDocumentSymbols failed: {ex.Message}", ex, outputPtr, outputMaxLen);
+        }
+    }
+
+    /// <summary>
+    /// Get the inlay hints of a query within a range.
+    /// </summary>
+    [UnmanagedCallersOnly(EntryPoint = "kql_get_inlay_hints")]
+    public static unsafe int GetInlayHints(
+        byte* queryPtr,
+        int queryLen,
+        int rangeStart,
+        int rangeEnd,
+        byte* schemaPtr,
+        int schemaLen,
+        byte* optionsPtr,
+        int optionsLen,
+        byte* outputPtr,
+        int outputMaxLen)
+    {
+        try
+        {
+            // Convert input bytes to string
+            var query = Encoding.UTF8.GetString(queryPtr, queryLen);
+
+            // Parse schema and options
+            var schemaJson = Encoding.UTF8.GetString(schemaPtr, schemaLen);
+            var schema = JsonSerializer.Deserialize<SchemaDefinition>(schemaJson);
+            var optionsJson = Encoding.UTF8.GetString(optionsPtr, optionsLen);
+            var options = JsonSerializer.Deserialize<ValidationOptions>(optionsJson);
+            if (schema == null || options == null)
+            {
+                return WriteError(ErrorParseError, "Failed to parse schema/options JSON", null, outputPtr, outputMaxLen);
+            }
+
+            // Collect hints
+            var result = InlayHintService.GetInlayHints(query, rangeStart, rangeEnd, schema, options);
+
+            // Serialize result to JSON
+            return WriteJsonResult(result, outputPtr, outputMaxLen);
+        }
+        catch (JsonException ex)
+        {
+            return WriteError(ErrorParseError, $"Schema/options JSON parse error: {ex.Message}", ex, outputPtr, outputMaxLen);
+        }
+        catch (Exception ex)
+        {
+            return WriteError(ErrorInternal, $"GetInlayHints failed: {ex.Message}", ex, outputPtr, outputMaxLen);
+        }
+    }
+
+    /// <summary>
+    /// Split a query's final pipeline into its stages.
+    /// </summary>
+    [UnmanagedCallersOnly(EntryPoint = "kql_analyze_pipeline")]
+    public static unsafe int AnalyzePipeline(
+        byte* queryPtr,
+        int queryLen,
+        byte* schemaPtr,
+        int schemaLen,
+        byte* optionsPtr,
+        int optionsLen,
+        byte* outputPtr,
+        int outputMaxLen)
+    {
+        try
+        {
+            // Convert input bytes to string
+            var query = Encoding.UTF8.GetString(queryPtr, queryLen);
+
+            // Parse schema and options
+            var schemaJson = Encoding.UTF8.GetString(schemaPtr, schemaLen);
+            var schema = JsonSerializer.Deserialize<SchemaDefinition>(schemaJson);
+            var optionsJson = Encoding.UTF8.GetString(optionsPtr, optionsLen);
+            var options = JsonSerializer.Deserialize<ValidationOptions>(optionsJson);
+            if (schema == null || options == null)
+            {
+                return WriteError(ErrorParseError, "Failed to parse schema/options JSON", null, outputPtr, outputMaxLen);
+            }
+
+            // Split the pipeline
+            var result = PipelineService.AnalyzePipeline(query, schema, options);
+
+            // Serialize result to JSON
+            return WriteJsonResult(result, outputPtr, outputMaxLen);
+        }
+        catch (JsonException ex)
+        {
+            return WriteError(ErrorParseError, $"Schema/options JSON parse error: {ex.Message}", ex, outputPtr, outputMaxLen);
+        }
+        catch (Exception ex)
+        {
+            return WriteError(ErrorInternal, $"AnalyzePipeline failed: {ex.Message}", ex, outputPtr, outputMaxLen);
+        }
+    }
+
+    /// <summary>
+    /// List a query's joins, lookups and unions.
+    /// </summary>
+    [UnmanagedCallersOnly(EntryPoint = "kql_analyze_joins")]
+    public static unsafe int AnalyzeJoins(
+        byte* queryPtr,
+        int queryLen,
+        byte* schemaPtr,
+        int schemaLen,
+        byte* optionsPtr,
+        int optionsLen,
+        byte* outputPtr,
+        int outputMaxLen)
+    {
+        try
+        {
+            // Convert input bytes to string
+            var query = Encoding.UTF8.GetString(queryPtr, queryLen);
+
+            // Parse schema and options
+            var schemaJson = Encoding.UTF8.GetString(schemaPtr, schemaLen);
+            var schema = JsonSerializer.Deserialize<SchemaDefinition>(schemaJson);
+            var optionsJson = Encoding.UTF8.GetString(optionsPtr, optionsLen);
+            var options = JsonSerializer.Deserialize<ValidationOptions>(optionsJson);
+            if (schema == null || options == null)
+            {
+                return WriteError(ErrorParseError, "Failed to parse schema/options JSON", null, outputPtr, outputMaxLen);
+            }
+
+            // Find the joins, lookups and unions
+            var result = JoinAnalysisService.AnalyzeJoins(query, schema, options);
+
+            // Serialize result to JSON
+            return WriteJsonResult(result, outputPtr, outputMaxLen);
+        }
+        catch (JsonException ex)
+        {
+            return WriteError(ErrorParseError, $"Schema/options JSON parse error: {ex.Message}", ex, outputPtr, outputMaxLen);
+        }
+        catch (Exception ex)
+        {
+            return WriteError(ErrorInternal, $"AnalyzeJoins failed: {ex.Message}", ex, outputPtr, outputMaxLen);
+        }
+    }
+
+    /// <summary>
+    /// Get the matching bracket pairs of a query.
+    /// </summary>
+    [UnmanagedCallersOnly(EntryPoint = "kql_get_bracket_pairs")]
+    public static unsafe int GetBracketPairs(
+        byte* queryPtr,
+        int queryLen,
+        byte* outputPtr,
+        int outputMaxLen)
+    {
+        try
+        {
+            // Convert input bytes to string
+            var query = Encoding.UTF8.GetString(queryPtr, queryLen);
+
+            // Match brackets
+            var result = SelectionService.GetBracketPairs(query);
+
+            // Serialize result to JSON
+            return WriteJsonResult(result, outputPtr, outputMaxLen);
+        }
+        catch (Exception ex)
+        {
+            return WriteError(ErrorInternal, $"GetBracketPairs failed: {ex.Message}", ex, outputPtr, outputMaxLen);
+        }
+    }
+
+    /// <summary>
+    /// Get the syntax ranges enclosing a position, innermost first.
+    /// </summary>
+    [UnmanagedCallersOnly(EntryPoint = "kql_get_enclosing_ranges")]
+    public static unsafe int GetEnclosingRanges(
+        byte* queryPtr,
+        int queryLen,
+        int position,
+        byte* outputPtr,
+        int outputMaxLen)
+    {
+        try
+        {
+            // Convert input bytes to string
+            var query = Encoding.UTF8.GetString(queryPtr, queryLen);
+
+            // Walk the syntax tree from the token at the position
+            var result = SelectionService.GetEnclosingRanges(query, position);
+
+            // Serialize result to JSON
+            return WriteJsonResult(result, outputPtr, outputMaxLen);
+        }
+        catch (Exception ex)
+        {
+            return WriteError(ErrorInternal, $"GetEnclosingRanges failed: {ex.Message}", ex, outputPtr, outputMaxLen);
+        }
+    }
+
+    /// <summary>
+    /// Get the syntax tree of a query, as nodes in pre-order.
+    /// </summary>
+    [UnmanagedCallersOnly(EntryPoint = "kql_get_syntax_tree")]
+    public static unsafe int GetSyntaxTree(
+        byte* queryPtr,
+        int queryLen,
+        byte* outputPtr,
+        int outputMaxLen)
+    {
+        try
+        {
+            // Convert input bytes to string
+            var query = Encoding.UTF8.GetString(queryPtr, queryLen);
+
+            // List the parse tree
+            var result = SyntaxTreeService.GetSyntaxTree(query);
+
+            // Serialize result to JSON
+            return WriteJsonResult(result, outputPtr, outputMaxLen);
+        }
+        catch (Exception ex)
+        {
+            return WriteError(ErrorInternal, $"GetSyntaxTree failed: {ex.Message}", ex, outputPtr, outputMaxLen);
+        }
+    }
+
+    /// <summary>
+    /// Format a query. The output is the formatted query as a JSON string.
+    /// </summary>
+    [UnmanagedCallersOnly(EntryPoint = "kql_format")]
+    public static unsafe int Format(
+        byte* queryPtr,
+        int queryLen,
+        byte* optionsPtr,
+        int optionsLen,
+        byte* outputPtr,
+        int outputMaxLen)
+    {
+        try
+        {
+            // Convert input bytes to string
+            var query = Encoding.UTF8.GetString(queryPtr, queryLen);
+
+            // Parse options
+            var optionsJson = Encoding.UTF8.GetString(optionsPtr, optionsLen);
+            var options = JsonSerializer.Deserialize<FormatOptions>(optionsJson);
+            if (options == null)
+            {
+                return WriteError(ErrorParseError, "Failed to parse options JSON", null, outputPtr, outputMaxLen);
+            }
+
+            // Format
+            var result = FormatService.Format(query, options);
+
+            // Serialize result to JSON
+            return WriteJsonResult(result, outputPtr, outputMaxLen);
+        }
+        catch (JsonException ex)
+        {
+            return WriteError(ErrorParseError, $"Options JSON parse error: {ex.Message}", ex, outputPtr, outputMaxLen);
+        }
+        catch (Exception ex)
+        {
+            return WriteError(ErrorInternal, $"Format failed: {ex.Message}", ex, outputPtr, outputMaxLen);
+        }
+    }
+
+    /// <summary>
+    /// Infer the result schema (output columns and types) of a query.
+    /// </summary>
+    [UnmanagedCallersOnly(EntryPoint = "kql_infer_result_schema")]
+    public static unsafe int InferResultSchema(
+        byte* queryPtr,
+        int queryLen,
+        byte* schemaPtr,
+        int schemaLen,
+        byte* optionsPtr,
+        int optionsLen,
+        byte* outputPtr,
+        int outputMaxLen)
+    {
+        try
+        {
+            // Convert input bytes to string
+            var query = Encoding.UTF8.GetString(queryPtr, queryLen);
+
+            // Parse schema and options
+            var schemaJson = Encoding.UTF8.GetString(schemaPtr, schemaLen);
+            var schema = JsonSerializer.Deserialize<SchemaDefinition>(schemaJson);
+            var optionsJson = Encoding.UTF8.GetString(optionsPtr, optionsLen);
+            var options = JsonSerializer.Deserialize<ValidationOptions>(optionsJson);
+            if (schema == null || options == null)
+            {
+                return WriteError(ErrorParseError, "Failed to parse schema/options JSON", null, outputPtr, outputMaxLen);
+            }
+
+            // Infer result schema
+            var result = ResultSchemaService.InferResultSchema(query, schema, options);
+            if (result == null)
+            {
+                return WriteError(ErrorParseError, "Query does not produce a tabular result", null, outputPtr, outputMaxLen);
+            }
+
+            // Serialize result to JSON
+            return WriteJsonResult(result, outputPtr, outputMaxLen);
+        }
+        catch (JsonException ex)
+        {
+            return WriteError(ErrorParseError, $"Schema/options JSON parse error: {ex.Message}", ex, outputPtr, outputMaxLen);
+        }
+        catch (Exception ex)
+        {
+            return WriteError(ErrorInternal, $"InferResultSchema failed: {ex.Message}", ex, outputPtr, outputMaxLen);
+        }
+    }
+
+    /// <summary>
+    /// Analyze the tables, columns, functions, clusters, and databases a query references.
+    /// </summary>
+    [UnmanagedCallersOnly(EntryPoint = "kql_analyze")]
+    public static unsafe int Analyze(
+        byte* queryPtr,
+        int queryLen,
+        byte* schemaPtr,
+        int schemaLen,
+        byte* optionsPtr,
+        int optionsLen,
+        byte* outputPtr,
+        int outputMaxLen)
+    {
+        try
+        {
+            // Convert input bytes to string
+            var query = Encoding.UTF8.GetString(queryPtr, queryLen);
+
+            // Parse schema and options
+            var schemaJson = Encoding.UTF8.GetString(schemaPtr, schemaLen);
+            var schema = JsonSerializer.Deserialize<SchemaDefinition>(schemaJson);
+            var optionsJson = Encoding.UTF8.GetString(optionsPtr, optionsLen);
+            var options = JsonSerializer.Deserialize<ValidationOptions>(optionsJson);
+            if (schema == null || options == null)
+            {
+                return WriteError(ErrorParseError, "Failed to parse schema/options JSON", null, outputPtr, outputMaxLen);
+            }
+
+            // Analyze
+            var result = AnalysisService.Analyze(query, schema, options);
+
+            // Serialize result to JSON
+            return WriteJsonResult(result, outputPtr, outputMaxLen);
+        }
+        catch (JsonException ex)
+        {
+            return WriteError(ErrorParseError, $"Schema/options JSON parse error: {ex.Message}", ex, outputPtr, outputMaxLen);
+        }
+        catch (Exception ex)
+        {
+            return WriteError(ErrorInternal, $"Analyze failed: {ex.Message}", ex, outputPtr, outputMaxLen);
+        }
+    }
+
+    /// <summary>
+    /// Register a schema for use by the *_with_schema_id functions.
+    /// </summary>
+    /// <returns>A positive schema id on success, negative error code on failure</returns>
+    [UnmanagedCallersOnly(EntryPoint = "kql_register_schema")]
+    public static unsafe int RegisterSchema(byte* schemaPtr, int schemaLen)
+    {
+        try
+        {
+            var schemaJson = Encoding.UTF8.GetString(schemaPtr, schemaLen);
+            var schema = JsonSerializer.Deserialize<SchemaDefinition>(schemaJson);
+            if (schema == null)
+            {
+                _lastError = "Failed to parse schema JSON";
+                return ErrorParseError;
+            }
+
+            return SchemaRegistry.Register(schema);
+        }
+        catch (JsonException ex)
+        {
+            _lastError = $"Schema JSON parse error: {ex.Message}";
+            NativeLog.Write(NativeLog.Warn, _lastError);
+            return ErrorParseError;
+        }
+        catch (Exception ex)
+        {
+            _lastError = $"RegisterSchema failed: {ex}";
+            NativeLog.Write(NativeLog.Error, _lastError);
+            return ErrorInternal;
+        }
+    }
+
+    /// <summary>
+    /// Release a schema registered with kql_register_schema.
+    /// </summary>
+    /// <returns>0 on success, -2 if the id is not registered</returns>
+    [UnmanagedCallersOnly(EntryPoint = "kql_release_schema")]
+    public static int ReleaseSchema(int schemaId)
+    {
+        if (SchemaRegistry.Release(schemaId))
+            return 0;
+
+        _lastError = $"Unknown schema id: {schemaId}";
+        return ErrorParseError;
+    }
+
+    /// <summary>
+    /// Validate a query against a registered schema.
+    /// </summary>
+    [UnmanagedCallersOnly(EntryPoint = "kql_validate_with_schema_id")]
+    public static unsafe int ValidateWithSchemaId(
+        byte* queryPtr,
+        int queryLen,
+        int schemaId,
+        byte* optionsPtr,
+        int optionsLen,
+        byte* outputPtr,
+        int outputMaxLen)
+    {
+        try
+        {
+            // Convert input bytes to string
+            var query = Encoding.UTF8.GetString(queryPtr, queryLen);
+
+            if (!SchemaRegistry.TryGet(schemaId, out var schema))
+            {
+                return WriteError(ErrorParseError, $"Unknown schema id: {schemaId}", null, outputPtr, outputMaxLen);
+            }
+
+            // Parse options
+            var optionsJson = Encoding.UTF8.GetString(optionsPtr, optionsLen);
+            var options = JsonSerializer.Deserialize<ValidationOptions>(optionsJson);
+            if (options == null)
+            {
+                return WriteError(ErrorParseError, "Failed to parse options JSON", null, outputPtr, outputMaxLen);
+            }
+
+            // Validate
+            var result = ValidationService.Validate(query, schema, options);
+
+            // Serialize result to JSON
+            return WriteJsonResult(result, outputPtr, outputMaxLen);
+        }
+        catch (OperationCanceledException)
+        {
+            return WriteError(ErrorCancelled, "Operation was cancelled", null, outputPtr, outputMaxLen);
+        }
+        catch (JsonException ex)
+        {
+            return WriteError(ErrorParseError, $"Options JSON parse error: {ex.Message}", ex, outputPtr, outputMaxLen);
+        }
+        catch (Exception ex)
+        {
+            return WriteError(ErrorInternal, $"ValidateWithSchemaId failed: {ex.Message}", ex, outputPtr, outputMaxLen);
+        }
+    }
+
+    /// <summary>
+    /// Get completion items at cursor position using a registered schema.
+    /// </summary>
+    [UnmanagedCallersOnly(EntryPoint = "kql_get_completions_with_schema_id")]
+    public static unsafe int GetCompletionsWithSchemaId(
+        byte* queryPtr,
+        int queryLen,
+        int cursorPosition,
+        int schemaId,
+        byte* outputPtr,
+        int outputMaxLen)
+    {
+        try
+        {
+            // Convert input bytes to string
+            var query = Encoding.UTF8.GetString(queryPtr, queryLen);
+
+            if (!SchemaRegistry.TryGet(schemaId, out var schema))
+            {
+                return WriteError(ErrorParseError, $"Unknown schema id: {schemaId}", null, outputPtr, outputMaxLen);
+            }
+
+            // Get completions
+            var globals = schema.GetGlobals(new ValidationOptions());
+            var result = CompletionService.GetCompletions(query, cursorPosition, globals);
+
+            // Serialize result to JSON
+            return WriteJsonResult(result, outputPtr, outputMaxLen);
+        }
+        catch (Exception ex)
+        {
+            return WriteError(ErrorInternal, $"GetCompletionsWithSchemaId failed: {ex.Message}", ex, outputPtr, outputMaxLen);
+        }
+    }
+
+    /// <summary>
+    /// Create a cancellation source. Pass its id as the "cancellation_id" option.
+    /// </summary>
+    /// <returns>A positive cancellation id, or an error code</returns>
+    [UnmanagedCallersOnly(EntryPoint = "kql_cancellation_create")]
+    public static int CancellationCreate()
+    {
+        try
+        {
+            return CancellationRegistry.Create();
+        }
+        catch (Exception ex)
+        {
+            _lastError = $"CancellationCreate failed: {ex}";
+            return ErrorInternal;
+        }
+    }
+
+    /// <summary>
+    /// Cancel the calls observing a cancellation source. Safe to call from any thread.
+    /// </summary>
+    /// <returns>0 on success, -2 if the id is not registered</returns>
+    [UnmanagedCallersOnly(EntryPoint = "kql_cancellation_cancel")]
+    public static int CancellationCancel(int cancellationId)
+    {
+        if (CancellationRegistry.Cancel(cancellationId))
+            return 0;
+
+        _lastError = $"Unknown cancellation id: {cancellationId}";
+        return ErrorParseError;
+    }
+
+    /// <summary>
+    /// Release a cancellation source created with kql_cancellation_create.
+    /// </summary>
+    /// <returns>0 on success, -2 if the id is not registered</returns>
+    [UnmanagedCallersOnly(EntryPoint = "kql_cancellation_release")]
+    public static int CancellationRelease(int cancellationId)
+    {
+        if (CancellationRegistry.Release(cancellationId))
+            return 0;
+
+        _lastError = $"Unknown cancellation id: {cancellationId}";
+        return ErrorParseError;
+    }
+
+    /// <summary>
+    /// Open a document whose parse state is kept between edits.
+    /// A schema id of 0 opens the document without a schema (syntax checks only).
+    /// </summary>
+    /// <returns>A positive document id, or an error code</returns>
+    [UnmanagedCallersOnly(EntryPoint = "kql_document_open")]
+    public static unsafe int DocumentOpen(
+        byte* textPtr,
+        int textLen,
+        int schemaId,
+        byte* optionsPtr,
+        int optionsLen)
+    {
+        try
+        {
+            // Convert input bytes to string
+            var text = Encoding.UTF8.GetString(textPtr, textLen);
+
+            RegisteredSchema? schema = null;
+            if (schemaId != 0 && !SchemaRegistry.TryGet(schemaId, out schema))
+            {
+                _lastError = $"Unknown schema id: {schemaId}";
+                return ErrorParseError;
+            }
+
+            // Parse options
+            var optionsJson = Encoding.UTF8.GetString(optionsPtr, optionsLen);
+            var options = JsonSerializer.Deserialize<ValidationOptions>(optionsJson);
+            if (options == null)
+            {
+                _lastError = "Failed to parse options JSON";
+                return ErrorParseError;
+            }
+
+            return DocumentRegistry.Open(new KqlDocument(text, schema, options));
+        }
+        catch (JsonException ex)
+        {
+            _lastError = $"Options JSON parse error: {ex.Message}";
+            return ErrorParseError;
+        }
+        catch (Exception ex)
+        {
+            _lastError = $"DocumentOpen failed: {ex}";
+            return ErrorInternal;
+        }
+    }
+
+    /// <summary>
+    /// Replace the characters in [start, end) of an open document.
+    /// </summary>
+    /// <returns>0 on success, -2 for an unknown id or a range outside the document</returns>
+    [UnmanagedCallersOnly(EntryPoint = "kql_document_edit")]
+    public static unsafe int DocumentEdit(
+        int documentId,
+        int start,
+        int end,
+        byte* textPtr,
+        int textLen)
+    {
+        try
+        {
+            if (!DocumentRegistry.TryGet(documentId, out var document))
+            {
+                _lastError = $"Unknown document id: {documentId}";
+                return ErrorParseError;
+            }
+
+            var text = Encoding.UTF8.GetString(textPtr, textLen);
+            if (!document.Edit(start, end, text))
+            {
+                _lastError = $"Edit range {start}..{end} is outside document {documentId}";
+                return ErrorParseError;
+            }
+
+            return 0;
+        }
+        catch (Exception ex)
+        {
+            _lastError = $"DocumentEdit failed: {ex}";
+            return ErrorInternal;
+        }
+    }
+
+    /// <summary>
+    /// Validate an open document.
+    /// </summary>
+    [UnmanagedCallersOnly(EntryPoint = "kql_document_get_diagnostics")]
+    public static unsafe int DocumentGetDiagnostics(
+        int documentId,
+        byte* outputPtr,
+        int outputMaxLen)
+    {
+        try
+        {
+            if (!DocumentRegistry.TryGet(documentId, out var document))
+            {
+                return WriteError(ErrorParseError, $"Unknown document id: {documentId}", null, outputPtr, outputMaxLen);
+            }
+
+            return WriteJsonResult(document.GetDiagnostics(), outputPtr, outputMaxLen);
+        }
+        catch (Exception ex)
+        {
+            return WriteError(ErrorInternal, $"DocumentGetDiagnostics failed: {ex.Message}", ex, outputPtr, outputMaxLen);
+        }
+    }
+
+    /// <summary>
+    /// Get completion items at a cursor position of an open document.
+    /// </summary>
+    [UnmanagedCallersOnly(EntryPoint = "kql_document_get_completions")]
+    public static unsafe int DocumentGetCompletions(
+        int documentId,
+        int cursorPosition,
+        byte* outputPtr,
+        int outputMaxLen)
+    {
+        try
+        {
+            if (!DocumentRegistry.TryGet(documentId, out var document))
+            {
+                return WriteError(ErrorParseError, $"Unknown document id: {documentId}", null, outputPtr, outputMaxLen);
+            }
+
+            return WriteJsonResult(document.GetCompletions(cursorPosition), outputPtr, outputMaxLen);
+        }
+        catch (Exception ex)
+        {
+            return WriteError(ErrorInternal, $"DocumentGetCompletions failed: {ex.Message}", ex, outputPtr, outputMaxLen);
+        }
+    }
+
+    /// <summary>
+    /// Close a document opened with kql_document_open.
+    /// </summary>
+    /// <returns>0 on success, -2 if the id is not open</returns>
+    [UnmanagedCallersOnly(EntryPoint = "kql_document_close")]
+    public static int DocumentClose(int documentId)
+    {
+        if (DocumentRegistry.Close(documentId))
+            return 0;
+
+        _lastError = $"Unknown document id: {documentId}";
+        return ErrorParseError;
+    }
+
+    /// <summary>
+    /// Get completion items at cursor position.
+    /// </summary>
+    [UnmanagedCallersOnly(EntryPoint = "kql_get_completions")]
+    public static unsafe int GetCompletions(
+        byte* queryPtr,
+        int queryLen,
+        int cursorPosition,
+        byte* schemaPtr,
+        int schemaLen,
+        byte* outputPtr,
+        int outputMaxLen)
+    {
+        try
+        {
+            // Convert input bytes to string
+            var query = Encoding.UTF8.GetString(queryPtr, queryLen);
+
+            // Parse schema if provided
+            SchemaDefinition? schema = null;
+            if (schemaPtr != null && schemaLen > 0)
+            {
+                var schemaJson = Encoding.UTF8.GetString(schemaPtr, schemaLen);
+                schema = JsonSerializer.Deserialize<SchemaDefinition>(schemaJson);
+            }
+
+            // Get completions
+            var result = CompletionService.GetCompletions(query, cursorPosition, schema);
+
+            // Serialize result to JSON
+            return WriteJsonResult(result, outputPtr, outputMaxLen);
+        }
+        catch (JsonException ex)
+        {
+            return WriteError(ErrorParseError, $"Schema JSON parse error: {ex.Message}", ex, outputPtr, outputMaxLen);
+        }
+        catch (Exception ex)
+        {
+            return WriteError(ErrorInternal, $"GetCompletions failed: {ex.Message}", ex, outputPtr, outputMaxLen);
+        }
+    }
+
+    /// <summary>
+    /// Get completion items at cursor position, filtered by per-call options.
+    /// </summary>
+    [UnmanagedCallersOnly(EntryPoint = "kql_get_completions_with_options")]
+    public static unsafe int GetCompletionsWithOptions(
+        byte* queryPtr,
+        int queryLen,
+        int cursorPosition,
+        byte* schemaPtr,
+        int schemaLen,
+        byte* optionsPtr,
+        int optionsLen,
+        byte* outputPtr,
+        int outputMaxLen)
+    {
+        try
+        {
+            // Convert input bytes to string
+            var query = Encoding.UTF8.GetString(queryPtr, queryLen);
+
+            // Parse schema if provided
+            SchemaDefinition? schema = null;
+            if (schemaPtr != null && schemaLen > 0)
+            {
+                var schemaJson = Encoding.UTF8.GetString(schemaPtr, schemaLen);
+                schema = JsonSerializer.Deserialize<SchemaDefinition>(schemaJson);
+            }
+
+            // Parse options
+            var optionsJson = Encoding.UTF8.GetString(optionsPtr, optionsLen);
+            var options = JsonSerializer.Deserialize<CompletionOptions>(optionsJson);
+            if (options == null)
+            {
+                return WriteError(ErrorParseError, "Failed to parse options JSON", null, outputPtr, outputMaxLen);
+            }
+
+            // Get completions
+            var result = CompletionService.GetCompletions(query, cursorPosition, schema, options);
+
+            // Serialize result to JSON
+            return WriteJsonResult(result, outputPtr, outputMaxLen);
+        }
+        catch (JsonException ex)
+        {
+            return WriteError(ErrorParseError, $"Schema/options JSON parse error: {ex.Message}", ex, outputPtr, outputMaxLen);
+        }
+        catch (Exception ex)
+        {
+            return WriteError(ErrorInternal, $"GetCompletionsWithOptions failed: {ex.Message}", ex, outputPtr, outputMaxLen);
+        }
+    }
+
+    /// <summary>
+    /// Get the last error message.
+    /// </summary>
+    [UnmanagedCallersOnly(EntryPoint = "kql_get_last_error")]
+    public static unsafe int GetLastError(byte* outputPtr, int outputMaxLen)
+    {
+        if (string.IsNullOrEmpty(_lastError))
+        {
+            return 0;
+        }
+
+        var bytes = Encoding.UTF8.GetBytes(_lastError);
+        if (bytes.Length > outputMaxLen)
+        {
+            _lastRequiredSize = bytes.Length;
+            return ErrorBufferTooSmall;
+        }
+
+        fixed (byte* src = bytes)
+        {
+            Buffer.MemoryCopy(src, outputPtr, outputMaxLen, bytes.Length);
+        }
+
+        // Clear the error after retrieval
+        var length = bytes.Length;
+        _lastError = null;
+        return length;
+    }
+
+    /// <summary>
+    /// Get the output size, in bytes, that the last call on this thread
+    /// returning -1 (buffer too small) needed. Retrying that call with a buffer
+    /// of exactly this size succeeds.
+    /// </summary>
+    /// <returns>The required size, or 0 if no call on this thread ran out of space</returns>
+    [UnmanagedCallersOnly(EntryPoint = "kql_get_last_required_size")]
+    public static int GetLastRequiredSize()
+    {
+        return _lastRequiredSize;
+    }
+
+    /// <summary>
+    /// Set the serialization format of results written by later calls on this thread.
+    /// </summary>
+    /// <returns>0 on success, -2 for an unknown format</returns>
+    [UnmanagedCallersOnly(EntryPoint = "kql_set_result_format")]
+    public static int SetResultFormat(int format)
+    {
+        if (format != ResultFormatJson && format != ResultFormatCbor)
+        {
+            _lastError = $"Unknown result format: {format}";
+            return ErrorParseError;
+        }
+
+        _resultFormat = format;
+        return 0;
+    }
+
+    /// <summary>
+    /// Read the result of the last call on this thread that returned -1, starting
+    /// at a byte offset. The result is released once read to the end.
+    /// </summary>
+    /// <returns>Chunk length, 0 at the end, or -2 if no result is pending</returns>
+    [UnmanagedCallersOnly(EntryPoint = "kql_read_result_chunk")]
+    public static unsafe int ReadResultChunk(int offset, byte* outputPtr, int outputMaxLen)
+    {
+        var pending = _pendingResult;
+        if (pending == null || offset < 0 || offset > pending.Length)
+        {
+            return WriteError(ErrorParseError, "No pending result to read", null, outputPtr, outputMaxLen);
+        }
+
+        var count = Math.Min(pending.Length - offset, outputMaxLen);
+        if (count <= 0)
+        {
+            _pendingResult = null;
+            return 0;
+        }
+
+        fixed (byte* src = &pending[offset])
+        {
+            Buffer.MemoryCopy(src, outputPtr, outputMaxLen, count);
+        }
+
+        return count;
+    }
+
+    /// <summary>
+    /// Free a result allocated in native-allocated mode (output_max_len of -1).
+    /// </summary>
+    [UnmanagedCallersOnly(EntryPoint = "kql_free_result")]
+    public static unsafe void FreeResult(byte* data)
+    {
+        NativeMemory.Free(data);
+    }
+
+    /// <summary>
+    /// Write a result object as JSON (or CBOR, per kql_set_result_format) to the
+    /// output buffer, or to a new allocation when outputMaxLen is AllocateResult.
+    /// </summary>
+    private static unsafe int WriteJsonResult<T>(T result, byte* outputPtr, int outputMaxLen)
+    {
+        var bytes = JsonSerializer.SerializeToUtf8Bytes(result, JsonOptions.Default);
+        if (_resultFormat == ResultFormatCbor)
+        {
+            bytes = CborTranscoder.FromJson(bytes);
+        }
+
+        if (outputMaxLen == AllocateResult)
+        {
+            // Native-allocated mode: outputPtr is a byte** slot, freed with kql_free_result
+            var data = (byte*)NativeMemory.Alloc((nuint)Math.Max(bytes.Length, 1));
+            fixed (byte* src = bytes)
+            {
+                Buffer.MemoryCopy(src, data, bytes.Length, bytes.Length);
+            }
+
+            *(byte**)outputPtr = data;
+            return bytes.Length;
+        }
+
+        if (bytes.Length > outputMaxLen)
+        {
+            _lastError = $"Output buffer too small: needed {bytes.Length}, got {outputMaxLen}";
+            _lastRequiredSize = bytes.Length;
+            _pendingResult = bytes;
+            return ErrorBufferTooSmall;
+        }
+
+        fixed (byte* src = bytes)
+        {
+            Buffer.MemoryCopy(src, outputPtr, outputMaxLen, bytes.Length);
+        }
+
+        return bytes.Length;
+    }
+
+    /// <summary>
+    /// Fail a call: record the error for kql_get_last_error and write an error
+    /// envelope, NUL-terminated, to the output buffer (or a new allocation when
+    /// outputMaxLen is AllocateResult) so the caller reads the error of its own call.
+    /// </summary>
+    /// <returns>The error code</returns>
+    private static unsafe int WriteError(int code, string message, Exception? ex, byte* outputPtr, int outputMaxLen)
+    {
+        _lastError = ex == null ? message : $"{message}\n{ex.StackTrace}";
+        NativeLog.Write(code switch
+        {
+            ErrorInternal => NativeLog.Error,
+            ErrorCancelled => NativeLog.Debug,
+            _ => NativeLog.Warn
+        }, _lastError);
+
+        var envelope = new ErrorEnvelope
+        {
+            Error = new ErrorDetails
+            {
+                Code = code,
+                Message = message,
+                ExceptionType = ex?.GetType().FullName,
+                Stack = ex?.StackTrace
+            }
+        };
+        var bytes = JsonSerializer.SerializeToUtf8Bytes(envelope, JsonOptions.Default);
+
+        byte* target;
+        if (outputMaxLen == AllocateResult)
+        {
+            target = (byte*)NativeMemory.Alloc((nuint)bytes.Length + 1);
+            *(byte**)outputPtr = target;
+        }
+        else if (outputPtr != null && bytes.Length < outputMaxLen)
+        {
+            target = outputPtr;
+        }
+        else
+        {
+            // No room: the caller falls back to kql_get_last_error
+            return code;
+        }
+
+        fixed (byte* src = bytes)
+        {
+            Buffer.MemoryCopy(src, target, bytes.Length, bytes.Length);
+        }
+        target[bytes.Length] = 0;
+
+        return code;
+    }
+}
+
+/// <summary>
+/// Error envelope written to the output of a failed call.
+/// </summary>
+internal sealed class ErrorEnvelope
+{
+    public bool Ok => false;
+    public ErrorDetails Error { get; init; } = new();
+}
+
+/// <summary>
+/// Details of a failed call.
+/// </summary>
+internal sealed class ErrorDetails
+{
+    public int Code { get; init; }
+    public string Message { get; init; } = "";
+    public string? ExceptionType { get; init; }
+    public string? Stack { get; init; }
 }
 
 /// <summary>
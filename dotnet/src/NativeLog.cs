@@ -0,0 +1,36 @@
+using System.Text;
+
+namespace KqlLanguageFfi;
+
+/// <summary>
+/// Sends log messages to the callback registered with kql_set_log_callback.
+/// Messages are dropped while no callback is registered.
+/// </summary>
+internal static unsafe class NativeLog
+{
+    // Levels matching the Rust log crate
+    public const int Error = 1;
+    public const int Warn = 2;
+    public const int Info = 3;
+    public const int Debug = 4;
+
+    private static delegate* unmanaged<int, byte*, int, void> _callback;
+
+    public static void SetCallback(delegate* unmanaged<int, byte*, int, void> callback)
+    {
+        _callback = callback;
+    }
+
+    public static void Write(int level, string message)
+    {
+        var callback = _callback;
+        if (callback == null)
+            return;
+
+        var bytes = Encoding.UTF8.GetBytes(message);
+        fixed (byte* ptr = bytes)
+        {
+            callback(level, ptr, bytes.Length);
+        }
+    }
+}
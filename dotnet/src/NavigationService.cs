@@ -0,0 +1,71 @@
+using Kusto.Language;
+using Kusto.Language.Symbols;
+using Kusto.Language.Syntax;
+
+namespace KqlLanguageFfi;
+
+/// <summary>
+/// Go-to-definition service for names declared within a query.
+/// </summary>
+public static class NavigationService
+{
+    /// <summary>
+    /// Find the declaration of the local name at a cursor position.
+    /// </summary>
+    /// <param name="query">The KQL query</param>
+    /// <param name="cursorPosition">Cursor position (0-based character offset)</param>
+    /// <returns>The declaration, or null if the cursor is not on a locally declared name</returns>
+    public static DefinitionResult? GetDefinition(string query, int cursorPosition)
+    {
+        try
+        {
+            var code = KustoCode.ParseAndAnalyze(query);
+
+            // A cursor just past the end of a name still refers to it
+            var token = GetIdentifierAt(code, cursorPosition) ?? GetIdentifierAt(code, cursorPosition - 1);
+            var symbol = token?.Parent switch
+            {
+                NameReference reference => reference.ReferencedSymbol,
+                NameDeclaration declaration => declaration.ReferencedSymbol,
+                _ => null
+            };
+            if (symbol == null)
+                return null;
+
+            var declared = code.Syntax.GetDescendants<NameDeclaration>(d => d.ReferencedSymbol == symbol).FirstOrDefault();
+            if (declared == null)
+                return null;
+
+            return new DefinitionResult
+            {
+                Name = declared.SimpleName,
+                Kind = symbol switch
+                {
+                    FunctionSymbol => "Function",
+                    ParameterSymbol => "Parameter",
+                    VariableSymbol { Type: FunctionSymbol } => "Function",
+                    VariableSymbol => "Variable",
+                    _ => "Other"
+                },
+                Start = declared.TextStart,
+                End = declared.End
+            };
+        }
+        catch (Exception)
+        {
+            // On error, return no definition
+            return null;
+        }
+    }
+
+    private static SyntaxToken? GetIdentifierAt(KustoCode code, int position)
+    {
+        if (position < 0)
+            return null;
+
+        var token = code.Syntax.GetTokenAt(position);
+        return token != null && token.Kind == SyntaxKind.IdentifierToken && token.TextStart <= position
+            ? token
+            : null;
+    }
+}
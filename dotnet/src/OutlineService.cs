@@ -0,0 +1,131 @@
+using Kusto.Language;
+using Kusto.Language.Syntax;
+
+namespace KqlLanguageFfi;
+
+/// <summary>
+/// Document outline service.
+/// Lists let bindings, declared functions and their parameters, and the
+/// pipeline stages of each query as a symbol tree.
+/// </summary>
+public static class OutlineService
+{
+    /// <summary>
+    /// Get the outline of a KQL document.
+    /// </summary>
+    /// <param name="query">The KQL document</param>
+    /// <returns>Top-level symbols, in source order</returns>
+    public static List<DocumentSymbolResponse> GetDocumentSymbols(string query)
+    {
+        try
+        {
+            var code = KustoCode.Parse(query);
+            var lines = new LineMap(query);
+            var symbols = new List<DocumentSymbolResponse>();
+
+            if (code.Syntax is QueryBlock block)
+            {
+                foreach (var element in block.Statements)
+                {
+                    var symbol = BuildStatement(element.Element, lines);
+                    if (symbol != null)
+                        symbols.Add(symbol);
+                }
+            }
+
+            return symbols;
+        }
+        catch (Exception)
+        {
+            // On error, return empty outline (let validation catch errors)
+            return new List<DocumentSymbolResponse>();
+        }
+    }
+
+    private static DocumentSymbolResponse? BuildStatement(Statement statement, LineMap lines)
+    {
+        switch (statement)
+        {
+            case LetStatement let when let.Expression is FunctionDeclaration function:
+            {
+                var symbol = MakeSymbol(let.Name.SimpleName, "Function", let.Name, let, lines);
+                foreach (var parameter in function.Parameters.Parameters)
+                {
+                    var name = parameter.Element.NameAndType.Name;
+                    symbol.Children.Add(MakeSymbol(name.SimpleName, "Parameter", name, parameter.Element, lines));
+                }
+                if (function.Body.Expression != null)
+                    symbol.Children.AddRange(GetStages(function.Body.Expression, lines));
+                return symbol;
+            }
+
+            case LetStatement let:
+            {
+                var symbol = MakeSymbol(let.Name.SimpleName, "Variable", let.Name, let, lines);
+                symbol.Children.AddRange(GetStages(let.Expression, lines));
+                return symbol;
+            }
+
+            case ExpressionStatement expression:
+            {
+                var source = GetSource(expression.Expression);
+                var symbol = MakeSymbol(GetSourceName(source), "Query", source, expression, lines);
+                symbol.Children.AddRange(GetStages(expression.Expression, lines));
+                return symbol;
+            }
+
+            default:
+                return null;
+        }
+    }
+
+    /// <summary>
+    /// Get one symbol per query operator of a pipeline, in source order.
+    /// </summary>
+    private static IEnumerable<DocumentSymbolResponse> GetStages(Expression expression, LineMap lines)
+    {
+        var stages = new List<DocumentSymbolResponse>();
+        while (expression is PipeExpression pipe)
+        {
+            var op = pipe.Operator;
+            stages.Add(MakeSymbol(op.GetFirstToken().Text, "Operator", op, op, lines));
+            expression = pipe.Expression;
+        }
+        stages.Reverse();
+        return stages;
+    }
+
+    private static Expression GetSource(Expression expression)
+    {
+        while (expression is PipeExpression pipe)
+            expression = pipe.Expression;
+        return expression;
+    }
+
+    private static string GetSourceName(Expression source)
+    {
+        return source switch
+        {
+            NameReference reference => reference.SimpleName,
+            _ => source.GetFirstToken()?.Text ?? ""
+        };
+    }
+
+    /// <summary>
+    /// Make a symbol whose name sits at <paramref name="selection"/> within the full <paramref name="range"/>.
+    /// </summary>
+    private static DocumentSymbolResponse MakeSymbol(string name, string kind, SyntaxNode selection, SyntaxNode range, LineMap lines)
+    {
+        return new DocumentSymbolResponse
+        {
+            Name = name,
+            Kind = kind,
+            Start = range.TextStart,
+            End = range.End,
+            StartLine = lines.GetLine(range.TextStart),
+            EndLine = lines.GetLine(range.End),
+            SelectionStart = selection.TextStart,
+            SelectionEnd = selection.End
+        };
+    }
+}
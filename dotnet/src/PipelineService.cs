@@ -0,0 +1,57 @@
+using Kusto.Language;
+using Kusto.Language.Symbols;
+using Kusto.Language.Syntax;
+
+namespace KqlLanguageFfi;
+
+/// <summary>
+/// Pipeline decomposition service.
+/// Splits the pipeline of a query's last statement into its source and
+/// operators, with the columns after each stage.
+/// </summary>
+public static class PipelineService
+{
+    /// <summary>
+    /// Get the stages of a query's final pipeline.
+    /// </summary>
+    /// <param name="query">The KQL query</param>
+    /// <param name="schema">Schema definition the query runs against</param>
+    /// <param name="options">Analysis options (dialect, ...)</param>
+    /// <returns>The source, then each operator, in source order</returns>
+    public static PipelineResult AnalyzePipeline(string query, SchemaDefinition schema, ValidationOptions options)
+    {
+        var globals = ValidationService.BuildGlobalState(schema, options);
+        var code = KustoCode.ParseAndAnalyze(query, globals);
+        var result = new PipelineResult();
+
+        var statement = (code.Syntax as QueryBlock)?.Statements
+            .Select(s => s.Element)
+            .OfType<ExpressionStatement>()
+            .LastOrDefault();
+        if (statement == null)
+            return result;
+
+        var expression = statement.Expression;
+        while (expression is PipeExpression pipe)
+        {
+            result.Stages.Add(MakeStage(query, pipe.Operator, pipe.ResultType));
+            expression = pipe.Expression;
+        }
+        result.Stages.Add(MakeStage(query, expression, expression.ResultType));
+        result.Stages.Reverse();
+
+        return result;
+    }
+
+    private static PipelineStageResult MakeStage(string query, SyntaxNode node, TypeSymbol? type)
+    {
+        return new PipelineStageResult
+        {
+            Kind = node.Kind.ToString(),
+            Text = query.Substring(node.TextStart, node.End - node.TextStart),
+            Start = node.TextStart,
+            End = node.End,
+            Output = type is TableSymbol table ? ResultSchemaService.ToTableDefinition(table) : null
+        };
+    }
+}
@@ -0,0 +1,53 @@
+using Kusto.Language;
+using Kusto.Language.Syntax;
+
+namespace KqlLanguageFfi;
+
+/// <summary>
+/// Related-location service.
+/// Points diagnostics reported on a locally declared name (a let variable,
+/// function, or parameter) at the name's declaration.
+/// </summary>
+public static class RelatedInfoService
+{
+    /// <summary>
+    /// Collect related locations for diagnostics of an analyzed query.
+    /// </summary>
+    /// <param name="code">The analyzed query</param>
+    /// <param name="diagnostics">Diagnostics to attach locations to</param>
+    /// <returns>Related locations per diagnostic (diagnostics without any are omitted)</returns>
+    public static Dictionary<Kusto.Language.Diagnostic, List<RelatedInfoDefinition>> Collect(
+        KustoCode code,
+        IEnumerable<Kusto.Language.Diagnostic> diagnostics)
+    {
+        var related = new Dictionary<Kusto.Language.Diagnostic, List<RelatedInfoDefinition>>(ReferenceEqualityComparer.Instance);
+
+        foreach (var diagnostic in diagnostics)
+        {
+            if (!diagnostic.HasLocation)
+                continue;
+
+            var token = code.Syntax.GetTokenAt(diagnostic.Start);
+            if (token?.Parent is not NameReference reference || reference.ReferencedSymbol == null)
+                continue;
+
+            var declaration = code.Syntax
+                .GetDescendants<NameDeclaration>(d => d.ReferencedSymbol == reference.ReferencedSymbol)
+                .FirstOrDefault();
+            if (declaration == null)
+                continue;
+
+            related[diagnostic] = new List<RelatedInfoDefinition>
+            {
+                new RelatedInfoDefinition
+                {
+                    Message = $"'{declaration.SimpleName}' is declared here",
+                    Start = declaration.TextStart,
+                    End = declaration.End
+                }
+            };
+        }
+
+        return related;
+    }
+}
@@ -0,0 +1,112 @@
+using Kusto.Language;
+using Kusto.Language.Symbols;
+using Kusto.Language.Syntax;
+
+namespace KqlLanguageFfi;
+
+/// <summary>
+/// Azure Resource Graph dialect.
+/// Resource Graph supports a subset of tabular operators (enforced through a
+/// built-in feature profile), limits how many joins, unions and mv-expands a
+/// query may use, accepts only some join kinds, and matches table names
+/// case-insensitively.
+/// </summary>
+public static class ResourceGraphDialect
+{
+    /// <summary>
+    /// Query uses more joins, unions or mv-expands than Resource Graph allows.
+    /// </summary>
+    public const string CodeOperatorLimitExceeded = "ARG001";
+
+    /// <summary>
+    /// Join kind not supported by Resource Graph.
+    /// </summary>
+    public const string CodeUnsupportedJoinKind = "ARG002";
+
+    /// <summary>
+    /// Most joins, unions or mv-expands in one query.
+    /// </summary>
+    public const int MaxLimitedOperators = 3;
+
+    /// <summary>
+    /// Tabular operators supported by Resource Graph.
+    /// </summary>
+    public static readonly FeatureProfileDefinition Profile = new()
+    {
+        Name = "ResourceGraph",
+        Operators = new HashSet<string>
+        {
+            "count", "distinct", "extend", "join", "limit", "mv-expand", "order", "parse", "project",
+            "project-away", "sample", "sample-distinct", "sort", "summarize", "take", "top", "union", "where"
+        },
+        Plugins = new HashSet<string>()
+    };
+
+    private static readonly HashSet<string> JoinKinds = new(StringComparer.OrdinalIgnoreCase)
+    {
+        "innerunique", "inner", "leftouter", "fullouter"
+    };
+
+    /// <summary>
+    /// Lowercase aliases of the schema's tables, so 'resources' and
+    /// 'Resources' both resolve.
+    /// </summary>
+    public static IEnumerable<Symbol> GetTableAliases(SchemaDefinition schema)
+    {
+        var names = (schema.Tables ?? Enumerable.Empty<TableDefinition>())
+            .Select(t => t.Name)
+            .ToHashSet(StringComparer.Ordinal);
+        var aliases = (schema.Tables ?? Enumerable.Empty<TableDefinition>())
+            .Where(t => !names.Contains(t.Name.ToLowerInvariant()))
+            .Select(t => new TableDefinition { Name = t.Name.ToLowerInvariant(), Columns = t.Columns });
+        return ValidationService.BuildTableSymbols(aliases);
+    }
+
+    /// <summary>
+    /// Run Resource Graph restriction checks over an already parsed (and possibly analyzed) query.
+    /// </summary>
+    public static List<Kusto.Language.Diagnostic> Apply(KustoCode code, List<Kusto.Language.Diagnostic> diagnostics)
+    {
+        var result = FeatureProfileService.Apply(code, Profile, diagnostics);
+
+        CheckLimit<JoinOperator>(code, "join", result);
+        CheckLimit<UnionOperator>(code, "union", result);
+        CheckLimit<MvExpandOperator>(code, "mv-expand", result);
+
+        foreach (var join in code.Syntax.GetDescendants<JoinOperator>())
+        {
+            foreach (var parameter in join.Parameters)
+            {
+                var kind = parameter.Expression.GetFirstToken()?.Text;
+                if (parameter.Name.SimpleName == "kind" && kind != null && !JoinKinds.Contains(kind))
+                {
+                    result.Add(MakeDiagnostic(
+                        CodeUnsupportedJoinKind,
+                        $"Resource Graph does not support join kind '{kind}'; use innerunique, inner, leftouter or fullouter",
+                        parameter));
+                }
+            }
+        }
+
+        return result.OrderBy(d => d.Start).ToList();
+    }
+
+    private static void CheckLimit<T>(KustoCode code, string name, List<Kusto.Language.Diagnostic> result)
+        where T : QueryOperator
+    {
+        var operators = code.Syntax.GetDescendants<T>();
+        if (operators.Count > MaxLimitedOperators)
+        {
+            result.Add(MakeDiagnostic(
+                CodeOperatorLimitExceeded,
+                $"Query has {operators.Count} '{name}' operators; Resource Graph allows at most {MaxLimitedOperators}",
+                operators[MaxLimitedOperators].GetFirstToken()));
+        }
+    }
+
+    private static Kusto.Language.Diagnostic MakeDiagnostic(string code, string message, SyntaxElement location)
+    {
+        return new Kusto.Language.Diagnostic(code, DiagnosticCategory.Correctness, DiagnosticSeverity.Error, message, message)
+            .WithLocation(location.TextStart, location.End - location.TextStart);
+    }
+}
@@ -0,0 +1,53 @@
+using Kusto.Language;
+using Kusto.Language.Symbols;
+
+namespace KqlLanguageFfi;
+
+/// <summary>
+/// Result-schema inference service.
+/// Uses Kusto.Language semantic analysis to determine the columns and types
+/// produced by the final statement of a query.
+/// </summary>
+public static class ResultSchemaService
+{
+    /// <summary>
+    /// Infer the result schema of a query.
+    /// </summary>
+    /// <param name="query">The KQL query</param>
+    /// <param name="schema">Schema definition the query runs against</param>
+    /// <param name="options">Analysis options (dialect, ...)</param>
+    /// <returns>The result table, or null if the query does not produce a tabular result</returns>
+    public static TableDefinition? InferResultSchema(string query, SchemaDefinition schema, ValidationOptions options)
+    {
+        var globals = ValidationService.BuildGlobalState(schema, options);
+        var code = KustoCode.ParseAndAnalyze(query, globals);
+
+        if (code.ResultType is not TableSymbol table)
+            return null;
+
+        return ToTableDefinition(table);
+    }
+
+    /// <summary>
+    /// Get the schema definition of a table symbol's columns.
+    /// </summary>
+    internal static TableDefinition ToTableDefinition(TableSymbol table)
+    {
+        return new TableDefinition
+        {
+            Name = table.Name,
+            Columns = table.Columns
+                .Select(c => new ColumnDefinition { Name = c.Name, DataType = GetTypeName(c.Type) })
+                .ToList()
+        };
+    }
+
+    /// <summary>
+    /// Get the schema type name of a column type ("long", "dynamic", ...).
+    /// </summary>
+    internal static string GetTypeName(TypeSymbol type)
+    {
+        // Dynamic bags and arrays with inferred shapes are still "dynamic" columns
+        return type is DynamicSymbol ? "dynamic" : type.Name;
+    }
+}
@@ -0,0 +1,73 @@
+using System.Collections.Concurrent;
+using Kusto.Language;
+
+namespace KqlLanguageFfi;
+
+/// <summary>
+/// Registry of schemas sent once by the caller and referenced by id afterwards.
+/// The GlobalState for each schema is built lazily per dialect and cached, so
+/// calls that pass a schema id skip both JSON parsing and symbol construction.
+/// </summary>
+public static class SchemaRegistry
+{
+    private static readonly ConcurrentDictionary<int, RegisteredSchema> Schemas = new();
+    private static int _nextId;
+
+    /// <summary>
+    /// Register a schema.
+    /// </summary>
+    /// <returns>A positive schema id</returns>
+    public static int Register(SchemaDefinition schema)
+    {
+        var id = Interlocked.Increment(ref _nextId);
+        Schemas[id] = new RegisteredSchema(schema);
+        return id;
+    }
+
+    /// <summary>
+    /// Release a registered schema.
+    /// </summary>
+    /// <returns>True if the id was registered</returns>
+    public static bool Release(int id)
+    {
+        return Schemas.TryRemove(id, out _);
+    }
+
+    /// <summary>
+    /// Look up a registered schema.
+    /// </summary>
+    public static bool TryGet(int id, out RegisteredSchema schema)
+    {
+        return Schemas.TryGetValue(id, out schema!);
+    }
+}
+
+/// <summary>
+/// A registered schema with its GlobalState cached per dialect.
+/// </summary>
+public sealed class RegisteredSchema
+{
+    private readonly ConcurrentDictionary<string, GlobalState> _globals = new();
+
+    public RegisteredSchema(SchemaDefinition definition)
+    {
+        Definition = definition;
+    }
+
+    /// <summary>
+    /// The schema as registered.
+    /// </summary>
+    public SchemaDefinition Definition { get; }
+
+    /// <summary>
+    /// Get the GlobalState for the given options, building it on first use.
+    /// </summary>
+    public GlobalState GetGlobals(ValidationOptions options)
+    {
+        // Option query parameters change the globals, so they are part of the key
+        var key = options.QueryParameters is { Count: > 0 } parameters
+            ? options.Dialect + "|" + string.Join(",", parameters.Select(p => $"{p.Name}:{p.DataType}"))
+            : options.Dialect;
+        return _globals.GetOrAdd(key, _ => ValidationService.BuildGlobalState(Definition, options));
+    }
+}
@@ -0,0 +1,130 @@
+using Kusto.Language;
+using Kusto.Language.Syntax;
+
+namespace KqlLanguageFfi;
+
+/// <summary>
+/// Selection service.
+/// Matches brackets and lists the syntax ranges enclosing a position, for
+/// rainbow brackets and expand-selection.
+/// </summary>
+public static class SelectionService
+{
+    /// <summary>
+    /// Get the matching bracket pairs of a query.
+    /// Strings and comments are single tokens or trivia, so their brackets are never matched.
+    /// </summary>
+    /// <param name="query">The KQL query</param>
+    /// <returns>Matched pairs ordered by the opening bracket</returns>
+    public static List<BracketPairResult> GetBracketPairs(string query)
+    {
+        var code = KustoCode.Parse(query);
+        var pairs = new List<BracketPairResult>();
+        var open = new Stack<(SyntaxToken Token, BracketPairResult Pair)>();
+
+        for (var token = code.Syntax.GetFirstToken(); token != null; token = token.GetNextToken())
+        {
+            // Tokens the parser inserted for missing brackets have no width
+            if (token.Width == 0)
+                continue;
+
+            switch (token.Kind)
+            {
+                case SyntaxKind.OpenParenToken:
+                case SyntaxKind.OpenBracketToken:
+                case SyntaxKind.OpenBraceToken:
+                    var pair = new BracketPairResult { Open = token.TextStart, Depth = open.Count };
+                    pairs.Add(pair);
+                    open.Push((token, pair));
+                    break;
+                case SyntaxKind.CloseParenToken:
+                case SyntaxKind.CloseBracketToken:
+                case SyntaxKind.CloseBraceToken:
+                    if (open.Count > 0 && Matches(open.Peek().Token.Kind, token.Kind))
+                    {
+                        open.Pop().Pair.Close = token.TextStart;
+                    }
+                    break;
+            }
+        }
+
+        // Brackets still open were never closed
+        return pairs.Where(p => p.Close >= 0).ToList();
+    }
+
+    /// <summary>
+    /// Get the syntax ranges enclosing a position, innermost first.
+    /// Each range is strictly larger than the one before it.
+    /// </summary>
+    /// <param name="query">The KQL query</param>
+    /// <param name="position">Position (0-based character offset)</param>
+    /// <returns>Enclosing ranges, ending with the whole document</returns>
+    public static List<EnclosingRangeResult> GetEnclosingRanges(string query, int position)
+    {
+        var code = KustoCode.Parse(query);
+        var ranges = new List<EnclosingRangeResult>();
+
+        // A position just past the end of a token still refers to it
+        var token = GetTokenAt(code, position) ?? GetTokenAt(code, position - 1);
+        if (token != null)
+        {
+            Add(ranges, token, "Token");
+            for (var node = token.Parent; node != null; node = node.Parent)
+            {
+                var kind = node switch
+                {
+                    QueryBlock => "Query",
+                    Statement => "Statement",
+                    QueryOperator => "Stage",
+                    PipeExpression => "Pipeline",
+                    Expression => "Expression",
+                    _ => null
+                };
+                if (kind != null)
+                {
+                    Add(ranges, node, kind);
+                }
+            }
+        }
+
+        Add(ranges, code.Syntax, "Query");
+        return ranges;
+    }
+
+    private static SyntaxToken? GetTokenAt(KustoCode code, int position)
+    {
+        if (position < 0)
+            return null;
+
+        var token = code.Syntax.GetTokenAt(position);
+        if (token == null || token.Width == 0 || token.TextStart > position)
+            return null;
+
+        return token;
+    }
+
+    private static void Add(List<EnclosingRangeResult> ranges, SyntaxElement element, string kind)
+    {
+        var start = element.TextStart;
+        var end = element.End;
+        if (ranges.Count > 0)
+        {
+            var last = ranges[^1];
+            if (start > last.Start || end < last.End || (start == last.Start && end == last.End))
+                return;
+        }
+
+        ranges.Add(new EnclosingRangeResult { Start = start, End = end, Kind = kind });
+    }
+
+    private static bool Matches(SyntaxKind open, SyntaxKind close)
+    {
+        return (open, close) switch
+        {
+            (SyntaxKind.OpenParenToken, SyntaxKind.CloseParenToken) => true,
+            (SyntaxKind.OpenBracketToken, SyntaxKind.CloseBracketToken) => true,
+            (SyntaxKind.OpenBraceToken, SyntaxKind.CloseBraceToken) => true,
+            _ => false
+        };
+    }
+}
@@ -0,0 +1,100 @@
+using Kusto.Language;
+using Kusto.Language.Syntax;
+
+namespace KqlLanguageFfi;
+
+/// <summary>
+/// Checks queries against Kusto service limits (query size, identifier
+/// lengths, summarize keys, in-list values).
+/// </summary>
+public static class ServiceLimitService
+{
+    /// <summary>
+    /// Category reported for service-limit violations.
+    /// </summary>
+    public const string Category = "ServiceLimit";
+
+    /// <summary>
+    /// Query text is longer than allowed.
+    /// </summary>
+    public const string CodeQueryTooLong = "SL001";
+
+    /// <summary>
+    /// Identifier is longer than allowed.
+    /// </summary>
+    public const string CodeIdentifierTooLong = "SL002";
+
+    /// <summary>
+    /// Summarize has more by keys than allowed.
+    /// </summary>
+    public const string CodeTooManySummarizeKeys = "SL003";
+
+    /// <summary>
+    /// Literal in / !in list has more values than allowed.
+    /// </summary>
+    public const string CodeTooManyInListValues = "SL004";
+
+    /// <summary>
+    /// Run the limit checks over a parsed query, appending any violations.
+    /// </summary>
+    public static List<Kusto.Language.Diagnostic> Apply(
+        KustoCode code,
+        ServiceLimitsDefinition limits,
+        List<Kusto.Language.Diagnostic> diagnostics)
+    {
+        var result = new List<Kusto.Language.Diagnostic>(diagnostics);
+
+        if (code.Text.Length > limits.MaxQueryLength)
+        {
+            result.Add(MakeDiagnostic(
+                CodeQueryTooLong,
+                $"Query is {code.Text.Length} characters long; the limit is {limits.MaxQueryLength}",
+                limits.MaxQueryLength,
+                code.Text.Length - limits.MaxQueryLength));
+        }
+
+        foreach (var name in code.Syntax.GetDescendants<Name>(n => n.SimpleName.Length > limits.MaxIdentifierLength))
+        {
+            result.Add(MakeDiagnostic(
+                CodeIdentifierTooLong,
+                $"Identifier is {name.SimpleName.Length} characters long; the limit is {limits.MaxIdentifierLength}",
+                name.TextStart,
+                name.End - name.TextStart));
+        }
+
+        foreach (var summarize in code.Syntax.GetDescendants<SummarizeOperator>(s => s.ByClause != null))
+        {
+            var keys = summarize.ByClause!.Expressions;
+            if (keys.Count > limits.MaxSummarizeKeys)
+            {
+                var first = keys[limits.MaxSummarizeKeys].Element;
+                result.Add(MakeDiagnostic(
+                    CodeTooManySummarizeKeys,
+                    $"summarize has {keys.Count} grouping keys; the limit is {limits.MaxSummarizeKeys}",
+                    first.TextStart,
+                    summarize.ByClause.End - first.TextStart));
+            }
+        }
+
+        foreach (var inExpression in code.Syntax.GetDescendants<InExpression>())
+        {
+            var values = inExpression.Right.Expressions;
+            if (values.Count > limits.MaxInListValues)
+            {
+                result.Add(MakeDiagnostic(
+                    CodeTooManyInListValues,
+                    $"'{inExpression.Operator.Text}' list has {values.Count} values; the limit is {limits.MaxInListValues}",
+                    inExpression.Right.TextStart,
+                    inExpression.Right.End - inExpression.Right.TextStart));
+            }
+        }
+
+        return result.OrderBy(d => d.Start).ToList();
+    }
+
+    private static Kusto.Language.Diagnostic MakeDiagnostic(string code, string message, int start, int length)
+    {
+        return new Kusto.Language.Diagnostic(code, Category, DiagnosticSeverity.Error, message, message)
+            .WithLocation(start, length);
+    }
+}
@@ -0,0 +1,192 @@
+using Kusto.Language;
+using Kusto.Language.Syntax;
+
+namespace KqlLanguageFfi;
+
+/// <summary>
+/// Document structure service.
+/// Builds one tree of statements, declarations, and foldable regions so that
+/// outline and folding information can be returned in a single call.
+/// </summary>
+public static class StructureService
+{
+    /// <summary>
+    /// Get the structure of a KQL document.
+    /// </summary>
+    /// <param name="query">The KQL document</param>
+    /// <returns>Top-level statements and comment blocks with nested declarations and regions</returns>
+    public static DocumentStructureResult GetStructure(string query)
+    {
+        try
+        {
+            var code = KustoCode.Parse(query);
+            var lines = new LineMap(query);
+            var statements = new List<StructureNodeResponse>();
+
+            if (code.Syntax is QueryBlock block)
+            {
+                foreach (var element in block.Statements)
+                {
+                    statements.Add(BuildStatement(element.Element, lines));
+                }
+            }
+
+            foreach (var comment in GetCommentBlocks(code.Syntax, lines))
+            {
+                Insert(statements, comment);
+            }
+
+            return new DocumentStructureResult { Statements = statements };
+        }
+        catch (Exception)
+        {
+            // On error, return empty result (let validation catch errors)
+            return new DocumentStructureResult();
+        }
+    }
+
+    private static StructureNodeResponse BuildStatement(Statement statement, LineMap lines)
+    {
+        var kind = statement switch
+        {
+            LetStatement => "LetStatement",
+            ExpressionStatement => "QueryStatement",
+            SetOptionStatement => "SetStatement",
+            QueryParametersStatement => "DeclareStatement",
+            _ => "OtherStatement"
+        };
+        var node = MakeNode(kind, null, statement.TextStart, statement.End, lines);
+
+        if (statement is LetStatement let)
+        {
+            var isFunction = let.Expression is FunctionDeclaration;
+            var declaration = MakeNode(
+                isFunction ? "FunctionDeclaration" : "VariableDeclaration",
+                let.Name.SimpleName,
+                let.Name.TextStart,
+                let.End,
+                lines);
+
+            if (let.Expression is FunctionDeclaration function)
+            {
+                foreach (var parameter in function.Parameters.Parameters)
+                {
+                    var name = parameter.Element.NameAndType.Name;
+                    declaration.Children!.Add(MakeNode(
+                        "ParameterDeclaration",
+                        name.SimpleName,
+                        parameter.Element.TextStart,
+                        parameter.Element.End,
+                        lines));
+                }
+            }
+
+            AddRegions(let.Expression, declaration.Children!, lines);
+            node.Children!.Add(declaration);
+        }
+        else
+        {
+            AddRegions(statement, node.Children!, lines);
+        }
+
+        return node;
+    }
+
+    /// <summary>
+    /// Add function bodies and multi-line parenthesized expressions under an element, nesting them.
+    /// </summary>
+    private static void AddRegions(SyntaxElement element, List<StructureNodeResponse> into, LineMap lines)
+    {
+        for (int i = 0; i < element.ChildCount; i++)
+        {
+            if (element.GetChild(i) is not SyntaxNode child)
+                continue;
+
+            StructureNodeResponse? region = child switch
+            {
+                FunctionBody => MakeNode("FunctionBody", null, child.TextStart, child.End, lines),
+                ParenthesizedExpression when lines.GetLine(child.TextStart) != lines.GetLine(child.End) =>
+                    MakeNode("Subquery", null, child.TextStart, child.End, lines),
+                _ => null
+            };
+
+            if (region != null)
+            {
+                into.Add(region);
+                AddRegions(child, region.Children!, lines);
+            }
+            else
+            {
+                AddRegions(child, into, lines);
+            }
+        }
+    }
+
+    /// <summary>
+    /// Find runs of two or more consecutive // comment lines in token trivia.
+    /// </summary>
+    private static IEnumerable<StructureNodeResponse> GetCommentBlocks(SyntaxNode root, LineMap lines)
+    {
+        for (var token = root.GetFirstToken(); token != null; token = token.GetNextToken())
+        {
+            if (string.IsNullOrEmpty(token.Trivia))
+                continue;
+
+            int blockStart = -1, blockEnd = -1, count = 0;
+            int offset = 0;
+            foreach (var line in token.Trivia.Split('\n'))
+            {
+                var trimmed = line.TrimStart();
+                if (trimmed.StartsWith("//"))
+                {
+                    var start = token.TriviaStart + offset + (line.Length - trimmed.Length);
+                    if (count == 0)
+                        blockStart = start;
+                    blockEnd = token.TriviaStart + offset + line.TrimEnd('\r').Length;
+                    count++;
+                }
+                else if (trimmed.Length > 0 || count > 0)
+                {
+                    if (count >= 2)
+                        yield return MakeNode("CommentBlock", null, blockStart, blockEnd, lines);
+                    count = 0;
+                }
+                offset += line.Length + 1;
+            }
+
+            if (count >= 2)
+                yield return MakeNode("CommentBlock", null, blockStart, blockEnd, lines);
+        }
+    }
+
+    /// <summary>
+    /// Insert a node under the innermost node that contains it, keeping source order.
+    /// </summary>
+    private static void Insert(List<StructureNodeResponse> nodes, StructureNodeResponse node)
+    {
+        var parent = nodes.FirstOrDefault(n => n.Start <= node.Start && node.End <= n.End);
+        if (parent != null)
+        {
+            parent.Children ??= new List<StructureNodeResponse>();
+            Insert(parent.Children, node);
+            return;
+        }
+
+        var index = nodes.FindIndex(n => n.Start > node.Start);
+        nodes.Insert(index < 0 ? nodes.Count : index, node);
+    }
+
+    private static StructureNodeResponse MakeNode(string kind, string? name, int start, int end, LineMap lines)
+    {
+        return new StructureNodeResponse
+        {
+            Kind = kind,
+            Name = name,
+            Start = start,
+            End = end,
+            StartLine = lines.GetLine(start),
+            EndLine = lines.GetLine(end),
+            Children = new List<StructureNodeResponse>()
+        };
+    }
+}
@@ -0,0 +1,53 @@
+using Kusto.Language;
+using Kusto.Language.Syntax;
+
+namespace KqlLanguageFfi;
+
+/// <summary>
+/// Syntax tree service.
+/// Lists the parse tree of a query so tools can walk it outside .NET.
+/// </summary>
+public static class SyntaxTreeService
+{
+    /// <summary>
+    /// Get the syntax tree of a query.
+    /// Nodes are listed in pre-order with the index of their parent, so
+    /// deep trees serialize without deep JSON nesting. Zero-width elements
+    /// (missing tokens, empty lists) are left out.
+    /// </summary>
+    /// <param name="query">The KQL query</param>
+    /// <returns>The tree's nodes and tokens, root first</returns>
+    public static SyntaxTreeResult GetSyntaxTree(string query)
+    {
+        var code = KustoCode.Parse(query);
+        var nodes = new List<SyntaxNodeResult>();
+        var pending = new Stack<(SyntaxElement Element, int Parent)>();
+        pending.Push((code.Syntax, -1));
+
+        while (pending.Count > 0)
+        {
+            var (element, parent) = pending.Pop();
+            if (parent >= 0 && element.Width == 0)
+                continue;
+
+            var index = nodes.Count;
+            nodes.Add(new SyntaxNodeResult
+            {
+                Kind = element.Kind.ToString(),
+                Start = element.TextStart,
+                End = element.End,
+                Text = element is SyntaxToken token ? token.Text : null,
+                Parent = parent >= 0 ? parent : null
+            });
+
+            // Push children last to first so they come off in source order
+            for (int i = element.ChildCount - 1; i >= 0; i--)
+            {
+                if (element.GetChild(i) is SyntaxElement child)
+                    pending.Push((child, index));
+            }
+        }
+
+        return new SyntaxTreeResult { Nodes = nodes };
+    }
+}
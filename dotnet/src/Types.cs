@@ -68,6 +68,96 @@ public class Diagnostic
     [JsonPropertyName("code")]
     [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
     public string? Code { get; set; }
+
+    /// <summary>
+    /// Category: "Correctness", "Performance", "Security", "ServiceLimit", "Analyzer", "General".
+    /// </summary>
+    [JsonPropertyName("category")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public string? Category { get; set; }
+
+    /// <summary>
+    /// Quick fixes that resolve this diagnostic.
+    /// </summary>
+    [JsonPropertyName("fixes")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public List<FixDefinition>? Fixes { get; set; }
+
+    /// <summary>
+    /// Secondary locations relevant to this diagnostic.
+    /// </summary>
+    [JsonPropertyName("related_spans")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public List<RelatedInfoDefinition>? RelatedSpans { get; set; }
+}
+
+/// <summary>
+/// A quick fix: one or more non-overlapping edits.
+/// Matches the Rust Fix struct.
+/// </summary>
+public class FixDefinition
+{
+    /// <summary>
+    /// Short description of the fix.
+    /// </summary>
+    [JsonPropertyName("title")]
+    public string Title { get; set; } = "";
+
+    /// <summary>
+    /// Edits to apply.
+    /// </summary>
+    [JsonPropertyName("edits")]
+    public List<TextEditDefinition> Edits { get; set; } = new();
+}
+
+/// <summary>
+/// A secondary location attached to a diagnostic.
+/// Matches the Rust RelatedInfo struct.
+/// </summary>
+public class RelatedInfoDefinition
+{
+    /// <summary>
+    /// What the location shows.
+    /// </summary>
+    [JsonPropertyName("message")]
+    public string Message { get; set; } = "";
+
+    /// <summary>
+    /// Start offset (0-based character position).
+    /// </summary>
+    [JsonPropertyName("start")]
+    public int Start { get; set; }
+
+    /// <summary>
+    /// End offset (0-based character position).
+    /// </summary>
+    [JsonPropertyName("end")]
+    public int End { get; set; }
+}
+
+/// <summary>
+/// Replacement of a span of the query text.
+/// Matches the Rust TextEdit struct.
+/// </summary>
+public class TextEditDefinition
+{
+    /// <summary>
+    /// Start offset of the replaced span (0-based character position).
+    /// </summary>
+    [JsonPropertyName("start")]
+    public int Start { get; set; }
+
+    /// <summary>
+    /// End offset of the replaced span (0-based character position).
+    /// </summary>
+    [JsonPropertyName("end")]
+    public int End { get; set; }
+
+    /// <summary>
+    /// Replacement text.
+    /// </summary>
+    [JsonPropertyName("new_text")]
+    public string NewText { get; set; } = "";
 }
 
 /// <summary>
@@ -93,6 +183,217 @@ public class SchemaDefinition
     /// </summary>
     [JsonPropertyName("functions")]
     public List<FunctionDefinition>? Functions { get; set; }
+
+    /// <summary>
+    /// Cross-resource scopes (workspace(), app(), resource(), adx()).
+    /// </summary>
+    [JsonPropertyName("scopes")]
+    public List<ScopeDefinition>? Scopes { get; set; }
+
+    /// <summary>
+    /// Ambient query parameters queries may reference without declaring.
+    /// </summary>
+    [JsonPropertyName("query_parameters")]
+    public List<ParameterDefinition>? QueryParameters { get; set; }
+}
+
+/// <summary>
+/// Tables reachable through a Log Analytics cross-resource scoping function.
+/// </summary>
+public class ScopeDefinition
+{
+    /// <summary>
+    /// Scope kind: "Workspace", "App", "Resource", "Adx".
+    /// </summary>
+    [JsonPropertyName("kind")]
+    public string Kind { get; set; } = "Workspace";
+
+    /// <summary>
+    /// Name, ID, or URL used as the function argument.
+    /// </summary>
+    [JsonPropertyName("name")]
+    public string Name { get; set; } = "";
+
+    /// <summary>
+    /// Tables in the scope.
+    /// </summary>
+    [JsonPropertyName("tables")]
+    public List<TableDefinition>? Tables { get; set; }
+}
+
+/// <summary>
+/// Analysis options passed alongside a query.
+/// Matches the Rust ValidationOptions struct.
+/// </summary>
+public class ValidationOptions
+{
+    /// <summary>
+    /// Dialect: "Kusto", "LogAnalytics", "DataCollectionRule", or "ResourceGraph".
+    /// </summary>
+    [JsonPropertyName("dialect")]
+    public string Dialect { get; set; } = "Kusto";
+
+    /// <summary>
+    /// Kind of text expected: "Query" or "Command".
+    /// </summary>
+    [JsonPropertyName("kind")]
+    public string Kind { get; set; } = "Query";
+
+    /// <summary>
+    /// Feature profiles the query must satisfy (all of them).
+    /// </summary>
+    [JsonPropertyName("profiles")]
+    public List<FeatureProfileDefinition>? Profiles { get; set; }
+
+    /// <summary>
+    /// Engine feature level the query must run on.
+    /// </summary>
+    [JsonPropertyName("engine")]
+    public EnginePinDefinition? Engine { get; set; }
+
+    /// <summary>
+    /// Service limits to check.
+    /// </summary>
+    [JsonPropertyName("limits")]
+    public ServiceLimitsDefinition? Limits { get; set; }
+
+    /// <summary>
+    /// Ambient query parameters, in addition to the schema's.
+    /// </summary>
+    [JsonPropertyName("query_parameters")]
+    public List<ParameterDefinition>? QueryParameters { get; set; }
+
+    /// <summary>
+    /// Suggest case-sensitive string operators for exact-case literals.
+    /// </summary>
+    [JsonPropertyName("case_sensitivity_advisory")]
+    public bool CaseSensitivityAdvisory { get; set; }
+
+    /// <summary>
+    /// Id from kql_cancellation_create whose token validation observes.
+    /// </summary>
+    [JsonPropertyName("cancellation_id")]
+    public int? CancellationId { get; set; }
+}
+
+/// <summary>
+/// Service limits checked during validation.
+/// Matches the Rust ServiceLimits struct.
+/// </summary>
+public class ServiceLimitsDefinition
+{
+    /// <summary>
+    /// Maximum query text length in characters.
+    /// </summary>
+    [JsonPropertyName("max_query_length")]
+    public int MaxQueryLength { get; set; } = 2 * 1024 * 1024;
+
+    /// <summary>
+    /// Maximum length of a table, column, function, or variable name.
+    /// </summary>
+    [JsonPropertyName("max_identifier_length")]
+    public int MaxIdentifierLength { get; set; } = 1024;
+
+    /// <summary>
+    /// Maximum number of by keys in a single summarize.
+    /// </summary>
+    [JsonPropertyName("max_summarize_keys")]
+    public int MaxSummarizeKeys { get; set; } = 64;
+
+    /// <summary>
+    /// Maximum number of values in a literal in / !in list.
+    /// </summary>
+    [JsonPropertyName("max_in_list_values")]
+    public int MaxInListValues { get; set; } = 1_000_000;
+}
+
+/// <summary>
+/// Engine feature-level pin.
+/// Matches the Rust EnginePin struct.
+/// </summary>
+public class EnginePinDefinition
+{
+    /// <summary>
+    /// Pinned feature level ("YYYY-MM").
+    /// </summary>
+    [JsonPropertyName("level")]
+    public string Level { get; set; } = "";
+
+    /// <summary>
+    /// Features introduced after the pinned level.
+    /// </summary>
+    [JsonPropertyName("unavailable")]
+    public List<FeatureAvailabilityDefinition>? Unavailable { get; set; }
+}
+
+/// <summary>
+/// When an engine feature became available.
+/// Matches the Rust FeatureAvailability struct.
+/// </summary>
+public class FeatureAvailabilityDefinition
+{
+    /// <summary>
+    /// Feature name (lowercase).
+    /// </summary>
+    [JsonPropertyName("name")]
+    public string Name { get; set; } = "";
+
+    /// <summary>
+    /// Kind: "Function", "Plugin", or "Operator".
+    /// </summary>
+    [JsonPropertyName("kind")]
+    public string Kind { get; set; } = "";
+
+    /// <summary>
+    /// First feature level the feature is available in ("YYYY-MM").
+    /// </summary>
+    [JsonPropertyName("introduced")]
+    public string Introduced { get; set; } = "";
+
+    /// <summary>
+    /// Whether the introduced date is known to be accurate.
+    /// Unverified entries are reported as warnings.
+    /// </summary>
+    [JsonPropertyName("verified")]
+    public bool Verified { get; set; } = true;
+}
+
+/// <summary>
+/// A restricted subset of KQL features.
+/// Null allow-lists leave that feature unrestricted.
+/// Matches the Rust FeatureProfile struct.
+/// </summary>
+public class FeatureProfileDefinition
+{
+    /// <summary>
+    /// Profile name, used in diagnostic messages.
+    /// </summary>
+    [JsonPropertyName("name")]
+    public string Name { get; set; } = "";
+
+    /// <summary>
+    /// Allowed tabular operators (lowercase keywords).
+    /// </summary>
+    [JsonPropertyName("operators")]
+    public HashSet<string>? Operators { get; set; }
+
+    /// <summary>
+    /// Allowed evaluate plugins (lowercase names).
+    /// </summary>
+    [JsonPropertyName("plugins")]
+    public HashSet<string>? Plugins { get; set; }
+
+    /// <summary>
+    /// Allowed functions (lowercase names).
+    /// </summary>
+    [JsonPropertyName("functions")]
+    public HashSet<string>? Functions { get; set; }
+
+    /// <summary>
+    /// Maximum number of piped operators in a single pipeline.
+    /// </summary>
+    [JsonPropertyName("max_pipeline_depth")]
+    public int? MaxPipelineDepth { get; set; }
 }
 
 /// <summary>
@@ -143,6 +444,18 @@ public class ColumnDefinition
     [JsonPropertyName("description")]
     [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
     public string? Description { get; set; }
+
+    /// <summary>
+    /// JSON paths observed in sample values ("user.name", "roles[].id").
+    /// </summary>
+    [JsonPropertyName("sample_paths")]
+    public HashSet<string>? SamplePaths { get; set; }
+
+    /// <summary>
+    /// Declared fields of a dynamic column, by JSON path ("location.city") to data type.
+    /// </summary>
+    [JsonPropertyName("fields")]
+    public Dictionary<string, string>? Fields { get; set; }
 }
 
 /// <summary>
@@ -168,6 +481,12 @@ public class FunctionDefinition
     [JsonPropertyName("return_type")]
     public string ReturnType { get; set; } = "dynamic";
 
+    /// <summary>
+    /// Kind: "scalar" or "tabular".
+    /// </summary>
+    [JsonPropertyName("kind")]
+    public string Kind { get; set; } = "scalar";
+
     /// <summary>
     /// Optional function body.
     /// </summary>
@@ -181,6 +500,13 @@ public class FunctionDefinition
     [JsonPropertyName("description")]
     [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
     public string? Description { get; set; }
+
+    /// <summary>
+    /// Optional folder.
+    /// </summary>
+    [JsonPropertyName("folder")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public string? Folder { get; set; }
 }
 
 /// <summary>
@@ -252,6 +578,55 @@ public class ClassifiedSpan
 // Completion Types (Phase 2)
 // ============================================================================
 
+/// <summary>
+/// Per-call completion filtering options.
+/// Matches the Rust CompletionOptions struct.
+/// </summary>
+public class CompletionOptions
+{
+    /// <summary>
+    /// Include punctuation items.
+    /// </summary>
+    [JsonPropertyName("include_punctuation")]
+    public bool IncludePunctuation { get; set; } = true;
+
+    /// <summary>
+    /// Include keyword items.
+    /// </summary>
+    [JsonPropertyName("include_keywords")]
+    public bool IncludeKeywords { get; set; } = true;
+
+    /// <summary>
+    /// Only include symbols from the schema.
+    /// </summary>
+    [JsonPropertyName("only_schema_symbols")]
+    public bool OnlySchemaSymbols { get; set; }
+
+    /// <summary>
+    /// Only include items of these kinds ("Column", "Function", ...); all if null or empty.
+    /// </summary>
+    [JsonPropertyName("kinds")]
+    public List<string>? Kinds { get; set; }
+
+    /// <summary>
+    /// Match items against the partially typed word at the cursor: "Prefix" or "Fuzzy".
+    /// </summary>
+    [JsonPropertyName("matching")]
+    public string? Matching { get; set; }
+
+    /// <summary>
+    /// Rank items by match quality and kind.
+    /// </summary>
+    [JsonPropertyName("ranked")]
+    public bool Ranked { get; set; }
+
+    /// <summary>
+    /// Maximum number of items to return.
+    /// </summary>
+    [JsonPropertyName("max_items")]
+    public int? MaxItems { get; set; }
+}
+
 /// <summary>
 /// Result of completion request.
 /// </summary>
@@ -262,6 +637,46 @@ public class CompletionResult
     /// </summary>
     [JsonPropertyName("items")]
     public List<CompletionItemResponse> Items { get; set; } = new();
+
+    /// <summary>
+    /// Where the list was asked for and the text the items replace.
+    /// </summary>
+    [JsonPropertyName("context")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public CompletionContextResult? Context { get; set; }
+
+    /// <summary>
+    /// Scalar type expected at the cursor, such as the left-hand side's type
+    /// in a comparison.
+    /// </summary>
+    [JsonPropertyName("expected_type")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public string? ExpectedType { get; set; }
+}
+
+/// <summary>
+/// Where a completion list was asked for.
+/// Matches the Rust CompletionContext struct.
+/// </summary>
+public class CompletionContextResult
+{
+    /// <summary>
+    /// What precedes the cursor: AfterPipe, AfterDot, FunctionArguments, ByClause or Other.
+    /// </summary>
+    [JsonPropertyName("trigger")]
+    public string Trigger { get; set; } = "Other";
+
+    /// <summary>
+    /// Character position where the replaced text starts.
+    /// </summary>
+    [JsonPropertyName("edit_start")]
+    public int EditStart { get; set; }
+
+    /// <summary>
+    /// Character position where the replaced text ends (exclusive).
+    /// </summary>
+    [JsonPropertyName("edit_end")]
+    public int EditEnd { get; set; }
 }
 
 /// <summary>
@@ -306,4 +721,550 @@ public class CompletionItemResponse
     /// </summary>
     [JsonPropertyName("edit_start")]
     public int EditStart { get; set; }
+
+    /// <summary>
+    /// Markdown documentation, filled in when the item is resolved.
+    /// </summary>
+    [JsonPropertyName("documentation")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public string? Documentation { get; set; }
+
+    /// <summary>
+    /// Whether the item's type is the type expected at the cursor.
+    /// </summary>
+    [JsonPropertyName("matches_expected_type")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingDefault)]
+    public bool MatchesExpectedType { get; set; }
+}
+
+// ============================================================================
+// Document Structure Types
+// ============================================================================
+
+/// <summary>
+/// Structure of a KQL document.
+/// Matches the Rust DocumentStructure struct.
+/// </summary>
+public class DocumentStructureResult
+{
+    /// <summary>
+    /// Top-level statements and comment blocks, in source order.
+    /// </summary>
+    [JsonPropertyName("statements")]
+    public List<StructureNodeResponse> Statements { get; set; } = new();
+}
+
+/// <summary>
+/// A node in the document structure tree.
+/// Matches the Rust StructureNode struct.
+/// </summary>
+public class StructureNodeResponse
+{
+    /// <summary>
+    /// Kind: "LetStatement", "QueryStatement", "FunctionDeclaration", "FunctionBody", etc.
+    /// </summary>
+    [JsonPropertyName("kind")]
+    public string Kind { get; set; } = "";
+
+    /// <summary>
+    /// Declared name (declarations only).
+    /// </summary>
+    [JsonPropertyName("name")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public string? Name { get; set; }
+
+    /// <summary>
+    /// Start offset (0-based character position).
+    /// </summary>
+    [JsonPropertyName("start")]
+    public int Start { get; set; }
+
+    /// <summary>
+    /// End offset (0-based character position).
+    /// </summary>
+    [JsonPropertyName("end")]
+    public int End { get; set; }
+
+    /// <summary>
+    /// Line of the start offset (1-based).
+    /// </summary>
+    [JsonPropertyName("start_line")]
+    public int StartLine { get; set; }
+
+    /// <summary>
+    /// Line of the end offset (1-based).
+    /// </summary>
+    [JsonPropertyName("end_line")]
+    public int EndLine { get; set; }
+
+    /// <summary>
+    /// Nested nodes, in source order.
+    /// </summary>
+    [JsonPropertyName("children")]
+    public List<StructureNodeResponse>? Children { get; set; }
+}
+
+/// <summary>
+/// Options for formatting a query.
+/// Matches the Rust FormatOptions struct.
+/// </summary>
+public class FormatOptions
+{
+    /// <summary>
+    /// Number of spaces per indentation level.
+    /// </summary>
+    [JsonPropertyName("indent_size")]
+    public int IndentSize { get; set; } = 4;
+
+    /// <summary>
+    /// Pipe placement: "NewLine", "Smart", or "Preserve".
+    /// </summary>
+    [JsonPropertyName("pipe_style")]
+    public string PipeStyle { get; set; } = "NewLine";
+
+    /// <summary>
+    /// Operator casing: "Lowercase" or "Preserve".
+    /// </summary>
+    [JsonPropertyName("operator_case")]
+    public string OperatorCase { get; set; } = "Lowercase";
+}
+
+/// <summary>
+/// Entities referenced by a query.
+/// Matches the Rust QueryAnalysis struct.
+/// </summary>
+public class QueryAnalysisResult
+{
+    /// <summary>
+    /// Schema tables referenced.
+    /// </summary>
+    [JsonPropertyName("tables")]
+    public List<string> Tables { get; set; } = new();
+
+    /// <summary>
+    /// Schema columns referenced, keyed by table name.
+    /// </summary>
+    [JsonPropertyName("columns")]
+    public Dictionary<string, List<string>> Columns { get; set; } = new();
+
+    /// <summary>
+    /// Schema functions referenced.
+    /// </summary>
+    [JsonPropertyName("functions")]
+    public List<string> Functions { get; set; } = new();
+
+    /// <summary>
+    /// Clusters named by cluster() calls.
+    /// </summary>
+    [JsonPropertyName("clusters")]
+    public List<string> Clusters { get; set; } = new();
+
+    /// <summary>
+    /// Databases named by database() calls.
+    /// </summary>
+    [JsonPropertyName("databases")]
+    public List<string> Databases { get; set; } = new();
+}
+
+/// <summary>
+/// Declaration of a locally declared name.
+/// Matches the Rust Definition struct.
+/// </summary>
+public class DefinitionResult
+{
+    /// <summary>
+    /// Declared name.
+    /// </summary>
+    [JsonPropertyName("name")]
+    public string Name { get; set; } = "";
+
+    /// <summary>
+    /// Kind: "Variable", "Function", or "Parameter".
+    /// </summary>
+    [JsonPropertyName("kind")]
+    public string Kind { get; set; } = "Other";
+
+    /// <summary>
+    /// Start offset of the declared name.
+    /// </summary>
+    [JsonPropertyName("start")]
+    public int Start { get; set; }
+
+    /// <summary>
+    /// End offset of the declared name.
+    /// </summary>
+    [JsonPropertyName("end")]
+    public int End { get; set; }
+}
+
+/// <summary>
+/// A symbol in the document outline.
+/// Matches the Rust DocumentSymbol struct.
+/// </summary>
+public class DocumentSymbolResponse
+{
+    /// <summary>
+    /// Symbol name: declared name, query source, or operator keyword.
+    /// </summary>
+    [JsonPropertyName("name")]
+    public string Name { get; set; } = "";
+
+    /// <summary>
+    /// Kind: "Variable", "Function", "Parameter", "Query", or "Operator".
+    /// </summary>
+    [JsonPropertyName("kind")]
+    public string Kind { get; set; } = "";
+
+    /// <summary>
+    /// Start offset of the whole symbol (0-based character position).
+    /// </summary>
+    [JsonPropertyName("start")]
+    public int Start { get; set; }
+
+    /// <summary>
+    /// End offset of the whole symbol (0-based character position).
+    /// </summary>
+    [JsonPropertyName("end")]
+    public int End { get; set; }
+
+    /// <summary>
+    /// Line of the start offset (1-based).
+    /// </summary>
+    [JsonPropertyName("start_line")]
+    public int StartLine { get; set; }
+
+    /// <summary>
+    /// Line of the end offset (1-based).
+    /// </summary>
+    [JsonPropertyName("end_line")]
+    public int EndLine { get; set; }
+
+    /// <summary>
+    /// Start offset of the symbol's name.
+    /// </summary>
+    [JsonPropertyName("selection_start")]
+    public int SelectionStart { get; set; }
+
+    /// <summary>
+    /// End offset of the symbol's name.
+    /// </summary>
+    [JsonPropertyName("selection_end")]
+    public int SelectionEnd { get; set; }
+
+    /// <summary>
+    /// Nested symbols, in source order.
+    /// </summary>
+    [JsonPropertyName("children")]
+    public List<DocumentSymbolResponse> Children { get; set; } = new();
+}
+
+/// <summary>
+/// A matching pair of brackets.
+/// Matches the Rust BracketPair struct.
+/// </summary>
+public class BracketPairResult
+{
+    /// <summary>
+    /// Offset of the opening bracket.
+    /// </summary>
+    [JsonPropertyName("open")]
+    public int Open { get; set; }
+
+    /// <summary>
+    /// Offset of the closing bracket (-1 while unmatched).
+    /// </summary>
+    [JsonPropertyName("close")]
+    public int Close { get; set; } = -1;
+
+    /// <summary>
+    /// Nesting depth, 0 for brackets not inside another pair.
+    /// </summary>
+    [JsonPropertyName("depth")]
+    public int Depth { get; set; }
+}
+
+/// <summary>
+/// A syntax range enclosing a position.
+/// Matches the Rust EnclosingRange struct.
+/// </summary>
+public class EnclosingRangeResult
+{
+    /// <summary>
+    /// Start offset of the range.
+    /// </summary>
+    [JsonPropertyName("start")]
+    public int Start { get; set; }
+
+    /// <summary>
+    /// End offset of the range.
+    /// </summary>
+    [JsonPropertyName("end")]
+    public int End { get; set; }
+
+    /// <summary>
+    /// Kind: "Token", "Expression", "Stage", "Pipeline", "Statement", or "Query".
+    /// </summary>
+    [JsonPropertyName("kind")]
+    public string Kind { get; set; } = "";
+}
+
+/// <summary>
+/// Syntax tree of a query, as nodes in pre-order.
+/// </summary>
+public class SyntaxTreeResult
+{
+    /// <summary>
+    /// Nodes and tokens, root first.
+    /// </summary>
+    [JsonPropertyName("nodes")]
+    public List<SyntaxNodeResult> Nodes { get; set; } = new();
+}
+
+/// <summary>
+/// A node or token of a syntax tree.
+/// Matches the Rust SyntaxNode struct, with the parent's index instead of children.
+/// </summary>
+public class SyntaxNodeResult
+{
+    /// <summary>
+    /// SyntaxKind name, such as "SummarizeOperator" or "IdentifierToken".
+    /// </summary>
+    [JsonPropertyName("kind")]
+    public string Kind { get; set; } = "";
+
+    /// <summary>
+    /// Start offset of the element.
+    /// </summary>
+    [JsonPropertyName("start")]
+    public int Start { get; set; }
+
+    /// <summary>
+    /// End offset of the element.
+    /// </summary>
+    [JsonPropertyName("end")]
+    public int End { get; set; }
+
+    /// <summary>
+    /// Text of a token (tokens only).
+    /// </summary>
+    [JsonPropertyName("text")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public string? Text { get; set; }
+
+    /// <summary>
+    /// Index of the parent node (all but the root).
+    /// </summary>
+    [JsonPropertyName("parent")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public int? Parent { get; set; }
+}
+
+/// <summary>
+/// The stages of a query's pipeline.
+/// Matches the Rust Pipeline struct.
+/// </summary>
+public class PipelineResult
+{
+    /// <summary>
+    /// The source, then each query operator, in source order.
+    /// </summary>
+    [JsonPropertyName("stages")]
+    public List<PipelineStageResult> Stages { get; set; } = new();
+}
+
+/// <summary>
+/// A stage of a pipeline.
+/// Matches the Rust PipelineStage struct.
+/// </summary>
+public class PipelineStageResult
+{
+    /// <summary>
+    /// SyntaxKind name of the operator, or of the source expression.
+    /// </summary>
+    [JsonPropertyName("kind")]
+    public string Kind { get; set; } = "";
+
+    /// <summary>
+    /// Source text of the stage.
+    /// </summary>
+    [JsonPropertyName("text")]
+    public string Text { get; set; } = "";
+
+    /// <summary>
+    /// Start offset of the stage.
+    /// </summary>
+    [JsonPropertyName("start")]
+    public int Start { get; set; }
+
+    /// <summary>
+    /// End offset of the stage.
+    /// </summary>
+    [JsonPropertyName("end")]
+    public int End { get; set; }
+
+    /// <summary>
+    /// Columns after the stage (tabular stages only).
+    /// </summary>
+    [JsonPropertyName("output")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public TableDefinition? Output { get; set; }
+}
+
+/// <summary>
+/// The joins, lookups and unions of a query.
+/// Matches the Rust JoinAnalysis struct.
+/// </summary>
+public class JoinAnalysisResult
+{
+    /// <summary>
+    /// Operations in source order.
+    /// </summary>
+    [JsonPropertyName("operations")]
+    public List<JoinOperationResult> Operations { get; set; } = new();
+
+    /// <summary>
+    /// Key warnings, ordered by position.
+    /// </summary>
+    [JsonPropertyName("diagnostics")]
+    public List<Diagnostic> Diagnostics { get; set; } = new();
+}
+
+/// <summary>
+/// A join, lookup or union.
+/// Matches the Rust JoinOperation struct.
+/// </summary>
+public class JoinOperationResult
+{
+    /// <summary>
+    /// Operator name: Join, Lookup or Union.
+    /// </summary>
+    [JsonPropertyName("operator")]
+    public string Operator { get; set; } = "";
+
+    /// <summary>
+    /// The kind= of the operation, or its default.
+    /// </summary>
+    [JsonPropertyName("kind")]
+    public string Kind { get; set; } = "";
+
+    /// <summary>
+    /// Source text of each input, the piped-in input first.
+    /// </summary>
+    [JsonPropertyName("sources")]
+    public List<string> Sources { get; set; } = new();
+
+    /// <summary>
+    /// Keys of a join or lookup.
+    /// </summary>
+    [JsonPropertyName("keys")]
+    public List<JoinKeyResult> Keys { get; set; } = new();
+
+    /// <summary>
+    /// Start offset of the operator.
+    /// </summary>
+    [JsonPropertyName("start")]
+    public int Start { get; set; }
+
+    /// <summary>
+    /// End offset of the operator.
+    /// </summary>
+    [JsonPropertyName("end")]
+    public int End { get; set; }
+}
+
+/// <summary>
+/// A key of a join or lookup.
+/// Matches the Rust JoinKey struct.
+/// </summary>
+public class JoinKeyResult
+{
+    /// <summary>
+    /// Column of the left input.
+    /// </summary>
+    [JsonPropertyName("left")]
+    public string Left { get; set; } = "";
+
+    /// <summary>
+    /// Column of the right input.
+    /// </summary>
+    [JsonPropertyName("right")]
+    public string Right { get; set; } = "";
+
+    /// <summary>
+    /// Type of the left column, if it resolves.
+    /// </summary>
+    [JsonPropertyName("left_type")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public string? LeftType { get; set; }
+
+    /// <summary>
+    /// Type of the right column, if it resolves.
+    /// </summary>
+    [JsonPropertyName("right_type")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public string? RightType { get; set; }
+}
+
+/// <summary>
+/// A hint shown inline at a position in the query.
+/// Matches the Rust InlayHint struct.
+/// </summary>
+public class InlayHintResult
+{
+    /// <summary>
+    /// Offset the hint is shown at.
+    /// </summary>
+    [JsonPropertyName("position")]
+    public int Position { get; set; }
+
+    /// <summary>
+    /// Text to show, such as ": long", "delimiter:" or "5 columns".
+    /// </summary>
+    [JsonPropertyName("label")]
+    public string Label { get; set; } = "";
+
+    /// <summary>
+    /// Kind: "Type", "Parameter", or "ColumnCount".
+    /// </summary>
+    [JsonPropertyName("kind")]
+    public string Kind { get; set; } = "";
+}
+
+/// <summary>
+/// Inferred type of the expression at a position.
+/// Matches the Rust ExpressionType struct.
+/// </summary>
+public class ExpressionTypeResult
+{
+    /// <summary>
+    /// Start offset of the expression.
+    /// </summary>
+    [JsonPropertyName("start")]
+    public int Start { get; set; }
+
+    /// <summary>
+    /// End offset of the expression.
+    /// </summary>
+    [JsonPropertyName("end")]
+    public int End { get; set; }
+
+    /// <summary>
+    /// Kind: "Scalar", "Tabular", or another symbol kind.
+    /// </summary>
+    [JsonPropertyName("kind")]
+    public string Kind { get; set; } = "";
+
+    /// <summary>
+    /// Scalar type name (scalar only).
+    /// </summary>
+    [JsonPropertyName("name")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public string? Name { get; set; }
+
+    /// <summary>
+    /// Columns of the table (tabular only).
+    /// </summary>
+    [JsonPropertyName("columns")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public List<ColumnDefinition>? Columns { get; set; }
 }
@@ -65,9 +65,12 @@ public static class ValidationService
             var code = KustoCode.ParseAndAnalyze(query, globals);
 
             // Get all diagnostics (syntax + semantic)
-            var diagnostics = code.GetDiagnostics();
+            var diagnostics = code.GetDiagnostics().ToList();
 
-            return CreateResult(query, diagnostics);
+            // Check dynamic property accesses against sample event shapes
+            diagnostics = DynamicSampleService.Apply(code, schema, diagnostics);
+
+            return CreateResult(query, diagnostics, related: RelatedInfoService.Collect(code, diagnostics));
         }
         catch (Exception ex)
         {
@@ -91,39 +94,254 @@ public static class ValidationService
     }
 
     /// <summary>
-    /// Build a GlobalState from a schema definition.
+    /// Validate a KQL query using analysis options and an optional schema.
+    /// Without a schema only syntax (and dialect-specific syntax) checks are run.
     /// </summary>
-    public static GlobalState BuildGlobalState(SchemaDefinition schema)
+    /// <param name="query">The KQL query to validate</param>
+    /// <param name="schema">Optional schema definition</param>
+    /// <param name="options">Analysis options (dialect, ...)</param>
+    /// <returns>Validation result with any diagnostics found</returns>
+    public static ValidationResult Validate(string query, SchemaDefinition? schema, ValidationOptions options)
     {
-        var tableSymbols = new List<TableSymbol>();
+        GlobalState? globals;
+        try
+        {
+            globals = schema != null ? BuildGlobalState(schema, options) : null;
+        }
+        catch (Exception ex)
+        {
+            return ExceptionResult(ex);
+        }
 
-        foreach (var table in schema.Tables ?? Enumerable.Empty<TableDefinition>())
+        return Validate(query, schema, globals, options);
+    }
+
+    /// <summary>
+    /// Validate a batch of KQL queries against the same schema and options.
+    /// The GlobalState is built once and shared by every query in the batch.
+    /// </summary>
+    /// <param name="queries">The KQL queries to validate</param>
+    /// <param name="schema">Optional schema definition</param>
+    /// <param name="options">Analysis options (dialect, ...)</param>
+    /// <returns>One validation result per query, in input order</returns>
+    public static List<ValidationResult> ValidateBatch(
+        IReadOnlyList<string> queries,
+        SchemaDefinition? schema,
+        ValidationOptions options)
+    {
+        GlobalState? globals;
+        try
         {
-            // Build column definition string: "(col1: type1, col2: type2, ...)"
-            var columnDefs = string.Join(", ",
-                (table.Columns ?? Enumerable.Empty<ColumnDefinition>())
-                    .Select(c => $"{c.Name}: {MapDataType(c.DataType)}"));
+            globals = schema != null ? BuildGlobalState(schema, options) : null;
+        }
+        catch (Exception ex)
+        {
+            var failure = ExceptionResult(ex);
+            return queries.Select(_ => failure).ToList();
+        }
 
-            var tableSymbol = new TableSymbol(table.Name, $"({columnDefs})");
-            tableSymbols.Add(tableSymbol);
+        var token = CancellationRegistry.Token(options.CancellationId);
+        return queries.Select(query =>
+        {
+            token.ThrowIfCancellationRequested();
+            return Validate(query, schema, globals, options);
+        }).ToList();
+    }
+
+    /// <summary>
+    /// Validate a KQL query against a registered schema, reusing its cached GlobalState.
+    /// </summary>
+    /// <param name="query">The KQL query to validate</param>
+    /// <param name="schema">The registered schema</param>
+    /// <param name="options">Analysis options (dialect, ...)</param>
+    /// <returns>Validation result with any diagnostics found</returns>
+    public static ValidationResult Validate(string query, RegisteredSchema schema, ValidationOptions options)
+    {
+        GlobalState globals;
+        try
+        {
+            globals = schema.GetGlobals(options);
+        }
+        catch (Exception ex)
+        {
+            return ExceptionResult(ex);
+        }
+
+        return Validate(query, schema.Definition, globals, options);
+    }
+
+    private static ValidationResult Validate(
+        string query,
+        SchemaDefinition? schema,
+        GlobalState? globals,
+        ValidationOptions options)
+    {
+        try
+        {
+            var token = CancellationRegistry.Token(options.CancellationId);
+            var code = globals != null
+                ? KustoCode.ParseAndAnalyze(query, globals, token)
+                : KustoCode.Parse(query);
+            token.ThrowIfCancellationRequested();
+
+            return Validate(code, schema, globals != null, options);
+        }
+        catch (OperationCanceledException)
+        {
+            throw;
+        }
+        catch (Exception ex)
+        {
+            return ExceptionResult(ex);
+        }
+    }
+
+    /// <summary>
+    /// Run the diagnostic pipeline (commands, dialects, profiles, limits, advisories)
+    /// over parsed code.
+    /// </summary>
+    /// <param name="code">Parsed, and analyzed if <paramref name="bound"/>, code</param>
+    /// <param name="schema">Optional schema definition</param>
+    /// <param name="bound">Whether the code was analyzed against a schema</param>
+    /// <param name="options">Analysis options (dialect, ...)</param>
+    /// <returns>Validation result with offsets relative to the code's text</returns>
+    internal static ValidationResult Validate(
+        KustoCode code,
+        SchemaDefinition? schema,
+        bool bound,
+        ValidationOptions options)
+    {
+        try
+        {
+            var token = CancellationRegistry.Token(options.CancellationId);
+            var diagnostics = code.GetDiagnostics(cancellationToken: token).ToList();
+            token.ThrowIfCancellationRequested();
+
+            if (options.Kind == "Command")
+            {
+                diagnostics = CommandService.Apply(code, diagnostics);
+            }
+
+            if (schema != null)
+            {
+                diagnostics = DynamicSampleService.Apply(code, schema, diagnostics);
+            }
+
+            diagnostics = options.Dialect switch
+            {
+                "LogAnalytics" => LogAnalyticsDialect.Apply(code, schema, diagnostics),
+                "DataCollectionRule" => DataCollectionRuleDialect.Apply(code, diagnostics),
+                "ResourceGraph" => ResourceGraphDialect.Apply(code, diagnostics),
+                _ => diagnostics
+            };
+
+            foreach (var profile in options.Profiles ?? Enumerable.Empty<FeatureProfileDefinition>())
+            {
+                diagnostics = FeatureProfileService.Apply(code, profile, diagnostics);
+            }
+
+            if (options.Engine != null)
+            {
+                diagnostics = EngineFeatureService.Apply(code, options.Engine, diagnostics);
+            }
+
+            if (options.Limits != null)
+            {
+                diagnostics = ServiceLimitService.Apply(code, options.Limits, diagnostics);
+            }
+
+            var fixes = new Dictionary<Kusto.Language.Diagnostic, List<FixDefinition>>(ReferenceEqualityComparer.Instance);
+            if (options.CaseSensitivityAdvisory)
+            {
+                diagnostics = CaseSensitivityAdvisory.Apply(code, diagnostics, fixes);
+            }
+
+            var related = bound
+                ? RelatedInfoService.Collect(code, diagnostics)
+                : null;
+
+            return CreateResult(code.Text, diagnostics, fixes, related);
+        }
+        catch (OperationCanceledException)
+        {
+            throw;
+        }
+        catch (Exception ex)
+        {
+            return ExceptionResult(ex);
         }
+    }
+
+    /// <summary>
+    /// Report an exception thrown during validation as a diagnostic.
+    /// </summary>
+    internal static ValidationResult ExceptionResult(Exception ex)
+    {
+        return new ValidationResult
+        {
+            Valid = false,
+            Diagnostics = new List<Diagnostic>
+            {
+                new Diagnostic
+                {
+                    Message = $"Parser exception: {ex.Message}",
+                    Severity = "Error",
+                    Start = 0,
+                    End = 0,
+                    Line = 1,
+                    Column = 1
+                }
+            }
+        };
+    }
+
+    /// <summary>
+    /// Build a GlobalState from a schema definition, applying dialect-specific symbols.
+    /// </summary>
+    public static GlobalState BuildGlobalState(SchemaDefinition schema, ValidationOptions options)
+    {
+        var globals = options.Dialect switch
+        {
+            "LogAnalytics" => BuildGlobalState(schema, LogAnalyticsDialect.GetScopeFunctions(schema)),
+            "ResourceGraph" => BuildGlobalState(schema, ResourceGraphDialect.GetTableAliases(schema)),
+            _ => BuildGlobalState(schema)
+        };
+
+        if (options.QueryParameters is not { Count: > 0 })
+            return globals;
+
+        // Option parameters override schema parameters of the same name
+        var names = options.QueryParameters.Select(p => p.Name).ToHashSet(StringComparer.OrdinalIgnoreCase);
+        return globals.WithParameters(globals.Parameters
+            .Where(p => !names.Contains(p.Name))
+            .Concat(BuildQueryParameters(options.QueryParameters))
+            .ToList());
+    }
+
+    /// <summary>
+    /// Build a GlobalState from a schema definition.
+    /// Schemas defining cross-resource scopes get the scoping functions, so
+    /// workspace('name').Table resolves and completes under any dialect.
+    /// </summary>
+    public static GlobalState BuildGlobalState(SchemaDefinition schema)
+    {
+        return BuildGlobalState(schema, schema.Scopes is { Count: > 0 }
+            ? LogAnalyticsDialect.GetScopeFunctions(schema)
+            : Enumerable.Empty<Symbol>());
+    }
+
+    /// <summary>
+    /// Build a GlobalState from a schema definition plus extra database members.
+    /// </summary>
+    private static GlobalState BuildGlobalState(SchemaDefinition schema, IEnumerable<Symbol> extraMembers)
+    {
+        var tableSymbols = BuildTableSymbols(schema.Tables);
 
         // Build function symbols
         var functionSymbols = new List<FunctionSymbol>();
         foreach (var func in schema.Functions ?? Enumerable.Empty<FunctionDefinition>())
         {
-            // Build parameter list
-            var parameters = (func.Parameters ?? Enumerable.Empty<ParameterDefinition>())
-                .Select(p => new Parameter(p.Name, MapScalarType(p.DataType)))
-                .ToList();
-
-            // Note: We use a simplified function definition
-            // Full function bodies would require more complex handling
-            var funcSymbol = new FunctionSymbol(
-                func.Name,
-                MapScalarType(func.ReturnType),
-                parameters.ToArray());
-            functionSymbols.Add(funcSymbol);
+            functionSymbols.Add(BuildFunctionSymbol(func));
         }
 
         // Create database symbol with tables and functions
@@ -131,11 +349,134 @@ public static class ValidationService
         var members = new List<Symbol>();
         members.AddRange(tableSymbols);
         members.AddRange(functionSymbols);
+        members.AddRange(extraMembers);
 
         var database = new DatabaseSymbol(databaseName, members.ToArray());
 
-        // Return globals with database
-        return GlobalState.Default.WithDatabase(database);
+        // Return globals with database and ambient query parameters
+        return GlobalState.Default
+            .WithDatabase(database)
+            .WithParameters(BuildQueryParameters(schema.QueryParameters));
+    }
+
+    /// <summary>
+    /// Build ambient parameter symbols from query parameter definitions.
+    /// </summary>
+    private static IReadOnlyList<ParameterSymbol> BuildQueryParameters(IEnumerable<ParameterDefinition>? parameters)
+    {
+        return (parameters ?? Enumerable.Empty<ParameterDefinition>())
+            .Select(p => new ParameterSymbol(p.Name, ScalarTypes.GetSymbol(MapDataType(p.DataType))))
+            .ToList();
+    }
+
+    /// <summary>
+    /// Build a function symbol from a function definition.
+    /// Functions with a body take their result type from the body, so tabular
+    /// functions can be used as query sources; without one, scalar functions
+    /// return their declared type and tabular functions the declared columns.
+    /// </summary>
+    private static FunctionSymbol BuildFunctionSymbol(FunctionDefinition func)
+    {
+        var definitions = func.Parameters ?? new List<ParameterDefinition>();
+
+        if (!string.IsNullOrWhiteSpace(func.Body))
+        {
+            // Declaration syntax: "(name: type = default, table: (col: type))"
+            var declarations = string.Join(", ", definitions.Select(p =>
+                $"{QuoteName(p.Name)}: {p.DataType ?? "string"}"
+                + (p.DefaultValue != null ? $" = {p.DefaultValue}" : "")));
+            var body = func.Body.Trim();
+            if (!body.StartsWith("{"))
+                body = $"{{ {body} }}";
+            return new FunctionSymbol(func.Name, $"({declarations})", body, func.Description);
+        }
+
+        // Parameters with a default value may be omitted
+        var parameters = definitions
+            .Select(p => new Parameter(
+                p.Name,
+                MapScalarType(p.DataType),
+                minOccurring: p.DefaultValue != null ? 0 : 1))
+            .ToArray();
+
+        if (func.Kind == "tabular")
+        {
+            var returnType = func.ReturnType.Trim();
+            var table = returnType.StartsWith("(") && returnType != "(*)"
+                ? new TableSymbol(func.Name, returnType)
+                : new TableSymbol(func.Name);
+            return new FunctionSymbol(func.Name, table, parameters);
+        }
+
+        return new FunctionSymbol(func.Name, MapScalarType(func.ReturnType), parameters);
+    }
+
+    /// <summary>
+    /// Quote a name as ['name'] unless it is a plain identifier.
+    /// </summary>
+    private static string QuoteName(string name)
+    {
+        var plain = name.Length > 0
+            && (char.IsLetter(name[0]) || name[0] == '_')
+            && name.All(c => char.IsLetterOrDigit(c) || c == '_');
+        return plain ? name : $"['{name.Replace("\\", "\\\\").Replace("'", "\\'")}']";
+    }
+
+    /// <summary>
+    /// Build table symbols from table definitions.
+    /// </summary>
+    public static List<TableSymbol> BuildTableSymbols(IEnumerable<TableDefinition>? tables)
+    {
+        var tableSymbols = new List<TableSymbol>();
+
+        foreach (var table in tables ?? Enumerable.Empty<TableDefinition>())
+        {
+            var columns = table.Columns ?? Enumerable.Empty<ColumnDefinition>();
+
+            // Columns with declared fields become typed dynamic bags, so
+            // completion after 'Column.' offers the fields
+            if (columns.Any(c => c.Fields is { Count: > 0 }))
+            {
+                tableSymbols.Add(new TableSymbol(table.Name, columns.Select(c => new ColumnSymbol(
+                    c.Name,
+                    c.Fields is { Count: > 0 }
+                        ? BuildDynamicBag(c.Fields)
+                        : ScalarTypes.GetSymbol(MapDataType(c.DataType))))));
+                continue;
+            }
+
+            // Build column definition string: "(col1: type1, col2: type2, ...)"
+            var columnDefs = string.Join(", ",
+                columns.Select(c => $"{c.Name}: {MapDataType(c.DataType)}"));
+
+            var tableSymbol = new TableSymbol(table.Name, $"({columnDefs})");
+            tableSymbols.Add(tableSymbol);
+        }
+
+        return tableSymbols;
+    }
+
+    /// <summary>
+    /// Build a dynamic bag type from field paths, nesting bags at each '.'.
+    /// Paths through arrays ("roles[].id") are left untyped.
+    /// </summary>
+    private static TypeSymbol BuildDynamicBag(IEnumerable<KeyValuePair<string, string>> fields)
+    {
+        var properties = fields
+            .Where(f => !f.Key.Contains("[]"))
+            .GroupBy(f => f.Key.Split('.')[0])
+            .Select(group =>
+            {
+                var nested = group
+                    .Where(f => f.Key.Length > group.Key.Length)
+                    .Select(f => new KeyValuePair<string, string>(f.Key.Substring(group.Key.Length + 1), f.Value))
+                    .ToList();
+                var type = nested.Count > 0
+                    ? BuildDynamicBag(nested)
+                    : ScalarTypes.GetSymbol(MapDataType(group.First().Value));
+                return new ColumnSymbol(group.Key, type);
+            });
+        return ScalarTypes.GetDynamicBag(properties);
     }
 
     /// <summary>
@@ -221,7 +562,11 @@ public static class ValidationService
     /// <summary>
     /// Create a ValidationResult from Kusto diagnostics.
     /// </summary>
-    private static ValidationResult CreateResult(string query, IReadOnlyList<Kusto.Language.Diagnostic> diagnostics)
+    internal static ValidationResult CreateResult(
+        string query,
+        IReadOnlyList<Kusto.Language.Diagnostic> diagnostics,
+        IReadOnlyDictionary<Kusto.Language.Diagnostic, List<FixDefinition>>? fixes = null,
+        IReadOnlyDictionary<Kusto.Language.Diagnostic, List<RelatedInfoDefinition>>? related = null)
     {
         var resultDiagnostics = new List<Diagnostic>();
         var hasErrors = false;
@@ -242,7 +587,10 @@ public static class ValidationService
                 End = diag.End,
                 Line = line,
                 Column = column,
-                Code = diag.Code
+                Code = diag.Code,
+                Category = diag.Category,
+                Fixes = fixes != null && fixes.TryGetValue(diag, out var diagFixes) ? diagFixes : null,
+                RelatedSpans = related != null && related.TryGetValue(diag, out var diagRelated) ? diagRelated : null
             });
         }
 
@@ -256,7 +604,7 @@ public static class ValidationService
     /// <summary>
     /// Calculate line and column from a character offset.
     /// </summary>
-    private static (int line, int column) GetLineAndColumn(string text, int offset)
+    internal static (int line, int column) GetLineAndColumn(string text, int offset)
     {
         if (offset < 0 || offset > text.Length)
             return (1, 1);
@@ -0,0 +1,114 @@
+using System.Buffers.Binary;
+using System.Text.Json;
+using System.Text.Json.Nodes;
+
+namespace KqlLanguageFfi;
+
+/// <summary>
+/// Entry point of the WASI build.
+/// Serves the worker protocol used by the Rust `worker` and `wasm` features:
+/// length-prefixed JSON requests on stdin, one response each on stdout,
+/// until stdin is closed.
+/// </summary>
+internal static class Program
+{
+    public static int Main()
+    {
+        using var input = Console.OpenStandardInput();
+        using var output = Console.OpenStandardOutput();
+
+        while (ReadFrame(input) is { } frame)
+        {
+            byte[] response;
+            try
+            {
+                var request = JsonNode.Parse(frame)?.AsObject()
+                    ?? throw new JsonException("Request is not a JSON object");
+                response = JsonSerializer.SerializeToUtf8Bytes(
+                    new { ok = true, result = Handle(request) },
+                    JsonOptions.Default);
+            }
+            catch (Exception ex)
+            {
+                response = JsonSerializer.SerializeToUtf8Bytes(
+                    new { ok = false, error = ex.Message },
+                    JsonOptions.Default);
+            }
+            WriteFrame(output, response);
+        }
+
+        return 0;
+    }
+
+    /// <summary>
+    /// Run one request, returning the result to serialize.
+    /// </summary>
+    private static object? Handle(JsonObject request)
+    {
+        var op = (string?)request["op"];
+        var query = (string?)request["query"] ?? "";
+
+        return op switch
+        {
+            "configure" or "ping" => null,
+            "validate_syntax" => ValidationService.ValidateSyntax(query),
+            "validate_with_schema" => ValidationService.ValidateWithSchema(
+                query,
+                ReadSchema(request["schema"]) ?? throw new JsonException("Missing schema")),
+            "get_completions" => CompletionService.GetCompletions(
+                query,
+                (int?)request["cursor"] ?? 0,
+                ReadSchema(request["schema"])),
+            "get_classifications" => ClassificationService.GetClassifications(query),
+            _ => throw new NotSupportedException($"Unsupported operation: {op}")
+        };
+    }
+
+    private static SchemaDefinition? ReadSchema(JsonNode? node) =>
+        node?.Deserialize<SchemaDefinition>();
+
+    /// <summary>
+    /// Read one frame: a little-endian u32 length, then the payload.
+    /// Returns null at end of stream.
+    /// </summary>
+    private static byte[]? ReadFrame(Stream input)
+    {
+        var header = new byte[4];
+        if (!ReadExactly(input, header, allowEnd: true))
+        {
+            return null;
+        }
+
+        var payload = new byte[BinaryPrimitives.ReadUInt32LittleEndian(header)];
+        ReadExactly(input, payload, allowEnd: false);
+        return payload;
+    }
+
+    private static bool ReadExactly(Stream input, byte[] buffer, bool allowEnd)
+    {
+        var read = 0;
+        while (read < buffer.Length)
+        {
+            var n = input.Read(buffer, read, buffer.Length - read);
+            if (n == 0)
+            {
+                if (read == 0 && allowEnd)
+                {
+                    return false;
+                }
+                throw new EndOfStreamException("Truncated frame");
+            }
+            read += n;
+        }
+        return true;
+    }
+
+    private static void WriteFrame(Stream output, byte[] payload)
+    {
+        var header = new byte[4];
+        BinaryPrimitives.WriteUInt32LittleEndian(header, (uint)payload.Length);
+        output.Write(header);
+        output.Write(payload);
+        output.Flush();
+    }
+}
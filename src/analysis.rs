@@ -0,0 +1,78 @@
+//! Referenced-entity analysis
+//!
+//! [`KqlValidator::analyze`](crate::KqlValidator::analyze) reports which
+//! schema tables, columns, and functions a query touches, and which clusters
+//! and databases it names through `cluster()` and `database()`. This is the
+//! information access-control checks need, without pattern-matching query
+//! text.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Entities referenced by a query
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QueryAnalysis {
+    /// Schema tables referenced
+    #[serde(default)]
+    pub tables: BTreeSet<String>,
+    /// Schema columns referenced, grouped by the table that defines them
+    #[serde(default)]
+    pub columns: BTreeMap<String, BTreeSet<String>>,
+    /// Schema functions referenced
+    #[serde(default)]
+    pub functions: BTreeSet<String>,
+    /// Clusters named by `cluster()` calls
+    #[serde(default)]
+    pub clusters: BTreeSet<String>,
+    /// Databases named by `database()` calls
+    #[serde(default)]
+    pub databases: BTreeSet<String>,
+}
+
+impl QueryAnalysis {
+    /// Check if the query references a table
+    #[must_use]
+    pub fn references_table(&self, name: &str) -> bool {
+        self.tables.contains(name)
+    }
+
+    /// Get the referenced columns of a table
+    pub fn columns_of(&self, table: &str) -> impl Iterator<Item = &str> {
+        self.columns
+            .get(table)
+            .into_iter()
+            .flatten()
+            .map(String::as_str)
+    }
+
+    /// Check if the query reaches outside the current database
+    #[must_use]
+    pub fn is_cross_database(&self) -> bool {
+        !self.clusters.is_empty() || !self.databases.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deserialize_analysis() {
+        let json = r#"{
+            "tables": ["SecurityEvent"],
+            "columns": {"SecurityEvent": ["Account", "EventID"]},
+            "functions": [],
+            "clusters": [],
+            "databases": ["Other"]
+        }"#;
+
+        let analysis: QueryAnalysis = serde_json::from_str(json).unwrap();
+        assert!(analysis.references_table("SecurityEvent"));
+        assert_eq!(
+            analysis.columns_of("SecurityEvent").collect::<Vec<_>>(),
+            ["Account", "EventID"]
+        );
+        assert_eq!(analysis.columns_of("Missing").count(), 0);
+        assert!(analysis.is_cross_database());
+    }
+}
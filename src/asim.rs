@@ -0,0 +1,238 @@
+//! Advanced Security Information Model (ASIM) schemas
+//!
+//! Enabled by the `asim` feature. Microsoft Sentinel's ASIM normalizes
+//! events from many sources into shared schemas, each read through parser
+//! functions. [`schema`] defines every schema's parsers and native table, so
+//! detections written against ASIM validate without a workspace:
+//!
+//! ```
+//! use kql_language_tools::asim::{self, AsimSchema};
+//!
+//! let schema = asim::schema();
+//! let dns = schema.get_function("imDns").unwrap();
+//! assert!(dns.is_tabular());
+//! assert!(schema.get_table("ASimDnsActivityLogs").is_some());
+//!
+//! // Only the authentication parsers and table
+//! let authentication = AsimSchema::Authentication.schema();
+//! assert_eq!(authentication.tables.len(), 1);
+//! ```
+//!
+//! Each schema `X` has four parsers returning its columns: `imX` and
+//! `_Im_X`, taking the schema's filtering parameters (all optional), and
+//! `ASimX` and `_ASim_X`, taking none. Columns cover the common ASIM fields,
+//! the schema's own fields and its aliases; merge the result with a
+//! workspace schema (see [`Schema::merge`]) to add custom tables.
+
+use crate::kql_type::KqlType;
+use crate::schema::{Column, Function, Parameter, Schema, Table};
+use serde::Deserialize;
+use std::sync::OnceLock;
+
+/// The bundled schema definitions
+static DEFINITIONS: &str = include_str!("asim.json");
+
+#[derive(Deserialize)]
+struct Definitions {
+    /// Fields shared by every schema
+    common: KqlType,
+    schemas: Vec<Definition>,
+}
+
+#[derive(Deserialize)]
+struct Definition {
+    name: String,
+    version: String,
+    table: String,
+    parameters: Vec<Parameter>,
+    columns: KqlType,
+}
+
+/// An ASIM schema
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AsimSchema {
+    /// Sign-ins and other authentication events (`imAuthentication`)
+    Authentication,
+    /// Configuration and administrative audit events (`imAuditEvent`)
+    AuditEvent,
+    /// DHCP lease events (`imDhcp`)
+    Dhcp,
+    /// DNS queries and responses (`imDns`)
+    Dns,
+    /// File creation, modification and deletion (`imFileEvent`)
+    FileEvent,
+    /// Network connections and flows (`imNetworkSession`)
+    NetworkSession,
+    /// Process creation and termination (`imProcessEvent`)
+    ProcessEvent,
+    /// Windows registry changes (`imRegistryEvent`)
+    RegistryEvent,
+    /// User and group management (`imUserManagement`)
+    UserManagement,
+    /// HTTP traffic (`imWebSession`)
+    WebSession,
+}
+
+impl AsimSchema {
+    /// Every ASIM schema
+    pub const ALL: [Self; 10] = [
+        Self::Authentication,
+        Self::AuditEvent,
+        Self::Dhcp,
+        Self::Dns,
+        Self::FileEvent,
+        Self::NetworkSession,
+        Self::ProcessEvent,
+        Self::RegistryEvent,
+        Self::UserManagement,
+        Self::WebSession,
+    ];
+
+    /// Name of the schema, as used in its parser names
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Authentication => "Authentication",
+            Self::AuditEvent => "AuditEvent",
+            Self::Dhcp => "Dhcp",
+            Self::Dns => "Dns",
+            Self::FileEvent => "FileEvent",
+            Self::NetworkSession => "NetworkSession",
+            Self::ProcessEvent => "ProcessEvent",
+            Self::RegistryEvent => "RegistryEvent",
+            Self::UserManagement => "UserManagement",
+            Self::WebSession => "WebSession",
+        }
+    }
+
+    /// Version of the schema the definitions follow
+    #[must_use]
+    pub fn version(self) -> &'static str {
+        &self.definition().version
+    }
+
+    /// Name of the schema's native table, such as `ASimDnsActivityLogs`
+    #[must_use]
+    pub fn table_name(self) -> &'static str {
+        &self.definition().table
+    }
+
+    /// The schema's parsers and native table
+    #[must_use]
+    pub fn schema(self) -> Schema {
+        let definitions = definitions();
+        let definition = self.definition();
+        let columns = match (&definitions.common, &definition.columns) {
+            (KqlType::Table(common), KqlType::Table(own)) => {
+                common.iter().chain(own).cloned().collect()
+            }
+            _ => Vec::new(),
+        };
+        let return_type = KqlType::Table(columns.clone());
+        let name = self.name();
+
+        let mut table = Table::new(&definition.table).folder("ASIM");
+        table.columns = columns
+            .into_iter()
+            .map(|(name, data_type)| Column::new(name, data_type))
+            .collect();
+
+        let parser = |function: String, parameters: &[Parameter], description: String| {
+            let mut function = Function::tabular(function, return_type.clone())
+                .folder("ASIM")
+                .description(description);
+            function.parameters = parameters.to_vec();
+            function
+        };
+        let filtering = format!("ASIM {name} parser, filtered by its parameters");
+        let plain = format!("ASIM {name} parser");
+        Schema::new()
+            .table(table)
+            .function(parser(
+                format!("im{name}"),
+                &definition.parameters,
+                filtering.clone(),
+            ))
+            .function(parser(
+                format!("_Im_{name}"),
+                &definition.parameters,
+                filtering,
+            ))
+            .function(parser(format!("ASim{name}"), &[], plain.clone()))
+            .function(parser(format!("_ASim_{name}"), &[], plain))
+    }
+
+    fn definition(self) -> &'static Definition {
+        definitions()
+            .schemas
+            .iter()
+            .find(|d| d.name == self.name())
+            .expect("every ASIM schema is defined")
+    }
+}
+
+/// Every ASIM schema's parsers and native table
+///
+/// The schema has no database name; add one with
+/// [`Schema::merge`] or by setting [`Schema::database`].
+#[must_use]
+pub fn schema() -> Schema {
+    let mut schema = Schema::new();
+    for asim in AsimSchema::ALL {
+        let part = asim.schema();
+        schema.tables.extend(part.tables);
+        schema.functions.extend(part.functions);
+    }
+    schema
+}
+
+fn definitions() -> &'static Definitions {
+    static DEFINED: OnceLock<Definitions> = OnceLock::new();
+    DEFINED.get_or_init(|| {
+        serde_json::from_str(DEFINITIONS).expect("bundled ASIM definitions are valid")
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_asim_schemas() {
+        let schema = schema();
+        assert_eq!(schema.validate(), []);
+        assert_eq!(schema.tables.len(), AsimSchema::ALL.len());
+        assert_eq!(schema.functions.len(), 4 * AsimSchema::ALL.len());
+
+        assert_eq!(AsimSchema::Dns.table_name(), "ASimDnsActivityLogs");
+        let dns = schema.get_table("ASimDnsActivityLogs").unwrap();
+        assert_eq!(
+            dns.get_column("TimeGenerated").unwrap().data_type,
+            KqlType::DateTime
+        );
+        assert_eq!(
+            dns.get_column("DnsResponseCode").unwrap().data_type,
+            KqlType::Int
+        );
+
+        let parser = schema.get_function("imNetworkSession").unwrap();
+        assert!(parser.parameters.iter().all(|p| p.default_value.is_some()));
+        assert!(schema
+            .get_function("_ASim_NetworkSession")
+            .unwrap()
+            .parameters
+            .is_empty());
+        assert_eq!(
+            parser.return_type,
+            KqlType::Table(
+                schema
+                    .get_table("ASimNetworkSessionLogs")
+                    .unwrap()
+                    .columns
+                    .iter()
+                    .map(|c| (c.name.clone(), c.data_type.clone()))
+                    .collect()
+            )
+        );
+    }
+}
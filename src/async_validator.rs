@@ -0,0 +1,364 @@
+//! Async validation API
+//!
+//! Enabled by the `async` feature. [`AsyncKqlValidator`] wraps a
+//! [`KqlValidator`] and runs each native call on tokio's blocking thread
+//! pool, so validating in an async service does not stall the runtime's
+//! worker threads.
+//!
+//! Dropping a returned future cancels the call cooperatively: a call that
+//! has not started on the blocking pool yet is skipped, and a batch stops
+//! before its next query. A native call that is already running completes
+//! on the blocking pool and its result is discarded.
+
+use crate::analysis::QueryAnalysis;
+use crate::classification::ClassificationResult;
+use crate::completion::CompletionResult;
+use crate::error::Error;
+use crate::format::FormatOptions;
+use crate::schema::Schema;
+use crate::script::ScriptValidation;
+use crate::types::ValidationResult;
+use crate::validator::KqlValidator;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// A [`KqlValidator`] whose methods return futures
+///
+/// Cloning is cheap; clones share the underlying validator.
+///
+/// # Example
+///
+/// ```no_run
+/// use kql_language_tools::AsyncKqlValidator;
+///
+/// async fn check(query: String) -> Result<bool, kql_language_tools::Error> {
+///     let validator = AsyncKqlValidator::new().await?;
+///     let result = validator.validate_syntax(query).await?;
+///     Ok(result.is_valid())
+/// }
+/// ```
+#[derive(Clone)]
+pub struct AsyncKqlValidator {
+    inner: Arc<KqlValidator>,
+}
+
+impl AsyncKqlValidator {
+    /// Create a validator, loading the native library on the blocking pool
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the native library cannot be loaded or
+    /// initialized.
+    pub async fn new() -> Result<Self, Error> {
+        spawn_cancellable(|| KqlValidator::new().map(Self::from))
+            .await
+            .and_then(|result| result)
+    }
+
+    /// Get the wrapped validator, for its configuration and `supports_*` checks
+    #[must_use]
+    pub fn validator(&self) -> &KqlValidator {
+        &self.inner
+    }
+
+    /// Validate a query for syntax errors
+    ///
+    /// See [`KqlValidator::validate_syntax`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the native call fails.
+    pub async fn validate_syntax(
+        &self,
+        query: impl Into<String>,
+    ) -> Result<ValidationResult, Error> {
+        let query = query.into();
+        self.run(move |validator| validator.validate_syntax(&query))
+            .await
+    }
+
+    /// Validate a query against a schema
+    ///
+    /// See [`KqlValidator::validate_with_schema`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the native call fails.
+    pub async fn validate_with_schema(
+        &self,
+        query: impl Into<String>,
+        schema: Arc<Schema>,
+    ) -> Result<ValidationResult, Error> {
+        let query = query.into();
+        self.run(move |validator| validator.validate_with_schema(&query, &schema))
+            .await
+    }
+
+    /// Validate an ADX control command
+    ///
+    /// See [`KqlValidator::validate_command`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the native call fails.
+    pub async fn validate_command(
+        &self,
+        text: impl Into<String>,
+    ) -> Result<ValidationResult, Error> {
+        let text = text.into();
+        self.run(move |validator| validator.validate_command(&text))
+            .await
+    }
+
+    /// Validate a script of blank-line separated queries
+    ///
+    /// See [`KqlValidator::validate_script`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the native call fails.
+    pub async fn validate_script(
+        &self,
+        text: impl Into<String>,
+    ) -> Result<ScriptValidation, Error> {
+        let text = text.into();
+        self.run(move |validator| validator.validate_script(&text))
+            .await
+    }
+
+    /// Validate several queries, optionally against a shared schema
+    ///
+    /// Unlike [`KqlValidator::validate_many`] the queries are validated one
+    /// at a time, so dropping the future stops the batch before its next
+    /// query.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a native call fails.
+    pub async fn validate_many(
+        &self,
+        queries: Vec<String>,
+        schema: Option<Arc<Schema>>,
+    ) -> Result<Vec<ValidationResult>, Error> {
+        let validator = Arc::clone(&self.inner);
+        let guard = CancelOnDrop::default();
+        let cancelled = guard.flag();
+
+        let task = tokio::task::spawn_blocking(move || {
+            let mut results = Vec::with_capacity(queries.len());
+            for query in &queries {
+                if cancelled.load(Ordering::Acquire) {
+                    return None;
+                }
+                results.push(match &schema {
+                    Some(schema) => validator.validate_with_schema(query, schema),
+                    None => validator.validate_syntax(query),
+                });
+            }
+            Some(results.into_iter().collect())
+        });
+
+        join(task.await)?
+    }
+
+    /// Analyze a query against a schema
+    ///
+    /// See [`KqlValidator::analyze`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the native call fails.
+    pub async fn analyze(
+        &self,
+        query: impl Into<String>,
+        schema: Arc<Schema>,
+    ) -> Result<QueryAnalysis, Error> {
+        let query = query.into();
+        self.run(move |validator| validator.analyze(&query, &schema))
+            .await
+    }
+
+    /// Get completion suggestions at a cursor position
+    ///
+    /// See [`KqlValidator::get_completions`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the native call fails.
+    pub async fn get_completions(
+        &self,
+        query: impl Into<String>,
+        cursor_position: usize,
+        schema: Option<Arc<Schema>>,
+    ) -> Result<CompletionResult, Error> {
+        let query = query.into();
+        self.run(move |validator| {
+            validator.get_completions(&query, cursor_position, schema.as_deref())
+        })
+        .await
+    }
+
+    /// Get syntax classifications for highlighting
+    ///
+    /// See [`KqlValidator::get_classifications`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the native call fails.
+    pub async fn get_classifications(
+        &self,
+        query: impl Into<String>,
+    ) -> Result<ClassificationResult, Error> {
+        let query = query.into();
+        self.run(move |validator| validator.get_classifications(&query))
+            .await
+    }
+
+    /// Format a query
+    ///
+    /// See [`KqlValidator::format`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the native call fails.
+    pub async fn format(
+        &self,
+        query: impl Into<String>,
+        options: FormatOptions,
+    ) -> Result<String, Error> {
+        let query = query.into();
+        self.run(move |validator| validator.format(&query, &options))
+            .await
+    }
+
+    /// Run a call against the wrapped validator on the blocking pool
+    async fn run<T, F>(&self, call: F) -> Result<T, Error>
+    where
+        T: Send + 'static,
+        F: FnOnce(&KqlValidator) -> Result<T, Error> + Send + 'static,
+    {
+        let validator = Arc::clone(&self.inner);
+        spawn_cancellable(move || call(&validator))
+            .await
+            .and_then(|result| result)
+    }
+}
+
+impl From<KqlValidator> for AsyncKqlValidator {
+    fn from(validator: KqlValidator) -> Self {
+        Self {
+            inner: Arc::new(validator),
+        }
+    }
+}
+
+impl std::fmt::Debug for AsyncKqlValidator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AsyncKqlValidator").finish_non_exhaustive()
+    }
+}
+
+/// Run a closure on the blocking pool, skipping it if the future is dropped first
+async fn spawn_cancellable<T, F>(call: F) -> Result<T, Error>
+where
+    T: Send + 'static,
+    F: FnOnce() -> T + Send + 'static,
+{
+    let guard = CancelOnDrop::default();
+    let cancelled = guard.flag();
+
+    let task = tokio::task::spawn_blocking(move || {
+        if cancelled.load(Ordering::Acquire) {
+            return None;
+        }
+        Some(call())
+    });
+
+    join(task.await)
+}
+
+/// Unwrap the result of a blocking task that checks for cancellation
+fn join<T>(joined: Result<Option<T>, tokio::task::JoinError>) -> Result<T, Error> {
+    match joined {
+        Ok(Some(value)) => Ok(value),
+        // The flag is only set once the awaiting future is dropped
+        Ok(None) => Err(Error::Internal {
+            message: "Native call was cancelled".to_string(),
+        }),
+        Err(err) => Err(Error::Internal {
+            message: format!("Native call task failed: {err}"),
+        }),
+    }
+}
+
+/// Sets a shared flag when dropped
+#[derive(Default)]
+struct CancelOnDrop {
+    flag: Arc<AtomicBool>,
+}
+
+impl CancelOnDrop {
+    fn flag(&self) -> Arc<AtomicBool> {
+        Arc::clone(&self.flag)
+    }
+}
+
+impl Drop for CancelOnDrop {
+    fn drop(&mut self) {
+        self.flag.store(true, Ordering::Release);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::future::Future;
+    use std::pin::Pin;
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::mpsc;
+    use std::task::{Context, Wake, Waker};
+
+    struct NoopWaker;
+
+    impl Wake for NoopWaker {
+        fn wake(self: Arc<Self>) {}
+    }
+
+    #[test]
+    fn test_dropped_call_is_skipped() {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .max_blocking_threads(1)
+            .build()
+            .unwrap();
+        let _entered = runtime.enter();
+        let waker = Waker::from(Arc::new(NoopWaker));
+        let mut cx = Context::from_waker(&waker);
+        let calls = Arc::new(AtomicUsize::new(0));
+
+        // Occupy the only blocking thread while the second call is queued
+        let (release, blocked) = mpsc::channel::<()>();
+        let mut first = Box::pin(spawn_cancellable(move || blocked.recv().ok()));
+        assert!(first.as_mut().poll(&mut cx).is_pending());
+
+        let counter = Arc::clone(&calls);
+        let mut second = Box::pin(spawn_cancellable(move || {
+            counter.fetch_add(1, Ordering::SeqCst);
+        }));
+        assert!(second.as_mut().poll(&mut cx).is_pending());
+        drop::<Pin<Box<_>>>(second);
+
+        release.send(()).unwrap();
+        runtime.block_on(first).unwrap().unwrap();
+        // The pool runs tasks in order, so the second call has been taken by now
+        runtime.block_on(spawn_cancellable(|| ())).unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn test_completed_call_returns_value() {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        assert_eq!(runtime.block_on(spawn_cancellable(|| 42)).unwrap(), 42);
+    }
+}
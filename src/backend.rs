@@ -0,0 +1,220 @@
+//! Interchangeable validation backends
+//!
+//! [`KqlEngine`] is the core of the validator API: validation, completion
+//! and classification. [`KqlValidator`] is a wrapper over an engine: the
+//! native library in process by default, or any other implementation
+//! selected with [`KqlValidator::with_engine`]. The trait is implemented by
+//! [`KqlValidator`] itself and [`CachedValidator`], by
+//! `worker::WorkerValidator` and `worker::KqlWorkerPool` with the `worker`
+//! feature, by `wasm::WasmValidator` with the `wasm` feature, by
+//! `fallback::FallbackEngine` with the `fallback` feature, and by
+//! `MockValidator` with the `mock` feature. Code written against
+//! `&dyn KqlEngine` or `Box<dyn KqlEngine>` runs on any of them, and tests
+//! can substitute their own implementation.
+
+use crate::cache::CachedValidator;
+use crate::classification::ClassificationResult;
+use crate::completion::CompletionResult;
+use crate::error::Error;
+use crate::schema::Schema;
+use crate::types::ValidationResult;
+use crate::validator::KqlValidator;
+use std::sync::Arc;
+
+/// A backend that validates, completes and classifies queries
+///
+/// Offsets follow the conventions of [`KqlValidator`]: character offsets
+/// unless the backend is configured otherwise.
+///
+/// # Example
+///
+/// ```no_run
+/// use kql_language_tools::{KqlEngine, KqlValidator};
+///
+/// fn count_errors(engine: &dyn KqlEngine, queries: &[&str]) -> Result<usize, kql_language_tools::Error> {
+///     let mut errors = 0;
+///     for query in queries {
+///         errors += engine.validate_syntax(query)?.errors().count();
+///     }
+///     Ok(errors)
+/// }
+///
+/// let engine: Box<dyn KqlEngine> = Box::new(KqlValidator::new()?);
+/// count_errors(engine.as_ref(), &["T | take 10"])?;
+/// # Ok::<(), kql_language_tools::Error>(())
+/// ```
+pub trait KqlEngine: Send + Sync {
+    /// Validate a query for syntax errors
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the backend call fails.
+    fn validate_syntax(&self, query: &str) -> Result<ValidationResult, Error>;
+
+    /// Validate a query against a schema
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the backend call fails.
+    fn validate_with_schema(&self, query: &str, schema: &Schema)
+        -> Result<ValidationResult, Error>;
+
+    /// Get completions at a cursor position
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the backend call fails.
+    fn get_completions(
+        &self,
+        query: &str,
+        cursor_position: usize,
+        schema: Option<&Schema>,
+    ) -> Result<CompletionResult, Error>;
+
+    /// Get syntax classifications for highlighting
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the backend call fails.
+    fn get_classifications(&self, query: &str) -> Result<ClassificationResult, Error>;
+}
+
+impl KqlEngine for KqlValidator {
+    fn validate_syntax(&self, query: &str) -> Result<ValidationResult, Error> {
+        KqlValidator::validate_syntax(self, query)
+    }
+
+    fn validate_with_schema(
+        &self,
+        query: &str,
+        schema: &Schema,
+    ) -> Result<ValidationResult, Error> {
+        KqlValidator::validate_with_schema(self, query, schema)
+    }
+
+    fn get_completions(
+        &self,
+        query: &str,
+        cursor_position: usize,
+        schema: Option<&Schema>,
+    ) -> Result<CompletionResult, Error> {
+        KqlValidator::get_completions(self, query, cursor_position, schema)
+    }
+
+    fn get_classifications(&self, query: &str) -> Result<ClassificationResult, Error> {
+        KqlValidator::get_classifications(self, query)
+    }
+}
+
+/// Validation goes through the cache; completion and classification do not
+impl KqlEngine for CachedValidator {
+    fn validate_syntax(&self, query: &str) -> Result<ValidationResult, Error> {
+        CachedValidator::validate_syntax(self, query)
+    }
+
+    fn validate_with_schema(
+        &self,
+        query: &str,
+        schema: &Schema,
+    ) -> Result<ValidationResult, Error> {
+        CachedValidator::validate_with_schema(self, query, schema)
+    }
+
+    fn get_completions(
+        &self,
+        query: &str,
+        cursor_position: usize,
+        schema: Option<&Schema>,
+    ) -> Result<CompletionResult, Error> {
+        self.validator()
+            .get_completions(query, cursor_position, schema)
+    }
+
+    fn get_classifications(&self, query: &str) -> Result<ClassificationResult, Error> {
+        self.validator().get_classifications(query)
+    }
+}
+
+macro_rules! forward_engine {
+    ($($pointer:ident),*) => {$(
+        impl<E: KqlEngine + ?Sized> KqlEngine for $pointer<E> {
+            fn validate_syntax(&self, query: &str) -> Result<ValidationResult, Error> {
+                (**self).validate_syntax(query)
+            }
+
+            fn validate_with_schema(
+                &self,
+                query: &str,
+                schema: &Schema,
+            ) -> Result<ValidationResult, Error> {
+                (**self).validate_with_schema(query, schema)
+            }
+
+            fn get_completions(
+                &self,
+                query: &str,
+                cursor_position: usize,
+                schema: Option<&Schema>,
+            ) -> Result<CompletionResult, Error> {
+                (**self).get_completions(query, cursor_position, schema)
+            }
+
+            fn get_classifications(&self, query: &str) -> Result<ClassificationResult, Error> {
+                (**self).get_classifications(query)
+            }
+        }
+    )*};
+}
+
+forward_engine!(Box, Arc);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Reports every query as valid and completes its own length
+    struct Echo;
+
+    impl KqlEngine for Echo {
+        fn validate_syntax(&self, _query: &str) -> Result<ValidationResult, Error> {
+            Ok(ValidationResult::valid())
+        }
+
+        fn validate_with_schema(
+            &self,
+            _query: &str,
+            _schema: &Schema,
+        ) -> Result<ValidationResult, Error> {
+            Err(Error::Internal {
+                message: "no schemas".to_string(),
+            })
+        }
+
+        fn get_completions(
+            &self,
+            _query: &str,
+            _cursor_position: usize,
+            _schema: Option<&Schema>,
+        ) -> Result<CompletionResult, Error> {
+            Ok(CompletionResult::default())
+        }
+
+        fn get_classifications(&self, _query: &str) -> Result<ClassificationResult, Error> {
+            Ok(ClassificationResult::default())
+        }
+    }
+
+    fn check(engine: &impl KqlEngine) -> bool {
+        engine.validate_syntax("T").unwrap().is_valid()
+            && engine.validate_with_schema("T", &Schema::new()).is_err()
+    }
+
+    #[test]
+    fn test_engines_are_interchangeable() {
+        let boxed: Box<dyn KqlEngine> = Box::new(Echo);
+        let shared: Arc<dyn KqlEngine> = Arc::new(Echo);
+        assert!(check(&Echo));
+        assert!(check(&boxed));
+        assert!(check(&shared));
+    }
+}
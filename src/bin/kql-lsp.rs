@@ -0,0 +1,12 @@
+//! KQL language server over stdin/stdout
+//!
+//! Build with `cargo build --features lsp --bin kql-lsp` and point an editor's
+//! LSP client at the binary.
+
+#[tokio::main]
+async fn main() {
+    if let Err(e) = kql_language_tools::lsp::serve_stdio().await {
+        eprintln!("kql-lsp: {e}");
+        std::process::exit(1);
+    }
+}
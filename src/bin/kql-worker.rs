@@ -0,0 +1,12 @@
+//! KQL worker process over stdin/stdout
+//!
+//! Build with `cargo build --features worker --bin kql-worker`. It is started
+//! by `WorkerValidator`, which talks to it over a length-prefixed JSON
+//! protocol; see the `worker` module docs.
+
+fn main() {
+    if let Err(e) = kql_language_tools::worker::serve_stdio() {
+        eprintln!("kql-worker: {e}");
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,100 @@
+//! Schema-bound validation
+//!
+//! A [`BoundValidator`] pairs a [`KqlValidator`] with one schema that has
+//! been registered with the native library, so repeated calls against a
+//! large schema skip serializing it and rebuilding its symbol tables.
+
+use crate::completion::CompletionResult;
+use crate::error::Error;
+use crate::handle::SchemaHandle;
+use crate::schema::Schema;
+use crate::spelling;
+use crate::types::ValidationResult;
+use crate::validator::KqlValidator;
+
+/// A validator bound to a single schema
+///
+/// Created with [`KqlValidator::with_schema`]. The native registration is
+/// released when the `BoundValidator` is dropped.
+pub struct BoundValidator {
+    validator: KqlValidator,
+    schema: Schema,
+    handle: Option<SchemaHandle>,
+}
+
+impl BoundValidator {
+    pub(crate) fn new(
+        validator: KqlValidator,
+        schema: Schema,
+        handle: Option<SchemaHandle>,
+    ) -> Self {
+        Self {
+            validator,
+            schema,
+            handle,
+        }
+    }
+
+    /// Validate a query against the bound schema
+    ///
+    /// The validator's options (dialect, profiles, ...) apply as in
+    /// [`KqlValidator::validate_with_schema`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the native call fails.
+    pub fn validate(&self, query: &str) -> Result<ValidationResult, Error> {
+        match &self.handle {
+            Some(handle) => {
+                let mut result = self.validator.check_with_handle(query, handle)?;
+                spelling::suggest_names(query, &self.schema, &mut result);
+                self.validator
+                    .offset_encoding()
+                    .encode_diagnostics(query, &mut result.diagnostics);
+                Ok(result)
+            }
+            None => self.validator.validate_with_schema(query, &self.schema),
+        }
+    }
+
+    /// Get completions at a cursor position using the bound schema
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if completion is not supported by the loaded library.
+    pub fn complete(&self, query: &str, cursor_position: usize) -> Result<CompletionResult, Error> {
+        match &self.handle {
+            Some(handle) => {
+                self.validator
+                    .get_completions_with_handle(query, cursor_position, handle)
+            }
+            None => self
+                .validator
+                .get_completions(query, cursor_position, Some(&self.schema)),
+        }
+    }
+
+    /// Get the bound schema
+    #[must_use]
+    pub fn schema(&self) -> &Schema {
+        &self.schema
+    }
+
+    /// Get the native schema handle, if the schema is registered
+    #[must_use]
+    pub fn handle(&self) -> Option<&SchemaHandle> {
+        self.handle.as_ref()
+    }
+
+    /// Get the underlying validator, for calls that take a schema explicitly
+    #[must_use]
+    pub fn validator(&self) -> &KqlValidator {
+        &self.validator
+    }
+
+    /// Check if the schema is registered natively rather than sent per call
+    #[must_use]
+    pub fn is_registered(&self) -> bool {
+        self.handle.is_some()
+    }
+}
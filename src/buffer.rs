@@ -0,0 +1,60 @@
+//! Reusable output buffers for native calls
+//!
+//! Every native call writes its JSON result into a caller-provided buffer.
+//! Rather than allocating a fresh buffer per call, each thread keeps one
+//! and lends it out; it grows when a result does not fit and is shrunk back
+//! after unusually large results so idle threads do not hold megabytes.
+
+use crate::ffi::DEFAULT_BUFFER_SIZE;
+use std::cell::RefCell;
+
+/// Largest buffer kept between calls (1MB)
+const RETAINED_BUFFER_SIZE: usize = 1024 * 1024;
+
+thread_local! {
+    static OUTPUT_BUFFER: RefCell<Vec<u8>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Run `f` with this thread's output buffer, at least `DEFAULT_BUFFER_SIZE` long
+///
+/// A nested call on the same thread gets a temporary buffer instead.
+pub(crate) fn with_output_buffer<R>(f: impl FnOnce(&mut Vec<u8>) -> R) -> R {
+    OUTPUT_BUFFER.with(|cell| match cell.try_borrow_mut() {
+        Ok(mut buffer) => {
+            if buffer.len() < DEFAULT_BUFFER_SIZE {
+                buffer.resize(DEFAULT_BUFFER_SIZE, 0);
+            }
+            let result = f(&mut buffer);
+            if buffer.len() > RETAINED_BUFFER_SIZE {
+                buffer.truncate(DEFAULT_BUFFER_SIZE);
+                buffer.shrink_to_fit();
+            }
+            result
+        }
+        Err(_) => f(&mut vec![0u8; DEFAULT_BUFFER_SIZE]),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_buffer_is_reused_and_shrunk() {
+        let first = with_output_buffer(|buffer| {
+            assert_eq!(buffer.len(), DEFAULT_BUFFER_SIZE);
+            buffer.as_ptr() as usize
+        });
+        let second = with_output_buffer(|buffer| buffer.as_ptr() as usize);
+        assert_eq!(first, second);
+
+        // Nested calls get their own buffer
+        with_output_buffer(|outer| {
+            let outer = outer.as_ptr() as usize;
+            with_output_buffer(|inner| assert_ne!(inner.as_ptr() as usize, outer));
+        });
+
+        with_output_buffer(|buffer| buffer.resize(RETAINED_BUFFER_SIZE * 2, 0));
+        with_output_buffer(|buffer| assert_eq!(buffer.len(), DEFAULT_BUFFER_SIZE));
+    }
+}
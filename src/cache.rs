@@ -0,0 +1,273 @@
+//! Cached validation
+//!
+//! A [`CachedValidator`] memoizes validation results by query text, schema
+//! and dialect, so re-validating unchanged text (an editor re-checking on
+//! every debounce) skips the native call. Entries are evicted least
+//! recently used first, and optionally expire after a time to live.
+
+use crate::dialect::Dialect;
+use crate::error::Error;
+use crate::schema::Schema;
+use crate::types::ValidationResult;
+use crate::validator::KqlValidator;
+use std::collections::HashMap;
+use std::sync::{Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+/// A validator that caches validation results
+///
+/// Created with [`KqlValidator::with_cache`]. Errors are not cached.
+///
+/// # Example
+///
+/// ```no_run
+/// use kql_language_tools::KqlValidator;
+/// use std::time::Duration;
+///
+/// fn main() -> Result<(), kql_language_tools::Error> {
+///     let validator = KqlValidator::new()?
+///         .with_cache(1000)
+///         .with_ttl(Duration::from_secs(300));
+///
+///     let first = validator.validate_syntax("T | take 10")?;
+///     // Served from the cache
+///     let second = validator.validate_syntax("T | take 10")?;
+///     assert_eq!(validator.len(), 1);
+///     Ok(())
+/// }
+/// ```
+pub struct CachedValidator {
+    validator: KqlValidator,
+    cache: Mutex<ResultCache>,
+}
+
+impl CachedValidator {
+    pub(crate) fn new(validator: KqlValidator, capacity: usize) -> Self {
+        Self {
+            validator,
+            cache: Mutex::new(ResultCache::new(capacity)),
+        }
+    }
+
+    /// Expire entries a fixed time after they were cached
+    #[must_use]
+    pub fn with_ttl(self, ttl: Duration) -> Self {
+        self.lock().ttl = Some(ttl);
+        self
+    }
+
+    /// Validate a query for syntax errors, using the cache
+    ///
+    /// See [`KqlValidator::validate_syntax`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the native call fails.
+    pub fn validate_syntax(&self, query: &str) -> Result<ValidationResult, Error> {
+        self.cached(query, None, || self.validator.validate_syntax(query))
+    }
+
+    /// Validate a query against a schema, using the cache
+    ///
+    /// See [`KqlValidator::validate_with_schema`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the native call fails.
+    pub fn validate_with_schema(
+        &self,
+        query: &str,
+        schema: &Schema,
+    ) -> Result<ValidationResult, Error> {
+        self.cached(query, Some(schema.fingerprint()), || {
+            self.validator.validate_with_schema(query, schema)
+        })
+    }
+
+    /// Get the wrapped validator
+    #[must_use]
+    pub fn validator(&self) -> &KqlValidator {
+        &self.validator
+    }
+
+    /// Get the number of cached results, including expired ones not yet evicted
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.lock().entries.len()
+    }
+
+    /// Check if no results are cached
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Remove all cached results
+    pub fn clear(&self) {
+        self.lock().entries.clear();
+    }
+
+    fn cached(
+        &self,
+        query: &str,
+        schema: Option<u64>,
+        validate: impl FnOnce() -> Result<ValidationResult, Error>,
+    ) -> Result<ValidationResult, Error> {
+        let key = CacheKey {
+            query: query.to_string(),
+            schema,
+            dialect: self.validator.dialect(),
+        };
+        if let Some(result) = self.lock().get(&key, Instant::now()) {
+            return Ok(result);
+        }
+
+        // Validate without holding the lock so other queries are not blocked
+        let result = validate()?;
+        self.lock().insert(key, result.clone(), Instant::now());
+        Ok(result)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, ResultCache> {
+        self.cache.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl std::fmt::Debug for CachedValidator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let cache = self.lock();
+        f.debug_struct("CachedValidator")
+            .field("len", &cache.entries.len())
+            .field("capacity", &cache.capacity)
+            .field("ttl", &cache.ttl)
+            .finish_non_exhaustive()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct CacheKey {
+    query: String,
+    schema: Option<u64>,
+    dialect: Dialect,
+}
+
+#[derive(Debug)]
+struct CacheEntry {
+    result: ValidationResult,
+    inserted: Instant,
+    last_used: u64,
+}
+
+/// Results keyed by query, evicted least recently used first
+#[derive(Debug)]
+struct ResultCache {
+    entries: HashMap<CacheKey, CacheEntry>,
+    capacity: usize,
+    ttl: Option<Duration>,
+    /// Incremented on every access to order entries by recency
+    clock: u64,
+}
+
+impl ResultCache {
+    fn new(capacity: usize) -> Self {
+        Self {
+            entries: HashMap::new(),
+            capacity,
+            ttl: None,
+            clock: 0,
+        }
+    }
+
+    fn get(&mut self, key: &CacheKey, now: Instant) -> Option<ValidationResult> {
+        let expired = self.entries.get(key).is_some_and(|entry| {
+            self.ttl
+                .is_some_and(|ttl| now.duration_since(entry.inserted) >= ttl)
+        });
+        if expired {
+            self.entries.remove(key);
+            return None;
+        }
+
+        self.clock += 1;
+        let entry = self.entries.get_mut(key)?;
+        entry.last_used = self.clock;
+        Some(entry.result.clone())
+    }
+
+    fn insert(&mut self, key: CacheKey, result: ValidationResult, now: Instant) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.entries.len() >= self.capacity && !self.entries.contains_key(&key) {
+            // Capacities are small enough that a scan beats keeping a list
+            if let Some(oldest) = self
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(key, _)| key.clone())
+            {
+                self.entries.remove(&oldest);
+            }
+        }
+
+        self.clock += 1;
+        self.entries.insert(
+            key,
+            CacheEntry {
+                result,
+                inserted: now,
+                last_used: self.clock,
+            },
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(query: &str) -> CacheKey {
+        CacheKey {
+            query: query.to_string(),
+            schema: None,
+            dialect: Dialect::default(),
+        }
+    }
+
+    #[test]
+    fn test_evicts_least_recently_used() {
+        let now = Instant::now();
+        let mut cache = ResultCache::new(2);
+        cache.insert(key("a"), ValidationResult::valid(), now);
+        cache.insert(key("b"), ValidationResult::valid(), now);
+
+        // Using "a" makes "b" the eviction candidate
+        assert!(cache.get(&key("a"), now).is_some());
+        cache.insert(key("c"), ValidationResult::valid(), now);
+
+        assert!(cache.get(&key("a"), now).is_some());
+        assert!(cache.get(&key("b"), now).is_none());
+        assert!(cache.get(&key("c"), now).is_some());
+
+        // Keys differing only in schema are distinct
+        let mut with_schema = key("a");
+        with_schema.schema = Some(1);
+        assert!(cache.get(&with_schema, now).is_none());
+    }
+
+    #[test]
+    fn test_entries_expire_after_ttl() {
+        let now = Instant::now();
+        let mut cache = ResultCache::new(4);
+        cache.ttl = Some(Duration::from_secs(60));
+        cache.insert(key("a"), ValidationResult::valid(), now);
+
+        assert!(cache
+            .get(&key("a"), now + Duration::from_secs(59))
+            .is_some());
+        assert!(cache
+            .get(&key("a"), now + Duration::from_secs(60))
+            .is_none());
+        assert!(cache.entries.is_empty());
+    }
+}
@@ -0,0 +1,231 @@
+//! Cancellation and deadlines for native calls
+//!
+//! A [`CancellationToken`] is passed to
+//! [`KqlValidator::run_cancellable`](crate::KqlValidator::run_cancellable).
+//! The call runs on the validator's worker thread while the caller waits on
+//! the token; when the token is cancelled or its deadline passes, the caller
+//! gets [`Error::Cancelled`] or [`Error::TimedOut`] right away. Libraries
+//! that export `kql_cancellation_create` also stop the analysis itself for
+//! validation calls; other calls keep the worker busy until they finish,
+//! and their result is discarded.
+
+use crate::error::Error;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+type Job = Box<dyn FnOnce() + Send>;
+
+/// A cancellation flag with an optional deadline
+///
+/// Clones share the flag, so one clone can be handed to the code that
+/// decides to cancel.
+///
+/// # Example
+///
+/// ```
+/// use kql_language_tools::CancellationToken;
+/// use std::time::Duration;
+///
+/// let token = CancellationToken::with_timeout(Duration::from_secs(2));
+/// assert!(!token.is_cancelled());
+///
+/// token.clone().cancel();
+/// assert!(token.is_cancelled());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+    deadline: Option<Instant>,
+}
+
+impl CancellationToken {
+    /// Create a token that is cancelled only explicitly
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a token that expires after a timeout
+    #[must_use]
+    pub fn with_timeout(timeout: Duration) -> Self {
+        Self {
+            cancelled: Arc::default(),
+            deadline: Instant::now().checked_add(timeout),
+        }
+    }
+
+    /// Create a token that expires at a deadline
+    #[must_use]
+    pub fn with_deadline(deadline: Instant) -> Self {
+        Self {
+            cancelled: Arc::default(),
+            deadline: Some(deadline),
+        }
+    }
+
+    /// Cancel every call observing this token or its clones
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Release);
+    }
+
+    /// Check whether the token was cancelled or its deadline has passed
+    #[must_use]
+    pub fn is_cancelled(&self) -> bool {
+        self.error().is_some()
+    }
+
+    /// Get the deadline, if any
+    #[must_use]
+    pub fn deadline(&self) -> Option<Instant> {
+        self.deadline
+    }
+
+    /// The error a call observing this token fails with, if it should stop
+    pub(crate) fn error(&self) -> Option<Error> {
+        if self.cancelled.load(Ordering::Acquire) {
+            Some(Error::Cancelled)
+        } else if self
+            .deadline
+            .is_some_and(|deadline| Instant::now() >= deadline)
+        {
+            Some(Error::TimedOut)
+        } else {
+            None
+        }
+    }
+}
+
+/// The thread a validator runs its cancellable calls on
+///
+/// The thread starts on first use and runs one call at a time. A call the
+/// caller stopped waiting for still occupies it until the native code
+/// returns, and new calls are rejected with [`Error::Busy`] meanwhile, so
+/// calls that time out cannot pile up threads. Clones start their own
+/// worker.
+#[derive(Default)]
+pub(crate) struct CallWorker {
+    sender: Mutex<Option<Sender<Job>>>,
+    busy: Arc<AtomicBool>,
+}
+
+impl CallWorker {
+    /// Run `job` on the worker, returning a receiver for its result
+    ///
+    /// The receiver disconnects without a result if `job` panics.
+    pub(crate) fn run<T, F>(&self, job: F) -> Result<Receiver<T>, Error>
+    where
+        T: Send + 'static,
+        F: FnOnce() -> T + Send + 'static,
+    {
+        if self
+            .busy
+            .compare_exchange(false, true, Ordering::AcqRel, Ordering::Acquire)
+            .is_err()
+        {
+            return Err(Error::Busy);
+        }
+
+        let (result_sender, result_receiver) = mpsc::channel();
+        let busy = Arc::clone(&self.busy);
+        let job: Job = Box::new(move || {
+            let result = panic::catch_unwind(AssertUnwindSafe(job));
+            // Free the worker before the caller sees the result, so it can
+            // make its next call right away
+            busy.store(false, Ordering::Release);
+            if let Ok(result) = result {
+                // The caller may have stopped waiting
+                let _ = result_sender.send(result);
+            }
+        });
+
+        let mut sender = self.sender.lock().unwrap_or_else(PoisonError::into_inner);
+        if sender.is_none() {
+            match Self::spawn() {
+                Ok(spawned) => *sender = Some(spawned),
+                Err(err) => {
+                    self.busy.store(false, Ordering::Release);
+                    return Err(err);
+                }
+            }
+        }
+        if let Some(sender) = sender.as_ref() {
+            // The thread only exits once this sender is dropped
+            let _ = sender.send(job);
+        }
+        Ok(result_receiver)
+    }
+
+    fn spawn() -> Result<Sender<Job>, Error> {
+        let (sender, receiver) = mpsc::channel::<Job>();
+        thread::Builder::new()
+            .name("kql-native-call".to_string())
+            .spawn(move || {
+                for job in receiver {
+                    job();
+                }
+            })
+            .map_err(|err| Error::Internal {
+                message: format!("Failed to start worker thread: {err}"),
+            })?;
+        Ok(sender)
+    }
+}
+
+impl Clone for CallWorker {
+    fn clone(&self) -> Self {
+        Self::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cancel_and_deadline() {
+        let token = CancellationToken::new();
+        assert!(token.error().is_none());
+        token.clone().cancel();
+        assert!(matches!(token.error(), Some(Error::Cancelled)));
+
+        let expired = CancellationToken::with_deadline(Instant::now());
+        assert!(matches!(expired.error(), Some(Error::TimedOut)));
+
+        // Explicit cancellation takes precedence over an expired deadline
+        expired.cancel();
+        assert!(matches!(expired.error(), Some(Error::Cancelled)));
+
+        let unbounded = CancellationToken::with_timeout(Duration::MAX);
+        assert!(unbounded.deadline().is_none());
+        assert!(!unbounded.is_cancelled());
+    }
+
+    #[test]
+    fn test_call_worker() {
+        let worker = CallWorker::default();
+        assert_eq!(worker.run(|| 1).unwrap().recv().unwrap(), 1);
+        // Free again as soon as the result arrives
+        assert_eq!(worker.run(|| 2).unwrap().recv().unwrap(), 2);
+
+        let (release, wait) = mpsc::channel::<()>();
+        let slow = worker
+            .run(move || {
+                let _ = wait.recv();
+                3
+            })
+            .unwrap();
+        assert!(matches!(worker.run(|| 4), Err(Error::Busy)));
+        // A clone has its own worker
+        assert_eq!(worker.clone().run(|| 5).unwrap().recv().unwrap(), 5);
+        release.send(()).unwrap();
+        assert_eq!(slow.recv().unwrap(), 3);
+
+        let panicked = worker.run(|| -> i32 { panic!("boom") }).unwrap();
+        assert!(panicked.recv().is_err());
+        assert_eq!(worker.run(|| 6).unwrap().recv().unwrap(), 6);
+    }
+}
@@ -0,0 +1,295 @@
+//! Compiled-in catalog of KQL words
+//!
+//! Operator, keyword, type and built-in function names known without the
+//! native library, shared by the pure-Rust fallback and the static
+//! completions.
+
+/// Tabular operators, expected after `|` or at the start of a statement
+pub(crate) const QUERY_OPERATORS: &[&str] = &[
+    "as",
+    "consume",
+    "count",
+    "datatable",
+    "distinct",
+    "evaluate",
+    "extend",
+    "externaldata",
+    "facet",
+    "filter",
+    "find",
+    "fork",
+    "getschema",
+    "invoke",
+    "join",
+    "limit",
+    "lookup",
+    "make-series",
+    "mv-apply",
+    "mv-expand",
+    "order",
+    "parse",
+    "parse-kv",
+    "parse-where",
+    "partition",
+    "print",
+    "project",
+    "project-away",
+    "project-keep",
+    "project-rename",
+    "project-reorder",
+    "range",
+    "reduce",
+    "render",
+    "sample",
+    "sample-distinct",
+    "scan",
+    "search",
+    "serialize",
+    "sort",
+    "summarize",
+    "take",
+    "top",
+    "top-hitters",
+    "top-nested",
+    "union",
+    "where",
+];
+
+/// Other reserved words
+pub(crate) const KEYWORDS: &[&str] = &[
+    "alias",
+    "asc",
+    "by",
+    "declare",
+    "desc",
+    "first",
+    "from",
+    "kind",
+    "last",
+    "let",
+    "nulls",
+    "of",
+    "on",
+    "pattern",
+    "query_parameters",
+    "restrict",
+    "set",
+    "step",
+    "to",
+    "typeof",
+    "with",
+];
+
+/// Word operators between scalar expressions (each also negates with `!`)
+pub(crate) const SCALAR_OPERATORS: &[&str] = &[
+    "and",
+    "between",
+    "contains",
+    "contains_cs",
+    "endswith",
+    "endswith_cs",
+    "has",
+    "has_all",
+    "has_any",
+    "has_cs",
+    "hasprefix",
+    "hasprefix_cs",
+    "hassuffix",
+    "hassuffix_cs",
+    "in",
+    "matches",
+    "or",
+    "regex",
+    "startswith",
+    "startswith_cs",
+];
+
+/// Scalar type names
+pub(crate) const TYPES: &[&str] = &[
+    "bool", "boolean", "date", "datetime", "decimal", "double", "dynamic", "guid", "int", "long",
+    "real", "string", "time", "timespan", "uniqueid", "uuid",
+];
+
+/// Built-in scalar functions
+pub(crate) const FUNCTIONS: &[&str] = &[
+    "abs",
+    "acos",
+    "ago",
+    "array_concat",
+    "array_index_of",
+    "array_length",
+    "array_reverse",
+    "array_slice",
+    "array_sort_asc",
+    "array_sort_desc",
+    "array_sum",
+    "asin",
+    "atan",
+    "atan2",
+    "bag_has_key",
+    "bag_keys",
+    "bag_merge",
+    "bag_pack",
+    "bag_remove_keys",
+    "base64_decode_tostring",
+    "base64_encode_tostring",
+    "bin",
+    "bin_at",
+    "case",
+    "ceiling",
+    "coalesce",
+    "cos",
+    "countof",
+    "datetime_add",
+    "datetime_diff",
+    "datetime_part",
+    "dayofmonth",
+    "dayofweek",
+    "dayofyear",
+    "endofday",
+    "endofmonth",
+    "endofweek",
+    "endofyear",
+    "exp",
+    "extract",
+    "extract_all",
+    "format_datetime",
+    "format_timespan",
+    "geo_distance_2points",
+    "getmonth",
+    "gettype",
+    "getyear",
+    "has_any_index",
+    "hash",
+    "hash_sha256",
+    "hourofday",
+    "iff",
+    "iif",
+    "indexof",
+    "ipv4_compare",
+    "ipv4_is_in_range",
+    "ipv4_is_private",
+    "isempty",
+    "isfinite",
+    "isinf",
+    "isnan",
+    "isnotempty",
+    "isnotnull",
+    "isnull",
+    "log",
+    "log10",
+    "log2",
+    "make_datetime",
+    "make_timespan",
+    "max_of",
+    "min_of",
+    "monthofyear",
+    "new_guid",
+    "not",
+    "now",
+    "pack",
+    "pack_array",
+    "parse_command_line",
+    "parse_csv",
+    "parse_ipv4",
+    "parse_json",
+    "parse_path",
+    "parse_url",
+    "parse_user_agent",
+    "parse_xml",
+    "percentile_tdigest",
+    "pow",
+    "rand",
+    "range",
+    "repeat",
+    "replace_regex",
+    "replace_string",
+    "reverse",
+    "round",
+    "set_difference",
+    "set_has_element",
+    "set_intersect",
+    "set_union",
+    "sign",
+    "sin",
+    "split",
+    "sqrt",
+    "startofday",
+    "startofmonth",
+    "startofweek",
+    "startofyear",
+    "strcat",
+    "strcat_array",
+    "strcat_delim",
+    "strcmp",
+    "string_size",
+    "strlen",
+    "strrep",
+    "substring",
+    "tan",
+    "tobool",
+    "todatetime",
+    "todecimal",
+    "todouble",
+    "todynamic",
+    "toguid",
+    "tohex",
+    "toint",
+    "tolong",
+    "tolower",
+    "toreal",
+    "tostring",
+    "totimespan",
+    "toupper",
+    "translate",
+    "trim",
+    "trim_end",
+    "trim_start",
+    "unixtime_milliseconds_todatetime",
+    "unixtime_seconds_todatetime",
+    "url_decode",
+    "url_encode",
+    "week_of_year",
+    "zip",
+];
+
+/// Built-in aggregation functions, valid in `summarize`
+pub(crate) const AGGREGATE_FUNCTIONS: &[&str] = &[
+    "any",
+    "arg_max",
+    "arg_min",
+    "avg",
+    "avgif",
+    "buildschema",
+    "count",
+    "count_distinct",
+    "count_distinctif",
+    "countif",
+    "dcount",
+    "dcountif",
+    "hll",
+    "hll_merge",
+    "make_bag",
+    "make_bag_if",
+    "make_list",
+    "make_list_if",
+    "make_list_with_nulls",
+    "make_set",
+    "make_set_if",
+    "max",
+    "maxif",
+    "min",
+    "minif",
+    "percentile",
+    "percentiles",
+    "percentilesw",
+    "stdev",
+    "stdevif",
+    "sum",
+    "sumif",
+    "take_any",
+    "take_anyif",
+    "tdigest",
+    "variance",
+    "varianceif",
+];
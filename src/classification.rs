@@ -3,12 +3,13 @@
 //! This module provides types and functionality for classifying KQL syntax
 //! elements for syntax highlighting purposes.
 
+use crate::positions::LineIndex;
 use serde::{Deserialize, Serialize};
 
 /// Classification kind for syntax highlighting
 ///
 /// These values match the `ClassificationKind` enum from Kusto.Language
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "PascalCase")]
 pub enum ClassificationKind {
     /// Plain text (no special highlighting)
@@ -105,17 +106,251 @@ impl ClassificationKind {
 /// A classified span for syntax highlighting
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ClassifiedSpan {
-    /// Start offset (0-based)
+    /// Start offset (0-based, character position)
     pub start: usize,
-    /// Length of the span
+    /// Length of the span in characters
     pub length: usize,
     /// Classification kind
     pub kind: ClassificationKind,
 }
 
+/// A classified span with its source text and line/column range
+///
+/// Returned by [`ClassificationResult::spans_with_text`]. Lines and columns
+/// are 1-based and count characters; the end position is just past the
+/// span's last character.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SpanWithText<'a> {
+    /// Start offset (0-based, character position)
+    pub start: usize,
+    /// Length of the span in characters
+    pub length: usize,
+    /// Classification kind
+    pub kind: ClassificationKind,
+    /// The span's text in the query
+    pub text: &'a str,
+    /// Line of the start offset (1-based)
+    pub start_line: usize,
+    /// Column of the start offset (1-based, in characters)
+    pub start_column: usize,
+    /// Line of the end offset (1-based)
+    pub end_line: usize,
+    /// Column of the end offset (1-based, in characters)
+    pub end_column: usize,
+}
+
 /// Result of syntax classification
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ClassificationResult {
     /// Classified spans
     pub spans: Vec<ClassifiedSpan>,
 }
+
+impl ClassificationResult {
+    /// Make the spans a sorted, non-overlapping, gap-free covering of `query`
+    ///
+    /// Spans are sorted by start offset. Where spans overlap, the one
+    /// starting first (or, at the same start, the longer one) keeps the
+    /// shared text and the other is clipped; spans past the end of the
+    /// query are cut off. Gaps, such as whitespace, become
+    /// [`PlainText`](ClassificationKind::PlainText) spans, and adjacent
+    /// plain text spans are merged, so the span lengths add up to the
+    /// query's length in characters.
+    pub fn make_contiguous(&mut self, query: &str) {
+        let total = query.chars().count();
+        let mut spans = std::mem::take(&mut self.spans);
+        spans.sort_by_key(|span| (span.start, std::cmp::Reverse(span.length)));
+
+        let mut covered: Vec<ClassifiedSpan> = Vec::with_capacity(spans.len() * 2 + 1);
+        let mut push = |start: usize, end: usize, kind: ClassificationKind| match covered.last_mut()
+        {
+            Some(last) if kind == ClassificationKind::PlainText && last.kind == kind => {
+                last.length += end - start;
+            }
+            _ => covered.push(ClassifiedSpan {
+                start,
+                length: end - start,
+                kind,
+            }),
+        };
+
+        let mut position = 0;
+        for span in spans {
+            let start = span.start.max(position);
+            let end = (span.start + span.length).min(total);
+            if start >= end {
+                continue;
+            }
+            if start > position {
+                push(position, start, ClassificationKind::PlainText);
+            }
+            push(start, end, span.kind);
+            position = end;
+        }
+        if position < total {
+            push(position, total, ClassificationKind::PlainText);
+        }
+        self.spans = covered;
+    }
+
+    /// Get the spans with their text in `query` and their line/column ranges
+    ///
+    /// Span offsets are characters, as the validator returns them after
+    /// converting the native UTF-16 offsets, so spans after multi-byte or
+    /// astral characters slice the right text. Spans reaching past the end
+    /// of `query` are clipped to it.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use kql_language_tools::{ClassificationKind, ClassificationResult, ClassifiedSpan};
+    ///
+    /// let query = "print 'é'\n| take 1";
+    /// let result = ClassificationResult {
+    ///     spans: vec![ClassifiedSpan { start: 12, length: 4, kind: ClassificationKind::QueryOperator }],
+    /// };
+    /// let spans = result.spans_with_text(query);
+    /// assert_eq!(spans[0].text, "take");
+    /// assert_eq!((spans[0].start_line, spans[0].start_column), (2, 3));
+    /// ```
+    #[must_use]
+    pub fn spans_with_text<'a>(&self, query: &'a str) -> Vec<SpanWithText<'a>> {
+        let index = LineIndex::new(query);
+        self.spans
+            .iter()
+            .map(|span| {
+                let start = span.start.min(index.len_chars());
+                let end = (span.start + span.length).min(index.len_chars());
+                let from = index.line_col(start);
+                let to = index.line_col(end);
+                SpanWithText {
+                    start: span.start,
+                    length: span.length,
+                    kind: span.kind,
+                    text: &query[index.char_to_byte(start)..index.char_to_byte(end)],
+                    start_line: from.line + 1,
+                    start_column: from.col + 1,
+                    end_line: to.line + 1,
+                    end_column: to.col + 1,
+                }
+            })
+            .collect()
+    }
+}
+
+/// Options for [`KqlValidator::get_classifications_with_options`](crate::KqlValidator::get_classifications_with_options)
+///
+/// # Example
+///
+/// ```
+/// use kql_language_tools::ClassificationOptions;
+///
+/// // Every character of the query in exactly one span
+/// let options = ClassificationOptions::default().contiguous(true);
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ClassificationOptions {
+    /// Return a sorted, non-overlapping covering of the whole query, with
+    /// whitespace and unclassified text as `PlainText` spans (see
+    /// [`ClassificationResult::make_contiguous`])
+    pub contiguous: bool,
+}
+
+impl ClassificationOptions {
+    /// Builder method to cover the whole query with sorted, non-overlapping spans
+    #[must_use]
+    pub fn contiguous(mut self, contiguous: bool) -> Self {
+        self.contiguous = contiguous;
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn span(start: usize, length: usize, kind: ClassificationKind) -> ClassifiedSpan {
+        ClassifiedSpan {
+            start,
+            length,
+            kind,
+        }
+    }
+
+    #[test]
+    fn test_make_contiguous() {
+        use ClassificationKind as Kind;
+
+        // "T | take 10 // é", with overlapping, unsorted and overlong spans
+        let query = "T | take 10 // é";
+        let mut result = ClassificationResult {
+            spans: vec![
+                span(9, 2, Kind::Literal),
+                span(0, 1, Kind::Table),
+                span(4, 4, Kind::QueryOperator),
+                span(6, 2, Kind::Identifier),
+                span(2, 1, Kind::PlainText),
+                span(12, 10, Kind::Comment),
+            ],
+        };
+        result.make_contiguous(query);
+
+        let spans: Vec<_> = result
+            .spans
+            .iter()
+            .map(|s| (s.start, s.length, s.kind))
+            .collect();
+        assert_eq!(
+            spans,
+            [
+                (0, 1, Kind::Table),
+                (1, 3, Kind::PlainText),
+                (4, 4, Kind::QueryOperator),
+                (8, 1, Kind::PlainText),
+                (9, 2, Kind::Literal),
+                (11, 1, Kind::PlainText),
+                (12, 4, Kind::Comment),
+            ]
+        );
+
+        let mut empty = ClassificationResult::default();
+        empty.make_contiguous("  ");
+        assert_eq!(empty.spans.len(), 1);
+        assert_eq!(empty.spans[0].length, 2);
+    }
+
+    #[test]
+    fn test_spans_with_text() {
+        use ClassificationKind as Kind;
+
+        let query = "print s = '😀é'\r\n| take 1";
+        // As the native library classifies it: in UTF-16 code units, with
+        // the emoji counting twice
+        let native = r#"{"spans":[
+            {"start":0,"length":5,"kind":"Keyword"},
+            {"start":10,"length":5,"kind":"StringLiteral"},
+            {"start":17,"length":1,"kind":"Punctuation"},
+            {"start":19,"length":4,"kind":"QueryOperator"},
+            {"start":24,"length":1,"kind":"Literal"}
+        ]}"#;
+        let mut result: ClassificationResult = serde_json::from_str(native).unwrap();
+        crate::positions::from_native(query, &mut result);
+        let spans = result.spans_with_text(query);
+
+        let texts: Vec<_> = spans.iter().map(|s| s.text).collect();
+        assert_eq!(texts, ["print", "'😀é'", "|", "take", "1"]);
+        assert_eq!((spans[1].start, spans[1].length), (10, 4));
+        assert_eq!((spans[1].start_line, spans[1].start_column), (1, 11));
+        assert_eq!((spans[1].end_line, spans[1].end_column), (1, 15));
+        assert_eq!((spans[3].start_line, spans[3].start_column), (2, 3));
+        assert_eq!((spans[4].end_line, spans[4].end_column), (2, 9));
+
+        // Spans past the end of the query are clipped to it
+        let overlong = ClassificationResult {
+            spans: vec![span(23, 5, Kind::Literal)],
+        };
+        let spans = overlong.spans_with_text(query);
+        assert_eq!(spans[0].text, "1");
+        assert_eq!(spans[0].length, 5);
+    }
+}
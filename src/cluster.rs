@@ -0,0 +1,299 @@
+//! Live schema fetch from a Kusto cluster
+//!
+//! Enabled by the `azure` feature. [`Schema::fetch_from_cluster`] runs
+//! `.show database schema as json` through the Kusto REST API and reads the
+//! result with [`Schema::from_kusto_show_schema`], so tools can validate
+//! against whatever the cluster currently defines:
+//!
+//! ```no_run
+//! use kql_language_tools::{KustoCredential, Schema};
+//!
+//! async fn load() -> Result<Schema, kql_language_tools::Error> {
+//!     Schema::fetch_from_cluster(
+//!         "https://help.kusto.windows.net",
+//!         "Samples",
+//!         &KustoCredential::AzureCli,
+//!     )
+//!     .await
+//! }
+//! ```
+//!
+//! Requests block, so they run on tokio's blocking thread pool.
+//! [`Schema::fetch_from_cluster_cached`] keeps the schema on disk and only
+//! asks the cluster again once the cached copy is older than a TTL.
+
+use crate::error::Error;
+use crate::schema::Schema;
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// How to authenticate to a Kusto cluster
+#[derive(Clone)]
+pub enum KustoCredential {
+    /// A bearer token already issued for the cluster
+    Token(String),
+    /// A Microsoft Entra application, using the client credentials flow
+    ClientSecret {
+        /// Directory (tenant) ID
+        tenant_id: String,
+        /// Application (client) ID
+        client_id: String,
+        /// Client secret value
+        client_secret: String,
+    },
+    /// The account signed in to the Azure CLI (`az account get-access-token`)
+    AzureCli,
+}
+
+impl std::fmt::Debug for KustoCredential {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Secrets are left out so credentials can be logged
+        match self {
+            Self::Token(_) => f.write_str("Token(..)"),
+            Self::ClientSecret {
+                tenant_id,
+                client_id,
+                ..
+            } => f
+                .debug_struct("ClientSecret")
+                .field("tenant_id", tenant_id)
+                .field("client_id", client_id)
+                .finish_non_exhaustive(),
+            Self::AzureCli => f.write_str("AzureCli"),
+        }
+    }
+}
+
+impl KustoCredential {
+    /// Get a bearer token for `endpoint`
+    fn token(&self, endpoint: &str) -> Result<String, Error> {
+        match self {
+            Self::Token(token) => Ok(token.clone()),
+            Self::ClientSecret {
+                tenant_id,
+                client_id,
+                client_secret,
+            } => {
+                let url =
+                    format!("https://login.microsoftonline.com/{tenant_id}/oauth2/v2.0/token");
+                let scope = format!("{endpoint}/.default");
+                let response = ureq::post(&url)
+                    .send_form(&[
+                        ("grant_type", "client_credentials"),
+                        ("client_id", client_id),
+                        ("client_secret", client_secret),
+                        ("scope", &scope),
+                    ])
+                    .map_err(|err| cluster_error("token request failed", err))?
+                    .into_string()?;
+                let response: TokenResponse = serde_json::from_str(&response)?;
+                Ok(response.access_token)
+            }
+            Self::AzureCli => {
+                let output = std::process::Command::new("az")
+                    .args(["account", "get-access-token", "--resource", endpoint])
+                    .args(["--query", "accessToken", "--output", "tsv"])
+                    .output()?;
+                if !output.status.success() {
+                    return Err(Error::Cluster {
+                        message: format!(
+                            "az account get-access-token failed: {}",
+                            String::from_utf8_lossy(&output.stderr).trim()
+                        ),
+                    });
+                }
+                Ok(std::str::from_utf8(&output.stdout)?.trim().to_string())
+            }
+        }
+    }
+}
+
+impl Schema {
+    /// Fetch a database's schema from a Kusto cluster
+    ///
+    /// `endpoint` is the cluster URL, such as
+    /// `https://help.kusto.windows.net`. Tables, materialized views and
+    /// functions are read as [`Schema::from_kusto_show_schema`] reads them.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Cluster`] if authentication or the request fails,
+    /// and [`Error::Json`] if the response is not a schema.
+    pub async fn fetch_from_cluster(
+        endpoint: &str,
+        database: &str,
+        credential: &KustoCredential,
+    ) -> Result<Self, Error> {
+        let endpoint = endpoint.trim_end_matches('/').to_string();
+        let database = database.to_string();
+        let credential = credential.clone();
+        tokio::task::spawn_blocking(move || fetch(&endpoint, &database, &credential))
+            .await
+            .map_err(|err| Error::Internal {
+                message: format!("Schema fetch task failed: {err}"),
+            })?
+    }
+
+    /// Fetch a database's schema, reusing a copy cached in `cache_dir`
+    ///
+    /// The cached copy is used while it is younger than `ttl`; otherwise the
+    /// schema is fetched with [`Schema::fetch_from_cluster`] and the cache
+    /// rewritten. Each cluster and database has its own cache file, so one
+    /// directory can serve several.
+    ///
+    /// # Errors
+    ///
+    /// As [`Schema::fetch_from_cluster`], and [`Error::Io`] if the cache
+    /// cannot be written.
+    pub async fn fetch_from_cluster_cached(
+        endpoint: &str,
+        database: &str,
+        credential: &KustoCredential,
+        cache_dir: impl AsRef<Path>,
+        ttl: Duration,
+    ) -> Result<Self, Error> {
+        let path = cache_path(cache_dir.as_ref(), endpoint, database);
+        if let Some(schema) = read_cache(&path, ttl) {
+            return Ok(schema);
+        }
+        let schema = Self::fetch_from_cluster(endpoint, database, credential).await?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        schema.save(&path)?;
+        Ok(schema)
+    }
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct QueryResponse {
+    tables: Vec<QueryTable>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct QueryTable {
+    rows: Vec<Vec<serde_json::Value>>,
+}
+
+fn fetch(endpoint: &str, database: &str, credential: &KustoCredential) -> Result<Schema, Error> {
+    let token = credential.token(endpoint)?;
+    let body = serde_json::json!({
+        "db": database,
+        "csl": ".show database schema as json",
+    });
+    let response = ureq::post(&format!("{endpoint}/v1/rest/mgmt"))
+        .set("Authorization", &format!("Bearer {token}"))
+        .set("Accept", "application/json")
+        .set("Content-Type", "application/json; charset=utf-8")
+        .set("x-ms-app", "kql-language-tools")
+        .send_string(&body.to_string())
+        .map_err(|err| cluster_error("schema request failed", err))?
+        .into_string()?;
+    parse_response(&response)
+}
+
+/// Read the schema from a `/v1/rest/mgmt` response
+fn parse_response(response: &str) -> Result<Schema, Error> {
+    let response: QueryResponse = serde_json::from_str(response)?;
+    let cell = response
+        .tables
+        .first()
+        .and_then(|table| table.rows.first())
+        .and_then(|row| row.first())
+        .and_then(serde_json::Value::as_str)
+        .ok_or_else(|| Error::Cluster {
+            message: "response holds no schema".to_string(),
+        })?;
+    Schema::from_kusto_show_schema(cell)
+}
+
+/// Cache file for a cluster's database, named after both
+fn cache_path(dir: &Path, endpoint: &str, database: &str) -> PathBuf {
+    let host = endpoint
+        .trim_start_matches("https://")
+        .trim_start_matches("http://")
+        .trim_end_matches('/');
+    let name: String = format!("{host}_{database}")
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '.' || c == '-' || c == '_' {
+                c.to_ascii_lowercase()
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    dir.join(format!("{name}.json"))
+}
+
+/// The cached schema, if it exists, parses and is younger than `ttl`
+fn read_cache(path: &Path, ttl: Duration) -> Option<Schema> {
+    let modified = std::fs::metadata(path).ok()?.modified().ok()?;
+    let age = SystemTime::now()
+        .duration_since(modified)
+        .unwrap_or_default();
+    if age >= ttl {
+        return None;
+    }
+    Schema::from_path(path).ok()
+}
+
+fn cluster_error(context: &str, err: ureq::Error) -> Error {
+    let message = match err {
+        ureq::Error::Status(code, response) => {
+            let body = response.into_string().unwrap_or_default();
+            format!("{context}: HTTP {code} {}", body.trim())
+        }
+        ureq::Error::Transport(transport) => format!("{context}: {transport}"),
+    };
+    Error::Cluster { message }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::Table;
+
+    #[test]
+    fn test_parse_response() {
+        let show = r#"{"Databases":{"Samples":{"Name":"Samples","Tables":{"StormEvents":{"Name":"StormEvents","OrderedColumns":[{"Name":"State","CslType":"string"}]}},"Functions":{}}}}"#;
+        let response = serde_json::json!({
+            "Tables": [{
+                "TableName": "Table_0",
+                "Columns": [{"ColumnName": "DatabaseSchema", "DataType": "String"}],
+                "Rows": [[show]],
+            }]
+        });
+        let schema = parse_response(&response.to_string()).unwrap();
+        assert_eq!(schema.database.as_deref(), Some("Samples"));
+        assert!(schema.get_table("StormEvents").is_some());
+
+        assert!(matches!(
+            parse_response(r#"{"Tables":[{"Rows":[]}]}"#),
+            Err(Error::Cluster { .. })
+        ));
+    }
+
+    #[test]
+    fn test_schema_cache() {
+        let dir = std::env::temp_dir().join(format!("kql-cluster-cache-{}", std::process::id()));
+        let path = cache_path(&dir, "https://Help.kusto.windows.net/", "My DB");
+        assert_eq!(path, dir.join("help.kusto.windows.net_my_db.json"));
+
+        assert!(read_cache(&path, Duration::from_secs(60)).is_none());
+        std::fs::create_dir_all(&dir).unwrap();
+        let schema = Schema::with_database("My DB").table(Table::new("T").with_column("a", "long"));
+        schema.save(&path).unwrap();
+        assert_eq!(read_cache(&path, Duration::from_secs(60)), Some(schema));
+        assert!(read_cache(&path, Duration::ZERO).is_none());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
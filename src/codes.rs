@@ -0,0 +1,249 @@
+//! Typed diagnostic codes
+//!
+//! Diagnostics carry their code as a string (`KS142`, `SL003`, ...).
+//! [`KustoDiagnosticCode`] parses it into something `match` can use, so
+//! callers can react to specific errors without inspecting messages.
+//! Codes this crate does not know parse to
+//! [`Unknown`](KustoDiagnosticCode::Unknown) and round-trip unchanged.
+
+use std::fmt;
+use std::str::FromStr;
+
+/// A parsed diagnostic code
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum KustoDiagnosticCode {
+    /// A Kusto.Language diagnostic (`KS###`), by number
+    Kusto(u16),
+    /// More than three joins, unions or mv-expands in a Resource Graph query (`ARG001`)
+    ResourceGraphOperatorLimit,
+    /// Join kind not supported by Resource Graph (`ARG002`)
+    UnsupportedJoinKind,
+    /// Case-insensitive operator used with an exact-case literal (`CS001`)
+    CaseSensitiveVariant,
+    /// Join or lookup key whose sides have different types (`JN001`)
+    JoinKeyTypeMismatch,
+    /// High-cardinality join key without a shuffle hint (`JN002`)
+    HighCardinalityJoinKey,
+    /// Text is a query, not a control command (`KC001`)
+    NotACommand,
+    /// DCR transformation reads from a table other than `source` (`DCR002`)
+    DcrInvalidSource,
+    /// Statement not supported in DCR transformations (`DCR004`)
+    DcrUnsupportedStatement,
+    /// Property path never seen in the column's sample events (`DS001`)
+    UnseenDynamicPath,
+    /// Property path not among the column's declared fields (`DS002`)
+    UndeclaredDynamicField,
+    /// Feature introduced after the pinned engine feature level (`EL001`)
+    FeatureUnavailable,
+    /// Bracket without a matching partner, from the fallback checker (`FB001`)
+    UnbalancedBracket,
+    /// String literal not closed, from the fallback checker (`FB002`)
+    UnterminatedString,
+    /// `|` without a query operator after it, from the fallback checker (`FB003`)
+    EmptyPipelineStage,
+    /// Tabular operator not allowed by a feature profile (`FP001`)
+    OperatorNotAllowed,
+    /// Evaluate plugin not allowed by a feature profile (`FP002`)
+    PluginNotAllowed,
+    /// Function not allowed by a feature profile (`FP003`)
+    FunctionNotAllowed,
+    /// Pipeline has more operators than a feature profile allows (`FP004`)
+    PipelineTooDeep,
+    /// Scoping function has the wrong number or form of arguments (`LA001`)
+    InvalidScopeArgument,
+    /// Scoping function used other than as a table source (`LA002`)
+    InvalidScopePosition,
+    /// Table not found in a known scope (`LA003`)
+    UnknownScopeTable,
+    /// `adx()` source without a remote schema (`LA004`)
+    UnresolvedAdxSource,
+    /// `search *` or `union *` (`LNT001`)
+    SearchStar,
+    /// Query over a table without a time filter (`LNT002`)
+    MissingTimeFilter,
+    /// Query text is longer than allowed (`SL001`)
+    QueryTooLong,
+    /// Identifier is longer than allowed (`SL002`)
+    IdentifierTooLong,
+    /// Summarize has more `by` keys than allowed (`SL003`)
+    TooManySummarizeKeys,
+    /// Literal `in` / `!in` list has more values than allowed (`SL004`)
+    TooManyInListValues,
+    /// Any other code, as reported
+    Unknown(String),
+}
+
+/// Where a diagnostic comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CodeCategory {
+    /// The query does not parse
+    Syntax,
+    /// The query parses but does not make sense against the schema or settings
+    Semantic,
+    /// The query is valid but a best-practice check flagged it
+    Analyzer,
+    /// The query exceeds a Kusto service limit; these diagnostics carry
+    /// [`DiagnosticCategory::ServiceLimit`](crate::DiagnosticCategory::ServiceLimit)
+    ServiceLimit,
+}
+
+/// Codes with a fixed spelling, in both directions
+const NAMED: &[(&str, KustoDiagnosticCode)] = &[
+    ("ARG001", KustoDiagnosticCode::ResourceGraphOperatorLimit),
+    ("ARG002", KustoDiagnosticCode::UnsupportedJoinKind),
+    ("CS001", KustoDiagnosticCode::CaseSensitiveVariant),
+    ("JN001", KustoDiagnosticCode::JoinKeyTypeMismatch),
+    ("JN002", KustoDiagnosticCode::HighCardinalityJoinKey),
+    ("KC001", KustoDiagnosticCode::NotACommand),
+    ("DCR002", KustoDiagnosticCode::DcrInvalidSource),
+    ("DCR004", KustoDiagnosticCode::DcrUnsupportedStatement),
+    ("DS001", KustoDiagnosticCode::UnseenDynamicPath),
+    ("DS002", KustoDiagnosticCode::UndeclaredDynamicField),
+    ("EL001", KustoDiagnosticCode::FeatureUnavailable),
+    ("FB001", KustoDiagnosticCode::UnbalancedBracket),
+    ("FB002", KustoDiagnosticCode::UnterminatedString),
+    ("FB003", KustoDiagnosticCode::EmptyPipelineStage),
+    ("FP001", KustoDiagnosticCode::OperatorNotAllowed),
+    ("FP002", KustoDiagnosticCode::PluginNotAllowed),
+    ("FP003", KustoDiagnosticCode::FunctionNotAllowed),
+    ("FP004", KustoDiagnosticCode::PipelineTooDeep),
+    ("LA001", KustoDiagnosticCode::InvalidScopeArgument),
+    ("LA002", KustoDiagnosticCode::InvalidScopePosition),
+    ("LA003", KustoDiagnosticCode::UnknownScopeTable),
+    ("LA004", KustoDiagnosticCode::UnresolvedAdxSource),
+    ("LNT001", KustoDiagnosticCode::SearchStar),
+    ("LNT002", KustoDiagnosticCode::MissingTimeFilter),
+    ("SL001", KustoDiagnosticCode::QueryTooLong),
+    ("SL002", KustoDiagnosticCode::IdentifierTooLong),
+    ("SL003", KustoDiagnosticCode::TooManySummarizeKeys),
+    ("SL004", KustoDiagnosticCode::TooManyInListValues),
+];
+
+impl KustoDiagnosticCode {
+    /// Parse a code string
+    #[must_use]
+    pub fn parse(code: &str) -> Self {
+        if let Some((_, known)) = NAMED.iter().find(|(name, _)| *name == code) {
+            return known.clone();
+        }
+
+        code.strip_prefix("KS")
+            .filter(|digits| digits.len() == 3 && digits.bytes().all(|b| b.is_ascii_digit()))
+            .and_then(|digits| digits.parse().ok())
+            .map_or_else(|| Self::Unknown(code.to_string()), Self::Kusto)
+    }
+
+    /// Get the category of the code
+    ///
+    /// Kusto.Language numbers parser diagnostics below `KS100` and its
+    /// analyzers from `KS500`; everything in between is semantic. The
+    /// service-limit checks (`SL###`) have their own category, as their
+    /// diagnostics do. Unknown codes are treated as semantic.
+    #[must_use]
+    pub fn category(&self) -> CodeCategory {
+        match self {
+            Self::Kusto(n) if *n < 100 => CodeCategory::Syntax,
+            Self::Kusto(n) if *n >= 500 => CodeCategory::Analyzer,
+            Self::CaseSensitiveVariant
+            | Self::JoinKeyTypeMismatch
+            | Self::HighCardinalityJoinKey
+            | Self::SearchStar
+            | Self::MissingTimeFilter => CodeCategory::Analyzer,
+            Self::UnbalancedBracket | Self::UnterminatedString | Self::EmptyPipelineStage => {
+                CodeCategory::Syntax
+            }
+            Self::QueryTooLong
+            | Self::IdentifierTooLong
+            | Self::TooManySummarizeKeys
+            | Self::TooManyInListValues => CodeCategory::ServiceLimit,
+            _ => CodeCategory::Semantic,
+        }
+    }
+
+    /// Check if the code reports a name that does not resolve
+    ///
+    /// These are Kusto.Language's "does not refer to any known" errors
+    /// (`KS142` for any name, `KS204` for table sources) and tables missing
+    /// from a scope (`LA003`).
+    #[must_use]
+    pub fn is_unresolved_name(&self) -> bool {
+        matches!(self, Self::Kusto(142 | 204) | Self::UnknownScopeTable)
+    }
+}
+
+impl FromStr for KustoDiagnosticCode {
+    type Err = std::convert::Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Self::parse(s))
+    }
+}
+
+impl fmt::Display for KustoDiagnosticCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Kusto(n) => write!(f, "KS{n:03}"),
+            Self::Unknown(code) => f.write_str(code),
+            named => {
+                let (name, _) = NAMED
+                    .iter()
+                    .find(|(_, code)| code == named)
+                    .expect("every named code is in NAMED");
+                f.write_str(name)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_round_trip() {
+        for code in [
+            "KS142", "KS005", "KS510", "SL003", "LNT001", "XX9", "KS12", "KS0142", "KSabc",
+        ] {
+            assert_eq!(KustoDiagnosticCode::parse(code).to_string(), code);
+        }
+        assert_eq!(
+            KustoDiagnosticCode::parse("KS142"),
+            KustoDiagnosticCode::Kusto(142)
+        );
+        assert_eq!(
+            KustoDiagnosticCode::parse("SL003"),
+            KustoDiagnosticCode::TooManySummarizeKeys
+        );
+        assert_eq!(
+            KustoDiagnosticCode::parse("KS12"),
+            KustoDiagnosticCode::Unknown("KS12".to_string())
+        );
+    }
+
+    #[test]
+    fn test_category() {
+        let category = |code: &str| KustoDiagnosticCode::parse(code).category();
+        assert_eq!(category("KS005"), CodeCategory::Syntax);
+        assert_eq!(category("KS142"), CodeCategory::Semantic);
+        assert_eq!(category("KS510"), CodeCategory::Analyzer);
+        assert_eq!(category("CS001"), CodeCategory::Analyzer);
+        assert_eq!(category("JN002"), CodeCategory::Analyzer);
+        assert_eq!(category("LA003"), CodeCategory::Semantic);
+        assert_eq!(category("ARG002"), CodeCategory::Semantic);
+        assert_eq!(category("FB001"), CodeCategory::Syntax);
+        assert_eq!(category("ORG001"), CodeCategory::Semantic);
+        assert_eq!(category("SL003"), CodeCategory::ServiceLimit);
+    }
+
+    #[test]
+    fn test_is_unresolved_name() {
+        let unresolved = |code: &str| KustoDiagnosticCode::parse(code).is_unresolved_name();
+        assert!(unresolved("KS142"));
+        assert!(unresolved("KS204"));
+        assert!(unresolved("LA003"));
+        assert!(!unresolved("KS005"));
+        assert!(!unresolved("SL002"));
+        assert!(!unresolved("KS1420"));
+    }
+}
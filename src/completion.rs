@@ -2,6 +2,10 @@
 //!
 //! This module provides types and functionality for KQL code completion.
 
+use crate::catalog::{
+    AGGREGATE_FUNCTIONS, FUNCTIONS, KEYWORDS, QUERY_OPERATORS, SCALAR_OPERATORS, TYPES,
+};
+use crate::kql_type::KqlType;
 use serde::{Deserialize, Serialize};
 
 /// A completion item
@@ -23,6 +27,396 @@ pub struct CompletionItem {
     /// Character position where replacement should start
     #[serde(default)]
     pub edit_start: usize,
+    /// Markdown documentation
+    ///
+    /// Completion lists leave this out; fill it in with
+    /// [`KqlValidator::resolve_completion`](crate::KqlValidator::resolve_completion).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub documentation: Option<String>,
+    /// Whether the item's type is [`CompletionResult::expected_type`]
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub matches_expected_type: bool,
+}
+
+impl CompletionItem {
+    /// LSP `sortText` for this item
+    ///
+    /// LSP clients sort completions by comparing `sortText` as strings, so
+    /// `sort_order` is offset to be non-negative and zero-padded to a fixed
+    /// width, followed by the case-folded label to keep ties stable.
+    #[must_use]
+    pub fn sort_text(&self) -> String {
+        let order = i64::from(self.sort_order) - i64::from(i32::MIN);
+        format!("{order:010}:{}", self.label.to_lowercase())
+    }
+
+    /// LSP `filterText` for this item
+    ///
+    /// The label case-folded, with bracket quoting (`['My Column']`) removed
+    /// so typing the bare name still matches.
+    #[must_use]
+    pub fn filter_text(&self) -> String {
+        let label = self.label.as_str();
+        let unquoted = ["['", "[\""]
+            .iter()
+            .zip(["']", "\"]"])
+            .find_map(|(open, close)| label.strip_prefix(open)?.strip_suffix(close))
+            .unwrap_or(label);
+        unquoted.to_lowercase()
+    }
+}
+
+/// Per-call completion filtering options
+///
+/// Filtering is done by the native library before results are serialized,
+/// so large schemas do not produce huge payloads that are then discarded.
+///
+/// Matching narrows items to those fitting the partially typed word before
+/// the cursor, and ranking orders them so the likeliest come first: the
+/// better the match, then columns and other names before keywords (except
+/// where an operator is expected, such as after a `|`), then Kusto's own
+/// order. `max_items` applies after both.
+///
+/// # Example
+///
+/// ```
+/// use kql_language_tools::{CompletionKind, CompletionMatch, CompletionOptions};
+///
+/// let options = CompletionOptions::default()
+///     .include_punctuation(false)
+///     .matching(CompletionMatch::Fuzzy)
+///     .ranked(true)
+///     .max_items(50);
+///
+/// // Only columns
+/// let columns = CompletionOptions::default().only_kinds([CompletionKind::Column]);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[allow(clippy::struct_excessive_bools)] // independent toggles, mirrored in the native options
+pub struct CompletionOptions {
+    /// Include punctuation items (brackets, commas, pipes)
+    pub include_punctuation: bool,
+    /// Include keyword items
+    pub include_keywords: bool,
+    /// Only include symbols from the schema (tables, columns, databases,
+    /// clusters, and database functions)
+    pub only_schema_symbols: bool,
+    /// Only include items of these kinds (all kinds if empty)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub kinds: Vec<CompletionKind>,
+    /// Match items against the partially typed word at the cursor
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub matching: Option<CompletionMatch>,
+    /// Rank items by match quality and kind
+    #[serde(default)]
+    pub ranked: bool,
+    /// Maximum number of items to return
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_items: Option<usize>,
+}
+
+impl Default for CompletionOptions {
+    fn default() -> Self {
+        Self {
+            include_punctuation: true,
+            include_keywords: true,
+            only_schema_symbols: false,
+            kinds: Vec::new(),
+            matching: None,
+            ranked: false,
+            max_items: None,
+        }
+    }
+}
+
+/// How items are matched against the partially typed word at the cursor
+///
+/// Both ignore case and bracket quoting (see
+/// [`CompletionItem::filter_text`]). An empty word matches every item.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CompletionMatch {
+    /// Labels starting with the word
+    Prefix,
+    /// Labels containing the word's characters in order, so `tgen`
+    /// matches `TimeGenerated`
+    Fuzzy,
+}
+
+impl CompletionOptions {
+    /// Builder method to include or exclude punctuation items
+    #[must_use]
+    pub fn include_punctuation(mut self, include: bool) -> Self {
+        self.include_punctuation = include;
+        self
+    }
+
+    /// Builder method to include or exclude keyword items
+    #[must_use]
+    pub fn include_keywords(mut self, include: bool) -> Self {
+        self.include_keywords = include;
+        self
+    }
+
+    /// Builder method to only include schema symbols
+    #[must_use]
+    pub fn only_schema_symbols(mut self, only: bool) -> Self {
+        self.only_schema_symbols = only;
+        self
+    }
+
+    /// Builder method to only include items of the given kinds
+    #[must_use]
+    pub fn only_kinds(mut self, kinds: impl IntoIterator<Item = CompletionKind>) -> Self {
+        self.kinds = kinds.into_iter().collect();
+        self
+    }
+
+    /// Builder method to match items against the word at the cursor
+    #[must_use]
+    pub fn matching(mut self, matching: CompletionMatch) -> Self {
+        self.matching = Some(matching);
+        self
+    }
+
+    /// Builder method to rank items by match quality and kind
+    #[must_use]
+    pub fn ranked(mut self, ranked: bool) -> Self {
+        self.ranked = ranked;
+        self
+    }
+
+    /// Builder method to limit the number of items returned
+    #[must_use]
+    pub fn max_items(mut self, max: usize) -> Self {
+        self.max_items = Some(max);
+        self
+    }
+
+    /// Whether kind filters, matching or ranking are asked for
+    pub(crate) fn uses_ranking(&self) -> bool {
+        !self.kinds.is_empty() || self.matching.is_some() || self.ranked
+    }
+
+    /// Filter a result in Rust, for libraries without native option support
+    ///
+    /// `before_cursor` is the query text up to the cursor. Database functions
+    /// cannot be told apart from built-in functions here, so
+    /// `only_schema_symbols` drops all functions.
+    pub(crate) fn apply(&self, result: &mut CompletionResult, before_cursor: &str) {
+        let word = partial_word(before_cursor);
+        let before_word = before_cursor[..before_cursor.len() - word.len()].trim_end();
+        let operator_expected = before_word.is_empty() || before_word.ends_with('|');
+        let word = word.to_lowercase();
+
+        result.items.retain(|item| {
+            self.includes(item.kind)
+                && self.matching.map_or(true, |m| {
+                    match_score(m, &item.filter_text(), &word).is_some()
+                })
+        });
+        if self.ranked {
+            let matching = self.matching.unwrap_or(CompletionMatch::Fuzzy);
+            result.items.sort_by_cached_key(|item| {
+                (
+                    match_score(matching, &item.filter_text(), &word).unwrap_or(u8::MAX),
+                    !item.matches_expected_type,
+                    kind_rank(item.kind, operator_expected),
+                    item.sort_order,
+                )
+            });
+        }
+        if let Some(max) = self.max_items {
+            result.items.truncate(max);
+        }
+        if self.ranked {
+            for (order, item) in (0..).zip(&mut result.items) {
+                item.sort_order = order;
+            }
+        }
+    }
+
+    fn includes(&self, kind: CompletionKind) -> bool {
+        let included = match kind {
+            CompletionKind::Punctuation => self.include_punctuation && !self.only_schema_symbols,
+            CompletionKind::Keyword => self.include_keywords && !self.only_schema_symbols,
+            CompletionKind::Table
+            | CompletionKind::Column
+            | CompletionKind::Database
+            | CompletionKind::Cluster => true,
+            _ => !self.only_schema_symbols,
+        };
+        included && (self.kinds.is_empty() || self.kinds.contains(&kind))
+    }
+}
+
+/// Completions from the compiled-in catalog, without calling the native library
+///
+/// Returns the words that fit `context_hint`: tabular operators after a
+/// pipe, built-in scalar functions in function arguments and `by` clauses,
+/// and keywords, functions, aggregations, scalar operators and type names
+/// elsewhere. After a dot, where only the schema could help, it returns
+/// nothing. Items have no schema knowledge and `edit_start` is 0.
+///
+/// [`KqlValidator::get_completions`](crate::KqlValidator::get_completions)
+/// uses these automatically when the native library lacks completion
+/// support, working out the hint from the query and keeping the items that
+/// start with the word at the cursor.
+///
+/// # Example
+///
+/// ```
+/// use kql_language_tools::{static_completions, CompletionTrigger};
+///
+/// let result = static_completions(CompletionTrigger::AfterPipe);
+/// assert!(result.items.iter().any(|i| i.label == "summarize"));
+/// ```
+#[must_use]
+pub fn static_completions(context_hint: CompletionTrigger) -> CompletionResult {
+    let words = |names: &'static [&'static str], kind| names.iter().map(move |name| (*name, kind));
+    let entries: Vec<(&str, CompletionKind)> = match context_hint {
+        CompletionTrigger::AfterPipe => words(QUERY_OPERATORS, CompletionKind::Keyword).collect(),
+        CompletionTrigger::AfterDot => Vec::new(),
+        CompletionTrigger::FunctionArguments | CompletionTrigger::ByClause => {
+            words(FUNCTIONS, CompletionKind::Function).collect()
+        }
+        CompletionTrigger::Other => words(FUNCTIONS, CompletionKind::Function)
+            .chain(words(
+                AGGREGATE_FUNCTIONS,
+                CompletionKind::AggregateFunction,
+            ))
+            .chain(words(SCALAR_OPERATORS, CompletionKind::Operator))
+            .chain(words(KEYWORDS, CompletionKind::Keyword))
+            .chain(words(TYPES, CompletionKind::Type))
+            .collect(),
+    };
+
+    let items = (0..)
+        .zip(entries)
+        .map(|(sort_order, (name, kind))| {
+            let is_function = matches!(
+                kind,
+                CompletionKind::Function | CompletionKind::AggregateFunction
+            );
+            CompletionItem {
+                label: if is_function {
+                    format!("{name}()")
+                } else {
+                    name.to_string()
+                },
+                kind,
+                detail: None,
+                insert_text: is_function.then(|| name.to_string()),
+                sort_order,
+                edit_start: 0,
+                documentation: None,
+                matches_expected_type: false,
+            }
+        })
+        .collect();
+    CompletionResult {
+        items,
+        context: None,
+        expected_type: None,
+    }
+}
+
+/// [`static_completions`] at a cursor, for libraries without completion
+/// support
+///
+/// The hint is worked out from the text before the cursor, items are
+/// narrowed to those starting with the word at the cursor, and offsets are
+/// in characters.
+pub(crate) fn static_completions_at(query: &str, cursor: usize) -> CompletionResult {
+    let (before, after) = query.split_at(
+        query
+            .char_indices()
+            .nth(cursor)
+            .map_or(query.len(), |(i, _)| i),
+    );
+    let word = partial_word(before);
+    let trigger = trigger_before(&before[..before.len() - word.len()]);
+    let edit_start = cursor - word.chars().count();
+    let rest = after
+        .chars()
+        .take_while(|c| c.is_alphanumeric() || *c == '_')
+        .count();
+
+    let mut result = static_completions(trigger);
+    for item in &mut result.items {
+        item.edit_start = edit_start;
+    }
+    CompletionOptions::default()
+        .matching(CompletionMatch::Prefix)
+        .apply(&mut result, before);
+    result.context = Some(CompletionContext {
+        trigger,
+        edit_start,
+        edit_end: cursor + rest,
+    });
+    result
+}
+
+/// Guess what precedes a word from the text before it
+fn trigger_before(before_word: &str) -> CompletionTrigger {
+    let before_word = before_word.trim_end();
+    if before_word.ends_with('|') {
+        return CompletionTrigger::AfterPipe;
+    }
+    if before_word.ends_with('.') {
+        return CompletionTrigger::AfterDot;
+    }
+
+    // Only the current pipeline step matters
+    let step = before_word.rsplit('|').next().unwrap_or_default();
+    let open = step.matches('(').count();
+    if open > step.matches(')').count() {
+        CompletionTrigger::FunctionArguments
+    } else if step.split_whitespace().any(|word| word == "by") {
+        CompletionTrigger::ByClause
+    } else {
+        CompletionTrigger::Other
+    }
+}
+
+/// The identifier characters immediately before the cursor
+fn partial_word(before_cursor: &str) -> &str {
+    let start = before_cursor
+        .char_indices()
+        .rev()
+        .take_while(|(_, c)| c.is_alphanumeric() || *c == '_')
+        .last()
+        .map_or(before_cursor.len(), |(i, _)| i);
+    &before_cursor[start..]
+}
+
+/// How well `filter_text` matches the case-folded `word`: 0 for an exact
+/// match, 1 for a prefix, 2 for a fuzzy match, `None` for no match
+fn match_score(matching: CompletionMatch, filter_text: &str, word: &str) -> Option<u8> {
+    if filter_text == word {
+        Some(0)
+    } else if filter_text.starts_with(word) {
+        Some(1)
+    } else if matching == CompletionMatch::Fuzzy {
+        let mut chars = filter_text.chars();
+        word.chars().all(|w| chars.any(|c| c == w)).then_some(2)
+    } else {
+        None
+    }
+}
+
+/// Rank of a kind when ranking: names first, keywords first only where an
+/// operator is expected, punctuation last
+fn kind_rank(kind: CompletionKind, operator_expected: bool) -> u8 {
+    match kind {
+        CompletionKind::Keyword if operator_expected => 0,
+        CompletionKind::Column => 1,
+        CompletionKind::LetVariable | CompletionKind::Variable | CompletionKind::Parameter => 2,
+        CompletionKind::Function | CompletionKind::AggregateFunction => 3,
+        CompletionKind::Table | CompletionKind::Database | CompletionKind::Cluster => 4,
+        CompletionKind::Keyword | CompletionKind::Operator | CompletionKind::Type => 5,
+        CompletionKind::Other => 6,
+        CompletionKind::Punctuation => 7,
+    }
 }
 
 /// Kind of completion item
@@ -41,6 +435,9 @@ pub enum CompletionKind {
     Column,
     /// A variable
     Variable,
+    /// A name bound by a `let` statement earlier in the query: a value,
+    /// tabular expression or locally defined function
+    LetVariable,
     /// An operator
     Operator,
     /// A parameter
@@ -62,4 +459,290 @@ pub enum CompletionKind {
 pub struct CompletionResult {
     /// Completion items
     pub items: Vec<CompletionItem>,
+    /// Where the list was asked for and the text the items replace
+    ///
+    /// `None` for libraries that do not report it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context: Option<CompletionContext>,
+    /// Scalar type expected at the cursor, such as `datetime` after
+    /// `where Timestamp > `
+    ///
+    /// Columns, functions and `let` bindings of this type are marked with
+    /// [`CompletionItem::matches_expected_type`] and listed first.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expected_type: Option<KqlType>,
+}
+
+/// Characters after which clients should open the completion list
+/// unprompted, for LSP's `triggerCharacters`
+pub const TRIGGER_CHARACTERS: &[&str] = &["|", ".", "(", ","];
+
+/// Where a completion list was asked for
+///
+/// Clients use [`trigger`](Self::trigger) to decide whether to pop the list
+/// up automatically, and replace the text from
+/// [`edit_start`](Self::edit_start) to [`edit_end`](Self::edit_end) with the
+/// chosen item, so completing in the middle of a word replaces all of it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CompletionContext {
+    /// What precedes the cursor
+    pub trigger: CompletionTrigger,
+    /// Character position where the replaced text starts
+    pub edit_start: usize,
+    /// Character position where the replaced text ends (exclusive)
+    pub edit_end: usize,
+}
+
+/// What precedes the cursor when completing
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub enum CompletionTrigger {
+    /// A `|`, where a query operator is expected
+    AfterPipe,
+    /// A `.`, as in `cluster('c').database('d').` or a dynamic path
+    AfterDot,
+    /// Inside a function call's argument list
+    FunctionArguments,
+    /// Inside a `by` clause, as in `summarize count() by `
+    ByClause,
+    /// Anywhere else
+    Other,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(label: &str, sort_order: i32) -> CompletionItem {
+        CompletionItem {
+            label: label.to_string(),
+            kind: CompletionKind::Column,
+            detail: None,
+            insert_text: None,
+            sort_order,
+            edit_start: 0,
+            documentation: None,
+            matches_expected_type: false,
+        }
+    }
+
+    #[test]
+    fn test_sort_text_orders_as_strings() {
+        let mut items = [item("b", 10), item("a", 10), item("z", -5), item("c", 2)];
+        items.sort_by_key(CompletionItem::sort_text);
+        let labels: Vec<_> = items.iter().map(|i| i.label.as_str()).collect();
+        assert_eq!(labels, ["z", "c", "a", "b"]);
+        assert_eq!(item("x", i32::MIN).sort_text(), "0000000000:x");
+    }
+
+    #[test]
+    fn test_options_fallback_filtering() {
+        let mut keyword = item("where", 0);
+        keyword.kind = CompletionKind::Keyword;
+        let mut pipe = item("|", 1);
+        pipe.kind = CompletionKind::Punctuation;
+        let mut function = item("strcat", 2);
+        function.kind = CompletionKind::Function;
+        let all = CompletionResult {
+            items: vec![
+                keyword,
+                pipe,
+                item("Account", 3),
+                function,
+                item("Computer", 4),
+            ],
+            context: None,
+            expected_type: None,
+        };
+
+        let mut result = all.clone();
+        CompletionOptions::default()
+            .include_punctuation(false)
+            .apply(&mut result, "T | ");
+        assert_eq!(result.items.len(), 4);
+
+        let mut result = all.clone();
+        CompletionOptions::default()
+            .only_schema_symbols(true)
+            .max_items(1)
+            .apply(&mut result, "T | ");
+        let labels: Vec<_> = result.items.iter().map(|i| i.label.as_str()).collect();
+        assert_eq!(labels, ["Account"]);
+
+        let mut result = all;
+        CompletionOptions::default()
+            .only_kinds([CompletionKind::Column, CompletionKind::Function])
+            .apply(&mut result, "T | ");
+        let labels: Vec<_> = result.items.iter().map(|i| i.label.as_str()).collect();
+        assert_eq!(labels, ["Account", "strcat", "Computer"]);
+    }
+
+    #[test]
+    fn test_options_matching_and_ranking() {
+        let labels =
+            |options: &CompletionOptions, before_cursor: &str| {
+                let mut keyword = item("project", 0);
+                keyword.kind = CompletionKind::Keyword;
+                let mut function = item("tolower()", 1);
+                function.kind = CompletionKind::Function;
+                let mut result = CompletionResult {
+                    items: vec![
+                        keyword,
+                        function,
+                        item("TimeGenerated", 2),
+                        item("['Target Host']", 3),
+                        item("Computer", 4),
+                    ],
+                    context: None,
+                    expected_type: None,
+                };
+                options.apply(&mut result, before_cursor);
+                assert!(result.items.iter().enumerate().all(
+                    |(i, item)| !options.ranked || item.sort_order == i32::try_from(i).unwrap()
+                ));
+                result
+                    .items
+                    .into_iter()
+                    .map(|i| i.label)
+                    .collect::<Vec<_>>()
+            };
+
+        let prefix = CompletionOptions::default().matching(CompletionMatch::Prefix);
+        assert_eq!(
+            labels(&prefix, "T | where t"),
+            ["tolower()", "TimeGenerated", "['Target Host']"]
+        );
+        assert_eq!(labels(&prefix, "T | where TIME"), ["TimeGenerated"]);
+
+        let fuzzy = CompletionOptions::default().matching(CompletionMatch::Fuzzy);
+        assert_eq!(labels(&fuzzy, "T | where tgen"), ["TimeGenerated"]);
+        assert_eq!(labels(&fuzzy, "T | where th"), ["['Target Host']"]);
+
+        // Columns before keywords after an operator, keywords first after a pipe
+        let ranked = CompletionOptions::default().ranked(true);
+        assert_eq!(
+            labels(&ranked, "T | project "),
+            [
+                "TimeGenerated",
+                "['Target Host']",
+                "Computer",
+                "tolower()",
+                "project"
+            ]
+        );
+        assert_eq!(labels(&ranked.clone().max_items(1), "T | "), ["project"]);
+        assert_eq!(
+            labels(&ranked.matching(CompletionMatch::Fuzzy), "T | where to"),
+            ["tolower()", "['Target Host']"]
+        );
+    }
+
+    #[test]
+    fn test_ranking_prefers_expected_type() {
+        let mut now = item("now()", 0);
+        now.kind = CompletionKind::Function;
+        now.matches_expected_type = true;
+        let mut generated = item("TimeGenerated", 2);
+        generated.matches_expected_type = true;
+        let mut result = CompletionResult {
+            items: vec![item("Account", 1), now, generated],
+            context: None,
+            expected_type: Some(KqlType::DateTime),
+        };
+        CompletionOptions::default()
+            .ranked(true)
+            .apply(&mut result, "T | where Timestamp > ");
+        let labels: Vec<_> = result.items.iter().map(|i| i.label.as_str()).collect();
+        assert_eq!(labels, ["TimeGenerated", "now()", "Account"]);
+
+        let result: CompletionResult = serde_json::from_str(
+            r#"{"items":[{"label":"now()","kind":"Function","matches_expected_type":true}],"expected_type":"datetime"}"#,
+        )
+        .unwrap();
+        assert_eq!(result.expected_type, Some(KqlType::DateTime));
+        assert!(result.items[0].matches_expected_type);
+    }
+
+    #[test]
+    fn test_static_completions() {
+        let after_pipe = static_completions(CompletionTrigger::AfterPipe);
+        assert!(after_pipe
+            .items
+            .iter()
+            .all(|i| i.kind == CompletionKind::Keyword));
+        assert!(static_completions(CompletionTrigger::AfterDot)
+            .items
+            .is_empty());
+        let other = static_completions(CompletionTrigger::Other);
+        let ago = other.items.iter().find(|i| i.label == "ago()").unwrap();
+        assert_eq!(ago.insert_text.as_deref(), Some("ago"));
+        assert!(other
+            .items
+            .iter()
+            .any(|i| i.label == "dcount()" && i.kind == CompletionKind::AggregateFunction));
+
+        let result = static_completions_at("T | su", 6);
+        let labels: Vec<_> = result.items.iter().map(|i| i.label.as_str()).collect();
+        assert_eq!(labels, ["summarize"]);
+        assert_eq!(result.items[0].edit_start, 4);
+        assert_eq!(
+            result.context,
+            Some(CompletionContext {
+                trigger: CompletionTrigger::AfterPipe,
+                edit_start: 4,
+                edit_end: 6,
+            })
+        );
+
+        let trigger = |query: &str| {
+            static_completions_at(query, query.chars().count())
+                .context
+                .unwrap()
+                .trigger
+        };
+        assert_eq!(
+            trigger("T | summarize count() by bi"),
+            CompletionTrigger::ByClause
+        );
+        assert_eq!(
+            trigger("T | extend x = strcat(to"),
+            CompletionTrigger::FunctionArguments
+        );
+        assert_eq!(trigger("T | where "), CompletionTrigger::Other);
+        assert!(static_completions_at("T | extend x = strcat(to", 24)
+            .items
+            .iter()
+            .any(|i| i.label == "tolower()"));
+
+        // The replaced range covers the rest of the word
+        let middle = static_completions_at("T | summ | take 1", 6)
+            .context
+            .unwrap();
+        assert_eq!((middle.edit_start, middle.edit_end), (4, 8));
+    }
+
+    #[test]
+    fn test_deserialize_context() {
+        let result: CompletionResult = serde_json::from_str(
+            r#"{"items":[],"context":{"trigger":"ByClause","edit_start":27,"edit_end":31}}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            result.context,
+            Some(CompletionContext {
+                trigger: CompletionTrigger::ByClause,
+                edit_start: 27,
+                edit_end: 31,
+            })
+        );
+        let result: CompletionResult = serde_json::from_str(r#"{"items":[]}"#).unwrap();
+        assert_eq!(result.context, None);
+    }
+
+    #[test]
+    fn test_filter_text_folds_case_and_quoting() {
+        assert_eq!(item("TimeGenerated", 0).filter_text(), "timegenerated");
+        assert_eq!(item("['My Column']", 0).filter_text(), "my column");
+        assert_eq!(item("[\"Other\"]", 0).filter_text(), "other");
+    }
 }
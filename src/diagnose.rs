@@ -0,0 +1,431 @@
+//! Native library loading diagnostics
+//!
+//! [`diagnose_loading`](crate::diagnose_loading) walks the same search
+//! locations as the loader and reports, for each one, whether a library is
+//! there and why it would or would not load, together with what was found
+//! of the .NET runtime. Its [`Display`](fmt::Display) output is meant to be
+//! pasted into bug reports.
+
+use crate::loader::{self, LIB_NAME};
+use serde::Serialize;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Where a candidate library location comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CandidateSource {
+    /// Set with [`set_library_path`](crate::set_library_path)
+    Configured,
+    /// The `KQL_LANGUAGE_TOOLS_PATH` environment variable
+    Environment,
+    /// Fetched at build time by the `bundled` feature
+    Bundled,
+    /// Extracted from the binary by the `embed` feature
+    Embedded,
+    /// The directory of the running executable
+    ExecutableDir,
+    /// `OUT_DIR` of the consuming crate
+    OutDir,
+    /// The Cargo target profile directory
+    TargetDir,
+    /// `native/{rid}` in the workspace root
+    WorkspaceNative,
+    /// `dotnet/native/{rid}` in this crate
+    CrateNative,
+    /// The per-user cache directory
+    UserCache,
+    /// The current working directory
+    CurrentDir,
+}
+
+impl fmt::Display for CandidateSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Configured => "set_library_path",
+            Self::Environment => loader::LIB_PATH_ENV,
+            Self::Bundled => "bundled",
+            Self::Embedded => "embedded",
+            Self::ExecutableDir => "executable directory",
+            Self::OutDir => "OUT_DIR",
+            Self::TargetDir => "target directory",
+            Self::WorkspaceNative => "workspace native directory",
+            Self::CrateNative => "crate native directory",
+            Self::UserCache => "user cache",
+            Self::CurrentDir => "current directory",
+        })
+    }
+}
+
+/// What was found at a candidate location
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum CandidateStatus {
+    /// No file at the path
+    Missing,
+    /// The file was built for another CPU architecture
+    WrongArchitecture {
+        /// Architecture of the file
+        found: String,
+    },
+    /// The platform loader rejected the file
+    LoadFailed {
+        /// Error text from the platform loader
+        message: String,
+    },
+    /// The file loads
+    Loadable,
+}
+
+/// One searched location
+#[derive(Debug, Clone, Serialize)]
+pub struct CandidateReport {
+    /// Where the location comes from
+    pub source: CandidateSource,
+    /// The library path searched
+    pub path: PathBuf,
+    /// What was found there
+    #[serde(flatten)]
+    pub status: CandidateStatus,
+}
+
+/// An installed .NET runtime
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DotnetRuntime {
+    /// Framework name, such as `Microsoft.NETCore.App`
+    pub name: String,
+    /// Runtime version
+    pub version: String,
+    /// Directory containing the runtime's versions
+    pub path: PathBuf,
+}
+
+/// Structured account of how the native library is found and loaded
+///
+/// # Example
+///
+/// ```no_run
+/// if !kql_language_tools::is_available() {
+///     eprintln!("{}", kql_language_tools::diagnose_loading());
+/// }
+/// ```
+#[derive(Debug, Clone, Serialize)]
+pub struct LoadReport {
+    /// Runtime identifier of this platform, such as `linux-x64`
+    pub rid: &'static str,
+    /// CPU architecture this crate was compiled for
+    pub arch: &'static str,
+    /// Library file name searched for
+    pub library_name: &'static str,
+    /// Every searched location, in search order
+    pub candidates: Vec<CandidateReport>,
+    /// The location the loader would pick, the first existing candidate
+    pub selected: Option<PathBuf>,
+    /// Path of the library currently loaded, if any
+    pub loaded: Option<PathBuf>,
+    /// Value of the `DOTNET_ROOT` environment variable
+    pub dotnet_root_env: Option<PathBuf>,
+    /// .NET root found by auto-detection
+    pub dotnet_root_detected: Option<PathBuf>,
+    /// Installed .NET runtimes
+    pub runtimes: Vec<DotnetRuntime>,
+}
+
+impl LoadReport {
+    /// Check if the selected library loads
+    #[must_use]
+    pub fn is_loadable(&self) -> bool {
+        self.loaded.is_some()
+            || self.candidates.iter().any(|c| {
+                Some(&c.path) == self.selected.as_ref() && c.status == CandidateStatus::Loadable
+            })
+    }
+}
+
+impl fmt::Display for LoadReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "Native library {} for {} ({})",
+            self.library_name, self.rid, self.arch
+        )?;
+        if let Some(loaded) = &self.loaded {
+            writeln!(f, "Loaded from: {}", loaded.display())?;
+        }
+        writeln!(f, "Searched:")?;
+        for candidate in &self.candidates {
+            let selected = if Some(&candidate.path) == self.selected.as_ref() {
+                " [selected]"
+            } else {
+                ""
+            };
+            write!(
+                f,
+                "  {} ({}){selected}: ",
+                candidate.path.display(),
+                candidate.source
+            )?;
+            match &candidate.status {
+                CandidateStatus::Missing => writeln!(f, "missing")?,
+                CandidateStatus::WrongArchitecture { found } => {
+                    writeln!(f, "built for {found}, expected {}", self.arch)?;
+                }
+                CandidateStatus::LoadFailed { message } => {
+                    writeln!(f, "failed to load: {message}")?;
+                }
+                CandidateStatus::Loadable => writeln!(f, "ok")?,
+            }
+        }
+        let show = |path: &Option<PathBuf>| {
+            path.as_ref()
+                .map_or_else(|| "not set".to_string(), |p| p.display().to_string())
+        };
+        writeln!(f, "DOTNET_ROOT: {}", show(&self.dotnet_root_env))?;
+        writeln!(
+            f,
+            "Detected .NET root: {}",
+            show(&self.dotnet_root_detected)
+        )?;
+        if self.runtimes.is_empty() {
+            write!(f, ".NET runtimes: none found")
+        } else {
+            write!(f, ".NET runtimes:")?;
+            for runtime in &self.runtimes {
+                write!(
+                    f,
+                    "\n  {} {} [{}]",
+                    runtime.name,
+                    runtime.version,
+                    runtime.path.display()
+                )?;
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Build a [`LoadReport`] for the current process
+pub(crate) fn diagnose() -> LoadReport {
+    let candidates: Vec<_> = loader::candidates()
+        .into_iter()
+        .map(|(source, path)| CandidateReport {
+            source,
+            status: check_candidate(&path),
+            path,
+        })
+        .collect();
+    let selected = candidates
+        .iter()
+        .find(|c| c.status != CandidateStatus::Missing)
+        .map(|c| c.path.clone());
+    let dotnet_root_env = std::env::var_os("DOTNET_ROOT").map(PathBuf::from);
+    let dotnet_root_detected = loader::find_dotnet_root();
+    let runtimes = list_runtimes(
+        dotnet_root_env
+            .as_deref()
+            .or(dotnet_root_detected.as_deref()),
+    );
+
+    LoadReport {
+        rid: loader::current_rid(),
+        arch: std::env::consts::ARCH,
+        library_name: LIB_NAME,
+        candidates,
+        selected,
+        loaded: loader::current_library().map(|lib| lib.path().to_path_buf()),
+        dotnet_root_env,
+        dotnet_root_detected,
+        runtimes,
+    }
+}
+
+/// Check whether the library at `path` exists, matches this architecture
+/// and loads
+fn check_candidate(path: &Path) -> CandidateStatus {
+    if !path.is_file() {
+        return CandidateStatus::Missing;
+    }
+    if let Some(found) = std::fs::read(path).ok().as_deref().and_then(binary_arch) {
+        if found != std::env::consts::ARCH {
+            return CandidateStatus::WrongArchitecture {
+                found: found.to_string(),
+            };
+        }
+    }
+    match loader::probe(path) {
+        Ok(()) => CandidateStatus::Loadable,
+        Err(message) => CandidateStatus::LoadFailed { message },
+    }
+}
+
+/// Read the CPU architecture from an ELF, Mach-O or PE header
+///
+/// Names follow [`std::env::consts::ARCH`]. Returns `None` for other
+/// formats and for universal Mach-O binaries.
+fn binary_arch(bytes: &[u8]) -> Option<&'static str> {
+    let u16_at = |at: usize| Some(u16::from_le_bytes(bytes.get(at..at + 2)?.try_into().ok()?));
+    let u32_at = |at: usize| Some(u32::from_le_bytes(bytes.get(at..at + 4)?.try_into().ok()?));
+
+    if bytes.starts_with(b"\x7fELF") {
+        return match u16_at(18)? {
+            0x03 => Some("x86"),
+            0x28 => Some("arm"),
+            0x3e => Some("x86_64"),
+            0xb7 => Some("aarch64"),
+            _ => None,
+        };
+    }
+    if bytes.starts_with(&[0xcf, 0xfa, 0xed, 0xfe]) {
+        return match u32_at(4)? {
+            0x0100_0007 => Some("x86_64"),
+            0x0100_000c => Some("aarch64"),
+            _ => None,
+        };
+    }
+    if bytes.starts_with(b"MZ") {
+        let pe = usize::try_from(u32_at(0x3c)?).ok()?;
+        if bytes.get(pe..pe + 4)? != b"PE\0\0" {
+            return None;
+        }
+        return match u16_at(pe + 4)? {
+            0x014c => Some("x86"),
+            0x8664 => Some("x86_64"),
+            0xaa64 => Some("aarch64"),
+            _ => None,
+        };
+    }
+    None
+}
+
+/// List the installed .NET runtimes
+///
+/// Asks `dotnet --list-runtimes`, from `dotnet_root` if known, and falls
+/// back to reading the runtime directories under `dotnet_root`.
+fn list_runtimes(dotnet_root: Option<&Path>) -> Vec<DotnetRuntime> {
+    let dotnet = dotnet_root.map_or_else(
+        || PathBuf::from("dotnet"),
+        |root| {
+            root.join(if cfg!(windows) {
+                "dotnet.exe"
+            } else {
+                "dotnet"
+            })
+        },
+    );
+    for program in [dotnet, PathBuf::from("dotnet")] {
+        if let Ok(output) = Command::new(program).arg("--list-runtimes").output() {
+            if output.status.success() {
+                return parse_runtimes(&String::from_utf8_lossy(&output.stdout));
+            }
+        }
+    }
+
+    let mut runtimes = Vec::new();
+    let Some(shared) = dotnet_root.map(|root| root.join("shared")) else {
+        return runtimes;
+    };
+    for framework in std::fs::read_dir(shared).into_iter().flatten().flatten() {
+        let name = framework.file_name().to_string_lossy().into_owned();
+        for version in std::fs::read_dir(framework.path())
+            .into_iter()
+            .flatten()
+            .flatten()
+        {
+            runtimes.push(DotnetRuntime {
+                name: name.clone(),
+                version: version.file_name().to_string_lossy().into_owned(),
+                path: framework.path(),
+            });
+        }
+    }
+    runtimes
+}
+
+/// Parse `dotnet --list-runtimes` output
+///
+/// Each line reads `Microsoft.NETCore.App 8.0.1 [/usr/share/dotnet/shared/Microsoft.NETCore.App]`.
+fn parse_runtimes(output: &str) -> Vec<DotnetRuntime> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let (name, rest) = line.trim().split_once(' ')?;
+            let (version, path) = rest.split_once(' ')?;
+            let path = path.trim().strip_prefix('[')?.strip_suffix(']')?;
+            Some(DotnetRuntime {
+                name: name.to_string(),
+                version: version.to_string(),
+                path: PathBuf::from(path),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_binary_arch() {
+        let mut elf = b"\x7fELF".to_vec();
+        elf.resize(20, 0);
+        elf[18] = 0xb7;
+        assert_eq!(binary_arch(&elf), Some("aarch64"));
+
+        let macho = [0xcf, 0xfa, 0xed, 0xfe, 0x07, 0x00, 0x00, 0x01];
+        assert_eq!(binary_arch(&macho), Some("x86_64"));
+
+        let mut pe = b"MZ".to_vec();
+        pe.resize(0x80, 0);
+        pe[0x3c] = 0x40;
+        pe[0x40..0x46].copy_from_slice(b"PE\0\0\x64\x86");
+        assert_eq!(binary_arch(&pe), Some("x86_64"));
+
+        assert_eq!(binary_arch(b"not a library"), None);
+        assert_eq!(binary_arch(b"\x7fELF"), None);
+    }
+
+    #[test]
+    fn test_check_candidate() {
+        let dir = std::env::temp_dir().join(format!("kql-diagnose-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        assert_eq!(
+            check_candidate(&dir.join("absent")),
+            CandidateStatus::Missing
+        );
+
+        let mut foreign = b"\x7fELF".to_vec();
+        foreign.resize(64, 0);
+        foreign[18] = if cfg!(target_arch = "aarch64") {
+            0x3e
+        } else {
+            0xb7
+        };
+        std::fs::write(dir.join("foreign"), &foreign).unwrap();
+        assert!(matches!(
+            check_candidate(&dir.join("foreign")),
+            CandidateStatus::WrongArchitecture { .. }
+        ));
+
+        std::fs::write(dir.join("garbage"), b"not a library").unwrap();
+        assert!(matches!(
+            check_candidate(&dir.join("garbage")),
+            CandidateStatus::LoadFailed { .. }
+        ));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_parse_runtimes() {
+        let output = "Microsoft.AspNetCore.App 8.0.1 [/usr/share/dotnet/shared/Microsoft.AspNetCore.App]\n\
+                      Microsoft.NETCore.App 8.0.1 [/usr/share/dotnet/shared/Microsoft.NETCore.App]\n";
+        let runtimes = parse_runtimes(output);
+        assert_eq!(runtimes.len(), 2);
+        assert_eq!(runtimes[1].name, "Microsoft.NETCore.App");
+        assert_eq!(runtimes[1].version, "8.0.1");
+        assert_eq!(
+            runtimes[1].path,
+            Path::new("/usr/share/dotnet/shared/Microsoft.NETCore.App")
+        );
+    }
+}
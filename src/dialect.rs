@@ -0,0 +1,51 @@
+//! Query dialects
+//!
+//! KQL is hosted by several Azure services, each of which accepts a slightly
+//! different surface on top of the core language. A [`Dialect`] tells the
+//! native library which additional rules to apply during validation.
+
+use serde::{Deserialize, Serialize};
+
+/// The KQL dialect a query is written for
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub enum Dialect {
+    /// Azure Data Explorer (plain Kusto.Language behaviour)
+    #[default]
+    Kusto,
+    /// Azure Monitor Log Analytics
+    ///
+    /// Enables validation of the cross-resource scoping functions
+    /// `workspace()`, `app()`, `resource()` and `adx()`, resolving their
+    /// tables against the [`ResourceScope`](crate::ResourceScope)s in the
+    /// schema when present. Schemas with scopes resolve the functions under
+    /// every dialect; this one also checks argument forms and positions.
+    LogAnalytics,
+    /// Data Collection Rule ingestion-time transformations (`transformKql`)
+    ///
+    /// Restricts queries to a single input table named `source` and to the
+    /// operators and functions Azure Monitor supports in transformations.
+    /// Supply the incoming stream's columns as a table named `source` in the
+    /// schema to have column references checked as well.
+    DataCollectionRule,
+    /// Azure Resource Graph
+    ///
+    /// Restricts queries to the tabular operators Resource Graph supports,
+    /// to at most three each of `join`, `union` and `mv-expand`, and to the
+    /// `innerunique`, `inner`, `leftouter` and `fullouter` join kinds.
+    /// Tables also resolve by their lowercase names, so a `Resources` table
+    /// in the schema can be queried as `resources`. The `resource-graph`
+    /// feature provides the Resource Graph tables.
+    ResourceGraph,
+}
+
+impl std::fmt::Display for Dialect {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Kusto => write!(f, "Kusto"),
+            Self::LogAnalytics => write!(f, "LogAnalytics"),
+            Self::DataCollectionRule => write!(f, "DataCollectionRule"),
+            Self::ResourceGraph => write!(f, "ResourceGraph"),
+        }
+    }
+}
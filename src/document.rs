@@ -0,0 +1,162 @@
+//! Incremental document sessions
+//!
+//! A [`Document`] keeps its parse state alive in the native library between
+//! edits. The text is split into blocks at blank lines, as in
+//! [`KqlValidator::validate_script`](crate::KqlValidator::validate_script),
+//! and after an edit only the blocks whose text changed are parsed and
+//! analyzed again. This suits editors that re-validate on every keystroke.
+
+use crate::completion::CompletionResult;
+use crate::error::Error;
+use crate::handle::SchemaHandle;
+use crate::positions::LineIndex;
+use crate::types::ValidationResult;
+use crate::validator::KqlValidator;
+use std::ffi::c_int;
+use std::fmt;
+use std::ops::Range;
+
+/// An open document whose parse state is kept between edits
+///
+/// Offsets in and out follow the validator's
+/// [`OffsetEncoding`](crate::OffsetEncoding). The native session is closed
+/// when the document is dropped.
+///
+/// # Example
+///
+/// ```no_run
+/// use kql_language_tools::{Document, KqlValidator};
+///
+/// fn main() -> Result<(), kql_language_tools::Error> {
+///     let validator = KqlValidator::new()?;
+///     let mut document = Document::open(&validator, "SecurityEvent | where")?;
+///     assert!(!document.diagnostics()?.is_valid());
+///
+///     document.apply_edit(21..21, " EventID == 4625")?;
+///     assert!(document.diagnostics()?.is_valid());
+///     Ok(())
+/// }
+/// ```
+pub struct Document<'a> {
+    validator: &'a KqlValidator,
+    id: c_int,
+    text: String,
+}
+
+impl<'a> Document<'a> {
+    /// Open a document checked for syntax only
+    ///
+    /// The validator's options (dialect, profiles, ...) apply to every
+    /// diagnostics call.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if document sessions are not supported by the
+    /// loaded library.
+    pub fn open(validator: &'a KqlValidator, text: impl Into<String>) -> Result<Self, Error> {
+        let text = text.into();
+        let id = validator.open_document(&text, None)?;
+        Ok(Self {
+            validator,
+            id,
+            text,
+        })
+    }
+
+    /// Open a document analyzed against a registered schema
+    ///
+    /// The document captures the schema's symbol tables when it is opened,
+    /// so the handle may be dropped afterwards.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if document sessions are not supported by the
+    /// loaded library.
+    pub fn open_with_handle(
+        validator: &'a KqlValidator,
+        text: impl Into<String>,
+        handle: &SchemaHandle,
+    ) -> Result<Self, Error> {
+        let text = text.into();
+        let id = validator.open_document(&text, Some(handle))?;
+        Ok(Self {
+            validator,
+            id,
+            text,
+        })
+    }
+
+    /// Get the current text
+    #[must_use]
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Replace a range of the text
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the range is outside the document or the native
+    /// call fails. The document is unchanged on error.
+    pub fn apply_edit(&mut self, range: Range<usize>, new_text: &str) -> Result<(), Error> {
+        let offsets = self.validator.offset_encoding();
+        let start = offsets.decode(&self.text, range.start);
+        let end = offsets.decode(&self.text, range.end);
+
+        self.validator
+            .edit_document(self.id, &self.text, start, end, new_text)?;
+
+        let index = LineIndex::new(self.text.as_str());
+        self.text
+            .replace_range(index.char_to_byte(start)..index.char_to_byte(end), new_text);
+        Ok(())
+    }
+
+    /// Validate the current text
+    ///
+    /// Blocks unchanged since the previous call reuse their results.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the native call fails.
+    pub fn diagnostics(&self) -> Result<ValidationResult, Error> {
+        let mut result = self.validator.document_diagnostics(self.id, &self.text)?;
+        self.validator
+            .offset_encoding()
+            .encode_diagnostics(&self.text, &mut result.diagnostics);
+        Ok(result)
+    }
+
+    /// Get completion suggestions at a cursor position
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the native call fails.
+    pub fn completions(&self, cursor_position: usize) -> Result<CompletionResult, Error> {
+        let offsets = self.validator.offset_encoding();
+        let cursor_position = offsets.decode(&self.text, cursor_position);
+        let mut result =
+            self.validator
+                .document_completions(self.id, &self.text, cursor_position)?;
+        offsets.encode_completions(&self.text, &mut result);
+        Ok(result)
+    }
+}
+
+impl fmt::Debug for Document<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Document")
+            .field("id", &self.id)
+            .field("text", &self.text)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Drop for Document<'_> {
+    fn drop(&mut self) {
+        match self.validator.close_document(self.id) {
+            Some(0) | None => {}
+            Some(code) => log::warn!("Failed to close document {}: code {code}", self.id),
+        }
+    }
+}
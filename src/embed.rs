@@ -0,0 +1,157 @@
+//! Native library embedded in the binary
+//!
+//! Enabled by the `embed` feature. The build script packs the native
+//! library directory (the export library, managed assemblies and runtime
+//! config) into the crate. On first load it is extracted to a per-user
+//! cache directory named after the crate version and the archive hash, so
+//! upgrades never load a stale copy, and the library is loaded from there.
+
+use crate::loader::{user_cache_dir, LIB_NAME};
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+
+/// The packed library directory, as written by the build script
+static ARCHIVE: &[u8] = include_bytes!(env!("KQL_LANGUAGE_TOOLS_EMBEDDED"));
+
+/// Hash of [`ARCHIVE`], computed by the build script
+const ARCHIVE_HASH: &str = env!("KQL_LANGUAGE_TOOLS_EMBEDDED_HASH");
+
+/// File written last during extraction, marking the directory complete
+const COMPLETE_MARKER: &str = ".complete";
+
+/// Get the directory the embedded library is extracted to
+pub(crate) fn extract_dir() -> Option<PathBuf> {
+    let version = env!("CARGO_PKG_VERSION");
+    user_cache_dir().map(|dir| {
+        dir.join("kql-language-tools")
+            .join(format!("{version}-{ARCHIVE_HASH}"))
+    })
+}
+
+/// Extract the embedded library if needed and return its path
+///
+/// Returns `None` if nothing was embedded or extraction failed.
+pub(crate) fn library_path() -> Option<PathBuf> {
+    if ARCHIVE.is_empty() {
+        return None;
+    }
+    let dir = extract_dir()?;
+    if !dir.join(COMPLETE_MARKER).exists() {
+        log::debug!("Extracting embedded native library to {}", dir.display());
+        if let Err(e) = extract(ARCHIVE, &dir) {
+            log::warn!("Failed to extract embedded native library: {e}");
+            return None;
+        }
+    }
+    Some(dir.join(LIB_NAME))
+}
+
+/// Split an archive into its files
+fn entries(mut archive: &[u8]) -> io::Result<Vec<(&str, &[u8])>> {
+    fn take<'a>(archive: &mut &'a [u8], len: usize) -> io::Result<&'a [u8]> {
+        if archive.len() < len {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "truncated embedded archive",
+            ));
+        }
+        let (head, rest) = archive.split_at(len);
+        *archive = rest;
+        Ok(head)
+    }
+    fn length(bytes: &[u8]) -> io::Result<usize> {
+        let mut buf = [0u8; 8];
+        buf[..bytes.len()].copy_from_slice(bytes);
+        usize::try_from(u64::from_le_bytes(buf))
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "entry too large"))
+    }
+
+    let mut files = Vec::new();
+    while !archive.is_empty() {
+        let name_len = length(take(&mut archive, 4)?)?;
+        let name = std::str::from_utf8(take(&mut archive, name_len)?)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let data_len = length(take(&mut archive, 8)?)?;
+        files.push((name, take(&mut archive, data_len)?));
+    }
+    Ok(files)
+}
+
+/// Extract an archive into `dir`
+///
+/// Files are written to a temporary sibling directory that is renamed into
+/// place, so concurrent processes never load a partially written library.
+fn extract(archive: &[u8], dir: &Path) -> io::Result<()> {
+    let files = entries(archive)?;
+    let parent = dir.parent().unwrap_or(dir);
+    fs::create_dir_all(parent)?;
+    let staging = parent.join(format!(".extract-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&staging);
+
+    let written = (|| {
+        for (name, data) in files {
+            let relative = Path::new(name);
+            if !relative
+                .components()
+                .all(|component| matches!(component, Component::Normal(_)))
+            {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("invalid path in embedded archive: {name}"),
+                ));
+            }
+            let path = staging.join(relative);
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(path, data)?;
+        }
+        fs::write(staging.join(COMPLETE_MARKER), [])
+    })();
+
+    let result = written.and_then(|()| match fs::rename(&staging, dir) {
+        // Another process finished extracting first
+        Err(_) if dir.join(COMPLETE_MARKER).exists() => Ok(()),
+        result => result,
+    });
+    let _ = fs::remove_dir_all(&staging);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pack(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut archive = Vec::new();
+        for (name, data) in files {
+            archive.extend_from_slice(&u32::try_from(name.len()).unwrap().to_le_bytes());
+            archive.extend_from_slice(name.as_bytes());
+            archive.extend_from_slice(&(data.len() as u64).to_le_bytes());
+            archive.extend_from_slice(data);
+        }
+        archive
+    }
+
+    #[test]
+    fn test_extract_archive() {
+        let archive = pack(&[("lib.so", b"native"), ("runtimes/a.dll", b"")]);
+        let dir = std::env::temp_dir()
+            .join(format!("kql-embed-test-{}", std::process::id()))
+            .join("0.1.0-hash");
+
+        extract(&archive, &dir).unwrap();
+        assert_eq!(fs::read(dir.join("lib.so")).unwrap(), b"native");
+        assert!(dir.join("runtimes/a.dll").is_file());
+        assert!(dir.join(COMPLETE_MARKER).is_file());
+
+        // A second extraction finds the directory complete
+        extract(&archive, &dir).unwrap();
+        fs::remove_dir_all(dir.parent().unwrap()).unwrap();
+
+        assert!(entries(&archive[..archive.len() - 1]).is_err());
+        let escaping = pack(&[("../evil", b"")]);
+        assert!(extract(&escaping, &std::env::temp_dir().join("kql-embed-never")).is_err());
+    }
+}
@@ -0,0 +1,375 @@
+//! Engine feature-level pinning
+//!
+//! Kusto engines are updated on a rolling schedule, and sovereign or
+//! government clouds can lag public Azure by months. Pinning validation to an
+//! [`EngineFeatureLevel`] flags queries that use functions, plugins, or
+//! operators introduced after that level, so they are caught before being
+//! deployed to a cluster that cannot run them.
+//!
+//! The built-in catalog records the approximate month each feature became
+//! generally available. Those dates have not been checked against the
+//! Kusto release notes, so features flagged only by the catalog are reported
+//! as warnings rather than errors. Entries can be added or overridden per
+//! validator with [`KqlValidator::with_feature_availability`](crate::KqlValidator::with_feature_availability);
+//! those are treated as verified and reported as errors.
+
+use crate::error::Error;
+use serde::{Deserialize, Serialize};
+
+/// An engine feature level, expressed as a release month
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct EngineFeatureLevel {
+    year: u16,
+    month: u8,
+}
+
+impl EngineFeatureLevel {
+    /// Create a feature level from a year and month (1-12)
+    ///
+    /// # Panics
+    ///
+    /// Panics if `month` is not in `1..=12`.
+    #[must_use]
+    pub const fn new(year: u16, month: u8) -> Self {
+        assert!(month >= 1 && month <= 12, "month must be in 1..=12");
+        Self { year, month }
+    }
+
+    /// Parse a feature level from `YYYY-MM` (a trailing `-DD` is ignored)
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidInput`] if `s` is not a `YYYY-MM` or
+    /// `YYYY-MM-DD` date.
+    pub fn parse(s: &str) -> Result<Self, Error> {
+        let invalid = || Error::InvalidInput {
+            message: format!("Invalid engine feature level '{s}': expected YYYY-MM"),
+        };
+        let number = |part: &str| {
+            part.bytes()
+                .all(|b| b.is_ascii_digit())
+                .then(|| part.parse::<u16>().ok())
+                .flatten()
+        };
+
+        let mut parts = s.trim().split('-');
+        let year = parts.next().and_then(number).ok_or_else(invalid)?;
+        let month = parts
+            .next()
+            .and_then(number)
+            .filter(|m| (1..=12).contains(m))
+            .ok_or_else(invalid)?;
+        if let Some(day) = parts.next() {
+            number(day)
+                .filter(|d| (1..=31).contains(d))
+                .ok_or_else(invalid)?;
+        }
+        if parts.next().is_some() {
+            return Err(invalid());
+        }
+
+        let month = u8::try_from(month).map_err(|_| invalid())?;
+        Ok(Self { year, month })
+    }
+
+    /// Year of the feature level
+    #[must_use]
+    pub fn year(&self) -> u16 {
+        self.year
+    }
+
+    /// Month of the feature level (1-12)
+    #[must_use]
+    pub fn month(&self) -> u8 {
+        self.month
+    }
+}
+
+impl std::fmt::Display for EngineFeatureLevel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:04}-{:02}", self.year, self.month)
+    }
+}
+
+impl std::str::FromStr for EngineFeatureLevel {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+impl Serialize for EngineFeatureLevel {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for EngineFeatureLevel {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        Self::parse(&s).map_err(serde::de::Error::custom)
+    }
+}
+
+/// Kind of engine feature tracked in the availability catalog
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub enum FeatureKind {
+    /// A scalar or aggregate function
+    Function,
+    /// An `evaluate` plugin
+    Plugin,
+    /// A tabular query operator
+    Operator,
+}
+
+/// When an engine feature became available
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FeatureAvailability {
+    /// Feature name (lowercase)
+    pub name: String,
+    /// Kind of feature
+    pub kind: FeatureKind,
+    /// First feature level the feature is available in
+    pub introduced: EngineFeatureLevel,
+    /// Whether `introduced` is known to be accurate
+    ///
+    /// Using an unavailable verified feature is an error; an unverified one
+    /// (the built-in catalog) is only a warning.
+    #[serde(default = "verified_by_default")]
+    pub verified: bool,
+}
+
+fn verified_by_default() -> bool {
+    true
+}
+
+impl FeatureAvailability {
+    /// Create a verified catalog entry
+    #[must_use]
+    pub fn new(name: impl Into<String>, kind: FeatureKind, introduced: EngineFeatureLevel) -> Self {
+        Self {
+            name: name.into().to_ascii_lowercase(),
+            kind,
+            introduced,
+            verified: true,
+        }
+    }
+}
+
+/// Built-in availability catalog: (name, kind, year, month)
+///
+/// The months are approximate and unverified; see the module docs.
+const CATALOG: &[(&str, FeatureKind, u16, u8)] = &[
+    // Functions
+    ("array_sort_asc", FeatureKind::Function, 2021, 3),
+    ("array_sort_desc", FeatureKind::Function, 2021, 3),
+    ("bag_remove_keys", FeatureKind::Function, 2021, 4),
+    ("indexof_regex", FeatureKind::Function, 2021, 5),
+    ("make_list_with_nulls", FeatureKind::Function, 2021, 6),
+    (
+        "unixtime_nanoseconds_todatetime",
+        FeatureKind::Function,
+        2021,
+        7,
+    ),
+    ("ipv6_compare", FeatureKind::Function, 2021, 9),
+    ("parse_ipv4_mask", FeatureKind::Function, 2021, 9),
+    (
+        "gzip_compress_to_base64_string",
+        FeatureKind::Function,
+        2021,
+        11,
+    ),
+    (
+        "gzip_decompress_from_base64_string",
+        FeatureKind::Function,
+        2021,
+        11,
+    ),
+    ("has_ipv4", FeatureKind::Function, 2022, 3),
+    ("has_ipv4_prefix", FeatureKind::Function, 2022, 3),
+    ("has_any_ipv4", FeatureKind::Function, 2022, 3),
+    ("has_any_ipv4_prefix", FeatureKind::Function, 2022, 3),
+    ("bag_has_key", FeatureKind::Function, 2022, 4),
+    ("ipv6_is_match", FeatureKind::Function, 2022, 5),
+    ("datetime_utc_to_local", FeatureKind::Function, 2022, 6),
+    ("datetime_local_to_utc", FeatureKind::Function, 2022, 6),
+    ("bitset_count_ones", FeatureKind::Function, 2022, 7),
+    (
+        "geo_h3cell_to_central_point",
+        FeatureKind::Function,
+        2022,
+        8,
+    ),
+    ("geo_point_to_h3cell", FeatureKind::Function, 2022, 8),
+    ("ipv4_is_in_any_range", FeatureKind::Function, 2022, 12),
+    ("ipv6_is_in_any_range", FeatureKind::Function, 2023, 1),
+    ("bag_set_key", FeatureKind::Function, 2023, 2),
+    ("bag_zip", FeatureKind::Function, 2023, 2),
+    ("jaccard_index", FeatureKind::Function, 2023, 3),
+    ("regex_quote", FeatureKind::Function, 2023, 4),
+    ("hash_xxhash64", FeatureKind::Function, 2023, 5),
+    (
+        "unicode_codepoints_from_string",
+        FeatureKind::Function,
+        2023,
+        6,
+    ),
+    (
+        "unicode_codepoints_to_string",
+        FeatureKind::Function,
+        2023,
+        6,
+    ),
+    (
+        "zlib_compress_to_base64_string",
+        FeatureKind::Function,
+        2023,
+        7,
+    ),
+    (
+        "zlib_decompress_from_base64_string",
+        FeatureKind::Function,
+        2023,
+        7,
+    ),
+    ("punycode_from_string", FeatureKind::Function, 2023, 8),
+    ("punycode_to_string", FeatureKind::Function, 2023, 8),
+    ("series_cosine_similarity", FeatureKind::Function, 2023, 10),
+    ("series_dot_product", FeatureKind::Function, 2023, 10),
+    ("series_magnitude", FeatureKind::Function, 2023, 10),
+    ("series_sum", FeatureKind::Function, 2023, 10),
+    ("datetime_list_timezones", FeatureKind::Function, 2024, 2),
+    ("geo_closest_point_on_line", FeatureKind::Function, 2024, 5),
+    ("geo_line_interpolate_point", FeatureKind::Function, 2024, 5),
+    // Plugins
+    ("ipv4_lookup", FeatureKind::Plugin, 2022, 4),
+    ("ipv6_lookup", FeatureKind::Plugin, 2023, 3),
+    // Operators
+    ("project-keep", FeatureKind::Operator, 2021, 6),
+    ("scan", FeatureKind::Operator, 2022, 3),
+    ("make-graph", FeatureKind::Operator, 2023, 5),
+    ("graph-match", FeatureKind::Operator, 2023, 5),
+    ("graph-merge", FeatureKind::Operator, 2023, 5),
+    ("graph-to-table", FeatureKind::Operator, 2023, 5),
+];
+
+/// Get the built-in feature availability catalog
+///
+/// Every entry is unverified.
+#[must_use]
+pub fn feature_catalog() -> Vec<FeatureAvailability> {
+    CATALOG
+        .iter()
+        .map(|&(name, kind, year, month)| FeatureAvailability {
+            verified: false,
+            ..FeatureAvailability::new(name, kind, EngineFeatureLevel::new(year, month))
+        })
+        .collect()
+}
+
+/// Look up when a feature was introduced, according to the built-in catalog
+#[must_use]
+pub fn introduced_in(name: &str) -> Option<EngineFeatureLevel> {
+    CATALOG
+        .iter()
+        .find(|(n, ..)| n.eq_ignore_ascii_case(name))
+        .map(|&(_, _, year, month)| EngineFeatureLevel::new(year, month))
+}
+
+/// Engine constraints sent to the native library
+///
+/// Holds the pinned level plus every catalog entry newer than it, so the
+/// native side only needs to match names.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct EnginePin {
+    pub level: EngineFeatureLevel,
+    pub unavailable: Vec<FeatureAvailability>,
+}
+
+impl EnginePin {
+    /// Pin to a level using the built-in catalog plus overrides
+    ///
+    /// Overrides replace catalog entries with the same name.
+    pub fn new(level: EngineFeatureLevel, overrides: &[FeatureAvailability]) -> Self {
+        let mut entries = feature_catalog();
+        entries.retain(|e| !overrides.iter().any(|o| o.name == e.name));
+        entries.extend(overrides.iter().cloned());
+
+        let unavailable = entries
+            .into_iter()
+            .filter(|e| e.introduced > level)
+            .collect();
+        Self { level, unavailable }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_order_levels() {
+        let level = EngineFeatureLevel::parse("2022-06").unwrap();
+        assert_eq!(level, EngineFeatureLevel::new(2022, 6));
+        assert_eq!(level.to_string(), "2022-06");
+        assert_eq!(EngineFeatureLevel::parse("2022-06-15").unwrap(), level);
+        assert!(EngineFeatureLevel::parse("2022-13").is_err());
+        assert!(EngineFeatureLevel::parse("june").is_err());
+        for invalid in ["2024-05-garbage", "2024-05-15-01", "2024-05-", "2024-+5"] {
+            assert!(matches!(
+                EngineFeatureLevel::parse(invalid),
+                Err(Error::InvalidInput { .. })
+            ));
+        }
+        assert!(EngineFeatureLevel::new(2022, 12) < EngineFeatureLevel::new(2023, 1));
+    }
+
+    #[test]
+    fn test_pin_lists_newer_features() {
+        let pin = EnginePin::new(EngineFeatureLevel::new(2023, 1), &[]);
+        let names: Vec<_> = pin.unavailable.iter().map(|e| e.name.as_str()).collect();
+        assert!(names.contains(&"bag_zip"));
+        assert!(!names.contains(&"has_ipv4"));
+        assert!(pin.unavailable.iter().all(|e| !e.verified));
+        assert_eq!(
+            introduced_in("HAS_IPV4"),
+            Some(EngineFeatureLevel::new(2022, 3))
+        );
+    }
+
+    #[test]
+    fn test_pin_overrides_replace_catalog() {
+        let overrides = [
+            FeatureAvailability::new(
+                "has_ipv4",
+                FeatureKind::Function,
+                EngineFeatureLevel::new(2024, 1),
+            ),
+            FeatureAvailability::new(
+                "my_new_plugin",
+                FeatureKind::Plugin,
+                EngineFeatureLevel::new(2024, 1),
+            ),
+        ];
+        let pin = EnginePin::new(EngineFeatureLevel::new(2023, 1), &overrides);
+        let has_ipv4: Vec<_> = pin
+            .unavailable
+            .iter()
+            .filter(|e| e.name == "has_ipv4")
+            .collect();
+        assert_eq!(has_ipv4.len(), 1);
+        assert!(has_ipv4[0].verified);
+        assert!(pin.unavailable.iter().any(|e| e.name == "my_new_plugin"));
+    }
+
+    #[test]
+    fn test_deserialized_availability_is_verified() {
+        let entry: FeatureAvailability =
+            serde_json::from_str(r#"{"name":"has_ipv4","kind":"Function","introduced":"2022-09"}"#)
+                .unwrap();
+        assert!(entry.verified);
+    }
+}
@@ -1,5 +1,6 @@
 //! Error types for KQL Language Tools
 
+use serde::Deserialize;
 use std::path::PathBuf;
 use thiserror::Error;
 
@@ -10,6 +11,10 @@ pub enum Error {
     #[error("Native library not found. Searched paths: {searched_paths:?}. Set KQL_LANGUAGE_TOOLS_PATH to specify location.")]
     LibraryNotFound { searched_paths: Vec<PathBuf> },
 
+    /// A native library is already loaded from a different location
+    #[error("Native library already loaded from {path}")]
+    LibraryAlreadyLoaded { path: PathBuf },
+
     /// The native library failed to load
     #[error("Failed to load native library from {path}: {message}")]
     LibraryLoadFailed { path: PathBuf, message: String },
@@ -18,13 +23,26 @@ pub enum Error {
     #[error("Symbol '{symbol}' not found in native library")]
     SymbolNotFound { symbol: String },
 
+    /// The native library was built for a different ABI version than this crate
+    #[error("Native library ABI version {found} does not match the version {expected} this crate requires")]
+    AbiMismatch { expected: i32, found: i32 },
+
     /// The library initialization failed
     #[error("Library initialization failed: {message}")]
     InitializationFailed { message: String },
 
     /// Native library call returned an error code
+    ///
+    /// The exception details are set when the call wrote an error envelope.
     #[error("Native call failed with code {code}: {message}")]
-    NativeError { code: i32, message: String },
+    NativeError {
+        code: i32,
+        message: String,
+        /// Full name of the .NET exception, if one was thrown
+        exception_type: Option<String>,
+        /// .NET stack trace of the exception
+        stack_trace: Option<String>,
+    },
 
     /// Output buffer was too small
     #[error("Output buffer too small (needed {needed} bytes, had {available})")]
@@ -34,6 +52,29 @@ pub enum Error {
     #[error("JSON error: {0}")]
     Json(#[from] serde_json::Error),
 
+    /// CBOR deserialization failed
+    #[cfg(feature = "cbor")]
+    #[error("CBOR error: {0}")]
+    Cbor(#[from] ciborium::de::Error<std::io::Error>),
+
+    /// YAML serialization/deserialization failed
+    #[cfg(feature = "yaml")]
+    #[error("YAML error: {0}")]
+    Yaml(#[from] serde_yaml::Error),
+
+    /// TOML serialization/deserialization failed
+    #[cfg(feature = "toml")]
+    #[error("TOML error: {message}")]
+    Toml { message: String },
+
+    /// A file could not be read or written
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    /// A schema file's extension names no supported format
+    #[error("Unsupported schema file format: {path}")]
+    UnsupportedSchemaFormat { path: PathBuf },
+
     /// UTF-8 conversion failed
     #[error("UTF-8 conversion error: {0}")]
     Utf8(#[from] std::str::Utf8Error),
@@ -42,6 +83,47 @@ pub enum Error {
     #[error("Library not initialized. Call KqlValidator::new() first.")]
     NotInitialized,
 
+    /// The call was cancelled through its `CancellationToken`
+    #[error("Operation was cancelled")]
+    Cancelled,
+
+    /// The call's `CancellationToken` deadline or the worker's call timeout
+    /// passed
+    #[error("Operation timed out")]
+    TimedOut,
+
+    /// The validator's worker is still running an earlier cancellable call
+    #[error("Validator is busy with an earlier call")]
+    Busy,
+
+    /// The out-of-process worker failed or exited
+    #[error("Worker error: {message}")]
+    Worker { message: String },
+
+    /// The WebAssembly module failed to load or run
+    #[error("WebAssembly error: {message}")]
+    Wasm { message: String },
+
+    /// Authenticating to or querying a Kusto cluster failed
+    #[error("Kusto cluster error: {message}")]
+    Cluster { message: String },
+
+    /// A schema names a type that is not a KQL type
+    #[error("Unknown KQL type '{name}'{}", context.as_ref().map(|c| format!(" for {c}")).unwrap_or_default())]
+    UnknownType {
+        name: String,
+        /// Where the type appears, such as `column SigninLogs.TimeGenerated`
+        context: Option<String>,
+    },
+
+    /// A CSL script could not be parsed
+    #[error("CSL script error at line {line}: {message}")]
+    Csl { line: usize, message: String },
+
+    /// A value supplied by the caller is malformed
+    #[error("Invalid input: {message}")]
+    InvalidInput { message: String },
+
     /// An internal error occurred
     #[error("Internal error: {message}")]
     Internal { message: String },
@@ -60,6 +142,9 @@ impl Error {
     /// Create a native error from a return code
     #[must_use]
     pub fn from_native_code(code: i32, context: &str) -> Self {
+        if code == crate::ffi::return_codes::CANCELLED {
+            return Self::Cancelled;
+        }
         let message = match code {
             -1 => "Buffer too small".to_string(),
             -2 => "Parse error in input".to_string(),
@@ -69,6 +154,83 @@ impl Error {
         Self::NativeError {
             code,
             message: format!("{context}: {message}"),
+            exception_type: None,
+            stack_trace: None,
         }
     }
+
+    /// Parse the error envelope a failed native call wrote to its output
+    ///
+    /// The envelope is NUL-terminated JSON of the form
+    /// `{"ok":false,"error":{"code":..,"message":..,"exceptionType":..,"stack":..}}`.
+    /// Returns `None` if the output holds no envelope, as with native
+    /// libraries that only report errors through `kql_get_last_error`.
+    pub(crate) fn from_error_envelope(output: &[u8]) -> Option<Self> {
+        let end = output.iter().position(|&b| b == 0).unwrap_or(output.len());
+        let envelope: ErrorEnvelope = serde_json::from_slice(&output[..end]).ok()?;
+        if envelope.ok {
+            return None;
+        }
+        let details = envelope.error;
+        if details.code == crate::ffi::return_codes::CANCELLED {
+            return Some(Self::Cancelled);
+        }
+        Some(Self::NativeError {
+            code: details.code,
+            message: details.message,
+            exception_type: details.exception_type,
+            stack_trace: details.stack,
+        })
+    }
+}
+
+#[derive(Deserialize)]
+struct ErrorEnvelope {
+    ok: bool,
+    error: ErrorDetails,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ErrorDetails {
+    code: i32,
+    message: String,
+    exception_type: Option<String>,
+    stack: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_envelope() {
+        let output = b"{\"ok\":false,\"error\":{\"code\":-3,\"message\":\"Format failed: boom\",\"exceptionType\":\"System.InvalidOperationException\",\"stack\":\"   at Format()\"}}\0stale";
+        let Some(Error::NativeError {
+            code,
+            message,
+            exception_type,
+            stack_trace,
+        }) = Error::from_error_envelope(output)
+        else {
+            panic!("expected a native error");
+        };
+        assert_eq!(code, -3);
+        assert_eq!(message, "Format failed: boom");
+        assert_eq!(
+            exception_type.as_deref(),
+            Some("System.InvalidOperationException")
+        );
+        assert_eq!(stack_trace.as_deref(), Some("   at Format()"));
+
+        let cancelled = br#"{"ok":false,"error":{"code":-4,"message":"Operation was cancelled"}}"#;
+        assert!(matches!(
+            Error::from_error_envelope(cancelled),
+            Some(Error::Cancelled)
+        ));
+
+        // Cleared or stale buffers hold no envelope
+        assert!(Error::from_error_envelope(b"\0{\"ok\":false}").is_none());
+        assert!(Error::from_error_envelope(br#"{"valid":true,"diagnostics":[]}"#).is_none());
+    }
 }
@@ -0,0 +1,84 @@
+//! Expression type inference
+//!
+//! [`KqlValidator::get_expression_type`](crate::KqlValidator::get_expression_type)
+//! reports the type Kusto.Language infers for the expression at a position:
+//! a scalar type such as `string` or `datetime`, or a table with its columns.
+//! Editors use it for hover and inlay hints.
+
+use crate::schema::Column;
+use serde::{Deserialize, Serialize};
+
+/// Inferred type of the expression at a position
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExpressionType {
+    /// Start offset of the expression (0-based, character position)
+    pub start: usize,
+    /// End offset of the expression (0-based, character position)
+    pub end: usize,
+    /// The inferred type
+    #[serde(flatten)]
+    pub kind: TypeKind,
+}
+
+/// A KQL type
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "PascalCase")]
+pub enum TypeKind {
+    /// A scalar type (`string`, `long`, `datetime`, `dynamic`, ...)
+    Scalar {
+        /// Type name
+        name: String,
+    },
+    /// A table
+    Tabular {
+        /// Columns of the table, in order
+        columns: Vec<Column>,
+    },
+    /// Any other type (e.g. a function or database reference)
+    #[serde(other)]
+    Other,
+}
+
+impl ExpressionType {
+    /// Get the scalar type name, if the expression is scalar
+    #[must_use]
+    pub fn scalar_name(&self) -> Option<&str> {
+        match &self.kind {
+            TypeKind::Scalar { name } => Some(name),
+            _ => None,
+        }
+    }
+
+    /// Get the columns, if the expression is tabular
+    #[must_use]
+    pub fn columns(&self) -> Option<&[Column]> {
+        match &self.kind {
+            TypeKind::Tabular { columns } => Some(columns),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deserialize_expression_types() {
+        let scalar: ExpressionType =
+            serde_json::from_str(r#"{"start":10,"end":19,"kind":"Scalar","name":"datetime"}"#)
+                .unwrap();
+        assert_eq!(scalar.scalar_name(), Some("datetime"));
+        assert_eq!(scalar.columns(), None);
+
+        let tabular: ExpressionType = serde_json::from_str(
+            r#"{"start":0,"end":1,"kind":"Tabular","columns":[{"name":"A","data_type":"long"}]}"#,
+        )
+        .unwrap();
+        assert_eq!(tabular.columns(), Some(&[Column::new("A", "long")][..]));
+
+        let other: ExpressionType =
+            serde_json::from_str(r#"{"start":0,"end":1,"kind":"Function"}"#).unwrap();
+        assert_eq!(other.kind, TypeKind::Other);
+    }
+}
@@ -0,0 +1,525 @@
+//! Pure-Rust fallback for basic syntax checks
+//!
+//! Enabled by the `fallback` feature. When the native library cannot be
+//! loaded, for example on a machine without .NET, [`classify`] and
+//! [`validate_syntax`] keep highlighting and basic checks working with a
+//! small tokenizer instead of Kusto.Language:
+//!
+//! - [`classify`] marks comments, strings, numbers, keywords, query and
+//!   scalar operators, type names and punctuation. Every other word is an
+//!   `Identifier`.
+//! - [`validate_syntax`] reports unbalanced brackets (`FB001`), unterminated
+//!   strings (`FB002`), and `|` with nothing before or after it (`FB003`).
+//! - [`FallbackEngine`] completes keywords, operators and built-in functions
+//!   from a compiled-in catalog (see
+//!   [`static_completions`](crate::static_completions)).
+//!
+//! None of them knows the KQL grammar, so a query the fallback accepts may
+//! still be rejected by the native library.
+//!
+//! ```
+//! use kql_language_tools::{fallback, KqlValidator};
+//!
+//! let query = "SecurityEvent | where EventID == 4625";
+//! let classifications = match KqlValidator::new() {
+//!     Ok(validator) => validator.get_classifications(query)?,
+//!     Err(_) => fallback::classify(query),
+//! };
+//! # Ok::<(), kql_language_tools::Error>(())
+//! ```
+
+use crate::backend::KqlEngine;
+use crate::catalog::{KEYWORDS, QUERY_OPERATORS, SCALAR_OPERATORS, TYPES};
+use crate::classification::{ClassificationKind, ClassificationResult, ClassifiedSpan};
+use crate::completion::CompletionResult;
+use crate::error::Error;
+use crate::schema::Schema;
+use crate::types::{Diagnostic, DiagnosticSeverity, ValidationResult};
+
+/// Characters that combine into symbolic operators (`==`, `!=`, `=~`, `>=`)
+const OPERATOR_CHARS: &str = "=<>!~+-*/%";
+
+/// A classified token, in character offsets
+struct Token {
+    start: usize,
+    end: usize,
+    kind: ClassificationKind,
+}
+
+/// Tokens of a query and the problems found while scanning them
+struct Scan {
+    chars: Vec<char>,
+    tokens: Vec<Token>,
+    /// Spans of strings that reach the end of their line or the query
+    unterminated: Vec<(usize, usize)>,
+}
+
+impl Scan {
+    fn new(query: &str) -> Self {
+        let mut scan = Self {
+            chars: query.chars().collect(),
+            tokens: Vec::new(),
+            unterminated: Vec::new(),
+        };
+        scan.tokenize();
+        scan
+    }
+
+    fn at(&self, pos: usize) -> Option<char> {
+        self.chars.get(pos).copied()
+    }
+
+    fn text(&self, start: usize, end: usize) -> String {
+        self.chars[start..end].iter().collect()
+    }
+
+    fn is_word_char(c: Option<char>) -> bool {
+        c.is_some_and(|c| c.is_alphanumeric() || c == '_')
+    }
+
+    fn scan_word(&self, mut pos: usize) -> usize {
+        while Self::is_word_char(self.at(pos)) {
+            pos += 1;
+        }
+        pos
+    }
+
+    /// Check whether the last significant token leaves room for a tabular operator
+    fn expects_operator(&self) -> bool {
+        self.tokens
+            .iter()
+            .rev()
+            .find(|token| token.kind != ClassificationKind::Comment)
+            .map_or(true, |token| {
+                matches!(self.chars[token.start], '|' | ';' | '(')
+            })
+    }
+
+    fn tokenize(&mut self) {
+        let mut pos = 0;
+        while let Some(c) = self.at(pos) {
+            let start = pos;
+            let kind = if c.is_whitespace() {
+                pos += 1;
+                continue;
+            } else if c == '/' && self.at(pos + 1) == Some('/') {
+                while self.at(pos).is_some_and(|c| c != '\n') {
+                    pos += 1;
+                }
+                ClassificationKind::Comment
+            } else if let Some(end) = self.scan_string(pos) {
+                pos = end;
+                ClassificationKind::StringLiteral
+            } else if c.is_ascii_digit() {
+                pos += 1;
+                while self.at(pos).is_some_and(|c| {
+                    c.is_alphanumeric()
+                        || c == '_'
+                        || (c == '.' && self.at(pos + 1).is_some_and(|c| c.is_ascii_digit()))
+                }) {
+                    pos += 1;
+                }
+                ClassificationKind::Literal
+            } else if c.is_alphabetic() || c == '_' || c == '$' {
+                pos = self.scan_word(pos + 1);
+                self.classify_word(start, &mut pos)
+            } else if c == '!' && self.at(pos + 1).is_some_and(char::is_alphabetic) {
+                let end = self.scan_word(pos + 1);
+                if SCALAR_OPERATORS.contains(&self.text(pos + 1, end).as_str()) {
+                    pos = end;
+                } else {
+                    pos += 1;
+                }
+                ClassificationKind::ScalarOperator
+            } else if c == '|' {
+                pos += 1;
+                ClassificationKind::QueryOperator
+            } else if "()[]{},;:.".contains(c) {
+                pos += 1;
+                ClassificationKind::Punctuation
+            } else if OPERATOR_CHARS.contains(c) {
+                while self.at(pos).is_some_and(|c| OPERATOR_CHARS.contains(c)) {
+                    pos += 1;
+                }
+                ClassificationKind::ScalarOperator
+            } else {
+                pos += 1;
+                ClassificationKind::PlainText
+            };
+            self.tokens.push(Token {
+                start,
+                end: pos,
+                kind,
+            });
+        }
+    }
+
+    /// Classify the word at `start..*end`, extending it over hyphenated operator names
+    fn classify_word(&self, start: usize, end: &mut usize) -> ClassificationKind {
+        let mut word = self.text(start, *end);
+        while self.at(*end) == Some('-') && self.at(*end + 1).is_some_and(char::is_alphabetic) {
+            let next = self.scan_word(*end + 1);
+            let hyphenated = self.text(start, next);
+            if !QUERY_OPERATORS.contains(&hyphenated.as_str()) {
+                break;
+            }
+            word = hyphenated;
+            *end = next;
+        }
+
+        let word = word.as_str();
+        if QUERY_OPERATORS.contains(&word) && self.expects_operator() {
+            ClassificationKind::QueryOperator
+        } else if KEYWORDS.contains(&word) {
+            ClassificationKind::Keyword
+        } else if SCALAR_OPERATORS.contains(&word) {
+            ClassificationKind::ScalarOperator
+        } else if word == "true" || word == "false" {
+            ClassificationKind::Literal
+        } else if TYPES.contains(&word) {
+            ClassificationKind::Type
+        } else {
+            ClassificationKind::Identifier
+        }
+    }
+
+    /// Scan a string literal starting at `pos`, returning its end
+    ///
+    /// Handles `'...'` and `"..."` with backslash escapes, verbatim `@'...'`
+    /// with doubled quotes, obfuscated `h'...'`, and multi-line
+    /// ```` ```...``` ````. Single-line strings that reach a newline are
+    /// recorded as unterminated.
+    fn scan_string(&mut self, start: usize) -> Option<usize> {
+        let mut pos = start;
+        if matches!(self.at(pos), Some('h' | 'H'))
+            && matches!(self.at(pos + 1), Some('\'' | '"' | '@'))
+        {
+            pos += 1;
+        }
+        let verbatim = self.at(pos) == Some('@');
+        if verbatim {
+            pos += 1;
+        }
+
+        let quote = self.at(pos).filter(|c| matches!(c, '\'' | '"' | '`'))?;
+        if quote == '`' {
+            if verbatim || pos != start || self.text(pos, (pos + 3).min(self.chars.len())) != "```"
+            {
+                return None;
+            }
+            pos += 3;
+            while pos < self.chars.len() {
+                if self.text(pos, (pos + 3).min(self.chars.len())) == "```" {
+                    return Some(pos + 3);
+                }
+                pos += 1;
+            }
+            self.unterminated.push((start, pos));
+            return Some(pos);
+        }
+
+        pos += 1;
+        loop {
+            match self.at(pos) {
+                None | Some('\n' | '\r') => {
+                    self.unterminated.push((start, pos));
+                    return Some(pos);
+                }
+                Some('\\') if !verbatim => pos += 2,
+                Some(c) if c == quote => {
+                    if verbatim && self.at(pos + 1) == Some(quote) {
+                        pos += 2;
+                    } else {
+                        return Some(pos + 1);
+                    }
+                }
+                Some(_) => pos += 1,
+            }
+        }
+    }
+
+    /// Build an error diagnostic for `start..end`
+    fn diagnostic(&self, code: &str, message: String, start: usize, end: usize) -> Diagnostic {
+        let before = &self.chars[..start];
+        let line = before.iter().filter(|&&c| c == '\n').count() + 1;
+        let column = start - before.iter().rposition(|&c| c == '\n').map_or(0, |i| i + 1) + 1;
+        Diagnostic {
+            message,
+            severity: DiagnosticSeverity::Error,
+            start,
+            end,
+            line,
+            column,
+            code: Some(code.to_string()),
+            category: None,
+            fixes: Vec::new(),
+            related_spans: Vec::new(),
+        }
+    }
+
+    /// Check that brackets pair up
+    fn check_brackets(&self, diagnostics: &mut Vec<Diagnostic>) {
+        let mut open: Vec<(char, usize)> = Vec::new();
+        for token in &self.tokens {
+            if token.kind != ClassificationKind::Punctuation {
+                continue;
+            }
+            let c = self.chars[token.start];
+            let opener = match c {
+                '(' | '[' | '{' => {
+                    open.push((c, token.start));
+                    continue;
+                }
+                ')' => '(',
+                ']' => '[',
+                '}' => '{',
+                _ => continue,
+            };
+            match open.pop() {
+                Some((found, _)) if found == opener => {}
+                Some((found, _)) => diagnostics.push(self.diagnostic(
+                    "FB001",
+                    format!("'{c}' does not close '{found}'"),
+                    token.start,
+                    token.end,
+                )),
+                None => diagnostics.push(self.diagnostic(
+                    "FB001",
+                    format!("'{c}' has no matching '{opener}'"),
+                    token.start,
+                    token.end,
+                )),
+            }
+        }
+        for (c, at) in open {
+            diagnostics.push(self.diagnostic(
+                "FB001",
+                format!("'{c}' is never closed"),
+                at,
+                at + 1,
+            ));
+        }
+    }
+    /// Check that every `|` sits between an expression and an operator
+    fn check_pipes(&self, diagnostics: &mut Vec<Diagnostic>) {
+        let significant: Vec<&Token> = self
+            .tokens
+            .iter()
+            .filter(|token| token.kind != ClassificationKind::Comment)
+            .collect();
+        let char_of = |token: Option<&&Token>| token.map(|token| self.chars[token.start]);
+
+        for (i, token) in significant.iter().enumerate() {
+            if self.chars[token.start] != '|' {
+                continue;
+            }
+            let before = char_of(i.checked_sub(1).and_then(|i| significant.get(i)));
+            let after = char_of(significant.get(i + 1));
+            if matches!(before, None | Some(';' | '(' | '[' | '{' | ',')) {
+                diagnostics.push(self.diagnostic(
+                    "FB003",
+                    "Expected an expression before '|'".to_string(),
+                    token.start,
+                    token.end,
+                ));
+            } else if matches!(after, None | Some('|' | ';' | ')' | ']' | '}')) {
+                diagnostics.push(self.diagnostic(
+                    "FB003",
+                    "Expected a query operator after '|'".to_string(),
+                    token.start,
+                    token.end,
+                ));
+            }
+        }
+    }
+}
+
+/// Classify a query for syntax highlighting without the native library
+///
+/// Spans use character offsets, like
+/// [`KqlValidator::get_classifications`](crate::KqlValidator::get_classifications).
+/// Whitespace is not covered by any span.
+#[must_use]
+pub fn classify(query: &str) -> ClassificationResult {
+    let scan = Scan::new(query);
+    ClassificationResult {
+        spans: scan
+            .tokens
+            .into_iter()
+            .map(|token| ClassifiedSpan {
+                start: token.start,
+                length: token.end - token.start,
+                kind: token.kind,
+            })
+            .collect(),
+    }
+}
+
+/// Check bracket balance, strings and pipeline structure without the native library
+///
+/// Diagnostics use character offsets and are all errors. A valid result
+/// only means none of these checks failed.
+#[must_use]
+pub fn validate_syntax(query: &str) -> ValidationResult {
+    let scan = Scan::new(query);
+    let mut diagnostics: Vec<Diagnostic> = scan
+        .unterminated
+        .iter()
+        .map(|&(start, end)| {
+            scan.diagnostic(
+                "FB002",
+                "String literal is not terminated".to_string(),
+                start,
+                end,
+            )
+        })
+        .collect();
+    scan.check_brackets(&mut diagnostics);
+    scan.check_pipes(&mut diagnostics);
+    diagnostics.sort_by_key(|d| d.start);
+
+    if diagnostics.is_empty() {
+        ValidationResult::valid()
+    } else {
+        ValidationResult::invalid(diagnostics)
+    }
+}
+
+/// A [`KqlEngine`] backed by the fallback checks
+///
+/// Schemas are ignored, and completions are
+/// [`static_completions`](crate::static_completions) for the text before the
+/// cursor.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FallbackEngine;
+
+impl KqlEngine for FallbackEngine {
+    fn validate_syntax(&self, query: &str) -> Result<ValidationResult, Error> {
+        Ok(validate_syntax(query))
+    }
+
+    fn validate_with_schema(
+        &self,
+        query: &str,
+        _schema: &Schema,
+    ) -> Result<ValidationResult, Error> {
+        Ok(validate_syntax(query))
+    }
+
+    fn get_completions(
+        &self,
+        query: &str,
+        cursor_position: usize,
+        _schema: Option<&Schema>,
+    ) -> Result<CompletionResult, Error> {
+        Ok(crate::completion::static_completions_at(
+            query,
+            cursor_position,
+        ))
+    }
+
+    fn get_classifications(&self, query: &str) -> Result<ClassificationResult, Error> {
+        Ok(classify(query))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(query: &str) -> Vec<(String, ClassificationKind)> {
+        let chars: Vec<char> = query.chars().collect();
+        classify(query)
+            .spans
+            .into_iter()
+            .map(|span| {
+                let text = chars[span.start..span.start + span.length].iter().collect();
+                (text, span.kind)
+            })
+            .collect()
+    }
+
+    fn codes(query: &str) -> Vec<(String, usize)> {
+        validate_syntax(query)
+            .diagnostics
+            .into_iter()
+            .map(|d| (d.code.unwrap_or_default(), d.start))
+            .collect()
+    }
+
+    #[test]
+    fn test_classify() {
+        use ClassificationKind::{
+            Comment, Identifier, Keyword, Literal, Punctuation, QueryOperator, ScalarOperator,
+            StringLiteral, Type,
+        };
+
+        let query = "let n = 5; // limit\nT | project-away X | where Name !has @'a''b' and Age > 1.5d\n| extend d = todynamic(\"x\")";
+        let expected = [
+            ("let", Keyword),
+            ("n", Identifier),
+            ("=", ScalarOperator),
+            ("5", Literal),
+            (";", Punctuation),
+            ("// limit", Comment),
+            ("T", Identifier),
+            ("|", QueryOperator),
+            ("project-away", QueryOperator),
+            ("X", Identifier),
+            ("|", QueryOperator),
+            ("where", QueryOperator),
+            ("Name", Identifier),
+            ("!has", ScalarOperator),
+            ("@'a''b'", StringLiteral),
+            ("and", ScalarOperator),
+            ("Age", Identifier),
+            (">", ScalarOperator),
+            ("1.5d", Literal),
+            ("|", QueryOperator),
+            ("extend", QueryOperator),
+            ("d", Identifier),
+            ("=", ScalarOperator),
+            ("todynamic", Identifier),
+            ("(", Punctuation),
+            ("\"x\"", StringLiteral),
+            (")", Punctuation),
+        ];
+        let expected: Vec<_> = expected
+            .iter()
+            .map(|&(text, kind)| (text.to_string(), kind))
+            .collect();
+        assert_eq!(kinds(query), expected);
+
+        // Operator names elsewhere are plain identifiers; type names are types
+        assert_eq!(
+            kinds("T | extend count = toint(x)")[3],
+            ("count".to_string(), Identifier)
+        );
+        assert_eq!(kinds("datetime(2024-01-01)")[0].1, Type);
+    }
+
+    #[test]
+    fn test_validate_syntax() {
+        assert!(validate_syntax("T | where x in (1, 2) | take 10").is_valid());
+        assert!(validate_syntax("T | where s == 'a|b(' // )\n| count").is_valid());
+        assert!(validate_syntax("print ```multi\nline```").is_valid());
+
+        assert_eq!(codes("T | where (x > 1"), [("FB001".to_string(), 10)]);
+        assert_eq!(codes("T | where x > 1)"), [("FB001".to_string(), 15)]);
+        assert_eq!(
+            codes("T | extend y = dynamic([1)]"),
+            [("FB001".to_string(), 25), ("FB001".to_string(), 26),]
+        );
+        assert_eq!(
+            codes("T | where s == 'abc\n| count"),
+            [("FB002".to_string(), 15)]
+        );
+        assert_eq!(codes("T | | take 1"), [("FB003".to_string(), 2)]);
+        assert_eq!(codes("| take 1"), [("FB003".to_string(), 0)]);
+        assert_eq!(codes("T | take 1 |"), [("FB003".to_string(), 11)]);
+
+        let result = validate_syntax("T\n| where (x");
+        let diagnostic = &result.diagnostics[0];
+        assert_eq!((diagnostic.line, diagnostic.column), (2, 9));
+    }
+}
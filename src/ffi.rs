@@ -6,7 +6,7 @@
 //! These functions should not be called directly - use the safe
 //! wrappers in the `validator` module instead.
 
-use std::ffi::c_int;
+use std::ffi::{c_int, c_void};
 
 /// Type alias for FFI function pointers
 pub type FfiResult = c_int;
@@ -69,6 +69,13 @@ pub type KqlValidateWithSchemaFn = unsafe extern "C" fn(
 /// * `-1` - Buffer too small
 pub type KqlGetLastErrorFn = unsafe extern "C" fn(output: *mut u8, output_max_len: c_int) -> FfiResult;
 
+/// FFI function type: Get the output size the last too-small call needed
+///
+/// # Returns
+/// * `> 0` - Bytes the last call on this thread returning `-1` needed
+/// * `0` - No call on this thread ran out of space
+pub type KqlGetLastRequiredSizeFn = unsafe extern "C" fn() -> FfiResult;
+
 /// FFI function type: Get completions at cursor position
 ///
 /// # Arguments
@@ -105,6 +112,712 @@ pub type KqlGetCompletionsFn = unsafe extern "C" fn(
 pub type KqlGetClassificationsFn =
     unsafe extern "C" fn(query: *const u8, query_len: c_int, output: *mut u8, output_max_len: c_int) -> FfiResult;
 
+/// FFI function type: Validate KQL with analysis options
+///
+/// # Arguments
+/// * `query` - Pointer to UTF-8 encoded query string
+/// * `query_len` - Length of the query in bytes
+/// * `schema_json` - Pointer to UTF-8 encoded JSON schema (can be null)
+/// * `schema_len` - Length of the schema JSON in bytes (0 if null)
+/// * `options_json` - Pointer to UTF-8 encoded JSON options
+/// * `options_len` - Length of the options JSON in bytes
+/// * `output` - Pointer to output buffer for JSON result
+/// * `output_max_len` - Maximum size of output buffer
+///
+/// # Returns
+/// Same as `KqlValidateSyntaxFn`
+pub type KqlValidateWithOptionsFn = unsafe extern "C" fn(
+    query: *const u8,
+    query_len: c_int,
+    schema_json: *const u8,
+    schema_len: c_int,
+    options_json: *const u8,
+    options_len: c_int,
+    output: *mut u8,
+    output_max_len: c_int,
+) -> FfiResult;
+
+/// FFI function type: Get completion items with per-call options
+///
+/// # Arguments
+/// * `query` - Pointer to UTF-8 encoded query string
+/// * `query_len` - Length of the query in bytes
+/// * `cursor_pos` - Cursor position (0-based character offset)
+/// * `schema_json` - Pointer to UTF-8 encoded JSON schema (can be null)
+/// * `schema_len` - Length of the schema JSON in bytes (0 if null)
+/// * `options_json` - Pointer to UTF-8 encoded JSON completion options
+/// * `options_len` - Length of the options JSON in bytes
+/// * `output` - Pointer to output buffer for JSON result
+/// * `output_max_len` - Maximum size of output buffer
+///
+/// # Returns
+/// Same as `KqlValidateSyntaxFn`
+pub type KqlGetCompletionsWithOptionsFn = unsafe extern "C" fn(
+    query: *const u8,
+    query_len: c_int,
+    cursor_pos: c_int,
+    schema_json: *const u8,
+    schema_len: c_int,
+    options_json: *const u8,
+    options_len: c_int,
+    output: *mut u8,
+    output_max_len: c_int,
+) -> FfiResult;
+
+/// FFI function type: Get document structure (statements, declarations, folding regions)
+///
+/// # Arguments
+/// * `query` - Pointer to UTF-8 encoded query string
+/// * `query_len` - Length of the query in bytes
+/// * `output` - Pointer to output buffer for JSON result
+/// * `output_max_len` - Maximum size of output buffer
+///
+/// # Returns
+/// Same as `KqlValidateSyntaxFn`
+pub type KqlGetDocumentStructureFn =
+    unsafe extern "C" fn(query: *const u8, query_len: c_int, output: *mut u8, output_max_len: c_int) -> FfiResult;
+
+/// FFI function type: Validate a batch of KQL queries
+///
+/// # Arguments
+/// * `queries_json` - Pointer to UTF-8 encoded JSON array of query strings
+/// * `queries_len` - Length of the queries JSON in bytes
+/// * `schema_json` - Pointer to UTF-8 encoded JSON schema (can be null)
+/// * `schema_len` - Length of the schema JSON in bytes (0 if null)
+/// * `options_json` - Pointer to UTF-8 encoded JSON options
+/// * `options_len` - Length of the options JSON in bytes
+/// * `output` - Pointer to output buffer for JSON array of results
+/// * `output_max_len` - Maximum size of output buffer
+///
+/// # Returns
+/// Same as `KqlValidateSyntaxFn`
+pub type KqlValidateBatchFn = unsafe extern "C" fn(
+    queries_json: *const u8,
+    queries_len: c_int,
+    schema_json: *const u8,
+    schema_len: c_int,
+    options_json: *const u8,
+    options_len: c_int,
+    output: *mut u8,
+    output_max_len: c_int,
+) -> FfiResult;
+
+/// FFI function type: Format a KQL query
+///
+/// # Arguments
+/// * `query` - Pointer to UTF-8 encoded query string
+/// * `query_len` - Length of the query string in bytes
+/// * `options_json` - Pointer to UTF-8 encoded JSON format options
+/// * `options_len` - Length of the options JSON in bytes
+/// * `output` - Pointer to output buffer for the formatted query as a JSON string
+/// * `output_max_len` - Maximum size of output buffer
+///
+/// # Returns
+/// Same as `KqlValidateSyntaxFn`
+pub type KqlFormatFn = unsafe extern "C" fn(
+    query: *const u8,
+    query_len: c_int,
+    options_json: *const u8,
+    options_len: c_int,
+    output: *mut u8,
+    output_max_len: c_int,
+) -> FfiResult;
+
+/// FFI function type: Infer the result schema of a KQL query
+///
+/// # Arguments
+/// * `query` - Pointer to UTF-8 encoded query string
+/// * `query_len` - Length of the query string in bytes
+/// * `schema_json` - Pointer to UTF-8 encoded JSON schema
+/// * `schema_len` - Length of the schema JSON in bytes
+/// * `options_json` - Pointer to UTF-8 encoded JSON options
+/// * `options_len` - Length of the options JSON in bytes
+/// * `output` - Pointer to output buffer for the result table JSON
+/// * `output_max_len` - Maximum size of output buffer
+///
+/// # Returns
+/// Same as `KqlValidateSyntaxFn`. Returns `-2` if the query does not
+/// produce a tabular result.
+pub type KqlInferResultSchemaFn = unsafe extern "C" fn(
+    query: *const u8,
+    query_len: c_int,
+    schema_json: *const u8,
+    schema_len: c_int,
+    options_json: *const u8,
+    options_len: c_int,
+    output: *mut u8,
+    output_max_len: c_int,
+) -> FfiResult;
+
+/// FFI function type: Analyze the entities referenced by a KQL query
+///
+/// # Arguments
+/// * `query` - Pointer to UTF-8 encoded query string
+/// * `query_len` - Length of the query string in bytes
+/// * `schema_json` - Pointer to UTF-8 encoded JSON schema
+/// * `schema_len` - Length of the schema JSON in bytes
+/// * `options_json` - Pointer to UTF-8 encoded JSON options
+/// * `options_len` - Length of the options JSON in bytes
+/// * `output` - Pointer to output buffer for the analysis JSON
+/// * `output_max_len` - Maximum size of output buffer
+///
+/// # Returns
+/// Same as `KqlValidateSyntaxFn`
+pub type KqlAnalyzeFn = unsafe extern "C" fn(
+    query: *const u8,
+    query_len: c_int,
+    schema_json: *const u8,
+    schema_len: c_int,
+    options_json: *const u8,
+    options_len: c_int,
+    output: *mut u8,
+    output_max_len: c_int,
+) -> FfiResult;
+
+/// FFI function type: Register a schema with the native library
+///
+/// # Arguments
+/// * `schema_json` - Pointer to UTF-8 encoded JSON schema
+/// * `schema_len` - Length of the schema JSON in bytes
+///
+/// # Returns
+/// * `> 0` - Schema id to pass to the `*_with_schema_id` functions
+/// * `-2` - Parse error in the schema JSON
+/// * `-3` - Internal error
+pub type KqlRegisterSchemaFn =
+    unsafe extern "C" fn(schema_json: *const u8, schema_len: c_int) -> FfiResult;
+
+/// FFI function type: Release a registered schema
+///
+/// # Returns
+/// * `0` - Success
+/// * `-2` - Unknown schema id
+pub type KqlReleaseSchemaFn = unsafe extern "C" fn(schema_id: c_int) -> FfiResult;
+
+/// FFI function type: Validate KQL against a registered schema
+///
+/// # Arguments
+/// * `query` - Pointer to UTF-8 encoded query string
+/// * `query_len` - Length of the query string in bytes
+/// * `schema_id` - Id returned by `kql_register_schema`
+/// * `options_json` - Pointer to UTF-8 encoded JSON options
+/// * `options_len` - Length of the options JSON in bytes
+/// * `output` - Pointer to output buffer for JSON result
+/// * `output_max_len` - Maximum size of output buffer
+///
+/// # Returns
+/// Same as `KqlValidateSyntaxFn`. Returns `-2` for an unknown schema id.
+pub type KqlValidateWithSchemaIdFn = unsafe extern "C" fn(
+    query: *const u8,
+    query_len: c_int,
+    schema_id: c_int,
+    options_json: *const u8,
+    options_len: c_int,
+    output: *mut u8,
+    output_max_len: c_int,
+) -> FfiResult;
+
+/// FFI function type: Get completions using a registered schema
+///
+/// # Arguments
+/// * `query` - Pointer to UTF-8 encoded query string
+/// * `query_len` - Length of the query string in bytes
+/// * `cursor_position` - Cursor position (0-based character offset)
+/// * `schema_id` - Id returned by `kql_register_schema`
+/// * `output` - Pointer to output buffer for JSON result
+/// * `output_max_len` - Maximum size of output buffer
+///
+/// # Returns
+/// Same as `KqlValidateSyntaxFn`. Returns `-2` for an unknown schema id.
+pub type KqlGetCompletionsWithSchemaIdFn = unsafe extern "C" fn(
+    query: *const u8,
+    query_len: c_int,
+    cursor_position: c_int,
+    schema_id: c_int,
+    output: *mut u8,
+    output_max_len: c_int,
+) -> FfiResult;
+
+/// FFI function type: Get schema-aware classifications for a KQL query
+///
+/// # Arguments
+/// * `query` - Pointer to UTF-8 encoded query string
+/// * `query_len` - Length of the query string in bytes
+/// * `schema_json` - Pointer to UTF-8 encoded JSON schema
+/// * `schema_len` - Length of the schema JSON in bytes
+/// * `options_json` - Pointer to UTF-8 encoded JSON options
+/// * `options_len` - Length of the options JSON in bytes
+/// * `output` - Pointer to output buffer for JSON result
+/// * `output_max_len` - Maximum size of output buffer
+///
+/// # Returns
+/// Same as `KqlValidateSyntaxFn`
+pub type KqlGetSemanticClassificationsFn = unsafe extern "C" fn(
+    query: *const u8,
+    query_len: c_int,
+    schema_json: *const u8,
+    schema_len: c_int,
+    options_json: *const u8,
+    options_len: c_int,
+    output: *mut u8,
+    output_max_len: c_int,
+) -> FfiResult;
+
+/// FFI function type: Get the syntax classifications of tokens starting within a range
+///
+/// # Arguments
+/// * `query` - Pointer to UTF-8 encoded query string
+/// * `query_len` - Length of the query string in bytes
+/// * `range_start` - Start of the range (0-based character offset)
+/// * `range_end` - End of the range (0-based character offset)
+/// * `output` - Pointer to output buffer for JSON result
+/// * `output_max_len` - Maximum size of output buffer
+///
+/// # Returns
+/// Same as `KqlValidateSyntaxFn`
+pub type KqlGetClassificationsInRangeFn = unsafe extern "C" fn(
+    query: *const u8,
+    query_len: c_int,
+    range_start: c_int,
+    range_end: c_int,
+    output: *mut u8,
+    output_max_len: c_int,
+) -> FfiResult;
+
+/// Receives one batch of classified spans from `kql_classify_chunks`
+///
+/// # Arguments
+/// * `context` - The context pointer passed to `kql_classify_chunks`
+/// * `batch` - Pointer to the UTF-8 JSON classification result, valid for the call
+/// * `len` - Length of the JSON in bytes
+///
+/// # Returns
+/// * `0` - Continue with the next batch
+/// * Anything else - Stop classifying
+pub type KqlClassifyBatchCallback =
+    extern "C" fn(context: *mut c_void, batch: *const u8, len: c_int) -> c_int;
+
+/// FFI function type: Classify a query, passing the spans to a callback in batches
+///
+/// The query is parsed once and its tokens are classified in order, so
+/// batches arrive sorted and only one batch is held at a time. Batches are
+/// always JSON, whatever the result format.
+///
+/// # Arguments
+/// * `query` - Pointer to UTF-8 encoded query string
+/// * `query_len` - Length of the query string in bytes
+/// * `batch_spans` - Maximum number of spans per batch
+/// * `callback` - Called with each batch
+/// * `context` - Passed through to `callback`
+///
+/// # Returns
+/// * `0` - Every batch was passed, or `callback` stopped early
+/// * `< 0` - Error code; message via `kql_get_last_error`
+pub type KqlClassifyChunksFn = unsafe extern "C" fn(
+    query: *const u8,
+    query_len: c_int,
+    batch_spans: c_int,
+    callback: KqlClassifyBatchCallback,
+    context: *mut c_void,
+) -> c_int;
+
+/// FFI function type: Get the definition of the name at a cursor position
+///
+/// # Arguments
+/// * `query` - Pointer to UTF-8 encoded query string
+/// * `query_len` - Length of the query string in bytes
+/// * `cursor_position` - Cursor position (0-based character offset)
+/// * `output` - Pointer to output buffer for JSON result (`null` if none)
+/// * `output_max_len` - Maximum size of output buffer
+///
+/// # Returns
+/// Same as `KqlValidateSyntaxFn`
+pub type KqlGetDefinitionFn = unsafe extern "C" fn(
+    query: *const u8,
+    query_len: c_int,
+    cursor_position: c_int,
+    output: *mut u8,
+    output_max_len: c_int,
+) -> FfiResult;
+
+/// FFI function type: Get the document outline (let bindings, functions, pipeline stages)
+///
+/// # Arguments
+/// * `query` - Pointer to UTF-8 encoded query string
+/// * `query_len` - Length of the query in bytes
+/// * `output` - Pointer to output buffer for JSON array of symbols
+/// * `output_max_len` - Maximum size of output buffer
+///
+/// # Returns
+/// Same as `KqlValidateSyntaxFn`
+pub type KqlGetDocumentSymbolsFn =
+    unsafe extern "C" fn(query: *const u8, query_len: c_int, output: *mut u8, output_max_len: c_int) -> FfiResult;
+
+/// FFI function type: Get the type of the expression at a position
+///
+/// # Arguments
+/// * `query` - Pointer to UTF-8 encoded query string
+/// * `query_len` - Length of the query string in bytes
+/// * `position` - Position (0-based character offset)
+/// * `schema_json` - Pointer to UTF-8 encoded JSON schema
+/// * `schema_len` - Length of the schema JSON in bytes
+/// * `options_json` - Pointer to UTF-8 encoded JSON options
+/// * `options_len` - Length of the options JSON in bytes
+/// * `output` - Pointer to output buffer for JSON result (`null` if none)
+/// * `output_max_len` - Maximum size of output buffer
+///
+/// # Returns
+/// Same as `KqlValidateSyntaxFn`
+pub type KqlGetExpressionTypeFn = unsafe extern "C" fn(
+    query: *const u8,
+    query_len: c_int,
+    position: c_int,
+    schema_json: *const u8,
+    schema_len: c_int,
+    options_json: *const u8,
+    options_len: c_int,
+    output: *mut u8,
+    output_max_len: c_int,
+) -> FfiResult;
+
+/// FFI function type: Fill in the documentation of a completion item
+///
+/// # Arguments
+/// * `item_json` - Pointer to UTF-8 encoded JSON completion item
+/// * `item_len` - Length of the item JSON in bytes
+/// * `schema_json` - Pointer to UTF-8 encoded JSON schema (nullable)
+/// * `schema_len` - Length of the schema JSON in bytes (0 if null)
+/// * `options_json` - Pointer to UTF-8 encoded JSON options
+/// * `options_len` - Length of the options JSON in bytes
+/// * `output` - Pointer to output buffer for the JSON completion item
+/// * `output_max_len` - Maximum size of output buffer
+///
+/// # Returns
+/// Same as `KqlValidateSyntaxFn`
+pub type KqlResolveCompletionFn = unsafe extern "C" fn(
+    item_json: *const u8,
+    item_len: c_int,
+    schema_json: *const u8,
+    schema_len: c_int,
+    options_json: *const u8,
+    options_len: c_int,
+    output: *mut u8,
+    output_max_len: c_int,
+) -> FfiResult;
+
+/// FFI function type: Get the matching bracket pairs of a query
+///
+/// # Arguments
+/// * `query` - Pointer to UTF-8 encoded query string
+/// * `query_len` - Length of the query in bytes
+/// * `output` - Pointer to output buffer for JSON array of bracket pairs
+/// * `output_max_len` - Maximum size of output buffer
+///
+/// # Returns
+/// Same as `KqlValidateSyntaxFn`
+pub type KqlGetBracketPairsFn =
+    unsafe extern "C" fn(query: *const u8, query_len: c_int, output: *mut u8, output_max_len: c_int) -> FfiResult;
+
+/// FFI function type: Get the syntax ranges enclosing a position
+///
+/// # Arguments
+/// * `query` - Pointer to UTF-8 encoded query string
+/// * `query_len` - Length of the query in bytes
+/// * `position` - Position (0-based character offset)
+/// * `output` - Pointer to output buffer for JSON array of ranges, innermost first
+/// * `output_max_len` - Maximum size of output buffer
+///
+/// # Returns
+/// Same as `KqlValidateSyntaxFn`
+pub type KqlGetEnclosingRangesFn = unsafe extern "C" fn(
+    query: *const u8,
+    query_len: c_int,
+    position: c_int,
+    output: *mut u8,
+    output_max_len: c_int,
+) -> FfiResult;
+
+/// FFI function type: Get the syntax tree of a query
+///
+/// # Arguments
+/// * `query` - Pointer to UTF-8 encoded query string
+/// * `query_len` - Length of the query in bytes
+/// * `output` - Pointer to output buffer for JSON result (nodes in pre-order)
+/// * `output_max_len` - Maximum size of output buffer
+///
+/// # Returns
+/// Same as `KqlValidateSyntaxFn`
+pub type KqlGetSyntaxTreeFn =
+    unsafe extern "C" fn(query: *const u8, query_len: c_int, output: *mut u8, output_max_len: c_int) -> FfiResult;
+
+/// FFI function type: Get the inlay hints of a query within a range
+///
+/// # Arguments
+/// * `query` - Pointer to UTF-8 encoded query string
+/// * `query_len` - Length of the query string in bytes
+/// * `range_start` - Start of the range (0-based character offset)
+/// * `range_end` - End of the range (0-based character offset)
+/// * `schema_json` - Pointer to UTF-8 encoded JSON schema
+/// * `schema_len` - Length of the schema JSON in bytes
+/// * `options_json` - Pointer to UTF-8 encoded JSON options
+/// * `options_len` - Length of the options JSON in bytes
+/// * `output` - Pointer to output buffer for JSON array of hints
+/// * `output_max_len` - Maximum size of output buffer
+///
+/// # Returns
+/// Same as `KqlValidateSyntaxFn`
+pub type KqlGetInlayHintsFn = unsafe extern "C" fn(
+    query: *const u8,
+    query_len: c_int,
+    range_start: c_int,
+    range_end: c_int,
+    schema_json: *const u8,
+    schema_len: c_int,
+    options_json: *const u8,
+    options_len: c_int,
+    output: *mut u8,
+    output_max_len: c_int,
+) -> FfiResult;
+
+/// FFI function type: Split a query's final pipeline into its stages
+///
+/// # Arguments
+/// * `query` - Pointer to UTF-8 encoded query string
+/// * `query_len` - Length of the query string in bytes
+/// * `schema_json` - Pointer to UTF-8 encoded JSON schema
+/// * `schema_len` - Length of the schema JSON in bytes
+/// * `options_json` - Pointer to UTF-8 encoded JSON options
+/// * `options_len` - Length of the options JSON in bytes
+/// * `output` - Pointer to output buffer for JSON result
+/// * `output_max_len` - Maximum size of output buffer
+///
+/// # Returns
+/// Same as `KqlValidateSyntaxFn`
+pub type KqlAnalyzePipelineFn = unsafe extern "C" fn(
+    query: *const u8,
+    query_len: c_int,
+    schema_json: *const u8,
+    schema_len: c_int,
+    options_json: *const u8,
+    options_len: c_int,
+    output: *mut u8,
+    output_max_len: c_int,
+) -> FfiResult;
+
+/// FFI function type: List a query's joins, lookups and unions
+///
+/// # Arguments
+/// * `query` - Pointer to UTF-8 encoded query string
+/// * `query_len` - Length of the query string in bytes
+/// * `schema_json` - Pointer to UTF-8 encoded JSON schema
+/// * `schema_len` - Length of the schema JSON in bytes
+/// * `options_json` - Pointer to UTF-8 encoded JSON options
+/// * `options_len` - Length of the options JSON in bytes
+/// * `output` - Pointer to output buffer for JSON result
+/// * `output_max_len` - Maximum size of output buffer
+///
+/// # Returns
+/// Same as `KqlValidateSyntaxFn`
+pub type KqlAnalyzeJoinsFn = unsafe extern "C" fn(
+    query: *const u8,
+    query_len: c_int,
+    schema_json: *const u8,
+    schema_len: c_int,
+    options_json: *const u8,
+    options_len: c_int,
+    output: *mut u8,
+    output_max_len: c_int,
+) -> FfiResult;
+
+/// FFI function type: Run the Kusto.Language best-practice analyzers
+///
+/// # Arguments
+/// * `query` - Pointer to UTF-8 encoded query string
+/// * `query_len` - Length of the query string in bytes
+/// * `schema_json` - Pointer to UTF-8 encoded JSON schema
+/// * `schema_len` - Length of the schema JSON in bytes
+/// * `options_json` - Pointer to UTF-8 encoded JSON options
+/// * `options_len` - Length of the options JSON in bytes
+/// * `output` - Pointer to output buffer for JSON array of diagnostics
+/// * `output_max_len` - Maximum size of output buffer
+///
+/// # Returns
+/// Same as `KqlValidateSyntaxFn`
+pub type KqlGetAnalyzerDiagnosticsFn = unsafe extern "C" fn(
+    query: *const u8,
+    query_len: c_int,
+    schema_json: *const u8,
+    schema_len: c_int,
+    options_json: *const u8,
+    options_len: c_int,
+    output: *mut u8,
+    output_max_len: c_int,
+) -> FfiResult;
+
+/// FFI function type: Open a document whose parse state is kept between edits
+///
+/// # Arguments
+/// * `text` - Pointer to UTF-8 encoded document text
+/// * `text_len` - Length of the text in bytes
+/// * `schema_id` - Id returned by `kql_register_schema`, or `0` for no schema
+/// * `options_json` - Pointer to UTF-8 encoded JSON options
+/// * `options_len` - Length of the options JSON in bytes
+///
+/// # Returns
+/// * `> 0` - Document id to pass to the `kql_document_*` functions
+/// * `-2` - Unknown schema id or parse error in the options JSON
+/// * `-3` - Internal error
+pub type KqlDocumentOpenFn = unsafe extern "C" fn(
+    text: *const u8,
+    text_len: c_int,
+    schema_id: c_int,
+    options_json: *const u8,
+    options_len: c_int,
+) -> FfiResult;
+
+/// FFI function type: Replace a range of an open document
+///
+/// # Arguments
+/// * `document_id` - Id returned by `kql_document_open`
+/// * `start` - Start of the replaced range (0-based character offset)
+/// * `end` - End of the replaced range (exclusive)
+/// * `text` - Pointer to UTF-8 encoded replacement text
+/// * `text_len` - Length of the replacement text in bytes
+///
+/// # Returns
+/// * `0` - Success
+/// * `-2` - Unknown document id or range outside the document
+pub type KqlDocumentEditFn = unsafe extern "C" fn(
+    document_id: c_int,
+    start: c_int,
+    end: c_int,
+    text: *const u8,
+    text_len: c_int,
+) -> FfiResult;
+
+/// FFI function type: Validate an open document
+///
+/// # Returns
+/// Same as `KqlValidateSyntaxFn`. Returns `-2` for an unknown document id.
+pub type KqlDocumentGetDiagnosticsFn =
+    unsafe extern "C" fn(document_id: c_int, output: *mut u8, output_max_len: c_int) -> FfiResult;
+
+/// FFI function type: Get completions in an open document
+///
+/// # Arguments
+/// * `document_id` - Id returned by `kql_document_open`
+/// * `cursor_position` - Cursor position (0-based character offset)
+/// * `output` - Pointer to output buffer for JSON result
+/// * `output_max_len` - Maximum size of output buffer
+///
+/// # Returns
+/// Same as `KqlValidateSyntaxFn`. Returns `-2` for an unknown document id.
+pub type KqlDocumentGetCompletionsFn = unsafe extern "C" fn(
+    document_id: c_int,
+    cursor_position: c_int,
+    output: *mut u8,
+    output_max_len: c_int,
+) -> FfiResult;
+
+/// FFI function type: Close an open document
+///
+/// # Returns
+/// * `0` - Success
+/// * `-2` - Unknown document id
+pub type KqlDocumentCloseFn = unsafe extern "C" fn(document_id: c_int) -> FfiResult;
+
+/// FFI function type: Create a cancellation source
+///
+/// Pass the id as the `cancellation_id` option to make validation observe it.
+///
+/// # Returns
+/// * `> 0` - Cancellation id
+/// * `-3` - Internal error
+pub type KqlCancellationCreateFn = unsafe extern "C" fn() -> FfiResult;
+
+/// FFI function type: Cancel the calls observing a cancellation source
+///
+/// Safe to call from any thread while a call is running.
+///
+/// # Returns
+/// * `0` - Success
+/// * `-2` - Unknown cancellation id
+pub type KqlCancellationCancelFn = unsafe extern "C" fn(cancellation_id: c_int) -> FfiResult;
+
+/// FFI function type: Release a cancellation source
+///
+/// # Returns
+/// * `0` - Success
+/// * `-2` - Unknown cancellation id
+pub type KqlCancellationReleaseFn = unsafe extern "C" fn(cancellation_id: c_int) -> FfiResult;
+
+/// FFI function type: Free a result allocated by the native library
+///
+/// Passing `ALLOCATE_RESULT` as `output_max_len` to any function with an
+/// output buffer makes the native library allocate the result instead:
+/// `output` is then read as a `uint8_t**` and receives the result pointer,
+/// and the return value is its length. The pointer must be released with
+/// this function.
+pub type KqlFreeResultFn = unsafe extern "C" fn(data: *mut u8);
+
+/// FFI function type: Read the result of the last too-small call in chunks
+///
+/// After a call on this thread returns `-1`, its serialized result is kept
+/// until read to the end.
+///
+/// # Arguments
+/// * `offset` - Byte offset into the pending result
+/// * `output` - Pointer to output buffer for the chunk
+/// * `output_max_len` - Maximum size of output buffer
+///
+/// # Returns
+/// * `> 0` - Length of the chunk written
+/// * `0` - End of the result; the pending result is released
+/// * `-2` - No pending result on this thread
+pub type KqlReadResultChunkFn =
+    unsafe extern "C" fn(offset: c_int, output: *mut u8, output_max_len: c_int) -> FfiResult;
+
+/// Set the serialization format of call results on this thread (optional)
+///
+/// # Arguments
+/// * `format` - One of [`result_formats`]
+///
+/// # Returns
+/// * `0` - Later results on this thread use the format
+/// * `-2` - The format is not supported
+pub type KqlSetResultFormatFn = unsafe extern "C" fn(format: c_int) -> c_int;
+
+/// Get the ABI version of the native library (optional)
+///
+/// # Returns
+/// The version; compared against [`ABI_VERSION`] at load time
+pub type KqlGetAbiVersionFn = unsafe extern "C" fn() -> c_int;
+
+/// Get the capabilities of the native library (optional)
+///
+/// # Arguments
+/// * `output` - Pointer to output buffer for JSON array of capability names
+/// * `output_max_len` - Maximum size of output buffer
+///
+/// # Returns
+/// * `> 0` - Length of JSON written
+/// * `-1` - Buffer too small
+pub type KqlGetCapabilitiesFn = unsafe extern "C" fn(output: *mut u8, output_max_len: c_int) -> c_int;
+
+/// Receives a log message from the native library
+///
+/// # Arguments
+/// * `level` - 1 error, 2 warn, 3 info, 4 debug, 5 trace
+/// * `message` - Pointer to UTF-8 message bytes, valid for the call
+/// * `len` - Length of message in bytes
+pub type KqlLogCallback = extern "C" fn(level: c_int, message: *const u8, len: c_int);
+
+/// Register the callback native log messages are sent to (optional)
+///
+/// # Returns
+/// * `0` - Registered
+pub type KqlSetLogCallbackFn = unsafe extern "C" fn(callback: KqlLogCallback) -> c_int;
+
 /// Symbol names in the native library
 pub mod symbols {
     /// Initialize function symbol
@@ -127,6 +840,126 @@ pub mod symbols {
 
     /// Get classifications function symbol
     pub const KQL_GET_CLASSIFICATIONS: &str = "kql_get_classifications";
+
+    /// Validate with options function symbol
+    pub const KQL_VALIDATE_WITH_OPTIONS: &str = "kql_validate_with_options";
+
+    /// Get completions with options function symbol
+    pub const KQL_GET_COMPLETIONS_WITH_OPTIONS: &str = "kql_get_completions_with_options";
+
+    /// Get document structure function symbol
+    pub const KQL_GET_DOCUMENT_STRUCTURE: &str = "kql_get_document_structure";
+
+    /// Validate batch function symbol
+    pub const KQL_VALIDATE_BATCH: &str = "kql_validate_batch";
+
+    /// Format function symbol
+    pub const KQL_FORMAT: &str = "kql_format";
+
+    /// Infer result schema function symbol
+    pub const KQL_INFER_RESULT_SCHEMA: &str = "kql_infer_result_schema";
+
+    /// Analyze function symbol
+    pub const KQL_ANALYZE: &str = "kql_analyze";
+
+    /// Register schema function symbol
+    pub const KQL_REGISTER_SCHEMA: &str = "kql_register_schema";
+
+    /// Release schema function symbol
+    pub const KQL_RELEASE_SCHEMA: &str = "kql_release_schema";
+
+    /// Validate with schema id function symbol
+    pub const KQL_VALIDATE_WITH_SCHEMA_ID: &str = "kql_validate_with_schema_id";
+
+    /// Get completions with schema id function symbol
+    pub const KQL_GET_COMPLETIONS_WITH_SCHEMA_ID: &str = "kql_get_completions_with_schema_id";
+
+    /// Get semantic classifications function symbol
+    pub const KQL_GET_SEMANTIC_CLASSIFICATIONS: &str = "kql_get_semantic_classifications";
+
+    /// Get classifications in range function symbol
+    pub const KQL_GET_CLASSIFICATIONS_IN_RANGE: &str = "kql_get_classifications_in_range";
+
+    /// Streaming classification function symbol
+    pub const KQL_CLASSIFY_CHUNKS: &str = "kql_classify_chunks";
+
+    /// Get definition function symbol
+    pub const KQL_GET_DEFINITION: &str = "kql_get_definition";
+
+    /// Get document symbols function symbol
+    pub const KQL_GET_DOCUMENT_SYMBOLS: &str = "kql_get_document_symbols";
+
+    /// Get expression type function symbol
+    pub const KQL_GET_EXPRESSION_TYPE: &str = "kql_get_expression_type";
+
+    /// Resolve completion function symbol
+    pub const KQL_RESOLVE_COMPLETION: &str = "kql_resolve_completion";
+
+    /// Get bracket pairs function symbol
+    pub const KQL_GET_BRACKET_PAIRS: &str = "kql_get_bracket_pairs";
+
+    /// Get enclosing ranges function symbol
+    pub const KQL_GET_ENCLOSING_RANGES: &str = "kql_get_enclosing_ranges";
+
+    /// Get syntax tree function symbol
+    pub const KQL_GET_SYNTAX_TREE: &str = "kql_get_syntax_tree";
+
+    /// Get inlay hints function symbol
+    pub const KQL_GET_INLAY_HINTS: &str = "kql_get_inlay_hints";
+
+    /// Analyze pipeline function symbol
+    pub const KQL_ANALYZE_PIPELINE: &str = "kql_analyze_pipeline";
+
+    /// Analyze joins function symbol
+    pub const KQL_ANALYZE_JOINS: &str = "kql_analyze_joins";
+
+    /// Get analyzer diagnostics function symbol
+    pub const KQL_GET_ANALYZER_DIAGNOSTICS: &str = "kql_get_analyzer_diagnostics";
+
+    /// Open document function symbol
+    pub const KQL_DOCUMENT_OPEN: &str = "kql_document_open";
+
+    /// Edit document function symbol
+    pub const KQL_DOCUMENT_EDIT: &str = "kql_document_edit";
+
+    /// Get document diagnostics function symbol
+    pub const KQL_DOCUMENT_GET_DIAGNOSTICS: &str = "kql_document_get_diagnostics";
+
+    /// Get document completions function symbol
+    pub const KQL_DOCUMENT_GET_COMPLETIONS: &str = "kql_document_get_completions";
+
+    /// Close document function symbol
+    pub const KQL_DOCUMENT_CLOSE: &str = "kql_document_close";
+
+    /// Create cancellation function symbol
+    pub const KQL_CANCELLATION_CREATE: &str = "kql_cancellation_create";
+
+    /// Cancel cancellation function symbol
+    pub const KQL_CANCELLATION_CANCEL: &str = "kql_cancellation_cancel";
+
+    /// Release cancellation function symbol
+    pub const KQL_CANCELLATION_RELEASE: &str = "kql_cancellation_release";
+
+    /// Get last required size function symbol
+    pub const KQL_GET_LAST_REQUIRED_SIZE: &str = "kql_get_last_required_size";
+
+    /// Free result function symbol
+    pub const KQL_FREE_RESULT: &str = "kql_free_result";
+
+    /// Read result chunk function symbol
+    pub const KQL_READ_RESULT_CHUNK: &str = "kql_read_result_chunk";
+
+    /// Set result format function symbol
+    pub const KQL_SET_RESULT_FORMAT: &str = "kql_set_result_format";
+
+    /// Get ABI version function symbol
+    pub const KQL_GET_ABI_VERSION: &str = "kql_get_abi_version";
+
+    /// Get capabilities function symbol
+    pub const KQL_GET_CAPABILITIES: &str = "kql_get_capabilities";
+
+    /// Set log callback function symbol
+    pub const KQL_SET_LOG_CALLBACK: &str = "kql_set_log_callback";
 }
 
 /// Return codes from FFI functions
@@ -136,6 +969,9 @@ pub mod return_codes {
     /// Buffer too small - need to retry with larger buffer
     pub const BUFFER_TOO_SMALL: c_int = -1;
 
+    /// The call observed a cancelled `cancellation_id`
+    pub const CANCELLED: c_int = -4;
+
     /// Check if return code indicates success
     pub fn is_success(code: c_int) -> bool {
         code >= 0
@@ -147,8 +983,31 @@ pub mod return_codes {
     }
 }
 
+/// ABI version of the native library this crate is built against
+///
+/// Bumped when an existing export changes its signature or result shape.
+/// Adding exports does not change it.
+pub const ABI_VERSION: c_int = 1;
+
 /// Default buffer size for FFI output (64KB)
 pub const DEFAULT_BUFFER_SIZE: usize = 64 * 1024;
 
 /// Maximum buffer size for FFI output (4MB)
 pub const MAX_BUFFER_SIZE: usize = 4 * 1024 * 1024;
+
+/// Serialization formats of call results
+#[cfg(feature = "cbor")]
+pub mod result_formats {
+    use std::ffi::c_int;
+
+    /// UTF-8 JSON, the default
+    pub const JSON: c_int = 0;
+
+    /// CBOR (RFC 8949) with the same structure as the JSON
+    pub const CBOR: c_int = 1;
+}
+
+/// `output_max_len` value asking the native library to allocate the result
+///
+/// Libraries without `kql_free_result` treat it as a too-small buffer.
+pub const ALLOCATE_RESULT: c_int = -1;
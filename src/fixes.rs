@@ -0,0 +1,131 @@
+//! Quick-fix application
+//!
+//! Diagnostics can carry [`Fix`]es (see [`Diagnostic::fixes`](crate::Diagnostic::fixes)).
+//! [`KqlValidator::apply_fixes`](crate::KqlValidator::apply_fixes) applies a
+//! selection of them to a query in one pass and re-validates the result.
+
+use crate::types::{Fix, TextEdit, ValidationResult};
+
+/// Result of applying quick fixes to a query
+#[derive(Debug, Clone)]
+pub struct FixOutcome {
+    /// The query with all applied fixes
+    pub query: String,
+    /// Fixes that were applied
+    pub applied: Vec<Fix>,
+    /// Fixes that were skipped because they overlap an earlier fix or fall
+    /// outside the query
+    pub conflicts: Vec<Fix>,
+    /// Validation result for the fixed query, with the schema passed to
+    /// `apply_fixes` if any
+    pub validation: ValidationResult,
+}
+
+impl FixOutcome {
+    /// Check if every requested fix was applied
+    #[must_use]
+    pub fn is_complete(&self) -> bool {
+        self.conflicts.is_empty()
+    }
+}
+
+/// Apply fixes in order, skipping any that conflict with an earlier one
+///
+/// Returns the fixed query, the applied fixes, and the conflicting fixes.
+pub(crate) fn apply(query: &str, fixes: &[Fix]) -> (String, Vec<Fix>, Vec<Fix>) {
+    let char_count = query.chars().count();
+    let mut accepted: Vec<&TextEdit> = Vec::new();
+    let mut applied = Vec::new();
+    let mut conflicts = Vec::new();
+
+    for fix in fixes {
+        let fits = fix.edits.iter().enumerate().all(|(i, edit)| {
+            edit.start <= edit.end
+                && edit.end <= char_count
+                && !accepted.iter().any(|other| overlaps(edit, other))
+                && !fix.edits[..i].iter().any(|other| overlaps(edit, other))
+        });
+
+        if fits {
+            accepted.extend(&fix.edits);
+            applied.push(fix.clone());
+        } else {
+            conflicts.push(fix.clone());
+        }
+    }
+
+    // Edit offsets are character positions; map them to byte offsets
+    let byte_offsets: Vec<usize> = query
+        .char_indices()
+        .map(|(i, _)| i)
+        .chain(std::iter::once(query.len()))
+        .collect();
+
+    // Apply from the end so earlier offsets stay valid
+    accepted.sort_by_key(|edit| (edit.start, edit.end));
+    let mut output = query.to_string();
+    for edit in accepted.iter().rev() {
+        output.replace_range(
+            byte_offsets[edit.start]..byte_offsets[edit.end],
+            &edit.new_text,
+        );
+    }
+
+    (output, applied, conflicts)
+}
+
+/// Two edits overlap if their spans intersect, or both insert at the same offset
+fn overlaps(a: &TextEdit, b: &TextEdit) -> bool {
+    (a.start < b.end && b.start < a.end)
+        || (a.start == a.end && b.start == b.end && a.start == b.start)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fix(title: &str, start: usize, end: usize, new_text: &str) -> Fix {
+        Fix {
+            title: title.to_string(),
+            edits: vec![TextEdit {
+                start,
+                end,
+                new_text: new_text.to_string(),
+            }],
+        }
+    }
+
+    #[test]
+    fn test_apply_non_overlapping_fixes() {
+        let query = "T | where A =~ '1' and B has '2'";
+        let fixes = [fix("b", 25, 28, "has_cs"), fix("a", 12, 14, "==")];
+
+        let (output, applied, conflicts) = apply(query, &fixes);
+        assert_eq!(output, "T | where A == '1' and B has_cs '2'");
+        assert_eq!(applied.len(), 2);
+        assert!(conflicts.is_empty());
+    }
+
+    #[test]
+    fn test_overlapping_and_out_of_range_fixes_conflict() {
+        let query = "T | where A =~ '1'";
+        let fixes = [
+            fix("first", 12, 14, "=="),
+            fix("overlap", 13, 16, "x"),
+            fix("out of range", 10, 40, "x"),
+        ];
+
+        let (output, applied, conflicts) = apply(query, &fixes);
+        assert_eq!(output, "T | where A == '1'");
+        assert_eq!(applied[0].title, "first");
+        let titles: Vec<_> = conflicts.iter().map(|f| f.title.as_str()).collect();
+        assert_eq!(titles, ["overlap", "out of range"]);
+    }
+
+    #[test]
+    fn test_offsets_are_characters() {
+        let query = "print 'é' =~ '1'";
+        let (output, ..) = apply(query, &[fix("a", 10, 12, "==")]);
+        assert_eq!(output, "print 'é' == '1'");
+    }
+}
@@ -0,0 +1,106 @@
+//! Query formatting
+//!
+//! [`KqlValidator::format`](crate::KqlValidator::format) reformats a query
+//! with the Kusto.Language formatter. [`FormatOptions`] controls where pipes
+//! are placed, how deeply nested expressions are indented, and whether
+//! mis-cased query operator names (`| Where`, `| SUMMARIZE`) are normalized.
+
+use serde::{Deserialize, Serialize};
+
+/// Options for formatting a query
+///
+/// # Example
+///
+/// ```
+/// use kql_language_tools::{FormatOptions, OperatorCase, PipeStyle};
+///
+/// let options = FormatOptions::default()
+///     .indent_size(2)
+///     .pipe_style(PipeStyle::Smart)
+///     .operator_case(OperatorCase::Preserve);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FormatOptions {
+    /// Number of spaces per indentation level
+    pub indent_size: usize,
+    /// Placement of `|` between query operators
+    pub pipe_style: PipeStyle,
+    /// Casing applied to query operator names
+    pub operator_case: OperatorCase,
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        Self {
+            indent_size: 4,
+            pipe_style: PipeStyle::NewLine,
+            operator_case: OperatorCase::Lowercase,
+        }
+    }
+}
+
+impl FormatOptions {
+    /// Builder method to set the indentation size
+    #[must_use]
+    pub fn indent_size(mut self, size: usize) -> Self {
+        self.indent_size = size;
+        self
+    }
+
+    /// Builder method to set pipe placement
+    #[must_use]
+    pub fn pipe_style(mut self, style: PipeStyle) -> Self {
+        self.pipe_style = style;
+        self
+    }
+
+    /// Builder method to set operator casing
+    #[must_use]
+    pub fn operator_case(mut self, case: OperatorCase) -> Self {
+        self.operator_case = case;
+        self
+    }
+}
+
+/// Placement of `|` between query operators
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub enum PipeStyle {
+    /// Start every query operator on a new line
+    NewLine,
+    /// Break onto new lines only when the pipeline is long
+    Smart,
+    /// Keep pipes where they are
+    Preserve,
+}
+
+/// Casing applied to query operator names
+///
+/// KQL operator names are case-sensitive and always lowercase, so
+/// [`Lowercase`](Self::Lowercase) turns `| Where` into a valid `| where`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub enum OperatorCase {
+    /// Lowercase operator names written in any other case
+    Lowercase,
+    /// Leave operator names as written
+    Preserve,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_options_json_shape() {
+        let json = serde_json::to_value(FormatOptions::default().indent_size(2)).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "indent_size": 2,
+                "pipe_style": "NewLine",
+                "operator_case": "Lowercase"
+            })
+        );
+    }
+}
@@ -0,0 +1,50 @@
+//! Registered schema handles
+//!
+//! [`KqlValidator::register_schema`](crate::KqlValidator::register_schema)
+//! sends a schema to the native library once and returns a [`SchemaHandle`].
+//! The native side caches the schema's symbol tables, and the
+//! `*_with_handle` calls refer to it by id instead of re-sending the JSON.
+
+use crate::loader::LoadedLibrary;
+use std::ffi::c_int;
+use std::fmt;
+use std::sync::Arc;
+
+/// A schema registered with the native library
+///
+/// The registration is released when the handle is dropped. Handles are not
+/// tied to the validator that created them and can be used with any
+/// [`KqlValidator`](crate::KqlValidator) in the process.
+pub struct SchemaHandle {
+    lib: Arc<LoadedLibrary>,
+    id: c_int,
+}
+
+impl SchemaHandle {
+    pub(crate) fn new(lib: Arc<LoadedLibrary>, id: c_int) -> Self {
+        Self { lib, id }
+    }
+
+    /// Get the native schema id
+    #[must_use]
+    pub fn id(&self) -> i32 {
+        self.id
+    }
+}
+
+impl fmt::Debug for SchemaHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SchemaHandle")
+            .field("id", &self.id)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Drop for SchemaHandle {
+    fn drop(&mut self) {
+        match self.lib.release_schema_id(self.id) {
+            Some(0) | None => {}
+            Some(code) => log::warn!("Failed to release schema {}: code {code}", self.id),
+        }
+    }
+}
@@ -0,0 +1,66 @@
+//! Inlay hints
+//!
+//! [`KqlValidator::get_inlay_hints`](crate::KqlValidator::get_inlay_hints)
+//! returns text an editor shows inline without it being part of the query:
+//! the inferred type after a scalar `let` name, the parameter name before a
+//! function call argument, and the column count after each pipeline stage.
+//! Labels are ready to display, so each hint maps directly onto an LSP
+//! `InlayHint`.
+
+use serde::{Deserialize, Serialize};
+
+/// A hint shown inline at a position in the query
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct InlayHint {
+    /// Offset the hint is shown at (0-based, character position)
+    pub position: usize,
+    /// Text to show, such as `: long`, `delimiter:` or `5 columns`
+    pub label: String,
+    /// Kind of hint
+    pub kind: InlayHintKind,
+}
+
+/// Kind of inlay hint
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub enum InlayHintKind {
+    /// Inferred type of a `let` binding, shown after its name
+    Type,
+    /// Parameter name, shown before a function call argument
+    Parameter,
+    /// Number of columns, shown after a pipeline stage
+    ColumnCount,
+    /// Unknown kind
+    #[serde(other)]
+    Other,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deserialize_inlay_hints() {
+        let json = r#"[
+            {"position":5,"label":": long","kind":"Type"},
+            {"position":31,"label":"delimiter:","kind":"Parameter"},
+            {"position":48,"label":"3 columns","kind":"ColumnCount"},
+            {"position":50,"label":"?","kind":"Chained"}
+        ]"#;
+
+        let hints: Vec<InlayHint> = serde_json::from_str(json).unwrap();
+        let hints: Vec<_> = hints
+            .iter()
+            .map(|h| (h.position, h.label.as_str(), h.kind))
+            .collect();
+        assert_eq!(
+            hints,
+            [
+                (5, ": long", InlayHintKind::Type),
+                (31, "delimiter:", InlayHintKind::Parameter),
+                (48, "3 columns", InlayHintKind::ColumnCount),
+                (50, "?", InlayHintKind::Other),
+            ]
+        );
+    }
+}
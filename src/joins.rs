@@ -0,0 +1,129 @@
+//! Join, lookup and union analysis
+//!
+//! [`KqlValidator::analyze_joins`](crate::KqlValidator::analyze_joins)
+//! lists every `join`, `lookup` and `union` in a query with its kind, its
+//! inputs and (for joins and lookups) its keys, for query review. Keys are
+//! resolved against the schema, and two kinds of key are reported as
+//! warnings:
+//!
+//! - [`JoinKeyTypeMismatch`](crate::KustoDiagnosticCode::JoinKeyTypeMismatch)
+//!   (`JN001`): the two sides of a key have different types, so rows never
+//!   match (or only after a conversion on every row)
+//! - [`HighCardinalityJoinKey`](crate::KustoDiagnosticCode::HighCardinalityJoinKey)
+//!   (`JN002`): the key has a type whose values are nearly all distinct
+//!   (`guid`, `datetime`, `timespan`, `real`, `decimal`), and the join has
+//!   no shuffle hint to spread the work across nodes
+
+use crate::kql_type::KqlType;
+use crate::types::Diagnostic;
+use serde::{Deserialize, Serialize};
+
+/// The joins, lookups and unions of a query
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct JoinAnalysis {
+    /// Operations in source order, including those in subqueries
+    pub operations: Vec<JoinOperation>,
+    /// Key warnings, ordered by position
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl JoinAnalysis {
+    /// Iterate over the operations of a kind
+    pub fn of_kind(&self, operator: JoinOperator) -> impl Iterator<Item = &JoinOperation> {
+        self.operations
+            .iter()
+            .filter(move |operation| operation.operator == operator)
+    }
+}
+
+/// A `join`, `lookup` or `union`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JoinOperation {
+    /// Which operator this is
+    pub operator: JoinOperator,
+    /// The `kind=` of the operation, or its default when not given
+    /// (`innerunique` for `join`, `leftouter` for `lookup`, `outer` for
+    /// `union`)
+    pub kind: String,
+    /// Source text of each input: the piped-in input first (when there is
+    /// one), then the operator's own arguments
+    pub sources: Vec<String>,
+    /// Keys of a `join` or `lookup`, in the order given
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub keys: Vec<JoinKey>,
+    /// Start offset of the operator (0-based, character position)
+    pub start: usize,
+    /// End offset of the operator (0-based, character position, exclusive)
+    pub end: usize,
+}
+
+/// Kind of [`JoinOperation`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub enum JoinOperator {
+    /// `join`
+    Join,
+    /// `lookup`
+    Lookup,
+    /// `union`
+    Union,
+    /// Unknown operator
+    #[serde(other)]
+    Other,
+}
+
+/// A key of a `join` or `lookup`
+///
+/// `on Id` has the same column on both sides; `on $left.A == $right.B`
+/// names each side's column.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JoinKey {
+    /// Column of the left (piped-in) input
+    pub left: String,
+    /// Column of the right input
+    pub right: String,
+    /// Type of the left column, if it resolves
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub left_type: Option<KqlType>,
+    /// Type of the right column, if it resolves
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub right_type: Option<KqlType>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codes::KustoDiagnosticCode;
+    use crate::types::DiagnosticCategory;
+
+    #[test]
+    fn test_join_analysis_deserialize() {
+        let json = r#"{
+            "operations": [
+                {"operator":"Join","kind":"leftouter","sources":["A","B"],"start":4,"end":34,
+                 "keys":[{"left":"Id","right":"UserId","left_type":"string","right_type":"long"}]},
+                {"operator":"Union","kind":"outer","sources":["C","D"],"start":37,"end":46}
+            ],
+            "diagnostics": [
+                {"message":"Join key types differ","severity":"Warning","start":22,"end":34,
+                 "line":1,"column":23,"code":"JN001","category":"Correctness"}
+            ]
+        }"#;
+        let analysis: JoinAnalysis = serde_json::from_str(json).unwrap();
+
+        let join = analysis.of_kind(JoinOperator::Join).next().unwrap();
+        assert_eq!(join.kind, "leftouter");
+        assert_eq!(join.keys[0].left_type, Some(KqlType::String));
+        assert_eq!(join.keys[0].right_type, Some(KqlType::Long));
+        assert!(analysis.operations[1].keys.is_empty());
+        assert_eq!(analysis.of_kind(JoinOperator::Lookup).count(), 0);
+
+        let diagnostic = &analysis.diagnostics[0];
+        assert_eq!(
+            diagnostic.parsed_code(),
+            Some(KustoDiagnosticCode::JoinKeyTypeMismatch)
+        );
+        assert_eq!(diagnostic.category, Some(DiagnosticCategory::Correctness));
+    }
+}
@@ -0,0 +1,27 @@
+//! Query kinds
+//!
+//! Kusto.Language parses both queries and control (management) commands
+//! such as `.create table` or `.show database schema`. A [`QueryKind`]
+//! tells validation which of the two the text is expected to be.
+
+use serde::{Deserialize, Serialize};
+
+/// The kind of text being validated
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub enum QueryKind {
+    /// A query; control commands are accepted as well
+    #[default]
+    Query,
+    /// A control command (text starting with `.`); queries are reported as
+    /// errors (`KC001`)
+    Command,
+}
+
+impl QueryKind {
+    /// Check if this is the default query kind
+    #[must_use]
+    pub fn is_query(&self) -> bool {
+        *self == Self::Query
+    }
+}
@@ -0,0 +1,243 @@
+//! KQL data types
+//!
+//! [`KqlType`] is the type of a schema column, function parameter or
+//! function result. Types convert from their KQL names, so builders keep
+//! accepting strings: `Column::new("Count", "long")`. Names are matched
+//! case-insensitively and include Kusto's aliases (`boolean`, `double`,
+//! `date`, `uuid`, ...) and the .NET type names of schema captures
+//! (`System.Int64`). A name that is not a KQL type becomes
+//! [`KqlType::Unknown`], which schema-aware calls reject with
+//! [`Error::UnknownType`] instead of silently treating the column as
+//! `dynamic`.
+
+use crate::error::Error;
+use crate::kusto_schema::{csl_name, CslParser};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::str::FromStr;
+
+/// A KQL data type
+///
+/// # Example
+///
+/// ```
+/// use kql_language_tools::KqlType;
+///
+/// assert_eq!(KqlType::from("DateTime"), KqlType::DateTime);
+/// assert_eq!("(Id:long, Name:string)".parse::<KqlType>()?.to_string(), "(Id:long, Name:string)");
+/// assert!("datetme".parse::<KqlType>().is_err());
+/// assert!(!KqlType::from("datetme").is_known());
+/// # Ok::<(), kql_language_tools::Error>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum KqlType {
+    /// `string`
+    String,
+    /// `long`, a 64-bit integer
+    Long,
+    /// `real`, a 64-bit float
+    Real,
+    /// `bool`
+    Bool,
+    /// `datetime`
+    DateTime,
+    /// `timespan`
+    TimeSpan,
+    /// `guid`
+    Guid,
+    /// `dynamic`, a JSON-like value
+    Dynamic,
+    /// `decimal`, a 128-bit decimal
+    Decimal,
+    /// `int`, a 32-bit integer
+    Int,
+    /// A table with the given columns, written `(Id:long, Name:string)`,
+    /// for tabular parameters and results; no columns is any table, `(*)`
+    Table(Vec<(String, KqlType)>),
+    /// A name that is not a KQL type, such as a misspelling
+    Unknown(String),
+}
+
+impl KqlType {
+    /// Check if this is a KQL type, including every column of a table
+    #[must_use]
+    pub fn is_known(&self) -> bool {
+        match self {
+            Self::Unknown(_) => false,
+            Self::Table(columns) => columns.iter().all(|(_, t)| t.is_known()),
+            _ => true,
+        }
+    }
+
+    /// Check if this is a table type
+    #[must_use]
+    pub fn is_table(&self) -> bool {
+        matches!(self, Self::Table(_))
+    }
+
+    /// Get the first name in this type that is not a KQL type
+    pub(crate) fn unknown_name(&self) -> Option<&str> {
+        match self {
+            Self::Unknown(name) => Some(name),
+            Self::Table(columns) => columns.iter().find_map(|(_, t)| t.unknown_name()),
+            _ => None,
+        }
+    }
+
+    /// Look up a scalar type by name or alias
+    pub(crate) fn scalar(name: &str) -> Option<Self> {
+        let scalar = match name.to_ascii_lowercase().as_str() {
+            "string" | "system.string" => Self::String,
+            "long" | "int64" | "system.int64" | "system.uint32" | "system.uint64" => Self::Long,
+            "real" | "double" | "system.double" | "system.single" => Self::Real,
+            "bool" | "boolean" | "system.boolean" => Self::Bool,
+            "datetime" | "date" | "system.datetime" | "system.datetimeoffset" => Self::DateTime,
+            "timespan" | "time" | "system.timespan" => Self::TimeSpan,
+            "guid" | "uuid" | "uniqueid" | "system.guid" => Self::Guid,
+            "dynamic" | "system.object" => Self::Dynamic,
+            "decimal" | "system.decimal" | "system.data.sqltypes.sqldecimal" => Self::Decimal,
+            "int" | "int32" | "system.int32" | "system.int16" | "system.uint16" | "system.byte"
+            | "system.sbyte" => Self::Int,
+            _ => return None,
+        };
+        Some(scalar)
+    }
+}
+
+impl fmt::Display for KqlType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::String => "string",
+            Self::Long => "long",
+            Self::Real => "real",
+            Self::Bool => "bool",
+            Self::DateTime => "datetime",
+            Self::TimeSpan => "timespan",
+            Self::Guid => "guid",
+            Self::Dynamic => "dynamic",
+            Self::Decimal => "decimal",
+            Self::Int => "int",
+            Self::Table(columns) if columns.is_empty() => "(*)",
+            Self::Table(columns) => {
+                f.write_str("(")?;
+                for (i, (name, data_type)) in columns.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{}:{data_type}", csl_name(name))?;
+                }
+                return f.write_str(")");
+            }
+            Self::Unknown(name) => name,
+        };
+        f.write_str(name)
+    }
+}
+
+impl FromStr for KqlType {
+    type Err = Error;
+
+    /// Parse a type name or a table type, rejecting unknown names
+    fn from_str(s: &str) -> Result<Self, Error> {
+        if let Some(scalar) = Self::scalar(s.trim()) {
+            return Ok(scalar);
+        }
+        let mut parser = CslParser::new(s);
+        match parser.data_type() {
+            Ok(data_type) if parser.at_end() => Ok(data_type),
+            _ => Err(Error::UnknownType {
+                name: s.to_string(),
+                context: None,
+            }),
+        }
+    }
+}
+
+impl From<&str> for KqlType {
+    fn from(name: &str) -> Self {
+        name.parse()
+            .unwrap_or_else(|_| Self::Unknown(name.to_string()))
+    }
+}
+
+impl From<String> for KqlType {
+    fn from(name: String) -> Self {
+        name.parse().unwrap_or(Self::Unknown(name))
+    }
+}
+
+impl From<&String> for KqlType {
+    fn from(name: &String) -> Self {
+        Self::from(name.as_str())
+    }
+}
+
+impl PartialEq<str> for KqlType {
+    fn eq(&self, other: &str) -> bool {
+        match other.parse::<Self>() {
+            Ok(parsed) => *self == parsed,
+            Err(_) => matches!(self, Self::Unknown(name) if name == other),
+        }
+    }
+}
+
+impl PartialEq<&str> for KqlType {
+    fn eq(&self, other: &&str) -> bool {
+        self == *other
+    }
+}
+
+impl Serialize for KqlType {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for KqlType {
+    /// Deserialize leniently, so results naming types this crate does not
+    /// know still parse
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(Self::from(String::deserialize(deserializer)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_types() {
+        assert_eq!(KqlType::from("LONG"), KqlType::Long);
+        assert_eq!(KqlType::from("boolean"), KqlType::Bool);
+        assert_eq!(KqlType::from("System.DateTime"), KqlType::DateTime);
+        assert_eq!(KqlType::from("(*)"), KqlType::Table(Vec::new()));
+        assert_eq!(
+            KqlType::from("( ['Event Id'] : long,Name:string )"),
+            KqlType::Table(vec![
+                ("Event Id".to_string(), KqlType::Long),
+                ("Name".to_string(), KqlType::String)
+            ])
+        );
+        assert_eq!(
+            KqlType::from("(['Event Id']:long)").to_string(),
+            "([\"Event Id\"]:long)"
+        );
+
+        assert_eq!(
+            KqlType::from("datetme"),
+            KqlType::Unknown("datetme".to_string())
+        );
+        assert!(!KqlType::from("(Id:lnog)").is_known());
+        assert_eq!(KqlType::from("(Id:lnog)").unknown_name(), Some("(Id:lnog)"));
+        assert!(matches!(
+            "string extra".parse::<KqlType>(),
+            Err(Error::UnknownType { .. })
+        ));
+
+        let json = serde_json::to_string(&KqlType::TimeSpan).unwrap();
+        assert_eq!(json, "\"timespan\"");
+        let parsed: KqlType = serde_json::from_str("\"unknown\"").unwrap();
+        assert_eq!(parsed, KqlType::Unknown("unknown".to_string()));
+        assert_eq!(KqlType::Real, "double");
+    }
+}
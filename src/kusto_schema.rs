@@ -0,0 +1,1060 @@
+//! Azure Data Explorer schema formats
+//!
+//! [`Schema::from_kusto_show_schema`] reads the output of ADX's
+//! `.show database <name> schema as json` command. The command returns a
+//! single cell holding a JSON document keyed by database, table and
+//! function name; only the parts the validator uses are read, and unknown
+//! properties are ignored.
+//!
+//! [`Schema::to_csl`] and [`Schema::from_csl`] convert between schemas and
+//! scripts of `.create table` and `.create function` control commands, as
+//! used to provision clusters.
+
+use crate::error::Error;
+use crate::kql_type::KqlType;
+use crate::schema::{Column, Function, FunctionKind, Parameter, Schema, Table};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ShowSchema {
+    databases: BTreeMap<String, ShowDatabase>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ShowDatabase {
+    name: String,
+    #[serde(default)]
+    tables: BTreeMap<String, ShowTable>,
+    #[serde(default)]
+    materialized_views: BTreeMap<String, ShowTable>,
+    #[serde(default)]
+    functions: BTreeMap<String, ShowFunction>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ShowTable {
+    name: String,
+    #[serde(default)]
+    ordered_columns: Vec<ShowColumn>,
+    folder: Option<String>,
+    doc_string: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ShowColumn {
+    name: String,
+    #[serde(rename = "Type")]
+    clr_type: Option<String>,
+    csl_type: Option<String>,
+    doc_string: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ShowFunction {
+    name: String,
+    #[serde(default)]
+    input_parameters: Vec<ShowParameter>,
+    body: Option<String>,
+    folder: Option<String>,
+    doc_string: Option<String>,
+    function_kind: Option<String>,
+    #[serde(default)]
+    output_columns: Vec<ShowColumn>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ShowParameter {
+    name: String,
+    #[serde(rename = "Type")]
+    clr_type: Option<String>,
+    csl_type: Option<String>,
+    csl_default_value: Option<String>,
+    /// Columns of a tabular parameter
+    columns: Option<Vec<ShowColumn>>,
+}
+
+impl Schema {
+    /// Parse the JSON returned by `.show database <name> schema as json`
+    ///
+    /// Tables and materialized views become [`Table`]s and stored functions
+    /// become [`Function`]s, keeping their folders and docstrings. Views and
+    /// functions with output columns are tabular, returning those columns;
+    /// other functions are given the return type `dynamic`, since the
+    /// command does not report it. Tabular parameters and return types are
+    /// written in Kusto's schema notation, such as `(Id:long, Name:string)`,
+    /// or `(*)` when the columns are not listed. External tables are not
+    /// read. If the document holds several databases, as
+    /// `.show databases schema as json` returns, the first by name is used.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Json`] if `json` is not a schema document.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use kql_language_tools::Schema;
+    ///
+    /// let json = r#"{"Databases": {"Samples": {
+    ///     "Name": "Samples",
+    ///     "Tables": {"StormEvents": {
+    ///         "Name": "StormEvents",
+    ///         "OrderedColumns": [{"Name": "State", "Type": "System.String", "CslType": "string"}],
+    ///         "Folder": "Storms",
+    ///         "DocString": "US storm events"
+    ///     }},
+    ///     "Functions": {}
+    /// }}}"#;
+    ///
+    /// let schema = Schema::from_kusto_show_schema(json)?;
+    /// assert_eq!(schema.database.as_deref(), Some("Samples"));
+    /// assert_eq!(schema.get_table("StormEvents").unwrap().folder.as_deref(), Some("Storms"));
+    /// # Ok::<(), kql_language_tools::Error>(())
+    /// ```
+    pub fn from_kusto_show_schema(json: &str) -> Result<Self, Error> {
+        let show: ShowSchema = serde_json::from_str(json)?;
+        let Some(database) = show.databases.into_values().next() else {
+            return Ok(Self::new());
+        };
+
+        let mut schema = Self::with_database(database.name);
+        for table in database
+            .tables
+            .into_values()
+            .chain(database.materialized_views.into_values())
+        {
+            schema.add_table(Table {
+                name: table.name,
+                columns: table.ordered_columns.into_iter().map(column).collect(),
+                description: non_empty(table.doc_string),
+                folder: non_empty(table.folder),
+            });
+        }
+        for function in database.functions.into_values() {
+            let tabular = !function.output_columns.is_empty()
+                || function.function_kind.as_deref() == Some("ViewFunction");
+            let (kind, return_type) = if tabular {
+                (
+                    FunctionKind::Tabular,
+                    columns_schema(function.output_columns),
+                )
+            } else {
+                (FunctionKind::Scalar, KqlType::Dynamic)
+            };
+            schema.add_function(Function {
+                name: function.name,
+                parameters: function
+                    .input_parameters
+                    .into_iter()
+                    .map(parameter)
+                    .collect(),
+                return_type,
+                kind,
+                body: non_empty(function.body),
+                description: non_empty(function.doc_string),
+                folder: non_empty(function.folder),
+            });
+        }
+        Ok(schema)
+    }
+}
+
+fn column(column: ShowColumn) -> Column {
+    let mut converted = Column::new(
+        column.name,
+        csl_type(column.csl_type, column.clr_type.as_deref()),
+    );
+    converted.description = non_empty(column.doc_string);
+    converted
+}
+
+fn parameter(parameter: ShowParameter) -> Parameter {
+    let data_type = match parameter.columns {
+        Some(columns) => columns_schema(columns),
+        None => csl_type(parameter.csl_type, parameter.clr_type.as_deref()),
+    };
+    Parameter {
+        name: parameter.name,
+        data_type,
+        default_value: non_empty(parameter.csl_default_value),
+    }
+}
+
+/// Get the table type with the given columns, `(*)` if there are none
+fn columns_schema(columns: Vec<ShowColumn>) -> KqlType {
+    KqlType::Table(
+        columns
+            .into_iter()
+            .map(|c| (c.name, csl_type(c.csl_type, c.clr_type.as_deref())))
+            .collect(),
+    )
+}
+
+/// Get a column's KQL type, from its `CslType` or else its .NET `Type`
+fn csl_type(csl_type: Option<String>, clr_type: Option<&str>) -> KqlType {
+    if let Some(csl_type) = non_empty(csl_type) {
+        return KqlType::from(csl_type);
+    }
+    match clr_type {
+        // Kusto stores bools as signed bytes
+        Some("System.SByte") => KqlType::Bool,
+        Some(clr_type) => KqlType::scalar(clr_type).unwrap_or(KqlType::Dynamic),
+        None => KqlType::Dynamic,
+    }
+}
+
+/// Treat the empty strings ADX writes for unset properties as absent
+fn non_empty(value: Option<String>) -> Option<String> {
+    value.filter(|s| !s.is_empty())
+}
+
+impl Schema {
+    /// Write the schema as a script of Kusto control commands
+    ///
+    /// Each table becomes a `.create table` command, followed by an
+    /// `.alter table ... column-docstrings` command if any column has a
+    /// description, and each function with a body becomes a
+    /// `.create function` command. Folders and descriptions are kept.
+    /// Commands are separated by blank lines, as Kusto tools expect. The
+    /// database name, scopes, sample paths and function return types have
+    /// no place in the script and are left out, as are functions without a
+    /// body.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use kql_language_tools::{Schema, Table};
+    ///
+    /// let schema = Schema::new().table(
+    ///     Table::new("Events")
+    ///         .with_column("Timestamp", "datetime")
+    ///         .folder("Logs"),
+    /// );
+    ///
+    /// assert_eq!(
+    ///     schema.to_csl(),
+    ///     ".create table Events (Timestamp:datetime) with (folder = \"Logs\")\n"
+    /// );
+    /// ```
+    #[must_use]
+    pub fn to_csl(&self) -> String {
+        let mut commands = Vec::new();
+        for table in &self.tables {
+            commands.extend(table_commands(".create table", table));
+        }
+        commands.extend(
+            self.functions
+                .iter()
+                .filter_map(|f| function_command(".create function", f)),
+        );
+
+        let mut script = commands.join("\n\n");
+        if !script.is_empty() {
+            script.push('\n');
+        }
+        script
+    }
+
+    /// Parse a script of Kusto control commands
+    ///
+    /// Reads the table and function definitions from `.create table`,
+    /// `.create-merge table`, `.alter table`, `.alter-merge table`,
+    /// `.create function`, `.create-or-alter function` and `.alter function`
+    /// commands, and descriptions and folders from their `with` properties
+    /// and from `.alter table ... column-docstrings`, `.alter table ...
+    /// docstring` and `.alter table ... folder`. Other commands, such as
+    /// policies and ingestion mappings, are skipped, so whole provisioning
+    /// scripts can be read. Later commands for a table add to or update its
+    /// columns. Function bodies are kept with their braces, and functions
+    /// are given the return type `dynamic`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Csl`] if a table or function command is malformed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use kql_language_tools::Schema;
+    ///
+    /// let script = r#"
+    /// .create table Events (Timestamp:datetime, Message:string)
+    ///
+    /// .alter table Events policy retention '{"SoftDeletePeriod": "30.00:00:00"}'
+    ///
+    /// .create function with (docstring = "Recent events") Recent(since:timespan = 1h)
+    /// {
+    ///     Events | where Timestamp > ago(since)
+    /// }
+    /// "#;
+    ///
+    /// let schema = Schema::from_csl(script)?;
+    /// assert_eq!(schema.get_table("Events").unwrap().columns.len(), 2);
+    /// let recent = schema.get_function("Recent").unwrap();
+    /// assert_eq!(recent.parameters[0].default_value.as_deref(), Some("1h"));
+    /// assert_eq!(recent.description.as_deref(), Some("Recent events"));
+    /// # Ok::<(), kql_language_tools::Error>(())
+    /// ```
+    pub fn from_csl(script: &str) -> Result<Self, Error> {
+        let mut parser = CslParser::new(script);
+        let mut schema = Self::new();
+        while let Some(c) = parser.peek() {
+            if c != '.' {
+                return Err(parser.error("expected a control command"));
+            }
+            parser.pos += 1;
+            let command = parser.word().to_ascii_lowercase();
+            let entity = parser.word().to_ascii_lowercase();
+            match (command.as_str(), entity.as_str()) {
+                ("create" | "create-merge" | "alter" | "alter-merge", "table") => {
+                    parser.table(&mut schema)?;
+                }
+                ("create" | "create-or-alter" | "alter", "function") => {
+                    parser.function(&mut schema)?;
+                }
+                _ => parser.skip_command(),
+            }
+            if !matches!(parser.peek(), None | Some('.')) {
+                return Err(parser.error("unexpected text after the command"));
+            }
+        }
+        Ok(schema)
+    }
+}
+
+/// Cursor over a CSL script
+pub(crate) struct CslParser<'a> {
+    script: &'a str,
+    pos: usize,
+}
+
+impl<'a> CslParser<'a> {
+    pub(crate) fn new(script: &'a str) -> Self {
+        Self { script, pos: 0 }
+    }
+
+    /// Check if only whitespace and comments remain
+    pub(crate) fn at_end(&mut self) -> bool {
+        self.peek().is_none()
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.script[self.pos..]
+    }
+
+    fn error(&self, message: &str) -> Error {
+        Error::Csl {
+            line: self.script[..self.pos].matches('\n').count() + 1,
+            message: message.to_string(),
+        }
+    }
+
+    /// Skip whitespace and `//` comments
+    fn skip_trivia(&mut self) {
+        loop {
+            let rest = self.rest();
+            let trimmed = rest.trim_start();
+            self.pos += rest.len() - trimmed.len();
+            if !trimmed.starts_with("//") {
+                return;
+            }
+            self.pos += trimmed.find('\n').unwrap_or(trimmed.len());
+        }
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.skip_trivia();
+        self.rest().chars().next()
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.pos += c.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), Error> {
+        if self.eat(c) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected '{c}'")))
+        }
+    }
+
+    /// Read a run of letters, digits, `_` and `-`, as in `create-merge`
+    fn word(&mut self) -> &'a str {
+        self.skip_trivia();
+        let rest = self.rest();
+        let len = rest
+            .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '-'))
+            .unwrap_or(rest.len());
+        self.pos += len;
+        &rest[..len]
+    }
+
+    /// Consume `keyword` if it is the next word
+    fn keyword(&mut self, keyword: &str) -> bool {
+        let start = self.pos;
+        if self.word().eq_ignore_ascii_case(keyword) {
+            true
+        } else {
+            self.pos = start;
+            false
+        }
+    }
+
+    /// Read an identifier or a bracketed name such as `['My Table']`
+    fn name(&mut self) -> Result<String, Error> {
+        if self.eat('[') {
+            let name = self.string()?;
+            self.expect(']')?;
+            return Ok(name);
+        }
+        self.skip_trivia();
+        let rest = self.rest();
+        let len = rest
+            .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+            .unwrap_or(rest.len());
+        if len == 0 {
+            return Err(self.error("expected a name"));
+        }
+        self.pos += len;
+        Ok(rest[..len].to_string())
+    }
+
+    /// Read a string literal, regular or verbatim (`@"..."`)
+    fn string(&mut self) -> Result<String, Error> {
+        self.skip_trivia();
+        let verbatim = self.rest().starts_with('@');
+        let start = self.pos + usize::from(verbatim);
+        let mut chars = self.script[start..].char_indices().peekable();
+        let Some((_, quote @ ('"' | '\''))) = chars.next() else {
+            return Err(self.error("expected a string"));
+        };
+        let mut value = String::new();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                _ if c == quote => {
+                    if verbatim && chars.peek().is_some_and(|&(_, next)| next == quote) {
+                        chars.next();
+                        value.push(quote);
+                        continue;
+                    }
+                    self.pos = start + i + 1;
+                    return Ok(value);
+                }
+                '\\' if !verbatim => match chars.next() {
+                    Some((_, 'n')) => value.push('\n'),
+                    Some((_, 'r')) => value.push('\r'),
+                    Some((_, 't')) => value.push('\t'),
+                    Some((_, escaped)) => value.push(escaped),
+                    None => break,
+                },
+                '\n' if !verbatim => break,
+                _ => value.push(c),
+            }
+        }
+        Err(self.error("unterminated string"))
+    }
+
+    /// Consume text up to the first character `stop` accepts outside
+    /// brackets, strings and comments
+    fn raw(&mut self, stop: impl Fn(char) -> bool) -> &'a str {
+        let start = self.pos;
+        let mut depth = 0usize;
+        let mut chars = self.rest().char_indices().peekable();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                _ if depth == 0 && stop(c) => {
+                    self.pos = start + i;
+                    return &self.script[start..self.pos];
+                }
+                '(' | '[' | '{' => depth += 1,
+                ')' | ']' | '}' => depth = depth.saturating_sub(1),
+                '"' | '\'' => {
+                    while let Some((_, next)) = chars.next() {
+                        match next {
+                            '\\' => {
+                                chars.next();
+                            }
+                            _ if next == c => break,
+                            _ => {}
+                        }
+                    }
+                }
+                '/' if chars.peek().is_some_and(|&(_, next)| next == '/') => {
+                    while chars.next_if(|&(_, next)| next != '\n').is_some() {}
+                }
+                _ => {}
+            }
+        }
+        self.pos = self.script.len();
+        &self.script[start..]
+    }
+
+    /// Skip to the next line starting with a command
+    fn skip_command(&mut self) {
+        loop {
+            self.raw(|c| c == '\n');
+            if matches!(self.peek(), None | Some('.')) {
+                return;
+            }
+        }
+    }
+
+    /// Read a parenthesized, comma-separated list
+    fn list<T>(
+        &mut self,
+        mut item: impl FnMut(&mut Self) -> Result<T, Error>,
+    ) -> Result<Vec<T>, Error> {
+        self.expect('(')?;
+        let mut items = Vec::new();
+        if self.eat(')') {
+            return Ok(items);
+        }
+        loop {
+            items.push(item(self)?);
+            if self.eat(')') {
+                return Ok(items);
+            }
+            self.expect(',')?;
+        }
+    }
+
+    /// Read a `name:type` column declaration
+    fn column(&mut self) -> Result<Column, Error> {
+        let name = self.name()?;
+        self.expect(':')?;
+        Ok(Column::new(name, self.scalar_type()?))
+    }
+
+    /// Read a scalar type name
+    fn scalar_type(&mut self) -> Result<KqlType, Error> {
+        let name = self.name()?;
+        KqlType::scalar(&name).ok_or_else(|| self.error(&format!("unknown type '{name}'")))
+    }
+
+    /// Read a scalar type, or a tabular one such as `(Id:long)` or `(*)`
+    pub(crate) fn data_type(&mut self) -> Result<KqlType, Error> {
+        if self.peek() != Some('(') {
+            return self.scalar_type();
+        }
+        let start = self.pos;
+        self.pos += 1;
+        if self.eat('*') {
+            self.expect(')')?;
+            return Ok(KqlType::Table(Vec::new()));
+        }
+        self.pos = start;
+        let columns = self.list(|parser| {
+            let column = parser.column()?;
+            Ok((column.name, column.data_type))
+        })?;
+        Ok(KqlType::Table(columns))
+    }
+
+    /// Read optional `with (name = value, ...)` properties, returning the
+    /// docstring and folder
+    fn properties(&mut self) -> Result<(Option<String>, Option<String>), Error> {
+        let mut properties = (None, None);
+        if !self.keyword("with") {
+            return Ok(properties);
+        }
+        self.list(|parser| {
+            let name = parser.name()?;
+            parser.expect('=')?;
+            let value = if matches!(parser.peek(), Some('"' | '\'' | '@')) {
+                parser.string()?
+            } else {
+                parser.name()?
+            };
+            if name.eq_ignore_ascii_case("docstring") {
+                properties.0 = Some(value);
+            } else if name.eq_ignore_ascii_case("folder") {
+                properties.1 = Some(value);
+            }
+            Ok(())
+        })?;
+        Ok(properties)
+    }
+
+    /// Read the rest of a table command, after `table`
+    fn table(&mut self, schema: &mut Schema) -> Result<(), Error> {
+        let name = self.name()?;
+        if self.peek() == Some('(') {
+            let columns = self.list(Self::column)?;
+            let (description, folder) = self.properties()?;
+            let table = table_mut(schema, name);
+            for column in columns {
+                match table.columns.iter_mut().find(|c| c.name == column.name) {
+                    Some(existing) => existing.data_type = column.data_type,
+                    None => table.columns.push(column),
+                }
+            }
+            table.description = description.or(table.description.take());
+            table.folder = folder.or(table.folder.take());
+        } else if self.keyword("column-docstrings") {
+            let docstrings = self.list(|parser| {
+                let column = parser.name()?;
+                parser.expect(':')?;
+                Ok((column, parser.string()?))
+            })?;
+            let table = table_mut(schema, name);
+            for (column, docstring) in docstrings {
+                if let Some(column) = table.columns.iter_mut().find(|c| c.name == column) {
+                    column.description = Some(docstring);
+                }
+            }
+        } else if self.keyword("docstring") {
+            table_mut(schema, name).description = Some(self.string()?);
+        } else if self.keyword("folder") {
+            table_mut(schema, name).folder = Some(self.string()?);
+        } else {
+            self.skip_command();
+        }
+        Ok(())
+    }
+
+    /// Read the rest of a function command, after `function`
+    fn function(&mut self, schema: &mut Schema) -> Result<(), Error> {
+        self.keyword("ifnotexists");
+        let (description, folder) = self.properties()?;
+        let name = self.name()?;
+        let existing = schema.functions.iter().position(|f| f.name == name);
+        if self.keyword("docstring") {
+            let docstring = self.string()?;
+            if let Some(index) = existing {
+                schema.functions[index].description = Some(docstring);
+            }
+            return Ok(());
+        }
+        if self.keyword("folder") {
+            let folder = self.string()?;
+            if let Some(index) = existing {
+                schema.functions[index].folder = Some(folder);
+            }
+            return Ok(());
+        }
+
+        let parameters = self.list(|parser| {
+            let name = parser.name()?;
+            parser.expect(':')?;
+            let data_type = parser.data_type()?;
+            let default_value = if parser.eat('=') {
+                parser.skip_trivia();
+                Some(parser.raw(|c| c == ',' || c == ')').trim().to_string())
+            } else {
+                None
+            };
+            Ok(Parameter {
+                name,
+                data_type,
+                default_value,
+            })
+        })?;
+        if self.peek() != Some('{') {
+            return Err(self.error("expected a function body"));
+        }
+        let start = self.pos;
+        self.pos += 1;
+        self.raw(|c| c == '}');
+        if !self.eat('}') {
+            return Err(self.error("unterminated function body"));
+        }
+
+        let function = Function {
+            name,
+            parameters,
+            return_type: KqlType::Dynamic,
+            kind: FunctionKind::Scalar,
+            body: Some(self.script[start..self.pos].to_string()),
+            description,
+            folder,
+        };
+        match existing {
+            Some(index) => schema.functions[index] = function,
+            None => {
+                schema.add_function(function);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Write `command` (`.create table` or `.alter table`) for a table, followed
+/// by its column docstrings
+pub(crate) fn table_commands(command: &str, table: &Table) -> Vec<String> {
+    let columns: Vec<_> = table
+        .columns
+        .iter()
+        .map(|c| format!("{}:{}", csl_name(&c.name), c.data_type))
+        .collect();
+    let mut create = format!(
+        "{command} {} ({})",
+        csl_name(&table.name),
+        columns.join(", ")
+    );
+    if let Some(properties) = csl_properties(table.description.as_deref(), table.folder.as_deref())
+    {
+        create.push(' ');
+        create.push_str(&properties);
+    }
+    let mut commands = vec![create];
+
+    let docstrings: Vec<_> = table
+        .columns
+        .iter()
+        .filter_map(|c| {
+            let description = c.description.as_deref()?;
+            Some(format!("{}:{}", csl_name(&c.name), csl_string(description)))
+        })
+        .collect();
+    if !docstrings.is_empty() {
+        commands.push(format!(
+            ".alter table {} column-docstrings ({})",
+            csl_name(&table.name),
+            docstrings.join(", ")
+        ));
+    }
+    commands
+}
+
+/// Write `command` (`.create function` or `.create-or-alter function`) for
+/// a function, or `None` if it has no body
+pub(crate) fn function_command(command: &str, function: &Function) -> Option<String> {
+    let body = function.body.as_deref()?.trim();
+    let parameters: Vec<_> = function
+        .parameters
+        .iter()
+        .map(|p| match &p.default_value {
+            Some(default) => format!("{}:{} = {default}", csl_name(&p.name), p.data_type),
+            None => format!("{}:{}", csl_name(&p.name), p.data_type),
+        })
+        .collect();
+    let mut command = format!("{command} ");
+    if let Some(properties) =
+        csl_properties(function.description.as_deref(), function.folder.as_deref())
+    {
+        command.push_str(&properties);
+        command.push(' ');
+    }
+    let _ = writeln!(
+        command,
+        "{}({})",
+        csl_name(&function.name),
+        parameters.join(", ")
+    );
+    if body.starts_with('{') && body.ends_with('}') {
+        command.push_str(body);
+    } else {
+        let _ = write!(command, "{{\n{body}\n}}");
+    }
+    Some(command)
+}
+
+/// Get the table named `name`, adding it if it is not in the schema
+fn table_mut(schema: &mut Schema, name: String) -> &mut Table {
+    if let Some(index) = schema.tables.iter().position(|t| t.name == name) {
+        return &mut schema.tables[index];
+    }
+    schema.add_table(Table::new(name));
+    schema.tables.last_mut().expect("table was just added")
+}
+
+/// Write a name, bracketing it if it is not a plain identifier
+pub(crate) fn csl_name(name: &str) -> String {
+    let plain = name.starts_with(|c: char| c.is_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_alphanumeric() || c == '_');
+    if plain {
+        name.to_string()
+    } else {
+        format!("[{}]", csl_string(name))
+    }
+}
+
+/// Write a string literal
+fn csl_string(value: &str) -> String {
+    let mut literal = String::with_capacity(value.len() + 2);
+    literal.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => literal.push_str("\\\""),
+            '\\' => literal.push_str("\\\\"),
+            '\n' => literal.push_str("\\n"),
+            '\r' => literal.push_str("\\r"),
+            '\t' => literal.push_str("\\t"),
+            _ => literal.push(c),
+        }
+    }
+    literal.push('"');
+    literal
+}
+
+/// Write `with (...)` properties for a docstring and folder, if any
+fn csl_properties(description: Option<&str>, folder: Option<&str>) -> Option<String> {
+    let properties: Vec<_> = [("docstring", description), ("folder", folder)]
+        .into_iter()
+        .filter_map(|(name, value)| Some(format!("{name} = {}", csl_string(value?))))
+        .collect();
+    (!properties.is_empty()).then(|| format!("with ({})", properties.join(", ")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Trimmed output of `.show database Samples schema as json`
+    const SAMPLES: &str = r#"{
+        "Plugins": [],
+        "Databases": {
+            "Samples": {
+                "Name": "Samples",
+                "Tables": {
+                    "StormEvents": {
+                        "Name": "StormEvents",
+                        "EntityType": "Table",
+                        "OrderedColumns": [
+                            {"Name": "StartTime", "Type": "System.DateTime", "CslType": "datetime", "DocString": "Start of the event"},
+                            {"Name": "State", "Type": "System.String", "CslType": "string"},
+                            {"Name": "DamageProperty", "Type": "System.Int32", "CslType": "int"},
+                            {"Name": "StormSummary", "Type": "System.Object"}
+                        ],
+                        "Folder": "Storm_Events",
+                        "DocString": "US storm events"
+                    },
+                    "Logs": {
+                        "Name": "Logs",
+                        "OrderedColumns": [],
+                        "Folder": "",
+                        "DocString": ""
+                    }
+                },
+                "ExternalTables": {
+                    "ArchivedLogs": {"Name": "ArchivedLogs", "OrderedColumns": []}
+                },
+                "MaterializedViews": {
+                    "DailyStorms": {
+                        "Name": "DailyStorms",
+                        "OrderedColumns": [{"Name": "Count", "Type": "System.Int64", "CslType": "long"}],
+                        "Folder": "Views"
+                    }
+                },
+                "Functions": {
+                    "StormsIn": {
+                        "Name": "StormsIn",
+                        "InputParameters": [
+                            {"Name": "state", "Type": "System.String", "CslType": "string", "CslDefaultValue": "\"TEXAS\""},
+                            {"Name": "events", "Columns": [
+                                {"Name": "State", "Type": "System.String", "CslType": "string"},
+                                {"Name": "Count", "Type": "System.Int64", "CslType": "long"}
+                            ]},
+                            {"Name": "any", "Columns": []}
+                        ],
+                        "Body": "{ StormEvents | where State == state }",
+                        "Folder": "Demo",
+                        "DocString": "Storms in a state",
+                        "FunctionKind": "Unknown",
+                        "OutputColumns": []
+                    }
+                },
+                "DatabaseAccessMode": "ReadWrite"
+            }
+        }
+    }"#;
+
+    #[test]
+    fn test_from_kusto_show_schema() {
+        let schema = Schema::from_kusto_show_schema(SAMPLES).unwrap();
+        assert_eq!(schema.database.as_deref(), Some("Samples"));
+        assert_eq!(schema.tables.len(), 3);
+        assert!(schema.get_table("ArchivedLogs").is_none());
+
+        let storms = schema.get_table("StormEvents").unwrap();
+        assert_eq!(storms.folder.as_deref(), Some("Storm_Events"));
+        assert_eq!(storms.description.as_deref(), Some("US storm events"));
+        let columns: Vec<_> = storms
+            .columns
+            .iter()
+            .map(|c| (c.name.as_str(), c.data_type.clone()))
+            .collect();
+        assert_eq!(
+            columns,
+            [
+                ("StartTime", KqlType::DateTime),
+                ("State", KqlType::String),
+                ("DamageProperty", KqlType::Int),
+                ("StormSummary", KqlType::Dynamic)
+            ]
+        );
+        assert_eq!(
+            storms.columns[0].description.as_deref(),
+            Some("Start of the event")
+        );
+
+        let logs = schema.get_table("Logs").unwrap();
+        assert_eq!((&logs.folder, &logs.description), (&None, &None));
+        let view = schema.get_table("DailyStorms").unwrap();
+        assert_eq!(view.get_column("Count").unwrap().data_type, "long");
+
+        let function = schema.get_function("StormsIn").unwrap();
+        assert_eq!(function.folder.as_deref(), Some("Demo"));
+        assert_eq!(function.description.as_deref(), Some("Storms in a state"));
+        assert_eq!(
+            function.body.as_deref(),
+            Some("{ StormEvents | where State == state }")
+        );
+        assert_eq!(function.parameters[0].data_type, "string");
+        assert_eq!(
+            function.parameters[0].default_value.as_deref(),
+            Some("\"TEXAS\"")
+        );
+        assert_eq!(
+            function.parameters[1].data_type,
+            "(State:string, Count:long)"
+        );
+        assert_eq!(function.parameters[2].data_type, "(*)");
+        assert!(!function.is_tabular());
+
+        let views = r#"{"Databases": {"Samples": {"Name": "Samples", "Functions": {
+            "Recent": {"Name": "Recent", "Body": "{ StormEvents | take 10 }", "FunctionKind": "ViewFunction"},
+            "Counts": {"Name": "Counts", "Body": "{ StormEvents | count }", "OutputColumns": [
+                {"Name": "Count", "Type": "System.Int64", "CslType": "long"}
+            ]}
+        }}}}"#;
+        let schema = Schema::from_kusto_show_schema(views).unwrap();
+        let recent = schema.get_function("Recent").unwrap();
+        assert_eq!(
+            (recent.kind, &recent.return_type),
+            (FunctionKind::Tabular, &KqlType::Table(Vec::new()))
+        );
+        let counts = schema.get_function("Counts").unwrap();
+        assert_eq!(counts.return_type, "(Count:long)");
+    }
+
+    #[test]
+    fn test_from_kusto_show_schema_empty_and_invalid() {
+        assert!(Schema::from_kusto_show_schema(r#"{"Databases": {}}"#)
+            .unwrap()
+            .is_empty());
+        assert!(matches!(
+            Schema::from_kusto_show_schema(r#"{"Tables": {}}"#),
+            Err(Error::Json(_))
+        ));
+    }
+
+    #[test]
+    fn test_csl_round_trip() {
+        let schema = Schema::new()
+            .table(
+                Table::new("My Table")
+                    .column(Column::string("Name").description("Say \"hi\""))
+                    .with_column("Count", "long")
+                    .description("Demo")
+                    .folder("A/B"),
+            )
+            .function(
+                Function::new("Top", "dynamic")
+                    .param("n", "long")
+                    .body("['My Table'] | take n")
+                    .folder("Queries"),
+            );
+        let script = schema.to_csl();
+        assert_eq!(
+            script,
+            ".create table [\"My Table\"] (Name:string, Count:long) with (docstring = \"Demo\", folder = \"A/B\")\n\
+             \n\
+             .alter table [\"My Table\"] column-docstrings (Name:\"Say \\\"hi\\\"\")\n\
+             \n\
+             .create function with (folder = \"Queries\") Top(n:long)\n\
+             {\n\
+             ['My Table'] | take n\n\
+             }\n"
+        );
+        let parsed = Schema::from_csl(&script).unwrap();
+        assert_eq!(
+            parsed.get_function("Top").unwrap().body.as_deref(),
+            Some("{\n['My Table'] | take n\n}")
+        );
+        assert_eq!(parsed.to_csl(), script);
+
+        let samples = Schema::from_kusto_show_schema(SAMPLES).unwrap();
+        let mut reparsed = Schema::from_csl(&samples.to_csl()).unwrap();
+        reparsed.database = samples.database.clone();
+        assert_eq!(reparsed, samples);
+    }
+
+    #[test]
+    fn test_from_csl_script() {
+        let script = r#"
+            // Provisioning script
+            .create-merge table Events (Timestamp:datetime, Level:int) with (folder = 'Logs')
+
+            .alter table Events policy retention
+            '{ "SoftDeletePeriod": "30.00:00:00" }'
+
+            .create table Events ingestion json mapping "EventsMapping" '[{"column": "Level"}]'
+
+            .alter-merge table Events (Message:string, Level:long)
+            .alter table Events docstring @"C:\events"
+
+            .create-or-alter function with (skipvalidation = "true") Errors(since:timespan = time(1d), t:(*))
+            {
+                Events | where Level > 3 and Timestamp > ago(since) // "}"
+            }
+        "#;
+        let schema = Schema::from_csl(script).unwrap();
+        assert_eq!(schema.tables.len(), 1);
+        let events = schema.get_table("Events").unwrap();
+        let columns: Vec<_> = events
+            .columns
+            .iter()
+            .map(|c| (c.name.as_str(), c.data_type.clone()))
+            .collect();
+        assert_eq!(
+            columns,
+            [
+                ("Timestamp", KqlType::DateTime),
+                ("Level", KqlType::Long),
+                ("Message", KqlType::String)
+            ]
+        );
+        assert_eq!(events.folder.as_deref(), Some("Logs"));
+        assert_eq!(events.description.as_deref(), Some("C:\\events"));
+
+        let errors = schema.get_function("Errors").unwrap();
+        assert_eq!(
+            errors.parameters[0].default_value.as_deref(),
+            Some("time(1d)")
+        );
+        assert_eq!(errors.parameters[1].data_type, "(*)");
+        assert!(errors
+            .body
+            .as_deref()
+            .unwrap()
+            .ends_with("// \"}\"\n            }"));
+
+        assert!(matches!(
+            Schema::from_csl(".create table T (a:long)\n\n.create table U (b long)"),
+            Err(Error::Csl { line: 3, .. })
+        ));
+        assert!(matches!(
+            Schema::from_csl(".create function F() { T"),
+            Err(Error::Csl { line: 1, .. })
+        ));
+        assert!(matches!(
+            Schema::from_csl("T | take 10"),
+            Err(Error::Csl { .. })
+        ));
+    }
+}
@@ -31,29 +31,165 @@
 //! }
 //! ```
 //!
+//! ## Offsets
+//!
+//! Offsets in the API (spans, cursor positions, edit ranges) are character
+//! offsets, not bytes or UTF-16 code units. The native library counts UTF-16
+//! code units, as .NET strings do; its offsets are converted as they cross
+//! the FFI boundary. See [`positions`] for conversions.
+//!
 //! ## Native Library
 //!
 //! This crate requires a native library built from the .NET AOT project.
 //! The library can be:
 //!
 //! 1. Built from source: `cd dotnet && dotnet publish -c Release -r <rid>`
-//! 2. Downloaded from releases (if using `bundled` feature)
+//! 2. Downloaded from releases and checksum-verified at build time (`bundled` feature)
 //! 3. Specified via `kql_language_tools_PATH` environment variable
 
+mod analysis;
+#[cfg(feature = "asim")]
+pub mod asim;
+#[cfg(feature = "async")]
+mod async_validator;
+mod backend;
+mod bound;
+mod buffer;
+mod cache;
+mod cancel;
+mod catalog;
 mod classification;
+#[cfg(feature = "azure")]
+mod cluster;
+mod codes;
 mod completion;
+mod diagnose;
+mod dialect;
+mod document;
+#[cfg(feature = "embed")]
+mod embed;
+pub mod engine;
 mod error;
+mod expression;
+#[cfg(feature = "fallback")]
+pub mod fallback;
 mod ffi;
+mod fixes;
+mod format;
+mod handle;
+mod inlay;
+mod joins;
+mod kind;
+mod kql_type;
+mod kusto_schema;
+mod limits;
+mod lint;
+// The loader and validator sit on the FFI boundary and are the only
+// modules permitted to use `unsafe`.
+#[allow(unsafe_code)]
 mod loader;
+#[cfg(feature = "lsp")]
+pub mod lsp;
+mod metrics;
+#[cfg(feature = "mock")]
+mod mock;
+mod native_log;
+mod navigation;
+mod options;
+mod outline;
+mod pipeline;
+pub mod positions;
+mod profile;
+pub mod render;
+#[cfg(feature = "miette")]
+mod report;
+#[cfg(feature = "resource-graph")]
+pub mod resource_graph;
 mod schema;
+mod schema_check;
+mod schema_diff;
+mod schema_file;
+mod schema_merge;
+mod script;
+mod selection;
+pub mod semantic_tokens;
+#[cfg(feature = "sentinel")]
+pub mod sentinel;
+mod severity;
+mod spelling;
+mod structure;
+mod suggest;
+mod syntax_tree;
+mod trace;
 mod types;
+#[allow(unsafe_code)]
 mod validator;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+#[cfg(feature = "worker")]
+pub mod worker;
 
-pub use classification::{ClassificationKind, ClassificationResult, ClassifiedSpan};
-pub use completion::{CompletionItem, CompletionKind, CompletionResult};
+pub use analysis::QueryAnalysis;
+#[cfg(feature = "async")]
+pub use async_validator::AsyncKqlValidator;
+pub use backend::KqlEngine;
+pub use bound::BoundValidator;
+pub use cache::CachedValidator;
+pub use cancel::CancellationToken;
+pub use classification::{
+    ClassificationKind, ClassificationOptions, ClassificationResult, ClassifiedSpan, SpanWithText,
+};
+#[cfg(feature = "azure")]
+pub use cluster::KustoCredential;
+pub use codes::{CodeCategory, KustoDiagnosticCode};
+pub use completion::{
+    static_completions, CompletionContext, CompletionItem, CompletionKind, CompletionMatch,
+    CompletionOptions, CompletionResult, CompletionTrigger, TRIGGER_CHARACTERS,
+};
+pub use diagnose::{CandidateReport, CandidateSource, CandidateStatus, DotnetRuntime, LoadReport};
+pub use dialect::Dialect;
+pub use document::Document;
+pub use engine::EngineFeatureLevel;
 pub use error::Error;
-pub use schema::{Column, Function, Schema, Table};
-pub use types::{Diagnostic, DiagnosticSeverity, ValidationResult};
+pub use expression::{ExpressionType, TypeKind};
+pub use fixes::FixOutcome;
+pub use format::{FormatOptions, OperatorCase, PipeStyle};
+pub use handle::SchemaHandle;
+pub use inlay::{InlayHint, InlayHintKind};
+pub use joins::{JoinAnalysis, JoinKey, JoinOperation, JoinOperator};
+pub use kind::QueryKind;
+pub use kql_type::KqlType;
+pub use limits::ServiceLimits;
+pub use lint::{LintContext, LintRule, Linter, NoSearchStar, RequireTimeFilter};
+pub use metrics::{CallMetrics, Metrics};
+#[cfg(feature = "mock")]
+pub use mock::MockValidator;
+pub use native_log::{reset_native_log_handler, set_native_log_handler, NATIVE_LOG_TARGET};
+pub use navigation::{Definition, DefinitionKind};
+pub use outline::{DocumentSymbol, DocumentSymbolKind};
+pub use pipeline::{Pipeline, PipelineStage};
+pub use positions::OffsetEncoding;
+pub use profile::FeatureProfile;
+#[cfg(feature = "miette")]
+pub use report::KqlReport;
+pub use schema::{
+    Column, Function, FunctionKind, Parameter, ResourceScope, Schema, ScopeKind, Table,
+};
+pub use schema_check::SchemaIssue;
+pub use schema_diff::{SchemaChange, SchemaDiff};
+pub use schema_merge::{MergeConflict, MergePolicy, SchemaMerge};
+pub use script::{ScriptBlock, ScriptValidation};
+pub use selection::{BracketPair, EnclosingRange, EnclosingRangeKind};
+pub use severity::DiagnosticsConfig;
+pub use structure::{
+    DocumentStructure, FoldingRange, FoldingRangeKind, StructureKind, StructureNode,
+};
+pub use suggest::InlineSuggestion;
+pub use syntax_tree::{NodeKind, SyntaxNode, SyntaxTree, SyntaxVisitor};
+pub use types::{
+    Diagnostic, DiagnosticCategory, DiagnosticSeverity, Fix, RelatedInfo, TextEdit,
+    ValidationResult,
+};
 pub use validator::KqlValidator;
 
 /// Result type alias for this crate
@@ -76,3 +212,60 @@ pub fn is_available() -> bool {
 pub fn library_path() -> Option<std::path::PathBuf> {
     loader::find_library_path()
 }
+
+/// Set the location of the native library, file or directory
+///
+/// Takes precedence over `KQL_LANGUAGE_TOOLS_PATH` and every other search
+/// location, without mutating the process environment. Call it before the
+/// first validator is created.
+///
+/// # Errors
+///
+/// Returns [`Error::LibraryAlreadyLoaded`] if a library from a different
+/// location is already loaded.
+pub fn set_library_path(path: impl Into<std::path::PathBuf>) -> Result<()> {
+    loader::set_library_path(path)
+}
+
+/// Report where the native library was searched for and why it does or
+/// does not load
+///
+/// Checks every search location, the platform loader's verdict on each
+/// library found, `DOTNET_ROOT` and the installed .NET runtimes. Print it
+/// when [`KqlValidator::new`] fails with [`Error::LibraryNotFound`] or
+/// [`Error::LibraryLoadFailed`].
+#[must_use]
+pub fn diagnose_loading() -> LoadReport {
+    diagnose::diagnose()
+}
+
+/// Check if the native library is loaded and initialized
+#[must_use]
+pub fn is_loaded() -> bool {
+    loader::is_loaded()
+}
+
+/// Unload the native library so the next validator loads it again
+///
+/// Lets long-running tools pick up a rebuilt library, or one at a new
+/// [`set_library_path`] location, without restarting. Existing validators
+/// keep the old library until they are dropped. Returns `false` if no
+/// library was loaded.
+///
+/// # Example
+///
+/// ```no_run
+/// use kql_language_tools::KqlValidator;
+///
+/// let validator = KqlValidator::new()?;
+/// drop(validator);
+///
+/// // ... rebuild the native library ...
+/// kql_language_tools::unload_library();
+/// let validator = KqlValidator::new()?;
+/// # Ok::<(), kql_language_tools::Error>(())
+/// ```
+#[allow(clippy::must_use_candidate)]
+pub fn unload_library() -> bool {
+    loader::unload()
+}
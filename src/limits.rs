@@ -0,0 +1,81 @@
+//! Service-limit checks
+//!
+//! Kusto rejects queries that exceed service limits, often only after the
+//! query has been submitted. [`ServiceLimits`] describes the limits to check
+//! during validation; violations are reported as errors with the
+//! [`DiagnosticCategory::ServiceLimit`](crate::DiagnosticCategory::ServiceLimit)
+//! category so they can be told apart from syntax and semantic errors.
+//!
+//! The defaults are conservative approximations of the documented limits.
+//! Adjust them to match the cluster the queries will run on.
+
+use serde::{Deserialize, Serialize};
+
+/// Service limits checked during validation
+///
+/// # Example
+///
+/// ```
+/// use kql_language_tools::ServiceLimits;
+///
+/// let limits = ServiceLimits::default()
+///     .max_query_length(64 * 1024)
+///     .max_summarize_keys(16);
+///
+/// assert_eq!(limits.max_identifier_length, 1024);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ServiceLimits {
+    /// Maximum query text length in characters (`SL001`)
+    pub max_query_length: usize,
+
+    /// Maximum length of a table, column, function, or variable name (`SL002`)
+    pub max_identifier_length: usize,
+
+    /// Maximum number of `by` keys in a single `summarize` (`SL003`)
+    pub max_summarize_keys: usize,
+
+    /// Maximum number of values in a literal `in` / `!in` list (`SL004`)
+    pub max_in_list_values: usize,
+}
+
+impl Default for ServiceLimits {
+    fn default() -> Self {
+        Self {
+            max_query_length: 2 * 1024 * 1024,
+            max_identifier_length: 1024,
+            max_summarize_keys: 64,
+            max_in_list_values: 1_000_000,
+        }
+    }
+}
+
+impl ServiceLimits {
+    /// Builder method to set the maximum query text length
+    #[must_use]
+    pub fn max_query_length(mut self, length: usize) -> Self {
+        self.max_query_length = length;
+        self
+    }
+
+    /// Builder method to set the maximum identifier length
+    #[must_use]
+    pub fn max_identifier_length(mut self, length: usize) -> Self {
+        self.max_identifier_length = length;
+        self
+    }
+
+    /// Builder method to set the maximum number of `summarize` keys
+    #[must_use]
+    pub fn max_summarize_keys(mut self, keys: usize) -> Self {
+        self.max_summarize_keys = keys;
+        self
+    }
+
+    /// Builder method to set the maximum number of values in an `in` list
+    #[must_use]
+    pub fn max_in_list_values(mut self, values: usize) -> Self {
+        self.max_in_list_values = values;
+        self
+    }
+}
@@ -0,0 +1,419 @@
+//! Rust-side lint rules
+//!
+//! A [`Linter`] runs [`LintRule`]s over the classified tokens of a query and
+//! collects the diagnostics they report. Rules are plain Rust, so
+//! organisation-specific policies ("every query must filter on time", "no
+//! `search *`") can be enforced without touching the native library.
+//!
+//! [`Linter::builtin`] starts from the rules in this module;
+//! [`KqlValidator::lint`](crate::KqlValidator::lint) classifies a query and
+//! runs a linter over it.
+//!
+//! # Example
+//!
+//! ```
+//! use kql_language_tools::{Diagnostic, DiagnosticSeverity, LintContext, Linter};
+//!
+//! let linter = Linter::builtin().rule_fn("ORG001", |context: &LintContext<'_>| {
+//!     context
+//!         .tokens()
+//!         .filter(|(_, text)| *text == "SecurityEvent")
+//!         .map(|(span, _)| {
+//!             context.diagnostic(
+//!                 "ORG001",
+//!                 DiagnosticSeverity::Warning,
+//!                 "Use the normalized ASIM views instead of SecurityEvent",
+//!                 span.start,
+//!                 span.start + span.length,
+//!             )
+//!         })
+//!         .collect::<Vec<Diagnostic>>()
+//! });
+//! assert!(linter.codes().any(|code| code == "ORG001"));
+//! ```
+
+use crate::classification::{ClassificationKind, ClassifiedSpan};
+use crate::types::{Diagnostic, DiagnosticCategory, DiagnosticSeverity};
+
+/// A lint rule
+pub trait LintRule: Send + Sync {
+    /// Code reported on this rule's diagnostics
+    fn code(&self) -> &str;
+
+    /// Check a query, returning any diagnostics
+    fn check(&self, context: &LintContext<'_>) -> Vec<Diagnostic>;
+}
+
+/// A query and its classified tokens, as seen by lint rules
+#[derive(Debug)]
+pub struct LintContext<'a> {
+    query: &'a str,
+    spans: &'a [ClassifiedSpan],
+    /// Byte offset of every character, plus the query length
+    byte_offsets: Vec<usize>,
+}
+
+impl<'a> LintContext<'a> {
+    /// Create a context from a query and its classification spans
+    #[must_use]
+    pub fn new(query: &'a str, spans: &'a [ClassifiedSpan]) -> Self {
+        let byte_offsets = query
+            .char_indices()
+            .map(|(offset, _)| offset)
+            .chain(std::iter::once(query.len()))
+            .collect();
+
+        Self {
+            query,
+            spans,
+            byte_offsets,
+        }
+    }
+
+    /// Get the query text
+    #[must_use]
+    pub fn query(&self) -> &'a str {
+        self.query
+    }
+
+    /// Get the classification spans, in source order
+    #[must_use]
+    pub fn spans(&self) -> &'a [ClassifiedSpan] {
+        self.spans
+    }
+
+    /// Get the text of a span
+    #[must_use]
+    pub fn text(&self, span: &ClassifiedSpan) -> &'a str {
+        self.slice(span.start, span.start + span.length)
+    }
+
+    /// Iterate over spans and their text, skipping comments
+    pub fn tokens(&self) -> impl Iterator<Item = (&'a ClassifiedSpan, &'a str)> + '_ {
+        self.spans
+            .iter()
+            .filter(|span| span.kind != ClassificationKind::Comment)
+            .map(|span| (span, self.text(span)))
+    }
+
+    /// Build a diagnostic for a span of the query, filling in line and column
+    #[must_use]
+    pub fn diagnostic(
+        &self,
+        code: &str,
+        severity: DiagnosticSeverity,
+        message: impl Into<String>,
+        start: usize,
+        end: usize,
+    ) -> Diagnostic {
+        let before = self.slice(0, start);
+        let line = before.matches('\n').count() + 1;
+        let column = before.rsplit('\n').next().map_or(0, |l| l.chars().count()) + 1;
+
+        Diagnostic {
+            message: message.into(),
+            severity,
+            start,
+            end,
+            line,
+            column,
+            code: Some(code.to_string()),
+            category: None,
+            fixes: Vec::new(),
+            related_spans: Vec::new(),
+        }
+    }
+
+    /// Slice the query by character offsets, clamped to its length
+    fn slice(&self, start: usize, end: usize) -> &'a str {
+        let last = self.byte_offsets.len() - 1;
+        let start = self.byte_offsets[start.min(last)];
+        let end = self.byte_offsets[end.min(last)];
+        &self.query[start..end.max(start)]
+    }
+}
+
+/// A set of lint rules
+#[derive(Default)]
+pub struct Linter {
+    rules: Vec<Box<dyn LintRule>>,
+}
+
+impl std::fmt::Debug for Linter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list().entries(self.codes()).finish()
+    }
+}
+
+impl Linter {
+    /// Create a linter with no rules
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a linter with the built-in rules
+    ///
+    /// - [`NoSearchStar`] (`LNT001`)
+    /// - [`RequireTimeFilter`] (`LNT002`), for `TimeGenerated` and `Timestamp`
+    #[must_use]
+    pub fn builtin() -> Self {
+        Self::new()
+            .rule(NoSearchStar)
+            .rule(RequireTimeFilter::default())
+    }
+
+    /// Builder method to add a rule
+    #[must_use]
+    pub fn rule(mut self, rule: impl LintRule + 'static) -> Self {
+        self.rules.push(Box::new(rule));
+        self
+    }
+
+    /// Builder method to add a rule from a closure
+    #[must_use]
+    pub fn rule_fn<F>(self, code: impl Into<String>, check: F) -> Self
+    where
+        F: Fn(&LintContext<'_>) -> Vec<Diagnostic> + Send + Sync + 'static,
+    {
+        self.rule(FnRule {
+            code: code.into(),
+            check,
+        })
+    }
+
+    /// Iterate over the codes of the registered rules
+    pub fn codes(&self) -> impl Iterator<Item = &str> {
+        self.rules.iter().map(|rule| rule.code())
+    }
+
+    /// Run every rule over a query and its classification spans
+    ///
+    /// Diagnostics are returned in source order.
+    #[must_use]
+    pub fn check(&self, query: &str, spans: &[ClassifiedSpan]) -> Vec<Diagnostic> {
+        let context = LintContext::new(query, spans);
+        let mut diagnostics: Vec<Diagnostic> = self
+            .rules
+            .iter()
+            .flat_map(|rule| rule.check(&context))
+            .collect();
+        diagnostics.sort_by_key(|d| d.start);
+        diagnostics
+    }
+}
+
+/// A rule backed by a closure
+struct FnRule<F> {
+    code: String,
+    check: F,
+}
+
+impl<F> LintRule for FnRule<F>
+where
+    F: Fn(&LintContext<'_>) -> Vec<Diagnostic> + Send + Sync,
+{
+    fn code(&self) -> &str {
+        &self.code
+    }
+
+    fn check(&self, context: &LintContext<'_>) -> Vec<Diagnostic> {
+        (self.check)(context)
+    }
+}
+
+/// Flags `search *` and `union *`, which scan every table in the database
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoSearchStar;
+
+impl LintRule for NoSearchStar {
+    fn code(&self) -> &'static str {
+        "LNT001"
+    }
+
+    fn check(&self, context: &LintContext<'_>) -> Vec<Diagnostic> {
+        let tokens: Vec<_> = context.tokens().collect();
+        tokens
+            .windows(2)
+            .filter(|pair| matches!(pair[0].1, "search" | "union") && pair[1].1 == "*")
+            .map(|pair| {
+                let (operator, _) = pair[0];
+                let (star, _) = pair[1];
+                let mut diagnostic = context.diagnostic(
+                    self.code(),
+                    DiagnosticSeverity::Warning,
+                    format!(
+                        "`{} *` scans every table; name the tables instead",
+                        pair[0].1
+                    ),
+                    operator.start,
+                    star.start + star.length,
+                );
+                diagnostic.category = Some(DiagnosticCategory::Performance);
+                diagnostic
+            })
+            .collect()
+    }
+}
+
+/// Requires queries over tables to filter on a time column
+///
+/// The rule is satisfied when one of the time columns appears after a
+/// `where` operator; otherwise it reports the first table reference.
+#[derive(Debug, Clone)]
+pub struct RequireTimeFilter {
+    columns: Vec<String>,
+}
+
+impl Default for RequireTimeFilter {
+    fn default() -> Self {
+        Self::new(["TimeGenerated", "Timestamp"])
+    }
+}
+
+impl RequireTimeFilter {
+    /// Create the rule for a set of time column names
+    #[must_use]
+    pub fn new<I, S>(columns: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            columns: columns.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl LintRule for RequireTimeFilter {
+    fn code(&self) -> &'static str {
+        "LNT002"
+    }
+
+    fn check(&self, context: &LintContext<'_>) -> Vec<Diagnostic> {
+        let Some((table, name)) = context
+            .tokens()
+            .find(|(span, _)| span.kind == ClassificationKind::Table)
+        else {
+            return Vec::new();
+        };
+
+        let filtered = context
+            .tokens()
+            .skip_while(|(span, text)| {
+                !(*text == "where"
+                    && matches!(
+                        span.kind,
+                        ClassificationKind::QueryOperator | ClassificationKind::Keyword
+                    ))
+            })
+            .any(|(_, text)| self.columns.iter().any(|c| c == text));
+        if filtered {
+            return Vec::new();
+        }
+
+        let mut diagnostic = context.diagnostic(
+            self.code(),
+            DiagnosticSeverity::Warning,
+            format!(
+                "Query over `{name}` has no time filter on {}",
+                self.columns.join(" or ")
+            ),
+            table.start,
+            table.start + table.length,
+        );
+        diagnostic.category = Some(DiagnosticCategory::Performance);
+        vec![diagnostic]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Classify whitespace-separated tokens by a tiny lookup, enough for the rules
+    fn classify(query: &str) -> Vec<ClassifiedSpan> {
+        let mut spans = Vec::new();
+        let mut start = None;
+        for (offset, c) in query.chars().chain(std::iter::once(' ')).enumerate() {
+            match (c.is_whitespace(), start) {
+                (false, None) => start = Some(offset),
+                (true, Some(s)) => {
+                    let text: String = query.chars().skip(s).take(offset - s).collect();
+                    let kind = match text.as_str() {
+                        "where" | "search" | "union" | "take" => ClassificationKind::QueryOperator,
+                        "SigninLogs" | "Événements" => ClassificationKind::Table,
+                        "TimeGenerated" | "UserId" => ClassificationKind::Column,
+                        _ => ClassificationKind::PlainText,
+                    };
+                    spans.push(ClassifiedSpan {
+                        start: s,
+                        length: offset - s,
+                        kind,
+                    });
+                    start = None;
+                }
+                _ => {}
+            }
+        }
+        spans
+    }
+
+    fn codes(linter: &Linter, query: &str) -> Vec<String> {
+        linter
+            .check(query, &classify(query))
+            .into_iter()
+            .filter_map(|d| d.code)
+            .collect()
+    }
+
+    #[test]
+    fn test_no_search_star() {
+        let linter = Linter::new().rule(NoSearchStar);
+        assert_eq!(codes(&linter, "search * | take 10"), ["LNT001"]);
+        assert_eq!(codes(&linter, "union *"), ["LNT001"]);
+        assert!(codes(&linter, "search \"error\"").is_empty());
+    }
+
+    #[test]
+    fn test_require_time_filter() {
+        let linter = Linter::new().rule(RequireTimeFilter::default());
+        assert!(codes(&linter, "SigninLogs | where TimeGenerated > ago(1h)").is_empty());
+        assert!(codes(&linter, "print 1").is_empty());
+
+        let query = "let x = 1;\nÉvénements | where UserId == x";
+        let diagnostics = linter.check(query, &classify(query));
+        assert_eq!(diagnostics.len(), 1);
+        let d = &diagnostics[0];
+        assert_eq!((d.start, d.end, d.line, d.column), (11, 21, 2, 1));
+        assert!(d.message.contains("Événements"));
+    }
+
+    #[test]
+    fn test_custom_rules_in_source_order() {
+        let linter = Linter::builtin().rule_fn("ORG001", |context: &LintContext<'_>| {
+            context
+                .tokens()
+                .filter(|(_, text)| *text == "take")
+                .map(|(span, _)| {
+                    context.diagnostic(
+                        "ORG001",
+                        DiagnosticSeverity::Hint,
+                        "Avoid take",
+                        span.start,
+                        span.start + span.length,
+                    )
+                })
+                .collect()
+        });
+
+        assert_eq!(
+            linter.codes().collect::<Vec<_>>(),
+            ["LNT001", "LNT002", "ORG001"]
+        );
+        assert_eq!(
+            codes(&linter, "SigninLogs | take 1 | union *"),
+            ["LNT002", "ORG001", "LNT001"]
+        );
+    }
+}
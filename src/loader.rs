@@ -3,14 +3,32 @@
 //! This module handles finding and loading the .NET AOT native library
 //! across different platforms.
 
+use crate::diagnose::CandidateSource;
 use crate::error::Error;
+#[cfg(feature = "cbor")]
+use crate::ffi::result_formats;
 use crate::ffi::{
-    symbols, KqlCleanupFn, KqlGetClassificationsFn, KqlGetCompletionsFn, KqlGetLastErrorFn,
-    KqlInitFn, KqlValidateSyntaxFn, KqlValidateWithSchemaFn,
+    return_codes, symbols, KqlAnalyzeFn, KqlAnalyzeJoinsFn, KqlAnalyzePipelineFn,
+    KqlCancellationCancelFn, KqlCancellationCreateFn, KqlCancellationReleaseFn,
+    KqlClassifyChunksFn, KqlCleanupFn, KqlDocumentCloseFn, KqlDocumentEditFn,
+    KqlDocumentGetCompletionsFn, KqlDocumentGetDiagnosticsFn, KqlDocumentOpenFn, KqlFormatFn,
+    KqlFreeResultFn, KqlGetAbiVersionFn, KqlGetAnalyzerDiagnosticsFn, KqlGetBracketPairsFn,
+    KqlGetCapabilitiesFn, KqlGetClassificationsFn, KqlGetClassificationsInRangeFn,
+    KqlGetCompletionsFn, KqlGetCompletionsWithOptionsFn, KqlGetCompletionsWithSchemaIdFn,
+    KqlGetDefinitionFn, KqlGetDocumentStructureFn, KqlGetDocumentSymbolsFn,
+    KqlGetEnclosingRangesFn, KqlGetExpressionTypeFn, KqlGetInlayHintsFn, KqlGetLastErrorFn,
+    KqlGetLastRequiredSizeFn, KqlGetSemanticClassificationsFn, KqlGetSyntaxTreeFn,
+    KqlInferResultSchemaFn, KqlInitFn, KqlReadResultChunkFn, KqlRegisterSchemaFn,
+    KqlReleaseSchemaFn, KqlResolveCompletionFn, KqlSetLogCallbackFn, KqlSetResultFormatFn,
+    KqlValidateBatchFn, KqlValidateSyntaxFn, KqlValidateWithOptionsFn, KqlValidateWithSchemaFn,
+    KqlValidateWithSchemaIdFn, ABI_VERSION, ALLOCATE_RESULT, DEFAULT_BUFFER_SIZE, MAX_BUFFER_SIZE,
 };
+use crate::native_log;
 use libloading::Library;
-use once_cell::sync::OnceCell;
-use std::path::PathBuf;
+use std::ffi::{c_int, CStr};
+use std::path::{Path, PathBuf};
+use std::ptr;
+use std::sync::{Arc, Mutex, PoisonError, RwLock, Weak};
 
 /// Environment variable for specifying library path
 pub const LIB_PATH_ENV: &str = "KQL_LANGUAGE_TOOLS_PATH";
@@ -46,36 +64,113 @@ pub fn current_rid() -> &'static str {
     return "win-arm64";
 }
 
+/// Library location set with [`set_library_path`]
+static CONFIGURED_PATH: RwLock<Option<PathBuf>> = RwLock::new(None);
+
+/// Set the native library location, taking precedence over every other
+/// search location
+///
+/// `path` is either the library file or a directory containing it. Unlike
+/// [`LIB_PATH_ENV`], this does not touch the process environment, so hosts
+/// that load this crate as a plugin can configure it at any point before
+/// the first validator is created.
+///
+/// # Errors
+///
+/// Returns [`Error::LibraryAlreadyLoaded`] if a library from a different
+/// location has already been loaded. Call
+/// [`unload_library`](crate::unload_library) first to switch libraries.
+pub fn set_library_path(path: impl Into<PathBuf>) -> Result<(), Error> {
+    let path = path.into();
+    if let Some(lib) = current_library() {
+        if library_in(&path).as_deref() != Some(lib.path()) {
+            return Err(Error::LibraryAlreadyLoaded {
+                path: lib.path().to_path_buf(),
+            });
+        }
+    }
+    *CONFIGURED_PATH
+        .write()
+        .unwrap_or_else(PoisonError::into_inner) = Some(path);
+    Ok(())
+}
+
+/// Get the location set with [`set_library_path`], if any
+pub fn configured_library_path() -> Option<PathBuf> {
+    CONFIGURED_PATH
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clone()
+}
+
+/// Resolve a library file, or a directory containing one
+fn library_in(path: &Path) -> Option<PathBuf> {
+    // If it's a file, use it directly
+    if path.is_file() {
+        return Some(path.to_path_buf());
+    }
+    // If it's a directory, look for the library file in it
+    if path.is_dir() {
+        let lib_path = path.join(LIB_NAME);
+        if lib_path.exists() {
+            return Some(lib_path);
+        }
+    }
+    None
+}
+
 /// Find the native library path
 ///
 /// Search order:
-/// 1. `kql_language_tools_PATH` environment variable
-/// 2. Same directory as the current executable
-/// 3. `native/{rid}/` relative to the crate root
-/// 4. Current working directory
+/// 1. The location set with [`set_library_path`]
+/// 2. `KQL_LANGUAGE_TOOLS_PATH` environment variable
+/// 3. The prebuilt library fetched at build time (`bundled` feature)
+/// 4. The library embedded in the binary, once extracted (`embed` feature)
+/// 5. Same directory as the current executable
+/// 6. `OUT_DIR` of the consuming crate, when run through `cargo run` or
+///    `cargo test`
+/// 7. The Cargo target profile directory (`target/debug`), for test and
+///    example binaries in `deps/` and `examples/`
+/// 8. `native/{rid}/` in the workspace root
+/// 9. `native/{rid}/` relative to the crate root
+/// 10. `kql-language-tools/{version}/{rid}/` in the per-user cache directory
+/// 11. Current working directory
 pub fn find_library_path() -> Option<PathBuf> {
-    // 1. Check environment variable
+    // 1. Location configured at runtime
+    if let Some(path) = configured_library_path() {
+        if let Some(lib_path) = library_in(&path) {
+            log::debug!("Found library at configured path: {}", lib_path.display());
+            return Some(lib_path);
+        }
+        log::warn!("No native library at configured path {}", path.display());
+    }
+
+    // 2. Check environment variable
     if let Ok(path) = std::env::var(LIB_PATH_ENV) {
-        let path = PathBuf::from(path);
-        // If it's a file, use it directly
-        if path.is_file() {
-            log::debug!("Found library via {LIB_PATH_ENV}: {}", path.display());
-            return Some(path);
+        if let Some(lib_path) = library_in(Path::new(&path)) {
+            log::debug!("Found library via {LIB_PATH_ENV}: {}", lib_path.display());
+            return Some(lib_path);
         }
-        // If it's a directory, look for the library file in it
-        if path.is_dir() {
-            let lib_path = path.join(LIB_NAME);
-            if lib_path.exists() {
-                log::debug!(
-                    "Found library in {LIB_PATH_ENV} directory: {}",
-                    lib_path.display()
-                );
-                return Some(lib_path);
-            }
+    }
+
+    // 3. Prebuilt library fetched by the build script
+    if let Some(lib_path) = bundled_library_path() {
+        if lib_path.exists() {
+            log::debug!("Found bundled library: {}", lib_path.display());
+            return Some(lib_path);
+        }
+    }
+
+    // 4. Embedded library, extracted to the user cache
+    #[cfg(feature = "embed")]
+    if let Some(lib_path) = crate::embed::library_path() {
+        if lib_path.exists() {
+            log::debug!("Found embedded library: {}", lib_path.display());
+            return Some(lib_path);
         }
     }
 
-    // 2. Same directory as executable
+    // 5. Same directory as executable
     if let Ok(exe_path) = std::env::current_exe() {
         if let Some(exe_dir) = exe_path.parent() {
             let lib_path = exe_dir.join(LIB_NAME);
@@ -86,7 +181,37 @@ pub fn find_library_path() -> Option<PathBuf> {
         }
     }
 
-    // 3. Native directory relative to crate (for development)
+    // 6. Build script output of the consuming crate
+    if let Some(out_dir) = std::env::var_os("OUT_DIR") {
+        let lib_path = PathBuf::from(out_dir).join(LIB_NAME);
+        if lib_path.exists() {
+            log::debug!("Found library in OUT_DIR: {}", lib_path.display());
+            return Some(lib_path);
+        }
+    }
+
+    // 7. Target profile directory, above deps/ and examples/
+    if let Some(profile_dir) = target_profile_dir() {
+        let lib_path = profile_dir.join(LIB_NAME);
+        if lib_path.exists() {
+            log::debug!("Found library in target directory: {}", lib_path.display());
+            return Some(lib_path);
+        }
+    }
+
+    // 8. Native directory in the workspace root
+    if let Some(native_dir) = workspace_native_dir() {
+        let lib_path = native_dir.join(LIB_NAME);
+        if lib_path.exists() {
+            log::debug!(
+                "Found library in workspace native directory: {}",
+                lib_path.display()
+            );
+            return Some(lib_path);
+        }
+    }
+
+    // 9. Native directory relative to crate (for development)
     let native_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
         .join("dotnet")
         .join("native")
@@ -97,7 +222,16 @@ pub fn find_library_path() -> Option<PathBuf> {
         return Some(lib_path);
     }
 
-    // 4. Current working directory
+    // 10. Per-user cache directory
+    if let Some(cache_dir) = user_library_dir() {
+        let lib_path = cache_dir.join(LIB_NAME);
+        if lib_path.exists() {
+            log::debug!("Found library in user cache: {}", lib_path.display());
+            return Some(lib_path);
+        }
+    }
+
+    // 11. Current working directory
     let cwd_path = PathBuf::from(LIB_NAME);
     if cwd_path.exists() {
         log::debug!("Found library in current directory: {}", cwd_path.display());
@@ -110,36 +244,170 @@ pub fn find_library_path() -> Option<PathBuf> {
 
 /// Get the list of paths that were searched
 pub fn searched_paths() -> Vec<PathBuf> {
+    candidates().into_iter().map(|(_, path)| path).collect()
+}
+
+/// Get every library location searched, in order, with where it comes from
+///
+/// Directories given through [`set_library_path`] or [`LIB_PATH_ENV`] are
+/// resolved to the library file in them.
+pub(crate) fn candidates() -> Vec<(CandidateSource, PathBuf)> {
+    let resolve = |path: PathBuf| {
+        if path.is_file() {
+            path
+        } else {
+            path.join(LIB_NAME)
+        }
+    };
     let mut paths = Vec::new();
 
+    // Configured location
+    if let Some(path) = configured_library_path() {
+        paths.push((CandidateSource::Configured, resolve(path)));
+    }
+
     // Environment variable
     if let Ok(path) = std::env::var(LIB_PATH_ENV) {
-        paths.push(PathBuf::from(&path));
-        paths.push(PathBuf::from(path).join(LIB_NAME));
+        paths.push((CandidateSource::Environment, resolve(PathBuf::from(path))));
+    }
+
+    // Bundled library
+    if let Some(path) = bundled_library_path() {
+        paths.push((CandidateSource::Bundled, path));
+    }
+
+    // Embedded library
+    #[cfg(feature = "embed")]
+    if let Some(dir) = crate::embed::extract_dir() {
+        paths.push((CandidateSource::Embedded, dir.join(LIB_NAME)));
     }
 
     // Executable directory
     if let Ok(exe_path) = std::env::current_exe() {
         if let Some(exe_dir) = exe_path.parent() {
-            paths.push(exe_dir.join(LIB_NAME));
+            paths.push((CandidateSource::ExecutableDir, exe_dir.join(LIB_NAME)));
         }
     }
 
+    // Build script output
+    if let Some(out_dir) = std::env::var_os("OUT_DIR") {
+        paths.push((
+            CandidateSource::OutDir,
+            PathBuf::from(out_dir).join(LIB_NAME),
+        ));
+    }
+
+    // Target profile directory
+    if let Some(dir) = target_profile_dir() {
+        paths.push((CandidateSource::TargetDir, dir.join(LIB_NAME)));
+    }
+
+    // Workspace native directory
+    if let Some(dir) = workspace_native_dir() {
+        paths.push((CandidateSource::WorkspaceNative, dir.join(LIB_NAME)));
+    }
+
     // Native directory
     let native_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
         .join("dotnet")
         .join("native")
         .join(current_rid());
-    paths.push(native_dir.join(LIB_NAME));
+    paths.push((CandidateSource::CrateNative, native_dir.join(LIB_NAME)));
+
+    // User cache directory
+    if let Some(dir) = user_library_dir() {
+        paths.push((CandidateSource::UserCache, dir.join(LIB_NAME)));
+    }
 
     // Current directory
-    paths.push(PathBuf::from(LIB_NAME));
+    paths.push((CandidateSource::CurrentDir, PathBuf::from(LIB_NAME)));
 
     paths
 }
 
-/// Loaded library instance (singleton)
-static LIBRARY: OnceCell<LoadedLibrary> = OnceCell::new();
+/// Cargo target profile directory of the running test or example binary
+///
+/// Cargo places these in `target/{profile}/deps` and
+/// `target/{profile}/examples`, one level below where build scripts and
+/// users usually put the library.
+fn target_profile_dir() -> Option<PathBuf> {
+    let exe_path = std::env::current_exe().ok()?;
+    let exe_dir = exe_path.parent()?;
+    match exe_dir.file_name()?.to_str()? {
+        "deps" | "examples" => exe_dir.parent().map(Path::to_path_buf),
+        _ => None,
+    }
+}
+
+/// `native/{rid}` in the root of the workspace the running binary was
+/// built in, found from its path under the `target` directory
+fn workspace_native_dir() -> Option<PathBuf> {
+    let exe_path = std::env::current_exe().ok()?;
+    let target_dir = exe_path
+        .ancestors()
+        .skip(1)
+        .find(|dir| dir.file_name().is_some_and(|name| name == "target"))?;
+    Some(target_dir.parent()?.join("native").join(current_rid()))
+}
+
+/// `kql-language-tools/{version}/{rid}` in the per-user cache directory
+fn user_library_dir() -> Option<PathBuf> {
+    user_cache_dir().map(|dir| {
+        dir.join("kql-language-tools")
+            .join(env!("CARGO_PKG_VERSION"))
+            .join(current_rid())
+    })
+}
+
+/// The per-user cache directory of the platform
+pub(crate) fn user_cache_dir() -> Option<PathBuf> {
+    let var = |name: &str| {
+        std::env::var_os(name)
+            .filter(|value| !value.is_empty())
+            .map(PathBuf::from)
+    };
+    if cfg!(target_os = "windows") {
+        var("LOCALAPPDATA")
+    } else if cfg!(target_os = "macos") {
+        var("HOME").map(|home| home.join("Library").join("Caches"))
+    } else {
+        var("XDG_CACHE_HOME").or_else(|| var("HOME").map(|home| home.join(".cache")))
+    }
+}
+
+/// Path of the prebuilt library the build script fetched, if it did
+fn bundled_library_path() -> Option<PathBuf> {
+    #[cfg(feature = "bundled")]
+    if let Some(dir) = option_env!("KQL_LANGUAGE_TOOLS_BUNDLED_DIR") {
+        return Some(PathBuf::from(dir).join(LIB_NAME));
+    }
+    None
+}
+
+/// The loaded library, shared by every validator created while it is
+/// current
+///
+/// [`unload_library`](crate::unload_library) clears it; the library itself
+/// stays loaded until the last validator, schema handle or result using it
+/// is dropped.
+static LIBRARY: Mutex<Option<Arc<LoadedLibrary>>> = Mutex::new(None);
+
+/// Every library instance still in use: the current one, unloaded ones
+/// and those loaded with [`load_library_from`]
+///
+/// The platform loader hands out the same handle for a path that is still
+/// open, so two instances loaded from one path would share native state and
+/// the cleanup of either on drop would tear down the other. Loading a path
+/// with a live instance returns that instance instead.
+static INSTANCES: Mutex<Vec<Weak<LoadedLibrary>>> = Mutex::new(Vec::new());
+
+/// Get the current library, if one is loaded
+pub(crate) fn current_library() -> Option<Arc<LoadedLibrary>> {
+    LIBRARY
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clone()
+}
 
 /// Container for loaded library and function pointers
 pub struct LoadedLibrary {
@@ -147,6 +415,12 @@ pub struct LoadedLibrary {
     #[allow(dead_code)]
     library: Library,
 
+    /// Path the library was loaded from
+    path: PathBuf,
+
+    /// Canonical form of `path`, identifying the instance
+    key: PathBuf,
+
     /// Initialize function
     pub init: KqlInitFn,
 
@@ -168,13 +442,133 @@ pub struct LoadedLibrary {
 
     /// Get classifications function (optional, Phase 3)
     pub get_classifications: Option<KqlGetClassificationsFn>,
+
+    /// Validate with options function (optional, dialect support)
+    pub validate_with_options: Option<KqlValidateWithOptionsFn>,
+
+    /// Get completions with options function (optional)
+    pub get_completions_with_options: Option<KqlGetCompletionsWithOptionsFn>,
+
+    /// Get document structure function (optional)
+    pub get_document_structure: Option<KqlGetDocumentStructureFn>,
+
+    /// Validate batch function (optional)
+    pub validate_batch: Option<KqlValidateBatchFn>,
+
+    /// Format function (optional)
+    pub format: Option<KqlFormatFn>,
+
+    /// Infer result schema function (optional)
+    pub infer_result_schema: Option<KqlInferResultSchemaFn>,
+
+    /// Analyze function (optional)
+    pub analyze: Option<KqlAnalyzeFn>,
+
+    /// Register schema function (optional)
+    pub register_schema: Option<KqlRegisterSchemaFn>,
+
+    /// Release schema function (optional)
+    pub release_schema: Option<KqlReleaseSchemaFn>,
+
+    /// Validate with schema id function (optional)
+    pub validate_with_schema_id: Option<KqlValidateWithSchemaIdFn>,
+
+    /// Get completions with schema id function (optional)
+    pub get_completions_with_schema_id: Option<KqlGetCompletionsWithSchemaIdFn>,
+
+    /// Get semantic classifications function (optional)
+    pub get_semantic_classifications: Option<KqlGetSemanticClassificationsFn>,
+
+    /// Get classifications in range function (optional)
+    pub get_classifications_in_range: Option<KqlGetClassificationsInRangeFn>,
+
+    /// Streaming classification function (optional)
+    pub classify_chunks: Option<KqlClassifyChunksFn>,
+
+    /// Get definition function (optional)
+    pub get_definition: Option<KqlGetDefinitionFn>,
+
+    /// Get document symbols function (optional)
+    pub get_document_symbols: Option<KqlGetDocumentSymbolsFn>,
+
+    /// Get expression type function (optional)
+    pub get_expression_type: Option<KqlGetExpressionTypeFn>,
+
+    /// Resolve completion function (optional)
+    pub resolve_completion: Option<KqlResolveCompletionFn>,
+
+    /// Get bracket pairs function (optional)
+    pub get_bracket_pairs: Option<KqlGetBracketPairsFn>,
+
+    /// Get enclosing ranges function (optional)
+    pub get_enclosing_ranges: Option<KqlGetEnclosingRangesFn>,
+
+    /// Get syntax tree function (optional)
+    pub get_syntax_tree: Option<KqlGetSyntaxTreeFn>,
+
+    /// Get inlay hints function (optional)
+    pub get_inlay_hints: Option<KqlGetInlayHintsFn>,
+
+    /// Analyze pipeline function (optional)
+    pub analyze_pipeline: Option<KqlAnalyzePipelineFn>,
+
+    /// Analyze joins function (optional)
+    pub analyze_joins: Option<KqlAnalyzeJoinsFn>,
+
+    /// Get analyzer diagnostics function (optional)
+    pub get_analyzer_diagnostics: Option<KqlGetAnalyzerDiagnosticsFn>,
+
+    /// Open document function (optional)
+    pub document_open: Option<KqlDocumentOpenFn>,
+
+    /// Edit document function (optional)
+    pub document_edit: Option<KqlDocumentEditFn>,
+
+    /// Get document diagnostics function (optional)
+    pub document_get_diagnostics: Option<KqlDocumentGetDiagnosticsFn>,
+
+    /// Get document completions function (optional)
+    pub document_get_completions: Option<KqlDocumentGetCompletionsFn>,
+
+    /// Close document function (optional)
+    pub document_close: Option<KqlDocumentCloseFn>,
+
+    /// Create cancellation function (optional)
+    pub cancellation_create: Option<KqlCancellationCreateFn>,
+
+    /// Cancel cancellation function (optional)
+    pub cancellation_cancel: Option<KqlCancellationCancelFn>,
+
+    /// Release cancellation function (optional)
+    pub cancellation_release: Option<KqlCancellationReleaseFn>,
+
+    /// Get last required size function (optional)
+    pub get_last_required_size: Option<KqlGetLastRequiredSizeFn>,
+
+    /// Free result function (optional)
+    pub free_result: Option<KqlFreeResultFn>,
+
+    /// Read result chunk function (optional)
+    pub read_result_chunk: Option<KqlReadResultChunkFn>,
+
+    /// Set result format function (optional)
+    pub set_result_format: Option<KqlSetResultFormatFn>,
+
+    /// Get ABI version function (optional)
+    pub get_abi_version: Option<KqlGetAbiVersionFn>,
+
+    /// Get capabilities function (optional)
+    pub get_capabilities: Option<KqlGetCapabilitiesFn>,
+
+    /// Set log callback function (optional)
+    pub set_log_callback: Option<KqlSetLogCallbackFn>,
 }
 
 // SAFETY: `LoadedLibrary` can be safely sent between threads because:
 // 1. The `Library` handle from libloading is Send (it's just a handle/pointer)
 // 2. All function pointers are plain data (Copy) with no interior mutability
 // 3. The underlying .NET runtime (DNNE) initializes thread-safe state
-// 4. We load the library exactly once via OnceLock, ensuring single initialization
+// 4. Each instance is loaded and initialized once, under the LIBRARY lock
 unsafe impl Send for LoadedLibrary {}
 
 // SAFETY: `LoadedLibrary` can be safely shared between threads because:
@@ -185,8 +579,75 @@ unsafe impl Send for LoadedLibrary {}
 // 4. The Library handle itself is read-only after initialization
 unsafe impl Sync for LoadedLibrary {}
 
+/// Look up an optional symbol, returning `None` if the library lacks it
+///
+/// # Safety
+///
+/// `T` must be the function pointer type matching the symbol's C ABI signature.
+unsafe fn optional_symbol<T: Copy>(library: &Library, symbol: &str) -> Option<T> {
+    library.get::<T>(symbol.as_bytes()).ok().map(|s| *s)
+}
+
+/// Look up a symbol the library must export
+///
+/// # Safety
+///
+/// Same as [`optional_symbol`].
+unsafe fn required_symbol<T: Copy>(library: &Library, symbol: &str) -> Result<T, Error> {
+    optional_symbol(library, symbol).ok_or_else(|| Error::SymbolNotFound {
+        symbol: symbol.to_string(),
+    })
+}
+
+/// A call result allocated by the native library, freed on drop
+pub struct NativeResult {
+    lib: Arc<LoadedLibrary>,
+    data: *mut u8,
+    len: usize,
+}
+
+impl NativeResult {
+    /// Get the result bytes
+    pub fn as_bytes(&self) -> &[u8] {
+        if self.data.is_null() || self.len == 0 {
+            return &[];
+        }
+        // SAFETY: The native library wrote a pointer to `len` initialized
+        // bytes, which stay valid until freed in drop.
+        unsafe { std::slice::from_raw_parts(self.data, self.len) }
+    }
+}
+
+impl Drop for NativeResult {
+    fn drop(&mut self) {
+        if self.data.is_null() {
+            return;
+        }
+        if let Some(free_fn) = self.lib.free_result {
+            // SAFETY: The pointer came from the native allocator and is
+            // freed exactly once.
+            unsafe { free_fn(self.data) };
+        }
+    }
+}
+
+/// Restores JSON call results on this thread when dropped
+#[cfg(feature = "cbor")]
+pub struct ResultFormatGuard {
+    set_fn: KqlSetResultFormatFn,
+}
+
+#[cfg(feature = "cbor")]
+impl Drop for ResultFormatGuard {
+    fn drop(&mut self) {
+        // SAFETY: As in use_result_format; JSON is always supported.
+        unsafe { (self.set_fn)(result_formats::JSON) };
+    }
+}
+
 impl LoadedLibrary {
     /// Load the library from the given path
+    #[allow(clippy::too_many_lines)] // One statement per optional symbol
     fn load_from(path: &PathBuf) -> Result<Self, Error> {
         log::info!("Loading KQL language library from {}", path.display());
 
@@ -205,69 +666,268 @@ impl LoadedLibrary {
         // 4. The library remains loaded for the lifetime of LoadedLibrary
 
         // Load required symbols
-        let init: KqlInitFn = unsafe {
-            *library
-                .get(symbols::KQL_INIT.as_bytes())
-                .map_err(|_| Error::SymbolNotFound {
-                    symbol: symbols::KQL_INIT.to_string(),
-                })?
-        };
+        let init: KqlInitFn = unsafe { required_symbol(&library, symbols::KQL_INIT)? };
 
-        let cleanup: KqlCleanupFn = unsafe {
-            *library
-                .get(symbols::KQL_CLEANUP.as_bytes())
-                .map_err(|_| Error::SymbolNotFound {
-                    symbol: symbols::KQL_CLEANUP.to_string(),
-                })?
-        };
+        let cleanup: KqlCleanupFn = unsafe { required_symbol(&library, symbols::KQL_CLEANUP)? };
 
-        let validate_syntax: KqlValidateSyntaxFn = unsafe {
-            *library
-                .get(symbols::KQL_VALIDATE_SYNTAX.as_bytes())
-                .map_err(|_| Error::SymbolNotFound {
-                    symbol: symbols::KQL_VALIDATE_SYNTAX.to_string(),
-                })?
-        };
+        let validate_syntax: KqlValidateSyntaxFn =
+            unsafe { required_symbol(&library, symbols::KQL_VALIDATE_SYNTAX)? };
 
-        let get_last_error: KqlGetLastErrorFn = unsafe {
-            *library
-                .get(symbols::KQL_GET_LAST_ERROR.as_bytes())
-                .map_err(|_| Error::SymbolNotFound {
-                    symbol: symbols::KQL_GET_LAST_ERROR.to_string(),
-                })?
-        };
+        let get_last_error: KqlGetLastErrorFn =
+            unsafe { required_symbol(&library, symbols::KQL_GET_LAST_ERROR)? };
 
         // Load optional symbols (don't fail if not present)
-        let validate_with_schema: Option<KqlValidateWithSchemaFn> = unsafe {
-            library
-                .get(symbols::KQL_VALIDATE_WITH_SCHEMA.as_bytes())
-                .ok()
-                .map(|s| *s)
-        };
+        let validate_with_schema: Option<KqlValidateWithSchemaFn> =
+            unsafe { optional_symbol(&library, symbols::KQL_VALIDATE_WITH_SCHEMA) };
 
-        let get_completions: Option<KqlGetCompletionsFn> = unsafe {
-            library
-                .get(symbols::KQL_GET_COMPLETIONS.as_bytes())
-                .ok()
-                .map(|s| *s)
-        };
+        let get_completions: Option<KqlGetCompletionsFn> =
+            unsafe { optional_symbol(&library, symbols::KQL_GET_COMPLETIONS) };
 
-        let get_classifications: Option<KqlGetClassificationsFn> = unsafe {
-            library
-                .get(symbols::KQL_GET_CLASSIFICATIONS.as_bytes())
-                .ok()
-                .map(|s| *s)
-        };
+        let get_classifications: Option<KqlGetClassificationsFn> =
+            unsafe { optional_symbol(&library, symbols::KQL_GET_CLASSIFICATIONS) };
 
-        log::debug!(
-            "Loaded symbols: validate_with_schema={}, get_completions={}, get_classifications={}",
-            validate_with_schema.is_some(),
-            get_completions.is_some(),
-            get_classifications.is_some()
-        );
+        let validate_with_options: Option<KqlValidateWithOptionsFn> =
+            unsafe { optional_symbol(&library, symbols::KQL_VALIDATE_WITH_OPTIONS) };
+
+        let get_completions_with_options: Option<KqlGetCompletionsWithOptionsFn> =
+            unsafe { optional_symbol(&library, symbols::KQL_GET_COMPLETIONS_WITH_OPTIONS) };
+
+        let get_document_structure: Option<KqlGetDocumentStructureFn> =
+            unsafe { optional_symbol(&library, symbols::KQL_GET_DOCUMENT_STRUCTURE) };
+
+        let validate_batch: Option<KqlValidateBatchFn> =
+            unsafe { optional_symbol(&library, symbols::KQL_VALIDATE_BATCH) };
+
+        let format: Option<KqlFormatFn> = unsafe { optional_symbol(&library, symbols::KQL_FORMAT) };
+
+        let infer_result_schema: Option<KqlInferResultSchemaFn> =
+            unsafe { optional_symbol(&library, symbols::KQL_INFER_RESULT_SCHEMA) };
+
+        let analyze: Option<KqlAnalyzeFn> =
+            unsafe { optional_symbol(&library, symbols::KQL_ANALYZE) };
+
+        let register_schema: Option<KqlRegisterSchemaFn> =
+            unsafe { optional_symbol(&library, symbols::KQL_REGISTER_SCHEMA) };
+
+        let release_schema: Option<KqlReleaseSchemaFn> =
+            unsafe { optional_symbol(&library, symbols::KQL_RELEASE_SCHEMA) };
+
+        let validate_with_schema_id: Option<KqlValidateWithSchemaIdFn> =
+            unsafe { optional_symbol(&library, symbols::KQL_VALIDATE_WITH_SCHEMA_ID) };
+
+        let get_completions_with_schema_id: Option<KqlGetCompletionsWithSchemaIdFn> =
+            unsafe { optional_symbol(&library, symbols::KQL_GET_COMPLETIONS_WITH_SCHEMA_ID) };
+
+        let get_semantic_classifications: Option<KqlGetSemanticClassificationsFn> =
+            unsafe { optional_symbol(&library, symbols::KQL_GET_SEMANTIC_CLASSIFICATIONS) };
+
+        let get_classifications_in_range: Option<KqlGetClassificationsInRangeFn> =
+            unsafe { optional_symbol(&library, symbols::KQL_GET_CLASSIFICATIONS_IN_RANGE) };
+
+        let classify_chunks: Option<KqlClassifyChunksFn> =
+            unsafe { optional_symbol(&library, symbols::KQL_CLASSIFY_CHUNKS) };
+
+        let get_definition: Option<KqlGetDefinitionFn> =
+            unsafe { optional_symbol(&library, symbols::KQL_GET_DEFINITION) };
+
+        let get_document_symbols: Option<KqlGetDocumentSymbolsFn> =
+            unsafe { optional_symbol(&library, symbols::KQL_GET_DOCUMENT_SYMBOLS) };
+
+        let get_expression_type: Option<KqlGetExpressionTypeFn> =
+            unsafe { optional_symbol(&library, symbols::KQL_GET_EXPRESSION_TYPE) };
+
+        let resolve_completion: Option<KqlResolveCompletionFn> =
+            unsafe { optional_symbol(&library, symbols::KQL_RESOLVE_COMPLETION) };
+
+        let get_bracket_pairs: Option<KqlGetBracketPairsFn> =
+            unsafe { optional_symbol(&library, symbols::KQL_GET_BRACKET_PAIRS) };
+
+        let get_enclosing_ranges: Option<KqlGetEnclosingRangesFn> =
+            unsafe { optional_symbol(&library, symbols::KQL_GET_ENCLOSING_RANGES) };
+
+        let get_syntax_tree: Option<KqlGetSyntaxTreeFn> =
+            unsafe { optional_symbol(&library, symbols::KQL_GET_SYNTAX_TREE) };
+
+        let get_inlay_hints: Option<KqlGetInlayHintsFn> =
+            unsafe { optional_symbol(&library, symbols::KQL_GET_INLAY_HINTS) };
+
+        let analyze_pipeline: Option<KqlAnalyzePipelineFn> =
+            unsafe { optional_symbol(&library, symbols::KQL_ANALYZE_PIPELINE) };
+
+        let analyze_joins: Option<KqlAnalyzeJoinsFn> =
+            unsafe { optional_symbol(&library, symbols::KQL_ANALYZE_JOINS) };
+
+        let get_analyzer_diagnostics: Option<KqlGetAnalyzerDiagnosticsFn> =
+            unsafe { optional_symbol(&library, symbols::KQL_GET_ANALYZER_DIAGNOSTICS) };
+
+        let document_open: Option<KqlDocumentOpenFn> =
+            unsafe { optional_symbol(&library, symbols::KQL_DOCUMENT_OPEN) };
+
+        let document_edit: Option<KqlDocumentEditFn> =
+            unsafe { optional_symbol(&library, symbols::KQL_DOCUMENT_EDIT) };
+
+        let document_get_diagnostics: Option<KqlDocumentGetDiagnosticsFn> =
+            unsafe { optional_symbol(&library, symbols::KQL_DOCUMENT_GET_DIAGNOSTICS) };
+
+        let document_get_completions: Option<KqlDocumentGetCompletionsFn> =
+            unsafe { optional_symbol(&library, symbols::KQL_DOCUMENT_GET_COMPLETIONS) };
+
+        let document_close: Option<KqlDocumentCloseFn> =
+            unsafe { optional_symbol(&library, symbols::KQL_DOCUMENT_CLOSE) };
+
+        let cancellation_create: Option<KqlCancellationCreateFn> =
+            unsafe { optional_symbol(&library, symbols::KQL_CANCELLATION_CREATE) };
+
+        let cancellation_cancel: Option<KqlCancellationCancelFn> =
+            unsafe { optional_symbol(&library, symbols::KQL_CANCELLATION_CANCEL) };
+
+        let cancellation_release: Option<KqlCancellationReleaseFn> =
+            unsafe { optional_symbol(&library, symbols::KQL_CANCELLATION_RELEASE) };
+
+        let get_last_required_size: Option<KqlGetLastRequiredSizeFn> =
+            unsafe { optional_symbol(&library, symbols::KQL_GET_LAST_REQUIRED_SIZE) };
+
+        let free_result: Option<KqlFreeResultFn> =
+            unsafe { optional_symbol(&library, symbols::KQL_FREE_RESULT) };
+
+        let read_result_chunk: Option<KqlReadResultChunkFn> =
+            unsafe { optional_symbol(&library, symbols::KQL_READ_RESULT_CHUNK) };
+
+        let set_result_format: Option<KqlSetResultFormatFn> =
+            unsafe { optional_symbol(&library, symbols::KQL_SET_RESULT_FORMAT) };
+
+        let get_abi_version: Option<KqlGetAbiVersionFn> =
+            unsafe { optional_symbol(&library, symbols::KQL_GET_ABI_VERSION) };
+
+        let get_capabilities: Option<KqlGetCapabilitiesFn> =
+            unsafe { optional_symbol(&library, symbols::KQL_GET_CAPABILITIES) };
+
+        let set_log_callback: Option<KqlSetLogCallbackFn> =
+            unsafe { optional_symbol(&library, symbols::KQL_SET_LOG_CALLBACK) };
+
+        let optional = [
+            (
+                symbols::KQL_VALIDATE_WITH_SCHEMA,
+                validate_with_schema.is_some(),
+            ),
+            (symbols::KQL_GET_COMPLETIONS, get_completions.is_some()),
+            (
+                symbols::KQL_GET_CLASSIFICATIONS,
+                get_classifications.is_some(),
+            ),
+            (
+                symbols::KQL_VALIDATE_WITH_OPTIONS,
+                validate_with_options.is_some(),
+            ),
+            (
+                symbols::KQL_GET_COMPLETIONS_WITH_OPTIONS,
+                get_completions_with_options.is_some(),
+            ),
+            (
+                symbols::KQL_GET_DOCUMENT_STRUCTURE,
+                get_document_structure.is_some(),
+            ),
+            (symbols::KQL_VALIDATE_BATCH, validate_batch.is_some()),
+            (symbols::KQL_FORMAT, format.is_some()),
+            (
+                symbols::KQL_INFER_RESULT_SCHEMA,
+                infer_result_schema.is_some(),
+            ),
+            (symbols::KQL_ANALYZE, analyze.is_some()),
+            (symbols::KQL_REGISTER_SCHEMA, register_schema.is_some()),
+            (symbols::KQL_RELEASE_SCHEMA, release_schema.is_some()),
+            (
+                symbols::KQL_VALIDATE_WITH_SCHEMA_ID,
+                validate_with_schema_id.is_some(),
+            ),
+            (
+                symbols::KQL_GET_COMPLETIONS_WITH_SCHEMA_ID,
+                get_completions_with_schema_id.is_some(),
+            ),
+            (
+                symbols::KQL_GET_SEMANTIC_CLASSIFICATIONS,
+                get_semantic_classifications.is_some(),
+            ),
+            (
+                symbols::KQL_GET_CLASSIFICATIONS_IN_RANGE,
+                get_classifications_in_range.is_some(),
+            ),
+            (symbols::KQL_CLASSIFY_CHUNKS, classify_chunks.is_some()),
+            (symbols::KQL_GET_DEFINITION, get_definition.is_some()),
+            (
+                symbols::KQL_GET_DOCUMENT_SYMBOLS,
+                get_document_symbols.is_some(),
+            ),
+            (
+                symbols::KQL_GET_EXPRESSION_TYPE,
+                get_expression_type.is_some(),
+            ),
+            (
+                symbols::KQL_RESOLVE_COMPLETION,
+                resolve_completion.is_some(),
+            ),
+            (symbols::KQL_GET_BRACKET_PAIRS, get_bracket_pairs.is_some()),
+            (
+                symbols::KQL_GET_ENCLOSING_RANGES,
+                get_enclosing_ranges.is_some(),
+            ),
+            (symbols::KQL_GET_SYNTAX_TREE, get_syntax_tree.is_some()),
+            (symbols::KQL_GET_INLAY_HINTS, get_inlay_hints.is_some()),
+            (symbols::KQL_ANALYZE_PIPELINE, analyze_pipeline.is_some()),
+            (symbols::KQL_ANALYZE_JOINS, analyze_joins.is_some()),
+            (
+                symbols::KQL_GET_ANALYZER_DIAGNOSTICS,
+                get_analyzer_diagnostics.is_some(),
+            ),
+            (symbols::KQL_DOCUMENT_OPEN, document_open.is_some()),
+            (symbols::KQL_DOCUMENT_EDIT, document_edit.is_some()),
+            (
+                symbols::KQL_DOCUMENT_GET_DIAGNOSTICS,
+                document_get_diagnostics.is_some(),
+            ),
+            (
+                symbols::KQL_DOCUMENT_GET_COMPLETIONS,
+                document_get_completions.is_some(),
+            ),
+            (symbols::KQL_DOCUMENT_CLOSE, document_close.is_some()),
+            (
+                symbols::KQL_CANCELLATION_CREATE,
+                cancellation_create.is_some(),
+            ),
+            (
+                symbols::KQL_CANCELLATION_CANCEL,
+                cancellation_cancel.is_some(),
+            ),
+            (
+                symbols::KQL_CANCELLATION_RELEASE,
+                cancellation_release.is_some(),
+            ),
+            (
+                symbols::KQL_GET_LAST_REQUIRED_SIZE,
+                get_last_required_size.is_some(),
+            ),
+            (symbols::KQL_FREE_RESULT, free_result.is_some()),
+            (symbols::KQL_READ_RESULT_CHUNK, read_result_chunk.is_some()),
+            (symbols::KQL_SET_RESULT_FORMAT, set_result_format.is_some()),
+            (symbols::KQL_GET_ABI_VERSION, get_abi_version.is_some()),
+            (symbols::KQL_GET_CAPABILITIES, get_capabilities.is_some()),
+            (symbols::KQL_SET_LOG_CALLBACK, set_log_callback.is_some()),
+        ];
+        let missing: Vec<&str> = optional
+            .iter()
+            .filter(|(_, loaded)| !loaded)
+            .map(|(name, _)| *name)
+            .collect();
+        if missing.is_empty() {
+            log::debug!("Loaded all {} optional symbols", optional.len());
+        } else {
+            log::debug!("Optional symbols not exported: {}", missing.join(", "));
+        }
 
         Ok(Self {
             library,
+            path: path.clone(),
+            key: instance_key(path),
             init,
             cleanup,
             validate_syntax,
@@ -275,6 +935,46 @@ impl LoadedLibrary {
             validate_with_schema,
             get_completions,
             get_classifications,
+            validate_with_options,
+            get_completions_with_options,
+            get_document_structure,
+            validate_batch,
+            format,
+            infer_result_schema,
+            analyze,
+            register_schema,
+            release_schema,
+            validate_with_schema_id,
+            get_completions_with_schema_id,
+            get_semantic_classifications,
+            get_classifications_in_range,
+            classify_chunks,
+            get_definition,
+            get_document_symbols,
+            get_expression_type,
+            resolve_completion,
+            get_bracket_pairs,
+            get_enclosing_ranges,
+            get_syntax_tree,
+            get_inlay_hints,
+            analyze_pipeline,
+            analyze_joins,
+            get_analyzer_diagnostics,
+            document_open,
+            document_edit,
+            document_get_diagnostics,
+            document_get_completions,
+            document_close,
+            cancellation_create,
+            cancellation_cancel,
+            cancellation_release,
+            get_last_required_size,
+            free_result,
+            read_result_chunk,
+            set_result_format,
+            get_abi_version,
+            get_capabilities,
+            set_log_callback,
         })
     }
 
@@ -292,6 +992,261 @@ impl LoadedLibrary {
     pub fn supports_classification(&self) -> bool {
         self.get_classifications.is_some()
     }
+
+    /// Check if validation options (dialects) are supported
+    pub fn supports_validation_options(&self) -> bool {
+        self.validate_with_options.is_some()
+    }
+
+    /// Check if completion options are applied natively
+    pub fn supports_completion_options(&self) -> bool {
+        self.get_completions_with_options.is_some()
+    }
+
+    /// Check if schema registration (and the schema-id call variants) is supported
+    pub fn supports_schema_registration(&self) -> bool {
+        self.register_schema.is_some()
+            && self.release_schema.is_some()
+            && self.validate_with_schema_id.is_some()
+            && self.get_completions_with_schema_id.is_some()
+    }
+
+    /// Release a registered schema
+    ///
+    /// Returns the native return code, or `None` if the library cannot
+    /// release schemas.
+    pub fn release_schema_id(&self, schema_id: c_int) -> Option<c_int> {
+        // SAFETY: Releasing takes only an integer id; unknown ids are reported
+        // as an error code rather than touching memory.
+        self.release_schema
+            .map(|release_fn| unsafe { release_fn(schema_id) })
+    }
+
+    /// Get the path the library was loaded from
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Get the ABI version the library reports, if it exports one
+    pub fn abi_version(&self) -> Option<c_int> {
+        // SAFETY: The call takes no arguments and returns a constant.
+        self.get_abi_version
+            .map(|version_fn| unsafe { version_fn() })
+    }
+
+    /// Get the capability names the library reports
+    ///
+    /// Empty if the library does not export `kql_get_capabilities`.
+    pub fn capabilities(&self) -> Vec<String> {
+        self.get_capabilities
+            .map(read_capabilities)
+            .unwrap_or_default()
+    }
+
+    /// Check if CBOR call results are supported
+    pub fn supports_binary_results(&self) -> bool {
+        self.set_result_format.is_some()
+    }
+
+    /// Switch call results on this thread to a format until the guard drops
+    ///
+    /// Returns `None` if the library does not support the format.
+    #[cfg(feature = "cbor")]
+    pub fn use_result_format(&self, format: c_int) -> Option<ResultFormatGuard> {
+        let set_fn = self.set_result_format?;
+        // SAFETY: The call takes an integer and sets thread-local state.
+        let code = unsafe { set_fn(format) };
+        (code == 0).then_some(ResultFormatGuard { set_fn })
+    }
+
+    /// Check if incremental document sessions are supported
+    pub fn supports_documents(&self) -> bool {
+        self.document_open.is_some()
+            && self.document_edit.is_some()
+            && self.document_get_diagnostics.is_some()
+            && self.document_get_completions.is_some()
+            && self.document_close.is_some()
+    }
+
+    /// Close an open document
+    ///
+    /// Returns the native return code, or `None` if the library cannot
+    /// close documents.
+    pub fn close_document_id(&self, document_id: c_int) -> Option<c_int> {
+        // SAFETY: Closing takes only an integer id; unknown ids are reported
+        // as an error code rather than touching memory.
+        self.document_close
+            .map(|close_fn| unsafe { close_fn(document_id) })
+    }
+
+    /// Check if native cancellation is supported
+    pub fn supports_cancellation(&self) -> bool {
+        self.cancellation_create.is_some()
+            && self.cancellation_cancel.is_some()
+            && self.cancellation_release.is_some()
+    }
+
+    /// Create a native cancellation source
+    ///
+    /// Returns `None` if the library cannot cancel calls or creation failed.
+    pub fn create_cancellation(&self) -> Option<c_int> {
+        let create_fn = self
+            .cancellation_create
+            .filter(|_| self.supports_cancellation())?;
+        // SAFETY: Creating a source takes no arguments and returns an id or
+        // an error code.
+        let id = unsafe { create_fn() };
+        return_codes::is_success(id).then_some(id)
+    }
+
+    /// Cancel a native cancellation source, returning the native return code
+    pub fn cancel_cancellation(&self, cancellation_id: c_int) -> Option<c_int> {
+        // SAFETY: Cancelling takes only an integer id; unknown ids are
+        // reported as an error code rather than touching memory.
+        self.cancellation_cancel
+            .map(|cancel_fn| unsafe { cancel_fn(cancellation_id) })
+    }
+
+    /// Release a native cancellation source, returning the native return code
+    pub fn release_cancellation(&self, cancellation_id: c_int) -> Option<c_int> {
+        // SAFETY: Releasing takes only an integer id; unknown ids are
+        // reported as an error code rather than touching memory.
+        self.cancellation_release
+            .map(|release_fn| unsafe { release_fn(cancellation_id) })
+    }
+
+    /// Get the exact output size the last too-small call on this thread needed
+    ///
+    /// Returns `None` if the library does not report sizes.
+    pub fn last_required_size(&self) -> Option<usize> {
+        // SAFETY: The call takes no arguments and reads thread-local state.
+        let size = unsafe { (self.get_last_required_size?)() };
+        usize::try_from(size).ok().filter(|&size| size > 0)
+    }
+
+    /// Check if results too large for the output buffer can be read in chunks
+    pub fn supports_result_chunks(&self) -> bool {
+        self.read_result_chunk.is_some()
+    }
+
+    /// Check if results can be allocated by the native library
+    pub fn supports_native_results(&self) -> bool {
+        self.free_result.is_some()
+    }
+
+    /// Call a function with an output buffer in native-allocated mode
+    ///
+    /// `ffi_call` receives the output pointer and length to pass on. Returns
+    /// the native return code and the result, which for a negative code
+    /// holds the call's error envelope, if it wrote one.
+    pub fn call_allocated(
+        self: &Arc<Self>,
+        mut ffi_call: impl FnMut(*mut u8, c_int) -> c_int,
+    ) -> (c_int, NativeResult) {
+        let mut data: *mut u8 = ptr::null_mut();
+        let code = ffi_call(ptr::addr_of_mut!(data).cast(), ALLOCATE_RESULT);
+        let len = match usize::try_from(code) {
+            Ok(len) => len,
+            // A failed call may allocate a NUL-terminated error envelope
+            Err(_) if !data.is_null() => {
+                // SAFETY: The native library wrote a pointer to a
+                // NUL-terminated allocation.
+                unsafe { CStr::from_ptr(data.cast()) }.to_bytes().len()
+            }
+            Err(_) => 0,
+        };
+        let result = NativeResult {
+            lib: Arc::clone(self),
+            data,
+            len,
+        };
+        (code, result)
+    }
+
+    /// Check if best-practice analysis is supported
+    pub fn supports_analyzers(&self) -> bool {
+        self.get_analyzer_diagnostics.is_some()
+    }
+
+    /// Check if expression type inference is supported
+    pub fn supports_expression_types(&self) -> bool {
+        self.get_expression_type.is_some()
+    }
+
+    /// Check if completion items can be resolved
+    pub fn supports_completion_resolve(&self) -> bool {
+        self.resolve_completion.is_some()
+    }
+
+    /// Check if bracket matching and enclosing ranges are supported
+    pub fn supports_selection_ranges(&self) -> bool {
+        self.get_bracket_pairs.is_some() && self.get_enclosing_ranges.is_some()
+    }
+
+    /// Check if syntax tree access is supported
+    pub fn supports_syntax_tree(&self) -> bool {
+        self.get_syntax_tree.is_some()
+    }
+
+    /// Check if inlay hints are supported
+    pub fn supports_inlay_hints(&self) -> bool {
+        self.get_inlay_hints.is_some()
+    }
+
+    /// Check if pipeline stage decomposition is supported
+    pub fn supports_pipeline_analysis(&self) -> bool {
+        self.analyze_pipeline.is_some()
+    }
+
+    /// Check if join and union analysis is supported
+    pub fn supports_join_analysis(&self) -> bool {
+        self.analyze_joins.is_some()
+    }
+
+    /// Check if the document outline is supported
+    pub fn supports_document_symbols(&self) -> bool {
+        self.get_document_symbols.is_some()
+    }
+
+    /// Check if go-to-definition is supported
+    pub fn supports_definitions(&self) -> bool {
+        self.get_definition.is_some()
+    }
+
+    /// Check if schema-aware classification is supported
+    pub fn supports_semantic_classification(&self) -> bool {
+        self.get_semantic_classifications.is_some()
+    }
+
+    /// Check if classifications can be requested for a range of the query
+    pub fn supports_classification_ranges(&self) -> bool {
+        self.get_classifications_in_range.is_some()
+    }
+
+    /// Check if referenced-entity analysis is supported
+    pub fn supports_analysis(&self) -> bool {
+        self.analyze.is_some()
+    }
+
+    /// Check if result-schema inference is supported
+    pub fn supports_result_schema(&self) -> bool {
+        self.infer_result_schema.is_some()
+    }
+
+    /// Check if formatting is supported
+    pub fn supports_formatting(&self) -> bool {
+        self.format.is_some()
+    }
+
+    /// Check if batch validation is supported natively
+    pub fn supports_batch_validation(&self) -> bool {
+        self.validate_batch.is_some()
+    }
+
+    /// Check if document structure is supported
+    pub fn supports_document_structure(&self) -> bool {
+        self.get_document_structure.is_some()
+    }
 }
 
 impl Drop for LoadedLibrary {
@@ -322,7 +1277,7 @@ fn ensure_dotnet_root() {
 }
 
 /// Try to find the .NET runtime root directory
-fn find_dotnet_root() -> Option<PathBuf> {
+pub(crate) fn find_dotnet_root() -> Option<PathBuf> {
     // Common locations to check
     let candidates = [
         // Homebrew on Apple Silicon
@@ -397,51 +1352,249 @@ fn find_dotnet_root() -> Option<PathBuf> {
     None
 }
 
-/// Load the library (or get cached instance)
-pub fn load_library() -> Result<&'static LoadedLibrary, Error> {
-    LIBRARY.get_or_try_init(|| {
-        // Ensure DOTNET_ROOT is set for DNNE libraries
-        ensure_dotnet_root();
-
-        let path = find_library_path().ok_or_else(|| Error::LibraryNotFound {
-            searched_paths: searched_paths(),
-        })?;
-
-        let lib = LoadedLibrary::load_from(&path)?;
-
-        // Initialize the library
-        let result = unsafe { (lib.init)() };
-        if result != 0 {
-            // Get error message
-            let mut error_buf = vec![0u8; 1024];
-            #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
-            let error_len =
-                unsafe { (lib.get_last_error)(error_buf.as_mut_ptr(), error_buf.len() as i32) };
-            let message = if error_len > 0 {
-                #[allow(clippy::cast_sign_loss)]
-                let len = error_len as usize;
-                String::from_utf8_lossy(&error_buf[..len]).to_string()
-            } else {
-                format!("Initialization returned error code: {result}")
-            };
-            return Err(Error::InitializationFailed { message });
-        }
+/// Load the library (or get the current instance)
+pub fn load_library() -> Result<Arc<LoadedLibrary>, Error> {
+    let mut current = LIBRARY.lock().unwrap_or_else(PoisonError::into_inner);
+    if let Some(lib) = current.as_ref() {
+        return Ok(Arc::clone(lib));
+    }
 
-        log::info!("KQL language library initialized successfully");
-        Ok(lib)
-    })
+    // Ensure DOTNET_ROOT is set for DNNE libraries
+    ensure_dotnet_root();
+
+    let path = find_library_path().ok_or_else(|| Error::LibraryNotFound {
+        searched_paths: searched_paths(),
+    })?;
+
+    let lib = instance(&path)?;
+    *current = Some(Arc::clone(&lib));
+    Ok(lib)
+}
+
+/// Load a library instance from `path`, independent of the process-wide
+/// library
+///
+/// `path` is the library file or a directory containing it. The instance
+/// is not affected by [`set_library_path`] or
+/// [`unload_library`](crate::unload_library), and is unloaded when the last
+/// validator using it is dropped. Loading a path that is already loaded, by
+/// either means, returns the existing instance.
+///
+/// Libraries from different paths can be loaded side by side, but share
+/// one .NET runtime, so they must target the same runtime version.
+pub fn load_library_from(path: &Path) -> Result<Arc<LoadedLibrary>, Error> {
+    let lib_path = library_in(path).ok_or_else(|| Error::LibraryNotFound {
+        searched_paths: vec![path.to_path_buf(), path.join(LIB_NAME)],
+    })?;
+
+    // Ensure DOTNET_ROOT is set for DNNE libraries
+    ensure_dotnet_root();
+
+    instance(&lib_path)
+}
+
+/// Get the live instance loaded from `path`, or load and register one
+fn instance(path: &PathBuf) -> Result<Arc<LoadedLibrary>, Error> {
+    let key = instance_key(path);
+    let mut instances = INSTANCES.lock().unwrap_or_else(PoisonError::into_inner);
+    instances.retain(|lib| lib.strong_count() > 0);
+    if let Some(lib) = instances
+        .iter()
+        .filter_map(Weak::upgrade)
+        .find(|lib| lib.key == key)
+    {
+        log::debug!("Reusing library already loaded from {}", path.display());
+        return Ok(lib);
+    }
+
+    let lib = Arc::new(initialize(path)?);
+    instances.push(Arc::downgrade(&lib));
+    Ok(lib)
+}
+
+/// Check that the platform loader accepts the library at `path`
+///
+/// The library is opened and closed again without calling into it. Returns
+/// the loader's error text on failure.
+pub(crate) fn probe(path: &Path) -> Result<(), String> {
+    // SAFETY: As in LoadedLibrary::load_from. No symbols are looked up or
+    // called, and DNNE starts the .NET runtime only on the first export call.
+    unsafe { Library::new(path) }
+        .map(drop)
+        .map_err(|e| e.to_string())
+}
+
+/// Identify a library by its canonical path, so that links to one file
+/// resolve to one instance
+fn instance_key(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// Load and initialize the library at `path`
+fn initialize(path: &PathBuf) -> Result<LoadedLibrary, Error> {
+    let lib = LoadedLibrary::load_from(path)?;
+    check_abi_version(lib.abi_version())?;
+
+    if let Some(set_log_callback) = lib.set_log_callback {
+        // SAFETY: forward_native_log matches the callback signature and
+        // lives for the whole program.
+        unsafe { set_log_callback(forward_native_log) };
+    }
+
+    // Initialize the library
+    let result = unsafe { (lib.init)() };
+    if result != 0 {
+        // Get error message
+        let mut error_buf = vec![0u8; 1024];
+        #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+        let error_len =
+            unsafe { (lib.get_last_error)(error_buf.as_mut_ptr(), error_buf.len() as i32) };
+        let message = if error_len > 0 {
+            #[allow(clippy::cast_sign_loss)]
+            let len = error_len as usize;
+            String::from_utf8_lossy(&error_buf[..len]).to_string()
+        } else {
+            format!("Initialization returned error code: {result}")
+        };
+        return Err(Error::InitializationFailed { message });
+    }
+
+    log::info!("KQL language library initialized successfully");
+    Ok(lib)
+}
+
+/// Unload the current library, so the next validator loads it afresh
+///
+/// The library is searched for again on next use, so a rebuilt library, or
+/// one at a new [`set_library_path`] location, is picked up without
+/// restarting the process. Validators, schema handles and results created
+/// before the call keep the old library alive until they are dropped; it is
+/// cleaned up and unloaded then, and reloading from the same path before
+/// that reuses it. Returns `false` if no library was loaded.
+///
+/// The .NET runtime itself stays resident in the process once started.
+pub fn unload() -> bool {
+    let lib = LIBRARY
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .take();
+    if let Some(lib) = &lib {
+        log::info!(
+            "Unloading KQL language library from {} ({} other users)",
+            lib.path().display(),
+            Arc::strong_count(lib) - 1
+        );
+    }
+    lib.is_some()
+}
+
+/// Receives log messages from the native library
+extern "C" fn forward_native_log(level: c_int, message: *const u8, len: c_int) {
+    let Ok(len) = usize::try_from(len) else {
+        return;
+    };
+    if message.is_null() {
+        return;
+    }
+    // SAFETY: The native library passes `len` bytes that stay valid for
+    // the duration of the call.
+    let bytes = unsafe { std::slice::from_raw_parts(message, len) };
+    let message = String::from_utf8_lossy(bytes);
+    // A panicking handler must not unwind into the native library
+    let _ = std::panic::catch_unwind(|| {
+        native_log::dispatch(native_log::level_from_native(level), &message);
+    });
+}
+
+/// Fail if a library reporting its ABI version reports a different one
+///
+/// Libraries predating `kql_get_abi_version` are accepted; their missing
+/// exports are handled as unsupported features.
+fn check_abi_version(found: Option<c_int>) -> Result<(), Error> {
+    match found {
+        Some(found) if found != ABI_VERSION => Err(Error::AbiMismatch {
+            expected: ABI_VERSION,
+            found,
+        }),
+        Some(_) => Ok(()),
+        None => {
+            log::debug!("Native library does not report an ABI version");
+            Ok(())
+        }
+    }
 }
 
 /// Check if the library is loaded
-#[allow(dead_code)]
 pub fn is_loaded() -> bool {
-    LIBRARY.get().is_some()
+    LIBRARY
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .is_some()
+}
+
+/// Call `kql_get_capabilities`, growing the buffer while it is too small
+fn read_capabilities(capabilities_fn: KqlGetCapabilitiesFn) -> Vec<String> {
+    let mut buffer = vec![0u8; DEFAULT_BUFFER_SIZE];
+    loop {
+        // SAFETY: buffer is a valid mutable slice we own and its length fits
+        // in c_int.
+        #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+        let code = unsafe { capabilities_fn(buffer.as_mut_ptr(), buffer.len() as c_int) };
+        if return_codes::is_buffer_too_small(code) && buffer.len() < MAX_BUFFER_SIZE {
+            buffer.resize(buffer.len() * 2, 0);
+            continue;
+        }
+        let Some(output) = usize::try_from(code).ok().and_then(|len| buffer.get(..len)) else {
+            log::warn!("kql_get_capabilities failed with code {code}");
+            return Vec::new();
+        };
+        return serde_json::from_slice(output).unwrap_or_else(|err| {
+            log::warn!("Invalid capabilities from native library: {err}");
+            Vec::new()
+        });
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_check_abi_version() {
+        assert!(check_abi_version(None).is_ok());
+        assert!(check_abi_version(Some(ABI_VERSION)).is_ok());
+        assert!(matches!(
+            check_abi_version(Some(ABI_VERSION + 1)),
+            Err(Error::AbiMismatch { expected, found })
+                if expected == ABI_VERSION && found == ABI_VERSION + 1
+        ));
+    }
+
+    #[test]
+    fn test_read_capabilities() {
+        /// Reports more capabilities than the default buffer holds
+        unsafe extern "C" fn many(output: *mut u8, output_max_len: c_int) -> c_int {
+            let names: Vec<_> = (0..10_000).map(|i| format!("feature_{i}")).collect();
+            let json = serde_json::to_vec(&names).unwrap();
+            let len = c_int::try_from(json.len()).unwrap();
+            if len > output_max_len {
+                return return_codes::BUFFER_TOO_SMALL;
+            }
+            std::ptr::copy_nonoverlapping(json.as_ptr(), output, json.len());
+            len
+        }
+
+        /// Reports a length past the end of the buffer
+        unsafe extern "C" fn overlong(_output: *mut u8, output_max_len: c_int) -> c_int {
+            output_max_len + 1
+        }
+
+        let capabilities = read_capabilities(many);
+        assert_eq!(capabilities.len(), 10_000);
+        assert_eq!(capabilities[9_999], "feature_9999");
+        assert!(read_capabilities(overlong).is_empty());
+    }
+
     #[test]
     fn test_current_rid() {
         let rid = current_rid();
@@ -459,4 +1612,30 @@ mod tests {
         let paths = searched_paths();
         assert!(!paths.is_empty());
     }
+
+    #[test]
+    fn test_cargo_search_dirs() {
+        // Unit tests run from target/{profile}/deps
+        let exe_path = std::env::current_exe().unwrap();
+        let profile_dir = exe_path.parent().and_then(Path::parent).unwrap();
+        assert_eq!(target_profile_dir().as_deref(), Some(profile_dir));
+
+        let native_dir = workspace_native_dir().unwrap();
+        assert!(native_dir.ends_with(Path::new("native").join(current_rid())));
+        assert!(profile_dir.starts_with(native_dir.parent().unwrap().parent().unwrap()));
+    }
+
+    #[test]
+    fn test_configured_library_path() {
+        let dir = std::env::temp_dir().join(format!("kql-configured-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join(LIB_NAME), []).unwrap();
+
+        set_library_path(&dir).unwrap();
+        assert_eq!(find_library_path(), Some(dir.join(LIB_NAME)));
+        assert_eq!(searched_paths()[0], dir.join(LIB_NAME));
+
+        *CONFIGURED_PATH.write().unwrap() = None;
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
 }
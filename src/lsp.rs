@@ -0,0 +1,687 @@
+//! KQL language server
+//!
+//! Enabled by the `lsp` feature. [`KqlLanguageServer`] implements the
+//! Language Server Protocol on top of [`tower_lsp`]:
+//!
+//! - diagnostics published on open and change (full document sync)
+//! - completion, with documentation filled in on `completionItem/resolve`
+//! - hover, showing the inferred type of the expression under the cursor
+//! - semantic tokens, using the [`semantic_tokens`] legend
+//! - selection ranges, expanding from a token to the whole document
+//! - inlay hints for `let` types, parameter names and column counts
+//!
+//! Names are resolved against a schema file (a JSON-serialized
+//! [`Schema`]) configured through initialization options or
+//! `workspace/didChangeConfiguration` settings, either at the top level or
+//! under a `kql` section:
+//!
+//! ```json
+//! { "kql": { "schemaFile": "/path/to/schema.json" } }
+//! ```
+//!
+//! The `kql-lsp` binary runs [`serve_stdio`]:
+//!
+//! ```text
+//! cargo install kql-language-tools --features lsp --bin kql-lsp
+//! ```
+
+use crate::classification::ClassificationResult;
+use crate::completion::{CompletionItem as KqlCompletionItem, CompletionKind, TRIGGER_CHARACTERS};
+use crate::error::Error;
+use crate::expression::TypeKind;
+use crate::inlay::{InlayHint as KqlInlayHint, InlayHintKind as KqlInlayHintKind};
+use crate::positions::{LineCol, LineIndex};
+use crate::schema::Schema;
+use crate::semantic_tokens;
+use crate::types::{Diagnostic as KqlDiagnostic, DiagnosticSeverity as KqlSeverity};
+use crate::validator::KqlValidator;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{PoisonError, RwLock};
+use tower_lsp::jsonrpc;
+use tower_lsp::lsp_types::{
+    CompletionItem, CompletionItemKind, CompletionOptions, CompletionParams, CompletionResponse,
+    CompletionTextEdit, Diagnostic, DiagnosticRelatedInformation, DiagnosticSeverity,
+    DidChangeConfigurationParams, DidChangeTextDocumentParams, DidCloseTextDocumentParams,
+    DidOpenTextDocumentParams, Documentation, Hover, HoverContents, HoverParams,
+    HoverProviderCapability, InitializeParams, InitializeResult, InitializedParams, InlayHint,
+    InlayHintKind, InlayHintLabel, InlayHintParams, Location, MarkupContent, MarkupKind,
+    MessageType, NumberOrString, OneOf, Position, Range, SelectionRange, SelectionRangeParams,
+    SelectionRangeProviderCapability, SemanticToken, SemanticTokenModifier, SemanticTokenType,
+    SemanticTokens, SemanticTokensFullOptions, SemanticTokensLegend, SemanticTokensOptions,
+    SemanticTokensParams, SemanticTokensResult, SemanticTokensServerCapabilities,
+    ServerCapabilities, ServerInfo, TextDocumentSyncCapability, TextDocumentSyncKind, TextEdit,
+    Url,
+};
+use tower_lsp::{Client, LanguageServer, LspService, Server};
+
+/// A KQL language server
+///
+/// Create one per client with [`LspService::new`], or run the whole server
+/// over stdin/stdout with [`serve_stdio`].
+pub struct KqlLanguageServer {
+    client: Client,
+    validator: KqlValidator,
+    documents: RwLock<HashMap<Url, String>>,
+    schema: RwLock<Option<Schema>>,
+}
+
+impl KqlLanguageServer {
+    /// Create a server for a client, using `validator` for every request
+    #[must_use]
+    pub fn new(client: Client, validator: KqlValidator) -> Self {
+        Self {
+            client,
+            validator,
+            documents: RwLock::new(HashMap::new()),
+            schema: RwLock::new(None),
+        }
+    }
+
+    /// Apply `schemaFile` from initialization options or configuration settings
+    async fn configure(&self, settings: &serde_json::Value) {
+        let settings = settings.get("kql").unwrap_or(settings);
+        let Some(path) = settings.get("schemaFile").and_then(|v| v.as_str()) else {
+            return;
+        };
+
+        match load_schema(Path::new(path)) {
+            Ok(schema) => {
+                *self.schema.write().unwrap_or_else(PoisonError::into_inner) = Some(schema);
+            }
+            Err(e) => {
+                self.client
+                    .show_message(
+                        MessageType::ERROR,
+                        format!("Failed to load schema file {path}: {e}"),
+                    )
+                    .await;
+            }
+        }
+    }
+
+    /// Validate a document and publish its diagnostics
+    async fn publish_diagnostics(&self, uri: Url) {
+        let Some(text) = self.document(&uri) else {
+            return;
+        };
+
+        let result = match self
+            .schema
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .as_ref()
+        {
+            Some(schema) => self.validator.validate_with_schema(&text, schema),
+            None => self.validator.validate_syntax(&text),
+        };
+
+        match result {
+            Ok(result) => {
+                let index = LineIndex::new(text.as_str());
+                let diagnostics = result
+                    .diagnostics
+                    .iter()
+                    .map(|d| to_lsp_diagnostic(&index, &uri, d))
+                    .collect();
+                self.client
+                    .publish_diagnostics(uri, diagnostics, None)
+                    .await;
+            }
+            Err(e) => {
+                self.client
+                    .log_message(MessageType::ERROR, format!("Validation failed: {e}"))
+                    .await;
+            }
+        }
+    }
+
+    fn document(&self, uri: &Url) -> Option<String> {
+        self.documents
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(uri)
+            .cloned()
+    }
+}
+
+#[tower_lsp::async_trait]
+impl LanguageServer for KqlLanguageServer {
+    async fn initialize(&self, params: InitializeParams) -> jsonrpc::Result<InitializeResult> {
+        if let Some(options) = &params.initialization_options {
+            self.configure(options).await;
+        }
+
+        let legend = semantic_tokens::legend();
+        Ok(InitializeResult {
+            capabilities: ServerCapabilities {
+                text_document_sync: Some(TextDocumentSyncCapability::Kind(
+                    TextDocumentSyncKind::FULL,
+                )),
+                completion_provider: self.validator.supports_completion().then(|| {
+                    CompletionOptions {
+                        resolve_provider: Some(self.validator.supports_completion_resolve()),
+                        trigger_characters: Some(
+                            TRIGGER_CHARACTERS.iter().map(ToString::to_string).collect(),
+                        ),
+                        ..CompletionOptions::default()
+                    }
+                }),
+                hover_provider: self
+                    .validator
+                    .supports_expression_types()
+                    .then_some(HoverProviderCapability::Simple(true)),
+                semantic_tokens_provider: self.validator.supports_classification().then(|| {
+                    SemanticTokensServerCapabilities::SemanticTokensOptions(SemanticTokensOptions {
+                        legend: SemanticTokensLegend {
+                            token_types: legend
+                                .token_types
+                                .iter()
+                                .map(|t| SemanticTokenType::new(t))
+                                .collect(),
+                            token_modifiers: legend
+                                .token_modifiers
+                                .iter()
+                                .map(|m| SemanticTokenModifier::new(m))
+                                .collect(),
+                        },
+                        full: Some(SemanticTokensFullOptions::Bool(true)),
+                        ..SemanticTokensOptions::default()
+                    })
+                }),
+                selection_range_provider: self
+                    .validator
+                    .supports_selection_ranges()
+                    .then_some(SelectionRangeProviderCapability::Simple(true)),
+                inlay_hint_provider: self
+                    .validator
+                    .supports_inlay_hints()
+                    .then_some(OneOf::Left(true)),
+                ..ServerCapabilities::default()
+            },
+            server_info: Some(ServerInfo {
+                name: "kql-lsp".to_string(),
+                version: Some(env!("CARGO_PKG_VERSION").to_string()),
+            }),
+        })
+    }
+
+    async fn initialized(&self, _: InitializedParams) {
+        self.client
+            .log_message(MessageType::INFO, "KQL language server initialized")
+            .await;
+    }
+
+    async fn shutdown(&self) -> jsonrpc::Result<()> {
+        Ok(())
+    }
+
+    async fn did_open(&self, params: DidOpenTextDocumentParams) {
+        let uri = params.text_document.uri;
+        self.documents
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(uri.clone(), params.text_document.text);
+        self.publish_diagnostics(uri).await;
+    }
+
+    async fn did_change(&self, params: DidChangeTextDocumentParams) {
+        // Full sync: the last change holds the whole document
+        let Some(change) = params.content_changes.into_iter().last() else {
+            return;
+        };
+        let uri = params.text_document.uri;
+        self.documents
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(uri.clone(), change.text);
+        self.publish_diagnostics(uri).await;
+    }
+
+    async fn did_close(&self, params: DidCloseTextDocumentParams) {
+        let uri = params.text_document.uri;
+        self.documents
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(&uri);
+        self.client.publish_diagnostics(uri, Vec::new(), None).await;
+    }
+
+    async fn did_change_configuration(&self, params: DidChangeConfigurationParams) {
+        self.configure(&params.settings).await;
+
+        let uris: Vec<Url> = self
+            .documents
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .keys()
+            .cloned()
+            .collect();
+        for uri in uris {
+            self.publish_diagnostics(uri).await;
+        }
+    }
+
+    async fn completion(
+        &self,
+        params: CompletionParams,
+    ) -> jsonrpc::Result<Option<CompletionResponse>> {
+        let position = params.text_document_position;
+        let Some(text) = self.document(&position.text_document.uri) else {
+            return Ok(None);
+        };
+        let index = LineIndex::new(text.as_str());
+        let cursor = offset(&index, position.position);
+
+        let schema = self
+            .schema
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone();
+        let result = self
+            .validator
+            .get_completions(&text, cursor, schema.as_ref())
+            .map_err(|e| internal_error(&e))?;
+
+        // Replace the whole word around the cursor when the library reports it
+        let replace = result
+            .context
+            .map(|context| range(&index, context.edit_start, context.edit_end));
+        Ok(Some(CompletionResponse::Array(
+            result
+                .items
+                .iter()
+                .map(|item| to_lsp_completion(item, replace))
+                .collect(),
+        )))
+    }
+
+    async fn completion_resolve(&self, item: CompletionItem) -> jsonrpc::Result<CompletionItem> {
+        // The crate's item travels in `data`; items without it are returned as-is
+        let Some(kql_item) = item
+            .data
+            .clone()
+            .and_then(|data| serde_json::from_value::<KqlCompletionItem>(data).ok())
+        else {
+            return Ok(item);
+        };
+
+        let schema = self
+            .schema
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone();
+        let resolved = self
+            .validator
+            .resolve_completion(&kql_item, schema.as_ref())
+            .map_err(|e| internal_error(&e))?;
+
+        Ok(CompletionItem {
+            detail: resolved.detail.or(item.detail),
+            documentation: resolved.documentation.map(|value| {
+                Documentation::MarkupContent(MarkupContent {
+                    kind: MarkupKind::Markdown,
+                    value,
+                })
+            }),
+            ..item
+        })
+    }
+
+    async fn hover(&self, params: HoverParams) -> jsonrpc::Result<Option<Hover>> {
+        let position = params.text_document_position_params;
+        let Some(text) = self.document(&position.text_document.uri) else {
+            return Ok(None);
+        };
+        let index = LineIndex::new(text.as_str());
+
+        let schema = self
+            .schema
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+            .unwrap_or_default();
+        let Some(expression) = self
+            .validator
+            .get_expression_type(&text, offset(&index, position.position), &schema)
+            .map_err(|e| internal_error(&e))?
+        else {
+            return Ok(None);
+        };
+
+        let value = match &expression.kind {
+            TypeKind::Scalar { name } => format!("```kql\n{name}\n```"),
+            TypeKind::Tabular { columns } => {
+                let columns: Vec<String> = columns
+                    .iter()
+                    .map(|c| format!("{}: {}", c.name, c.data_type))
+                    .collect();
+                format!("```kql\n({})\n```", columns.join(", "))
+            }
+            TypeKind::Other => return Ok(None),
+        };
+
+        Ok(Some(Hover {
+            contents: HoverContents::Markup(MarkupContent {
+                kind: MarkupKind::Markdown,
+                value,
+            }),
+            range: Some(range(&index, expression.start, expression.end)),
+        }))
+    }
+
+    async fn semantic_tokens_full(
+        &self,
+        params: SemanticTokensParams,
+    ) -> jsonrpc::Result<Option<SemanticTokensResult>> {
+        let Some(text) = self.document(&params.text_document.uri) else {
+            return Ok(None);
+        };
+
+        let schema = self
+            .schema
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone();
+        let classifications = match schema {
+            Some(schema) if self.validator.supports_semantic_classification() => {
+                self.validator.get_semantic_classifications(&text, &schema)
+            }
+            _ => self.validator.get_classifications(&text),
+        }
+        .map_err(|e| internal_error(&e))?;
+
+        Ok(Some(SemanticTokensResult::Tokens(SemanticTokens {
+            result_id: None,
+            data: encode_tokens(&text, &classifications),
+        })))
+    }
+
+    async fn selection_range(
+        &self,
+        params: SelectionRangeParams,
+    ) -> jsonrpc::Result<Option<Vec<SelectionRange>>> {
+        let Some(text) = self.document(&params.text_document.uri) else {
+            return Ok(None);
+        };
+        let index = LineIndex::new(text.as_str());
+
+        let mut selections = Vec::with_capacity(params.positions.len());
+        for position in params.positions {
+            let ranges = self
+                .validator
+                .get_enclosing_ranges(&text, offset(&index, position))
+                .map_err(|e| internal_error(&e))?;
+            // Ranges come innermost first; each one's parent is the next
+            let selection = ranges.iter().rev().fold(None, |parent, r| {
+                Some(SelectionRange {
+                    range: range(&index, r.start, r.end),
+                    parent: parent.map(Box::new),
+                })
+            });
+            selections.push(selection.unwrap_or(SelectionRange {
+                range: Range::new(position, position),
+                parent: None,
+            }));
+        }
+        Ok(Some(selections))
+    }
+
+    async fn inlay_hint(&self, params: InlayHintParams) -> jsonrpc::Result<Option<Vec<InlayHint>>> {
+        let Some(text) = self.document(&params.text_document.uri) else {
+            return Ok(None);
+        };
+        let index = LineIndex::new(text.as_str());
+
+        let schema = self
+            .schema
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+            .unwrap_or_default();
+        let range = offset(&index, params.range.start)..offset(&index, params.range.end);
+        let hints = self
+            .validator
+            .get_inlay_hints(&text, &schema, range)
+            .map_err(|e| internal_error(&e))?;
+        Ok(Some(
+            hints
+                .iter()
+                .map(|hint| to_lsp_inlay_hint(&index, hint))
+                .collect(),
+        ))
+    }
+}
+
+/// Run a language server over stdin/stdout until the client exits
+///
+/// # Errors
+///
+/// Returns an error if the native library cannot be loaded.
+pub async fn serve_stdio() -> Result<(), Error> {
+    let validator = KqlValidator::new()?;
+    let (service, socket) = LspService::new(|client| KqlLanguageServer::new(client, validator));
+    Server::new(tokio::io::stdin(), tokio::io::stdout(), socket)
+        .serve(service)
+        .await;
+    Ok(())
+}
+
+fn load_schema(path: &Path) -> Result<Schema, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+    serde_json::from_str(&text).map_err(|e| e.to_string())
+}
+
+fn internal_error(error: &Error) -> jsonrpc::Error {
+    jsonrpc::Error {
+        code: jsonrpc::ErrorCode::InternalError,
+        message: error.to_string().into(),
+        data: None,
+    }
+}
+
+fn to_lsp_diagnostic(index: &LineIndex, uri: &Url, diagnostic: &KqlDiagnostic) -> Diagnostic {
+    let related: Vec<DiagnosticRelatedInformation> = diagnostic
+        .related_spans
+        .iter()
+        .map(|related| DiagnosticRelatedInformation {
+            location: Location::new(uri.clone(), range(index, related.start, related.end)),
+            message: related.message.clone(),
+        })
+        .collect();
+
+    Diagnostic {
+        range: range(index, diagnostic.start, diagnostic.end),
+        severity: Some(match diagnostic.severity {
+            KqlSeverity::Error => DiagnosticSeverity::ERROR,
+            KqlSeverity::Warning => DiagnosticSeverity::WARNING,
+            KqlSeverity::Information => DiagnosticSeverity::INFORMATION,
+            KqlSeverity::Hint => DiagnosticSeverity::HINT,
+        }),
+        code: diagnostic.code.clone().map(NumberOrString::String),
+        source: Some("kql".to_string()),
+        message: diagnostic.message.clone(),
+        related_information: (!related.is_empty()).then_some(related),
+        ..Diagnostic::default()
+    }
+}
+
+fn to_lsp_completion(item: &KqlCompletionItem, replace: Option<Range>) -> CompletionItem {
+    let kind = match item.kind {
+        CompletionKind::Keyword => CompletionItemKind::KEYWORD,
+        CompletionKind::Function | CompletionKind::AggregateFunction => {
+            CompletionItemKind::FUNCTION
+        }
+        CompletionKind::Table => CompletionItemKind::CLASS,
+        CompletionKind::Column => CompletionItemKind::FIELD,
+        CompletionKind::Variable | CompletionKind::LetVariable | CompletionKind::Parameter => {
+            CompletionItemKind::VARIABLE
+        }
+        CompletionKind::Operator | CompletionKind::Punctuation => CompletionItemKind::OPERATOR,
+        CompletionKind::Database | CompletionKind::Cluster => CompletionItemKind::MODULE,
+        CompletionKind::Type => CompletionItemKind::TYPE_PARAMETER,
+        CompletionKind::Other => CompletionItemKind::TEXT,
+    };
+
+    CompletionItem {
+        label: item.label.clone(),
+        kind: Some(kind),
+        detail: item.detail.clone(),
+        insert_text: item.insert_text.clone(),
+        text_edit: replace.map(|range| {
+            CompletionTextEdit::Edit(TextEdit::new(
+                range,
+                item.insert_text
+                    .clone()
+                    .unwrap_or_else(|| item.label.clone()),
+            ))
+        }),
+        sort_text: Some(item.sort_text()),
+        filter_text: Some(item.filter_text()),
+        data: serde_json::to_value(item).ok(),
+        ..CompletionItem::default()
+    }
+}
+
+/// Encode classifications as LSP relative semantic tokens
+///
+/// Spans that cross lines are split, since clients are not required to
+/// support multiline tokens.
+fn encode_tokens(text: &str, classifications: &ClassificationResult) -> Vec<SemanticToken> {
+    let index = LineIndex::new(text);
+    let mut spans: Vec<_> = classifications.spans.iter().collect();
+    spans.sort_by_key(|s| s.start);
+
+    let mut tokens = Vec::new();
+    let mut previous = Position::new(0, 0);
+    for span in spans {
+        let Some(token) = semantic_tokens::token_for(span.kind) else {
+            continue;
+        };
+        let end = position(&index, span.start + span.length);
+        let mut start = position(&index, span.start);
+
+        while start.line <= end.line {
+            let length = if start.line == end.line {
+                end.character - start.character
+            } else {
+                line_length(&index, start.line) - start.character
+            };
+            if length > 0 {
+                let delta_line = start.line - previous.line;
+                let delta_start = if delta_line == 0 {
+                    start.character - previous.character
+                } else {
+                    start.character
+                };
+                tokens.push(SemanticToken {
+                    delta_line,
+                    delta_start,
+                    length,
+                    token_type: token.token_type,
+                    token_modifiers_bitset: token.token_modifiers,
+                });
+                previous = start;
+            }
+            start = Position::new(start.line + 1, 0);
+        }
+    }
+    tokens
+}
+
+/// LSP position (UTF-16 column) of a character offset
+fn position(index: &LineIndex, offset: usize) -> Position {
+    let position = index.line_col_utf16(offset);
+    Position::new(to_u32(position.line), to_u32(position.col))
+}
+
+/// Character offset of an LSP position
+fn offset(index: &LineIndex, position: Position) -> usize {
+    index.offset_utf16(LineCol {
+        line: position.line as usize,
+        col: position.character as usize,
+    })
+}
+
+fn to_lsp_inlay_hint(index: &LineIndex, hint: &KqlInlayHint) -> InlayHint {
+    let kind = match hint.kind {
+        KqlInlayHintKind::Type => Some(InlayHintKind::TYPE),
+        KqlInlayHintKind::Parameter => Some(InlayHintKind::PARAMETER),
+        KqlInlayHintKind::ColumnCount | KqlInlayHintKind::Other => None,
+    };
+    // Type labels start with ": " already; parameter names sit before
+    // their argument and column counts after the stage
+    InlayHint {
+        position: position(index, hint.position),
+        label: InlayHintLabel::String(hint.label.clone()),
+        kind,
+        text_edits: None,
+        tooltip: None,
+        padding_left: Some(hint.kind == KqlInlayHintKind::ColumnCount),
+        padding_right: Some(hint.kind == KqlInlayHintKind::Parameter),
+        data: None,
+    }
+}
+
+fn range(index: &LineIndex, start: usize, end: usize) -> Range {
+    Range::new(position(index, start), position(index, end.max(start)))
+}
+
+/// Length of a line in UTF-16 code units
+fn line_length(index: &LineIndex, line: u32) -> u32 {
+    let length: usize = index
+        .line(line as usize)
+        .unwrap_or_default()
+        .chars()
+        .map(char::len_utf16)
+        .sum();
+    to_u32(length)
+}
+
+fn to_u32(value: usize) -> u32 {
+    u32::try_from(value).unwrap_or(u32::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::classification::{ClassificationKind, ClassifiedSpan};
+
+    #[test]
+    fn test_line_index_utf16() {
+        let text = "print s = '😀'\r\n| take 1";
+        let index = LineIndex::new(text);
+
+        // The emoji is one character but two UTF-16 code units
+        assert_eq!(position(&index, 11), Position::new(0, 11));
+        assert_eq!(position(&index, 12), Position::new(0, 13));
+        assert_eq!(position(&index, 15), Position::new(1, 0));
+        assert_eq!(offset(&index, Position::new(0, 13)), 12);
+        assert_eq!(offset(&index, Position::new(1, 2)), 17);
+        assert_eq!(offset(&index, Position::new(5, 0)), text.chars().count());
+    }
+
+    #[test]
+    fn test_encode_tokens_splits_lines() {
+        let text = "T\n/* a\nb */ | take 1";
+        let span = |start, length, kind| ClassifiedSpan {
+            start,
+            length,
+            kind,
+        };
+        let classifications = ClassificationResult {
+            spans: vec![
+                span(2, 9, ClassificationKind::Comment),
+                span(0, 1, ClassificationKind::Table),
+                span(12, 1, ClassificationKind::Punctuation),
+                span(14, 4, ClassificationKind::QueryOperator),
+            ],
+        };
+
+        let encoded: Vec<(u32, u32, u32)> = encode_tokens(text, &classifications)
+            .iter()
+            .map(|t| (t.delta_line, t.delta_start, t.length))
+            .collect();
+        assert_eq!(encoded, [(0, 0, 1), (1, 0, 4), (1, 0, 4), (0, 7, 4)]);
+    }
+}
@@ -0,0 +1,59 @@
+//! Per-call metrics hooks
+//!
+//! A [`Metrics`] implementation set with
+//! [`KqlValidator::with_metrics`](crate::KqlValidator::with_metrics) is
+//! called once per native call with a [`CallMetrics`] record, for feeding
+//! counters and histograms in the embedding service.
+
+use std::time::Duration;
+
+/// Timing and sizes of one native call
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct CallMetrics {
+    /// Validator operation, such as `"validate_with_options"`
+    pub operation: &'static str,
+    /// Wall time of the whole call, including serialization and parsing
+    pub duration: Duration,
+    /// Time spent inside the native library
+    pub native_duration: Duration,
+    /// Bytes of query, schema and options passed to the native library
+    pub input_bytes: usize,
+    /// Bytes of result returned by the native library
+    pub output_bytes: usize,
+    /// How often the call was repeated with a larger output buffer
+    pub buffer_retries: u32,
+    /// Whether the call returned an error
+    pub failed: bool,
+}
+
+/// Receives a [`CallMetrics`] record for every native call
+///
+/// Implemented for closures taking `&CallMetrics`.
+///
+/// # Example
+///
+/// ```no_run
+/// use kql_language_tools::{CallMetrics, KqlValidator};
+///
+/// fn main() -> Result<(), kql_language_tools::Error> {
+///     let validator = KqlValidator::new()?.with_metrics(|call: &CallMetrics| {
+///         println!("{} took {:?}", call.operation, call.duration);
+///     });
+///     validator.validate_syntax("T | take 10")?;
+///     Ok(())
+/// }
+/// ```
+pub trait Metrics: Send + Sync {
+    /// Record one completed call
+    fn record_call(&self, call: &CallMetrics);
+}
+
+impl<F> Metrics for F
+where
+    F: Fn(&CallMetrics) + Send + Sync,
+{
+    fn record_call(&self, call: &CallMetrics) {
+        self(call);
+    }
+}
@@ -0,0 +1,295 @@
+//! Scripted validator for tests
+//!
+//! Enabled by the `mock` feature. [`MockValidator`] answers validation,
+//! completion and classification calls with canned results instead of
+//! calling the native library, so code built on this crate can be unit
+//! tested where .NET is not installed. It implements [`KqlEngine`].
+
+use crate::backend::KqlEngine;
+use crate::classification::ClassificationResult;
+use crate::completion::{CompletionItem, CompletionResult};
+use crate::error::Error;
+use crate::schema::Schema;
+use crate::types::{Diagnostic, DiagnosticSeverity, ValidationResult};
+use std::collections::HashMap;
+use std::sync::{Mutex, PoisonError};
+
+/// A validator returning programmed results
+///
+/// Results are looked up by exact query text. Queries without a scripted
+/// result are valid, with no completions and no classifications, unless
+/// the defaults are changed. Schemas and cursor positions are ignored.
+///
+/// # Example
+///
+/// ```
+/// use kql_language_tools::MockValidator;
+///
+/// let mock = MockValidator::new()
+///     .with_invalid("T | where", "Expected an expression")
+///     .with_error("T | crash", "native library unavailable");
+///
+/// assert!(mock.validate_syntax("T | take 10")?.is_valid());
+/// assert!(!mock.validate_syntax("T | where")?.is_valid());
+/// assert!(mock.validate_syntax("T | crash").is_err());
+/// assert_eq!(mock.queries(), ["T | take 10", "T | where", "T | crash"]);
+/// # Ok::<(), kql_language_tools::Error>(())
+/// ```
+#[derive(Debug, Default)]
+pub struct MockValidator {
+    validations: HashMap<String, Scripted<ValidationResult>>,
+    completions: HashMap<String, CompletionResult>,
+    classifications: HashMap<String, ClassificationResult>,
+    default_validation: Option<ValidationResult>,
+    default_completions: CompletionResult,
+    queries: Mutex<Vec<String>>,
+}
+
+/// A scripted result or error message
+#[derive(Debug, Clone)]
+enum Scripted<T> {
+    Result(T),
+    Error(String),
+}
+
+impl MockValidator {
+    /// Create a mock that finds every query valid
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Return `result` when validating `query`
+    #[must_use]
+    pub fn with_result(mut self, query: impl Into<String>, result: ValidationResult) -> Self {
+        self.validations
+            .insert(query.into(), Scripted::Result(result));
+        self
+    }
+
+    /// Report `query` as invalid, with one error spanning the whole query
+    #[must_use]
+    pub fn with_invalid(self, query: impl Into<String>, message: impl Into<String>) -> Self {
+        let query = query.into();
+        let diagnostic = Diagnostic {
+            message: message.into(),
+            severity: DiagnosticSeverity::Error,
+            start: 0,
+            end: query.chars().count(),
+            line: 1,
+            column: 1,
+            code: None,
+            category: None,
+            fixes: Vec::new(),
+            related_spans: Vec::new(),
+        };
+        self.with_result(query, ValidationResult::invalid(vec![diagnostic]))
+    }
+
+    /// Fail every call for `query` with [`Error::Internal`]
+    #[must_use]
+    pub fn with_error(mut self, query: impl Into<String>, message: impl Into<String>) -> Self {
+        self.validations
+            .insert(query.into(), Scripted::Error(message.into()));
+        self
+    }
+
+    /// Return `result` when validating queries without a scripted result
+    #[must_use]
+    pub fn with_default_result(mut self, result: ValidationResult) -> Self {
+        self.default_validation = Some(result);
+        self
+    }
+
+    /// Return `items` when completing `query`, at any cursor position
+    #[must_use]
+    pub fn with_completions(
+        mut self,
+        query: impl Into<String>,
+        items: impl IntoIterator<Item = CompletionItem>,
+    ) -> Self {
+        self.completions.insert(
+            query.into(),
+            CompletionResult {
+                items: items.into_iter().collect(),
+                context: None,
+                expected_type: None,
+            },
+        );
+        self
+    }
+
+    /// Return `items` when completing queries without scripted completions
+    #[must_use]
+    pub fn with_default_completions(
+        mut self,
+        items: impl IntoIterator<Item = CompletionItem>,
+    ) -> Self {
+        self.default_completions = CompletionResult {
+            items: items.into_iter().collect(),
+            context: None,
+            expected_type: None,
+        };
+        self
+    }
+
+    /// Return `result` when classifying `query`
+    #[must_use]
+    pub fn with_classifications(
+        mut self,
+        query: impl Into<String>,
+        result: ClassificationResult,
+    ) -> Self {
+        self.classifications.insert(query.into(), result);
+        self
+    }
+
+    /// Get the queries passed to each call so far, in order
+    #[must_use]
+    pub fn queries(&self) -> Vec<String> {
+        self.queries
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /// Validate a query, returning its scripted result
+    ///
+    /// # Errors
+    ///
+    /// Returns the error scripted with [`with_error`](Self::with_error).
+    pub fn validate_syntax(&self, query: &str) -> Result<ValidationResult, Error> {
+        self.record(query)?;
+        Ok(match self.validations.get(query) {
+            Some(Scripted::Result(result)) => result.clone(),
+            _ => self
+                .default_validation
+                .clone()
+                .unwrap_or_else(ValidationResult::valid),
+        })
+    }
+
+    /// Validate a query against a schema, returning its scripted result
+    ///
+    /// # Errors
+    ///
+    /// Returns the error scripted with [`with_error`](Self::with_error).
+    pub fn validate_with_schema(
+        &self,
+        query: &str,
+        _schema: &Schema,
+    ) -> Result<ValidationResult, Error> {
+        self.validate_syntax(query)
+    }
+
+    /// Get the scripted completions for a query
+    ///
+    /// # Errors
+    ///
+    /// Returns the error scripted with [`with_error`](Self::with_error).
+    pub fn get_completions(
+        &self,
+        query: &str,
+        _cursor_position: usize,
+        _schema: Option<&Schema>,
+    ) -> Result<CompletionResult, Error> {
+        self.record(query)?;
+        Ok(self
+            .completions
+            .get(query)
+            .unwrap_or(&self.default_completions)
+            .clone())
+    }
+
+    /// Get the scripted classifications for a query
+    ///
+    /// # Errors
+    ///
+    /// Returns the error scripted with [`with_error`](Self::with_error).
+    pub fn get_classifications(&self, query: &str) -> Result<ClassificationResult, Error> {
+        self.record(query)?;
+        Ok(self.classifications.get(query).cloned().unwrap_or_default())
+    }
+
+    /// Record a call, failing it if an error is scripted for the query
+    fn record(&self, query: &str) -> Result<(), Error> {
+        self.queries
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(query.to_string());
+        match self.validations.get(query) {
+            Some(Scripted::Error(message)) => Err(Error::Internal {
+                message: message.clone(),
+            }),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl KqlEngine for MockValidator {
+    fn validate_syntax(&self, query: &str) -> Result<ValidationResult, Error> {
+        MockValidator::validate_syntax(self, query)
+    }
+
+    fn validate_with_schema(
+        &self,
+        query: &str,
+        schema: &Schema,
+    ) -> Result<ValidationResult, Error> {
+        MockValidator::validate_with_schema(self, query, schema)
+    }
+
+    fn get_completions(
+        &self,
+        query: &str,
+        cursor_position: usize,
+        schema: Option<&Schema>,
+    ) -> Result<CompletionResult, Error> {
+        MockValidator::get_completions(self, query, cursor_position, schema)
+    }
+
+    fn get_classifications(&self, query: &str) -> Result<ClassificationResult, Error> {
+        MockValidator::get_classifications(self, query)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::completion::CompletionKind;
+
+    #[test]
+    fn test_scripted_results() {
+        let where_item = CompletionItem {
+            label: "where".to_string(),
+            kind: CompletionKind::Keyword,
+            detail: None,
+            insert_text: None,
+            sort_order: 0,
+            edit_start: 4,
+            documentation: None,
+            matches_expected_type: false,
+        };
+        let mock = MockValidator::new()
+            .with_default_result(ValidationResult::invalid(Vec::new()))
+            .with_result("T", ValidationResult::valid())
+            .with_completions("T | ", [where_item])
+            .with_error("boom", "scripted failure");
+
+        assert!(mock
+            .validate_with_schema("T", &Schema::new())
+            .unwrap()
+            .is_valid());
+        assert!(!mock.validate_syntax("U").unwrap().is_valid());
+        assert_eq!(
+            mock.get_completions("T | ", 4, None).unwrap().items[0].label,
+            "where"
+        );
+        assert!(mock.get_completions("U", 1, None).unwrap().items.is_empty());
+        assert!(matches!(
+            mock.get_classifications("boom"),
+            Err(Error::Internal { message }) if message == "scripted failure"
+        ));
+        assert_eq!(mock.queries(), ["T", "U", "T | ", "U", "boom"]);
+    }
+}
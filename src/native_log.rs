@@ -0,0 +1,90 @@
+//! Log messages from the native library
+//!
+//! Native libraries exporting `kql_set_log_callback` report their own log
+//! messages (schema parse failures, exceptions) to this crate. By default
+//! they are forwarded to the [`log`] crate under the
+//! [`NATIVE_LOG_TARGET`] target; [`set_native_log_handler`] routes them
+//! elsewhere instead, such as into `tracing`.
+
+use std::ffi::c_int;
+use std::sync::RwLock;
+
+/// `log` target native log messages are forwarded to
+pub const NATIVE_LOG_TARGET: &str = "kql_language_tools::native";
+
+type Handler = Box<dyn Fn(log::Level, &str) + Send + Sync>;
+
+static HANDLER: RwLock<Option<Handler>> = RwLock::new(None);
+
+/// Send native log messages to a handler instead of the `log` crate
+///
+/// The handler may be called from any thread making native calls.
+///
+/// # Example
+///
+/// ```
+/// use kql_language_tools::set_native_log_handler;
+///
+/// set_native_log_handler(|level, message| eprintln!("[kql native {level}] {message}"));
+/// ```
+pub fn set_native_log_handler(handler: impl Fn(log::Level, &str) + Send + Sync + 'static) {
+    *HANDLER
+        .write()
+        .unwrap_or_else(std::sync::PoisonError::into_inner) = Some(Box::new(handler));
+}
+
+/// Forward native log messages to the `log` crate again
+pub fn reset_native_log_handler() {
+    *HANDLER
+        .write()
+        .unwrap_or_else(std::sync::PoisonError::into_inner) = None;
+}
+
+/// Map a native log level to a `log` level
+pub(crate) fn level_from_native(level: c_int) -> log::Level {
+    match level {
+        ..=1 => log::Level::Error,
+        2 => log::Level::Warn,
+        3 => log::Level::Info,
+        4 => log::Level::Debug,
+        _ => log::Level::Trace,
+    }
+}
+
+/// Deliver a native log message to the handler or the `log` crate
+pub(crate) fn dispatch(level: log::Level, message: &str) {
+    let handler = HANDLER
+        .read()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    match handler.as_ref() {
+        Some(handler) => handler(level, message),
+        None => log::log!(target: NATIVE_LOG_TARGET, level, "{message}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn test_handler_receives_messages() {
+        assert_eq!(level_from_native(1), log::Level::Error);
+        assert_eq!(level_from_native(4), log::Level::Debug);
+        assert_eq!(level_from_native(9), log::Level::Trace);
+
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&received);
+        set_native_log_handler(move |level, message| {
+            sink.lock().unwrap().push((level, message.to_string()));
+        });
+        dispatch(log::Level::Warn, "Schema JSON parse error");
+        reset_native_log_handler();
+        dispatch(log::Level::Warn, "not captured");
+
+        assert_eq!(
+            *received.lock().unwrap(),
+            vec![(log::Level::Warn, "Schema JSON parse error".to_string())]
+        );
+    }
+}
@@ -0,0 +1,59 @@
+//! Navigation within a query
+//!
+//! [`KqlValidator::get_definition`](crate::KqlValidator::get_definition)
+//! resolves the name under the cursor to the place it is declared in the
+//! same query: a `let` variable, a locally-declared function, or a function
+//! or lambda parameter.
+
+use serde::{Deserialize, Serialize};
+
+/// Where a name used in a query is declared
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Definition {
+    /// Declared name
+    pub name: String,
+    /// Kind of declaration
+    pub kind: DefinitionKind,
+    /// Start offset of the declared name (0-based, character position)
+    pub start: usize,
+    /// End offset of the declared name (0-based, character position)
+    pub end: usize,
+}
+
+/// Kind of local declaration
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub enum DefinitionKind {
+    /// A variable declared by `let`
+    Variable,
+    /// A function declared by `let`
+    Function,
+    /// A parameter of a declared function or lambda
+    Parameter,
+    /// Unknown kind
+    #[serde(other)]
+    Other,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deserialize_definition() {
+        let json = r#"{"name":"threshold","kind":"Variable","start":4,"end":13}"#;
+        let definition: Option<Definition> = serde_json::from_str(json).unwrap();
+        assert_eq!(
+            definition,
+            Some(Definition {
+                name: "threshold".to_string(),
+                kind: DefinitionKind::Variable,
+                start: 4,
+                end: 13,
+            })
+        );
+
+        let none: Option<Definition> = serde_json::from_str("null").unwrap();
+        assert_eq!(none, None);
+    }
+}
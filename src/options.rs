@@ -0,0 +1,51 @@
+//! Options sent to the native library alongside a query
+//!
+//! These are serialized to JSON and passed to the `*_with_options` FFI
+//! exports, and to worker processes. When every option is at its default,
+//! the validator uses the original exports so that older native libraries
+//! keep working.
+
+use crate::dialect::Dialect;
+use crate::engine::EnginePin;
+use crate::kind::QueryKind;
+use crate::limits::ServiceLimits;
+use crate::profile::FeatureProfile;
+use crate::schema::Parameter;
+use serde::{Deserialize, Serialize};
+use std::ffi::c_int;
+
+/// Per-validator analysis options
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct ValidationOptions {
+    /// Dialect to validate against
+    pub dialect: Dialect,
+    /// Kind of text expected (query or control command)
+    #[serde(skip_serializing_if = "QueryKind::is_query")]
+    pub kind: QueryKind,
+    /// Feature profiles the query must satisfy (all of them)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub profiles: Vec<FeatureProfile>,
+    /// Engine feature level the query must run on
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub engine: Option<EnginePin>,
+    /// Service limits to check
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limits: Option<ServiceLimits>,
+    /// Ambient query parameters, in addition to the schema's
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub query_parameters: Vec<Parameter>,
+    /// Suggest case-sensitive string operators for exact-case literals
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub case_sensitivity_advisory: bool,
+    /// Native cancellation source the call observes; local to the process
+    #[serde(skip_serializing_if = "Option::is_none", skip_deserializing)]
+    pub cancellation_id: Option<c_int>,
+}
+
+impl ValidationOptions {
+    /// Check whether all options are at their defaults
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
@@ -0,0 +1,101 @@
+//! Document outline
+//!
+//! [`KqlValidator::get_document_symbols`](crate::KqlValidator::get_document_symbols)
+//! returns the symbols of a document as a tree: `let` bindings, declared
+//! functions with their parameters, and the pipeline stages of each query.
+//! Each symbol carries both its full span and the span of its name, which is
+//! what an LSP `documentSymbol` response needs.
+
+use serde::{Deserialize, Serialize};
+
+/// A symbol in the document outline
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DocumentSymbol {
+    /// Declared name, query source (e.g. the table), or operator keyword
+    pub name: String,
+    /// Kind of symbol
+    pub kind: DocumentSymbolKind,
+    /// Start offset of the whole symbol (0-based, character position)
+    pub start: usize,
+    /// End offset of the whole symbol (0-based, character position)
+    pub end: usize,
+    /// Line of the start offset (1-based)
+    pub start_line: usize,
+    /// Line of the end offset (1-based)
+    pub end_line: usize,
+    /// Start offset of the symbol's name
+    pub selection_start: usize,
+    /// End offset of the symbol's name
+    pub selection_end: usize,
+    /// Nested symbols, in source order
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<DocumentSymbol>,
+}
+
+/// Kind of document symbol
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub enum DocumentSymbolKind {
+    /// A variable declared by `let`
+    Variable,
+    /// A function declared by `let`
+    Function,
+    /// A function parameter
+    Parameter,
+    /// A query statement, named after its source
+    Query,
+    /// A pipeline stage, named after its operator
+    Operator,
+    /// Unknown kind
+    #[serde(other)]
+    Other,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deserialize_outline() {
+        let json = r#"[
+            {"name":"f","kind":"Function","start":0,"end":30,"start_line":1,"end_line":1,
+             "selection_start":4,"selection_end":5,"children":[
+                {"name":"x","kind":"Parameter","start":9,"end":16,"start_line":1,"end_line":1,
+                 "selection_start":9,"selection_end":10}
+            ]},
+            {"name":"T","kind":"Query","start":32,"end":42,"start_line":2,"end_line":2,
+             "selection_start":32,"selection_end":33,"children":[
+                {"name":"take","kind":"Operator","start":36,"end":42,"start_line":2,"end_line":2,
+                 "selection_start":36,"selection_end":42}
+            ]}
+        ]"#;
+
+        let symbols: Vec<DocumentSymbol> = serde_json::from_str(json).unwrap();
+        let tree: Vec<_> = symbols
+            .iter()
+            .map(|s| {
+                let children: Vec<_> = s
+                    .children
+                    .iter()
+                    .map(|c| (c.kind, c.name.as_str()))
+                    .collect();
+                (s.kind, s.name.as_str(), children)
+            })
+            .collect();
+        assert_eq!(
+            tree,
+            [
+                (
+                    DocumentSymbolKind::Function,
+                    "f",
+                    vec![(DocumentSymbolKind::Parameter, "x")]
+                ),
+                (
+                    DocumentSymbolKind::Query,
+                    "T",
+                    vec![(DocumentSymbolKind::Operator, "take")]
+                ),
+            ]
+        );
+    }
+}
@@ -0,0 +1,108 @@
+//! Pipeline stage decomposition
+//!
+//! [`KqlValidator::analyze_pipeline`](crate::KqlValidator::analyze_pipeline)
+//! splits a query's final pipeline into its stages: the tabular source,
+//! then each query operator in order, with the columns the pipeline has
+//! after that stage. Tools can use it to explain a query stage by stage, or
+//! run it cut short with [`Pipeline::prefix`] to see intermediate results.
+
+use crate::schema::Table;
+use crate::syntax_tree::NodeKind;
+use serde::{Deserialize, Serialize};
+
+/// The stages of a query's pipeline
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Pipeline {
+    /// The source, then each query operator, in source order
+    pub stages: Vec<PipelineStage>,
+}
+
+impl Pipeline {
+    /// Get the query made of the first `count` stages
+    ///
+    /// Stages are joined with `|` on new lines, so comments and layout
+    /// between stages are not kept. A `count` past the last stage gives the
+    /// whole pipeline.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use kql_language_tools::{Pipeline, PipelineStage};
+    ///
+    /// let stage = |kind: &str, text: &str| PipelineStage {
+    ///     kind: kind.to_string(),
+    ///     text: text.to_string(),
+    ///     start: 0,
+    ///     end: 0,
+    ///     output: None,
+    /// };
+    /// let pipeline = Pipeline {
+    ///     stages: vec![
+    ///         stage("NameReference", "SigninLogs"),
+    ///         stage("FilterOperator", "where ResultType != 0"),
+    ///         stage("SummarizeOperator", "summarize count() by UserPrincipalName"),
+    ///     ],
+    /// };
+    /// assert_eq!(pipeline.prefix(2), "SigninLogs\n| where ResultType != 0");
+    /// ```
+    #[must_use]
+    pub fn prefix(&self, count: usize) -> String {
+        self.stages
+            .iter()
+            .take(count)
+            .map(|stage| stage.text.as_str())
+            .collect::<Vec<_>>()
+            .join("\n| ")
+    }
+}
+
+/// A stage of a [`Pipeline`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PipelineStage {
+    /// Kusto.Language `SyntaxKind` name: an operator such as
+    /// `FilterOperator`, or for the source its expression (`NameReference`
+    /// for a table, `FunctionCallExpression`, ...)
+    pub kind: String,
+    /// Source text of the stage, without the `|` before it
+    pub text: String,
+    /// Start offset (0-based, character position)
+    pub start: usize,
+    /// End offset (0-based, character position, exclusive)
+    pub end: usize,
+    /// Columns after this stage (`None` when the stage has no tabular result,
+    /// for example because a name does not resolve)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output: Option<Table>,
+}
+
+impl PipelineStage {
+    /// Get the stage's kind, if it is one of [`NodeKind`]'s
+    #[must_use]
+    pub fn node_kind(&self) -> Option<NodeKind> {
+        NodeKind::parse(&self.kind)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pipeline_deserialize() {
+        let json = r#"{"stages":[
+            {"kind":"NameReference","text":"T","start":0,"end":1,"output":{"name":"T","columns":[{"name":"a","data_type":"long"},{"name":"b","data_type":"string"}]}},
+            {"kind":"ProjectOperator","text":"project a","start":4,"end":13,"output":{"name":"","columns":[{"name":"a","data_type":"long"}]}},
+            {"kind":"RenderOperator","text":"render x","start":16,"end":24}
+        ]}"#;
+        let pipeline: Pipeline = serde_json::from_str(json).unwrap();
+
+        assert_eq!(pipeline.stages.len(), 3);
+        let project = &pipeline.stages[1];
+        assert_eq!(project.node_kind(), Some(NodeKind::ProjectOperator));
+        assert_eq!(project.output.as_ref().unwrap().columns.len(), 1);
+        assert!(pipeline.stages[2].output.is_none());
+        assert_eq!(pipeline.prefix(2), "T\n| project a");
+        assert_eq!(pipeline.prefix(10), "T\n| project a\n| render x");
+        assert_eq!(pipeline.prefix(0), "");
+    }
+}
@@ -0,0 +1,714 @@
+//! Position mapping between offset conventions
+//!
+//! Offsets in this crate's API (diagnostic spans, completion cursors, edit
+//! ranges, classified spans, ...) are **character offsets**: 0-based counts
+//! of Unicode scalar values (`char`s) into the query. They are not byte
+//! offsets into the Rust `&str`, and not the UTF-16 code units that LSP
+//! clients and JavaScript editors use. The three only agree for ASCII text;
+//! `é` is one character but two bytes, and `😀` is one character, four
+//! bytes, and two UTF-16 code units.
+//!
+//! Kusto.Language counts UTF-16 code units, as .NET strings do, so the
+//! validator converts the offsets of every native result into characters as
+//! it reads them, and converts cursors and ranges back before passing them in.
+//!
+//! [`LineIndex`] converts between the three conventions, and between
+//! offsets and 0-based line/column pairs. Alternatively,
+//! [`KqlValidator::with_offset_encoding`](crate::KqlValidator::with_offset_encoding)
+//! switches the validator's diagnostics and completions to UTF-16 code units.
+//!
+//! # Example
+//!
+//! ```
+//! use kql_language_tools::positions::{LineCol, LineIndex};
+//!
+//! let index = LineIndex::new("print s = '😀'\n| take 1");
+//!
+//! // The closing quote is character 12, byte 15, UTF-16 unit 13
+//! assert_eq!(index.char_to_byte(12), 15);
+//! assert_eq!(index.char_to_utf16(12), 13);
+//! assert_eq!(index.line_col_utf16(12), LineCol { line: 0, col: 13 });
+//!
+//! // An LSP position on the second line back to a character offset
+//! assert_eq!(index.offset_utf16(LineCol { line: 1, col: 2 }), 16);
+//! ```
+
+use crate::classification::ClassificationResult;
+use crate::completion::CompletionResult;
+use crate::expression::ExpressionType;
+use crate::inlay::InlayHint;
+use crate::joins::JoinAnalysis;
+use crate::navigation::Definition;
+use crate::outline::DocumentSymbol;
+use crate::pipeline::Pipeline;
+use crate::script::ScriptBlock;
+use crate::selection::{BracketPair, EnclosingRange};
+use crate::structure::{DocumentStructure, StructureNode};
+use crate::syntax_tree::FlatTree;
+use crate::types::{Diagnostic, Fix, ValidationResult};
+use serde::{Deserialize, Serialize};
+
+/// Unit of the offsets a [`KqlValidator`](crate::KqlValidator) accepts and returns
+///
+/// Applies to completion cursor positions and `edit_start`, and to the spans,
+/// columns, fix edits, and related spans of diagnostics (including script
+/// block bounds). Other position APIs (classification, structure, outline,
+/// definitions, expression types, selection ranges, inlay hints, syntax
+/// trees, pipeline stages, join operations) always use characters.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum OffsetEncoding {
+    /// Characters (Unicode scalar values)
+    #[default]
+    Chars,
+    /// UTF-16 code units, as LSP clients use by default
+    Utf16,
+}
+
+impl OffsetEncoding {
+    /// Check if offsets in this encoding can differ from character offsets
+    /// into `text`
+    fn converts(self, text: &str) -> bool {
+        self == Self::Utf16 && has_surrogate_pairs(text)
+    }
+
+    /// Convert an offset in this encoding into a character offset
+    pub(crate) fn decode(self, text: &str, offset: usize) -> usize {
+        if self.converts(text) {
+            scan(text, offset, |u| u.utf16).char
+        } else {
+            offset
+        }
+    }
+
+    /// Convert the character offsets of diagnostics into this encoding
+    pub(crate) fn encode_diagnostics(self, text: &str, diagnostics: &mut [Diagnostic]) {
+        if !diagnostics.is_empty() && self.converts(text) {
+            encode_diagnostics(&LineIndex::new(text), diagnostics);
+        }
+    }
+
+    /// Convert the character offsets of script blocks and their diagnostics
+    /// into this encoding
+    pub(crate) fn encode_script(self, text: &str, blocks: &mut [ScriptBlock]) {
+        if !self.converts(text) {
+            return;
+        }
+
+        let index = LineIndex::new(text);
+        for block in blocks {
+            block.start = index.char_to_utf16(block.start);
+            block.end = index.char_to_utf16(block.end);
+            encode_diagnostics(&index, &mut block.result.diagnostics);
+        }
+    }
+
+    /// Convert the character offsets of fix edits into this encoding
+    pub(crate) fn encode_fixes(self, text: &str, fixes: &mut [Fix]) {
+        if self.converts(text) {
+            encode_fixes(&LineIndex::new(text), fixes);
+        }
+    }
+
+    /// Convert the offsets of fix edits in this encoding into character offsets
+    pub(crate) fn decode_fixes(self, text: &str, fixes: &mut [Fix]) {
+        if !self.converts(text) {
+            return;
+        }
+
+        let index = LineIndex::new(text);
+        for edit in fixes.iter_mut().flat_map(|f| f.edits.iter_mut()) {
+            edit.start = index.utf16_to_char(edit.start);
+            edit.end = index.utf16_to_char(edit.end);
+        }
+    }
+
+    /// Convert the character offsets of completion items into this encoding
+    pub(crate) fn encode_completions(self, text: &str, completions: &mut CompletionResult) {
+        if !self.converts(text) {
+            return;
+        }
+
+        let index = LineIndex::new(text);
+        for item in &mut completions.items {
+            item.edit_start = index.char_to_utf16(item.edit_start);
+        }
+        if let Some(context) = &mut completions.context {
+            context.edit_start = index.char_to_utf16(context.edit_start);
+            context.edit_end = index.char_to_utf16(context.edit_end);
+        }
+    }
+}
+
+fn encode_diagnostics(index: &LineIndex, diagnostics: &mut [Diagnostic]) {
+    for diagnostic in diagnostics {
+        diagnostic.column = index.line_col_utf16(diagnostic.start).col + 1;
+        diagnostic.start = index.char_to_utf16(diagnostic.start);
+        diagnostic.end = index.char_to_utf16(diagnostic.end);
+        for related in &mut diagnostic.related_spans {
+            related.start = index.char_to_utf16(related.start);
+            related.end = index.char_to_utf16(related.end);
+        }
+        encode_fixes(index, &mut diagnostic.fixes);
+    }
+}
+
+fn encode_fixes(index: &LineIndex, fixes: &mut [Fix]) {
+    for edit in fixes.iter_mut().flat_map(|f| f.edits.iter_mut()) {
+        edit.start = index.char_to_utf16(edit.start);
+        edit.end = index.char_to_utf16(edit.end);
+    }
+}
+
+/// A native result whose offsets are converted to characters as it is read
+///
+/// Kusto.Language works on .NET strings, so every offset the native library
+/// returns or accepts counts UTF-16 code units. Units and characters only
+/// differ after a character outside the Basic Multilingual Plane (an emoji,
+/// say), which takes two units.
+pub(crate) trait NativeOffsets {
+    /// Convert UTF-16 offsets into character offsets
+    fn utf16_to_chars(&mut self, index: &LineIndex);
+}
+
+/// Convert the UTF-16 offsets of a native result for `text` into characters
+pub(crate) fn from_native<T: NativeOffsets + ?Sized>(text: &str, result: &mut T) {
+    if let Some(index) = native_index(text) {
+        result.utf16_to_chars(&index);
+    }
+}
+
+/// Index `text` for converting many native results, or `None` if its
+/// native offsets are already character offsets
+pub(crate) fn native_index(text: &str) -> Option<LineIndex> {
+    has_surrogate_pairs(text).then(|| LineIndex::new(text))
+}
+
+/// Convert a character offset into `text` into the UTF-16 offset the native
+/// library expects
+pub(crate) fn to_native(text: &str, offset: usize) -> usize {
+    if has_surrogate_pairs(text) {
+        scan(text, offset, |u| u.char).utf16
+    } else {
+        offset
+    }
+}
+
+/// Check if any character of `text` takes two UTF-16 code units
+fn has_surrogate_pairs(text: &str) -> bool {
+    // Exactly the characters above U+FFFF start with a 0xF0-0xF4 byte
+    text.bytes().any(|b| b >= 0xF0)
+}
+
+impl<T: NativeOffsets> NativeOffsets for [T] {
+    fn utf16_to_chars(&mut self, index: &LineIndex) {
+        for item in self {
+            item.utf16_to_chars(index);
+        }
+    }
+}
+
+impl<T: NativeOffsets> NativeOffsets for Vec<T> {
+    fn utf16_to_chars(&mut self, index: &LineIndex) {
+        self.as_mut_slice().utf16_to_chars(index);
+    }
+}
+
+impl<T: NativeOffsets> NativeOffsets for Option<T> {
+    fn utf16_to_chars(&mut self, index: &LineIndex) {
+        if let Some(value) = self {
+            value.utf16_to_chars(index);
+        }
+    }
+}
+
+impl NativeOffsets for Diagnostic {
+    fn utf16_to_chars(&mut self, index: &LineIndex) {
+        self.start = index.utf16_to_char(self.start);
+        self.end = index.utf16_to_char(self.end);
+        self.column = index.line_col(self.start).col + 1;
+        for related in &mut self.related_spans {
+            related.start = index.utf16_to_char(related.start);
+            related.end = index.utf16_to_char(related.end);
+        }
+        for edit in self.fixes.iter_mut().flat_map(|f| f.edits.iter_mut()) {
+            edit.start = index.utf16_to_char(edit.start);
+            edit.end = index.utf16_to_char(edit.end);
+        }
+    }
+}
+
+impl NativeOffsets for ValidationResult {
+    fn utf16_to_chars(&mut self, index: &LineIndex) {
+        self.diagnostics.utf16_to_chars(index);
+    }
+}
+
+impl NativeOffsets for CompletionResult {
+    fn utf16_to_chars(&mut self, index: &LineIndex) {
+        for item in &mut self.items {
+            item.edit_start = index.utf16_to_char(item.edit_start);
+        }
+        if let Some(context) = &mut self.context {
+            context.edit_start = index.utf16_to_char(context.edit_start);
+            context.edit_end = index.utf16_to_char(context.edit_end);
+        }
+    }
+}
+
+impl NativeOffsets for ClassificationResult {
+    fn utf16_to_chars(&mut self, index: &LineIndex) {
+        for span in &mut self.spans {
+            let end = index.utf16_to_char(span.start + span.length);
+            span.start = index.utf16_to_char(span.start);
+            span.length = end - span.start;
+        }
+    }
+}
+
+impl NativeOffsets for DocumentStructure {
+    fn utf16_to_chars(&mut self, index: &LineIndex) {
+        self.statements.utf16_to_chars(index);
+    }
+}
+
+impl NativeOffsets for StructureNode {
+    fn utf16_to_chars(&mut self, index: &LineIndex) {
+        self.start = index.utf16_to_char(self.start);
+        self.end = index.utf16_to_char(self.end);
+        self.children.utf16_to_chars(index);
+    }
+}
+
+impl NativeOffsets for DocumentSymbol {
+    fn utf16_to_chars(&mut self, index: &LineIndex) {
+        self.start = index.utf16_to_char(self.start);
+        self.end = index.utf16_to_char(self.end);
+        self.selection_start = index.utf16_to_char(self.selection_start);
+        self.selection_end = index.utf16_to_char(self.selection_end);
+        self.children.utf16_to_chars(index);
+    }
+}
+
+impl NativeOffsets for BracketPair {
+    fn utf16_to_chars(&mut self, index: &LineIndex) {
+        self.open = index.utf16_to_char(self.open);
+        self.close = index.utf16_to_char(self.close);
+    }
+}
+
+impl NativeOffsets for EnclosingRange {
+    fn utf16_to_chars(&mut self, index: &LineIndex) {
+        self.start = index.utf16_to_char(self.start);
+        self.end = index.utf16_to_char(self.end);
+    }
+}
+
+impl NativeOffsets for FlatTree {
+    fn utf16_to_chars(&mut self, index: &LineIndex) {
+        for node in &mut self.nodes {
+            node.start = index.utf16_to_char(node.start);
+            node.end = index.utf16_to_char(node.end);
+        }
+    }
+}
+
+impl NativeOffsets for Definition {
+    fn utf16_to_chars(&mut self, index: &LineIndex) {
+        self.start = index.utf16_to_char(self.start);
+        self.end = index.utf16_to_char(self.end);
+    }
+}
+
+impl NativeOffsets for ExpressionType {
+    fn utf16_to_chars(&mut self, index: &LineIndex) {
+        self.start = index.utf16_to_char(self.start);
+        self.end = index.utf16_to_char(self.end);
+    }
+}
+
+impl NativeOffsets for InlayHint {
+    fn utf16_to_chars(&mut self, index: &LineIndex) {
+        self.position = index.utf16_to_char(self.position);
+    }
+}
+
+impl NativeOffsets for Pipeline {
+    fn utf16_to_chars(&mut self, index: &LineIndex) {
+        for stage in &mut self.stages {
+            stage.start = index.utf16_to_char(stage.start);
+            stage.end = index.utf16_to_char(stage.end);
+        }
+    }
+}
+
+impl NativeOffsets for JoinAnalysis {
+    fn utf16_to_chars(&mut self, index: &LineIndex) {
+        for operation in &mut self.operations {
+            operation.start = index.utf16_to_char(operation.start);
+            operation.end = index.utf16_to_char(operation.end);
+        }
+        self.diagnostics.utf16_to_chars(index);
+    }
+}
+
+/// A 0-based line and column
+///
+/// Whether `col` counts characters or UTF-16 code units depends on the
+/// [`LineIndex`] method that produced or accepts it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct LineCol {
+    /// Line (0-based)
+    pub line: usize,
+    /// Column (0-based)
+    pub col: usize,
+}
+
+/// The same position measured in each convention
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct Units {
+    byte: usize,
+    char: usize,
+    utf16: usize,
+}
+
+/// Offset and line/column conversions for a text
+///
+/// Lines are split on `\n`; a trailing `\r` is not part of a line's text.
+/// Conversions clamp offsets past the end of the text to its end, and round
+/// offsets inside a character (a byte inside a multi-byte character, or the
+/// second half of a surrogate pair) down to its start.
+#[derive(Debug, Clone)]
+pub struct LineIndex {
+    text: String,
+    /// Start of each line
+    starts: Vec<Units>,
+    /// Position of every [`CHECKPOINT_INTERVAL`]th character, so conversions
+    /// never scan further than that (empty for ASCII text)
+    checkpoints: Vec<Units>,
+    /// End of the text
+    end: Units,
+}
+
+/// Characters between [`LineIndex`] checkpoints
+const CHECKPOINT_INTERVAL: usize = 64;
+
+impl LineIndex {
+    /// Index a text
+    #[must_use]
+    pub fn new(text: impl Into<String>) -> Self {
+        let text = text.into();
+        let mut starts = vec![Units::default()];
+        let mut checkpoints = Vec::new();
+        let mut at = Units::default();
+        if text.is_ascii() {
+            // Every unit is one byte, so only line starts are needed
+            for (byte, _) in text.match_indices('\n') {
+                starts.push(Units {
+                    byte: byte + 1,
+                    char: byte + 1,
+                    utf16: byte + 1,
+                });
+            }
+            at = Units {
+                byte: text.len(),
+                char: text.len(),
+                utf16: text.len(),
+            };
+        } else {
+            for c in text.chars() {
+                if at.char % CHECKPOINT_INTERVAL == 0 {
+                    checkpoints.push(at);
+                }
+                at = step(at, c);
+                if c == '\n' {
+                    starts.push(at);
+                }
+            }
+        }
+
+        Self {
+            text,
+            starts,
+            checkpoints,
+            end: at,
+        }
+    }
+
+    /// Get the indexed text
+    #[must_use]
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Get the number of lines (at least 1)
+    #[must_use]
+    pub fn line_count(&self) -> usize {
+        self.starts.len()
+    }
+
+    /// Get the text of a line (0-based), without its line ending
+    #[must_use]
+    pub fn line(&self, line: usize) -> Option<&str> {
+        let start = self.starts.get(line)?.byte;
+        let end = self.starts.get(line + 1).map_or(self.end.byte, |s| s.byte);
+        let text = &self.text[start..end];
+        let text = text.strip_suffix('\n').unwrap_or(text);
+        Some(text.strip_suffix('\r').unwrap_or(text))
+    }
+
+    /// Get the length of the text in characters
+    #[must_use]
+    pub fn len_chars(&self) -> usize {
+        self.end.char
+    }
+
+    /// Convert a byte offset to a character offset
+    #[must_use]
+    pub fn byte_to_char(&self, byte: usize) -> usize {
+        self.seek(byte, |u| u.byte).char
+    }
+
+    /// Convert a character offset to a byte offset
+    #[must_use]
+    pub fn char_to_byte(&self, char: usize) -> usize {
+        self.seek(char, |u| u.char).byte
+    }
+
+    /// Convert a character offset to a UTF-16 offset
+    #[must_use]
+    pub fn char_to_utf16(&self, char: usize) -> usize {
+        self.seek(char, |u| u.char).utf16
+    }
+
+    /// Convert a UTF-16 offset to a character offset
+    #[must_use]
+    pub fn utf16_to_char(&self, utf16: usize) -> usize {
+        self.seek(utf16, |u| u.utf16).char
+    }
+
+    /// Get the line and character column of a character offset
+    #[must_use]
+    pub fn line_col(&self, char: usize) -> LineCol {
+        let (line, at) = self.locate(char);
+        LineCol {
+            line,
+            col: at.char - self.starts[line].char,
+        }
+    }
+
+    /// Get the line and UTF-16 column of a character offset (an LSP `Position`)
+    #[must_use]
+    pub fn line_col_utf16(&self, char: usize) -> LineCol {
+        let (line, at) = self.locate(char);
+        LineCol {
+            line,
+            col: at.utf16 - self.starts[line].utf16,
+        }
+    }
+
+    /// Get the character offset of a line and character column
+    ///
+    /// Columns past the end of the line clamp to its end; lines past the end
+    /// of the text clamp to the end of the text.
+    #[must_use]
+    pub fn offset(&self, position: LineCol) -> usize {
+        let Some(line_end) = self.line_end(position.line) else {
+            return self.end.char;
+        };
+        (self.starts[position.line].char + position.col).min(line_end.char)
+    }
+
+    /// Get the character offset of a line and UTF-16 column (an LSP `Position`)
+    ///
+    /// Clamps like [`offset`](Self::offset).
+    #[must_use]
+    pub fn offset_utf16(&self, position: LineCol) -> usize {
+        let Some(line_end) = self.line_end(position.line) else {
+            return self.end.char;
+        };
+        let utf16 = (self.starts[position.line].utf16 + position.col).min(line_end.utf16);
+        self.seek(utf16, |u| u.utf16).char
+    }
+
+    /// Find the line containing a character offset, and the clamped position
+    fn locate(&self, char: usize) -> (usize, Units) {
+        let at = self.seek(char, |u| u.char);
+        let line = self.starts.partition_point(|s| s.char <= at.char) - 1;
+        (line, at)
+    }
+
+    /// End of a line's text, before its line ending
+    fn line_end(&self, line: usize) -> Option<Units> {
+        let len = self.line(line)?.len();
+        let byte = self.starts[line].byte + len;
+        Some(self.seek(byte, |u| u.byte))
+    }
+
+    /// Find the position at `target`, measured in the unit `key` selects
+    fn seek(&self, target: usize, key: impl Fn(&Units) -> usize) -> Units {
+        if target >= key(&self.end) {
+            return self.end;
+        }
+        if self.checkpoints.is_empty() {
+            return Units {
+                byte: target,
+                char: target,
+                utf16: target,
+            };
+        }
+
+        let checkpoint = self.checkpoints.partition_point(|s| key(s) <= target) - 1;
+        advance(&self.text, self.checkpoints[checkpoint], target, key)
+    }
+}
+
+/// Find the position at `target` in `text` by scanning from its start
+///
+/// For one-off conversions, where building a [`LineIndex`] costs more than
+/// the scan.
+fn scan(text: &str, target: usize, key: impl Fn(&Units) -> usize) -> Units {
+    advance(text, Units::default(), target, key)
+}
+
+/// Scan forward from `at` to the position at `target`
+fn advance(text: &str, mut at: Units, target: usize, key: impl Fn(&Units) -> usize) -> Units {
+    for c in text[at.byte..].chars() {
+        let next = step(at, c);
+        if key(&next) > target {
+            break;
+        }
+        at = next;
+    }
+    at
+}
+
+/// Advance a position past a character
+fn step(at: Units, c: char) -> Units {
+    Units {
+        byte: at.byte + c.len_utf8(),
+        char: at.char + 1,
+        utf16: at.utf16 + c.len_utf16(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_conversions() {
+        let index = LineIndex::new("é😀\r\nx");
+
+        assert_eq!(index.char_to_byte(1), 2);
+        assert_eq!(index.char_to_byte(2), 6);
+        assert_eq!(index.char_to_utf16(2), 3);
+        assert_eq!(index.byte_to_char(4), 1);
+        assert_eq!(index.utf16_to_char(2), 1);
+        assert_eq!(index.utf16_to_char(5), 4);
+        assert_eq!(index.char_to_byte(99), 9);
+        assert_eq!(index.len_chars(), 5);
+    }
+
+    #[test]
+    fn test_utf16_encoding() {
+        let text = "print '😀' | take x";
+        // As the native library reports an error on `x`: in UTF-16 code units,
+        // with the emoji counting twice
+        let native = r#"{"valid":false,"diagnostics":[{"message":"x","severity":"Error",
+            "start":18,"end":19,"line":1,"column":19,
+            "fixes":[{"title":"Use 1","edits":[{"start":18,"end":19,"new_text":"1"}]}]}]}"#;
+        let read = || {
+            let mut result: ValidationResult = serde_json::from_str(native).unwrap();
+            from_native(text, &mut result);
+            result.diagnostics
+        };
+
+        let chars = read();
+        let d = &chars[0];
+        assert_eq!((d.start, d.end, d.column), (17, 18, 18));
+        assert_eq!(d.fixes[0].edits[0].start, 17);
+
+        // UTF-16 mode hands the native offsets back unchanged
+        let mut utf16 = read();
+        OffsetEncoding::Utf16.encode_diagnostics(text, &mut utf16);
+        let d = &utf16[0];
+        assert_eq!((d.start, d.end, d.column), (18, 19, 19));
+        assert_eq!(d.fixes[0].edits[0].start, 18);
+
+        OffsetEncoding::Utf16.decode_fixes(text, &mut utf16[0].fixes);
+        assert_eq!(utf16[0].fixes[0].edits[0].start, 17);
+
+        // Cursors reach the native library in UTF-16 code units either way
+        let cursor =
+            |encoding: OffsetEncoding, offset| to_native(text, encoding.decode(text, offset));
+        assert_eq!(cursor(OffsetEncoding::Chars, 17), 18);
+        assert_eq!(cursor(OffsetEncoding::Utf16, 18), 18);
+        assert_eq!(to_native("print 'é' | take x", 17), 17);
+    }
+
+    #[test]
+    fn test_native_structure_offsets() {
+        let text = "let s = '😀';\nT | take 1";
+        // Native offsets count the emoji twice, so the second statement starts at 14
+        let native = r#"{"statements":[
+            {"kind":"LetStatement","start":0,"end":13,"start_line":1,"end_line":1,
+             "children":[{"kind":"VariableDeclaration","name":"s","start":4,"end":12,"start_line":1,"end_line":1}]},
+            {"kind":"QueryStatement","start":14,"end":24,"start_line":2,"end_line":2}
+        ]}"#;
+        let mut structure: DocumentStructure = serde_json::from_str(native).unwrap();
+        from_native(text, &mut structure);
+
+        let spans: Vec<_> = structure.nodes().map(|n| (n.start, n.end)).collect();
+        assert_eq!(spans, [(0, 12), (4, 11), (13, 23)]);
+        assert_eq!(text.chars().nth(13), Some('T'));
+    }
+
+    #[test]
+    fn test_lines() {
+        let index = LineIndex::new("é😀\r\nx\n");
+
+        assert_eq!(index.line_count(), 3);
+        assert_eq!(index.line(0), Some("é😀"));
+        assert_eq!(index.line(1), Some("x"));
+        assert_eq!(index.line(2), Some(""));
+        assert_eq!(index.line(3), None);
+
+        assert_eq!(index.line_col(2), LineCol { line: 0, col: 2 });
+        assert_eq!(index.line_col_utf16(2), LineCol { line: 0, col: 3 });
+        assert_eq!(index.line_col(4), LineCol { line: 1, col: 0 });
+        assert_eq!(index.offset(LineCol { line: 0, col: 9 }), 2);
+        assert_eq!(index.offset_utf16(LineCol { line: 0, col: 2 }), 1);
+        assert_eq!(index.offset_utf16(LineCol { line: 1, col: 1 }), 5);
+        assert_eq!(index.offset(LineCol { line: 7, col: 0 }), 6);
+    }
+
+    #[test]
+    fn test_long_lines() {
+        // A single machine-generated line, spanning many checkpoints
+        let text: String = (0..1000)
+            .map(|i| match i % 3 {
+                0 => "a",
+                1 => "é",
+                _ => "😀",
+            })
+            .collect();
+        let index = LineIndex::new(text.as_str());
+
+        let mut utf16 = 0;
+        for (char, (byte, c)) in text.char_indices().enumerate() {
+            assert_eq!(index.char_to_byte(char), byte);
+            assert_eq!(index.byte_to_char(byte), char);
+            assert_eq!(index.char_to_utf16(char), utf16);
+            assert_eq!(index.utf16_to_char(utf16), char);
+            assert_eq!(to_native(&text, char), utf16);
+            assert_eq!(OffsetEncoding::Utf16.decode(&text, utf16), char);
+            utf16 += c.len_utf16();
+        }
+        assert_eq!(index.line_col_utf16(999), LineCol { line: 0, col: 1332 });
+
+        let ascii = LineIndex::new("T\n| take 10");
+        assert_eq!(ascii.char_to_utf16(7), 7);
+        assert_eq!(ascii.line_col(7), LineCol { line: 1, col: 5 });
+        assert_eq!(ascii.offset(LineCol { line: 1, col: 99 }), 11);
+    }
+}
@@ -0,0 +1,245 @@
+//! Restricted feature profiles
+//!
+//! Many products host a subset of KQL: alert rules, dashboards, and
+//! ingestion-time transformations each reject operators or functions that
+//! are fine in a regular query. A [`FeatureProfile`] describes such a subset
+//! so that queries can be checked against it before they are deployed.
+//!
+//! Profiles are composable: a validator can carry several profiles, and a
+//! query must satisfy all of them. [`FeatureProfile::intersect`] combines two
+//! profiles into one with the same effect.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+
+/// A restricted subset of KQL features
+///
+/// Each allow-list is optional; `None` leaves that feature unrestricted.
+/// Names are compared case-insensitively: the builders and deserialization
+/// store them lowercased, so sets filled in directly should hold lowercase
+/// names too.
+///
+/// # Example
+///
+/// ```
+/// use kql_language_tools::FeatureProfile;
+///
+/// let alert_rule = FeatureProfile::new("alert-rule")
+///     .allow_operators(["where", "extend", "project", "summarize"])
+///     .max_pipeline_depth(8);
+///
+/// assert!(alert_rule.allows_operator("WHERE"));
+/// assert!(!alert_rule.allows_operator("join"));
+/// assert!(alert_rule.allows_function("anything"));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FeatureProfile {
+    /// Profile name, used in diagnostic messages
+    pub name: String,
+
+    /// Allowed tabular operators (`where`, `project-away`, `mv-expand`, ...)
+    #[serde(
+        default,
+        deserialize_with = "deserialize_names",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub operators: Option<BTreeSet<String>>,
+
+    /// Allowed `evaluate` plugins (`bag_unpack`, `pivot`, ...)
+    #[serde(
+        default,
+        deserialize_with = "deserialize_names",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub plugins: Option<BTreeSet<String>>,
+
+    /// Allowed scalar and aggregate functions
+    #[serde(
+        default,
+        deserialize_with = "deserialize_names",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub functions: Option<BTreeSet<String>>,
+
+    /// Maximum number of piped operators in a single pipeline
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_pipeline_depth: Option<usize>,
+}
+
+impl FeatureProfile {
+    /// Create an unrestricted profile with the given name
+    #[must_use]
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            ..Self::default()
+        }
+    }
+
+    /// Builder method to restrict the allowed tabular operators
+    #[must_use]
+    pub fn allow_operators<I, S>(mut self, operators: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.operators = Some(normalize(operators));
+        self
+    }
+
+    /// Builder method to restrict the allowed `evaluate` plugins
+    #[must_use]
+    pub fn allow_plugins<I, S>(mut self, plugins: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.plugins = Some(normalize(plugins));
+        self
+    }
+
+    /// Builder method to restrict the allowed functions
+    #[must_use]
+    pub fn allow_functions<I, S>(mut self, functions: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.functions = Some(normalize(functions));
+        self
+    }
+
+    /// Builder method to limit the number of operators in a pipeline
+    #[must_use]
+    pub fn max_pipeline_depth(mut self, depth: usize) -> Self {
+        self.max_pipeline_depth = Some(depth);
+        self
+    }
+
+    /// Check if a tabular operator is allowed
+    #[must_use]
+    pub fn allows_operator(&self, operator: &str) -> bool {
+        allows(self.operators.as_ref(), operator)
+    }
+
+    /// Check if an `evaluate` plugin is allowed
+    #[must_use]
+    pub fn allows_plugin(&self, plugin: &str) -> bool {
+        allows(self.plugins.as_ref(), plugin)
+    }
+
+    /// Check if a function is allowed
+    #[must_use]
+    pub fn allows_function(&self, function: &str) -> bool {
+        allows(self.functions.as_ref(), function)
+    }
+
+    /// Combine two profiles into one that only allows what both allow
+    #[must_use]
+    pub fn intersect(&self, other: &Self) -> Self {
+        Self {
+            name: format!("{}+{}", self.name, other.name),
+            operators: intersect_sets(self.operators.as_ref(), other.operators.as_ref()),
+            plugins: intersect_sets(self.plugins.as_ref(), other.plugins.as_ref()),
+            functions: intersect_sets(self.functions.as_ref(), other.functions.as_ref()),
+            max_pipeline_depth: match (self.max_pipeline_depth, other.max_pipeline_depth) {
+                (Some(a), Some(b)) => Some(a.min(b)),
+                (a, b) => a.or(b),
+            },
+        }
+    }
+}
+
+fn normalize<I, S>(names: I) -> BTreeSet<String>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    names
+        .into_iter()
+        .map(|n| n.as_ref().to_ascii_lowercase())
+        .collect()
+}
+
+/// Deserialize an allow-list, lowercasing its names as the builders do
+fn deserialize_names<'de, D>(deserializer: D) -> Result<Option<BTreeSet<String>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let names = Option::<Vec<String>>::deserialize(deserializer)?;
+    Ok(names.map(normalize))
+}
+
+fn allows(set: Option<&BTreeSet<String>>, name: &str) -> bool {
+    set.map_or(true, |s| s.contains(&name.to_ascii_lowercase()))
+}
+
+fn intersect_sets(
+    a: Option<&BTreeSet<String>>,
+    b: Option<&BTreeSet<String>>,
+) -> Option<BTreeSet<String>> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.intersection(b).cloned().collect()),
+        (Some(s), None) | (None, Some(s)) => Some(s.clone()),
+        (None, None) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unrestricted_profile_allows_everything() {
+        let profile = FeatureProfile::new("any");
+        assert!(profile.allows_operator("join"));
+        assert!(profile.allows_plugin("bag_unpack"));
+        assert!(profile.allows_function("strcat"));
+    }
+
+    #[test]
+    fn test_intersect_profiles() {
+        let a = FeatureProfile::new("a")
+            .allow_operators(["where", "project", "summarize"])
+            .max_pipeline_depth(10);
+        let b = FeatureProfile::new("b")
+            .allow_operators(["Where", "Project"])
+            .allow_functions(["strcat"])
+            .max_pipeline_depth(4);
+
+        let combined = a.intersect(&b);
+        assert_eq!(combined.name, "a+b");
+        assert!(combined.allows_operator("where"));
+        assert!(!combined.allows_operator("summarize"));
+        assert!(combined.allows_function("STRCAT"));
+        assert!(!combined.allows_function("tolower"));
+        assert!(combined.allows_plugin("pivot"));
+        assert_eq!(combined.max_pipeline_depth, Some(4));
+    }
+
+    #[test]
+    fn test_profile_serialization_omits_unrestricted() {
+        let json = serde_json::to_value(FeatureProfile::new("p").allow_plugins(["Pivot"])).unwrap();
+        assert_eq!(json["plugins"][0], "pivot");
+        assert!(json.get("operators").is_none());
+    }
+
+    #[test]
+    fn test_deserialized_names_are_lowercased() {
+        let profile: FeatureProfile = serde_json::from_str(
+            r#"{"name":"p","operators":["Where","PROJECT"],"functions":["StrCat"]}"#,
+        )
+        .unwrap();
+        assert!(profile.allows_operator("where"));
+        assert!(profile.allows_operator("Project"));
+        assert!(!profile.allows_operator("join"));
+        assert!(profile.allows_function("strcat"));
+        assert!(profile.plugins.is_none());
+        assert_eq!(
+            profile,
+            FeatureProfile::new("p")
+                .allow_operators(["where", "project"])
+                .allow_functions(["strcat"])
+        );
+    }
+}
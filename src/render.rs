@@ -0,0 +1,1141 @@
+//! Rendering of diagnostics and highlighted queries
+//!
+//! [`Renderer`] formats diagnostics against the query they belong to in the
+//! style of `rustc`: a header with the severity and code, the location, the
+//! offending source line with the span underlined, and notes for related
+//! locations and quick fixes.
+//!
+//! ```text
+//! error[KS142]: The name 'Acount' does not refer to any known column
+//!  --> query:1:25
+//!   |
+//! 1 | SecurityEvent | project Acount
+//!   |                         ^^^^^^
+//!   = help: Did you mean `Account`?
+//! ```
+//!
+//! ANSI colors are available with the `color` feature.
+//!
+//! [`to_html`] turns a query and its classifications into escaped HTML, each
+//! classified span wrapped in a `<span>` styled by a [`Theme`], for embedding
+//! highlighted KQL in reports and wikis. [`to_ansi`] highlights a query for
+//! a terminal with the same themes.
+
+use crate::classification::{ClassificationKind, ClassificationResult, ClassifiedSpan};
+use crate::positions::LineIndex;
+use crate::types::{Diagnostic, DiagnosticSeverity, ValidationResult};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+/// Renders diagnostics as annotated source snippets
+///
+/// # Example
+///
+/// ```
+/// use kql_language_tools::render::Renderer;
+/// use kql_language_tools::ValidationResult;
+///
+/// let renderer = Renderer::new().origin("rules/failed_logons.kql");
+/// let output = renderer.render("T | take 1", &ValidationResult::valid());
+/// assert!(output.is_empty());
+/// ```
+#[derive(Debug, Clone)]
+pub struct Renderer {
+    origin: String,
+    color: bool,
+}
+
+impl Default for Renderer {
+    fn default() -> Self {
+        Self {
+            origin: "query".to_string(),
+            color: false,
+        }
+    }
+}
+
+impl Renderer {
+    /// Create a renderer without colors, naming the source `query`
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Builder method to set the source name shown after `-->` (e.g. a file path)
+    #[must_use]
+    pub fn origin(mut self, origin: impl Into<String>) -> Self {
+        self.origin = origin.into();
+        self
+    }
+
+    /// Builder method to enable ANSI colors
+    #[cfg(feature = "color")]
+    #[must_use]
+    pub fn color(mut self, enabled: bool) -> Self {
+        self.color = enabled;
+        self
+    }
+
+    /// Render every diagnostic of a result, separated by blank lines
+    #[must_use]
+    pub fn render(&self, query: &str, result: &ValidationResult) -> String {
+        result
+            .diagnostics
+            .iter()
+            .map(|d| self.render_diagnostic(query, d))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Render a single diagnostic
+    #[must_use]
+    pub fn render_diagnostic(&self, query: &str, diagnostic: &Diagnostic) -> String {
+        let index = LineIndex::new(query);
+        let (line, column) = position(&index, diagnostic.start);
+        // Tabs are replaced so carets line up
+        let text = index.line(line - 1).unwrap_or_default().replace('\t', " ");
+        let gutter = " ".repeat(line.to_string().len());
+
+        // Underline to the end of the span or of its first line, at least one caret
+        let span_end = if diagnostic.end > diagnostic.start {
+            let (end_line, end_column) = position(&index, diagnostic.end);
+            if end_line == line {
+                end_column
+            } else {
+                text.chars().count() + 1
+            }
+        } else {
+            column
+        };
+        let carets = span_end.saturating_sub(column).max(1);
+
+        let (label, style) = match diagnostic.severity {
+            DiagnosticSeverity::Error => ("error", RED),
+            DiagnosticSeverity::Warning => ("warning", YELLOW),
+            DiagnosticSeverity::Information => ("info", CYAN),
+            DiagnosticSeverity::Hint => ("hint", GREEN),
+        };
+        let code = diagnostic
+            .code
+            .as_deref()
+            .map(|c| format!("[{c}]"))
+            .unwrap_or_default();
+
+        let mut out = String::new();
+        let _ = writeln!(
+            out,
+            "{}: {}",
+            self.paint(style, &format!("{label}{code}")),
+            self.paint(BOLD, &diagnostic.message)
+        );
+        let _ = writeln!(
+            out,
+            "{gutter}{} {}:{line}:{column}",
+            self.paint(BLUE, "-->"),
+            self.origin
+        );
+        let _ = writeln!(out, "{gutter} {}", self.paint(BLUE, "|"));
+        let _ = writeln!(out, "{} {text}", self.paint(BLUE, &format!("{line} |")));
+        let _ = writeln!(
+            out,
+            "{gutter} {} {}{}",
+            self.paint(BLUE, "|"),
+            " ".repeat(column - 1),
+            self.paint(style, &"^".repeat(carets))
+        );
+
+        for related in &diagnostic.related_spans {
+            let (related_line, related_column) = position(&index, related.start);
+            let _ = writeln!(
+                out,
+                "{gutter} {} note: {} (at {related_line}:{related_column})",
+                self.paint(BLUE, "="),
+                related.message
+            );
+        }
+        for fix in &diagnostic.fixes {
+            let _ = writeln!(
+                out,
+                "{gutter} {} help: {}",
+                self.paint(BLUE, "="),
+                fix.title
+            );
+        }
+
+        out
+    }
+
+    /// Wrap text in an ANSI style when colors are enabled
+    fn paint(&self, style: &str, text: &str) -> String {
+        if self.color {
+            format!("{style}{text}{RESET}")
+        } else {
+            text.to_string()
+        }
+    }
+}
+
+/// Render every diagnostic of a result with the default renderer
+#[must_use]
+pub fn render(query: &str, result: &ValidationResult) -> String {
+    Renderer::new().render(query, result)
+}
+
+/// An RGB color
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Color {
+    /// Red component
+    pub r: u8,
+    /// Green component
+    pub g: u8,
+    /// Blue component
+    pub b: u8,
+}
+
+impl Color {
+    /// Create a color from its components
+    #[must_use]
+    pub const fn rgb(r: u8, g: u8, b: u8) -> Self {
+        Self { r, g, b }
+    }
+
+    /// Create a color from a `0xRRGGBB` value
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation)] // keeps one byte per component
+    pub const fn hex(rgb: u32) -> Self {
+        Self::rgb((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8)
+    }
+
+    /// Parse `#rrggbb` or `#rgb`
+    #[must_use]
+    pub fn parse(s: &str) -> Option<Self> {
+        let digits = s.trim().strip_prefix('#')?;
+        if !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+        let value = u32::from_str_radix(digits, 16).ok()?;
+        match digits.len() {
+            6 => Some(Self::hex(value)),
+            // Each digit is doubled: #f80 is #ff8800
+            3 => Some(Self::hex(
+                (value & 0xf00) * 0x1100 + (value & 0x0f0) * 0x110 + (value & 0x00f) * 0x11,
+            )),
+            _ => None,
+        }
+    }
+}
+
+impl Serialize for Color {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Color {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        Self::parse(&s).ok_or_else(|| serde::de::Error::custom(format!("invalid color '{s}'")))
+    }
+}
+
+impl std::fmt::Display for Color {
+    /// Formats the color as `#rrggbb`
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "#{:02x}{:02x}{:02x}", self.r, self.g, self.b)
+    }
+}
+
+/// How text of one classification kind is shown
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Style {
+    /// Text color, or the default color when `None`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub foreground: Option<Color>,
+    /// Bold text
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub bold: bool,
+    /// Italic text
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub italic: bool,
+    /// Underlined text
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub underline: bool,
+}
+
+impl Style {
+    /// A style in the default color without emphasis
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            foreground: None,
+            bold: false,
+            italic: false,
+            underline: false,
+        }
+    }
+
+    /// Builder method to set the text color
+    #[must_use]
+    pub const fn foreground(mut self, color: Color) -> Self {
+        self.foreground = Some(color);
+        self
+    }
+
+    /// Builder method to make the text bold
+    #[must_use]
+    pub const fn bold(mut self) -> Self {
+        self.bold = true;
+        self
+    }
+
+    /// Builder method to make the text italic
+    #[must_use]
+    pub const fn italic(mut self) -> Self {
+        self.italic = true;
+        self
+    }
+
+    /// Builder method to underline the text
+    #[must_use]
+    pub const fn underline(mut self) -> Self {
+        self.underline = true;
+        self
+    }
+
+    /// Check if the style changes nothing
+    #[must_use]
+    pub fn is_plain(&self) -> bool {
+        *self == Self::new()
+    }
+
+    /// The style as inline CSS, such as `color:#0000ff;font-weight:bold`
+    #[must_use]
+    pub fn to_css(&self) -> String {
+        let mut css = Vec::new();
+        if let Some(color) = self.foreground {
+            css.push(format!("color:{color}"));
+        }
+        if self.bold {
+            css.push("font-weight:bold".to_string());
+        }
+        if self.italic {
+            css.push("font-style:italic".to_string());
+        }
+        if self.underline {
+            css.push("text-decoration:underline".to_string());
+        }
+        css.join(";")
+    }
+
+    /// The style as an ANSI escape sequence, or an empty string if plain
+    #[must_use]
+    pub fn to_ansi(&self, depth: ColorDepth) -> String {
+        let mut codes = Vec::new();
+        if self.bold {
+            codes.push("1".to_string());
+        }
+        if self.italic {
+            codes.push("3".to_string());
+        }
+        if self.underline {
+            codes.push("4".to_string());
+        }
+        if let Some(color) = self.foreground {
+            codes.push(depth.foreground(color));
+        }
+        if codes.is_empty() {
+            String::new()
+        } else {
+            format!("\x1b[{}m", codes.join(";"))
+        }
+    }
+}
+
+/// Colors a terminal can show, for [`to_ansi`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum ColorDepth {
+    /// The 16 standard colors, which every color terminal supports
+    Basic,
+    /// The xterm 256-color palette
+    Ansi256,
+    /// 24-bit RGB colors
+    #[default]
+    TrueColor,
+}
+
+impl ColorDepth {
+    /// Guess the terminal's colors from `COLORTERM` and `TERM`
+    ///
+    /// `COLORTERM=truecolor` (or `24bit`) means [`TrueColor`](Self::TrueColor)
+    /// and a `TERM` ending in `256color` means [`Ansi256`](Self::Ansi256);
+    /// anything else falls back to [`Basic`](Self::Basic).
+    #[must_use]
+    pub fn detect() -> Self {
+        let var = |name| std::env::var(name).unwrap_or_default();
+        let colorterm = var("COLORTERM");
+        if colorterm == "truecolor" || colorterm == "24bit" {
+            Self::TrueColor
+        } else if var("TERM").ends_with("256color") {
+            Self::Ansi256
+        } else {
+            Self::Basic
+        }
+    }
+
+    /// SGR parameters setting the foreground to `color`
+    fn foreground(self, color: Color) -> String {
+        match self {
+            Self::TrueColor => format!("38;2;{};{};{}", color.r, color.g, color.b),
+            Self::Ansi256 => format!("38;5;{}", ansi256(color)),
+            Self::Basic => {
+                let index = nearest(color, &BASIC_PALETTE);
+                // 30-37 for the normal colors, 90-97 for the bright ones
+                if index < 8 {
+                    (30 + index).to_string()
+                } else {
+                    (90 + index - 8).to_string()
+                }
+            }
+        }
+    }
+}
+
+/// The xterm defaults for the 16 standard colors
+const BASIC_PALETTE: [Color; 16] = [
+    Color::hex(0x00_0000),
+    Color::hex(0xcd_0000),
+    Color::hex(0x00_cd00),
+    Color::hex(0xcd_cd00),
+    Color::hex(0x00_00ee),
+    Color::hex(0xcd_00cd),
+    Color::hex(0x00_cdcd),
+    Color::hex(0xe5_e5e5),
+    Color::hex(0x7f_7f7f),
+    Color::hex(0xff_0000),
+    Color::hex(0x00_ff00),
+    Color::hex(0xff_ff00),
+    Color::hex(0x5c_5cff),
+    Color::hex(0xff_00ff),
+    Color::hex(0x00_ffff),
+    Color::hex(0xff_ffff),
+];
+
+/// Index of the color in `palette` closest to `color`
+fn nearest(color: Color, palette: &[Color]) -> usize {
+    let distance = |other: &Color| {
+        let d = |a: u8, b: u8| (i32::from(a) - i32::from(b)).pow(2);
+        d(color.r, other.r) + d(color.g, other.g) + d(color.b, other.b)
+    };
+    palette
+        .iter()
+        .enumerate()
+        .min_by_key(|(_, other)| distance(other))
+        .map_or(0, |(index, _)| index)
+}
+
+/// Closest xterm 256-color index, from the 6x6x6 cube or the gray ramp
+fn ansi256(color: Color) -> u8 {
+    const LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+    let level = |c: u8| {
+        LEVELS
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, l)| (i32::from(**l) - i32::from(c)).abs())
+            .map_or(0, |(index, _)| index)
+    };
+    let (r, g, b) = (level(color.r), level(color.g), level(color.b));
+    let cube = Color::rgb(LEVELS[r], LEVELS[g], LEVELS[b]);
+
+    // Grays 232-255 run from 8 to 238 in steps of 10
+    let average = (u32::from(color.r) + u32::from(color.g) + u32::from(color.b)) / 3;
+    let step = average.saturating_sub(3).min(230) / 10;
+    #[allow(clippy::cast_possible_truncation)]
+    let gray_level = (8 + step * 10) as u8;
+    let gray = Color::rgb(gray_level, gray_level, gray_level);
+
+    #[allow(clippy::cast_possible_truncation)]
+    if nearest(color, &[cube, gray]) == 0 {
+        (16 + 36 * r + 6 * g + b) as u8
+    } else {
+        (232 + step) as u8
+    }
+}
+
+/// A mapping from classification kinds to [`Style`]s, used by [`to_html`]
+/// and [`to_ansi`]
+///
+/// Start from a preset and override kinds with [`Theme::style`], or load a
+/// user's theme from JSON: themes serialize as their name, class prefix and
+/// a map from kind name to style.
+///
+/// In HTML, a span also gets a `class` attribute when the theme has a class
+/// prefix; spans with neither a class nor a style, and plain text, are
+/// emitted without a `<span>`. In a terminal, only styles apply.
+///
+/// # Example
+///
+/// ```
+/// use kql_language_tools::render::{Color, Style, Theme};
+/// use kql_language_tools::ClassificationKind;
+///
+/// // class="hl-query-operator", for a site stylesheet
+/// let classes = Theme::classes().class_prefix("hl-");
+///
+/// // style="color:#c586c0", for email clients that drop stylesheets
+/// let theme = Theme::dark_plus().style(
+///     ClassificationKind::Comment,
+///     Style::new().foreground(Color::hex(0x80_8080)).italic(),
+/// );
+///
+/// let json = serde_json::to_string(&theme).unwrap();
+/// assert!(json.contains(r##""Comment":{"foreground":"#808080","italic":true}"##));
+/// let loaded: Theme = serde_json::from_str(&json).unwrap();
+/// assert_eq!(loaded, theme);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Theme {
+    name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    class_prefix: Option<String>,
+    #[serde(default)]
+    styles: BTreeMap<ClassificationKind, Style>,
+    #[serde(skip)]
+    color_depth: ColorDepth,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::classes()
+    }
+}
+
+impl Theme {
+    /// Names of the built-in presets, as accepted by [`Theme::preset`]
+    pub const PRESETS: &'static [&'static str] =
+        &["classes", "plain", "light-plus", "dark-plus", "monokai"];
+
+    /// A built-in preset by name (case-insensitive)
+    #[must_use]
+    pub fn preset(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "classes" => Some(Self::classes()),
+            "plain" => Some(Self::plain()),
+            "light-plus" => Some(Self::light_plus()),
+            "dark-plus" => Some(Self::dark_plus()),
+            "monokai" => Some(Self::monokai()),
+            _ => None,
+        }
+    }
+
+    /// A theme emitting class names such as `kql-keyword` and no styles
+    #[must_use]
+    pub fn classes() -> Self {
+        Self::with_styles("classes", []).class_prefix("kql-")
+    }
+
+    /// A theme without class names or styles, for uncolored output
+    #[must_use]
+    pub fn plain() -> Self {
+        Self::with_styles("plain", [])
+    }
+
+    /// The VS Code Light+ colors, for light backgrounds
+    #[must_use]
+    pub fn light_plus() -> Self {
+        use ClassificationKind as Kind;
+
+        let color = |rgb| Style::new().foreground(Color::hex(rgb));
+        Self::with_styles(
+            "light-plus",
+            [
+                (Kind::Comment, color(0x00_8000)),
+                (Kind::Directive, color(0x80_8080)),
+                (Kind::Literal, color(0x09_8658)),
+                (Kind::StringLiteral, color(0xa3_1515)),
+                (Kind::Type, color(0x26_7f99)),
+                (Kind::Column, color(0x00_1080)),
+                (Kind::Table, color(0x26_7f99).bold()),
+                (Kind::Database, color(0x26_7f99)),
+                (Kind::Cluster, color(0x26_7f99)),
+                (Kind::ScalarFunction, color(0x79_5e26)),
+                (Kind::AggregateFunction, color(0x79_5e26)),
+                (Kind::MaterializedViewFunction, color(0x79_5e26)),
+                (Kind::Plugin, color(0x79_5e26)),
+                (Kind::Keyword, color(0x00_00ff)),
+                (Kind::CommandKeyword, color(0x00_00ff)),
+                (Kind::QueryOperator, color(0x00_00ff)),
+                (Kind::Variable, color(0x00_1080)),
+                (Kind::Parameter, color(0x00_1080)),
+                (Kind::QueryParameter, color(0x00_1080)),
+                (Kind::Option, color(0xaf_00db)),
+                (Kind::ClientDirective, color(0xaf_00db)),
+            ],
+        )
+    }
+
+    /// The VS Code Dark+ colors, for dark backgrounds
+    #[must_use]
+    pub fn dark_plus() -> Self {
+        use ClassificationKind as Kind;
+
+        let color = |rgb| Style::new().foreground(Color::hex(rgb));
+        Self::with_styles(
+            "dark-plus",
+            [
+                (Kind::Comment, color(0x6a_9955)),
+                (Kind::Directive, color(0x9b_9b9b)),
+                (Kind::Literal, color(0xb5_cea8)),
+                (Kind::StringLiteral, color(0xce_9178)),
+                (Kind::Type, color(0x4e_c9b0)),
+                (Kind::Column, color(0x9c_dcfe)),
+                (Kind::Table, color(0x4e_c9b0).bold()),
+                (Kind::Database, color(0x4e_c9b0)),
+                (Kind::Cluster, color(0x4e_c9b0)),
+                (Kind::ScalarFunction, color(0xdc_dcaa)),
+                (Kind::AggregateFunction, color(0xdc_dcaa)),
+                (Kind::MaterializedViewFunction, color(0xdc_dcaa)),
+                (Kind::Plugin, color(0xdc_dcaa)),
+                (Kind::Keyword, color(0x56_9cd6)),
+                (Kind::CommandKeyword, color(0x56_9cd6)),
+                (Kind::QueryOperator, color(0xc5_86c0)),
+                (Kind::Variable, color(0x9c_dcfe)),
+                (Kind::Parameter, color(0x9c_dcfe)),
+                (Kind::QueryParameter, color(0x9c_dcfe)),
+                (Kind::Option, color(0xc5_86c0)),
+                (Kind::ClientDirective, color(0xc5_86c0)),
+            ],
+        )
+    }
+
+    /// The Monokai colors, for dark backgrounds
+    #[must_use]
+    pub fn monokai() -> Self {
+        use ClassificationKind as Kind;
+
+        let color = |rgb| Style::new().foreground(Color::hex(rgb));
+        Self::with_styles(
+            "monokai",
+            [
+                (Kind::Comment, color(0x75_715e)),
+                (Kind::Directive, color(0x75_715e)),
+                (Kind::Literal, color(0xae_81ff)),
+                (Kind::StringLiteral, color(0xe6_db74)),
+                (Kind::Type, color(0x66_d9ef).italic()),
+                (Kind::Table, color(0x66_d9ef)),
+                (Kind::Database, color(0x66_d9ef)),
+                (Kind::Cluster, color(0x66_d9ef)),
+                (Kind::ScalarFunction, color(0xa6_e22e)),
+                (Kind::AggregateFunction, color(0xa6_e22e)),
+                (Kind::MaterializedViewFunction, color(0xa6_e22e)),
+                (Kind::Plugin, color(0xa6_e22e)),
+                (Kind::Keyword, color(0xf9_2672)),
+                (Kind::CommandKeyword, color(0xf9_2672)),
+                (Kind::QueryOperator, color(0xf9_2672)),
+                (Kind::Operator, color(0xf9_2672)),
+                (Kind::ScalarOperator, color(0xf9_2672)),
+                (Kind::Parameter, color(0xfd_971f).italic()),
+                (Kind::QueryParameter, color(0xfd_971f)),
+                (Kind::Option, color(0x66_d9ef)),
+                (Kind::ClientDirective, color(0xae_81ff)),
+            ],
+        )
+    }
+
+    fn with_styles<const N: usize>(name: &str, styles: [(ClassificationKind, Style); N]) -> Self {
+        Self {
+            name: name.to_string(),
+            class_prefix: None,
+            styles: styles.into_iter().collect(),
+            color_depth: ColorDepth::default(),
+        }
+    }
+
+    /// Name of the theme, such as `dark-plus`
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Builder method to rename the theme
+    #[must_use]
+    pub fn named(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    /// Builder method to set the prefix of class names (`kql-` in
+    /// [`Theme::classes`], none in the other presets)
+    #[must_use]
+    pub fn class_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.class_prefix = Some(prefix.into());
+        self
+    }
+
+    /// Builder method to emit no class names
+    #[must_use]
+    pub fn without_classes(mut self) -> Self {
+        self.class_prefix = None;
+        self
+    }
+
+    /// Builder method to set the style of a kind, replacing any it had
+    #[must_use]
+    pub fn style(mut self, kind: ClassificationKind, style: Style) -> Self {
+        self.styles.insert(kind, style);
+        self
+    }
+
+    /// Builder method to set the colors [`to_ansi`] may use
+    /// ([`ColorDepth::TrueColor`] by default)
+    ///
+    /// The color depth describes the terminal rather than the theme, so it
+    /// is not serialized.
+    #[must_use]
+    pub fn color_depth(mut self, depth: ColorDepth) -> Self {
+        self.color_depth = depth;
+        self
+    }
+
+    /// Get the style of a kind, if the theme styles it
+    #[must_use]
+    pub fn get_style(&self, kind: ClassificationKind) -> Option<&Style> {
+        self.styles.get(&kind).filter(|style| !style.is_plain())
+    }
+
+    /// Every styled kind and its style, in [`ClassificationKind`] order
+    pub fn styles(&self) -> impl Iterator<Item = (ClassificationKind, &Style)> {
+        self.styles.iter().map(|(kind, style)| (*kind, style))
+    }
+
+    /// Opening tag for a span of `kind`, if it is styled at all
+    fn open_tag(&self, kind: ClassificationKind) -> Option<String> {
+        if kind == ClassificationKind::PlainText {
+            return None;
+        }
+        let mut tag = String::from("<span");
+        if let Some(prefix) = &self.class_prefix {
+            let _ = write!(
+                tag,
+                " class=\"{}{}\"",
+                escape_html(prefix),
+                class_name(kind)
+            );
+        }
+        if let Some(style) = self.get_style(kind) {
+            let _ = write!(tag, " style=\"{}\"", escape_html(&style.to_css()));
+        }
+        (tag.len() > "<span".len()).then(|| tag + ">")
+    }
+}
+
+/// Render a query as HTML, wrapping each classified span in a `<span>`
+///
+/// Text is escaped and line breaks are kept, so the result belongs in a
+/// `<pre>` or `<code>` element. Spans are character offsets as the
+/// classification APIs return them (converted from the native UTF-16
+/// offsets); overlapping spans are clipped to the text the previous span
+/// left.
+///
+/// # Example
+///
+/// ```
+/// use kql_language_tools::render::{self, Theme};
+/// use kql_language_tools::{ClassificationKind, ClassificationResult, ClassifiedSpan};
+///
+/// let query = "T | where Name == '<b>'";
+/// let classifications = ClassificationResult {
+///     spans: vec![
+///         ClassifiedSpan { start: 4, length: 5, kind: ClassificationKind::QueryOperator },
+///         ClassifiedSpan { start: 18, length: 5, kind: ClassificationKind::StringLiteral },
+///     ],
+/// };
+///
+/// let html = render::to_html(query, &classifications, &Theme::classes());
+/// assert_eq!(
+///     html,
+///     "T | <span class=\"kql-query-operator\">where</span> Name == \
+///      <span class=\"kql-string-literal\">&#39;&lt;b&gt;&#39;</span>"
+/// );
+/// ```
+#[must_use]
+pub fn to_html(query: &str, classifications: &ClassificationResult, theme: &Theme) -> String {
+    let mut out = String::with_capacity(query.len() * 2);
+    for_each_span(query, &classifications.spans, |kind, text| {
+        match kind.and_then(|kind| theme.open_tag(kind)) {
+            Some(tag) => {
+                out.push_str(&tag);
+                out.push_str(&escape_html(text));
+                out.push_str("</span>");
+            }
+            None => out.push_str(&escape_html(text)),
+        }
+    });
+    out
+}
+
+/// Render a query with ANSI colors, styling each classified span
+///
+/// Text between spans, plain text, and kinds the theme does not style are
+/// printed as is. Styles are reset at the end of each line, so the output
+/// can be split into lines (e.g. for a TUI) without colors leaking. Colors
+/// are limited to the theme's [`ColorDepth`]. Spans are handled as in
+/// [`to_html`].
+///
+/// # Example
+///
+/// ```
+/// use kql_language_tools::render::{self, ColorDepth, Theme};
+/// use kql_language_tools::{ClassificationKind, ClassificationResult, ClassifiedSpan};
+///
+/// let query = "T | take 10";
+/// let classifications = ClassificationResult {
+///     spans: vec![ClassifiedSpan { start: 4, length: 4, kind: ClassificationKind::QueryOperator }],
+/// };
+///
+/// let theme = Theme::light_plus().color_depth(ColorDepth::Ansi256);
+/// assert_eq!(
+///     render::to_ansi(query, &classifications, &theme),
+///     "T | \x1b[38;5;21mtake\x1b[0m 10"
+/// );
+/// ```
+#[must_use]
+pub fn to_ansi(query: &str, classifications: &ClassificationResult, theme: &Theme) -> String {
+    let mut out = String::with_capacity(query.len() * 2);
+    for_each_span(query, &classifications.spans, |kind, text| {
+        let escape = kind
+            .and_then(|kind| theme.get_style(kind))
+            .map(|style| style.to_ansi(theme.color_depth))
+            .unwrap_or_default();
+        if escape.is_empty() {
+            out.push_str(text);
+            return;
+        }
+        for (i, line) in text.split('\n').enumerate() {
+            if i > 0 {
+                out.push('\n');
+            }
+            if !line.is_empty() {
+                let _ = write!(out, "{escape}{line}{RESET}");
+            }
+        }
+    });
+    out
+}
+
+/// Call `f` for each piece of the query in order: classified spans with
+/// their kind, and the text between them with `None`
+fn for_each_span(
+    query: &str,
+    spans: &[ClassifiedSpan],
+    mut f: impl FnMut(Option<ClassificationKind>, &str),
+) {
+    let index = LineIndex::new(query);
+    let mut spans: Vec<_> = spans.iter().collect();
+    spans.sort_by_key(|span| span.start);
+
+    let mut last = 0;
+    for span in spans {
+        let start = index.char_to_byte(span.start).max(last);
+        let end = index.char_to_byte(span.start + span.length);
+        if start >= end {
+            continue;
+        }
+        if start > last {
+            f(None, &query[last..start]);
+        }
+        f(Some(span.kind), &query[start..end]);
+        last = end;
+    }
+    if last < query.len() {
+        f(None, &query[last..]);
+    }
+}
+
+/// Escape text for HTML content and attribute values
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// CSS class name of a kind, after the theme's prefix
+fn class_name(kind: ClassificationKind) -> &'static str {
+    use ClassificationKind as Kind;
+
+    match kind {
+        Kind::PlainText => "plain-text",
+        Kind::Comment => "comment",
+        Kind::Punctuation => "punctuation",
+        Kind::Directive => "directive",
+        Kind::Literal => "literal",
+        Kind::StringLiteral => "string-literal",
+        Kind::Type => "type",
+        Kind::Identifier => "identifier",
+        Kind::Column => "column",
+        Kind::Table => "table",
+        Kind::Database => "database",
+        Kind::ScalarFunction => "scalar-function",
+        Kind::AggregateFunction => "aggregate-function",
+        Kind::Keyword => "keyword",
+        Kind::Operator => "operator",
+        Kind::Variable => "variable",
+        Kind::Parameter => "parameter",
+        Kind::CommandKeyword => "command-keyword",
+        Kind::QueryOperator => "query-operator",
+        Kind::ScalarOperator => "scalar-operator",
+        Kind::MaterializedViewFunction => "materialized-view-function",
+        Kind::Plugin => "plugin",
+        Kind::Option => "option",
+        Kind::ClientDirective => "client-directive",
+        Kind::QueryParameter => "query-parameter",
+        Kind::Cluster => "cluster",
+    }
+}
+
+const RESET: &str = "\x1b[0m";
+const BOLD: &str = "\x1b[1m";
+const RED: &str = "\x1b[1;31m";
+const YELLOW: &str = "\x1b[1;33m";
+const CYAN: &str = "\x1b[1;36m";
+const GREEN: &str = "\x1b[1;32m";
+const BLUE: &str = "\x1b[1;34m";
+
+/// Line and column (both 1-based) of a character offset
+fn position(index: &LineIndex, offset: usize) -> (usize, usize) {
+    let position = index.line_col(offset);
+    (position.line + 1, position.col + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Fix, RelatedInfo};
+
+    fn diagnostic(start: usize, end: usize) -> Diagnostic {
+        Diagnostic {
+            message: "The name 'Acount' does not refer to any known column".to_string(),
+            severity: DiagnosticSeverity::Error,
+            start,
+            end,
+            line: 0,
+            column: 0,
+            code: Some("KS142".to_string()),
+            category: None,
+            fixes: Vec::new(),
+            related_spans: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_render_snippet() {
+        let query = "let x = 1;\r\nSecurityEvent | project Acount";
+        let mut d = diagnostic(36, 42);
+        d.fixes.push(Fix {
+            title: "Did you mean `Account`?".to_string(),
+            edits: Vec::new(),
+        });
+        d.related_spans.push(RelatedInfo {
+            message: "'x' is declared here".to_string(),
+            start: 4,
+            end: 5,
+        });
+
+        let expected = "\
+error[KS142]: The name 'Acount' does not refer to any known column
+ --> rule.kql:2:25
+  |
+2 | SecurityEvent | project Acount
+  |                         ^^^^^^
+  = note: 'x' is declared here (at 1:5)
+  = help: Did you mean `Account`?
+";
+        assert_eq!(
+            Renderer::new()
+                .origin("rule.kql")
+                .render_diagnostic(query, &d),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_to_html() {
+        use crate::classification::ClassifiedSpan;
+
+        let span = |start, length, kind| ClassifiedSpan {
+            start,
+            length,
+            kind,
+        };
+        // Offsets are characters: 'é' is one character but two bytes
+        let query = "print s = 'é' // a & b";
+        let classifications = ClassificationResult {
+            spans: vec![
+                span(14, 8, ClassificationKind::Comment),
+                span(0, 5, ClassificationKind::Keyword),
+                span(10, 3, ClassificationKind::StringLiteral),
+                span(12, 1, ClassificationKind::Punctuation),
+                span(6, 1, ClassificationKind::PlainText),
+            ],
+        };
+
+        assert_eq!(
+            to_html(
+                query,
+                &classifications,
+                &Theme::classes().class_prefix("hl-")
+            ),
+            "<span class=\"hl-keyword\">print</span> s = \
+             <span class=\"hl-string-literal\">&#39;é&#39;</span> \
+             <span class=\"hl-comment\">// a &amp; b</span>"
+        );
+
+        let theme = Theme::light_plus().style(ClassificationKind::Keyword, Style::new().bold());
+        assert_eq!(
+            to_html(query, &classifications, &theme),
+            "<span style=\"font-weight:bold\">print</span> s = \
+             <span style=\"color:#a31515\">&#39;é&#39;</span> \
+             <span style=\"color:#008000\">// a &amp; b</span>"
+        );
+        assert_eq!(
+            to_html(query, &classifications, &Theme::classes().without_classes()),
+            "print s = &#39;é&#39; // a &amp; b"
+        );
+
+        // Native offsets count the emoji as two UTF-16 units
+        let query = "print '😀' // x";
+        let mut classifications: ClassificationResult = serde_json::from_str(
+            r#"{"spans":[
+                {"start":6,"length":4,"kind":"StringLiteral"},
+                {"start":11,"length":4,"kind":"Comment"}
+            ]}"#,
+        )
+        .unwrap();
+        crate::positions::from_native(query, &mut classifications);
+        assert_eq!(
+            to_html(query, &classifications, &Theme::classes()),
+            "print <span class=\"kql-string-literal\">&#39;😀&#39;</span> \
+             <span class=\"kql-comment\">// x</span>"
+        );
+    }
+
+    #[test]
+    fn test_theme_presets() {
+        for name in Theme::PRESETS {
+            let theme = Theme::preset(name).unwrap();
+            assert_eq!(theme.name(), *name);
+
+            let json = serde_json::to_string(&theme).unwrap();
+            assert_eq!(serde_json::from_str::<Theme>(&json).unwrap(), theme);
+        }
+        assert!(Theme::preset("Monokai").is_some());
+        assert!(Theme::preset("solarized").is_none());
+
+        let dark = Theme::dark_plus();
+        assert_eq!(
+            dark.get_style(ClassificationKind::StringLiteral),
+            Some(&Style::new().foreground(Color::hex(0xce_9178)))
+        );
+        assert_eq!(dark.get_style(ClassificationKind::PlainText), None);
+        assert_eq!(Theme::plain().styles().count(), 0);
+
+        let theme: Theme = serde_json::from_str(
+            r##"{"name":"mine","class_prefix":"k-","styles":{
+                "Keyword":{"foreground":"#F80","bold":true},
+                "Comment":{"italic":true}
+            }}"##,
+        )
+        .unwrap();
+        assert_eq!(
+            theme.get_style(ClassificationKind::Keyword),
+            Some(&Style::new().foreground(Color::hex(0xff_8800)).bold())
+        );
+        let kinds: Vec<_> = theme.styles().map(|(kind, _)| kind).collect();
+        assert_eq!(
+            kinds,
+            [ClassificationKind::Comment, ClassificationKind::Keyword]
+        );
+        assert!(serde_json::from_str::<Theme>(
+            r#"{"name":"bad","styles":{"Keyword":{"foreground":"blue"}}}"#
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_to_ansi() {
+        use crate::classification::ClassifiedSpan;
+
+        let query = "print s = 'a\nb' // c";
+        let classifications = ClassificationResult {
+            spans: vec![
+                ClassifiedSpan {
+                    start: 0,
+                    length: 5,
+                    kind: ClassificationKind::Keyword,
+                },
+                ClassifiedSpan {
+                    start: 10,
+                    length: 5,
+                    kind: ClassificationKind::StringLiteral,
+                },
+                ClassifiedSpan {
+                    start: 6,
+                    length: 1,
+                    kind: ClassificationKind::Identifier,
+                },
+            ],
+        };
+        let theme = Theme::classes()
+            .style(
+                ClassificationKind::Keyword,
+                Style::new().foreground(Color::hex(0xff_8000)).bold(),
+            )
+            .style(
+                ClassificationKind::StringLiteral,
+                Style::new().foreground(Color::hex(0x80_8080)),
+            );
+
+        assert_eq!(
+            to_ansi(query, &classifications, &theme),
+            "\x1b[1;38;2;255;128;0mprint\x1b[0m s = \
+             \x1b[38;2;128;128;128m'a\x1b[0m\n\x1b[38;2;128;128;128mb'\x1b[0m // c"
+        );
+        assert_eq!(
+            to_ansi(
+                query,
+                &classifications,
+                &theme.clone().color_depth(ColorDepth::Ansi256)
+            ),
+            "\x1b[1;38;5;208mprint\x1b[0m s = \
+             \x1b[38;5;244m'a\x1b[0m\n\x1b[38;5;244mb'\x1b[0m // c"
+        );
+        assert!(to_ansi(
+            query,
+            &classifications,
+            &theme.color_depth(ColorDepth::Basic)
+        )
+        .starts_with("\x1b[1;33mprint\x1b[0m"));
+        assert_eq!(to_ansi(query, &classifications, &Theme::classes()), query);
+    }
+
+    #[test]
+    fn test_empty_and_multiline_spans() {
+        let query = "T\n| where (\nx";
+
+        let empty = Renderer::new().render_diagnostic(query, &diagnostic(13, 13));
+        assert!(empty.contains(" --> query:3:2\n"));
+        assert!(empty.ends_with("3 | x\n  |  ^\n"));
+
+        let multiline = Renderer::new().render_diagnostic(query, &diagnostic(8, 13));
+        assert!(multiline.ends_with("2 | | where (\n  |       ^^^\n"));
+    }
+}
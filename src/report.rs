@@ -0,0 +1,248 @@
+//! miette integration
+//!
+//! Enabled by the `miette` feature. [`Error`] implements
+//! [`miette::Diagnostic`], and validation diagnostics convert into
+//! [`KqlReport`]s that carry the query as source code, so a miette report
+//! handler prints them with labeled spans:
+//!
+//! ```no_run
+//! use kql_language_tools::KqlValidator;
+//!
+//! fn main() -> miette::Result<()> {
+//!     let query = "SecurityEvent | where";
+//!     let result = KqlValidator::new()?.validate_syntax(query)?;
+//!     if let Some(report) = result.to_reports(query).into_iter().next() {
+//!         return Err(report.into());
+//!     }
+//!     Ok(())
+//! }
+//! ```
+
+use crate::error::Error;
+use crate::positions::LineIndex;
+use crate::types::{Diagnostic, DiagnosticSeverity, ValidationResult};
+use miette::{LabeledSpan, NamedSource, Severity, SourceCode, SourceSpan};
+use std::fmt;
+
+/// A validation diagnostic with its source query, reportable through miette
+///
+/// The primary label covers the diagnostic's span, related spans become
+/// secondary labels, and fix titles are shown as help.
+#[derive(Debug, thiserror::Error)]
+#[error("{message}")]
+pub struct KqlReport {
+    message: String,
+    code: Option<String>,
+    severity: DiagnosticSeverity,
+    source_code: NamedSource<String>,
+    labels: Vec<LabeledSpan>,
+    help: Option<String>,
+}
+
+impl KqlReport {
+    /// Create a report for a diagnostic of `query`, naming the source `query`
+    #[must_use]
+    pub fn new(query: &str, diagnostic: &Diagnostic) -> Self {
+        let index = LineIndex::new(query);
+        let span = |start: usize, end: usize| {
+            let start = index.char_to_byte(start);
+            SourceSpan::from(start..index.char_to_byte(end).max(start))
+        };
+
+        let mut labels = vec![LabeledSpan::new_primary_with_span(
+            None,
+            span(diagnostic.start, diagnostic.end),
+        )];
+        labels.extend(diagnostic.related_spans.iter().map(|related| {
+            LabeledSpan::new_with_span(
+                Some(related.message.clone()),
+                span(related.start, related.end),
+            )
+        }));
+
+        let help = (!diagnostic.fixes.is_empty()).then(|| {
+            diagnostic
+                .fixes
+                .iter()
+                .map(|fix| fix.title.as_str())
+                .collect::<Vec<_>>()
+                .join("\n")
+        });
+
+        Self {
+            message: diagnostic.message.clone(),
+            code: diagnostic.code.clone(),
+            severity: diagnostic.severity,
+            source_code: NamedSource::new("query", query.to_string()),
+            labels,
+            help,
+        }
+    }
+
+    /// Builder method to set the source name shown in the report (e.g. a file path)
+    #[must_use]
+    pub fn with_source_name(mut self, name: impl AsRef<str>) -> Self {
+        self.source_code = NamedSource::new(name, self.source_code.inner().clone());
+        self
+    }
+}
+
+impl miette::Diagnostic for KqlReport {
+    fn code<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        self.code
+            .as_ref()
+            .map(|code| Box::new(code) as Box<dyn fmt::Display>)
+    }
+
+    fn severity(&self) -> Option<Severity> {
+        Some(match self.severity {
+            DiagnosticSeverity::Error => Severity::Error,
+            DiagnosticSeverity::Warning => Severity::Warning,
+            DiagnosticSeverity::Information | DiagnosticSeverity::Hint => Severity::Advice,
+        })
+    }
+
+    fn help<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        self.help
+            .as_ref()
+            .map(|help| Box::new(help) as Box<dyn fmt::Display>)
+    }
+
+    fn source_code(&self) -> Option<&dyn SourceCode> {
+        Some(&self.source_code)
+    }
+
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = LabeledSpan> + '_>> {
+        Some(Box::new(self.labels.iter().cloned()))
+    }
+}
+
+impl Diagnostic {
+    /// Convert to a miette report against the query it was reported for
+    #[must_use]
+    pub fn to_report(&self, query: &str) -> KqlReport {
+        KqlReport::new(query, self)
+    }
+}
+
+impl ValidationResult {
+    /// Convert every diagnostic to a miette report against the validated query
+    #[must_use]
+    pub fn to_reports(&self, query: &str) -> Vec<KqlReport> {
+        self.diagnostics
+            .iter()
+            .map(|d| KqlReport::new(query, d))
+            .collect()
+    }
+}
+
+impl miette::Diagnostic for Error {
+    fn code<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        let code = match self {
+            Self::LibraryNotFound { .. } => "kql_language_tools::library_not_found",
+            Self::LibraryAlreadyLoaded { .. } => "kql_language_tools::library_already_loaded",
+            Self::LibraryLoadFailed { .. } => "kql_language_tools::library_load_failed",
+            Self::SymbolNotFound { .. } => "kql_language_tools::symbol_not_found",
+            Self::AbiMismatch { .. } => "kql_language_tools::abi_mismatch",
+            Self::InitializationFailed { .. } => "kql_language_tools::initialization_failed",
+            Self::NativeError { .. } => "kql_language_tools::native_error",
+            Self::BufferTooSmall { .. } => "kql_language_tools::buffer_too_small",
+            Self::Json(_) => "kql_language_tools::json",
+            #[cfg(feature = "cbor")]
+            Self::Cbor(_) => "kql_language_tools::cbor",
+            #[cfg(feature = "yaml")]
+            Self::Yaml(_) => "kql_language_tools::yaml",
+            #[cfg(feature = "toml")]
+            Self::Toml { .. } => "kql_language_tools::toml",
+            Self::Io(_) => "kql_language_tools::io",
+            Self::UnsupportedSchemaFormat { .. } => "kql_language_tools::unsupported_schema_format",
+            Self::Utf8(_) => "kql_language_tools::utf8",
+            Self::Cancelled => "kql_language_tools::cancelled",
+            Self::TimedOut => "kql_language_tools::timed_out",
+            Self::Busy => "kql_language_tools::busy",
+            Self::Worker { .. } => "kql_language_tools::worker",
+            Self::Wasm { .. } => "kql_language_tools::wasm",
+            Self::Cluster { .. } => "kql_language_tools::cluster",
+            Self::Csl { .. } => "kql_language_tools::csl",
+            Self::UnknownType { .. } => "kql_language_tools::unknown_type",
+            Self::NotInitialized => "kql_language_tools::not_initialized",
+            Self::InvalidInput { .. } => "kql_language_tools::invalid_input",
+            Self::Internal { .. } => "kql_language_tools::internal",
+        };
+        Some(Box::new(code))
+    }
+
+    fn help<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        let help = match self {
+            Self::LibraryNotFound { .. } | Self::LibraryLoadFailed { .. } => {
+                "Build the native library with `cargo build` or point KQL_LANGUAGE_TOOLS_PATH at it; `kql_language_tools::diagnose_loading()` shows what was searched"
+            }
+            Self::LibraryAlreadyLoaded { .. } => {
+                "Set the library path before creating the first validator"
+            }
+            Self::UnknownType { .. } => {
+                "KQL types are bool, datetime, decimal, dynamic, guid, int, long, real, string and timespan"
+            }
+            Self::UnsupportedSchemaFormat { .. } => {
+                "Use .json, .kql or .csl, .yaml or .yml with the `yaml` feature, or .toml with the `toml` feature"
+            }
+            Self::SymbolNotFound { .. } => {
+                "The native library is older than this crate; rebuild it from dotnet/"
+            }
+            Self::AbiMismatch { .. } => {
+                "The native library was built from a different crate version; rebuild it from dotnet/"
+            }
+            Self::Busy => {
+                "A cancelled or timed-out call is still running natively; retry later or use a clone of the validator"
+            }
+            _ => return None,
+        };
+        Some(Box::new(help))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Fix, RelatedInfo};
+    use miette::Diagnostic as _;
+
+    #[test]
+    fn test_report_spans_are_bytes() {
+        let query = "print é = 1 | project Acount";
+        let diagnostic = Diagnostic {
+            message: "The name 'Acount' does not refer to any known column".to_string(),
+            severity: DiagnosticSeverity::Warning,
+            start: 22,
+            end: 28,
+            line: 1,
+            column: 23,
+            code: Some("KS142".to_string()),
+            category: None,
+            fixes: vec![Fix {
+                title: "Did you mean `Account`?".to_string(),
+                edits: Vec::new(),
+            }],
+            related_spans: vec![RelatedInfo {
+                message: "'é' is declared here".to_string(),
+                start: 6,
+                end: 7,
+            }],
+        };
+
+        let report = diagnostic.to_report(query).with_source_name("rule.kql");
+        let spans: Vec<(usize, usize, bool)> = report
+            .labels()
+            .unwrap()
+            .map(|l| (l.offset(), l.len(), l.primary()))
+            .collect();
+        assert_eq!(spans, [(23, 6, true), (6, 2, false)]);
+        assert_eq!(report.code().unwrap().to_string(), "KS142");
+        assert_eq!(report.severity(), Some(Severity::Warning));
+        assert_eq!(
+            report.help().unwrap().to_string(),
+            "Did you mean `Account`?"
+        );
+        assert_eq!(&query[23..29], "Acount");
+    }
+}
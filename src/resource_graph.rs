@@ -0,0 +1,113 @@
+//! Azure Resource Graph table schemas
+//!
+//! Enabled by the `resource-graph` feature. [`schema`] defines the Resource
+//! Graph tables with their well-known columns; pair it with
+//! [`Dialect::ResourceGraph`](crate::Dialect::ResourceGraph) to apply the
+//! service's operator and join restrictions:
+//!
+//! ```
+//! use kql_language_tools::resource_graph;
+//!
+//! let schema = resource_graph::schema();
+//! let resources = schema.get_table("Resources").unwrap();
+//! assert!(resources.get_column("subscriptionId").is_some());
+//! assert!(schema.get_table("ResourceContainers").is_some());
+//! ```
+//!
+//! Every table has the columns shared by Azure Resource Manager resources.
+//! Type-specific fields live under the `dynamic` `properties` column, as they
+//! do in the service.
+
+use crate::kql_type::KqlType;
+use crate::schema::{Column, Schema, Table};
+
+/// Names of the Resource Graph tables
+pub const TABLES: &[&str] = &[
+    "Resources",
+    "ResourceContainers",
+    "AdvisorResources",
+    "AlertsManagementResources",
+    "AppServiceResources",
+    "AuthorizationResources",
+    "ExtendedLocationResources",
+    "GuestConfigurationResources",
+    "HealthResources",
+    "InsightsResources",
+    "IoTSecurityResources",
+    "KubernetesConfigurationResources",
+    "MaintenanceResources",
+    "NetworkResources",
+    "PatchAssessmentResources",
+    "PatchInstallationResources",
+    "PolicyResources",
+    "RecoveryServicesResources",
+    "SecurityResources",
+    "ServiceHealthResources",
+    "SpotResources",
+];
+
+/// Columns every Resource Graph table has
+const COLUMNS: &[(&str, KqlType)] = &[
+    ("id", KqlType::String),
+    ("name", KqlType::String),
+    ("type", KqlType::String),
+    ("tenantId", KqlType::String),
+    ("kind", KqlType::String),
+    ("location", KqlType::String),
+    ("resourceGroup", KqlType::String),
+    ("subscriptionId", KqlType::String),
+    ("managedBy", KqlType::String),
+    ("sku", KqlType::Dynamic),
+    ("plan", KqlType::Dynamic),
+    ("properties", KqlType::Dynamic),
+    ("tags", KqlType::Dynamic),
+    ("identity", KqlType::Dynamic),
+    ("zones", KqlType::Dynamic),
+    ("extendedLocation", KqlType::Dynamic),
+];
+
+/// Every Resource Graph table
+///
+/// The schema has no database name; merge it with other schemas using
+/// [`Schema::merge`].
+#[must_use]
+pub fn schema() -> Schema {
+    let mut schema = Schema::new();
+    for name in TABLES {
+        let mut table = Table::new(*name).folder("ResourceGraph");
+        table.columns = COLUMNS
+            .iter()
+            .map(|(column, data_type)| Column::new(*column, data_type.clone()))
+            .collect();
+        schema = schema.table(table);
+    }
+    schema
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resource_graph_schema() {
+        let schema = schema();
+        assert_eq!(schema.validate(), []);
+        assert_eq!(schema.tables.len(), TABLES.len());
+
+        let resources = schema.get_table("resources").unwrap();
+        assert_eq!(resources.name, "Resources");
+        assert_eq!(
+            resources.get_column("properties").unwrap().data_type,
+            KqlType::Dynamic
+        );
+        assert_eq!(
+            schema
+                .get_table("SecurityResources")
+                .unwrap()
+                .get_column("type")
+                .unwrap()
+                .data_type,
+            KqlType::String
+        );
+    }
+}
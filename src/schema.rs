@@ -4,13 +4,23 @@
 //! schema-aware validation. The schema includes tables, columns,
 //! and user-defined functions.
 
+use crate::error::Error;
+use crate::kql_type::KqlType;
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::{self, Write as _};
+use std::hash::Hasher;
 
 /// Database schema for semantic validation
 ///
 /// Contains definitions of tables, columns, and functions that
 /// the KQL validator should be aware of when performing semantic
 /// analysis.
+///
+/// Two schemas are equal when they define the same tables, columns,
+/// functions and scopes, in any order: column order within a table is
+/// ignored too, though comparing two [`Table`]s directly does not ignore
+/// it. See [`Schema::fingerprint`].
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Schema {
     /// Database name (optional)
@@ -24,6 +34,15 @@ pub struct Schema {
     /// User-defined functions
     #[serde(default)]
     pub functions: Vec<Function>,
+
+    /// Cross-resource scopes (Log Analytics `workspace()`, `app()`, ...)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub scopes: Vec<ResourceScope>,
+
+    /// Ambient query parameters, as supplied by `declare query_parameters`
+    /// or the client running the query
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub query_parameters: Vec<Parameter>,
 }
 
 impl Schema {
@@ -68,6 +87,34 @@ impl Schema {
         self
     }
 
+    /// Builder method to add a cross-resource scope
+    #[must_use]
+    pub fn scope(mut self, scope: ResourceScope) -> Self {
+        self.scopes.push(scope);
+        self
+    }
+
+    /// Builder method to add an ambient query parameter
+    ///
+    /// Queries may reference the parameter by name without declaring it, as
+    /// when the client passes it alongside the query.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use kql_language_tools::{Parameter, Schema};
+    ///
+    /// let schema = Schema::new()
+    ///     .query_parameter(Parameter::new("_startTime", "datetime"))
+    ///     .query_parameter(Parameter::new("_userName", "string"));
+    /// assert!(schema.get_query_parameter("_STARTTIME").is_some());
+    /// ```
+    #[must_use]
+    pub fn query_parameter(mut self, parameter: Parameter) -> Self {
+        self.query_parameters.push(parameter);
+        self
+    }
+
     /// Check if the schema is empty
     #[must_use]
     pub fn is_empty(&self) -> bool {
@@ -87,10 +134,392 @@ impl Schema {
             .iter()
             .find(|f| f.name.eq_ignore_ascii_case(name))
     }
+
+    /// Get an ambient query parameter by name
+    #[must_use]
+    pub fn get_query_parameter(&self, name: &str) -> Option<&Parameter> {
+        self.query_parameters
+            .iter()
+            .find(|p| p.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Get a cross-resource scope by kind and name
+    #[must_use]
+    pub fn get_scope(&self, kind: ScopeKind, name: &str) -> Option<&ResourceScope> {
+        self.scopes
+            .iter()
+            .find(|s| s.kind == kind && s.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Get a hash of the schema's content that ignores ordering
+    ///
+    /// Tables, columns (within each table), functions, scopes and query
+    /// parameters may be listed in any order; function parameters are
+    /// positional and their order counts. Schemas that compare equal have
+    /// the same fingerprint. The hash is stable across runs and crate
+    /// builds, so it can key caches and persisted data.
+    ///
+    /// The schema is hashed in place, without copying or serializing it.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use kql_language_tools::{Schema, Table};
+    ///
+    /// let a = Schema::new()
+    ///     .table(Table::new("A").with_column("x", "long"))
+    ///     .table(Table::new("B"));
+    /// let b = Schema::new()
+    ///     .table(Table::new("B"))
+    ///     .table(Table::new("A").with_column("x", "long"));
+    ///
+    /// assert_eq!(a.fingerprint(), b.fingerprint());
+    /// assert_eq!(a, b);
+    /// ```
+    #[must_use]
+    pub fn fingerprint(&self) -> u64 {
+        let mut hasher = StableHasher::default();
+        self.stable_hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Serialize the schema for the native library, checking its types
+    pub(crate) fn to_native_json(&self) -> Result<String, Error> {
+        self.check_types()?;
+        Ok(serde_json::to_string(self)?)
+    }
+}
+
+impl PartialEq for Schema {
+    fn eq(&self, other: &Self) -> bool {
+        self.database == other.database
+            && same_items(&self.tables, &other.tables, |t| &t.name, same_table)
+            && same_items(&self.functions, &other.functions, |f| &f.name, Function::eq)
+            && same_items(
+                &self.scopes,
+                &other.scopes,
+                |s| &s.name,
+                |a, b| {
+                    a.kind == b.kind && same_items(&a.tables, &b.tables, |t| &t.name, same_table)
+                },
+            )
+            && same_items(
+                &self.query_parameters,
+                &other.query_parameters,
+                |p| &p.name,
+                Parameter::eq,
+            )
+    }
+}
+
+impl Eq for Schema {}
+
+impl std::hash::Hash for Schema {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_u64(self.fingerprint());
+    }
+}
+
+/// Check that two lists hold the same items in any order
+///
+/// Items are matched within groups of the same name, so only same-named
+/// items are compared with `eq`.
+fn same_items<T>(a: &[T], b: &[T], name: impl Fn(&T) -> &str, eq: impl Fn(&T, &T) -> bool) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    let mut candidates: Vec<(&T, bool)> = b.iter().map(|item| (item, false)).collect();
+    candidates.sort_by(|(x, _), (y, _)| name(x).cmp(name(y)));
+    a.iter().all(|item| {
+        let start = candidates.partition_point(|(other, _)| name(other) < name(item));
+        candidates[start..]
+            .iter_mut()
+            .take_while(|(other, _)| name(other) == name(item))
+            .find(|(other, used)| !used && eq(item, other))
+            .map(|(_, used)| *used = true)
+            .is_some()
+    })
+}
+
+/// Compare tables regardless of column order
+fn same_table(a: &Table, b: &Table) -> bool {
+    a.name == b.name
+        && a.description == b.description
+        && a.folder == b.folder
+        && same_items(&a.columns, &b.columns, |c| &c.name, Column::eq)
+}
+
+/// Content fed to a [`StableHasher`] field by field
+trait StableHash {
+    fn stable_hash(&self, hasher: &mut StableHasher);
+}
+
+impl StableHash for str {
+    fn stable_hash(&self, hasher: &mut StableHasher) {
+        hasher.write(self.as_bytes());
+        // Never part of UTF-8, so adjacent strings cannot run together
+        hasher.write_u8(0xff);
+    }
+}
+
+impl StableHash for String {
+    fn stable_hash(&self, hasher: &mut StableHasher) {
+        self.as_str().stable_hash(hasher);
+    }
+}
+
+impl<T: StableHash> StableHash for Option<T> {
+    fn stable_hash(&self, hasher: &mut StableHasher) {
+        match self {
+            None => hasher.write_u8(0),
+            Some(value) => {
+                hasher.write_u8(1);
+                value.stable_hash(hasher);
+            }
+        }
+    }
+}
+
+/// In order, for positional items
+impl<T: StableHash> StableHash for [T] {
+    fn stable_hash(&self, hasher: &mut StableHasher) {
+        hasher.write(&(self.len() as u64).to_le_bytes());
+        for item in self {
+            item.stable_hash(hasher);
+        }
+    }
+}
+
+impl StableHash for KqlType {
+    fn stable_hash(&self, hasher: &mut StableHasher) {
+        // The type's KQL name; writing to the hasher cannot fail
+        let _ = write!(hasher, "{self}");
+        hasher.write_u8(0xff);
+    }
+}
+
+impl StableHash for Schema {
+    fn stable_hash(&self, hasher: &mut StableHasher) {
+        self.database.stable_hash(hasher);
+        hash_unordered(&self.tables, hasher);
+        hash_unordered(&self.functions, hasher);
+        hash_unordered(&self.scopes, hasher);
+        hash_unordered(&self.query_parameters, hasher);
+    }
+}
+
+impl StableHash for ResourceScope {
+    fn stable_hash(&self, hasher: &mut StableHasher) {
+        self.kind.function_name().stable_hash(hasher);
+        self.name.stable_hash(hasher);
+        hash_unordered(&self.tables, hasher);
+    }
+}
+
+impl StableHash for Table {
+    fn stable_hash(&self, hasher: &mut StableHasher) {
+        self.name.stable_hash(hasher);
+        hash_unordered(&self.columns, hasher);
+        self.description.stable_hash(hasher);
+        self.folder.stable_hash(hasher);
+    }
+}
+
+impl StableHash for Column {
+    fn stable_hash(&self, hasher: &mut StableHasher) {
+        self.name.stable_hash(hasher);
+        self.data_type.stable_hash(hasher);
+        self.description.stable_hash(hasher);
+        hasher.write(&(self.sample_paths.len() as u64).to_le_bytes());
+        for path in &self.sample_paths {
+            path.stable_hash(hasher);
+        }
+        hasher.write(&(self.fields.len() as u64).to_le_bytes());
+        for (path, data_type) in &self.fields {
+            path.stable_hash(hasher);
+            data_type.stable_hash(hasher);
+        }
+    }
+}
+
+impl StableHash for Function {
+    fn stable_hash(&self, hasher: &mut StableHasher) {
+        self.name.stable_hash(hasher);
+        self.parameters.stable_hash(hasher);
+        self.return_type.stable_hash(hasher);
+        hasher.write_u8(match self.kind {
+            FunctionKind::Scalar => 0,
+            FunctionKind::Tabular => 1,
+        });
+        self.body.stable_hash(hasher);
+        self.description.stable_hash(hasher);
+        self.folder.stable_hash(hasher);
+    }
+}
+
+impl StableHash for Parameter {
+    fn stable_hash(&self, hasher: &mut StableHasher) {
+        self.name.stable_hash(hasher);
+        self.data_type.stable_hash(hasher);
+        self.default_value.stable_hash(hasher);
+    }
+}
+
+/// Hash items in any order: each item is hashed on its own and the hashes
+/// are summed
+fn hash_unordered<T: StableHash>(items: &[T], hasher: &mut StableHasher) {
+    let sum = items.iter().fold(0u64, |sum, item| {
+        let mut item_hasher = StableHasher::default();
+        item.stable_hash(&mut item_hasher);
+        sum.wrapping_add(item_hasher.finish())
+    });
+    hasher.write(&(items.len() as u64).to_le_bytes());
+    hasher.write(&sum.to_le_bytes());
+}
+
+/// 64-bit FNV-1a, whose output does not depend on the Rust version
+struct StableHasher(u64);
+
+impl Default for StableHasher {
+    fn default() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Hasher for StableHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 ^= u64::from(byte);
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+impl fmt::Write for StableHasher {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.write(s.as_bytes());
+        Ok(())
+    }
+}
+
+/// Kind of cross-resource scoping function
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub enum ScopeKind {
+    /// `workspace('name')` - another Log Analytics workspace
+    Workspace,
+    /// `app('name')` - an Application Insights resource
+    App,
+    /// `resource('/subscriptions/...')` - an Azure resource
+    Resource,
+    /// `adx('cluster/database')` - an Azure Data Explorer database
+    Adx,
+}
+
+impl ScopeKind {
+    /// Name of the KQL function that references this kind of scope
+    #[must_use]
+    pub fn function_name(self) -> &'static str {
+        match self {
+            Self::Workspace => "workspace",
+            Self::App => "app",
+            Self::Resource => "resource",
+            Self::Adx => "adx",
+        }
+    }
+}
+
+/// Tables reachable through a cross-resource scoping function
+///
+/// `workspace('name').Table` is resolved against the scope of kind
+/// [`ScopeKind::Workspace`] with a matching name, and completion after
+/// `workspace('name').` offers its tables. A schema with scopes makes the
+/// scoping functions known under any dialect; the
+/// [`LogAnalytics`](crate::Dialect::LogAnalytics) dialect also checks their
+/// arguments and positions.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResourceScope {
+    /// Kind of scoping function
+    pub kind: ScopeKind,
+
+    /// Name, ID, or URL used as the function argument
+    pub name: String,
+
+    /// Tables in the scope
+    #[serde(default)]
+    pub tables: Vec<Table>,
+}
+
+impl ResourceScope {
+    /// Create a new scope
+    #[must_use]
+    pub fn new(kind: ScopeKind, name: impl Into<String>) -> Self {
+        Self {
+            kind,
+            name: name.into(),
+            tables: Vec::new(),
+        }
+    }
+
+    /// Create a `workspace()` scope
+    #[must_use]
+    pub fn workspace(name: impl Into<String>) -> Self {
+        Self::new(ScopeKind::Workspace, name)
+    }
+
+    /// Create an `app()` scope
+    #[must_use]
+    pub fn app(name: impl Into<String>) -> Self {
+        Self::new(ScopeKind::App, name)
+    }
+
+    /// Create a `resource()` scope
+    #[must_use]
+    pub fn resource(id: impl Into<String>) -> Self {
+        Self::new(ScopeKind::Resource, id)
+    }
+
+    /// Create an `adx()` scope from a `cluster/database` string or URL
+    ///
+    /// Names match `adx()` arguments regardless of scheme and of short vs
+    /// fully-qualified cluster names: a scope named `help/Samples` resolves
+    /// `adx('https://help.kusto.windows.net/Samples')`. Without a matching
+    /// scope, errors in the pipeline an `adx()` source feeds are reported
+    /// as warnings, since the remote schema is unknown.
+    #[must_use]
+    pub fn adx(cluster_and_database: impl Into<String>) -> Self {
+        Self::new(ScopeKind::Adx, cluster_and_database)
+    }
+
+    /// Create an `adx()` scope from separate cluster and database names
+    #[must_use]
+    pub fn adx_database(cluster: &str, database: &str) -> Self {
+        Self::new(
+            ScopeKind::Adx,
+            format!("{}/{}", cluster.trim_end_matches('/'), database),
+        )
+    }
+
+    /// Builder method to add a table
+    #[must_use]
+    pub fn table(mut self, table: Table) -> Self {
+        self.tables.push(table);
+        self
+    }
+
+    /// Get a table by name
+    #[must_use]
+    pub fn get_table(&self, name: &str) -> Option<&Table> {
+        self.tables.iter().find(|t| t.name.eq_ignore_ascii_case(name))
+    }
 }
 
 /// Table definition
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Table {
     /// Table name
     pub name: String,
@@ -102,6 +531,10 @@ pub struct Table {
     /// Optional table description
     #[serde(skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
+
+    /// Optional folder the table is organized under
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub folder: Option<String>,
 }
 
 impl Table {
@@ -112,6 +545,7 @@ impl Table {
             name: name.into(),
             columns: Vec::new(),
             description: None,
+            folder: None,
         }
     }
 
@@ -130,7 +564,7 @@ impl Table {
 
     /// Builder method to add a column with name and type
     #[must_use]
-    pub fn with_column(mut self, name: impl Into<String>, data_type: impl Into<String>) -> Self {
+    pub fn with_column(mut self, name: impl Into<String>, data_type: impl Into<KqlType>) -> Self {
         self.columns.push(Column::new(name, data_type));
         self
     }
@@ -142,6 +576,13 @@ impl Table {
         self
     }
 
+    /// Set the folder
+    #[must_use]
+    pub fn folder(mut self, folder: impl Into<String>) -> Self {
+        self.folder = Some(folder.into());
+        self
+    }
+
     /// Get a column by name
     #[must_use]
     pub fn get_column(&self, name: &str) -> Option<&Column> {
@@ -149,30 +590,103 @@ impl Table {
             .iter()
             .find(|c| c.name.eq_ignore_ascii_case(name))
     }
+
+    /// Builder method to record the shapes of sample events
+    ///
+    /// Each event is a JSON object keyed by column name. For `dynamic`
+    /// columns (and `string` columns holding serialized JSON), every JSON
+    /// path seen in the samples is recorded on the column. Schema-aware
+    /// validation then warns (`DS001`) about property accesses such as
+    /// `parse_json(Properties).user.nmae` whose path was never observed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use kql_language_tools::{Column, Table};
+    /// use serde_json::json;
+    ///
+    /// let table = Table::new("SigninLogs")
+    ///     .column(Column::dynamic("Properties"))
+    ///     .with_samples([json!({"Properties": {"user": {"name": "alice"}}})]);
+    ///
+    /// let paths = &table.get_column("Properties").unwrap().sample_paths;
+    /// assert!(paths.contains("user.name"));
+    /// ```
+    #[must_use]
+    pub fn with_samples<I>(mut self, events: I) -> Self
+    where
+        I: IntoIterator<Item = serde_json::Value>,
+    {
+        for event in events {
+            let serde_json::Value::Object(fields) = event else {
+                continue;
+            };
+            for (name, value) in &fields {
+                if let Some(column) = self
+                    .columns
+                    .iter_mut()
+                    .find(|c| c.name.eq_ignore_ascii_case(name))
+                {
+                    column.observe_sample(value);
+                }
+            }
+        }
+        self
+    }
 }
 
 /// Column definition
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Column {
     /// Column name
     pub name: String,
 
     /// KQL data type (string, long, datetime, dynamic, etc.)
-    pub data_type: String,
+    pub data_type: KqlType,
 
     /// Optional column description
     #[serde(skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
+
+    /// JSON paths observed in sample values (`user.name`, `roles[].id`)
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub sample_paths: BTreeSet<String>,
+
+    /// Known fields of a `dynamic` column, by JSON path (`user.name`)
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub fields: BTreeMap<String, KqlType>,
 }
 
 impl Column {
     /// Create a new column
     #[must_use]
-    pub fn new(name: impl Into<String>, data_type: impl Into<String>) -> Self {
+    pub fn new(name: impl Into<String>, data_type: impl Into<KqlType>) -> Self {
         Self {
             name: name.into(),
             data_type: data_type.into(),
             description: None,
+            sample_paths: BTreeSet::new(),
+            fields: BTreeMap::new(),
+        }
+    }
+
+    /// Record the JSON paths of a sample value
+    ///
+    /// Only `dynamic` columns, and `string` columns holding serialized JSON
+    /// objects or arrays, have paths.
+    pub fn observe_sample(&mut self, value: &serde_json::Value) {
+        match value {
+            serde_json::Value::String(s) if self.data_type == KqlType::String => {
+                if let Ok(parsed) = serde_json::from_str::<serde_json::Value>(s) {
+                    if parsed.is_object() || parsed.is_array() {
+                        collect_paths(&parsed, "", &mut self.sample_paths);
+                    }
+                }
+            }
+            _ if self.data_type == KqlType::Dynamic => {
+                collect_paths(value, "", &mut self.sample_paths);
+            }
+            _ => {}
         }
     }
 
@@ -183,57 +697,132 @@ impl Column {
         self
     }
 
+    /// Builder method to declare the known fields of a `dynamic` column
+    ///
+    /// Paths use dots for nested objects (`location.city`). Schema-aware
+    /// completion after `Properties.` offers the fields, and validation
+    /// warns (`DS002`) about accesses to paths that are neither declared
+    /// nor seen in samples.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use kql_language_tools::{Column, KqlType};
+    ///
+    /// let column = Column::dynamic("Properties")
+    ///     .with_fields([("ipAddress", "string"), ("location.city", "string")]);
+    /// assert_eq!(column.fields["ipAddress"], KqlType::String);
+    /// ```
+    #[must_use]
+    pub fn with_fields<I, K, T>(mut self, fields: I) -> Self
+    where
+        I: IntoIterator<Item = (K, T)>,
+        K: Into<String>,
+        T: Into<KqlType>,
+    {
+        self.fields.extend(
+            fields
+                .into_iter()
+                .map(|(path, data_type)| (path.into(), data_type.into())),
+        );
+        self
+    }
+
     /// Create a string column
     #[must_use]
     pub fn string(name: impl Into<String>) -> Self {
-        Self::new(name, "string")
+        Self::new(name, KqlType::String)
     }
 
     /// Create a long column
     #[must_use]
     pub fn long(name: impl Into<String>) -> Self {
-        Self::new(name, "long")
+        Self::new(name, KqlType::Long)
     }
 
     /// Create a real column
     #[must_use]
     pub fn real(name: impl Into<String>) -> Self {
-        Self::new(name, "real")
+        Self::new(name, KqlType::Real)
     }
 
     /// Create a bool column
     #[must_use]
     pub fn bool(name: impl Into<String>) -> Self {
-        Self::new(name, "bool")
+        Self::new(name, KqlType::Bool)
     }
 
     /// Create a datetime column
     #[must_use]
     pub fn datetime(name: impl Into<String>) -> Self {
-        Self::new(name, "datetime")
+        Self::new(name, KqlType::DateTime)
     }
 
     /// Create a timespan column
     #[must_use]
     pub fn timespan(name: impl Into<String>) -> Self {
-        Self::new(name, "timespan")
+        Self::new(name, KqlType::TimeSpan)
     }
 
     /// Create a guid column
     #[must_use]
     pub fn guid(name: impl Into<String>) -> Self {
-        Self::new(name, "guid")
+        Self::new(name, KqlType::Guid)
     }
 
     /// Create a dynamic column
     #[must_use]
     pub fn dynamic(name: impl Into<String>) -> Self {
-        Self::new(name, "dynamic")
+        Self::new(name, KqlType::Dynamic)
+    }
+}
+
+/// Record every path below `prefix` in `value`
+fn collect_paths(value: &serde_json::Value, prefix: &str, paths: &mut BTreeSet<String>) {
+    match value {
+        serde_json::Value::Object(fields) => {
+            for (key, child) in fields {
+                let path = if prefix.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{prefix}.{key}")
+                };
+                collect_paths(child, &path, paths);
+                paths.insert(path);
+            }
+        }
+        serde_json::Value::Array(items) => {
+            let path = format!("{prefix}[]");
+            for item in items {
+                collect_paths(item, &path, paths);
+            }
+            paths.insert(path);
+        }
+        _ => {}
     }
 }
 
 /// User-defined function definition
-#[derive(Debug, Clone, Serialize, Deserialize)]
+///
+/// Functions with a body take their result type from the body, as stored
+/// functions do in Kusto. Without a body, scalar functions return
+/// `return_type` and tabular functions return a table whose columns are
+/// given by `return_type` in Kusto's schema notation, such as
+/// `(Computer:string, Count:long)`.
+///
+/// # Example
+///
+/// ```
+/// use kql_language_tools::{Function, FunctionKind};
+///
+/// let heartbeats = Function::tabular("RecentHeartbeats", "(Computer:string, LastSeen:datetime)")
+///     .param("window", "timespan")
+///     .folder("Monitoring")
+///     .description("Last heartbeat per computer");
+///
+/// assert_eq!(heartbeats.kind, FunctionKind::Tabular);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Function {
     /// Function name
     pub name: String,
@@ -243,30 +832,48 @@ pub struct Function {
     pub parameters: Vec<Parameter>,
 
     /// Return type
-    pub return_type: String,
+    pub return_type: KqlType,
+
+    /// Whether the function returns a scalar or a table
+    #[serde(default, skip_serializing_if = "FunctionKind::is_scalar")]
+    pub kind: FunctionKind,
 
     /// Optional function body (KQL expression)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub body: Option<String>,
 
-    /// Optional description
-    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Optional description, the function's docstring
+    #[serde(alias = "doc_string", skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
+
+    /// Optional folder the function is organized under
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub folder: Option<String>,
 }
 
 impl Function {
     /// Create a new function
     #[must_use]
-    pub fn new(name: impl Into<String>, return_type: impl Into<String>) -> Self {
+    pub fn new(name: impl Into<String>, return_type: impl Into<KqlType>) -> Self {
         Self {
             name: name.into(),
             parameters: Vec::new(),
             return_type: return_type.into(),
+            kind: FunctionKind::Scalar,
             body: None,
             description: None,
+            folder: None,
         }
     }
 
+    /// Create a tabular function returning the columns in `columns`
+    ///
+    /// `columns` is in Kusto's schema notation, such as `(Id:long)`.
+    #[must_use]
+    pub fn tabular(name: impl Into<String>, columns: impl Into<KqlType>) -> Self {
+        Self::new(name, columns).kind(FunctionKind::Tabular)
+    }
+
     /// Add a parameter
     pub fn add_parameter(&mut self, param: Parameter) -> &mut Self {
         self.parameters.push(param);
@@ -275,7 +882,7 @@ impl Function {
 
     /// Builder method to add a parameter
     #[must_use]
-    pub fn param(mut self, name: impl Into<String>, data_type: impl Into<String>) -> Self {
+    pub fn param(mut self, name: impl Into<String>, data_type: impl Into<KqlType>) -> Self {
         self.parameters.push(Parameter::new(name, data_type));
         self
     }
@@ -293,16 +900,57 @@ impl Function {
         self.description = Some(desc.into());
         self
     }
+
+    /// Set the folder
+    #[must_use]
+    pub fn folder(mut self, folder: impl Into<String>) -> Self {
+        self.folder = Some(folder.into());
+        self
+    }
+
+    /// Set whether the function returns a scalar or a table
+    #[must_use]
+    pub fn kind(mut self, kind: FunctionKind) -> Self {
+        self.kind = kind;
+        self
+    }
+
+    /// Check if the function returns a table
+    #[must_use]
+    pub fn is_tabular(&self) -> bool {
+        self.kind == FunctionKind::Tabular
+    }
+}
+
+/// Whether a function returns a scalar or a table
+///
+/// Tabular functions, including stored views, can be used as query
+/// sources: `MyFunction() | take 10`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FunctionKind {
+    /// Returns a scalar value
+    #[default]
+    Scalar,
+    /// Returns a table
+    Tabular,
+}
+
+impl FunctionKind {
+    #[allow(clippy::trivially_copy_pass_by_ref)]
+    fn is_scalar(&self) -> bool {
+        *self == Self::Scalar
+    }
 }
 
 /// Function parameter definition
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Parameter {
     /// Parameter name
     pub name: String,
 
     /// Parameter data type
-    pub data_type: String,
+    pub data_type: KqlType,
 
     /// Optional default value
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -312,7 +960,7 @@ pub struct Parameter {
 impl Parameter {
     /// Create a new parameter
     #[must_use]
-    pub fn new(name: impl Into<String>, data_type: impl Into<String>) -> Self {
+    pub fn new(name: impl Into<String>, data_type: impl Into<KqlType>) -> Self {
         Self {
             name: name.into(),
             data_type: data_type.into(),
@@ -370,4 +1018,206 @@ mod tests {
         assert_eq!(parsed.tables[0].name, "Test");
         assert_eq!(parsed.tables[0].columns.len(), 2);
     }
+
+    #[test]
+    fn test_scope_lookup_and_serialization() {
+        let schema = Schema::new().scope(
+            ResourceScope::workspace("contoso-prod")
+                .table(Table::new("SigninLogs").with_column("UserPrincipalName", "string")),
+        );
+
+        let scope = schema
+            .get_scope(ScopeKind::Workspace, "Contoso-Prod")
+            .expect("scope should be found case-insensitively");
+        assert!(scope.get_table("signinlogs").is_some());
+        assert!(schema.get_scope(ScopeKind::App, "contoso-prod").is_none());
+
+        let json = serde_json::to_value(&schema).unwrap();
+        assert_eq!(json["scopes"][0]["kind"], "Workspace");
+
+        let adx = ResourceScope::adx_database("https://help.kusto.windows.net/", "Samples");
+        assert_eq!(adx.name, "https://help.kusto.windows.net/Samples");
+
+        // Scopes are omitted entirely when empty
+        let json = serde_json::to_value(Schema::new()).unwrap();
+        assert!(json.get("scopes").is_none());
+    }
+
+    #[test]
+    fn test_sample_paths() {
+        let table = Table::new("SigninLogs")
+            .column(Column::dynamic("Properties"))
+            .column(Column::string("RawJson"))
+            .column(Column::string("UserPrincipalName"))
+            .with_samples([
+                serde_json::json!({
+                    "Properties": {"user": {"name": "alice"}, "roles": [{"id": 1}]},
+                    "RawJson": "{\"client\": {\"ip\": \"10.0.0.1\"}}",
+                    "UserPrincipalName": "alice@contoso.com"
+                }),
+                serde_json::json!({"Properties": {"user": {"id": 7}}}),
+            ]);
+
+        let properties = &table.get_column("Properties").unwrap().sample_paths;
+        let expected = ["roles", "roles[]", "roles[].id", "user", "user.id", "user.name"];
+        assert!(properties.iter().eq(expected.iter()));
+
+        let raw = &table.get_column("RawJson").unwrap().sample_paths;
+        assert!(raw.contains("client.ip"));
+        assert!(table
+            .get_column("UserPrincipalName")
+            .unwrap()
+            .sample_paths
+            .is_empty());
+    }
+
+    #[test]
+    fn test_query_parameters() {
+        let a = Schema::new()
+            .query_parameter(Parameter::new("_startTime", "datetime"))
+            .query_parameter(Parameter::new("_limit", "long").default("100"));
+        let b = Schema::new()
+            .query_parameter(Parameter::new("_limit", "long").default("100"))
+            .query_parameter(Parameter::new("_startTime", "datetime"));
+        assert_eq!(a, b);
+        assert_ne!(a, Schema::new());
+
+        let json = serde_json::to_value(&a).unwrap();
+        assert_eq!(json["query_parameters"][0]["data_type"], "datetime");
+        assert!(serde_json::to_value(Schema::new())
+            .unwrap()
+            .get("query_parameters")
+            .is_none());
+    }
+
+    #[test]
+    fn test_column_fields() {
+        let column = Column::dynamic("Properties")
+            .with_fields([("ipAddress", "string"), ("location.city", "string")])
+            .with_fields([("riskLevel", KqlType::Int)]);
+        let paths: Vec<_> = column.fields.keys().map(String::as_str).collect();
+        assert_eq!(paths, ["ipAddress", "location.city", "riskLevel"]);
+
+        let json = serde_json::to_value(&column).unwrap();
+        assert_eq!(json["fields"]["riskLevel"], "int");
+        let parsed: Column = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed, column);
+        assert!(serde_json::to_value(Column::dynamic("Raw"))
+            .unwrap()
+            .get("fields")
+            .is_none());
+    }
+
+    #[test]
+    fn test_function_kind_serialization() {
+        let scalar = Function::new("f", "long");
+        let json = serde_json::to_string(&scalar).unwrap();
+        assert!(!json.contains("kind"));
+
+        let parsed: Function = serde_json::from_str(
+            r#"{"name": "g", "return_type": "(x:long)", "kind": "tabular", "doc_string": "Docs"}"#,
+        )
+        .unwrap();
+        assert!(parsed.is_tabular());
+        assert_eq!(parsed.description.as_deref(), Some("Docs"));
+    }
+
+    #[test]
+    fn test_check_types() {
+        let schema = Schema::new()
+            .table(Table::new("T").with_column("Timestamp", "DateTime"))
+            .function(Function::new("f", "real").param("since", "timespan"));
+        assert!(schema.check_types().is_ok());
+
+        let typo = schema
+            .clone()
+            .table(Table::new("U").with_column("When", "datetme"));
+        let error = typo.check_types().unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Unknown KQL type 'datetme' for column U.When"
+        );
+        assert!(typo.to_native_json().is_err());
+
+        let param = schema.function(Function::new("g", "long").param("x", "lnog"));
+        assert!(matches!(
+            param.check_types(),
+            Err(Error::UnknownType { context: Some(c), .. }) if c == "parameter g.x"
+        ));
+    }
+
+    #[test]
+    fn test_fingerprint_ignores_order() {
+        let a = Schema::new()
+            .table(
+                Table::new("A")
+                    .with_column("x", "long")
+                    .with_column("y", "string"),
+            )
+            .table(Table::new("B"))
+            .function(
+                Function::new("f", "long")
+                    .param("a", "long")
+                    .param("b", "long"),
+            );
+        let b = Schema::new()
+            .function(
+                Function::new("f", "long")
+                    .param("a", "long")
+                    .param("b", "long"),
+            )
+            .table(Table::new("B"))
+            .table(
+                Table::new("A")
+                    .with_column("y", "string")
+                    .with_column("x", "long"),
+            );
+        assert_eq!(a.fingerprint(), b.fingerprint());
+        assert_eq!(a, b);
+
+        // Parameter order is part of a function's signature
+        let c = Schema::new()
+            .table(
+                Table::new("A")
+                    .with_column("x", "long")
+                    .with_column("y", "string"),
+            )
+            .table(Table::new("B"))
+            .function(
+                Function::new("f", "long")
+                    .param("b", "long")
+                    .param("a", "long"),
+            );
+        assert_ne!(a.fingerprint(), c.fingerprint());
+        assert_ne!(a, c);
+
+        let d = Schema::new().table(Table::new("B").with_column("x", "long"));
+        assert_ne!(a.fingerprint(), d.fingerprint());
+
+        // Same-named items are matched one to one
+        let twice = Schema::new().table(Table::new("A")).table(Table::new("A"));
+        let mixed = Schema::new()
+            .table(Table::new("A"))
+            .table(Table::new("A").with_column("x", "long"));
+        assert_ne!(twice, mixed);
+        assert_ne!(twice.fingerprint(), mixed.fingerprint());
+        assert_eq!(mixed, mixed.clone());
+
+        // Column order is ignored inside scopes too
+        let scoped = |columns: [&str; 2]| {
+            Schema::new().scope(
+                ResourceScope::workspace("w").table(
+                    Table::new("T")
+                        .with_column(columns[0], "long")
+                        .with_column(columns[1], "long"),
+                ),
+            )
+        };
+        assert_eq!(scoped(["x", "y"]), scoped(["y", "x"]));
+        assert_eq!(
+            scoped(["x", "y"]).fingerprint(),
+            scoped(["y", "x"]).fingerprint()
+        );
+        assert_ne!(scoped(["x", "y"]), scoped(["x", "z"]));
+    }
 }
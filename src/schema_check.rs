@@ -0,0 +1,272 @@
+//! Schema self-validation
+//!
+//! [`Schema::validate`] looks for definitions that would make query
+//! diagnostics confusing rather than wrong: a second table with the same
+//! name hides the first, a misspelled type makes its column `dynamic`, and a
+//! function with a broken body reports errors at every call. Run it in CI
+//! over hand-written schemas:
+//!
+//! ```
+//! use kql_language_tools::{Schema, SchemaIssue, Table};
+//!
+//! let schema = Schema::new()
+//!     .table(Table::new("Events").with_column("Timestamp", "datetme"))
+//!     .table(Table::new("Empty"));
+//! let issues = schema.validate();
+//! assert_eq!(issues.len(), 2);
+//! assert!(matches!(&issues[1], SchemaIssue::EmptyTable { table } if table == "Empty"));
+//! ```
+//!
+//! Function bodies are only checked for being empty here;
+//! [`KqlValidator::validate_schema`](crate::KqlValidator::validate_schema)
+//! also parses them.
+
+use crate::error::Error;
+use crate::kql_type::KqlType;
+use crate::schema::{Schema, Table};
+use std::collections::HashSet;
+use std::fmt;
+
+/// A problem found in a schema definition
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SchemaIssue {
+    /// A table name is used more than once in the database or a scope
+    ///
+    /// Lookups are case-insensitive, so the later tables are unreachable.
+    DuplicateTable {
+        /// Table name, prefixed by its scope for scoped tables
+        table: String,
+    },
+    /// A column name is used more than once in a table
+    DuplicateColumn {
+        /// Table name, prefixed by its scope for scoped tables
+        table: String,
+        /// Column name
+        column: String,
+    },
+    /// A table has no columns
+    EmptyTable {
+        /// Table name, prefixed by its scope for scoped tables
+        table: String,
+    },
+    /// A column, parameter or return type is not a KQL type
+    UnknownType {
+        /// Where the type appears, such as `column Events.Timestamp`
+        location: String,
+        /// The unknown type name
+        name: String,
+    },
+    /// A function name is used more than once
+    DuplicateFunction {
+        /// Function name
+        function: String,
+    },
+    /// A function body is empty or does not parse
+    InvalidFunctionBody {
+        /// Function name
+        function: String,
+        /// Why the body is invalid
+        message: String,
+    },
+}
+
+impl fmt::Display for SchemaIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::DuplicateTable { table } => {
+                write!(f, "Table '{table}' is defined more than once")
+            }
+            Self::DuplicateColumn { table, column } => {
+                write!(
+                    f,
+                    "Column '{column}' is defined more than once in '{table}'"
+                )
+            }
+            Self::EmptyTable { table } => write!(f, "Table '{table}' has no columns"),
+            Self::UnknownType { location, name } => {
+                write!(f, "Unknown KQL type '{name}' for {location}")
+            }
+            Self::DuplicateFunction { function } => {
+                write!(f, "Function '{function}' is defined more than once")
+            }
+            Self::InvalidFunctionBody { function, message } => {
+                write!(f, "Body of function '{function}' is invalid: {message}")
+            }
+        }
+    }
+}
+
+impl Schema {
+    /// Check the schema for duplicate, empty and mistyped definitions
+    ///
+    /// Reports tables and functions defined twice (names compare
+    /// case-insensitively, as lookups do), duplicate columns, tables without
+    /// columns, unknown type names, and empty function bodies. Issues are in
+    /// schema order; an empty list means none were found.
+    #[must_use]
+    pub fn validate(&self) -> Vec<SchemaIssue> {
+        let mut issues = Vec::new();
+        let scopes = self.scopes.iter().map(|scope| {
+            let prefix = format!("{}('{}').", scope.kind.function_name(), scope.name);
+            (prefix, &scope.tables)
+        });
+        for (prefix, tables) in std::iter::once((String::new(), &self.tables)).chain(scopes) {
+            check_tables(&prefix, tables, &mut issues);
+        }
+
+        for parameter in &self.query_parameters {
+            let location = format!("query parameter {}", parameter.name);
+            push_unknown(&parameter.data_type, location, &mut issues);
+        }
+
+        let mut names = HashSet::new();
+        for function in &self.functions {
+            if !names.insert(function.name.to_lowercase()) {
+                issues.push(SchemaIssue::DuplicateFunction {
+                    function: function.name.clone(),
+                });
+            }
+            for parameter in &function.parameters {
+                let location = format!("parameter {}.{}", function.name, parameter.name);
+                push_unknown(&parameter.data_type, location, &mut issues);
+            }
+            let location = format!("return type of {}", function.name);
+            push_unknown(&function.return_type, location, &mut issues);
+            if function
+                .body
+                .as_deref()
+                .is_some_and(|body| body_text(body).is_empty())
+            {
+                issues.push(SchemaIssue::InvalidFunctionBody {
+                    function: function.name.clone(),
+                    message: "the body is empty".to_string(),
+                });
+            }
+        }
+        issues
+    }
+
+    /// Check that every column, parameter and return type is a KQL type
+    ///
+    /// Schema-aware calls make this check, so a misspelled type such as
+    /// `datetme` is reported instead of being treated as `dynamic`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::UnknownType`] naming the first unknown type and
+    /// where it appears.
+    pub fn check_types(&self) -> Result<(), Error> {
+        let first = self.validate().into_iter().find_map(|issue| match issue {
+            SchemaIssue::UnknownType { location, name } => Some((location, name)),
+            _ => None,
+        });
+        match first {
+            Some((location, name)) => Err(Error::UnknownType {
+                name,
+                context: Some(location),
+            }),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Check the tables of the database or of one scope
+fn check_tables(prefix: &str, tables: &[Table], issues: &mut Vec<SchemaIssue>) {
+    let mut names = HashSet::new();
+    for table in tables {
+        let name = format!("{prefix}{}", table.name);
+        if !names.insert(table.name.to_lowercase()) {
+            issues.push(SchemaIssue::DuplicateTable {
+                table: name.clone(),
+            });
+        }
+        if table.columns.is_empty() {
+            issues.push(SchemaIssue::EmptyTable {
+                table: name.clone(),
+            });
+        }
+        let mut columns = HashSet::new();
+        for column in &table.columns {
+            if !columns.insert(column.name.to_lowercase()) {
+                issues.push(SchemaIssue::DuplicateColumn {
+                    table: name.clone(),
+                    column: column.name.clone(),
+                });
+            }
+            push_unknown(
+                &column.data_type,
+                format!("column {name}.{}", column.name),
+                issues,
+            );
+            for (path, data_type) in &column.fields {
+                let location = format!("field {name}.{}.{path}", column.name);
+                push_unknown(data_type, location, issues);
+            }
+        }
+    }
+}
+
+/// The statements of a function body, without its braces
+pub(crate) fn body_text(body: &str) -> &str {
+    let body = body.trim();
+    body.strip_prefix('{')
+        .and_then(|b| b.strip_suffix('}'))
+        .map_or(body, str::trim)
+}
+
+fn push_unknown(data_type: &KqlType, location: String, issues: &mut Vec<SchemaIssue>) {
+    if let Some(name) = data_type.unknown_name() {
+        issues.push(SchemaIssue::UnknownType {
+            location,
+            name: name.to_string(),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::{Column, Function, ResourceScope};
+
+    #[test]
+    fn test_validate_schema() {
+        let schema = Schema::new()
+            .table(
+                Table::new("Events")
+                    .with_column("Timestamp", "datetime")
+                    .with_column("timestamp", "datetme"),
+            )
+            .table(
+                Table::new("EVENTS")
+                    .with_column("Id", "long")
+                    .column(Column::dynamic("Data").with_fields([("client.ip", "strnig")])),
+            )
+            .table(Table::new("Empty"))
+            .scope(ResourceScope::workspace("other").table(Table::new("Events")))
+            .function(Function::new("f", "long").param("x", "lnog").body("{ }"))
+            .function(Function::new("F", "long").body("x + 1"));
+        let issues: Vec<String> = schema.validate().iter().map(ToString::to_string).collect();
+        assert_eq!(
+            issues,
+            [
+                "Column 'timestamp' is defined more than once in 'Events'",
+                "Unknown KQL type 'datetme' for column Events.timestamp",
+                "Table 'EVENTS' is defined more than once",
+                "Unknown KQL type 'strnig' for field EVENTS.Data.client.ip",
+                "Table 'Empty' has no columns",
+                "Table 'workspace('other').Events' has no columns",
+                "Unknown KQL type 'lnog' for parameter f.x",
+                "Body of function 'f' is invalid: the body is empty",
+                "Function 'F' is defined more than once",
+            ]
+        );
+        assert!(matches!(
+            schema.check_types(),
+            Err(Error::UnknownType { name, .. }) if name == "datetme"
+        ));
+
+        let valid = Schema::new().table(Table::new("T").with_column("a", "long"));
+        assert!(valid.validate().is_empty());
+        assert!(valid.check_types().is_ok());
+    }
+}
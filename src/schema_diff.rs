@@ -0,0 +1,458 @@
+//! Schema diffing
+//!
+//! [`Schema::diff`] lists what changed between two versions of a schema,
+//! for example a pinned copy of an upstream database and its current
+//! `.show database schema` output. Removed and retyped columns can break
+//! saved queries, so the changes that may do so are flagged:
+//!
+//! ```
+//! use kql_language_tools::{Schema, Table};
+//!
+//! let old = Schema::new().table(Table::new("Events").with_column("Id", "long"));
+//! let new = Schema::new().table(Table::new("Events").with_column("Id", "string"));
+//!
+//! let diff = old.diff(&new);
+//! assert!(diff.has_breaking_changes());
+//! assert_eq!(diff.changes[0].to_string(), "Changed column 'Events.Id' from long to string");
+//! assert_eq!(diff.to_csl(), ".alter column Events.Id type=string\n");
+//! ```
+
+use crate::kql_type::KqlType;
+use crate::kusto_schema::{csl_name, function_command, table_commands};
+use crate::schema::{Function, ResourceScope, Schema, Table};
+use std::fmt;
+
+/// One change between two schemas
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SchemaChange {
+    /// A table was added
+    TableAdded {
+        /// Table name, prefixed by its scope for scoped tables
+        table: String,
+    },
+    /// A table was removed
+    TableRemoved {
+        /// Table name, prefixed by its scope for scoped tables
+        table: String,
+    },
+    /// A column was added to a table
+    ColumnAdded {
+        /// Table name, prefixed by its scope for scoped tables
+        table: String,
+        /// Column name
+        column: String,
+        /// Column type
+        data_type: KqlType,
+    },
+    /// A column was removed from a table
+    ColumnRemoved {
+        /// Table name, prefixed by its scope for scoped tables
+        table: String,
+        /// Column name
+        column: String,
+    },
+    /// A column's type changed
+    ColumnRetyped {
+        /// Table name, prefixed by its scope for scoped tables
+        table: String,
+        /// Column name
+        column: String,
+        /// Type before the change
+        from: KqlType,
+        /// Type after the change
+        to: KqlType,
+    },
+    /// A function was added
+    FunctionAdded {
+        /// Function name
+        function: String,
+    },
+    /// A function was removed
+    FunctionRemoved {
+        /// Function name
+        function: String,
+    },
+    /// A function's definition changed
+    FunctionChanged {
+        /// Function name
+        function: String,
+        /// Whether its parameters, return type or kind changed, rather than
+        /// only its body or documentation
+        signature: bool,
+    },
+}
+
+impl SchemaChange {
+    /// Check if the change can break queries written against the old schema
+    ///
+    /// Removals, retyped columns and function signature changes are
+    /// breaking; additions and function body changes are not.
+    #[must_use]
+    pub fn is_breaking(&self) -> bool {
+        match self {
+            Self::TableRemoved { .. }
+            | Self::ColumnRemoved { .. }
+            | Self::ColumnRetyped { .. }
+            | Self::FunctionRemoved { .. } => true,
+            Self::FunctionChanged { signature, .. } => *signature,
+            Self::TableAdded { .. } | Self::ColumnAdded { .. } | Self::FunctionAdded { .. } => {
+                false
+            }
+        }
+    }
+}
+
+impl fmt::Display for SchemaChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TableAdded { table } => write!(f, "Added table '{table}'"),
+            Self::TableRemoved { table } => write!(f, "Removed table '{table}'"),
+            Self::ColumnAdded {
+                table,
+                column,
+                data_type,
+            } => write!(f, "Added column '{table}.{column}' of type {data_type}"),
+            Self::ColumnRemoved { table, column } => {
+                write!(f, "Removed column '{table}.{column}'")
+            }
+            Self::ColumnRetyped {
+                table,
+                column,
+                from,
+                to,
+            } => write!(f, "Changed column '{table}.{column}' from {from} to {to}"),
+            Self::FunctionAdded { function } => write!(f, "Added function '{function}'"),
+            Self::FunctionRemoved { function } => write!(f, "Removed function '{function}'"),
+            Self::FunctionChanged {
+                function,
+                signature: true,
+            } => write!(f, "Changed signature of function '{function}'"),
+            Self::FunctionChanged {
+                function,
+                signature: false,
+            } => write!(f, "Changed definition of function '{function}'"),
+        }
+    }
+}
+
+/// The changes from one schema to another, from [`Schema::diff`]
+#[derive(Debug, Clone)]
+pub struct SchemaDiff {
+    /// Changes in schema order: tables, then functions, then scoped tables
+    pub changes: Vec<SchemaChange>,
+    /// Number of changes before the scoped tables
+    database_changes: usize,
+    /// The schema diffed against, for writing commands
+    target: Schema,
+}
+
+impl SchemaDiff {
+    /// Check if the schemas define the same tables, columns and functions
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// Check if any change can break queries written against the old schema
+    #[must_use]
+    pub fn has_breaking_changes(&self) -> bool {
+        self.changes.iter().any(SchemaChange::is_breaking)
+    }
+
+    /// Iterate over the changes that can break queries
+    pub fn breaking(&self) -> impl Iterator<Item = &SchemaChange> {
+        self.changes.iter().filter(|c| c.is_breaking())
+    }
+
+    /// Write control commands that turn the old schema into the new one
+    ///
+    /// Added tables get `.create table`, retyped columns `.alter column`,
+    /// tables with added or removed columns `.alter table` with the new
+    /// column list, and removed tables `.drop table`. Added and changed
+    /// functions get `.create-or-alter function`, and removed ones
+    /// `.drop function`. Scoped tables belong to other databases and are
+    /// left out. Commands are separated by blank lines, like
+    /// [`Schema::to_csl`].
+    #[must_use]
+    pub fn to_csl(&self) -> String {
+        let changes = &self.changes[..self.database_changes];
+        let mut commands = Vec::new();
+        let mut altered: Vec<&str> = Vec::new();
+        for change in changes {
+            match change {
+                SchemaChange::TableAdded { table } => {
+                    if let Some(table) = self.target.get_table(table) {
+                        commands.extend(table_commands(".create table", table));
+                    }
+                }
+                SchemaChange::TableRemoved { table } => {
+                    commands.push(format!(".drop table {}", csl_name(table)));
+                }
+                SchemaChange::ColumnRetyped {
+                    table, column, to, ..
+                } => {
+                    commands.push(format!(
+                        ".alter column {}.{} type={to}",
+                        csl_name(table),
+                        csl_name(column)
+                    ));
+                }
+                SchemaChange::ColumnAdded { table, .. }
+                | SchemaChange::ColumnRemoved { table, .. }
+                    if !altered.contains(&table.as_str()) =>
+                {
+                    altered.push(table);
+                }
+                _ => {}
+            }
+        }
+        for table in altered {
+            if let Some(table) = self.target.get_table(table) {
+                commands.extend(table_commands(".alter table", table));
+            }
+        }
+        for change in changes {
+            match change {
+                SchemaChange::FunctionAdded { function }
+                | SchemaChange::FunctionChanged { function, .. } => {
+                    commands.extend(
+                        self.target
+                            .get_function(function)
+                            .and_then(|f| function_command(".create-or-alter function", f)),
+                    );
+                }
+                SchemaChange::FunctionRemoved { function } => {
+                    commands.push(format!(".drop function {}", csl_name(function)));
+                }
+                _ => {}
+            }
+        }
+
+        let mut script = commands.join("\n\n");
+        if !script.is_empty() {
+            script.push('\n');
+        }
+        script
+    }
+}
+
+impl Schema {
+    /// List the changes from this schema to `other`
+    ///
+    /// Tables, columns and functions are matched by name, case-insensitively
+    /// as lookups are, and scoped tables within scopes of the same kind and
+    /// name. Column order, descriptions and folders are not compared.
+    #[must_use]
+    pub fn diff(&self, other: &Schema) -> SchemaDiff {
+        let mut changes = Vec::new();
+        diff_tables(&self.tables, &other.tables, "", &mut changes);
+        diff_functions(&self.functions, &other.functions, &mut changes);
+
+        let database_changes = changes.len();
+
+        let mut seen: Vec<&ResourceScope> = Vec::new();
+        for scope in self.scopes.iter().chain(&other.scopes) {
+            if seen.iter().any(|s| same_scope(s, scope)) {
+                continue;
+            }
+            seen.push(scope);
+            let prefix = format!("{}('{}').", scope.kind.function_name(), scope.name);
+            diff_tables(
+                scope_tables(self, scope),
+                scope_tables(other, scope),
+                &prefix,
+                &mut changes,
+            );
+        }
+
+        SchemaDiff {
+            changes,
+            database_changes,
+            target: other.clone(),
+        }
+    }
+}
+
+fn same_scope(a: &ResourceScope, b: &ResourceScope) -> bool {
+    a.kind == b.kind && a.name.eq_ignore_ascii_case(&b.name)
+}
+
+/// The tables of the scope in `schema` matching `scope`, if any
+fn scope_tables<'a>(schema: &'a Schema, scope: &ResourceScope) -> &'a [Table] {
+    schema
+        .scopes
+        .iter()
+        .find(|s| same_scope(s, scope))
+        .map_or(&[], |s| &s.tables)
+}
+
+/// Diff the tables of the database or of one scope
+fn diff_tables(old: &[Table], new: &[Table], prefix: &str, changes: &mut Vec<SchemaChange>) {
+    let find = |tables: &[Table], name: &str| {
+        tables
+            .iter()
+            .position(|t| t.name.eq_ignore_ascii_case(name))
+    };
+    for table in old {
+        let name = format!("{prefix}{}", table.name);
+        let Some(index) = find(new, &table.name) else {
+            changes.push(SchemaChange::TableRemoved { table: name });
+            continue;
+        };
+        let new_table = &new[index];
+        for column in &table.columns {
+            match new_table.get_column(&column.name) {
+                None => changes.push(SchemaChange::ColumnRemoved {
+                    table: name.clone(),
+                    column: column.name.clone(),
+                }),
+                Some(new_column) if new_column.data_type != column.data_type => {
+                    changes.push(SchemaChange::ColumnRetyped {
+                        table: name.clone(),
+                        column: column.name.clone(),
+                        from: column.data_type.clone(),
+                        to: new_column.data_type.clone(),
+                    });
+                }
+                Some(_) => {}
+            }
+        }
+        for column in &new_table.columns {
+            if table.get_column(&column.name).is_none() {
+                changes.push(SchemaChange::ColumnAdded {
+                    table: name.clone(),
+                    column: column.name.clone(),
+                    data_type: column.data_type.clone(),
+                });
+            }
+        }
+    }
+    for table in new {
+        if find(old, &table.name).is_none() {
+            changes.push(SchemaChange::TableAdded {
+                table: format!("{prefix}{}", table.name),
+            });
+        }
+    }
+}
+
+fn diff_functions(old: &[Function], new: &[Function], changes: &mut Vec<SchemaChange>) {
+    let find = |functions: &[Function], name: &str| {
+        functions
+            .iter()
+            .position(|f| f.name.eq_ignore_ascii_case(name))
+    };
+    for function in old {
+        let Some(index) = find(new, &function.name) else {
+            changes.push(SchemaChange::FunctionRemoved {
+                function: function.name.clone(),
+            });
+            continue;
+        };
+        let new_function = &new[index];
+        if new_function != function {
+            let signature = new_function.parameters != function.parameters
+                || new_function.return_type != function.return_type
+                || new_function.kind != function.kind;
+            changes.push(SchemaChange::FunctionChanged {
+                function: function.name.clone(),
+                signature,
+            });
+        }
+    }
+    for function in new {
+        if find(old, &function.name).is_none() {
+            changes.push(SchemaChange::FunctionAdded {
+                function: function.name.clone(),
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff() {
+        let old = Schema::new()
+            .table(
+                Table::new("Events")
+                    .with_column("Id", "long")
+                    .with_column("Level", "int")
+                    .with_column("Legacy", "string"),
+            )
+            .table(Table::new("Retired").with_column("x", "long"))
+            .function(Function::new("f", "long").body("1"))
+            .function(Function::new("g", "long").param("x", "long").body("x"))
+            .function(Function::new("gone", "long").body("0"))
+            .scope(
+                ResourceScope::workspace("other").table(Table::new("A").with_column("a", "long")),
+            );
+        let new = Schema::new()
+            .table(
+                Table::new("events")
+                    .with_column("Id", "long")
+                    .with_column("Level", "string")
+                    .with_column("Message", "string"),
+            )
+            .table(Table::new("Fresh").with_column("y", "real"))
+            .function(Function::new("f", "long").body("2"))
+            .function(Function::new("g", "long").param("x", "int").body("x"))
+            .function(Function::new("h", "long").body("3"))
+            .scope(ResourceScope::workspace("Other"));
+
+        let diff = old.diff(&new);
+        let changes: Vec<String> = diff.changes.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            changes,
+            [
+                "Changed column 'Events.Level' from int to string",
+                "Removed column 'Events.Legacy'",
+                "Added column 'Events.Message' of type string",
+                "Removed table 'Retired'",
+                "Added table 'Fresh'",
+                "Changed definition of function 'f'",
+                "Changed signature of function 'g'",
+                "Removed function 'gone'",
+                "Added function 'h'",
+                "Removed table 'workspace('other').A'",
+            ]
+        );
+        let breaking: Vec<_> = diff.breaking().map(ToString::to_string).collect();
+        assert_eq!(breaking.len(), 6);
+
+        assert_eq!(
+            diff.to_csl(),
+            "\
+.alter column Events.Level type=string
+
+.drop table Retired
+
+.create table Fresh (y:real)
+
+.alter table events (Id:long, Level:string, Message:string)
+
+.create-or-alter function f()
+{
+2
+}
+
+.create-or-alter function g(x:int)
+{
+x
+}
+
+.drop function gone
+
+.create-or-alter function h()
+{
+3
+}
+"
+        );
+
+        assert!(old.diff(&old).is_empty());
+        assert!(!old.diff(&old).has_breaking_changes());
+    }
+}
@@ -0,0 +1,288 @@
+//! Schema files
+//!
+//! Schemas are read from and written to JSON, CSL scripts and, with the
+//! `yaml` and `toml` features, YAML and TOML. JSON, YAML and TOML share the
+//! field names of [`Schema`]'s serde form, so a table definition reads the
+//! same in each:
+//!
+//! ```yaml
+//! tables:
+//!   - name: SigninLogs
+//!     columns:
+//!       - name: TimeGenerated
+//!         data_type: datetime
+//! ```
+
+use crate::error::Error;
+use crate::schema::Schema;
+use std::path::Path;
+
+/// A schema file format, chosen by file extension
+enum Format {
+    Json,
+    Csl,
+    #[cfg(feature = "yaml")]
+    Yaml,
+    #[cfg(feature = "toml")]
+    Toml,
+}
+
+impl Format {
+    fn of(path: &Path) -> Result<Self, Error> {
+        let extension = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(str::to_ascii_lowercase);
+        match extension.as_deref() {
+            Some("json") => Ok(Self::Json),
+            Some("kql" | "csl") => Ok(Self::Csl),
+            #[cfg(feature = "yaml")]
+            Some("yaml" | "yml") => Ok(Self::Yaml),
+            #[cfg(feature = "toml")]
+            Some("toml") => Ok(Self::Toml),
+            _ => Err(Error::UnsupportedSchemaFormat {
+                path: path.to_path_buf(),
+            }),
+        }
+    }
+}
+
+impl Schema {
+    /// Parse a schema from JSON
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Json`] if `json` is not a valid schema.
+    pub fn from_json(json: &str) -> Result<Self, Error> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    /// Write the schema as pretty-printed JSON
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Json`] if serialization fails.
+    pub fn to_json(&self) -> Result<String, Error> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// Parse a schema from YAML
+    ///
+    /// Enabled by the `yaml` feature.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Yaml`] if `yaml` is not a valid schema.
+    #[cfg(feature = "yaml")]
+    pub fn from_yaml(yaml: &str) -> Result<Self, Error> {
+        Ok(serde_yaml::from_str(yaml)?)
+    }
+
+    /// Write the schema as YAML
+    ///
+    /// Enabled by the `yaml` feature.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Yaml`] if serialization fails.
+    #[cfg(feature = "yaml")]
+    pub fn to_yaml(&self) -> Result<String, Error> {
+        Ok(serde_yaml::to_string(self)?)
+    }
+
+    /// Parse a schema from TOML
+    ///
+    /// Enabled by the `toml` feature.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Toml`] if `toml` is not a valid schema.
+    #[cfg(feature = "toml")]
+    pub fn from_toml(toml: &str) -> Result<Self, Error> {
+        toml::from_str(toml).map_err(|e| Error::Toml {
+            message: e.to_string(),
+        })
+    }
+
+    /// Write the schema as TOML
+    ///
+    /// Enabled by the `toml` feature.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Toml`] if serialization fails.
+    #[cfg(feature = "toml")]
+    pub fn to_toml(&self) -> Result<String, Error> {
+        toml::to_string(self).map_err(|e| Error::Toml {
+            message: e.to_string(),
+        })
+    }
+
+    /// Load a schema file, choosing the format by extension
+    ///
+    /// `.json` files are read as JSON, `.kql` and `.csl` files as scripts of
+    /// control commands (see [`from_csl`](Self::from_csl)), `.yaml` and
+    /// `.yml` files as YAML with the `yaml` feature, and `.toml` files as
+    /// TOML with the `toml` feature.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::UnsupportedSchemaFormat`] for other extensions,
+    /// [`Error::Io`] if the file cannot be read, or the format's error if
+    /// it does not hold a valid schema.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use kql_language_tools::Schema;
+    ///
+    /// let schema = Schema::from_path("schemas/sentinel.json")?;
+    /// # Ok::<(), kql_language_tools::Error>(())
+    /// ```
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let format = Format::of(path)?;
+        let text = std::fs::read_to_string(path)?;
+        match format {
+            Format::Json => Self::from_json(&text),
+            Format::Csl => Self::from_csl(&text),
+            #[cfg(feature = "yaml")]
+            Format::Yaml => Self::from_yaml(&text),
+            #[cfg(feature = "toml")]
+            Format::Toml => Self::from_toml(&text),
+        }
+    }
+
+    /// Save the schema to a file, choosing the format by extension
+    ///
+    /// Extensions are chosen as by [`from_path`](Self::from_path). CSL
+    /// scripts leave out what [`to_csl`](Self::to_csl) does.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::UnsupportedSchemaFormat`] for unsupported
+    /// extensions, [`Error::Io`] if the file cannot be written, or the
+    /// format's error if serialization fails.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+        let path = path.as_ref();
+        let text = match Format::of(path)? {
+            Format::Json => self.to_json()?,
+            Format::Csl => self.to_csl(),
+            #[cfg(feature = "yaml")]
+            Format::Yaml => self.to_yaml()?,
+            #[cfg(feature = "toml")]
+            Format::Toml => self.to_toml()?,
+        };
+        std::fs::write(path, text)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::{Column, Function, ResourceScope, Table};
+
+    fn sample() -> Schema {
+        Schema::with_database("Security")
+            .table(
+                Table::new("SigninLogs")
+                    .with_column("TimeGenerated", "datetime")
+                    .column(Column::dynamic("Properties").description("Raw event"))
+                    .folder("Identity"),
+            )
+            .function(
+                Function::new("Failures", "dynamic")
+                    .param("since", "timespan")
+                    .body("SigninLogs | where TimeGenerated > ago(since)"),
+            )
+            .scope(
+                ResourceScope::workspace("other")
+                    .table(Table::new("Heartbeat").with_column("Computer", "string")),
+            )
+    }
+
+    #[test]
+    fn test_schema_files() {
+        let dir = std::env::temp_dir().join(format!("kql-schema-file-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let schema = sample();
+
+        schema.save(dir.join("schema.JSON")).unwrap();
+        assert_eq!(Schema::from_path(dir.join("schema.JSON")).unwrap(), schema);
+
+        std::fs::write(dir.join("tables.kql"), ".create table T (a:long)\n").unwrap();
+        let script = Schema::from_path(dir.join("tables.kql")).unwrap();
+        assert_eq!(script.get_table("T").unwrap().columns[0].data_type, "long");
+
+        assert!(matches!(
+            schema.save(dir.join("schema.xml")),
+            Err(Error::UnsupportedSchemaFormat { .. })
+        ));
+        assert!(matches!(
+            Schema::from_path(dir.join("absent.json")),
+            Err(Error::Io(_))
+        ));
+
+        #[cfg(feature = "yaml")]
+        {
+            schema.save(dir.join("schema.yml")).unwrap();
+            assert_eq!(Schema::from_path(dir.join("schema.yml")).unwrap(), schema);
+        }
+        #[cfg(feature = "toml")]
+        {
+            schema.save(dir.join("schema.toml")).unwrap();
+            assert_eq!(Schema::from_path(dir.join("schema.toml")).unwrap(), schema);
+        }
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(feature = "yaml")]
+    #[test]
+    fn test_from_yaml() {
+        let yaml = "
+tables:
+  - name: SigninLogs
+    folder: Identity
+    columns:
+      - name: TimeGenerated
+        data_type: datetime
+      - name: UserPrincipalName
+        data_type: string
+        description: Sign-in name
+";
+        let schema = Schema::from_yaml(yaml).unwrap();
+        let table = schema.get_table("SigninLogs").unwrap();
+        assert_eq!(table.folder.as_deref(), Some("Identity"));
+        assert_eq!(
+            table
+                .get_column("UserPrincipalName")
+                .unwrap()
+                .description
+                .as_deref(),
+            Some("Sign-in name")
+        );
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn test_from_toml() {
+        let toml = r#"
+database = "Security"
+
+[[tables]]
+name = "SigninLogs"
+columns = [
+    { name = "TimeGenerated", data_type = "datetime" },
+    { name = "ResultType", data_type = "string" },
+]
+"#;
+        let schema = Schema::from_toml(toml).unwrap();
+        assert_eq!(schema.database.as_deref(), Some("Security"));
+        assert_eq!(schema.get_table("SigninLogs").unwrap().columns.len(), 2);
+        assert!(matches!(
+            Schema::from_toml("tables = 1"),
+            Err(Error::Toml { .. })
+        ));
+    }
+}
@@ -0,0 +1,317 @@
+//! Schema merging
+//!
+//! [`Schema::merge`] overlays one schema on another, for example a base
+//! Azure Monitor schema with a workspace's custom `_CL` tables and saved
+//! functions:
+//!
+//! ```
+//! use kql_language_tools::{Function, MergePolicy, Schema, Table};
+//!
+//! let base = Schema::new().table(Table::new("SigninLogs").with_column("TimeGenerated", "datetime"));
+//! let workspace = Schema::new()
+//!     .table(Table::new("AppAudit_CL").with_column("Action_s", "string"))
+//!     .function(Function::tabular("FailedSignins", "(*)").body("SigninLogs | take 10"));
+//!
+//! let merged = base.merge(&workspace, MergePolicy::Union);
+//! assert!(merged.conflicts.is_empty());
+//! assert!(merged.schema.get_table("AppAudit_CL").is_some());
+//! ```
+//!
+//! Tables with the same name are merged column by column, and scopes with
+//! the same kind and name table by table. When both schemas define a column
+//! with different types, or a function differently, the [`MergePolicy`]
+//! picks the definition to keep and the clash is reported as a
+//! [`MergeConflict`].
+
+use crate::kql_type::KqlType;
+use crate::schema::{Schema, Table};
+use std::fmt;
+
+/// Which definition wins when two merged schemas disagree
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MergePolicy {
+    /// Keep the base schema's definition, only adding what it lacks
+    #[default]
+    Union,
+    /// Replace the base schema's definition with the overlay's
+    Override,
+}
+
+/// A definition the two merged schemas disagree on
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MergeConflict {
+    /// A column has a different type in each schema
+    Column {
+        /// Table name, prefixed by its scope for scoped tables
+        table: String,
+        /// Column name
+        column: String,
+        /// Type in the base schema
+        base: KqlType,
+        /// Type in the overlay
+        overlay: KqlType,
+    },
+    /// A function is defined differently in each schema
+    Function {
+        /// Function name
+        function: String,
+    },
+}
+
+impl fmt::Display for MergeConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Column {
+                table,
+                column,
+                base,
+                overlay,
+            } => write!(
+                f,
+                "Column '{table}.{column}' is {base} in the base schema but {overlay} in the overlay"
+            ),
+            Self::Function { function } => {
+                write!(f, "Function '{function}' is defined differently in each schema")
+            }
+        }
+    }
+}
+
+/// The result of [`Schema::merge`]
+#[derive(Debug, Clone)]
+pub struct SchemaMerge {
+    /// The merged schema
+    pub schema: Schema,
+    /// Definitions the schemas disagreed on, resolved by the policy
+    pub conflicts: Vec<MergeConflict>,
+}
+
+impl Schema {
+    /// Merge `other` into a copy of this schema
+    ///
+    /// Tables, columns, functions, scopes and query parameters only `other`
+    /// defines are appended. Names match case-insensitively, as lookups do.
+    /// Conflicting columns and functions are resolved by `policy` and
+    /// reported in the result; table descriptions, folders, query parameter
+    /// types and the database name come from the winning side when it has
+    /// them.
+    #[must_use]
+    pub fn merge(&self, other: &Schema, policy: MergePolicy) -> SchemaMerge {
+        let overlay = policy == MergePolicy::Override;
+        let mut schema = self.clone();
+        let mut conflicts = Vec::new();
+
+        schema.database = if overlay {
+            other.database.clone().or(schema.database)
+        } else {
+            schema.database.or_else(|| other.database.clone())
+        };
+        merge_tables(
+            &mut schema.tables,
+            &other.tables,
+            "",
+            overlay,
+            &mut conflicts,
+        );
+
+        for function in &other.functions {
+            let existing = schema
+                .functions
+                .iter_mut()
+                .find(|f| f.name.eq_ignore_ascii_case(&function.name));
+            match existing {
+                None => schema.functions.push(function.clone()),
+                Some(existing) if existing == function => {}
+                Some(existing) => {
+                    conflicts.push(MergeConflict::Function {
+                        function: existing.name.clone(),
+                    });
+                    if overlay {
+                        *existing = function.clone();
+                    }
+                }
+            }
+        }
+
+        for parameter in &other.query_parameters {
+            let existing = schema
+                .query_parameters
+                .iter_mut()
+                .find(|p| p.name.eq_ignore_ascii_case(&parameter.name));
+            match existing {
+                None => schema.query_parameters.push(parameter.clone()),
+                Some(existing) if overlay => *existing = parameter.clone(),
+                Some(_) => {}
+            }
+        }
+
+        for scope in &other.scopes {
+            let existing = schema
+                .scopes
+                .iter_mut()
+                .find(|s| s.kind == scope.kind && s.name.eq_ignore_ascii_case(&scope.name));
+            match existing {
+                None => schema.scopes.push(scope.clone()),
+                Some(existing) => {
+                    let prefix = format!("{}('{}').", scope.kind.function_name(), scope.name);
+                    merge_tables(
+                        &mut existing.tables,
+                        &scope.tables,
+                        &prefix,
+                        overlay,
+                        &mut conflicts,
+                    );
+                }
+            }
+        }
+
+        SchemaMerge { schema, conflicts }
+    }
+}
+
+/// Merge the tables of the database or of one scope
+fn merge_tables(
+    tables: &mut Vec<Table>,
+    others: &[Table],
+    prefix: &str,
+    overlay: bool,
+    conflicts: &mut Vec<MergeConflict>,
+) {
+    for other in others {
+        let Some(table) = tables
+            .iter_mut()
+            .find(|t| t.name.eq_ignore_ascii_case(&other.name))
+        else {
+            tables.push(other.clone());
+            continue;
+        };
+        if overlay {
+            table.description = other.description.clone().or(table.description.take());
+            table.folder = other.folder.clone().or(table.folder.take());
+        } else {
+            table.description = table
+                .description
+                .take()
+                .or_else(|| other.description.clone());
+            table.folder = table.folder.take().or_else(|| other.folder.clone());
+        }
+
+        for column in &other.columns {
+            let existing = table
+                .columns
+                .iter_mut()
+                .find(|c| c.name.eq_ignore_ascii_case(&column.name));
+            match existing {
+                None => table.columns.push(column.clone()),
+                Some(existing) => {
+                    if existing.data_type != column.data_type {
+                        conflicts.push(MergeConflict::Column {
+                            table: format!("{prefix}{}", table.name),
+                            column: existing.name.clone(),
+                            base: existing.data_type.clone(),
+                            overlay: column.data_type.clone(),
+                        });
+                    }
+                    if overlay {
+                        *existing = column.clone();
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::{Function, Parameter, ResourceScope};
+
+    fn schemas() -> (Schema, Schema) {
+        let base = Schema::with_database("Base")
+            .table(
+                Table::new("Events")
+                    .with_column("Id", "long")
+                    .with_column("Level", "int")
+                    .folder("Core"),
+            )
+            .function(Function::new("f", "long").body("1"))
+            .scope(
+                ResourceScope::workspace("other").table(Table::new("A").with_column("x", "long")),
+            );
+        let overlay = Schema::new()
+            .table(
+                Table::new("events")
+                    .with_column("Level", "string")
+                    .with_column("Message", "string")
+                    .folder("Custom"),
+            )
+            .table(Table::new("Custom_CL").with_column("Data_s", "string"))
+            .function(Function::new("F", "long").body("2"))
+            .function(Function::new("g", "real"))
+            .query_parameter(Parameter::new("_since", "timespan"))
+            .scope(
+                ResourceScope::workspace("OTHER").table(Table::new("B").with_column("y", "long")),
+            );
+        (base, overlay)
+    }
+
+    #[test]
+    fn test_merge_union() {
+        let (base, overlay) = schemas();
+        let merged = base.merge(&overlay, MergePolicy::Union);
+        let schema = &merged.schema;
+
+        assert_eq!(schema.database.as_deref(), Some("Base"));
+        let events = schema.get_table("Events").unwrap();
+        let columns: Vec<_> = events
+            .columns
+            .iter()
+            .map(|c| (c.name.as_str(), c.data_type.clone()))
+            .collect();
+        assert_eq!(
+            columns,
+            [
+                ("Id", KqlType::Long),
+                ("Level", KqlType::Int),
+                ("Message", KqlType::String)
+            ]
+        );
+        assert_eq!(events.folder.as_deref(), Some("Core"));
+        assert!(schema.get_table("Custom_CL").is_some());
+        assert_eq!(schema.get_function("f").unwrap().body.as_deref(), Some("1"));
+        assert_eq!(schema.functions.len(), 2);
+        assert!(schema.get_query_parameter("_since").is_some());
+        assert_eq!(schema.scopes.len(), 1);
+        assert_eq!(schema.scopes[0].tables.len(), 2);
+
+        let conflicts: Vec<String> = merged.conflicts.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            conflicts,
+            [
+                "Column 'Events.Level' is int in the base schema but string in the overlay",
+                "Function 'f' is defined differently in each schema",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_merge_override() {
+        let (base, overlay) = schemas();
+        let merged = base.merge(&overlay, MergePolicy::Override);
+        let schema = &merged.schema;
+
+        let events = schema.get_table("Events").unwrap();
+        assert_eq!(
+            events.get_column("Level").unwrap().data_type,
+            KqlType::String
+        );
+        assert_eq!(events.columns.len(), 3);
+        assert_eq!(events.folder.as_deref(), Some("Custom"));
+        assert_eq!(schema.get_function("f").unwrap().body.as_deref(), Some("2"));
+        assert_eq!(merged.conflicts.len(), 2);
+
+        let same = base.merge(&base, MergePolicy::Override);
+        assert!(same.conflicts.is_empty());
+        assert_eq!(same.schema, base);
+    }
+}
@@ -0,0 +1,175 @@
+//! Multi-query script validation
+//!
+//! ADX tools treat a document as a series of query blocks separated by one
+//! or more blank lines. [`KqlValidator::validate_script`](crate::KqlValidator::validate_script)
+//! splits a document the same way, validates every block in one native call,
+//! and remaps each block's diagnostics into document coordinates.
+
+use crate::types::{Diagnostic, ValidationResult};
+
+/// Result of validating a multi-query script
+#[derive(Debug, Clone, Default)]
+pub struct ScriptValidation {
+    /// Query blocks, in document order
+    pub blocks: Vec<ScriptBlock>,
+}
+
+impl ScriptValidation {
+    /// Check if every block is valid
+    #[must_use]
+    pub fn is_valid(&self) -> bool {
+        self.blocks.iter().all(|b| b.result.is_valid())
+    }
+
+    /// Iterate over the diagnostics of all blocks, in document order
+    pub fn diagnostics(&self) -> impl Iterator<Item = &Diagnostic> {
+        self.blocks.iter().flat_map(|b| b.result.diagnostics.iter())
+    }
+
+    /// Get the block containing a document offset
+    #[must_use]
+    pub fn block_at(&self, offset: usize) -> Option<&ScriptBlock> {
+        self.blocks
+            .iter()
+            .find(|b| b.start <= offset && offset <= b.end)
+    }
+}
+
+/// A single query block of a script
+#[derive(Debug, Clone)]
+pub struct ScriptBlock {
+    /// Start offset of the block in the document (0-based, character position)
+    pub start: usize,
+    /// End offset of the block in the document (0-based, character position)
+    pub end: usize,
+    /// Line of the block's first line in the document (1-based)
+    pub start_line: usize,
+    /// Validation result, with offsets and lines relative to the document
+    pub result: ValidationResult,
+}
+
+/// A block of query text located in its document
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Block<'a> {
+    pub text: &'a str,
+    pub start: usize,
+    pub start_line: usize,
+}
+
+impl Block<'_> {
+    /// Attach a block-relative result, remapping it into document coordinates
+    pub(crate) fn into_script_block(self, mut result: ValidationResult) -> ScriptBlock {
+        for diagnostic in &mut result.diagnostics {
+            diagnostic.start += self.start;
+            diagnostic.end += self.start;
+            // Blocks start at column 1, so only lines move
+            diagnostic.line += self.start_line - 1;
+            for edit in diagnostic.fixes.iter_mut().flat_map(|f| f.edits.iter_mut()) {
+                edit.start += self.start;
+                edit.end += self.start;
+            }
+            for related in &mut diagnostic.related_spans {
+                related.start += self.start;
+                related.end += self.start;
+            }
+        }
+
+        ScriptBlock {
+            start: self.start,
+            end: self.start + self.text.chars().count(),
+            start_line: self.start_line,
+            result,
+        }
+    }
+}
+
+/// Split a document into blocks separated by blank lines
+pub(crate) fn split(text: &str) -> Vec<Block<'_>> {
+    let mut blocks = Vec::new();
+    // (byte offset, char offset, line) of the current block's first line
+    let mut current: Option<(usize, usize, usize)> = None;
+    let mut block_end = 0;
+    let mut byte_offset = 0;
+    let mut char_offset = 0;
+
+    for (index, line) in text.split_inclusive('\n').enumerate() {
+        if line.trim().is_empty() {
+            if let Some((byte_start, char_start, start_line)) = current.take() {
+                blocks.push(Block {
+                    text: &text[byte_start..block_end],
+                    start: char_start,
+                    start_line,
+                });
+            }
+        } else {
+            current.get_or_insert((byte_offset, char_offset, index + 1));
+            block_end = byte_offset + line.trim_end_matches(['\r', '\n']).len();
+        }
+
+        byte_offset += line.len();
+        char_offset += line.chars().count();
+    }
+
+    if let Some((byte_start, char_start, start_line)) = current {
+        blocks.push(Block {
+            text: &text[byte_start..block_end],
+            start: char_start,
+            start_line,
+        });
+    }
+
+    blocks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{DiagnosticSeverity, RelatedInfo};
+
+    #[test]
+    fn test_split_on_blank_lines() {
+        let text = "T | take 1\n| count\n\n  \r\nStormEvents\r\n| where é == 1\n\n\nX";
+        let blocks = split(text);
+
+        let texts: Vec<_> = blocks.iter().map(|b| b.text).collect();
+        assert_eq!(
+            texts,
+            ["T | take 1\n| count", "StormEvents\r\n| where é == 1", "X"]
+        );
+        let starts: Vec<_> = blocks.iter().map(|b| (b.start, b.start_line)).collect();
+        assert_eq!(starts, [(0, 1), (24, 5), (54, 9)]);
+        assert!(split("\n \n").is_empty());
+    }
+
+    #[test]
+    fn test_remap_diagnostics() {
+        let text = "T | take 1\n\nT\n| where";
+        let block = split(text)[1];
+        let diagnostic = Diagnostic {
+            message: "Missing expression".to_string(),
+            severity: DiagnosticSeverity::Error,
+            start: 9,
+            end: 9,
+            line: 2,
+            column: 8,
+            code: None,
+            category: None,
+            fixes: Vec::new(),
+            related_spans: vec![RelatedInfo {
+                message: "Table referenced here".to_string(),
+                start: 0,
+                end: 1,
+            }],
+        };
+
+        let remapped = block.into_script_block(ValidationResult::invalid(vec![diagnostic]));
+        assert_eq!(
+            (remapped.start, remapped.end, remapped.start_line),
+            (12, 21, 3)
+        );
+        let d = &remapped.result.diagnostics[0];
+        assert_eq!((d.start, d.line, d.column), (21, 4, 8));
+        assert_eq!(text.chars().nth(d.start - 1), Some('e'));
+        assert_eq!((d.related_spans[0].start, d.related_spans[0].end), (12, 13));
+    }
+}
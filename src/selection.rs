@@ -0,0 +1,104 @@
+//! Bracket matching and expand-selection ranges
+//!
+//! [`KqlValidator::get_bracket_pairs`](crate::KqlValidator::get_bracket_pairs)
+//! returns the matching `()`, `[]` and `{}` pairs of a query, skipping
+//! brackets inside strings and comments, with their nesting depth for
+//! rainbow highlighting.
+//! [`KqlValidator::get_enclosing_ranges`](crate::KqlValidator::get_enclosing_ranges)
+//! returns the syntax ranges containing a position, innermost first, which
+//! is what an LSP `selectionRange` response needs: each expand-selection step
+//! moves to the next range.
+
+use serde::{Deserialize, Serialize};
+
+/// A matching pair of brackets
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct BracketPair {
+    /// Offset of the opening bracket (0-based, character position)
+    pub open: usize,
+    /// Offset of the closing bracket (0-based, character position)
+    pub close: usize,
+    /// Nesting depth, 0 for brackets not inside another pair
+    pub depth: usize,
+}
+
+/// A syntax range enclosing a position
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct EnclosingRange {
+    /// Start offset (0-based, character position)
+    pub start: usize,
+    /// End offset (0-based, character position, exclusive)
+    pub end: usize,
+    /// Kind of syntax the range covers
+    pub kind: EnclosingRangeKind,
+}
+
+/// Kind of syntax an [`EnclosingRange`] covers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub enum EnclosingRangeKind {
+    /// A single token, such as a name or literal
+    Token,
+    /// An expression
+    Expression,
+    /// A pipeline stage (one query operator)
+    Stage,
+    /// A pipeline up to and including a stage
+    Pipeline,
+    /// A statement, such as a `let` or a query
+    Statement,
+    /// The whole document
+    Query,
+    /// Unknown kind
+    #[serde(other)]
+    Other,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deserialize_enclosing_ranges() {
+        let json = r#"[
+            {"start":10,"end":11,"kind":"Token"},
+            {"start":10,"end":15,"kind":"Expression"},
+            {"start":4,"end":15,"kind":"Stage"},
+            {"start":0,"end":15,"kind":"Pipeline"},
+            {"start":0,"end":15,"kind":"Statement"},
+            {"start":0,"end":15,"kind":"Query"},
+            {"start":0,"end":15,"kind":"Clause"}
+        ]"#;
+
+        let ranges: Vec<EnclosingRange> = serde_json::from_str(json).unwrap();
+        let kinds: Vec<_> = ranges.iter().map(|r| r.kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                EnclosingRangeKind::Token,
+                EnclosingRangeKind::Expression,
+                EnclosingRangeKind::Stage,
+                EnclosingRangeKind::Pipeline,
+                EnclosingRangeKind::Statement,
+                EnclosingRangeKind::Query,
+                EnclosingRangeKind::Other,
+            ]
+        );
+        assert!(ranges
+            .windows(2)
+            .all(|w| w[1].start <= w[0].start && w[0].end <= w[1].end));
+
+        let pairs: Vec<BracketPair> = serde_json::from_str(
+            r#"[{"open":8,"close":20,"depth":0},{"open":12,"close":15,"depth":1}]"#,
+        )
+        .unwrap();
+        assert_eq!(
+            pairs[1],
+            BracketPair {
+                open: 12,
+                close: 15,
+                depth: 1
+            }
+        );
+    }
+}
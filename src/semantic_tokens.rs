@@ -0,0 +1,162 @@
+//! Semantic-token legend for LSP servers
+//!
+//! LSP semantic tokens are encoded as indices into a legend that the server
+//! announces once. This module defines the canonical legend for the
+//! [`ClassificationKind`]s this crate emits, using the LSP standard token
+//! type and modifier names, so servers and clients agree without each
+//! maintaining their own table.
+//!
+//! # Example
+//!
+//! ```
+//! use kql_language_tools::semantic_tokens;
+//! use kql_language_tools::ClassificationKind;
+//!
+//! let legend = semantic_tokens::legend();
+//! let token = semantic_tokens::token_for(ClassificationKind::Column).unwrap();
+//! assert_eq!(legend.token_types[token.token_type as usize], "property");
+//! ```
+
+use crate::classification::ClassificationKind;
+
+/// Token types announced in the legend, in index order
+pub const TOKEN_TYPES: &[&str] = &[
+    "comment",
+    "string",
+    "number",
+    "keyword",
+    "operator",
+    "function",
+    "variable",
+    "parameter",
+    "property",
+    "type",
+    "class",
+    "namespace",
+    "macro",
+];
+
+/// Token modifiers announced in the legend, in bit order
+pub const TOKEN_MODIFIERS: &[&str] = &["readonly", "defaultLibrary"];
+
+/// Bit for the `readonly` modifier
+pub const MODIFIER_READONLY: u32 = 1 << 0;
+
+/// Bit for the `defaultLibrary` modifier
+pub const MODIFIER_DEFAULT_LIBRARY: u32 = 1 << 1;
+
+/// The semantic-token legend (`SemanticTokensLegend` in LSP)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Legend {
+    /// Token type names; a token's type is an index into this list
+    pub token_types: &'static [&'static str],
+    /// Token modifier names; a token's modifiers are a bitset over this list
+    pub token_modifiers: &'static [&'static str],
+}
+
+/// Get the canonical legend
+#[must_use]
+pub fn legend() -> Legend {
+    Legend {
+        token_types: TOKEN_TYPES,
+        token_modifiers: TOKEN_MODIFIERS,
+    }
+}
+
+/// Encoded type and modifiers of a semantic token
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SemanticToken {
+    /// Index into [`Legend::token_types`]
+    pub token_type: u32,
+    /// Bitset over [`Legend::token_modifiers`]
+    pub token_modifiers: u32,
+}
+
+/// Map a classification to its semantic token
+///
+/// Returns `None` for kinds that should not be emitted as semantic tokens
+/// (plain text and punctuation), leaving them to the client's syntax grammar.
+#[must_use]
+pub fn token_for(kind: ClassificationKind) -> Option<SemanticToken> {
+    use ClassificationKind as K;
+
+    let (name, modifiers) = match kind {
+        K::PlainText | K::Punctuation => return None,
+        K::Comment => ("comment", 0),
+        K::StringLiteral => ("string", 0),
+        K::Literal => ("number", 0),
+        K::Keyword | K::CommandKeyword | K::QueryOperator => ("keyword", 0),
+        K::Operator | K::ScalarOperator => ("operator", 0),
+        K::ScalarFunction | K::MaterializedViewFunction => ("function", 0),
+        K::AggregateFunction | K::Plugin => ("function", MODIFIER_DEFAULT_LIBRARY),
+        K::Identifier => ("variable", 0),
+        K::Variable => ("variable", MODIFIER_READONLY),
+        K::Parameter | K::QueryParameter => ("parameter", 0),
+        K::Column | K::Option => ("property", 0),
+        K::Type => ("type", 0),
+        K::Table => ("class", 0),
+        K::Database | K::Cluster => ("namespace", 0),
+        K::Directive | K::ClientDirective => ("macro", 0),
+    };
+
+    let token_type = TOKEN_TYPES.iter().position(|t| *t == name)?;
+    Some(SemanticToken {
+        token_type: u32::try_from(token_type).ok()?,
+        token_modifiers: modifiers,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_every_emitted_kind_is_in_legend() {
+        use ClassificationKind as K;
+
+        let emitted = [
+            K::Comment,
+            K::Directive,
+            K::Literal,
+            K::StringLiteral,
+            K::Type,
+            K::Identifier,
+            K::Column,
+            K::Table,
+            K::Database,
+            K::ScalarFunction,
+            K::AggregateFunction,
+            K::Keyword,
+            K::Operator,
+            K::Variable,
+            K::Parameter,
+            K::CommandKeyword,
+            K::QueryOperator,
+            K::ScalarOperator,
+            K::MaterializedViewFunction,
+            K::Plugin,
+            K::Option,
+            K::ClientDirective,
+            K::QueryParameter,
+            K::Cluster,
+        ];
+        for kind in emitted {
+            let token = token_for(kind).unwrap_or_else(|| panic!("{kind:?} not mapped"));
+            assert!((token.token_type as usize) < TOKEN_TYPES.len());
+            assert!(token.token_modifiers < 1 << TOKEN_MODIFIERS.len());
+        }
+        assert_eq!(token_for(K::PlainText), None);
+    }
+
+    #[test]
+    fn test_modifier_bits_match_legend() {
+        assert_eq!(
+            TOKEN_MODIFIERS[MODIFIER_READONLY.trailing_zeros() as usize],
+            "readonly"
+        );
+        assert_eq!(
+            TOKEN_MODIFIERS[MODIFIER_DEFAULT_LIBRARY.trailing_zeros() as usize],
+            "defaultLibrary"
+        );
+    }
+}
@@ -0,0 +1,153 @@
+//! Microsoft Sentinel helper functions
+//!
+//! Enabled by the `sentinel` feature, which also enables `asim`. Sentinel
+//! workspaces provide functions that analytic rules call without defining
+//! them, such as `_GetWatchlist('alias')` and the `_ASIM_*` helpers used by
+//! the ASIM parsers. [`schema`] defines them together with the ASIM parsers
+//! of [`asim::schema`](crate::asim::schema):
+//!
+//! ```
+//! use kql_language_tools::sentinel::{self, Watchlist};
+//!
+//! let schema = sentinel::schema_with_watchlists(&[
+//!     Watchlist::new("HighValueAssets").with_column("Hostname", "string"),
+//! ]);
+//! let watchlist = schema.get_function("_GetWatchlist").unwrap();
+//! assert!(watchlist.is_tabular());
+//! assert!(schema.get_function("_ASim_Dns").is_some());
+//! ```
+//!
+//! A function's return type cannot depend on its argument, so
+//! `_GetWatchlist` returns the columns every watchlist has plus the columns
+//! of all the watchlists passed to [`schema_with_watchlists`].
+
+use crate::kql_type::KqlType;
+use crate::schema::{Function, Schema};
+
+/// Columns every watchlist returned by `_GetWatchlist` has
+const WATCHLIST_COLUMNS: &[(&str, KqlType)] = &[
+    ("_DTItemId", KqlType::String),
+    ("LastUpdatedTimeUTC", KqlType::DateTime),
+    ("SearchKey", KqlType::String),
+    ("WatchlistItem", KqlType::Dynamic),
+];
+
+/// A Sentinel watchlist and the columns of its items
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Watchlist {
+    /// Watchlist alias, as passed to `_GetWatchlist`
+    pub alias: String,
+    /// Columns of the watchlist's items
+    pub columns: Vec<(String, KqlType)>,
+}
+
+impl Watchlist {
+    /// Create a watchlist without item columns
+    #[must_use]
+    pub fn new(alias: impl Into<String>) -> Self {
+        Self {
+            alias: alias.into(),
+            columns: Vec::new(),
+        }
+    }
+
+    /// Builder method to add an item column
+    #[must_use]
+    pub fn with_column(mut self, name: impl Into<String>, data_type: impl Into<KqlType>) -> Self {
+        self.columns.push((name.into(), data_type.into()));
+        self
+    }
+}
+
+/// The Sentinel helper functions and the ASIM parsers
+///
+/// The schema has no database name or tables; merge it with a workspace
+/// schema using [`Schema::merge`].
+#[must_use]
+pub fn schema() -> Schema {
+    schema_with_watchlists(&[])
+}
+
+/// The Sentinel helper functions and the ASIM parsers, with the item
+/// columns of `watchlists` added to `_GetWatchlist`'s result
+#[must_use]
+pub fn schema_with_watchlists(watchlists: &[Watchlist]) -> Schema {
+    let mut columns: Vec<(String, KqlType)> = WATCHLIST_COLUMNS
+        .iter()
+        .map(|(name, data_type)| ((*name).to_string(), data_type.clone()))
+        .collect();
+    for (name, data_type) in watchlists.iter().flat_map(|w| &w.columns) {
+        if !columns.iter().any(|(c, _)| c.eq_ignore_ascii_case(name)) {
+            columns.push((name.clone(), data_type.clone()));
+        }
+    }
+
+    let helper = |function: Function| function.folder("Sentinel");
+    let mut schema = Schema::new()
+        .function(helper(
+            Function::tabular("_GetWatchlist", KqlType::Table(columns))
+                .param("watchlistAlias", KqlType::String)
+                .description("Items of the watchlist with the given alias"),
+        ))
+        .function(helper(
+            Function::new("_ASIM_GetSourceBySourceType", KqlType::Dynamic)
+                .param("SourceType", KqlType::String)
+                .description("Sources configured for an ASIM source type, from the ASimSourceType watchlist"),
+        ))
+        .function(helper(
+            Function::new("_ASIM_GetUsernameType", KqlType::String)
+                .param("username", KqlType::String)
+                .description("Format of a username: Simple, UPN, Windows, DN or Unknown"),
+        ))
+        .function(helper(
+            Function::new("_ASIM_GetUserType", KqlType::String)
+                .param("name", KqlType::String)
+                .param("sid", KqlType::String)
+                .description("Type of a user from its name and SID: Regular, Machine, Admin, System or Other"),
+        ));
+    schema.functions.extend(crate::asim::schema().functions);
+    schema
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sentinel_schema() {
+        let schema = schema_with_watchlists(&[
+            Watchlist::new("HighValueAssets")
+                .with_column("Hostname", "string")
+                .with_column("SearchKey", "string"),
+            Watchlist::new("VIPUsers").with_column("UserPrincipalName", "string"),
+        ]);
+        assert_eq!(schema.validate(), []);
+
+        let watchlist = schema.get_function("_GetWatchlist").unwrap();
+        let KqlType::Table(columns) = &watchlist.return_type else {
+            panic!("expected a tabular return type");
+        };
+        let names: Vec<_> = columns.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "_DTItemId",
+                "LastUpdatedTimeUTC",
+                "SearchKey",
+                "WatchlistItem",
+                "Hostname",
+                "UserPrincipalName"
+            ]
+        );
+        assert_eq!(
+            schema
+                .get_function("_ASIM_GetUserType")
+                .unwrap()
+                .parameters
+                .len(),
+            2
+        );
+        assert!(schema.get_function("imAuthentication").is_some());
+        assert!(schema.tables.is_empty());
+    }
+}
@@ -0,0 +1,165 @@
+//! Diagnostic severity overrides
+//!
+//! A [`DiagnosticsConfig`] changes the severity of diagnostics after
+//! validation: promote or demote diagnostics by code, silence codes
+//! entirely, or treat every warning as an error (as CI pipelines usually
+//! want). Validity is recomputed from the effective severities, so
+//! [`ValidationResult::is_valid`] reflects the overrides.
+
+use crate::types::{DiagnosticSeverity, ValidationResult};
+use std::collections::BTreeMap;
+
+/// Severity overrides applied to validation results
+///
+/// Per-code overrides take precedence over
+/// [`warnings_as_errors`](Self::warnings_as_errors), so a code demoted to a
+/// warning stays a warning.
+///
+/// # Example
+///
+/// ```
+/// use kql_language_tools::{DiagnosticSeverity, DiagnosticsConfig};
+///
+/// let config = DiagnosticsConfig::new()
+///     .warnings_as_errors(true)
+///     .severity("KS142", DiagnosticSeverity::Information)
+///     .suppress("KS204");
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DiagnosticsConfig {
+    /// Severity per diagnostic code; `None` suppresses the code
+    overrides: BTreeMap<String, Option<DiagnosticSeverity>>,
+    /// Promote warnings without a per-code override to errors
+    warnings_as_errors: bool,
+}
+
+impl DiagnosticsConfig {
+    /// Create a configuration that changes nothing
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Builder method to set the severity of a diagnostic code
+    #[must_use]
+    pub fn severity(mut self, code: impl Into<String>, severity: DiagnosticSeverity) -> Self {
+        self.overrides.insert(code.into(), Some(severity));
+        self
+    }
+
+    /// Builder method to drop diagnostics with a code
+    #[must_use]
+    pub fn suppress(mut self, code: impl Into<String>) -> Self {
+        self.overrides.insert(code.into(), None);
+        self
+    }
+
+    /// Builder method to treat warnings as errors
+    #[must_use]
+    pub fn warnings_as_errors(mut self, enabled: bool) -> Self {
+        self.warnings_as_errors = enabled;
+        self
+    }
+
+    /// Check whether the configuration changes nothing
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.overrides.is_empty() && !self.warnings_as_errors
+    }
+
+    /// Apply the overrides to a validation result
+    ///
+    /// Suppressed diagnostics are removed, the rest get their effective
+    /// severity, and the result's validity is recomputed.
+    pub fn apply(&self, result: &mut ValidationResult) {
+        if self.is_empty() {
+            return;
+        }
+
+        result.diagnostics.retain_mut(|diagnostic| {
+            let code_override = diagnostic
+                .code
+                .as_deref()
+                .and_then(|code| self.overrides.get(code));
+
+            match code_override {
+                Some(Some(severity)) => diagnostic.severity = *severity,
+                Some(None) => return false,
+                None if self.warnings_as_errors
+                    && diagnostic.severity == DiagnosticSeverity::Warning =>
+                {
+                    diagnostic.severity = DiagnosticSeverity::Error;
+                }
+                None => {}
+            }
+            true
+        });
+        result.valid = !result.has_errors();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Diagnostic;
+
+    fn diagnostic(code: &str, severity: DiagnosticSeverity) -> Diagnostic {
+        Diagnostic {
+            message: code.to_string(),
+            severity,
+            start: 0,
+            end: 1,
+            line: 1,
+            column: 1,
+            code: Some(code.to_string()),
+            category: None,
+            fixes: Vec::new(),
+            related_spans: Vec::new(),
+        }
+    }
+
+    fn severities(result: &ValidationResult) -> Vec<(&str, DiagnosticSeverity)> {
+        result
+            .diagnostics
+            .iter()
+            .map(|d| (d.code.as_deref().unwrap(), d.severity))
+            .collect()
+    }
+
+    #[test]
+    fn test_warnings_as_errors() {
+        let mut result =
+            ValidationResult::invalid(vec![diagnostic("KS500", DiagnosticSeverity::Warning)]);
+        result.valid = true;
+
+        DiagnosticsConfig::new()
+            .warnings_as_errors(true)
+            .apply(&mut result);
+        assert_eq!(severities(&result), [("KS500", DiagnosticSeverity::Error)]);
+        assert!(!result.is_valid());
+    }
+
+    #[test]
+    fn test_overrides_take_precedence() {
+        let mut result = ValidationResult::invalid(vec![
+            diagnostic("KS142", DiagnosticSeverity::Error),
+            diagnostic("KS204", DiagnosticSeverity::Error),
+            diagnostic("KS500", DiagnosticSeverity::Warning),
+        ]);
+
+        DiagnosticsConfig::new()
+            .warnings_as_errors(true)
+            .severity("KS142", DiagnosticSeverity::Information)
+            .severity("KS500", DiagnosticSeverity::Warning)
+            .suppress("KS204")
+            .apply(&mut result);
+        assert_eq!(
+            severities(&result),
+            [
+                ("KS142", DiagnosticSeverity::Information),
+                ("KS500", DiagnosticSeverity::Warning),
+            ]
+        );
+        assert!(result.is_valid());
+    }
+}
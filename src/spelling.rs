@@ -0,0 +1,183 @@
+//! "Did you mean" suggestions for misspelled names
+//!
+//! When schema validation reports that a name does not resolve (see
+//! [`KustoDiagnosticCode::is_unresolved_name`]), the closest table, column,
+//! and function names are attached to the diagnostic as quick fixes. Matching is case-insensitive edit distance, so
+//! `SignInLogs` finds `SigninLogs` and `UserPrincipleName` finds
+//! `UserPrincipalName`.
+
+use crate::codes::KustoDiagnosticCode;
+use crate::schema::Schema;
+use crate::types::{Fix, TextEdit, ValidationResult};
+use std::collections::BTreeSet;
+
+/// Maximum number of suggestions attached to a diagnostic
+const MAX_SUGGESTIONS: usize = 3;
+
+/// Attach "did you mean" fixes to unresolved-name errors
+pub(crate) fn suggest_names(query: &str, schema: &Schema, result: &mut ValidationResult) {
+    let names = schema_names(schema);
+    if names.is_empty() {
+        return;
+    }
+
+    let unresolved = |code: KustoDiagnosticCode| code.is_unresolved_name();
+    for diagnostic in result
+        .diagnostics
+        .iter_mut()
+        .filter(|d| d.is_error() && d.parsed_code().is_some_and(unresolved))
+    {
+        let name: String = query
+            .chars()
+            .skip(diagnostic.start)
+            .take(diagnostic.length())
+            .collect();
+        if !is_identifier(&name) || names.contains(name.as_str()) {
+            continue;
+        }
+
+        for candidate in closest(&name, &names) {
+            diagnostic.fixes.push(Fix {
+                title: format!("Did you mean `{candidate}`?"),
+                edits: vec![TextEdit {
+                    start: diagnostic.start,
+                    end: diagnostic.end,
+                    new_text: candidate.to_string(),
+                }],
+            });
+        }
+    }
+}
+
+/// Collect every table, column, and function name in the schema
+fn schema_names(schema: &Schema) -> BTreeSet<&str> {
+    let tables = schema
+        .tables
+        .iter()
+        .chain(schema.scopes.iter().flat_map(|s| s.tables.iter()));
+
+    let mut names = BTreeSet::new();
+    for table in tables {
+        names.insert(table.name.as_str());
+        names.extend(table.columns.iter().map(|c| c.name.as_str()));
+    }
+    names.extend(schema.functions.iter().map(|f| f.name.as_str()));
+    names
+}
+
+/// Check if text is a plain (unquoted) KQL identifier
+fn is_identifier(text: &str) -> bool {
+    let mut chars = text.chars();
+    chars.next().is_some_and(|c| c.is_alphabetic() || c == '_')
+        && chars.all(|c| c.is_alphanumeric() || c == '_')
+}
+
+/// Find the names closest to `name`, best first
+///
+/// A name qualifies when its case-insensitive edit distance is at most a
+/// third of the name's length (and at least 1).
+fn closest<'a>(name: &str, names: &BTreeSet<&'a str>) -> Vec<&'a str> {
+    let lower = name.to_lowercase();
+    let max_distance = (name.chars().count() / 3).max(1);
+
+    let mut matches: Vec<(usize, &str)> = names
+        .iter()
+        .map(|candidate| (edit_distance(&lower, &candidate.to_lowercase()), *candidate))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .collect();
+    matches.sort_unstable();
+    matches
+        .into_iter()
+        .take(MAX_SUGGESTIONS)
+        .map(|(_, candidate)| candidate)
+        .collect()
+}
+
+/// Levenshtein distance between two strings, by character
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+
+    for (i, ca) in a.chars().enumerate() {
+        current[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::Table;
+    use crate::types::{Diagnostic, DiagnosticSeverity};
+
+    fn error_at(query: &str, name: &str, code: &str) -> Diagnostic {
+        let start = query[..query.find(name).unwrap()].chars().count();
+        Diagnostic {
+            message: format!("The name '{name}' does not refer to any known table"),
+            severity: DiagnosticSeverity::Error,
+            start,
+            end: start + name.chars().count(),
+            line: 1,
+            column: start + 1,
+            code: Some(code.to_string()),
+            category: None,
+            fixes: Vec::new(),
+            related_spans: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("signinlogs", "signinlogs"), 0);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("", "abc"), 3);
+    }
+
+    #[test]
+    fn test_suggest_names() {
+        let schema = Schema::new()
+            .table(
+                Table::new("SigninLogs")
+                    .with_column("UserPrincipalName", "string")
+                    .with_column("ResultType", "string"),
+            )
+            .table(Table::new("AuditLogs"));
+        let query = "SignInLogs | where UserPrincipleName == 'é' | take Foo | AuditLog";
+
+        let mut result = ValidationResult::invalid(vec![
+            error_at(query, "SignInLogs", "KS204"),
+            error_at(query, "UserPrincipleName", "KS142"),
+            error_at(query, "Foo", "KS142"),
+            error_at(query, "'é'", "KS142"),
+            // Not a name lookup, so no suggestion despite the close name
+            error_at(query, "AuditLog", "KS005"),
+        ]);
+        suggest_names(query, &schema, &mut result);
+
+        let titles: Vec<Vec<&str>> = result
+            .diagnostics
+            .iter()
+            .map(|d| d.fixes.iter().map(|f| f.title.as_str()).collect())
+            .collect();
+        assert_eq!(
+            titles,
+            [
+                vec!["Did you mean `SigninLogs`?"],
+                vec!["Did you mean `UserPrincipalName`?"],
+                vec![],
+                vec![],
+                vec![],
+            ]
+        );
+
+        let edit = &result.diagnostics[1].fixes[0].edits[0];
+        assert_eq!((edit.start, edit.end), (19, 36));
+    }
+}
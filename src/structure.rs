@@ -0,0 +1,232 @@
+//! Document structure types
+//!
+//! A single tree of statements, declarations, and foldable regions, so that
+//! an LSP server can build the outline, folding ranges, and statement list
+//! for a document from one native call.
+//!
+//! [`DocumentStructure::folding_ranges`] flattens the foldable regions into
+//! the one-range-per-line list editors expect.
+
+use serde::{Deserialize, Serialize};
+
+/// Structure of a KQL document
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DocumentStructure {
+    /// Top-level statements and comment blocks, in source order
+    pub statements: Vec<StructureNode>,
+}
+
+impl DocumentStructure {
+    /// Iterate over every node in the tree, depth-first in source order
+    pub fn nodes(&self) -> impl Iterator<Item = &StructureNode> {
+        let mut stack: Vec<&StructureNode> = self.statements.iter().rev().collect();
+        std::iter::from_fn(move || {
+            let node = stack.pop()?;
+            stack.extend(node.children.iter().rev());
+            Some(node)
+        })
+    }
+
+    /// Iterate over declarations (variables, functions, parameters)
+    pub fn declarations(&self) -> impl Iterator<Item = &StructureNode> {
+        self.nodes().filter(|n| n.kind.is_declaration())
+    }
+
+    /// Iterate over nodes that span more than one line
+    pub fn foldable(&self) -> impl Iterator<Item = &StructureNode> {
+        self.nodes().filter(|n| n.is_foldable())
+    }
+
+    /// Get folding ranges for the document, ordered by start line
+    ///
+    /// Covers multi-line `let` statements, function bodies, parenthesized
+    /// expressions, and comment blocks. Where several regions start on the
+    /// same line (a `let` statement and its function body, say), only the
+    /// outermost is kept, since editors fold by line.
+    #[must_use]
+    pub fn folding_ranges(&self) -> Vec<FoldingRange> {
+        let mut ranges: Vec<FoldingRange> = self
+            .foldable()
+            .map(|node| FoldingRange {
+                start: node.start,
+                end: node.end,
+                start_line: node.start_line,
+                end_line: node.end_line,
+                kind: if node.kind == StructureKind::CommentBlock {
+                    FoldingRangeKind::Comment
+                } else {
+                    FoldingRangeKind::Region
+                },
+            })
+            .collect();
+
+        ranges.sort_by(|a, b| {
+            a.start_line
+                .cmp(&b.start_line)
+                .then(b.end_line.cmp(&a.end_line))
+        });
+        ranges.dedup_by_key(|r| r.start_line);
+        ranges
+    }
+}
+
+/// A foldable range of lines
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FoldingRange {
+    /// Start offset (0-based, character position)
+    pub start: usize,
+    /// End offset (0-based, character position)
+    pub end: usize,
+    /// First line of the range (1-based)
+    pub start_line: usize,
+    /// Last line of the range (1-based)
+    pub end_line: usize,
+    /// Kind of range
+    pub kind: FoldingRangeKind,
+}
+
+/// Kind of folding range
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FoldingRangeKind {
+    /// A block of `//` comment lines
+    Comment,
+    /// A statement, function body, or parenthesized expression
+    Region,
+}
+
+/// A node in the document structure tree
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StructureNode {
+    /// Kind of node
+    pub kind: StructureKind,
+    /// Declared name (declarations only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Start offset (0-based, character position)
+    pub start: usize,
+    /// End offset (0-based, character position)
+    pub end: usize,
+    /// Line of the start offset (1-based)
+    pub start_line: usize,
+    /// Line of the end offset (1-based)
+    pub end_line: usize,
+    /// Nested nodes, in source order
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<StructureNode>,
+}
+
+impl StructureNode {
+    /// Check if the node spans more than one line
+    #[must_use]
+    pub fn is_foldable(&self) -> bool {
+        self.end_line > self.start_line
+    }
+}
+
+/// Kind of document structure node
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub enum StructureKind {
+    /// A `let` statement
+    LetStatement,
+    /// A query (tabular expression) statement
+    QueryStatement,
+    /// A `set` option statement
+    SetStatement,
+    /// A `declare query_parameters` statement
+    DeclareStatement,
+    /// Any other statement
+    OtherStatement,
+    /// A variable declared by `let`
+    VariableDeclaration,
+    /// A function declared by `let`
+    FunctionDeclaration,
+    /// A function parameter
+    ParameterDeclaration,
+    /// The `{ ... }` body of a function
+    FunctionBody,
+    /// A parenthesized subquery or expression
+    Subquery,
+    /// Consecutive `//` comment lines
+    CommentBlock,
+    /// Unknown kind
+    #[serde(other)]
+    Other,
+}
+
+impl StructureKind {
+    /// Check if this kind is a statement
+    #[must_use]
+    pub fn is_statement(self) -> bool {
+        matches!(
+            self,
+            Self::LetStatement
+                | Self::QueryStatement
+                | Self::SetStatement
+                | Self::DeclareStatement
+                | Self::OtherStatement
+        )
+    }
+
+    /// Check if this kind is a declaration
+    #[must_use]
+    pub fn is_declaration(self) -> bool {
+        matches!(
+            self,
+            Self::VariableDeclaration | Self::FunctionDeclaration | Self::ParameterDeclaration
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deserialize_and_walk_tree() {
+        let json = r#"{"statements":[
+            {"kind":"CommentBlock","start":0,"end":20,"start_line":1,"end_line":2},
+            {"kind":"LetStatement","start":21,"end":60,"start_line":3,"end_line":5,"children":[
+                {"kind":"FunctionDeclaration","name":"f","start":25,"end":26,"start_line":3,"end_line":3,"children":[
+                    {"kind":"ParameterDeclaration","name":"x","start":28,"end":29,"start_line":3,"end_line":3},
+                    {"kind":"FunctionBody","start":35,"end":59,"start_line":3,"end_line":5}
+                ]}
+            ]},
+            {"kind":"QueryStatement","start":62,"end":70,"start_line":6,"end_line":6}
+        ]}"#;
+
+        let structure: DocumentStructure = serde_json::from_str(json).unwrap();
+        let kinds: Vec<_> = structure.nodes().map(|n| n.kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                StructureKind::CommentBlock,
+                StructureKind::LetStatement,
+                StructureKind::FunctionDeclaration,
+                StructureKind::ParameterDeclaration,
+                StructureKind::FunctionBody,
+                StructureKind::QueryStatement,
+            ]
+        );
+
+        let names: Vec<_> = structure
+            .declarations()
+            .filter_map(|n| n.name.as_deref())
+            .collect();
+        assert_eq!(names, ["f", "x"]);
+        assert_eq!(structure.foldable().count(), 3);
+
+        let ranges: Vec<_> = structure
+            .folding_ranges()
+            .iter()
+            .map(|r| (r.start_line, r.end_line, r.kind))
+            .collect();
+        assert_eq!(
+            ranges,
+            [
+                (1, 2, FoldingRangeKind::Comment),
+                (3, 5, FoldingRangeKind::Region),
+            ]
+        );
+    }
+}
@@ -0,0 +1,156 @@
+//! Inline (ghost-text) suggestions
+//!
+//! Editors show a single greyed-out continuation after the cursor that the
+//! user accepts with Tab. [`KqlValidator::suggest_next`](crate::KqlValidator::suggest_next)
+//! produces that continuation from the completion items at the cursor plus
+//! a few pipeline heuristics:
+//!
+//! - a partially typed word is completed with the best matching item
+//! - after a `|`, the next operator is chosen from the operators already in
+//!   the pipeline (`where` first, then `summarize count() by`, then `order by`)
+//! - at the end of a finished line, a new `| ...` step is suggested
+
+use crate::completion::{CompletionKind, CompletionResult};
+use serde::{Deserialize, Serialize};
+
+/// A single inline continuation
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InlineSuggestion {
+    /// Text to insert at the cursor
+    pub text: String,
+}
+
+/// Pick the continuation for the text before the cursor
+pub(crate) fn suggest(prefix: &str, completions: &CompletionResult) -> Option<InlineSuggestion> {
+    let word = &prefix[prefix.trim_end_matches(is_word_char).len()..];
+
+    let text = if !word.is_empty() {
+        complete_word(word, completions)?
+    } else if prefix.trim_end().ends_with('|') {
+        let separator = if prefix.ends_with('|') { " " } else { "" };
+        format!("{separator}{}", next_operator(prefix))
+    } else if prefix.ends_with('\n') && ends_complete_step(prefix) {
+        format!("| {}", next_operator(prefix))
+    } else {
+        return None;
+    };
+
+    Some(InlineSuggestion { text })
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// Complete a partially typed word with the best matching completion item
+fn complete_word(word: &str, completions: &CompletionResult) -> Option<String> {
+    let lower = word.to_lowercase();
+    completions
+        .items
+        .iter()
+        .filter(|item| item.kind != CompletionKind::Punctuation)
+        .map(|item| item.insert_text.as_deref().unwrap_or(&item.label))
+        .filter(|text| text.len() > word.len() && text.to_lowercase().starts_with(&lower))
+        .find_map(|text| text.get(word.len()..))
+        .map(str::to_string)
+}
+
+/// Choose the next operator from the operators already in the last pipeline
+fn next_operator(prefix: &str) -> &'static str {
+    let statement = prefix.rsplit(';').next().unwrap_or(prefix);
+    let operators: Vec<String> = statement
+        .split('|')
+        .skip(1)
+        .filter_map(|step| step.split_whitespace().next())
+        .map(str::to_lowercase)
+        .collect();
+    let has = |name: &str| operators.iter().any(|op| op == name);
+
+    if has("summarize") {
+        if has("order") || has("sort") || has("top") {
+            "take 100"
+        } else {
+            "order by count_ desc"
+        }
+    } else if has("where") {
+        "summarize count() by "
+    } else {
+        "where "
+    }
+}
+
+/// Check the last line ends a pipeline step rather than mid-expression
+fn ends_complete_step(prefix: &str) -> bool {
+    match prefix.trim_end().chars().last() {
+        None => false,
+        Some(c) => !matches!(
+            c,
+            '|' | ',' | '(' | '[' | '{' | '=' | '<' | '>' | '+' | '-' | '*' | '/' | '%' | '!' | ';'
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::completion::CompletionItem;
+
+    fn completions(labels: &[&str]) -> CompletionResult {
+        CompletionResult {
+            items: labels
+                .iter()
+                .enumerate()
+                .map(|(i, label)| CompletionItem {
+                    label: (*label).to_string(),
+                    kind: CompletionKind::Table,
+                    detail: None,
+                    insert_text: None,
+                    sort_order: i32::try_from(i).unwrap(),
+                    edit_start: 0,
+                    documentation: None,
+                    matches_expected_type: false,
+                })
+                .collect(),
+            context: None,
+            expected_type: None,
+        }
+    }
+
+    fn text(prefix: &str, labels: &[&str]) -> Option<String> {
+        suggest(prefix, &completions(labels)).map(|s| s.text)
+    }
+
+    #[test]
+    fn test_completes_partial_word() {
+        let labels = ["SecurityAlert", "SecurityEvent"];
+        assert_eq!(text("Secu", &labels).as_deref(), Some("rityAlert"));
+        assert_eq!(
+            text("T | where x == 1 and SecurityE", &labels).as_deref(),
+            Some("vent")
+        );
+        assert_eq!(text("Foo", &labels), None);
+    }
+
+    #[test]
+    fn test_suggests_next_operator_after_pipe() {
+        assert_eq!(text("SecurityEvent |", &[]).as_deref(), Some(" where "));
+        assert_eq!(
+            text("SecurityEvent | where EventID == 4625 | ", &[]).as_deref(),
+            Some("summarize count() by ")
+        );
+        assert_eq!(
+            text("T | where A | summarize count() by B | ", &[]).as_deref(),
+            Some("order by count_ desc")
+        );
+    }
+
+    #[test]
+    fn test_suggests_new_step_after_finished_line() {
+        assert_eq!(
+            text("SecurityEvent\n| where EventID == 4625\n", &[]).as_deref(),
+            Some("| summarize count() by ")
+        );
+        assert_eq!(text("SecurityEvent\n| where EventID ==\n", &[]), None);
+        assert_eq!(text("T | where x > ", &[]), None);
+    }
+}
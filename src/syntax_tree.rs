@@ -0,0 +1,576 @@
+//! Syntax tree access
+//!
+//! [`KqlValidator::get_syntax_tree`](crate::KqlValidator::get_syntax_tree)
+//! returns Kusto.Language's parse tree for a query as a [`SyntaxTree`].
+//! Nodes and tokens are both [`SyntaxNode`]s: `kind` is the Kusto.Language
+//! `SyntaxKind` name (`SummarizeOperator`, `FunctionCallExpression`,
+//! `IdentifierToken`, `WhereKeyword`, ...), and only tokens have text.
+//! Comments and whitespace are not part of the tree, and neither are the
+//! empty tokens the parser inserts for missing syntax.
+//!
+//! Trees serialize to nested JSON for other tools. To find nodes, select
+//! them by [`NodeKind`] or by position:
+//!
+//! ```
+//! use kql_language_tools::{NodeKind, SyntaxTree};
+//!
+//! fn grouped_columns(tree: &SyntaxTree) -> usize {
+//!     tree.find_all(NodeKind::SummarizeOperator)
+//!         .flat_map(|summarize| summarize.find_all(NodeKind::NameReference))
+//!         .count()
+//! }
+//!
+//! fn enclosing_operator(tree: &SyntaxTree, offset: usize) -> Option<&str> {
+//!     let token = tree.node_at(offset)?;
+//!     tree.ancestors(token)
+//!         .find(|node| node.node_kind().is_some_and(NodeKind::is_query_operator))
+//!         .map(|node| node.kind.as_str())
+//! }
+//! ```
+//!
+//! To look at every node with enter and leave callbacks, implement
+//! [`SyntaxVisitor`] and pass it to [`SyntaxNode::walk`]:
+//!
+//! ```
+//! use kql_language_tools::{SyntaxNode, SyntaxVisitor};
+//!
+//! struct Operators(Vec<String>);
+//!
+//! impl SyntaxVisitor for Operators {
+//!     fn enter(&mut self, node: &SyntaxNode) -> bool {
+//!         if node.kind.ends_with("Operator") {
+//!             self.0.push(node.kind.clone());
+//!         }
+//!         true
+//!     }
+//! }
+//!
+//! # let tree = kql_language_tools::SyntaxTree::default();
+//! let mut operators = Operators(Vec::new());
+//! tree.root.walk(&mut operators);
+//! ```
+
+use serde::{Deserialize, Serialize};
+
+/// The parse tree of a query
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SyntaxTree {
+    /// Root node, covering the whole query
+    pub root: SyntaxNode,
+}
+
+impl SyntaxTree {
+    /// Visit every node of the tree in source order (see [`SyntaxNode::walk`])
+    pub fn walk<V: SyntaxVisitor + ?Sized>(&self, visitor: &mut V) {
+        self.root.walk(visitor);
+    }
+
+    /// Iterate over every node and token of the tree, in source order
+    pub fn descendants(&self) -> impl Iterator<Item = &SyntaxNode> {
+        self.root.descendants()
+    }
+
+    /// Iterate over the nodes of a kind, in source order
+    pub fn find_all(&self, kind: NodeKind) -> impl Iterator<Item = &SyntaxNode> {
+        self.root.find_all(kind)
+    }
+
+    /// Get the innermost node or token covering a character offset
+    #[must_use]
+    pub fn node_at(&self, offset: usize) -> Option<&SyntaxNode> {
+        self.root.node_at(offset)
+    }
+
+    /// Get the parent of a node of this tree
+    ///
+    /// Returns `None` for the root and for nodes of another tree.
+    #[must_use]
+    pub fn parent(&self, node: &SyntaxNode) -> Option<&SyntaxNode> {
+        self.ancestors(node).next()
+    }
+
+    /// Iterate over the ancestors of a node of this tree, parent first and
+    /// root last
+    ///
+    /// `node` must be borrowed from this tree (for example, returned by
+    /// [`find_all`](Self::find_all) or [`node_at`](Self::node_at)); nodes of
+    /// another tree have no ancestors.
+    pub fn ancestors<'a>(&'a self, node: &SyntaxNode) -> impl Iterator<Item = &'a SyntaxNode> {
+        self.path_to(node).into_iter().rev()
+    }
+
+    /// The nodes from the root down to `node`'s parent
+    fn path_to(&self, node: &SyntaxNode) -> Vec<&SyntaxNode> {
+        // Siblings never overlap, so only one child can contain the node
+        let mut path = Vec::new();
+        let mut current = &self.root;
+        while !std::ptr::eq(current, node) {
+            path.push(current);
+            let Some(child) = current
+                .children
+                .iter()
+                .find(|child| child.start <= node.start && node.end <= child.end)
+            else {
+                return Vec::new();
+            };
+            current = child;
+        }
+        path
+    }
+
+    /// Build the tree from nodes in pre-order, each naming its parent
+    pub(crate) fn from_nodes(nodes: Vec<FlatNode>) -> Self {
+        let mut parents = Vec::with_capacity(nodes.len());
+        let mut tree: Vec<SyntaxNode> = nodes
+            .into_iter()
+            .map(|node| {
+                parents.push(node.parent);
+                SyntaxNode {
+                    kind: node.kind,
+                    start: node.start,
+                    end: node.end,
+                    text: node.text,
+                    children: Vec::new(),
+                }
+            })
+            .collect();
+
+        // A node's children come after it, so moving nodes into their parents
+        // from the back completes each node before it is moved
+        for index in (1..tree.len()).rev() {
+            let mut node = std::mem::take(&mut tree[index]);
+            node.children.reverse();
+            match parents[index] {
+                Some(parent) if parent < index => tree[parent].children.push(node),
+                _ => {}
+            }
+        }
+        let mut root = tree.into_iter().next().unwrap_or_default();
+        root.children.reverse();
+        Self { root }
+    }
+}
+
+/// A node or token of a [`SyntaxTree`]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SyntaxNode {
+    /// Kusto.Language `SyntaxKind` name, such as `SummarizeOperator`
+    pub kind: String,
+    /// Start offset (0-based, character position)
+    pub start: usize,
+    /// End offset (0-based, character position, exclusive)
+    pub end: usize,
+    /// Text of a token (`None` for nodes)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    /// Child nodes and tokens, in source order
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<SyntaxNode>,
+}
+
+impl SyntaxNode {
+    /// Check if this is a token rather than a node
+    #[must_use]
+    pub fn is_token(&self) -> bool {
+        self.text.is_some()
+    }
+
+    /// Get the node's kind, if it is one of [`NodeKind`]'s
+    #[must_use]
+    pub fn node_kind(&self) -> Option<NodeKind> {
+        NodeKind::parse(&self.kind)
+    }
+
+    /// Check if the node is of a kind
+    #[must_use]
+    pub fn is(&self, kind: NodeKind) -> bool {
+        self.kind == kind.name()
+    }
+
+    /// Iterate over this node and everything under it, in source order
+    pub fn descendants(&self) -> impl Iterator<Item = &SyntaxNode> {
+        let mut stack = vec![self];
+        std::iter::from_fn(move || {
+            let node = stack.pop()?;
+            stack.extend(node.children.iter().rev());
+            Some(node)
+        })
+    }
+
+    /// Iterate over this node and the nodes under it of a kind, in source order
+    ///
+    /// For kinds [`NodeKind`] does not name, filter
+    /// [`descendants`](Self::descendants) on [`kind`](Self::kind) instead.
+    pub fn find_all(&self, kind: NodeKind) -> impl Iterator<Item = &SyntaxNode> {
+        self.descendants().filter(move |node| node.is(kind))
+    }
+
+    /// Get the innermost node or token covering a character offset
+    ///
+    /// A node covers the offsets from its start up to, but not including,
+    /// its end, so the offset just past a token is not in it.
+    #[must_use]
+    pub fn node_at(&self, offset: usize) -> Option<&SyntaxNode> {
+        let covers = |node: &SyntaxNode| node.start <= offset && offset < node.end;
+        if !covers(self) {
+            return None;
+        }
+        let mut current = self;
+        while let Some(child) = current.children.iter().find(|child| covers(child)) {
+            current = child;
+        }
+        Some(current)
+    }
+
+    /// Visit this node and its descendants in source order
+    ///
+    /// [`SyntaxVisitor::enter`] is called before a node's children and
+    /// [`SyntaxVisitor::leave`] after them. The walk keeps its own stack, so
+    /// deeply nested trees (such as long pipelines) do not overflow the
+    /// thread's stack.
+    pub fn walk<V: SyntaxVisitor + ?Sized>(&self, visitor: &mut V) {
+        let mut stack: Vec<(&SyntaxNode, usize)> = Vec::new();
+        if visitor.enter(self) {
+            stack.push((self, 0));
+        } else {
+            visitor.leave(self);
+        }
+        while let Some((node, next)) = stack.last_mut() {
+            let Some(child) = node.children.get(*next) else {
+                visitor.leave(node);
+                stack.pop();
+                continue;
+            };
+            *next += 1;
+            if visitor.enter(child) {
+                stack.push((child, 0));
+            } else {
+                visitor.leave(child);
+            }
+        }
+    }
+}
+
+/// Kinds of syntax nodes to select by
+///
+/// Each is named after its Kusto.Language `SyntaxKind`, which is also the
+/// [`SyntaxNode::kind`] of matching nodes. Query operators are named after
+/// their syntax rather than their keyword: `where` is a
+/// [`FilterOperator`](Self::FilterOperator) and `order by` a
+/// [`SortOperator`](Self::SortOperator).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NodeKind {
+    /// A whole query or script
+    QueryBlock,
+    /// A `let` statement
+    LetStatement,
+    /// A statement holding an expression, such as a query
+    ExpressionStatement,
+    /// A `set` option statement
+    SetOptionStatement,
+    /// A user-defined function's parameters and body
+    FunctionDeclaration,
+    /// `source | operator`
+    PipeExpression,
+    /// A function call, such as `count()`
+    FunctionCallExpression,
+    /// A reference to a table, column, variable or other name
+    NameReference,
+    /// A literal, such as `10` or `'text'`
+    LiteralExpression,
+    /// A `.`-separated path, such as `Properties.name`
+    PathExpression,
+    /// An indexer, such as `Tags['env']`
+    ElementExpression,
+    /// An expression in parentheses
+    ParenthesizedExpression,
+    /// `as`
+    AsOperator,
+    /// `count`
+    CountOperator,
+    /// `distinct`
+    DistinctOperator,
+    /// `evaluate`
+    EvaluateOperator,
+    /// `extend`
+    ExtendOperator,
+    /// `where` or `filter`
+    FilterOperator,
+    /// `getschema`
+    GetSchemaOperator,
+    /// `invoke`
+    InvokeOperator,
+    /// `join`
+    JoinOperator,
+    /// `lookup`
+    LookupOperator,
+    /// `make-series`
+    MakeSeriesOperator,
+    /// `mv-apply`
+    MvApplyOperator,
+    /// `mv-expand`
+    MvExpandOperator,
+    /// `parse`
+    ParseOperator,
+    /// `parse-where`
+    ParseWhereOperator,
+    /// `print`
+    PrintOperator,
+    /// `project`
+    ProjectOperator,
+    /// `project-away`
+    ProjectAwayOperator,
+    /// `project-keep`
+    ProjectKeepOperator,
+    /// `project-rename`
+    ProjectRenameOperator,
+    /// `project-reorder`
+    ProjectReorderOperator,
+    /// `range`
+    RangeOperator,
+    /// `render`
+    RenderOperator,
+    /// `search`
+    SearchOperator,
+    /// `serialize`
+    SerializeOperator,
+    /// `order by` or `sort by`
+    SortOperator,
+    /// `summarize`
+    SummarizeOperator,
+    /// `take` or `limit`
+    TakeOperator,
+    /// `top`
+    TopOperator,
+    /// `union`
+    UnionOperator,
+}
+
+impl NodeKind {
+    /// Every node kind
+    pub const ALL: [Self; 42] = [
+        Self::QueryBlock,
+        Self::LetStatement,
+        Self::ExpressionStatement,
+        Self::SetOptionStatement,
+        Self::FunctionDeclaration,
+        Self::PipeExpression,
+        Self::FunctionCallExpression,
+        Self::NameReference,
+        Self::LiteralExpression,
+        Self::PathExpression,
+        Self::ElementExpression,
+        Self::ParenthesizedExpression,
+        Self::AsOperator,
+        Self::CountOperator,
+        Self::DistinctOperator,
+        Self::EvaluateOperator,
+        Self::ExtendOperator,
+        Self::FilterOperator,
+        Self::GetSchemaOperator,
+        Self::InvokeOperator,
+        Self::JoinOperator,
+        Self::LookupOperator,
+        Self::MakeSeriesOperator,
+        Self::MvApplyOperator,
+        Self::MvExpandOperator,
+        Self::ParseOperator,
+        Self::ParseWhereOperator,
+        Self::PrintOperator,
+        Self::ProjectOperator,
+        Self::ProjectAwayOperator,
+        Self::ProjectKeepOperator,
+        Self::ProjectRenameOperator,
+        Self::ProjectReorderOperator,
+        Self::RangeOperator,
+        Self::RenderOperator,
+        Self::SearchOperator,
+        Self::SerializeOperator,
+        Self::SortOperator,
+        Self::SummarizeOperator,
+        Self::TakeOperator,
+        Self::TopOperator,
+        Self::UnionOperator,
+    ];
+
+    /// The Kusto.Language `SyntaxKind` name of the kind
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::QueryBlock => "QueryBlock",
+            Self::LetStatement => "LetStatement",
+            Self::ExpressionStatement => "ExpressionStatement",
+            Self::SetOptionStatement => "SetOptionStatement",
+            Self::FunctionDeclaration => "FunctionDeclaration",
+            Self::PipeExpression => "PipeExpression",
+            Self::FunctionCallExpression => "FunctionCallExpression",
+            Self::NameReference => "NameReference",
+            Self::LiteralExpression => "LiteralExpression",
+            Self::PathExpression => "PathExpression",
+            Self::ElementExpression => "ElementExpression",
+            Self::ParenthesizedExpression => "ParenthesizedExpression",
+            Self::AsOperator => "AsOperator",
+            Self::CountOperator => "CountOperator",
+            Self::DistinctOperator => "DistinctOperator",
+            Self::EvaluateOperator => "EvaluateOperator",
+            Self::ExtendOperator => "ExtendOperator",
+            Self::FilterOperator => "FilterOperator",
+            Self::GetSchemaOperator => "GetSchemaOperator",
+            Self::InvokeOperator => "InvokeOperator",
+            Self::JoinOperator => "JoinOperator",
+            Self::LookupOperator => "LookupOperator",
+            Self::MakeSeriesOperator => "MakeSeriesOperator",
+            Self::MvApplyOperator => "MvApplyOperator",
+            Self::MvExpandOperator => "MvExpandOperator",
+            Self::ParseOperator => "ParseOperator",
+            Self::ParseWhereOperator => "ParseWhereOperator",
+            Self::PrintOperator => "PrintOperator",
+            Self::ProjectOperator => "ProjectOperator",
+            Self::ProjectAwayOperator => "ProjectAwayOperator",
+            Self::ProjectKeepOperator => "ProjectKeepOperator",
+            Self::ProjectRenameOperator => "ProjectRenameOperator",
+            Self::ProjectReorderOperator => "ProjectReorderOperator",
+            Self::RangeOperator => "RangeOperator",
+            Self::RenderOperator => "RenderOperator",
+            Self::SearchOperator => "SearchOperator",
+            Self::SerializeOperator => "SerializeOperator",
+            Self::SortOperator => "SortOperator",
+            Self::SummarizeOperator => "SummarizeOperator",
+            Self::TakeOperator => "TakeOperator",
+            Self::TopOperator => "TopOperator",
+            Self::UnionOperator => "UnionOperator",
+        }
+    }
+
+    /// Parse a `SyntaxKind` name, returning `None` for kinds not listed here
+    #[must_use]
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.name() == name)
+    }
+
+    /// Check if the kind is a tabular query operator (a pipeline stage)
+    #[must_use]
+    pub fn is_query_operator(self) -> bool {
+        self.name().ends_with("Operator")
+    }
+}
+
+/// Callbacks for [`SyntaxNode::walk`]
+pub trait SyntaxVisitor {
+    /// Called when a node is reached; return `false` to skip its children
+    fn enter(&mut self, node: &SyntaxNode) -> bool {
+        let _ = node;
+        true
+    }
+
+    /// Called once a node and its children have been visited
+    fn leave(&mut self, node: &SyntaxNode) {
+        let _ = node;
+    }
+}
+
+/// Syntax tree as returned by the native library
+///
+/// Nested JSON would run into the parser's depth limit on long pipelines,
+/// so the library lists nodes in pre-order with the index of their parent.
+#[derive(Debug, Default, Deserialize)]
+pub(crate) struct FlatTree {
+    pub nodes: Vec<FlatNode>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct FlatNode {
+    kind: String,
+    pub start: usize,
+    pub end: usize,
+    #[serde(default)]
+    text: Option<String>,
+    #[serde(default)]
+    parent: Option<usize>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Token texts outside `take`, and the number of nodes left
+    struct Tokens(Vec<String>, usize);
+
+    impl SyntaxVisitor for Tokens {
+        fn enter(&mut self, node: &SyntaxNode) -> bool {
+            self.0.extend(node.text.clone());
+            node.kind != "TakeOperator"
+        }
+
+        fn leave(&mut self, _: &SyntaxNode) {
+            self.1 += 1;
+        }
+    }
+
+    #[test]
+    fn test_syntax_tree_from_nodes() {
+        // "T | take 1"
+        let json = r#"{"nodes":[
+            {"kind":"QueryBlock","start":0,"end":10},
+            {"kind":"List","start":0,"end":10,"parent":0},
+            {"kind":"ExpressionStatement","start":0,"end":10,"parent":1},
+            {"kind":"PipeExpression","start":0,"end":10,"parent":2},
+            {"kind":"NameReference","start":0,"end":1,"parent":3},
+            {"kind":"IdentifierToken","start":0,"end":1,"text":"T","parent":4},
+            {"kind":"BarToken","start":2,"end":3,"text":"|","parent":3},
+            {"kind":"TakeOperator","start":4,"end":10,"parent":3},
+            {"kind":"TakeKeyword","start":4,"end":8,"text":"take","parent":7},
+            {"kind":"LiteralExpression","start":9,"end":10,"parent":7},
+            {"kind":"LongLiteralToken","start":9,"end":10,"text":"1","parent":9}
+        ]}"#;
+        let flat: FlatTree = serde_json::from_str(json).unwrap();
+        let tree = SyntaxTree::from_nodes(flat.nodes);
+
+        let pipe = &tree.root.children[0].children[0].children[0];
+        assert_eq!(pipe.kind, "PipeExpression");
+        let kinds: Vec<_> = pipe.children.iter().map(|c| c.kind.as_str()).collect();
+        assert_eq!(kinds, ["NameReference", "BarToken", "TakeOperator"]);
+        assert!(pipe.children[1].is_token());
+
+        let mut tokens = Tokens(Vec::new(), 0);
+        tree.walk(&mut tokens);
+        assert_eq!(tokens.0, ["T", "|"]);
+        assert_eq!(tokens.1, 8);
+
+        let take = tree.find_all(NodeKind::TakeOperator).next().unwrap();
+        assert_eq!(tree.descendants().count(), 11);
+        assert_eq!(take.find_all(NodeKind::LiteralExpression).count(), 1);
+
+        let literal = tree.node_at(9).unwrap();
+        assert_eq!(literal.text.as_deref(), Some("1"));
+        let ancestors: Vec<_> = tree.ancestors(literal).map(|n| n.kind.as_str()).collect();
+        assert_eq!(
+            ancestors,
+            [
+                "LiteralExpression",
+                "TakeOperator",
+                "PipeExpression",
+                "ExpressionStatement",
+                "List",
+                "QueryBlock"
+            ]
+        );
+        assert!(tree.parent(&tree.root).is_none());
+        assert_eq!(
+            tree.parent(take).unwrap().node_kind(),
+            Some(NodeKind::PipeExpression)
+        );
+        assert_eq!(tree.node_at(3).unwrap().kind, "PipeExpression");
+        assert!(tree.node_at(10).is_none());
+        assert!(tree.ancestors(&take.clone()).next().is_none());
+
+        for kind in NodeKind::ALL {
+            assert_eq!(NodeKind::parse(kind.name()), Some(kind));
+        }
+        assert_eq!(NodeKind::parse("List"), None);
+
+        let json = serde_json::to_string(&tree).unwrap();
+        assert_eq!(serde_json::from_str::<SyntaxTree>(&json).unwrap(), tree);
+        assert_eq!(SyntaxTree::from_nodes(Vec::new()), SyntaxTree::default());
+    }
+}
@@ -0,0 +1,196 @@
+//! Instrumentation of native calls
+//!
+//! Each native call runs in a [`CallScope`] that collects its stats: input
+//! sizes, how often the output buffer had to grow, and the time spent in the
+//! native library. When the scope ends the stats go to the validator's
+//! [`Metrics`] hook, if any. With the `tracing` feature the scope is also a
+//! `kql_ffi` debug span recording the same fields.
+
+use crate::metrics::{CallMetrics, Metrics};
+use std::cell::Cell;
+use std::time::{Duration, Instant};
+
+/// Open a [`CallScope`] for a validator operation
+///
+/// Takes the validator, the operation name, and the input lengths as
+/// `tracing` fields (`query_len` or `schema_len = expr`).
+macro_rules! call_scope {
+    ($validator:expr, $call:literal $(, $field:ident $(= $value:expr)?)* $(,)?) => {{
+        let input_bytes = 0 $(+ $crate::trace::byte_len(call_scope!(@value $field $(= $value)?)))*;
+        #[cfg(feature = "tracing")]
+        let span = tracing::debug_span!(
+            "kql_ffi",
+            call = $call,
+            buffer_resizes = tracing::field::Empty,
+            native_us = tracing::field::Empty,
+            $($field $(= $value)?,)*
+        )
+        .entered();
+        $crate::trace::CallScope::new(
+            $call,
+            input_bytes,
+            $validator.metrics.as_deref(),
+            #[cfg(feature = "tracing")]
+            span,
+        )
+    }};
+    (@value $field:ident) => {
+        $field
+    };
+    (@value $field:ident = $value:expr) => {
+        $value
+    };
+}
+
+pub(crate) use call_scope;
+
+/// Length of an input as recorded in metrics
+pub(crate) fn byte_len(len: impl TryInto<usize>) -> usize {
+    len.try_into().unwrap_or_default()
+}
+
+thread_local! {
+    /// Stats of the innermost open scope on this thread
+    static CURRENT: Cell<CallStats> = const { Cell::new(CallStats::EMPTY) };
+}
+
+/// Output buffer resizes, native time and output size of one call
+#[derive(Debug, Default, Clone, Copy)]
+pub(crate) struct CallStats {
+    pub buffer_resizes: u32,
+    pub native_time: Duration,
+    pub output_bytes: usize,
+    pub failed: bool,
+}
+
+impl CallStats {
+    const EMPTY: Self = Self {
+        buffer_resizes: 0,
+        native_time: Duration::ZERO,
+        output_bytes: 0,
+        failed: false,
+    };
+
+    /// Run a native call, adding its duration
+    pub fn time<R>(&mut self, call: impl FnOnce() -> R) -> R {
+        let started = Instant::now();
+        let result = call();
+        self.native_time += started.elapsed();
+        result
+    }
+
+    /// Add these stats to the innermost open scope on this thread
+    pub fn finish(self) {
+        CURRENT.with(|current| {
+            let total = current.get();
+            current.set(Self {
+                buffer_resizes: total.buffer_resizes + self.buffer_resizes,
+                native_time: total.native_time + self.native_time,
+                output_bytes: total.output_bytes + self.output_bytes,
+                failed: total.failed || self.failed,
+            });
+        });
+    }
+}
+
+/// An open validator operation; reports its stats when dropped
+pub(crate) struct CallScope<'a> {
+    operation: &'static str,
+    input_bytes: usize,
+    started: Instant,
+    metrics: Option<&'a dyn Metrics>,
+    /// Stats of the enclosing scope, restored on drop
+    outer: CallStats,
+    #[cfg(feature = "tracing")]
+    span: tracing::span::EnteredSpan,
+}
+
+impl<'a> CallScope<'a> {
+    pub fn new(
+        operation: &'static str,
+        input_bytes: usize,
+        metrics: Option<&'a dyn Metrics>,
+        #[cfg(feature = "tracing")] span: tracing::span::EnteredSpan,
+    ) -> Self {
+        Self {
+            operation,
+            input_bytes,
+            started: Instant::now(),
+            metrics,
+            outer: CURRENT.with(|current| current.replace(CallStats::EMPTY)),
+            #[cfg(feature = "tracing")]
+            span,
+        }
+    }
+}
+
+impl Drop for CallScope<'_> {
+    fn drop(&mut self) {
+        let stats = CURRENT.with(|current| current.replace(self.outer));
+
+        #[cfg(feature = "tracing")]
+        {
+            self.span.record("buffer_resizes", stats.buffer_resizes);
+            self.span.record(
+                "native_us",
+                u64::try_from(stats.native_time.as_micros()).unwrap_or(u64::MAX),
+            );
+        }
+
+        if let Some(metrics) = self.metrics {
+            metrics.record_call(&CallMetrics {
+                operation: self.operation,
+                duration: self.started.elapsed(),
+                native_duration: stats.native_time,
+                input_bytes: self.input_bytes,
+                output_bytes: stats.output_bytes,
+                buffer_retries: stats.buffer_resizes,
+                failed: stats.failed,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Default)]
+    struct Recorder(Mutex<Vec<CallMetrics>>);
+
+    impl Metrics for Recorder {
+        fn record_call(&self, call: &CallMetrics) {
+            self.0.lock().unwrap().push(call.clone());
+        }
+    }
+
+    struct Validator {
+        metrics: Option<Arc<dyn Metrics>>,
+    }
+
+    #[test]
+    fn test_scope_reports_call_stats() {
+        let recorder = Arc::new(Recorder::default());
+        let validator = Validator {
+            metrics: Some(recorder.clone()),
+        };
+        let query_len = 12;
+        {
+            let _scope = call_scope!(validator, "test_call", query_len, schema_len = 30_usize);
+            let mut stats = CallStats::default();
+            assert_eq!(stats.time(|| 7), 7);
+            stats.buffer_resizes = 1;
+            stats.output_bytes = 64;
+            stats.finish();
+        }
+
+        let calls = recorder.0.lock().unwrap();
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].operation, "test_call");
+        assert_eq!(calls[0].input_bytes, 42);
+        assert_eq!(calls[0].output_bytes, 64);
+        assert_eq!(calls[0].buffer_retries, 1);
+        assert!(!calls[0].failed);
+    }
+}
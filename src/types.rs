@@ -1,5 +1,6 @@
 //! Validation types for KQL Language Tools
 
+use crate::codes::KustoDiagnosticCode;
 use serde::{Deserialize, Serialize};
 
 /// Result of validating a KQL query
@@ -71,6 +72,11 @@ impl ValidationResult {
             .iter()
             .filter(|d| d.severity == DiagnosticSeverity::Warning)
     }
+
+    /// Get the quick fixes of all diagnostics
+    pub fn fixes(&self) -> impl Iterator<Item = &Fix> {
+        self.diagnostics.iter().flat_map(|d| d.fixes.iter())
+    }
 }
 
 /// A diagnostic message from validation
@@ -86,11 +92,20 @@ pub struct Diagnostic {
     pub end: usize,
     /// Line number (1-based)
     pub line: usize,
-    /// Column number (1-based)
+    /// Column number (1-based, in characters)
     pub column: usize,
     /// Error/warning code (if available)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub code: Option<String>,
+    /// Diagnostic category (if available)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub category: Option<DiagnosticCategory>,
+    /// Quick fixes that resolve this diagnostic
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fixes: Vec<Fix>,
+    /// Secondary locations relevant to this diagnostic
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub related_spans: Vec<RelatedInfo>,
 }
 
 impl Diagnostic {
@@ -100,6 +115,12 @@ impl Diagnostic {
         self.end.saturating_sub(self.start)
     }
 
+    /// Get the diagnostic code parsed into a [`KustoDiagnosticCode`]
+    #[must_use]
+    pub fn parsed_code(&self) -> Option<KustoDiagnosticCode> {
+        self.code.as_deref().map(KustoDiagnosticCode::parse)
+    }
+
     /// Check if this is an error
     #[must_use]
     pub fn is_error(&self) -> bool {
@@ -113,6 +134,37 @@ impl Diagnostic {
     }
 }
 
+/// A quick fix: one or more edits that resolve a diagnostic
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Fix {
+    /// Short description of the fix (e.g. "Use `has_cs`")
+    pub title: String,
+    /// Edits to apply, in any order; edits never overlap
+    pub edits: Vec<TextEdit>,
+}
+
+/// A secondary location attached to a diagnostic (e.g. where a name is declared)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RelatedInfo {
+    /// What the location shows
+    pub message: String,
+    /// Start offset (0-based, character position)
+    pub start: usize,
+    /// End offset (0-based, character position)
+    pub end: usize,
+}
+
+/// Replacement of a span of the query text
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TextEdit {
+    /// Start offset of the replaced span (0-based, character position)
+    pub start: usize,
+    /// End offset of the replaced span (0-based, character position)
+    pub end: usize,
+    /// Replacement text
+    pub new_text: String,
+}
+
 /// Severity level of a diagnostic
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "PascalCase")]
@@ -142,6 +194,25 @@ impl DiagnosticSeverity {
     }
 }
 
+/// Category of a diagnostic
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub enum DiagnosticCategory {
+    /// The query is incorrect and cannot run
+    Correctness,
+    /// The query runs but may be slow or expensive
+    Performance,
+    /// The query may expose sensitive data or behave unsafely
+    Security,
+    /// The query exceeds a Kusto service limit
+    ServiceLimit,
+    /// A best-practice finding from a Kusto.Language analyzer
+    Analyzer,
+    /// Any other category reported by the native library
+    #[serde(other)]
+    General,
+}
+
 impl std::fmt::Display for DiagnosticSeverity {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -152,4 +223,3 @@ impl std::fmt::Display for DiagnosticSeverity {
         }
     }
 }
-
@@ -2,12 +2,110 @@
 //!
 //! This module provides the high-level API for validating KQL queries.
 
+use crate::analysis::QueryAnalysis;
+use crate::backend::KqlEngine;
+use crate::bound::BoundValidator;
+use crate::buffer::with_output_buffer;
+use crate::cache::CachedValidator;
+use crate::cancel::{CallWorker, CancellationToken};
+use crate::classification::{ClassificationOptions, ClassificationResult, ClassifiedSpan};
+use crate::completion::{self, CompletionItem, CompletionOptions, CompletionResult};
+use crate::dialect::Dialect;
+use crate::engine::{EngineFeatureLevel, EnginePin, FeatureAvailability};
 use crate::error::Error;
+use crate::expression::ExpressionType;
+#[cfg(feature = "cbor")]
+use crate::ffi::result_formats;
 use crate::ffi::{return_codes, DEFAULT_BUFFER_SIZE, MAX_BUFFER_SIZE};
+use crate::fixes::{self, FixOutcome};
+use crate::format::FormatOptions;
+use crate::handle::SchemaHandle;
+use crate::inlay::InlayHint;
+use crate::joins::JoinAnalysis;
+use crate::kind::QueryKind;
+use crate::limits::ServiceLimits;
+use crate::lint::Linter;
+#[cfg(feature = "cbor")]
+use crate::loader::ResultFormatGuard;
 use crate::loader::{self, LoadedLibrary};
-use crate::schema::Schema;
-use crate::types::ValidationResult;
-use std::ffi::c_int;
+use crate::metrics::Metrics;
+use crate::navigation::Definition;
+use crate::options::ValidationOptions;
+use crate::outline::DocumentSymbol;
+use crate::pipeline::Pipeline;
+use crate::positions::{self, LineIndex, NativeOffsets, OffsetEncoding};
+use crate::profile::FeatureProfile;
+use crate::schema::{Parameter, Schema, Table};
+use crate::schema_check::{self, SchemaIssue};
+use crate::script::{self, ScriptBlock, ScriptValidation};
+use crate::selection::{BracketPair, EnclosingRange};
+use crate::severity::DiagnosticsConfig;
+use crate::spelling;
+use crate::structure::{DocumentStructure, FoldingRange};
+use crate::suggest::{self, InlineSuggestion};
+use crate::syntax_tree::{FlatTree, SyntaxTree};
+use crate::trace::{call_scope, CallStats};
+use crate::types::{Diagnostic, Fix, ValidationResult};
+#[cfg(feature = "worker")]
+use crate::worker::WorkerBuilder;
+use std::any::Any;
+use std::ffi::{c_int, c_void};
+use std::ops::Range;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::ptr;
+use std::sync::mpsc::RecvTimeoutError;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Serialization format of the results of one call
+enum ResultFormat {
+    Json,
+    /// Switched back to JSON when dropped
+    #[cfg(feature = "cbor")]
+    Cbor {
+        _guard: ResultFormatGuard,
+    },
+}
+
+impl ResultFormat {
+    /// Deserialize a call result; empty output is `None`
+    fn decode<T>(&self, bytes: &[u8]) -> Result<Option<T>, Error>
+    where
+        T: for<'de> serde::Deserialize<'de>,
+    {
+        if bytes.is_empty() {
+            return Ok(None);
+        }
+        match self {
+            Self::Json => {
+                let json = std::str::from_utf8(bytes)?;
+                log::trace!("FFI returned JSON: {json}");
+                Ok(Some(serde_json::from_str(json)?))
+            }
+            #[cfg(feature = "cbor")]
+            Self::Cbor { .. } => {
+                log::trace!("FFI returned {} bytes of CBOR", bytes.len());
+                Ok(Some(ciborium::from_reader(bytes)?))
+            }
+        }
+    }
+}
+
+/// Length of an output buffer as passed to the native library
+fn output_len(buffer: &[u8]) -> c_int {
+    c_int::try_from(buffer.len()).unwrap_or(c_int::MAX)
+}
+
+/// How often a cancellable call checks its token
+const WATCHDOG_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Spans per batch in [`KqlValidator::classify_chunks`]
+const CLASSIFY_BATCH_SPANS: usize = 8 * 1024;
+
+/// Batch calls per rayon thread in [`KqlValidator::validate_all_par`]
+#[cfg(feature = "rayon")]
+const PARALLEL_CHUNKS_PER_THREAD: usize = 4;
 
 /// KQL query validator
 ///
@@ -15,6 +113,11 @@ use std::ffi::c_int;
 /// the connection to the native library and provides safe wrappers
 /// around the FFI functions.
 ///
+/// Validation, completion and classification run on a [`KqlEngine`]: the
+/// native library in process by default, or another engine selected with
+/// [`with_engine`](Self::with_engine). The rest of the API always calls
+/// the native library.
+///
 /// # Example
 ///
 /// ```no_run
@@ -39,8 +142,30 @@ use std::ffi::c_int;
 ///     Ok(())
 /// }
 /// ```
+#[derive(Clone)]
 pub struct KqlValidator {
-    lib: &'static LoadedLibrary,
+    native: NativeEngine,
+    /// Engine selected in place of `native`
+    engine: Option<Arc<dyn KqlEngine>>,
+    feature_overrides: Vec<FeatureAvailability>,
+    offsets: OffsetEncoding,
+}
+
+/// The native library, called in process
+///
+/// The default [`KqlEngine`] of a [`KqlValidator`], which also uses it
+/// directly for the rest of its API. Offsets are in characters.
+#[derive(Clone)]
+struct NativeEngine {
+    lib: Arc<LoadedLibrary>,
+    options: ValidationOptions,
+    diagnostics: DiagnosticsConfig,
+    native_results: bool,
+    max_buffer_size: usize,
+    #[cfg(feature = "cbor")]
+    binary_results: bool,
+    metrics: Option<Arc<dyn Metrics>>,
+    worker: CallWorker,
 }
 
 impl KqlValidator {
@@ -56,8 +181,463 @@ impl KqlValidator {
     /// - The library fails to load
     /// - Initialization fails
     pub fn new() -> Result<Self, Error> {
-        let lib = loader::load_library()?;
-        Ok(Self { lib })
+        loader::load_library().map(Self::with_library)
+    }
+
+    /// Create a validator using its own instance of the library at `path`
+    ///
+    /// `path` is the library file or a directory containing it. Unlike
+    /// [`from_library_path`](Self::from_library_path), this leaves the
+    /// process-wide library alone, so validators for two library versions
+    /// can run side by side. Validators created from the same path share
+    /// one instance.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use kql_language_tools::KqlValidator;
+    ///
+    /// let current = KqlValidator::from_path("/opt/kql/1.4/native")?;
+    /// let candidate = KqlValidator::from_path("/opt/kql/1.5/native")?;
+    ///
+    /// let query = "T | take 10";
+    /// let same = current.validate_syntax(query)?.is_valid()
+    ///     == candidate.validate_syntax(query)?.is_valid();
+    /// # Ok::<(), kql_language_tools::Error>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no library is found at `path`, or it fails to
+    /// load or initialize.
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self, Error> {
+        loader::load_library_from(path.as_ref()).map(Self::with_library)
+    }
+
+    /// Wrap a loaded library with default options
+    fn with_library(lib: Arc<LoadedLibrary>) -> Self {
+        Self {
+            native: NativeEngine {
+                lib,
+                options: ValidationOptions::default(),
+                diagnostics: DiagnosticsConfig::default(),
+                native_results: false,
+                max_buffer_size: MAX_BUFFER_SIZE,
+                #[cfg(feature = "cbor")]
+                binary_results: false,
+                metrics: None,
+                worker: CallWorker::default(),
+            },
+            engine: None,
+            feature_overrides: Vec::new(),
+            offsets: OffsetEncoding::default(),
+        }
+    }
+
+    /// Create a validator, loading the process-wide native library from
+    /// `path`
+    ///
+    /// `path` is the library file or a directory containing it. See
+    /// [`set_library_path`](crate::set_library_path).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a library from another location is already
+    /// loaded, or if [`new`](Self::new) fails.
+    pub fn from_library_path(path: impl Into<PathBuf>) -> Result<Self, Error> {
+        loader::set_library_path(path)?;
+        Self::new()
+    }
+
+    /// Set the dialect queries are validated against
+    ///
+    /// Non-default dialects require a native library exporting
+    /// `kql_validate_with_options`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use kql_language_tools::{Dialect, KqlValidator, ResourceScope, Schema, Table};
+    ///
+    /// fn main() -> Result<(), kql_language_tools::Error> {
+    ///     let validator = KqlValidator::new()?.with_dialect(Dialect::LogAnalytics);
+    ///
+    ///     let schema = Schema::new().scope(
+    ///         ResourceScope::workspace("contoso-prod")
+    ///             .table(Table::new("SigninLogs").with_column("UserPrincipalName", "string")),
+    ///     );
+    ///     let result = validator.validate_with_schema(
+    ///         "workspace('contoso-prod').SigninLogs | project UserPrincipalName",
+    ///         &schema,
+    ///     )?;
+    ///     assert!(result.is_valid());
+    ///     Ok(())
+    /// }
+    /// ```
+    #[must_use]
+    pub fn with_dialect(mut self, dialect: Dialect) -> Self {
+        self.native.options.dialect = dialect;
+        self
+    }
+
+    /// Get the dialect queries are validated against
+    #[must_use]
+    pub fn dialect(&self) -> Dialect {
+        self.native.options.dialect
+    }
+
+    /// Set the kind of text validated
+    ///
+    /// With [`QueryKind::Command`], text that is not a control command is
+    /// reported as an error (`KC001`). Use
+    /// [`validate_command`](Self::validate_command) to check a single
+    /// command without changing the validator.
+    ///
+    /// Requires a native library exporting `kql_validate_with_options`.
+    #[must_use]
+    pub fn with_query_kind(mut self, kind: QueryKind) -> Self {
+        self.native.options.kind = kind;
+        self
+    }
+
+    /// Get the kind of text validated
+    #[must_use]
+    pub fn query_kind(&self) -> QueryKind {
+        self.native.options.kind
+    }
+
+    /// Add a feature profile that queries must satisfy
+    ///
+    /// Profiles accumulate: a query must satisfy every profile added, in
+    /// addition to any restrictions implied by the dialect. Violations are
+    /// reported as errors with `FP`-prefixed codes.
+    ///
+    /// Requires a native library exporting `kql_validate_with_options`.
+    #[must_use]
+    pub fn with_profile(mut self, profile: FeatureProfile) -> Self {
+        self.native.options.profiles.push(profile);
+        self
+    }
+
+    /// Get the feature profiles queries must satisfy
+    #[must_use]
+    pub fn profiles(&self) -> &[FeatureProfile] {
+        &self.native.options.profiles
+    }
+
+    /// Pin validation to an engine feature level
+    ///
+    /// Functions, plugins, and operators introduced after `level` are
+    /// reported with code `EL001`. Features flagged by the built-in
+    /// [`engine`](crate::engine) catalog, whose dates are unverified, are
+    /// warnings; features added with
+    /// [`with_feature_availability`](Self::with_feature_availability) are
+    /// errors.
+    ///
+    /// Requires a native library exporting `kql_validate_with_options`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use kql_language_tools::{EngineFeatureLevel, KqlValidator};
+    ///
+    /// fn main() -> Result<(), kql_language_tools::Error> {
+    ///     let validator = KqlValidator::new()?
+    ///         .with_engine_feature_level(EngineFeatureLevel::parse("2022-06")?);
+    ///
+    ///     // The catalog lists bag_zip() as shipping after the pinned level
+    ///     let result = validator.validate_syntax("print bag_zip(dynamic(['a']), dynamic([1]))")?;
+    ///     assert!(result.warnings().any(|d| d.code.as_deref() == Some("EL001")));
+    ///     Ok(())
+    /// }
+    /// ```
+    #[must_use]
+    pub fn with_engine_feature_level(mut self, level: EngineFeatureLevel) -> Self {
+        self.native.options.engine = Some(EnginePin::new(level, &self.feature_overrides));
+        self
+    }
+
+    /// Add or override a feature availability catalog entry
+    ///
+    /// Use this for features missing from the built-in catalog, or when a
+    /// particular cloud received a feature later than public Azure. Entries
+    /// created with [`FeatureAvailability::new`] are verified, so using them
+    /// past the pinned level is an error.
+    #[must_use]
+    pub fn with_feature_availability(mut self, availability: FeatureAvailability) -> Self {
+        self.feature_overrides
+            .retain(|o| o.name != availability.name);
+        self.feature_overrides.push(availability);
+        if let Some(pin) = &self.native.options.engine {
+            self.native.options.engine = Some(EnginePin::new(pin.level, &self.feature_overrides));
+        }
+        self
+    }
+
+    /// Get the engine feature level validation is pinned to, if any
+    #[must_use]
+    pub fn engine_feature_level(&self) -> Option<EngineFeatureLevel> {
+        self.native.options.engine.as_ref().map(|pin| pin.level)
+    }
+
+    /// Check queries against service limits
+    ///
+    /// Violations are reported as errors with
+    /// [`DiagnosticCategory::ServiceLimit`](crate::DiagnosticCategory::ServiceLimit).
+    ///
+    /// Requires a native library exporting `kql_validate_with_options`.
+    #[must_use]
+    pub fn with_service_limits(mut self, limits: ServiceLimits) -> Self {
+        self.native.options.limits = Some(limits);
+        self
+    }
+
+    /// Declare an ambient query parameter for schema-aware calls
+    ///
+    /// Like [`Schema::query_parameter`], but kept with the validator, for
+    /// parameters a client passes with every query whatever the schema.
+    /// Without a schema, queries are only parsed and unknown names are not
+    /// reported anyway.
+    ///
+    /// Requires a native library exporting `kql_validate_with_options`.
+    #[must_use]
+    pub fn with_query_parameter(mut self, parameter: Parameter) -> Self {
+        self.native.options.query_parameters.push(parameter);
+        self
+    }
+
+    /// Suggest case-sensitive string operators where the literal is exact-case
+    ///
+    /// Case-insensitive operators (`has`, `=~`, `in~`, ...) are slower than
+    /// their case-sensitive variants. When enabled, comparisons against
+    /// literals whose case cannot matter (no letters, e.g. IP addresses) or
+    /// is conventionally fixed (GUIDs, hex hashes) produce a `CS001` hint
+    /// in the [`Performance`](crate::DiagnosticCategory::Performance)
+    /// category, with the replacement operator as a quick fix.
+    ///
+    /// Requires a native library exporting `kql_validate_with_options`.
+    #[must_use]
+    pub fn with_case_sensitivity_advisory(mut self, enabled: bool) -> Self {
+        self.native.options.case_sensitivity_advisory = enabled;
+        self
+    }
+
+    /// Set the largest output buffer allocated for a call (default 4MB)
+    ///
+    /// Results that do not fit are read from the native library in chunks of
+    /// this size when it exports `kql_read_result_chunk`, and fail with
+    /// [`Error::BufferTooSmall`] otherwise.
+    #[must_use]
+    pub fn with_max_buffer_size(mut self, bytes: usize) -> Self {
+        self.native.max_buffer_size = bytes.max(DEFAULT_BUFFER_SIZE);
+        self
+    }
+
+    /// Let the native library allocate call results
+    ///
+    /// By default results are written into a buffer owned by this crate,
+    /// which is grown and the call repeated when a result does not fit, up
+    /// to the [maximum buffer size](Self::with_max_buffer_size). When enabled, the native library
+    /// allocates each result at its exact size and this crate frees it after
+    /// parsing, so large results (classifications of long scripts, batches)
+    /// take one call and have no size ceiling.
+    ///
+    /// Requires a native library exporting `kql_free_result`; otherwise the
+    /// buffer protocol is used.
+    #[must_use]
+    pub fn with_native_results(mut self, enabled: bool) -> Self {
+        self.native.native_results = enabled;
+        self
+    }
+
+    /// Receive call results as CBOR instead of JSON
+    ///
+    /// Enabled by the `cbor` feature. CBOR is smaller than JSON and cheaper
+    /// to parse, which adds up for large completion and classification
+    /// results; results deserialize to the same types either way. Libraries
+    /// without `kql_set_result_format` keep returning JSON.
+    #[cfg(feature = "cbor")]
+    #[must_use]
+    pub fn with_binary_results(mut self, enabled: bool) -> Self {
+        self.native.binary_results = enabled;
+        self
+    }
+
+    /// Report timing and sizes of every native call to a metrics hook
+    ///
+    /// See [`Metrics`] for an example.
+    #[must_use]
+    pub fn with_metrics(mut self, metrics: impl Metrics + 'static) -> Self {
+        self.native.metrics = Some(Arc::new(metrics));
+        self
+    }
+
+    /// Override diagnostic severities in validation results
+    ///
+    /// Applies to every method returning a [`ValidationResult`]; validity is
+    /// recomputed from the effective severities. To override a single call
+    /// instead, use [`DiagnosticsConfig::apply`] on its result.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use kql_language_tools::{DiagnosticsConfig, KqlValidator};
+    ///
+    /// fn main() -> Result<(), kql_language_tools::Error> {
+    ///     let validator = KqlValidator::new()?
+    ///         .with_case_sensitivity_advisory(true)
+    ///         .with_diagnostics_config(DiagnosticsConfig::new().suppress("CS001"));
+    ///
+    ///     let result = validator.validate_syntax("T | where Ip has '10.0.0.1'")?;
+    ///     assert!(result.diagnostics.is_empty());
+    ///     Ok(())
+    /// }
+    /// ```
+    #[must_use]
+    pub fn with_diagnostics_config(mut self, config: DiagnosticsConfig) -> Self {
+        self.native.diagnostics = config;
+        self
+    }
+
+    /// Get the diagnostic severity overrides
+    #[must_use]
+    pub fn diagnostics_config(&self) -> &DiagnosticsConfig {
+        &self.native.diagnostics
+    }
+
+    /// Set the unit of offsets passed to and returned from the validator
+    ///
+    /// With [`OffsetEncoding::Utf16`], completion cursor positions are read
+    /// as UTF-16 code units, and completion `edit_start` and diagnostic
+    /// spans, columns, fix edits, and related spans are returned in UTF-16
+    /// code units, matching what LSP clients send and expect.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use kql_language_tools::{KqlValidator, OffsetEncoding};
+    ///
+    /// fn main() -> Result<(), kql_language_tools::Error> {
+    ///     let validator = KqlValidator::new()?.with_offset_encoding(OffsetEncoding::Utf16);
+    ///
+    ///     // `x` is character 17 but UTF-16 code unit 18
+    ///     let result = validator.validate_syntax("print '😀' | take x")?;
+    ///     assert_eq!(result.diagnostics[0].start, 18);
+    ///     Ok(())
+    /// }
+    /// ```
+    #[must_use]
+    pub fn with_offset_encoding(mut self, encoding: OffsetEncoding) -> Self {
+        self.offsets = encoding;
+        self
+    }
+
+    /// Get the unit of offsets passed to and returned from the validator
+    #[must_use]
+    pub fn offset_encoding(&self) -> OffsetEncoding {
+        self.offsets
+    }
+
+    /// Get the service limits queries are checked against, if any
+    #[must_use]
+    pub fn service_limits(&self) -> Option<&ServiceLimits> {
+        self.native.options.limits.as_ref()
+    }
+
+    /// Run validation, completion and classification on another engine
+    ///
+    /// [`validate_syntax`](Self::validate_syntax),
+    /// [`validate_with_schema`](Self::validate_with_schema),
+    /// [`get_completions`](Self::get_completions) and
+    /// [`get_classifications`](Self::get_classifications) (and the methods
+    /// built on them, such as caching and batches on libraries without
+    /// `kql_validate_batch`) go to `engine` instead of the native library.
+    /// The offset encoding and diagnostics config still apply to its
+    /// results; the analysis settings (dialect, profiles, ...) do not, so
+    /// configure the engine itself.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use kql_language_tools::{KqlValidator, OffsetEncoding};
+    ///
+    /// // Validate with a candidate library, in the encoding of this validator
+    /// let candidate = KqlValidator::from_path("/opt/kql/1.5/native")?;
+    /// let validator = KqlValidator::new()?
+    ///     .with_offset_encoding(OffsetEncoding::Utf16)
+    ///     .with_engine(candidate);
+    ///
+    /// assert!(!validator.validate_syntax("T | where")?.is_valid());
+    /// # Ok::<(), kql_language_tools::Error>(())
+    /// ```
+    #[must_use]
+    pub fn with_engine(mut self, engine: impl KqlEngine + 'static) -> Self {
+        self.engine = Some(Arc::new(engine));
+        self
+    }
+
+    /// Run validation, completion and classification in a worker process
+    ///
+    /// Enabled by the `worker` feature. Starts a worker from `worker` with
+    /// this validator's analysis settings (dialect, query kind, profiles,
+    /// engine pin, service limits, query parameters and advisories) and uses
+    /// it as the [engine](Self::with_engine), so a crash in the native
+    /// library during those calls takes down only the worker, which the
+    /// next call restarts. The settings are copied when the worker starts:
+    /// select the worker after configuring the validator.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use kql_language_tools::worker::WorkerValidator;
+    /// use kql_language_tools::{Dialect, KqlValidator};
+    /// use std::time::Duration;
+    ///
+    /// let validator = KqlValidator::new()?
+    ///     .with_dialect(Dialect::LogAnalytics)
+    ///     .with_worker(WorkerValidator::builder().timeout(Duration::from_secs(5)))?;
+    ///
+    /// let result = validator.validate_syntax("SigninLogs | take 10")?;
+    /// # Ok::<(), kql_language_tools::Error>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the worker cannot be started or fails to load
+    /// the native library.
+    #[cfg(feature = "worker")]
+    pub fn with_worker(self, worker: WorkerBuilder) -> Result<Self, Error> {
+        // The engine contract is characters; this validator encodes them
+        let worker = worker
+            .options(self.native.options.clone())
+            .offset_encoding(OffsetEncoding::Chars)
+            .spawn()?;
+        Ok(self.with_engine(worker))
+    }
+
+    /// Replace the analysis options, as received by a worker process
+    #[cfg(feature = "worker")]
+    pub(crate) fn with_options(mut self, options: ValidationOptions) -> Self {
+        self.native.options = options;
+        self
+    }
+
+    /// The engine validation, completion and classification run on
+    fn engine(&self) -> &dyn KqlEngine {
+        self.engine.as_deref().unwrap_or(&self.native)
+    }
+
+    /// Validate on the engine, with offsets in characters
+    fn check(&self, query: &str, schema: Option<&Schema>) -> Result<ValidationResult, Error> {
+        let mut result = match schema {
+            Some(schema) => self.engine().validate_with_schema(query, schema)?,
+            None => self.engine().validate_syntax(query)?,
+        };
+        // The native engine has applied it already
+        if self.engine.is_some() {
+            self.native.diagnostics.apply(&mut result);
+        }
+        Ok(result)
     }
 
     /// Validate a KQL query for syntax errors only
@@ -74,32 +654,10 @@ impl KqlValidator {
     ///
     /// A `ValidationResult` containing any diagnostics found.
     pub fn validate_syntax(&self, query: &str) -> Result<ValidationResult, Error> {
-        let query_bytes = query.as_bytes();
-
-        // Validate input size fits in c_int (2GB limit on 32-bit)
-        let query_len = c_int::try_from(query_bytes.len()).map_err(|_| Error::Internal {
-            message: format!(
-                "Query too large: {} bytes exceeds c_int max",
-                query_bytes.len()
-            ),
-        })?;
-
-        self.call_ffi_with_retry(|buffer| {
-            // SAFETY: This FFI call is safe because:
-            // 1. query_bytes.as_ptr() points to valid UTF-8 data for the duration of the call
-            // 2. query_len accurately represents the byte length
-            // 3. buffer is a valid mutable slice we own
-            // 4. The FFI function only reads from query and writes to buffer
-            #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
-            unsafe {
-                (self.lib.validate_syntax)(
-                    query_bytes.as_ptr(),
-                    query_len,
-                    buffer.as_mut_ptr(),
-                    buffer.len() as c_int,
-                )
-            }
-        })
+        let mut result = self.check(query, None)?;
+        self.offsets
+            .encode_diagnostics(query, &mut result.diagnostics);
+        Ok(result)
     }
 
     /// Validate a KQL query with schema awareness
@@ -115,7 +673,9 @@ impl KqlValidator {
     ///
     /// # Returns
     ///
-    /// A `ValidationResult` containing any diagnostics found.
+    /// A `ValidationResult` containing any diagnostics found. Errors on
+    /// names missing from the schema carry "did you mean" quick fixes for
+    /// the closest table, column, or function names.
     ///
     /// # Errors
     ///
@@ -126,349 +686,4711 @@ impl KqlValidator {
         query: &str,
         schema: &Schema,
     ) -> Result<ValidationResult, Error> {
-        let validate_fn = self
-            .lib
-            .validate_with_schema
-            .ok_or_else(|| Error::Internal {
-                message: "Schema validation not supported by loaded library".to_string(),
-            })?;
+        let mut result = self.check(query, Some(schema))?;
+        self.offsets
+            .encode_diagnostics(query, &mut result.diagnostics);
+        Ok(result)
+    }
 
-        let query_bytes = query.as_bytes();
-        let schema_json = serde_json::to_string(schema)?;
-        let schema_bytes = schema_json.as_bytes();
+    /// Analyze the entities a query references
+    ///
+    /// Returns the schema tables, columns (grouped per table), and functions
+    /// the query references, plus the clusters and databases it names
+    /// through `cluster()` and `database()`. Intended for access-control
+    /// checks that would otherwise have to pattern-match query text.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use kql_language_tools::{KqlValidator, Schema, Table};
+    ///
+    /// fn main() -> Result<(), kql_language_tools::Error> {
+    ///     let validator = KqlValidator::new()?;
+    ///     let schema = Schema::new().table(
+    ///         Table::new("SecurityEvent")
+    ///             .with_column("Account", "string")
+    ///             .with_column("EventID", "long"),
+    ///     );
+    ///
+    ///     let analysis = validator.analyze("SecurityEvent | where EventID == 4625", &schema)?;
+    ///
+    ///     assert!(analysis.references_table("SecurityEvent"));
+    ///     assert_eq!(analysis.columns_of("SecurityEvent").collect::<Vec<_>>(), ["EventID"]);
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if analysis is not supported by the loaded library.
+    pub fn analyze(&self, query: &str, schema: &Schema) -> Result<QueryAnalysis, Error> {
+        let analyze_fn = self.native.lib.analyze.ok_or_else(|| Error::Internal {
+            message: "Analysis not supported by loaded library".to_string(),
+        })?;
 
-        // Validate input sizes fit in c_int
+        let query_bytes = query.as_bytes();
         let query_len = c_int::try_from(query_bytes.len()).map_err(|_| Error::Internal {
             message: format!("Query too large: {} bytes", query_bytes.len()),
         })?;
-        let schema_len = c_int::try_from(schema_bytes.len()).map_err(|_| Error::Internal {
-            message: format!("Schema too large: {} bytes", schema_bytes.len()),
+        let schema_json = schema.to_native_json()?;
+        let schema_len = c_int::try_from(schema_json.len()).map_err(|_| Error::Internal {
+            message: format!("Schema too large: {} bytes", schema_json.len()),
         })?;
+        let options_json = serde_json::to_string(&self.native.options)?;
 
-        self.call_ffi_with_retry(|buffer| {
+        let _scope = call_scope!(self.native, "analyze", query_len, schema_len);
+        self.native.call_ffi_json(|output, output_len| {
             // SAFETY: See validate_syntax for safety invariants.
-            // Additionally, schema_bytes is valid UTF-8 JSON for the call duration.
             #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
             unsafe {
-                validate_fn(
+                analyze_fn(
                     query_bytes.as_ptr(),
                     query_len,
-                    schema_bytes.as_ptr(),
+                    schema_json.as_ptr(),
                     schema_len,
-                    buffer.as_mut_ptr(),
-                    buffer.len() as c_int,
+                    options_json.as_ptr(),
+                    options_json.len() as c_int,
+                    output,
+                    output_len,
                 )
             }
         })
     }
 
-    /// Check if schema validation is supported
-    #[must_use]
-    pub fn supports_schema_validation(&self) -> bool {
-        self.lib.supports_schema_validation()
-    }
-
-    /// Check if completion is supported
-    #[must_use]
-    pub fn supports_completion(&self) -> bool {
-        self.lib.supports_completion()
-    }
-
-    /// Check if classification is supported
-    #[must_use]
-    pub fn supports_classification(&self) -> bool {
-        self.lib.supports_classification()
-    }
-
-    /// Get syntax classifications for a KQL query (for syntax highlighting)
+    /// Infer the result schema of a query
     ///
-    /// Returns a list of classified spans that can be used to highlight
-    /// different parts of the query (keywords, operators, identifiers, etc.)
+    /// Runs semantic analysis against `schema` and returns the columns and
+    /// types produced by the query's final statement. The returned table is
+    /// named after the result table Kusto.Language infers, which is usually
+    /// empty. The validator's dialect applies, so Log Analytics scoping
+    /// functions resolve as in validation.
     ///
-    /// # Arguments
+    /// # Example
     ///
-    /// * `query` - The KQL query string to classify
+    /// ```no_run
+    /// use kql_language_tools::{KqlValidator, Schema, Table};
     ///
-    /// # Returns
+    /// fn main() -> Result<(), kql_language_tools::Error> {
+    ///     let validator = KqlValidator::new()?;
+    ///     let schema = Schema::new().table(
+    ///         Table::new("SecurityEvent")
+    ///             .with_column("Account", "string")
+    ///             .with_column("EventID", "long"),
+    ///     );
     ///
-    /// A `ClassificationResult` containing spans with their classification kinds.
+    ///     let result = validator
+    ///         .infer_result_schema("SecurityEvent | summarize Count = count() by Account", &schema)?;
+    ///
+    ///     for column in &result.columns {
+    ///         println!("{}: {}", column.name, column.data_type); // Account: string, Count: long
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
     ///
     /// # Errors
     ///
-    /// Returns an error if classification is not supported by the loaded library.
-    pub fn get_classifications(
-        &self,
-        query: &str,
-    ) -> Result<crate::classification::ClassificationResult, Error> {
-        let classify_fn = self
+    /// Returns an error if result-schema inference is not supported by the
+    /// loaded library, or if the query does not produce a tabular result.
+    pub fn infer_result_schema(&self, query: &str, schema: &Schema) -> Result<Table, Error> {
+        let infer_fn = self
+            .native
             .lib
-            .get_classifications
+            .infer_result_schema
             .ok_or_else(|| Error::Internal {
-                message: "Classification not supported by loaded library".to_string(),
+                message: "Result-schema inference not supported by loaded library".to_string(),
             })?;
 
         let query_bytes = query.as_bytes();
         let query_len = c_int::try_from(query_bytes.len()).map_err(|_| Error::Internal {
             message: format!("Query too large: {} bytes", query_bytes.len()),
         })?;
+        let schema_json = schema.to_native_json()?;
+        let schema_len = c_int::try_from(schema_json.len()).map_err(|_| Error::Internal {
+            message: format!("Schema too large: {} bytes", schema_json.len()),
+        })?;
+        let options_json = serde_json::to_string(&self.native.options)?;
 
-        self.call_ffi_json(|buffer| {
+        let _scope = call_scope!(self.native, "infer_result_schema", query_len, schema_len);
+        let table: Option<Table> = self.native.call_ffi_json(|output, output_len| {
             // SAFETY: See validate_syntax for safety invariants.
             #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
             unsafe {
-                classify_fn(
+                infer_fn(
                     query_bytes.as_ptr(),
                     query_len,
-                    buffer.as_mut_ptr(),
-                    buffer.len() as c_int,
+                    schema_json.as_ptr(),
+                    schema_len,
+                    options_json.as_ptr(),
+                    options_json.len() as c_int,
+                    output,
+                    output_len,
                 )
             }
+        })?;
+
+        table.ok_or_else(|| Error::Internal {
+            message: "Result-schema inference returned no table".to_string(),
         })
     }
 
-    /// Get completion suggestions at a cursor position
+    /// Split a query's pipeline into its stages
     ///
-    /// Returns completion items (keywords, functions, tables, columns, etc.)
-    /// that are valid at the given cursor position.
+    /// The pipeline is the one of the query's last statement, as for
+    /// [`infer_result_schema`](Self::infer_result_schema); pipelines nested
+    /// in subqueries (such as a `join`'s right side) are part of their
+    /// operator's stage. Each stage has the columns the pipeline has after
+    /// it, resolved against `schema`.
     ///
-    /// # Arguments
+    /// # Example
     ///
-    /// * `query` - The KQL query string
-    /// * `cursor_position` - Cursor position (0-based character offset)
-    /// * `schema` - Optional schema for context-aware completions
+    /// ```no_run
+    /// use kql_language_tools::{KqlValidator, Schema, Table};
     ///
-    /// # Returns
+    /// fn main() -> Result<(), kql_language_tools::Error> {
+    ///     let validator = KqlValidator::new()?;
+    ///     let schema = Schema::new().table(
+    ///         Table::new("SigninLogs")
+    ///             .with_column("UserPrincipalName", "string")
+    ///             .with_column("ResultType", "string"),
+    ///     );
+    ///     let query = "SigninLogs | where ResultType != '0' | summarize Failures = count() by UserPrincipalName";
     ///
-    /// A `CompletionResult` containing completion items.
+    ///     let pipeline = validator.analyze_pipeline(query, &schema)?;
+    ///     for stage in &pipeline.stages {
+    ///         let columns = stage.output.as_ref().map_or(0, |table| table.columns.len());
+    ///         println!("{} ({} columns)", stage.text, columns);
+    ///     }
+    ///     // Run the query without its last stage
+    ///     let partial = pipeline.prefix(pipeline.stages.len() - 1);
+    ///     Ok(())
+    /// }
+    /// ```
     ///
     /// # Errors
     ///
-    /// Returns an error if completion is not supported by the loaded library.
-    pub fn get_completions(
+    /// Returns an error if pipeline analysis is not supported by the loaded
+    /// library.
+    pub fn analyze_pipeline(&self, query: &str, schema: &Schema) -> Result<Pipeline, Error> {
+        let pipeline_fn = self
+            .native
+            .lib
+            .analyze_pipeline
+            .ok_or_else(|| Error::Internal {
+                message: "Pipeline analysis not supported by loaded library".to_string(),
+            })?;
+
+        let query_bytes = query.as_bytes();
+        let query_len = c_int::try_from(query_bytes.len()).map_err(|_| Error::Internal {
+            message: format!("Query too large: {} bytes", query_bytes.len()),
+        })?;
+        let schema_json = schema.to_native_json()?;
+        let schema_len = c_int::try_from(schema_json.len()).map_err(|_| Error::Internal {
+            message: format!("Schema too large: {} bytes", schema_json.len()),
+        })?;
+        let options_json = serde_json::to_string(&self.native.options)?;
+
+        let _scope = call_scope!(self.native, "analyze_pipeline", query_len, schema_len);
+        self.native.call_ffi_offsets(query, |output, output_len| {
+            // SAFETY: See validate_syntax for safety invariants.
+            #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+            unsafe {
+                pipeline_fn(
+                    query_bytes.as_ptr(),
+                    query_len,
+                    schema_json.as_ptr(),
+                    schema_len,
+                    options_json.as_ptr(),
+                    options_json.len() as c_int,
+                    output,
+                    output_len,
+                )
+            }
+        })
+    }
+
+    /// List a query's joins, lookups and unions
+    ///
+    /// Every `join`, `lookup` and `union` is listed in source order, including
+    /// those inside subqueries and `let` statements, with its kind, the source
+    /// text of its inputs, and the keys of joins and lookups with their types
+    /// resolved against `schema`. Keys whose sides have different types, and
+    /// high-cardinality keys joined without a shuffle hint, are reported in
+    /// [`JoinAnalysis::diagnostics`] as warnings (see [`JoinAnalysis`] for the
+    /// codes).
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use kql_language_tools::{JoinOperator, KqlValidator, Schema, Table};
+    ///
+    /// fn main() -> Result<(), kql_language_tools::Error> {
+    ///     let validator = KqlValidator::new()?;
+    ///     let schema = Schema::new()
+    ///         .table(Table::new("SigninLogs").with_column("UserId", "string"))
+    ///         .table(Table::new("IdentityInfo").with_column("AccountObjectId", "guid"));
+    ///     let query = "SigninLogs | join kind=leftouter IdentityInfo on $left.UserId == $right.AccountObjectId";
+    ///
+    ///     let analysis = validator.analyze_joins(query, &schema)?;
+    ///     for join in analysis.of_kind(JoinOperator::Join) {
+    ///         println!("{} join of {}", join.kind, join.sources.join(", "));
+    ///     }
+    ///     for diagnostic in &analysis.diagnostics {
+    ///         println!("{}", diagnostic.message);
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if join analysis is not supported by the loaded
+    /// library.
+    pub fn analyze_joins(&self, query: &str, schema: &Schema) -> Result<JoinAnalysis, Error> {
+        let joins_fn = self
+            .native
+            .lib
+            .analyze_joins
+            .ok_or_else(|| Error::Internal {
+                message: "Join analysis not supported by loaded library".to_string(),
+            })?;
+
+        let query_bytes = query.as_bytes();
+        let query_len = c_int::try_from(query_bytes.len()).map_err(|_| Error::Internal {
+            message: format!("Query too large: {} bytes", query_bytes.len()),
+        })?;
+        let schema_json = schema.to_native_json()?;
+        let schema_len = c_int::try_from(schema_json.len()).map_err(|_| Error::Internal {
+            message: format!("Schema too large: {} bytes", schema_json.len()),
+        })?;
+        let options_json = serde_json::to_string(&self.native.options)?;
+
+        let _scope = call_scope!(self.native, "analyze_joins", query_len, schema_len);
+        let mut analysis: JoinAnalysis =
+            self.native.call_ffi_offsets(query, |output, output_len| {
+                // SAFETY: See validate_syntax for safety invariants.
+                #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+                unsafe {
+                    joins_fn(
+                        query_bytes.as_ptr(),
+                        query_len,
+                        schema_json.as_ptr(),
+                        schema_len,
+                        options_json.as_ptr(),
+                        options_json.len() as c_int,
+                        output,
+                        output_len,
+                    )
+                }
+            })?;
+        self.offsets
+            .encode_diagnostics(query, &mut analysis.diagnostics);
+        Ok(analysis)
+    }
+
+    /// Run the Kusto.Language best-practice analyzers
+    ///
+    /// The analyzers flag queries that are valid but slow or fragile: `contains`
+    /// where `has` would do, `==` on `dynamic` values, `has` with terms too
+    /// short to use the term index, and so on. Every diagnostic has
+    /// [`DiagnosticCategory::Analyzer`](crate::DiagnosticCategory::Analyzer).
+    /// Syntax and semantic errors are not repeated here, so combine with
+    /// [`validate_with_schema`](Self::validate_with_schema) for the full picture.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use kql_language_tools::{KqlValidator, Schema, Table};
+    ///
+    /// fn main() -> Result<(), kql_language_tools::Error> {
+    ///     let validator = KqlValidator::new()?;
+    ///     let schema = Schema::new().table(Table::new("SigninLogs").with_column("AppDisplayName", "string"));
+    ///
+    ///     let query = "SigninLogs | where AppDisplayName contains \"Office\"";
+    ///     for d in validator.get_analyzer_diagnostics(query, &schema)? {
+    ///         println!("{}: {}", d.code.as_deref().unwrap_or("-"), d.message);
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if best-practice analysis is not supported by the loaded library.
+    pub fn get_analyzer_diagnostics(
+        &self,
+        query: &str,
+        schema: &Schema,
+    ) -> Result<Vec<Diagnostic>, Error> {
+        let analyzer_fn =
+            self.native
+                .lib
+                .get_analyzer_diagnostics
+                .ok_or_else(|| Error::Internal {
+                    message: "Best-practice analysis not supported by loaded library".to_string(),
+                })?;
+
+        let query_bytes = query.as_bytes();
+        let query_len = c_int::try_from(query_bytes.len()).map_err(|_| Error::Internal {
+            message: format!("Query too large: {} bytes", query_bytes.len()),
+        })?;
+        let schema_json = schema.to_native_json()?;
+        let schema_len = c_int::try_from(schema_json.len()).map_err(|_| Error::Internal {
+            message: format!("Schema too large: {} bytes", schema_json.len()),
+        })?;
+        let options_json = serde_json::to_string(&self.native.options)?;
+
+        let _scope = call_scope!(
+            self.native,
+            "get_analyzer_diagnostics",
+            query_len,
+            schema_len
+        );
+        let mut diagnostics: Vec<Diagnostic> =
+            self.native.call_ffi_offsets(query, |output, output_len| {
+                // SAFETY: See validate_syntax for safety invariants.
+                #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+                unsafe {
+                    analyzer_fn(
+                        query_bytes.as_ptr(),
+                        query_len,
+                        schema_json.as_ptr(),
+                        schema_len,
+                        options_json.as_ptr(),
+                        options_json.len() as c_int,
+                        output,
+                        output_len,
+                    )
+                }
+            })?;
+        self.offsets.encode_diagnostics(query, &mut diagnostics);
+        Ok(diagnostics)
+    }
+
+    /// Get the type of the expression at a position
+    ///
+    /// Returns the innermost expression containing `position` whose type
+    /// Kusto.Language could infer against `schema`, with its span: a scalar
+    /// type for columns, literals, and function calls, or the columns of a
+    /// table for tabular expressions and query operators. Returns `None` when
+    /// there is no typed expression at the position. A position just past the
+    /// end of a token counts as being on it.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use kql_language_tools::{KqlValidator, Schema, Table};
+    ///
+    /// fn main() -> Result<(), kql_language_tools::Error> {
+    ///     let validator = KqlValidator::new()?;
+    ///     let schema = Schema::new().table(
+    ///         Table::new("SigninLogs").with_column("TimeGenerated", "datetime"),
+    ///     );
+    ///
+    ///     let query = "SigninLogs | where TimeGenerated > ago(1h)";
+    ///     let position = query.find("TimeGenerated").unwrap();
+    ///     if let Some(ty) = validator.get_expression_type(query, position, &schema)? {
+    ///         assert_eq!(ty.scalar_name(), Some("datetime"));
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if expression type inference is not supported by the
+    /// loaded library.
+    pub fn get_expression_type(
+        &self,
+        query: &str,
+        position: usize,
+        schema: &Schema,
+    ) -> Result<Option<ExpressionType>, Error> {
+        let type_fn = self
+            .native
+            .lib
+            .get_expression_type
+            .ok_or_else(|| Error::Internal {
+                message: "Expression type inference not supported by loaded library".to_string(),
+            })?;
+
+        let query_bytes = query.as_bytes();
+        let query_len = c_int::try_from(query_bytes.len()).map_err(|_| Error::Internal {
+            message: format!("Query too large: {} bytes", query_bytes.len()),
+        })?;
+        let position = positions::to_native(query, position);
+        let position = c_int::try_from(position).map_err(|_| Error::Internal {
+            message: format!("Position too large: {position}"),
+        })?;
+        let schema_json = schema.to_native_json()?;
+        let schema_len = c_int::try_from(schema_json.len()).map_err(|_| Error::Internal {
+            message: format!("Schema too large: {} bytes", schema_json.len()),
+        })?;
+        let options_json = serde_json::to_string(&self.native.options)?;
+
+        let _scope = call_scope!(self.native, "get_expression_type", query_len, schema_len);
+        self.native.call_ffi_offsets(query, |output, output_len| {
+            // SAFETY: See validate_syntax for safety invariants.
+            #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+            unsafe {
+                type_fn(
+                    query_bytes.as_ptr(),
+                    query_len,
+                    position,
+                    schema_json.as_ptr(),
+                    schema_len,
+                    options_json.as_ptr(),
+                    options_json.len() as c_int,
+                    output,
+                    output_len,
+                )
+            }
+        })
+    }
+
+    /// Get the inlay hints of a query within a character range
+    ///
+    /// Hints are the inferred types of scalar `let` bindings, the parameter
+    /// names of function call arguments (for functions with more than one
+    /// parameter), and the number of columns after each pipeline stage.
+    /// Only hints positioned inside `range` are returned, ordered by position.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use kql_language_tools::{KqlValidator, Schema, Table};
+    ///
+    /// fn main() -> Result<(), kql_language_tools::Error> {
+    ///     let validator = KqlValidator::new()?;
+    ///     let schema = Schema::new().table(Table::new("T").with_column("Name", "string"));
+    ///     let query = "let n = 5;\nT | extend Parts = split(Name, '.') | take n";
+    ///
+    ///     for hint in validator.get_inlay_hints(query, &schema, 0..query.len())? {
+    ///         println!("{} {:?} {}", hint.position, hint.kind, hint.label);
+    ///         // 5 Type : long, then a Parameter hint per split argument, ...
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if inlay hints are not supported by the loaded library.
+    pub fn get_inlay_hints(
+        &self,
+        query: &str,
+        schema: &Schema,
+        range: Range<usize>,
+    ) -> Result<Vec<InlayHint>, Error> {
+        let hints_fn = self
+            .native
+            .lib
+            .get_inlay_hints
+            .ok_or_else(|| Error::Internal {
+                message: "Inlay hints not supported by loaded library".to_string(),
+            })?;
+
+        let query_bytes = query.as_bytes();
+        let query_len = c_int::try_from(query_bytes.len()).map_err(|_| Error::Internal {
+            message: format!("Query too large: {} bytes", query_bytes.len()),
+        })?;
+        // Ranges reaching past the query (e.g. 0..usize::MAX) cover all of it
+        let range_start =
+            c_int::try_from(positions::to_native(query, range.start)).unwrap_or(c_int::MAX);
+        let range_end =
+            c_int::try_from(positions::to_native(query, range.end)).unwrap_or(c_int::MAX);
+        let schema_json = schema.to_native_json()?;
+        let schema_len = c_int::try_from(schema_json.len()).map_err(|_| Error::Internal {
+            message: format!("Schema too large: {} bytes", schema_json.len()),
+        })?;
+        let options_json = serde_json::to_string(&self.native.options)?;
+
+        let _scope = call_scope!(self.native, "get_inlay_hints", query_len, schema_len);
+        self.native.call_ffi_offsets(query, |output, output_len| {
+            // SAFETY: See validate_syntax for safety invariants.
+            #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+            unsafe {
+                hints_fn(
+                    query_bytes.as_ptr(),
+                    query_len,
+                    range_start,
+                    range_end,
+                    schema_json.as_ptr(),
+                    schema_len,
+                    options_json.as_ptr(),
+                    options_json.len() as c_int,
+                    output,
+                    output_len,
+                )
+            }
+        })
+    }
+
+    /// Cache validation results, keeping up to `capacity` of them
+    ///
+    /// See [`CachedValidator`].
+    #[must_use]
+    pub fn with_cache(self, capacity: usize) -> CachedValidator {
+        CachedValidator::new(self, capacity)
+    }
+
+    /// Bind the validator to a schema
+    ///
+    /// The schema is serialized and registered with the native library once;
+    /// [`BoundValidator::validate`] and [`BoundValidator::complete`] then
+    /// refer to it by id, so large schemas are not re-sent (or their symbol
+    /// tables rebuilt) on every call. Libraries without schema registration
+    /// fall back to passing the schema with each call.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use kql_language_tools::{KqlValidator, Schema, Table};
+    ///
+    /// fn main() -> Result<(), kql_language_tools::Error> {
+    ///     let schema = Schema::new().table(
+    ///         Table::new("SecurityEvent").with_column("EventID", "long"),
+    ///     );
+    ///     let bound = KqlValidator::new()?.with_schema(schema)?;
+    ///
+    ///     assert!(bound.validate("SecurityEvent | where EventID == 4625")?.is_valid());
+    ///     let completions = bound.complete("SecurityEvent | where ", 22)?;
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the native library rejects the schema.
+    pub fn with_schema(self, schema: Schema) -> Result<BoundValidator, Error> {
+        let handle = if self.supports_schema_registration() {
+            Some(self.register_schema(&schema)?)
+        } else {
+            None
+        };
+        Ok(BoundValidator::new(self, schema, handle))
+    }
+
+    /// Register a schema with the native library
+    ///
+    /// The native side parses the schema and caches its symbol tables once.
+    /// Pass the returned handle to [`validate_with_handle`](Self::validate_with_handle)
+    /// and [`get_completions_with_handle`](Self::get_completions_with_handle)
+    /// instead of the schema itself. The registration is released when the
+    /// handle is dropped.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use kql_language_tools::{KqlValidator, Schema, Table};
+    ///
+    /// fn main() -> Result<(), kql_language_tools::Error> {
+    ///     let validator = KqlValidator::new()?;
+    ///     let schema = Schema::new().table(
+    ///         Table::new("SecurityEvent").with_column("EventID", "long"),
+    ///     );
+    ///     let handle = validator.register_schema(&schema)?;
+    ///
+    ///     let result = validator.validate_with_handle("SecurityEvent | take 10", &handle)?;
+    ///     assert!(result.is_valid());
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if schema registration is not supported by the
+    /// loaded library, or if the native library rejects the schema.
+    pub fn register_schema(&self, schema: &Schema) -> Result<SchemaHandle, Error> {
+        // Registration is only useful if the id-based calls exist too
+        let register_fn = self
+            .native
+            .lib
+            .register_schema
+            .filter(|_| self.native.lib.supports_schema_registration())
+            .ok_or_else(|| Error::Internal {
+                message: "Schema registration not supported by loaded library".to_string(),
+            })?;
+
+        let schema_json = schema.to_native_json()?;
+        let schema_len = c_int::try_from(schema_json.len()).map_err(|_| Error::Internal {
+            message: format!("Schema too large: {} bytes", schema_json.len()),
+        })?;
+
+        let _scope = call_scope!(self.native, "register_schema", schema_len);
+        // SAFETY: schema_json is valid UTF-8 for the duration of the call and
+        // schema_len is its exact length.
+        let result = unsafe { register_fn(schema_json.as_ptr(), schema_len) };
+        if !return_codes::is_success(result) {
+            let error_msg = self.native.get_last_error().unwrap_or_default();
+            return Err(Error::from_native_code(result, &error_msg));
+        }
+
+        Ok(SchemaHandle::new(Arc::clone(&self.native.lib), result))
+    }
+
+    /// Validate a query against a registered schema
+    ///
+    /// Equivalent to [`validate_with_schema`](Self::validate_with_schema)
+    /// with the registered schema, including the validator's options.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if schema registration is not supported by the
+    /// loaded library.
+    pub fn validate_with_handle(
+        &self,
+        query: &str,
+        handle: &SchemaHandle,
+    ) -> Result<ValidationResult, Error> {
+        let mut result = self.check_with_handle(query, handle)?;
+        self.offsets
+            .encode_diagnostics(query, &mut result.diagnostics);
+        Ok(result)
+    }
+
+    /// Validate against a registered schema, with offsets in characters
+    pub(crate) fn check_with_handle(
+        &self,
+        query: &str,
+        handle: &SchemaHandle,
+    ) -> Result<ValidationResult, Error> {
+        let validate_fn =
+            self.native
+                .lib
+                .validate_with_schema_id
+                .ok_or_else(|| Error::Internal {
+                    message: "Schema registration not supported by loaded library".to_string(),
+                })?;
+
+        let query_bytes = query.as_bytes();
+        let query_len = c_int::try_from(query_bytes.len()).map_err(|_| Error::Internal {
+            message: format!("Query too large: {} bytes", query_bytes.len()),
+        })?;
+        let options_json = serde_json::to_string(&self.native.options)?;
+
+        let _scope = call_scope!(self.native, "check_with_handle", query_len);
+        self.native
+            .call_ffi_with_retry(query, |output, output_len| {
+                // SAFETY: See validate_syntax for safety invariants.
+                #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+                unsafe {
+                    validate_fn(
+                        query_bytes.as_ptr(),
+                        query_len,
+                        handle.id(),
+                        options_json.as_ptr(),
+                        options_json.len() as c_int,
+                        output,
+                        output_len,
+                    )
+                }
+            })
+    }
+
+    /// Get completion suggestions using a registered schema
+    ///
+    /// Equivalent to [`get_completions`](Self::get_completions) with the
+    /// registered schema.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if schema registration is not supported by the
+    /// loaded library.
+    pub fn get_completions_with_handle(
         &self,
         query: &str,
         cursor_position: usize,
-        schema: Option<&Schema>,
-    ) -> Result<crate::completion::CompletionResult, Error> {
-        let completions_fn = self.lib.get_completions.ok_or_else(|| Error::Internal {
-            message: "Completion not supported by loaded library".to_string(),
+        handle: &SchemaHandle,
+    ) -> Result<CompletionResult, Error> {
+        let completions_fn = self
+            .native
+            .lib
+            .get_completions_with_schema_id
+            .ok_or_else(|| Error::Internal {
+                message: "Schema registration not supported by loaded library".to_string(),
+            })?;
+
+        let query_bytes = query.as_bytes();
+        let query_len = c_int::try_from(query_bytes.len()).map_err(|_| Error::Internal {
+            message: format!("Query too large: {} bytes", query_bytes.len()),
+        })?;
+        let cursor_position =
+            positions::to_native(query, self.offsets.decode(query, cursor_position));
+        let cursor_pos = c_int::try_from(cursor_position).map_err(|_| Error::Internal {
+            message: format!("Cursor position too large: {cursor_position}"),
+        })?;
+
+        let _scope = call_scope!(self.native, "get_completions_with_handle", query_len);
+        let mut result: CompletionResult =
+            self.native.call_ffi_offsets(query, |output, output_len| {
+                // SAFETY: See validate_syntax for safety invariants.
+                #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+                unsafe {
+                    completions_fn(
+                        query_bytes.as_ptr(),
+                        query_len,
+                        cursor_pos,
+                        handle.id(),
+                        output,
+                        output_len,
+                    )
+                }
+            })?;
+        self.offsets.encode_completions(query, &mut result);
+        Ok(result)
+    }
+
+    /// Open a native document session, with offsets in characters
+    pub(crate) fn open_document(
+        &self,
+        text: &str,
+        handle: Option<&SchemaHandle>,
+    ) -> Result<c_int, Error> {
+        let open_fn = self
+            .native
+            .lib
+            .document_open
+            .filter(|_| self.native.lib.supports_documents())
+            .ok_or_else(|| Error::Internal {
+                message: "Document sessions not supported by loaded library".to_string(),
+            })?;
+
+        let text_len = c_int::try_from(text.len()).map_err(|_| Error::Internal {
+            message: format!("Document too large: {} bytes", text.len()),
+        })?;
+        let options_json = serde_json::to_string(&self.native.options)?;
+
+        let _scope = call_scope!(self.native, "open_document", text_len);
+        // SAFETY: text and options_json are valid UTF-8 for the duration of
+        // the call and the lengths are exact.
+        #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+        let result = unsafe {
+            open_fn(
+                text.as_ptr(),
+                text_len,
+                handle.map_or(0, SchemaHandle::id),
+                options_json.as_ptr(),
+                options_json.len() as c_int,
+            )
+        };
+        if !return_codes::is_success(result) {
+            let error_msg = self.native.get_last_error().unwrap_or_default();
+            return Err(Error::from_native_code(result, &error_msg));
+        }
+
+        Ok(result)
+    }
+
+    /// Replace a character range of a native document holding `text`
+    pub(crate) fn edit_document(
+        &self,
+        id: c_int,
+        text: &str,
+        start: usize,
+        end: usize,
+        new_text: &str,
+    ) -> Result<(), Error> {
+        let edit_fn = self
+            .native
+            .lib
+            .document_edit
+            .ok_or_else(|| Error::Internal {
+                message: "Document sessions not supported by loaded library".to_string(),
+            })?;
+
+        let start = positions::to_native(text, start);
+        let end = positions::to_native(text, end);
+        let start = c_int::try_from(start).map_err(|_| Error::Internal {
+            message: format!("Edit start too large: {start}"),
+        })?;
+        let end = c_int::try_from(end).map_err(|_| Error::Internal {
+            message: format!("Edit end too large: {end}"),
+        })?;
+        let text_len = c_int::try_from(new_text.len()).map_err(|_| Error::Internal {
+            message: format!("Edit too large: {} bytes", new_text.len()),
+        })?;
+
+        let _scope = call_scope!(self.native, "edit_document", text_len);
+        // SAFETY: new_text is valid UTF-8 for the duration of the call and
+        // text_len is its exact length.
+        let result = unsafe { edit_fn(id, start, end, new_text.as_ptr(), text_len) };
+        if !return_codes::is_success(result) {
+            let error_msg = self.native.get_last_error().unwrap_or_default();
+            return Err(Error::from_native_code(result, &error_msg));
+        }
+
+        Ok(())
+    }
+
+    /// Close a native document session, returning the native return code
+    pub(crate) fn close_document(&self, id: c_int) -> Option<c_int> {
+        self.native.lib.close_document_id(id)
+    }
+
+    /// Validate a native document holding `text`, with offsets in characters
+    pub(crate) fn document_diagnostics(
+        &self,
+        id: c_int,
+        text: &str,
+    ) -> Result<ValidationResult, Error> {
+        let diagnostics_fn =
+            self.native
+                .lib
+                .document_get_diagnostics
+                .ok_or_else(|| Error::Internal {
+                    message: "Document sessions not supported by loaded library".to_string(),
+                })?;
+
+        let _scope = call_scope!(self.native, "document_diagnostics");
+        self.native.call_ffi_with_retry(text, |output, output_len| {
+            // SAFETY: See validate_syntax for safety invariants.
+            #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+            unsafe {
+                diagnostics_fn(id, output, output_len)
+            }
+        })
+    }
+
+    /// Get completions in a native document holding `text`, with offsets in characters
+    pub(crate) fn document_completions(
+        &self,
+        id: c_int,
+        text: &str,
+        cursor_position: usize,
+    ) -> Result<CompletionResult, Error> {
+        let completions_fn =
+            self.native
+                .lib
+                .document_get_completions
+                .ok_or_else(|| Error::Internal {
+                    message: "Document sessions not supported by loaded library".to_string(),
+                })?;
+
+        let cursor_position = positions::to_native(text, cursor_position);
+        let cursor_pos = c_int::try_from(cursor_position).map_err(|_| Error::Internal {
+            message: format!("Cursor position too large: {cursor_position}"),
         })?;
 
-        let query_bytes = query.as_bytes();
-        let schema_json = schema.map(serde_json::to_string).transpose()?;
+        let _scope = call_scope!(self.native, "document_completions");
+        self.native.call_ffi_offsets(text, |output, output_len| {
+            // SAFETY: See validate_syntax for safety invariants.
+            #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+            unsafe {
+                completions_fn(id, cursor_pos, output, output_len)
+            }
+        })
+    }
+
+    /// Validate an ADX control command
+    ///
+    /// Parses `text` as a management command such as `.create table`,
+    /// `.set-or-append`, or `.show database schema`. Text that is a query
+    /// rather than a command is reported as an error (`KC001`).
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use kql_language_tools::KqlValidator;
+    ///
+    /// fn main() -> Result<(), kql_language_tools::Error> {
+    ///     let validator = KqlValidator::new()?;
+    ///
+    ///     let result = validator.validate_command(".create table Logs (Timestamp: datetime, Message: string)")?;
+    ///     assert!(result.is_valid());
+    ///
+    ///     let result = validator.validate_command("Logs | take 10")?;
+    ///     assert!(!result.is_valid());
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the loaded library does not export
+    /// `kql_validate_with_options`.
+    pub fn validate_command(&self, text: &str) -> Result<ValidationResult, Error> {
+        let options = ValidationOptions {
+            kind: QueryKind::Command,
+            ..self.native.options.clone()
+        };
+        let mut result = self.native.validate_with_options(text, None, &options)?;
+        self.offsets
+            .encode_diagnostics(text, &mut result.diagnostics);
+        Ok(result)
+    }
+
+    /// Validate an ADX control command against a schema
+    ///
+    /// See [`validate_command`](Self::validate_command). With a schema,
+    /// commands that reference tables (e.g. `.set-or-append Logs <| ...`)
+    /// have their queries analyzed as well.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the loaded library does not export
+    /// `kql_validate_with_options`.
+    pub fn validate_command_with_schema(
+        &self,
+        text: &str,
+        schema: &Schema,
+    ) -> Result<ValidationResult, Error> {
+        let options = ValidationOptions {
+            kind: QueryKind::Command,
+            ..self.native.options.clone()
+        };
+        let mut result = self
+            .native
+            .validate_with_options(text, Some(schema), &options)?;
+        spelling::suggest_names(text, schema, &mut result);
+        self.offsets
+            .encode_diagnostics(text, &mut result.diagnostics);
+        Ok(result)
+    }
+
+    /// Validate a document of queries separated by blank lines
+    ///
+    /// Splits the document into query blocks the way ADX tools do (one or
+    /// more blank lines end a block), validates all blocks in one native
+    /// call, and remaps each block's diagnostic offsets, lines, and fix edits
+    /// into document coordinates.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use kql_language_tools::KqlValidator;
+    ///
+    /// fn main() -> Result<(), kql_language_tools::Error> {
+    ///     let validator = KqlValidator::new()?;
+    ///     let script = validator.validate_script("T | take 10\n\nT\n| where")?;
+    ///
+    ///     assert_eq!(script.blocks.len(), 2);
+    ///     for diagnostic in script.diagnostics() {
+    ///         println!("line {}: {}", diagnostic.line, diagnostic.message); // line 4: ...
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the native call fails.
+    pub fn validate_script(&self, text: &str) -> Result<ScriptValidation, Error> {
+        let blocks = script::split(text);
+        let queries: Vec<&str> = blocks.iter().map(|b| b.text).collect();
+        let results = self.validate_batch(&queries, None)?;
+        Ok(self.collect_script(text, blocks, results))
+    }
+
+    /// Validate a document of queries separated by blank lines against a schema
+    ///
+    /// See [`validate_script`](Self::validate_script).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the native call fails, or if schema validation is
+    /// not supported by the loaded library.
+    pub fn validate_script_with_schema(
+        &self,
+        text: &str,
+        schema: &Schema,
+    ) -> Result<ScriptValidation, Error> {
+        let blocks = script::split(text);
+        let queries: Vec<&str> = blocks.iter().map(|b| b.text).collect();
+        let results = self.validate_batch(&queries, Some(schema))?;
+        Ok(self.collect_script(text, blocks, results))
+    }
+
+    /// Check a schema's definitions, parsing function bodies
+    ///
+    /// Returns the issues of [`Schema::validate`], plus an
+    /// [`InvalidFunctionBody`](SchemaIssue::InvalidFunctionBody) for each
+    /// function whose body has syntax errors. Bodies are parsed on their
+    /// own, so references to parameters and tables are not resolved.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use kql_language_tools::{Function, KqlValidator, Schema};
+    ///
+    /// let validator = KqlValidator::new()?;
+    /// let schema = Schema::new().function(Function::new("Broken", "long").body("T | where"));
+    /// for issue in validator.validate_schema(&schema)? {
+    ///     eprintln!("{issue}");
+    /// }
+    /// # Ok::<(), kql_language_tools::Error>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the native call fails.
+    pub fn validate_schema(&self, schema: &Schema) -> Result<Vec<SchemaIssue>, Error> {
+        let mut issues = schema.validate();
+        // Empty bodies are already reported
+        let (functions, bodies): (Vec<_>, Vec<_>) = schema
+            .functions
+            .iter()
+            .filter_map(|f| {
+                let body = schema_check::body_text(f.body.as_deref()?);
+                (!body.is_empty()).then_some((f, body))
+            })
+            .unzip();
+        let results = self.validate_batch(&bodies, None)?;
+        for (function, result) in functions.into_iter().zip(results) {
+            if let Some(error) = result.errors().next() {
+                issues.push(SchemaIssue::InvalidFunctionBody {
+                    function: function.name.clone(),
+                    message: error.message.clone(),
+                });
+            }
+        }
+        Ok(issues)
+    }
+
+    fn collect_script(
+        &self,
+        text: &str,
+        blocks: Vec<script::Block<'_>>,
+        results: Vec<ValidationResult>,
+    ) -> ScriptValidation {
+        let mut blocks: Vec<ScriptBlock> = blocks
+            .into_iter()
+            .zip(results)
+            .map(|(block, result)| block.into_script_block(result))
+            .collect();
+        self.offsets.encode_script(text, &mut blocks);
+        ScriptValidation { blocks }
+    }
+
+    /// Validate many KQL queries for syntax errors in one native call
+    ///
+    /// Returns one result per query, in input order. The validator's options
+    /// (dialect, profiles, ...) apply to every query. Libraries without
+    /// `kql_validate_batch` fall back to validating queries one at a time.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use kql_language_tools::KqlValidator;
+    ///
+    /// fn main() -> Result<(), kql_language_tools::Error> {
+    ///     let validator = KqlValidator::new()?;
+    ///     let results = validator.validate_many(&["T | take 10", "T | where"])?;
+    ///
+    ///     assert!(results[0].is_valid());
+    ///     assert!(!results[1].is_valid());
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the native call fails.
+    pub fn validate_many(&self, queries: &[&str]) -> Result<Vec<ValidationResult>, Error> {
+        let mut results = self.validate_batch(queries, None)?;
+        self.encode_batch(queries, &mut results);
+        Ok(results)
+    }
+
+    /// Validate many KQL queries against a schema in one native call
+    ///
+    /// The native library builds the schema's symbol tables once and shares
+    /// them across the batch.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the native call fails, or if schema validation is
+    /// not supported by the loaded library.
+    pub fn validate_many_with_schema(
+        &self,
+        queries: &[&str],
+        schema: &Schema,
+    ) -> Result<Vec<ValidationResult>, Error> {
+        let mut results = self.validate_batch(queries, Some(schema))?;
+        self.encode_batch(queries, &mut results);
+        Ok(results)
+    }
+
+    /// Validate many KQL queries in parallel on the rayon thread pool
+    ///
+    /// The queries are split into a few chunks per pool thread, and each
+    /// chunk is validated with one batch call (as in
+    /// [`validate_many`](Self::validate_many)), so every thread reuses a
+    /// single output buffer and, with a schema, one set of symbol tables per
+    /// chunk. Chunks smaller than one per thread would leave threads idle
+    /// behind a slow query; more would rebuild the symbol tables needlessly.
+    /// Results are returned in input order.
+    ///
+    /// # Thread safety
+    ///
+    /// The native library may be called from any number of threads at once:
+    /// the Kusto.Language syntax trees and symbol tables are immutable, the
+    /// schema and document registries are concurrent maps, and the last
+    /// error message is stored per thread.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use kql_language_tools::KqlValidator;
+    ///
+    /// fn main() -> Result<(), kql_language_tools::Error> {
+    ///     let validator = KqlValidator::new()?;
+    ///     let corpus = vec!["SecurityEvent | take 10"; 3000];
+    ///
+    ///     let results = validator.validate_all_par(&corpus, None)?;
+    ///     assert_eq!(results.len(), corpus.len());
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns the first error of any chunk's native call.
+    #[cfg(feature = "rayon")]
+    pub fn validate_all_par(
+        &self,
+        queries: &[&str],
+        schema: Option<&Schema>,
+    ) -> Result<Vec<ValidationResult>, Error> {
+        use rayon::prelude::*;
+
+        let chunks = rayon::current_num_threads() * PARALLEL_CHUNKS_PER_THREAD;
+        let chunk_size = ((queries.len() + chunks - 1) / chunks).max(1);
+
+        let results = queries
+            .par_chunks(chunk_size)
+            .map(|chunk| match schema {
+                Some(schema) => self.validate_many_with_schema(chunk, schema),
+                None => self.validate_many(chunk),
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        Ok(results.into_iter().flatten().collect())
+    }
+
+    /// Convert batch results into the configured offset encoding
+    fn encode_batch(&self, queries: &[&str], results: &mut [ValidationResult]) {
+        for (query, result) in queries.iter().zip(results) {
+            self.offsets
+                .encode_diagnostics(query, &mut result.diagnostics);
+        }
+    }
+
+    /// Validate a batch, with offsets in characters
+    fn validate_batch(
+        &self,
+        queries: &[&str],
+        schema: Option<&Schema>,
+    ) -> Result<Vec<ValidationResult>, Error> {
+        let native = self
+            .native
+            .lib
+            .validate_batch
+            .filter(|_| self.engine.is_none());
+        let Some(batch_fn) = native else {
+            return queries
+                .iter()
+                .map(|query| self.check(query, schema))
+                .collect();
+        };
+
+        if queries.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let queries_json = serde_json::to_string(queries)?;
+        let schema_json = schema.map(Schema::to_native_json).transpose()?;
+        let options_json = serde_json::to_string(&self.native.options)?;
+
+        let queries_len = c_int::try_from(queries_json.len()).map_err(|_| Error::Internal {
+            message: format!("Batch too large: {} bytes", queries_json.len()),
+        })?;
+
+        let _scope = call_scope!(
+            self.native,
+            "validate_batch",
+            queries_len,
+            schema_len = schema_json.as_ref().map_or(0, String::len),
+        );
+        let mut results: Vec<ValidationResult> =
+            self.native.call_ffi_json(|output, output_len| {
+                // SAFETY: See validate_syntax for safety invariants.
+                // schema_ptr may be null (handled by FFI), schema_len is 0 in that case.
+                #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+                unsafe {
+                    let (schema_ptr, schema_len) = match &schema_json {
+                        Some(json) => (json.as_ptr(), json.len() as c_int),
+                        None => (std::ptr::null(), 0),
+                    };
+
+                    batch_fn(
+                        queries_json.as_ptr(),
+                        queries_len,
+                        schema_ptr,
+                        schema_len,
+                        options_json.as_ptr(),
+                        options_json.len() as c_int,
+                        output,
+                        output_len,
+                    )
+                }
+            })?;
+
+        if results.len() != queries.len() {
+            return Err(Error::Internal {
+                message: format!(
+                    "Batch validation returned {} results for {} queries",
+                    results.len(),
+                    queries.len()
+                ),
+            });
+        }
+
+        for (query, result) in queries.iter().zip(&mut results) {
+            positions::from_native(query, result);
+            self.native.diagnostics.apply(result);
+            if let Some(schema) = schema {
+                spelling::suggest_names(query, schema, result);
+            }
+        }
+        Ok(results)
+    }
+
+    /// Apply quick fixes to a query and re-validate it
+    ///
+    /// Fixes are applied in order in a single pass; a fix that overlaps an
+    /// earlier one (or falls outside the query) is skipped and reported in
+    /// [`FixOutcome::conflicts`]. The fixed query is re-validated the way
+    /// the fixes were produced: with
+    /// [`validate_with_schema`](Self::validate_with_schema) when `schema`
+    /// is given (e.g. for "did you mean" fixes), and with
+    /// [`validate_syntax`](Self::validate_syntax) otherwise.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use kql_language_tools::KqlValidator;
+    ///
+    /// fn main() -> Result<(), kql_language_tools::Error> {
+    ///     let validator = KqlValidator::new()?.with_case_sensitivity_advisory(true);
+    ///     let query = "T | where Ip has '10.0.0.1'";
+    ///
+    ///     let result = validator.validate_syntax(query)?;
+    ///     let fixes: Vec<_> = result.fixes().cloned().collect();
+    ///
+    ///     let outcome = validator.apply_fixes(query, &fixes, None)?;
+    ///     assert_eq!(outcome.query, "T | where Ip has_cs '10.0.0.1'");
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if re-validation fails.
+    pub fn apply_fixes(
+        &self,
+        query: &str,
+        fixes: &[Fix],
+        schema: Option<&Schema>,
+    ) -> Result<FixOutcome, Error> {
+        let mut decoded = fixes.to_vec();
+        self.offsets.decode_fixes(query, &mut decoded);
+        let (fixed_query, mut applied, mut conflicts) = fixes::apply(query, &decoded);
+        self.offsets.encode_fixes(query, &mut applied);
+        self.offsets.encode_fixes(query, &mut conflicts);
+        let validation = match schema {
+            Some(schema) => self.validate_with_schema(&fixed_query, schema)?,
+            None => self.validate_syntax(&fixed_query)?,
+        };
+
+        Ok(FixOutcome {
+            query: fixed_query,
+            applied,
+            conflicts,
+            validation,
+        })
+    }
+
+    /// Check if schema validation is supported
+    #[must_use]
+    pub fn supports_schema_validation(&self) -> bool {
+        self.native.lib.supports_schema_validation()
+    }
+
+    /// Check if completion is supported
+    #[must_use]
+    pub fn supports_completion(&self) -> bool {
+        self.native.lib.supports_completion()
+    }
+
+    /// Check if completion options are applied by the native library
+    ///
+    /// When `false`, [`get_completions_with_options`](Self::get_completions_with_options)
+    /// still works but filters in Rust.
+    #[must_use]
+    pub fn supports_completion_options(&self) -> bool {
+        self.native.lib.supports_completion_options()
+    }
+
+    /// Check if classification is supported
+    #[must_use]
+    pub fn supports_classification(&self) -> bool {
+        self.native.lib.supports_classification()
+    }
+
+    /// Check if schemas can be registered with the native library
+    ///
+    /// When `false`, [`register_schema`](Self::register_schema) fails and
+    /// [`with_schema`](Self::with_schema) falls back to sending the schema
+    /// with every call.
+    #[must_use]
+    pub fn supports_schema_registration(&self) -> bool {
+        self.native.lib.supports_schema_registration()
+    }
+
+    /// Check if incremental document sessions are supported
+    ///
+    /// When `false`, [`Document::open`](crate::Document::open) fails.
+    #[must_use]
+    pub fn supports_documents(&self) -> bool {
+        self.native.lib.supports_documents()
+    }
+
+    /// Check if native cancellation is supported
+    ///
+    /// When `false`, [`run_cancellable`](Self::run_cancellable) still
+    /// returns on cancellation but the native call runs to completion.
+    #[must_use]
+    pub fn supports_cancellation(&self) -> bool {
+        self.native.lib.supports_cancellation()
+    }
+
+    /// Check if the document outline is supported
+    #[must_use]
+    pub fn supports_document_symbols(&self) -> bool {
+        self.native.lib.supports_document_symbols()
+    }
+
+    /// Check if syntax tree access is supported
+    #[must_use]
+    pub fn supports_syntax_tree(&self) -> bool {
+        self.native.lib.supports_syntax_tree()
+    }
+
+    /// Check if inlay hints are supported
+    #[must_use]
+    pub fn supports_inlay_hints(&self) -> bool {
+        self.native.lib.supports_inlay_hints()
+    }
+
+    /// Check if pipeline stage decomposition is supported
+    #[must_use]
+    pub fn supports_pipeline_analysis(&self) -> bool {
+        self.native.lib.supports_pipeline_analysis()
+    }
+
+    /// Check if join and union analysis is supported
+    #[must_use]
+    pub fn supports_join_analysis(&self) -> bool {
+        self.native.lib.supports_join_analysis()
+    }
+
+    /// Check if bracket matching and enclosing ranges are supported
+    #[must_use]
+    pub fn supports_selection_ranges(&self) -> bool {
+        self.native.lib.supports_selection_ranges()
+    }
+
+    /// Check if expression type inference is supported
+    #[must_use]
+    pub fn supports_expression_types(&self) -> bool {
+        self.native.lib.supports_expression_types()
+    }
+
+    /// Check if completion items can be resolved
+    #[must_use]
+    pub fn supports_completion_resolve(&self) -> bool {
+        self.native.lib.supports_completion_resolve()
+    }
+
+    /// Check if best-practice analysis is supported
+    #[must_use]
+    pub fn supports_analyzers(&self) -> bool {
+        self.native.lib.supports_analyzers()
+    }
+
+    /// Get the ABI version the native library reports
+    ///
+    /// `None` for libraries predating the version handshake. Libraries
+    /// reporting a version other than the one this crate requires fail to
+    /// load with [`Error::AbiMismatch`].
+    #[must_use]
+    pub fn abi_version(&self) -> Option<i32> {
+        self.native.lib.abi_version()
+    }
+
+    /// Get the capability names the native library reports
+    ///
+    /// Empty for libraries predating `kql_get_capabilities`; use the
+    /// `supports_*` methods to check individual features.
+    #[must_use]
+    pub fn capabilities(&self) -> Vec<String> {
+        self.native.lib.capabilities()
+    }
+
+    /// Check if the native library can return CBOR call results
+    #[must_use]
+    pub fn supports_binary_results(&self) -> bool {
+        self.native.lib.supports_binary_results()
+    }
+
+    /// Check if go-to-definition is supported
+    #[must_use]
+    pub fn supports_definitions(&self) -> bool {
+        self.native.lib.supports_definitions()
+    }
+
+    /// Check if schema-aware classification is supported
+    #[must_use]
+    pub fn supports_semantic_classification(&self) -> bool {
+        self.native.lib.supports_semantic_classification()
+    }
+
+    /// Check if classifications can be requested for a range of the query
+    #[must_use]
+    pub fn supports_classification_ranges(&self) -> bool {
+        self.native.lib.supports_classification_ranges()
+    }
+
+    /// Check if referenced-entity analysis is supported
+    #[must_use]
+    pub fn supports_analysis(&self) -> bool {
+        self.native.lib.supports_analysis()
+    }
+
+    /// Check if result-schema inference is supported
+    #[must_use]
+    pub fn supports_result_schema(&self) -> bool {
+        self.native.lib.supports_result_schema()
+    }
+
+    /// Check if formatting is supported
+    #[must_use]
+    pub fn supports_formatting(&self) -> bool {
+        self.native.lib.supports_formatting()
+    }
+
+    /// Check if batch validation is done in a single native call
+    ///
+    /// When `false`, [`validate_many`](Self::validate_many) still works but
+    /// validates queries one at a time.
+    #[must_use]
+    pub fn supports_batch_validation(&self) -> bool {
+        self.native.lib.supports_batch_validation()
+    }
+
+    /// Check if document structure is supported
+    #[must_use]
+    pub fn supports_document_structure(&self) -> bool {
+        self.native.lib.supports_document_structure()
+    }
+
+    /// Check if non-default dialects are supported
+    #[must_use]
+    pub fn supports_dialects(&self) -> bool {
+        self.native.lib.supports_validation_options()
+    }
+
+    /// Get syntax classifications for a KQL query (for syntax highlighting)
+    ///
+    /// Returns a list of classified spans that can be used to highlight
+    /// different parts of the query (keywords, operators, identifiers, etc.)
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - The KQL query string to classify
+    ///
+    /// # Returns
+    ///
+    /// A `ClassificationResult` containing spans with their classification kinds.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if classification is not supported by the loaded library.
+    pub fn get_classifications(
+        &self,
+        query: &str,
+    ) -> Result<crate::classification::ClassificationResult, Error> {
+        self.engine().get_classifications(query)
+    }
+
+    /// Get syntax classifications with per-call options
+    ///
+    /// Same as [`get_classifications`](Self::get_classifications), but with
+    /// [`ClassificationOptions::contiguous`] the spans are a sorted,
+    /// non-overlapping covering of the whole query, so a highlighter can
+    /// print them in order without handling gaps.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use kql_language_tools::{ClassificationOptions, KqlValidator};
+    ///
+    /// fn main() -> Result<(), kql_language_tools::Error> {
+    ///     let validator = KqlValidator::new()?;
+    ///     let query = "T | take 10";
+    ///     let options = ClassificationOptions::default().contiguous(true);
+    ///
+    ///     let result = validator.get_classifications_with_options(query, &options)?;
+    ///     let covered: usize = result.spans.iter().map(|s| s.length).sum();
+    ///     assert_eq!(covered, query.chars().count());
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if classification is not supported by the loaded library.
+    pub fn get_classifications_with_options(
+        &self,
+        query: &str,
+        options: &ClassificationOptions,
+    ) -> Result<crate::classification::ClassificationResult, Error> {
+        let mut result = self.get_classifications(query)?;
+        if options.contiguous {
+            result.make_contiguous(query);
+        }
+        Ok(result)
+    }
+
+    /// Get syntax classifications for the tokens starting within a character range
+    ///
+    /// Spans are the ones [`get_classifications`](Self::get_classifications)
+    /// returns whose start lies in `range`, so the results for adjacent ranges
+    /// never share a span. The native library reuses the last query it
+    /// analyzed while it is still in memory, so classifying one query range
+    /// by range usually parses it once. For whole queries,
+    /// [`classify_chunks`](Self::classify_chunks) parses once and streams
+    /// the spans.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if range classification is not supported by the
+    /// loaded library.
+    pub fn get_classifications_in_range(
+        &self,
+        query: &str,
+        range: Range<usize>,
+    ) -> Result<ClassificationResult, Error> {
+        let classify_fn = self
+            .native
+            .lib
+            .get_classifications_in_range
+            .ok_or_else(|| Error::Internal {
+                message: "Range classification not supported by loaded library".to_string(),
+            })?;
+
+        let query_bytes = query.as_bytes();
+        let query_len = c_int::try_from(query_bytes.len()).map_err(|_| Error::Internal {
+            message: format!("Query too large: {} bytes", query_bytes.len()),
+        })?;
+        // Ranges reaching past the query (e.g. 0..usize::MAX) cover all of it
+        let range_start =
+            c_int::try_from(positions::to_native(query, range.start)).unwrap_or(c_int::MAX);
+        let range_end =
+            c_int::try_from(positions::to_native(query, range.end)).unwrap_or(c_int::MAX);
+
+        let _scope = call_scope!(self.native, "get_classifications_in_range", query_len);
+        self.native.call_ffi_offsets(query, |output, output_len| {
+            // SAFETY: See validate_syntax for safety invariants.
+            unsafe {
+                classify_fn(
+                    query_bytes.as_ptr(),
+                    query_len,
+                    range_start,
+                    range_end,
+                    output,
+                    output_len,
+                )
+            }
+        })
+    }
+
+    /// Classify a query in batches, passing each batch to `on_batch` as it arrives
+    ///
+    /// Meant for very large (for example machine-generated) queries: the
+    /// query crosses to the native library once, is parsed once, and its
+    /// spans come back a batch at a time, so highlighting can start after the
+    /// first batch and only one batch of spans is held at once. Batches
+    /// arrive in query order and each span is passed once, sorted by start
+    /// offset.
+    ///
+    /// Libraries without `kql_classify_chunks` classify the whole query in
+    /// one call, and its spans are then passed on in batches.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use kql_language_tools::KqlValidator;
+    ///
+    /// fn main() -> Result<(), kql_language_tools::Error> {
+    ///     let validator = KqlValidator::new()?;
+    ///     let query = "T | where x in (1, 2, 3)".repeat(100_000);
+    ///
+    ///     let mut count = 0;
+    ///     validator.classify_chunks(&query, |batch| count += batch.len())?;
+    ///     println!("{count} spans");
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns the native error, or the error decoding a batch; batches
+    /// passed before it stay passed.
+    pub fn classify_chunks<F>(&self, query: &str, mut on_batch: F) -> Result<(), Error>
+    where
+        F: FnMut(&[ClassifiedSpan]),
+    {
+        let Some(classify_fn) = self.native.lib.classify_chunks else {
+            let result = self.get_classifications(query)?;
+            pass_in_batches(result.spans, &mut on_batch);
+            return Ok(());
+        };
+
+        let query_bytes = query.as_bytes();
+        let query_len = c_int::try_from(query_bytes.len()).map_err(|_| Error::Internal {
+            message: format!("Query too large: {} bytes", query_bytes.len()),
+        })?;
+        let batch_spans = c_int::try_from(CLASSIFY_BATCH_SPANS).unwrap_or(c_int::MAX);
+        let mut stream = BatchStream {
+            index: positions::native_index(query),
+            on_batch: &mut on_batch,
+            error: None,
+            panic: None,
+        };
+
+        let _scope = call_scope!(self.native, "classify_chunks", query_len);
+        // SAFETY: query_bytes is valid for the call, receive_batch matches the
+        // callback signature, and the context points to `stream`, which
+        // outlives the call and is only used by receive_batch during it.
+        let result = unsafe {
+            classify_fn(
+                query_bytes.as_ptr(),
+                query_len,
+                batch_spans,
+                receive_batch,
+                ptr::addr_of_mut!(stream).cast(),
+            )
+        };
+
+        if let Some(panic) = stream.panic {
+            panic::resume_unwind(panic);
+        }
+        if let Some(error) = stream.error {
+            return Err(error);
+        }
+        if !return_codes::is_success(result) {
+            let error_msg = self.native.get_last_error().unwrap_or_default();
+            return Err(Error::from_native_code(result, &error_msg));
+        }
+        Ok(())
+    }
+
+    /// Get schema-aware classifications for a KQL query
+    ///
+    /// Like [`get_classifications`](Self::get_classifications), but names are
+    /// resolved against `schema`, so tables, columns, and functions from the
+    /// schema are classified as [`Table`](crate::ClassificationKind::Table),
+    /// [`Column`](crate::ClassificationKind::Column), and
+    /// [`ScalarFunction`](crate::ClassificationKind::ScalarFunction) rather
+    /// than as plain identifiers.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use kql_language_tools::{ClassificationKind, KqlValidator, Schema, Table};
+    ///
+    /// fn main() -> Result<(), kql_language_tools::Error> {
+    ///     let validator = KqlValidator::new()?;
+    ///     let schema = Schema::new().table(
+    ///         Table::new("SecurityEvent").with_column("EventID", "long"),
+    ///     );
+    ///
+    ///     let result = validator.get_semantic_classifications("SecurityEvent | where EventID == 4625", &schema)?;
+    ///     assert_eq!(result.spans[0].kind, ClassificationKind::Table);
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if schema-aware classification is not supported by
+    /// the loaded library.
+    pub fn get_semantic_classifications(
+        &self,
+        query: &str,
+        schema: &Schema,
+    ) -> Result<crate::classification::ClassificationResult, Error> {
+        let classify_fn = self
+            .native
+            .lib
+            .get_semantic_classifications
+            .ok_or_else(|| Error::Internal {
+                message: "Semantic classification not supported by loaded library".to_string(),
+            })?;
+
+        let query_bytes = query.as_bytes();
+        let query_len = c_int::try_from(query_bytes.len()).map_err(|_| Error::Internal {
+            message: format!("Query too large: {} bytes", query_bytes.len()),
+        })?;
+        let schema_json = schema.to_native_json()?;
+        let schema_len = c_int::try_from(schema_json.len()).map_err(|_| Error::Internal {
+            message: format!("Schema too large: {} bytes", schema_json.len()),
+        })?;
+        let options_json = serde_json::to_string(&self.native.options)?;
+
+        let _scope = call_scope!(
+            self.native,
+            "get_semantic_classifications",
+            query_len,
+            schema_len
+        );
+        self.native.call_ffi_offsets(query, |output, output_len| {
+            // SAFETY: See validate_syntax for safety invariants.
+            #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+            unsafe {
+                classify_fn(
+                    query_bytes.as_ptr(),
+                    query_len,
+                    schema_json.as_ptr(),
+                    schema_len,
+                    options_json.as_ptr(),
+                    options_json.len() as c_int,
+                    output,
+                    output_len,
+                )
+            }
+        })
+    }
+
+    /// Get the structure of a KQL document
+    ///
+    /// Returns statements, `let` declarations, and foldable regions as one
+    /// tree in a single native call, enough to build an LSP outline
+    /// (document symbols) and folding ranges together.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use kql_language_tools::KqlValidator;
+    ///
+    /// fn main() -> Result<(), kql_language_tools::Error> {
+    ///     let validator = KqlValidator::new()?;
+    ///     let structure = validator.get_document_structure("let x = 1;\nT | take x")?;
+    ///
+    ///     for decl in structure.declarations() {
+    ///         println!("{:?} {:?} at line {}", decl.kind, decl.name, decl.start_line);
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if document structure is not supported by the loaded library.
+    pub fn get_document_structure(&self, query: &str) -> Result<DocumentStructure, Error> {
+        let structure_fn =
+            self.native
+                .lib
+                .get_document_structure
+                .ok_or_else(|| Error::Internal {
+                    message: "Document structure not supported by loaded library".to_string(),
+                })?;
+
+        let query_bytes = query.as_bytes();
+        let query_len = c_int::try_from(query_bytes.len()).map_err(|_| Error::Internal {
+            message: format!("Query too large: {} bytes", query_bytes.len()),
+        })?;
+
+        let _scope = call_scope!(self.native, "get_document_structure", query_len);
+        self.native.call_ffi_offsets(query, |output, output_len| {
+            // SAFETY: See validate_syntax for safety invariants.
+            #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+            unsafe {
+                structure_fn(query_bytes.as_ptr(), query_len, output, output_len)
+            }
+        })
+    }
+
+    /// Run Rust-side lint rules over a query
+    ///
+    /// Classifies the query and passes the spans to every rule of `linter`.
+    /// Only lint diagnostics are returned; validate the query separately.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use kql_language_tools::{KqlValidator, Linter};
+    ///
+    /// fn main() -> Result<(), kql_language_tools::Error> {
+    ///     let validator = KqlValidator::new()?;
+    ///     let linter = Linter::builtin();
+    ///
+    ///     for d in validator.lint("search * | take 10", &linter)? {
+    ///         println!("{}: {}", d.code.as_deref().unwrap_or("-"), d.message);
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if classification is not supported by the loaded library.
+    pub fn lint(&self, query: &str, linter: &Linter) -> Result<Vec<Diagnostic>, Error> {
+        let classifications = self.get_classifications(query)?;
+        let mut diagnostics = linter.check(query, &classifications.spans);
+        self.offsets.encode_diagnostics(query, &mut diagnostics);
+        Ok(diagnostics)
+    }
+
+    /// Get the outline of a KQL document
+    ///
+    /// Returns `let` variables and functions (with their parameters), and
+    /// query statements named after their source, each with its pipeline
+    /// stages as children.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use kql_language_tools::KqlValidator;
+    ///
+    /// fn main() -> Result<(), kql_language_tools::Error> {
+    ///     let validator = KqlValidator::new()?;
+    ///     let symbols = validator.get_document_symbols("let x = 1;\nT | where A > x | take 10")?;
+    ///
+    ///     for symbol in &symbols {
+    ///         println!("{:?} {}", symbol.kind, symbol.name); // Variable x, Query T
+    ///         for stage in &symbol.children {
+    ///             println!("  {:?} {}", stage.kind, stage.name); // Operator where, ...
+    ///         }
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the document outline is not supported by the loaded library.
+    pub fn get_document_symbols(&self, query: &str) -> Result<Vec<DocumentSymbol>, Error> {
+        let symbols_fn = self
+            .native
+            .lib
+            .get_document_symbols
+            .ok_or_else(|| Error::Internal {
+                message: "Document outline not supported by loaded library".to_string(),
+            })?;
+
+        let query_bytes = query.as_bytes();
+        let query_len = c_int::try_from(query_bytes.len()).map_err(|_| Error::Internal {
+            message: format!("Query too large: {} bytes", query_bytes.len()),
+        })?;
+
+        let _scope = call_scope!(self.native, "get_document_symbols", query_len);
+        self.native.call_ffi_offsets(query, |output, output_len| {
+            // SAFETY: See validate_syntax for safety invariants.
+            #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+            unsafe {
+                symbols_fn(query_bytes.as_ptr(), query_len, output, output_len)
+            }
+        })
+    }
+
+    /// Get the matching bracket pairs of a KQL document
+    ///
+    /// Returns one pair per matched `()`, `[]` or `{}`, ordered by the
+    /// opening bracket. Brackets inside strings and comments are skipped, and
+    /// unmatched brackets are left out.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if bracket matching is not supported by the loaded library.
+    pub fn get_bracket_pairs(&self, query: &str) -> Result<Vec<BracketPair>, Error> {
+        let pairs_fn = self
+            .native
+            .lib
+            .get_bracket_pairs
+            .ok_or_else(|| Error::Internal {
+                message: "Bracket matching not supported by loaded library".to_string(),
+            })?;
+
+        let query_bytes = query.as_bytes();
+        let query_len = c_int::try_from(query_bytes.len()).map_err(|_| Error::Internal {
+            message: format!("Query too large: {} bytes", query_bytes.len()),
+        })?;
+
+        let _scope = call_scope!(self.native, "get_bracket_pairs", query_len);
+        self.native.call_ffi_offsets(query, |output, output_len| {
+            // SAFETY: See validate_syntax for safety invariants.
+            #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+            unsafe {
+                pairs_fn(query_bytes.as_ptr(), query_len, output, output_len)
+            }
+        })
+    }
+
+    /// Get the syntax ranges enclosing a position, innermost first
+    ///
+    /// Ranges grow from the token at `position` through its expressions,
+    /// pipeline stage and pipeline to the statement and the whole document,
+    /// each strictly larger than the one before. Editors use them for
+    /// expand-selection (LSP `selectionRange`).
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use kql_language_tools::KqlValidator;
+    ///
+    /// fn main() -> Result<(), kql_language_tools::Error> {
+    ///     let validator = KqlValidator::new()?;
+    ///     let query = "T | where A > 10 | take 5";
+    ///
+    ///     // Expanding from `10`: 10, A > 10, where A > 10, T | where A > 10, ...
+    ///     for range in validator.get_enclosing_ranges(query, 14)? {
+    ///         println!("{:?} {}", range.kind, &query[range.start..range.end]);
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if enclosing ranges are not supported by the loaded library.
+    pub fn get_enclosing_ranges(
+        &self,
+        query: &str,
+        position: usize,
+    ) -> Result<Vec<EnclosingRange>, Error> {
+        let ranges_fn = self
+            .native
+            .lib
+            .get_enclosing_ranges
+            .ok_or_else(|| Error::Internal {
+                message: "Enclosing ranges not supported by loaded library".to_string(),
+            })?;
+
+        let query_bytes = query.as_bytes();
+        let query_len = c_int::try_from(query_bytes.len()).map_err(|_| Error::Internal {
+            message: format!("Query too large: {} bytes", query_bytes.len()),
+        })?;
+        let position = positions::to_native(query, position);
+        let position = c_int::try_from(position).map_err(|_| Error::Internal {
+            message: format!("Position too large: {position}"),
+        })?;
+
+        let _scope = call_scope!(self.native, "get_enclosing_ranges", query_len);
+        self.native.call_ffi_offsets(query, |output, output_len| {
+            // SAFETY: See validate_syntax for safety invariants.
+            #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+            unsafe {
+                ranges_fn(
+                    query_bytes.as_ptr(),
+                    query_len,
+                    position,
+                    output,
+                    output_len,
+                )
+            }
+        })
+    }
+
+    /// Get the syntax tree of a KQL query
+    ///
+    /// The tree is parsed without a schema, so it is the same whatever the
+    /// query's names refer to. Queries with syntax errors still get a tree,
+    /// with the parser's recovery nodes where syntax is missing.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use kql_language_tools::KqlValidator;
+    ///
+    /// fn main() -> Result<(), kql_language_tools::Error> {
+    ///     let validator = KqlValidator::new()?;
+    ///     let tree = validator.get_syntax_tree("T | summarize count() by State")?;
+    ///     println!("{}", serde_json::to_string_pretty(&tree)?);
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if syntax tree access is not supported by the loaded library.
+    pub fn get_syntax_tree(&self, query: &str) -> Result<SyntaxTree, Error> {
+        let tree_fn = self
+            .native
+            .lib
+            .get_syntax_tree
+            .ok_or_else(|| Error::Internal {
+                message: "Syntax tree not supported by loaded library".to_string(),
+            })?;
+
+        let query_bytes = query.as_bytes();
+        let query_len = c_int::try_from(query_bytes.len()).map_err(|_| Error::Internal {
+            message: format!("Query too large: {} bytes", query_bytes.len()),
+        })?;
+
+        let _scope = call_scope!(self.native, "get_syntax_tree", query_len);
+        let tree: FlatTree = self.native.call_ffi_offsets(query, |output, output_len| {
+            // SAFETY: See validate_syntax for safety invariants.
+            unsafe { tree_fn(query_bytes.as_ptr(), query_len, output, output_len) }
+        })?;
+        Ok(SyntaxTree::from_nodes(tree.nodes))
+    }
+
+    /// Get folding ranges for a KQL document
+    ///
+    /// Returns one range per foldable line for multi-line `let` statements,
+    /// function bodies, parenthesized expressions, and `//` comment blocks.
+    /// This is [`DocumentStructure::folding_ranges`] of
+    /// [`get_document_structure`](Self::get_document_structure); call that
+    /// instead when the outline is needed too.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if document structure is not supported by the loaded library.
+    pub fn get_folding_ranges(&self, query: &str) -> Result<Vec<FoldingRange>, Error> {
+        Ok(self.get_document_structure(query)?.folding_ranges())
+    }
+
+    /// Format a KQL query
+    ///
+    /// Reformats the query with the Kusto.Language formatter, placing pipes
+    /// and indenting nested expressions according to `options`. Queries with
+    /// syntax errors are formatted as far as they parse.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use kql_language_tools::{FormatOptions, KqlValidator};
+    ///
+    /// fn main() -> Result<(), kql_language_tools::Error> {
+    ///     let validator = KqlValidator::new()?;
+    ///     let formatted = validator.format(
+    ///         "SecurityEvent | Where EventID == 4625 | take 10",
+    ///         &FormatOptions::default(),
+    ///     )?;
+    ///
+    ///     assert_eq!(formatted, "SecurityEvent\n| where EventID == 4625\n| take 10");
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if formatting is not supported by the loaded library.
+    pub fn format(&self, query: &str, options: &FormatOptions) -> Result<String, Error> {
+        let format_fn = self.native.lib.format.ok_or_else(|| Error::Internal {
+            message: "Formatting not supported by loaded library".to_string(),
+        })?;
+
+        let query_bytes = query.as_bytes();
+        let query_len = c_int::try_from(query_bytes.len()).map_err(|_| Error::Internal {
+            message: format!("Query too large: {} bytes", query_bytes.len()),
+        })?;
+        let options_json = serde_json::to_string(options)?;
+
+        let _scope = call_scope!(self.native, "format", query_len);
+        self.native.call_ffi_json(|output, output_len| {
+            // SAFETY: See validate_syntax for safety invariants.
+            #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+            unsafe {
+                format_fn(
+                    query_bytes.as_ptr(),
+                    query_len,
+                    options_json.as_ptr(),
+                    options_json.len() as c_int,
+                    output,
+                    output_len,
+                )
+            }
+        })
+    }
+
+    /// Find where the name under the cursor is declared
+    ///
+    /// Resolves `let` variables, locally-declared functions, and function or
+    /// lambda parameters to the span of their declared name. Returns `None`
+    /// when the cursor is not on such a name (e.g. on a table, a built-in
+    /// function, or whitespace). A cursor just past the end of a name counts
+    /// as being on it.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use kql_language_tools::KqlValidator;
+    ///
+    /// fn main() -> Result<(), kql_language_tools::Error> {
+    ///     let validator = KqlValidator::new()?;
+    ///     let query = "let threshold = 5;\nT | where Count > threshold";
+    ///
+    ///     let definition = validator.get_definition(query, query.len() - 2)?.unwrap();
+    ///     assert_eq!((definition.start, definition.end), (4, 13));
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if go-to-definition is not supported by the loaded library.
+    pub fn get_definition(
+        &self,
+        query: &str,
+        cursor_position: usize,
+    ) -> Result<Option<Definition>, Error> {
+        let definition_fn = self
+            .native
+            .lib
+            .get_definition
+            .ok_or_else(|| Error::Internal {
+                message: "Go-to-definition not supported by loaded library".to_string(),
+            })?;
+
+        let query_bytes = query.as_bytes();
+        let query_len = c_int::try_from(query_bytes.len()).map_err(|_| Error::Internal {
+            message: format!("Query too large: {} bytes", query_bytes.len()),
+        })?;
+        let cursor_position = positions::to_native(query, cursor_position);
+        let cursor_pos = c_int::try_from(cursor_position).map_err(|_| Error::Internal {
+            message: format!("Cursor position too large: {cursor_position}"),
+        })?;
+
+        let _scope = call_scope!(self.native, "get_definition", query_len);
+        self.native.call_ffi_offsets(query, |output, output_len| {
+            // SAFETY: See validate_syntax for safety invariants.
+            #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+            unsafe {
+                definition_fn(
+                    query_bytes.as_ptr(),
+                    query_len,
+                    cursor_pos,
+                    output,
+                    output_len,
+                )
+            }
+        })
+    }
+
+    /// Get completion suggestions at a cursor position
+    ///
+    /// Returns completion items (keywords, functions, tables, columns, etc.)
+    /// that are valid at the given cursor position.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - The KQL query string
+    /// * `cursor_position` - Cursor position (0-based character offset)
+    /// * `schema` - Optional schema for context-aware completions
+    ///
+    /// # Returns
+    ///
+    /// A `CompletionResult` containing completion items. Libraries without
+    /// `kql_get_completions` get [`static_completions`](crate::static_completions)
+    /// instead: keywords, operators and built-in functions, without schema
+    /// names.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the native call fails.
+    pub fn get_completions(
+        &self,
+        query: &str,
+        cursor_position: usize,
+        schema: Option<&Schema>,
+    ) -> Result<CompletionResult, Error> {
+        let cursor = self.offsets.decode(query, cursor_position);
+        let mut result = self.engine().get_completions(query, cursor, schema)?;
+        self.offsets.encode_completions(query, &mut result);
+        Ok(result)
+    }
+
+    /// Get completion suggestions with per-call filtering options
+    ///
+    /// Same as [`get_completions`](Self::get_completions), but the native
+    /// library filters items before serializing them. Libraries without
+    /// `kql_get_completions_with_options`, or without the
+    /// `completion_ranking` capability when kind filters, matching or
+    /// ranking are asked for, fall back to filtering in Rust, as do
+    /// validators running on [another engine](Self::with_engine).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if completion is not supported by the loaded library.
+    pub fn get_completions_with_options(
+        &self,
+        query: &str,
+        cursor_position: usize,
+        schema: Option<&Schema>,
+        options: &CompletionOptions,
+    ) -> Result<CompletionResult, Error> {
+        let native = self.native.lib.get_completions_with_options.filter(|_| {
+            self.engine.is_none()
+                && (!options.uses_ranking()
+                    || self
+                        .capabilities()
+                        .iter()
+                        .any(|c| c == "completion_ranking"))
+        });
+        let Some(completions_fn) = native else {
+            let mut result = self.get_completions(query, cursor_position, schema)?;
+            let cursor = self.offsets.decode(query, cursor_position);
+            let before_cursor = &query[..LineIndex::new(query).char_to_byte(cursor)];
+            options.apply(&mut result, before_cursor);
+            return Ok(result);
+        };
+
+        let query_bytes = query.as_bytes();
+        let schema_json = schema.map(Schema::to_native_json).transpose()?;
+        let options_json = serde_json::to_string(options)?;
+
+        let query_len = c_int::try_from(query_bytes.len()).map_err(|_| Error::Internal {
+            message: format!("Query too large: {} bytes", query_bytes.len()),
+        })?;
+        let cursor_position =
+            positions::to_native(query, self.offsets.decode(query, cursor_position));
+        let cursor_pos = c_int::try_from(cursor_position).map_err(|_| Error::Internal {
+            message: format!("Cursor position too large: {cursor_position}"),
+        })?;
+
+        let _scope = call_scope!(self.native, "get_completions_with_options", query_len);
+        let mut result: CompletionResult =
+            self.native.call_ffi_offsets(query, |output, output_len| {
+                // SAFETY: See validate_syntax for safety invariants.
+                // schema_ptr may be null (handled by FFI), schema_len is 0 in that case.
+                #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+                unsafe {
+                    let (schema_ptr, schema_len) = match &schema_json {
+                        Some(json) => (json.as_ptr(), json.len() as c_int),
+                        None => (std::ptr::null(), 0),
+                    };
+
+                    completions_fn(
+                        query_bytes.as_ptr(),
+                        query_len,
+                        cursor_pos,
+                        schema_ptr,
+                        schema_len,
+                        options_json.as_ptr(),
+                        options_json.len() as c_int,
+                        output,
+                        output_len,
+                    )
+                }
+            })?;
+        self.offsets.encode_completions(query, &mut result);
+        Ok(result)
+    }
+
+    /// Fill in the documentation and signature of a completion item
+    ///
+    /// Completion lists leave out documentation so they stay fast; resolve
+    /// only the item the user is looking at, as LSP's `completionItem/resolve`
+    /// does. Functions get their signatures in [`detail`] and a markdown
+    /// [`documentation`] with signatures and description; tables and columns
+    /// get their columns or type and the schema's description. Other items
+    /// come back unchanged. Pass the schema the item was completed against.
+    ///
+    /// [`detail`]: CompletionItem::detail
+    /// [`documentation`]: CompletionItem::documentation
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use kql_language_tools::KqlValidator;
+    ///
+    /// fn main() -> Result<(), kql_language_tools::Error> {
+    ///     let validator = KqlValidator::new()?;
+    ///     let query = "print ag";
+    ///     let completions = validator.get_completions(query, query.len(), None)?;
+    ///
+    ///     if let Some(item) = completions.items.iter().find(|i| i.label.starts_with("ago")) {
+    ///         let item = validator.resolve_completion(item, None)?;
+    ///         println!("{}", item.documentation.unwrap_or_default());
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if completion resolution is not supported by the
+    /// loaded library.
+    pub fn resolve_completion(
+        &self,
+        item: &CompletionItem,
+        schema: Option<&Schema>,
+    ) -> Result<CompletionItem, Error> {
+        let resolve_fn = self
+            .native
+            .lib
+            .resolve_completion
+            .ok_or_else(|| Error::Internal {
+                message: "Completion resolution not supported by loaded library".to_string(),
+            })?;
+
+        let item_json = serde_json::to_string(item)?;
+        let item_len = c_int::try_from(item_json.len()).map_err(|_| Error::Internal {
+            message: format!("Completion item too large: {} bytes", item_json.len()),
+        })?;
+        let schema_json = schema.map(Schema::to_native_json).transpose()?;
+        let options_json = serde_json::to_string(&self.native.options)?;
+
+        let _scope = call_scope!(self.native, "resolve_completion", item_len);
+        let resolved: Option<CompletionItem> =
+            self.native.call_ffi_json(|output, output_len| {
+                // SAFETY: See validate_syntax for safety invariants.
+                // schema_ptr may be null (handled by FFI), schema_len is 0 in that case.
+                #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+                unsafe {
+                    let (schema_ptr, schema_len) = match &schema_json {
+                        Some(json) => (json.as_ptr(), json.len() as c_int),
+                        None => (std::ptr::null(), 0),
+                    };
+
+                    resolve_fn(
+                        item_json.as_ptr(),
+                        item_len,
+                        schema_ptr,
+                        schema_len,
+                        options_json.as_ptr(),
+                        options_json.len() as c_int,
+                        output,
+                        output_len,
+                    )
+                }
+            })?;
+        Ok(resolved.unwrap_or_else(|| item.clone()))
+    }
+
+    /// Suggest a single continuation at the cursor for inline (ghost-text) display
+    ///
+    /// Combines the completion items at `cursor_position` with pipeline
+    /// heuristics: a partially typed word is completed with the best
+    /// matching item, and after a `|` (or at the end of a finished line) the
+    /// next operator is suggested, e.g. `summarize count() by ` after a
+    /// `where`. Returns `None` when there is no confident suggestion.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use kql_language_tools::KqlValidator;
+    ///
+    /// fn main() -> Result<(), kql_language_tools::Error> {
+    ///     let validator = KqlValidator::new()?;
+    ///     let query = "SecurityEvent | where EventID == 4625 | ";
+    ///
+    ///     if let Some(suggestion) = validator.suggest_next(query, query.len(), None)? {
+    ///         assert_eq!(suggestion.text, "summarize count() by ");
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if completion is not supported by the loaded library.
+    pub fn suggest_next(
+        &self,
+        query: &str,
+        cursor_position: usize,
+        schema: Option<&Schema>,
+    ) -> Result<Option<InlineSuggestion>, Error> {
+        let prefix_end = query
+            .char_indices()
+            .nth(self.offsets.decode(query, cursor_position))
+            .map_or(query.len(), |(i, _)| i);
+        let completions = self.get_completions(query, cursor_position, schema)?;
+        Ok(suggest::suggest(&query[..prefix_end], &completions))
+    }
+
+    /// Run a call with cancellation and an optional deadline
+    ///
+    /// The call runs on this validator's worker thread with a copy of the
+    /// validator. When `token` is cancelled or its deadline passes, this
+    /// returns [`Error::Cancelled`] or [`Error::TimedOut`] without waiting
+    /// for the call. Libraries that support native cancellation (see
+    /// [`supports_cancellation`](Self::supports_cancellation)) also stop the
+    /// analysis of validation calls.
+    ///
+    /// Other calls are not stopped: the native work runs to completion and
+    /// its result is discarded. The worker runs one call at a time, so until
+    /// then further calls fail with [`Error::Busy`] rather than starting
+    /// more threads. Clones of the validator have their own worker.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use kql_language_tools::{CancellationToken, Error, KqlValidator};
+    /// use std::time::Duration;
+    ///
+    /// fn main() -> Result<(), Error> {
+    ///     let validator = KqlValidator::new()?;
+    ///     let query = "SecurityEvent | where EventID == 4625".to_string();
+    ///
+    ///     let token = CancellationToken::with_timeout(Duration::from_secs(2));
+    ///     match validator.run_cancellable(&token, move |v| v.validate_syntax(&query)) {
+    ///         Ok(result) => println!("{} diagnostics", result.diagnostics.len()),
+    ///         Err(Error::TimedOut) => println!("gave up"),
+    ///         Err(err) => return Err(err),
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Cancelled`] or [`Error::TimedOut`] when the token
+    /// stops the call, [`Error::Busy`] while an earlier call still runs, or
+    /// the call's own error.
+    pub fn run_cancellable<T, F>(&self, token: &CancellationToken, call: F) -> Result<T, Error>
+    where
+        T: Send + 'static,
+        F: FnOnce(&KqlValidator) -> Result<T, Error> + Send + 'static,
+    {
+        if let Some(err) = token.error() {
+            return Err(err);
+        }
+
+        let lib = Arc::clone(&self.native.lib);
+        let native_id = lib.create_cancellation();
+        let mut validator = self.clone();
+        validator.native.options.cancellation_id = native_id;
+
+        let receiver = self
+            .native
+            .worker
+            .run(move || {
+                let result = call(&validator);
+                if let Some(id) = native_id {
+                    validator.native.lib.release_cancellation(id);
+                }
+                result
+            })
+            .map_err(|err| {
+                if let Some(id) = native_id {
+                    lib.release_cancellation(id);
+                }
+                err
+            })?;
+
+        loop {
+            match receiver.recv_timeout(WATCHDOG_INTERVAL) {
+                Ok(result) => return result,
+                Err(RecvTimeoutError::Timeout) => {
+                    if let Some(err) = token.error() {
+                        if let Some(id) = native_id {
+                            lib.cancel_cancellation(id);
+                        }
+                        return Err(err);
+                    }
+                }
+                Err(RecvTimeoutError::Disconnected) => {
+                    return Err(Error::Internal {
+                        message: "Worker thread panicked".to_string(),
+                    })
+                }
+            }
+        }
+    }
+}
+
+impl NativeEngine {
+    /// Get syntax classifications, with offsets in characters
+    fn classify(&self, query: &str) -> Result<ClassificationResult, Error> {
+        let classify_fn = self
+            .lib
+            .get_classifications
+            .ok_or_else(|| Error::Internal {
+                message: "Classification not supported by loaded library".to_string(),
+            })?;
+
+        let query_bytes = query.as_bytes();
+        let query_len = c_int::try_from(query_bytes.len()).map_err(|_| Error::Internal {
+            message: format!("Query too large: {} bytes", query_bytes.len()),
+        })?;
+
+        let _scope = call_scope!(self, "get_classifications", query_len);
+        self.call_ffi_offsets(query, |output, output_len| {
+            // SAFETY: See validate_syntax for safety invariants.
+            #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+            unsafe {
+                classify_fn(query_bytes.as_ptr(), query_len, output, output_len)
+            }
+        })
+    }
+
+    /// Get completions at a character cursor, with offsets in characters
+    fn complete(
+        &self,
+        query: &str,
+        cursor_position: usize,
+        schema: Option<&Schema>,
+    ) -> Result<CompletionResult, Error> {
+        let Some(completions_fn) = self.lib.get_completions else {
+            return Ok(completion::static_completions_at(query, cursor_position));
+        };
+
+        let query_bytes = query.as_bytes();
+        let schema_json = schema.map(Schema::to_native_json).transpose()?;
+
+        // Validate sizes fit in c_int
+        let query_len = c_int::try_from(query_bytes.len()).map_err(|_| Error::Internal {
+            message: format!("Query too large: {} bytes", query_bytes.len()),
+        })?;
+        let cursor_position = positions::to_native(query, cursor_position);
+        let cursor_pos = c_int::try_from(cursor_position).map_err(|_| Error::Internal {
+            message: format!("Cursor position too large: {cursor_position}"),
+        })?;
+
+        let _scope = call_scope!(self, "get_completions", query_len);
+        self.call_ffi_offsets(query, |output, output_len| {
+            // SAFETY: See validate_syntax for safety invariants.
+            // schema_ptr may be null (handled by FFI), schema_len is 0 in that case.
+            #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+            unsafe {
+                let (schema_ptr, schema_len) = match &schema_json {
+                    Some(json) => (json.as_ptr(), json.len() as c_int),
+                    None => (std::ptr::null(), 0),
+                };
+
+                completions_fn(
+                    query_bytes.as_ptr(),
+                    query_len,
+                    cursor_pos,
+                    schema_ptr,
+                    schema_len,
+                    output,
+                    output_len,
+                )
+            }
+        })
+    }
+
+    /// Validate syntax, with offsets in characters
+    fn check_syntax(&self, query: &str) -> Result<ValidationResult, Error> {
+        if !self.options.is_default() {
+            return self.validate_with_options(query, None, &self.options);
+        }
+
+        let query_bytes = query.as_bytes();
+
+        // Validate input size fits in c_int (2GB limit on 32-bit)
+        let query_len = c_int::try_from(query_bytes.len()).map_err(|_| Error::Internal {
+            message: format!(
+                "Query too large: {} bytes exceeds c_int max",
+                query_bytes.len()
+            ),
+        })?;
+
+        let _scope = call_scope!(self, "check_syntax", query_len);
+        self.call_ffi_with_retry(query, |output, output_len| {
+            // SAFETY: This FFI call is safe because:
+            // 1. query_bytes.as_ptr() points to valid UTF-8 data for the duration of the call
+            // 2. query_len accurately represents the byte length
+            // 3. output is a buffer of output_len bytes we own, or a result
+            //    slot the native library fills in native-allocated mode
+            // 4. The FFI function only reads from query and writes to output
+            #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+            unsafe {
+                (self.lib.validate_syntax)(query_bytes.as_ptr(), query_len, output, output_len)
+            }
+        })
+    }
+
+    /// Validate against a schema with name suggestions, with offsets in characters
+    fn check_with_schema(&self, query: &str, schema: &Schema) -> Result<ValidationResult, Error> {
+        let mut result = if self.options.is_default() {
+            self.validate_against_schema(query, schema)?
+        } else {
+            self.validate_with_options(query, Some(schema), &self.options)?
+        };
+        spelling::suggest_names(query, schema, &mut result);
+        Ok(result)
+    }
+
+    /// Validate with the original schema export (no options)
+    fn validate_against_schema(
+        &self,
+        query: &str,
+        schema: &Schema,
+    ) -> Result<ValidationResult, Error> {
+        let validate_fn = self
+            .lib
+            .validate_with_schema
+            .ok_or_else(|| Error::Internal {
+                message: "Schema validation not supported by loaded library".to_string(),
+            })?;
+
+        let query_bytes = query.as_bytes();
+        let schema_json = schema.to_native_json()?;
+        let schema_bytes = schema_json.as_bytes();
+
+        // Validate input sizes fit in c_int
+        let query_len = c_int::try_from(query_bytes.len()).map_err(|_| Error::Internal {
+            message: format!("Query too large: {} bytes", query_bytes.len()),
+        })?;
+        let schema_len = c_int::try_from(schema_bytes.len()).map_err(|_| Error::Internal {
+            message: format!("Schema too large: {} bytes", schema_bytes.len()),
+        })?;
+
+        let _scope = call_scope!(self, "validate_against_schema", query_len, schema_len);
+        self.call_ffi_with_retry(query, |output, output_len| {
+            // SAFETY: See validate_syntax for safety invariants.
+            // Additionally, schema_bytes is valid UTF-8 JSON for the call duration.
+            #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+            unsafe {
+                validate_fn(
+                    query_bytes.as_ptr(),
+                    query_len,
+                    schema_bytes.as_ptr(),
+                    schema_len,
+                    output,
+                    output_len,
+                )
+            }
+        })
+    }
+
+    /// Validate a KQL query, passing the validator's options to the native side
+    fn validate_with_options(
+        &self,
+        query: &str,
+        schema: Option<&Schema>,
+        options: &ValidationOptions,
+    ) -> Result<ValidationResult, Error> {
+        let validate_fn = self
+            .lib
+            .validate_with_options
+            .ok_or_else(|| Error::Internal {
+                message: "Validation options not supported by loaded library (missing kql_validate_with_options)"
+                    .to_string(),
+            })?;
+
+        let query_bytes = query.as_bytes();
+        let schema_json = schema.map(Schema::to_native_json).transpose()?;
+        let options_json = serde_json::to_string(options)?;
+
+        let query_len = c_int::try_from(query_bytes.len()).map_err(|_| Error::Internal {
+            message: format!("Query too large: {} bytes", query_bytes.len()),
+        })?;
+        let options_len = c_int::try_from(options_json.len()).map_err(|_| Error::Internal {
+            message: format!("Options too large: {} bytes", options_json.len()),
+        })?;
+
+        let _scope = call_scope!(
+            self,
+            "validate_with_options",
+            query_len,
+            schema_len = schema_json.as_ref().map_or(0, String::len),
+        );
+        self.call_ffi_with_retry(query, |output, output_len| {
+            // SAFETY: See validate_syntax for safety invariants.
+            // schema_ptr may be null (handled by FFI), schema_len is 0 in that case.
+            #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+            unsafe {
+                let (schema_ptr, schema_len) = match &schema_json {
+                    Some(json) => (json.as_ptr(), json.len() as c_int),
+                    None => (std::ptr::null(), 0),
+                };
+
+                validate_fn(
+                    query_bytes.as_ptr(),
+                    query_len,
+                    schema_ptr,
+                    schema_len,
+                    options_json.as_ptr(),
+                    options_len,
+                    output,
+                    output_len,
+                )
+            }
+        })
+    }
+
+    /// Call an FFI function validating `text`, with automatic buffer retry on overflow
+    fn call_ffi_with_retry<F>(&self, text: &str, ffi_call: F) -> Result<ValidationResult, Error>
+    where
+        F: FnMut(*mut u8, c_int) -> c_int,
+    {
+        // Empty result means valid query
+        let Some(mut validation_result) = self.call_ffi_output::<ValidationResult, _>(ffi_call)?
+        else {
+            return Ok(ValidationResult::valid());
+        };
+        positions::from_native(text, &mut validation_result);
+        self.diagnostics.apply(&mut validation_result);
+        Ok(validation_result)
+    }
+
+    /// Call an FFI function whose result holds offsets into `text`
+    ///
+    /// The native UTF-16 offsets are converted into character offsets.
+    fn call_ffi_offsets<T, F>(&self, text: &str, ffi_call: F) -> Result<T, Error>
+    where
+        T: for<'de> serde::Deserialize<'de> + Default + NativeOffsets,
+        F: FnMut(*mut u8, c_int) -> c_int,
+    {
+        let mut result: T = self.call_ffi_json(ffi_call)?;
+        positions::from_native(text, &mut result);
+        Ok(result)
+    }
+
+    /// Call an FFI function and deserialize its result to a generic type
+    fn call_ffi_json<T, F>(&self, ffi_call: F) -> Result<T, Error>
+    where
+        T: for<'de> serde::Deserialize<'de> + Default,
+        F: FnMut(*mut u8, c_int) -> c_int,
+    {
+        Ok(self.call_ffi_output(ffi_call)?.unwrap_or_default())
+    }
+
+    /// Call an FFI function and deserialize its output (`None` for a `0` return)
+    ///
+    /// With [`with_native_results`](KqlValidator::with_native_results) the native
+    /// library allocates the output. Otherwise the output goes into this
+    /// thread's reusable buffer (see [`buffer`](crate::buffer)), which is
+    /// grown and the call retried when the output does not fit.
+    fn call_ffi_output<T, F>(&self, ffi_call: F) -> Result<Option<T>, Error>
+    where
+        T: for<'de> serde::Deserialize<'de>,
+        F: FnMut(*mut u8, c_int) -> c_int,
+    {
+        let format = self.result_format();
+        let mut stats = CallStats::default();
+        let result = self.read_ffi_output(ffi_call, &format, &mut stats);
+        stats.failed = result.is_err();
+        stats.finish();
+        result
+    }
+
+    /// Run an FFI call and decode its output, collecting call stats
+    fn read_ffi_output<T, F>(
+        &self,
+        mut ffi_call: F,
+        format: &ResultFormat,
+        stats: &mut CallStats,
+    ) -> Result<Option<T>, Error>
+    where
+        T: for<'de> serde::Deserialize<'de>,
+        F: FnMut(*mut u8, c_int) -> c_int,
+    {
+        if self.native_results && self.lib.supports_native_results() {
+            let (result, output) = stats.time(|| self.lib.call_allocated(ffi_call));
+            stats.output_bytes = output.as_bytes().len();
+            self.check_native_code(result, output.as_bytes())?;
+            return format.decode(output.as_bytes());
+        }
+
+        with_output_buffer(|buffer| {
+            let mut call = |buffer: &mut [u8], stats: &mut CallStats| {
+                // Clear any envelope a previous call left in the reused buffer
+                buffer[0] = 0;
+                stats.time(|| ffi_call(buffer.as_mut_ptr(), output_len(buffer)))
+            };
+            let mut result = call(buffer, stats);
+
+            // Handle buffer too small - retry with a larger buffer
+            while return_codes::is_buffer_too_small(result) {
+                match self.retry_buffer_size(buffer.len()) {
+                    Ok(size) => buffer.resize(size, 0),
+                    // Past the cap, read the pending result in buffer-sized chunks
+                    Err(Error::BufferTooSmall { needed, available })
+                        if needed > available && self.lib.supports_result_chunks() =>
+                    {
+                        let output = stats.time(|| self.read_result_chunks(buffer))?;
+                        stats.output_bytes = output.len();
+                        return format.decode(&output);
+                    }
+                    Err(err) => return Err(err),
+                }
+                stats.buffer_resizes += 1;
+                result = call(buffer, stats);
+            }
+            self.check_native_code(result, buffer)?;
+
+            let len = usize::try_from(result).unwrap_or_default();
+            stats.output_bytes = len;
+            format.decode(&buffer[..len])
+        })
+    }
+
+    /// Switch this thread to CBOR results for one call, if enabled and supported
+    #[cfg(feature = "cbor")]
+    fn result_format(&self) -> ResultFormat {
+        if !self.binary_results {
+            return ResultFormat::Json;
+        }
+        self.lib
+            .use_result_format(result_formats::CBOR)
+            .map_or(ResultFormat::Json, |guard| ResultFormat::Cbor {
+                _guard: guard,
+            })
+    }
+
+    /// Call results are always JSON without the `cbor` feature
+    #[cfg(not(feature = "cbor"))]
+    #[allow(clippy::unused_self)]
+    fn result_format(&self) -> ResultFormat {
+        ResultFormat::Json
+    }
+
+    /// Read the result of the last too-small call on this thread in chunks
+    fn read_result_chunks(&self, buffer: &mut [u8]) -> Result<Vec<u8>, Error> {
+        let read_fn = self.lib.read_result_chunk.ok_or_else(|| Error::Internal {
+            message: "Chunked results not supported by loaded library".to_string(),
+        })?;
+
+        let mut output = Vec::new();
+        loop {
+            let offset = c_int::try_from(output.len()).map_err(|_| Error::Internal {
+                message: format!("Result too large: over {} bytes", output.len()),
+            })?;
+            // SAFETY: buffer is a valid mutable slice we own and output_len
+            // does not exceed its length.
+            buffer[0] = 0;
+            let read = unsafe { read_fn(offset, buffer.as_mut_ptr(), output_len(buffer)) };
+            self.check_native_code(read, buffer)?;
+            if read == 0 {
+                return Ok(output);
+            }
+            output.extend_from_slice(&buffer[..usize::try_from(read).unwrap_or_default()]);
+        }
+    }
+
+    /// Turn a negative native return code into an error
+    ///
+    /// The error comes from the envelope the call wrote to `output`, falling
+    /// back to `kql_get_last_error` for libraries that do not write one.
+    fn check_native_code(&self, result: c_int, output: &[u8]) -> Result<(), Error> {
+        if return_codes::is_success(result) {
+            Ok(())
+        } else if let Some(err) = Error::from_error_envelope(output) {
+            Err(err)
+        } else {
+            let error_msg = self.get_last_error().unwrap_or_default();
+            Err(Error::from_native_code(result, &error_msg))
+        }
+    }
+
+    /// Size to retry a call with after it reported `available` bytes as too small
+    ///
+    /// Libraries exporting `kql_get_last_required_size` report the exact
+    /// size, so the retry allocates once and succeeds. Older libraries are
+    /// retried with double the buffer, up to the validator's
+    /// [maximum buffer size](KqlValidator::with_max_buffer_size).
+    fn retry_buffer_size(&self, available: usize) -> Result<usize, Error> {
+        let needed = match self.lib.last_required_size() {
+            // A retry that would not grow the buffer cannot succeed
+            Some(needed) if needed <= available => {
+                return Err(Error::BufferTooSmall { needed, available });
+            }
+            Some(needed) => needed,
+            None => available * 2,
+        };
+        if needed > self.max_buffer_size {
+            return Err(Error::BufferTooSmall {
+                needed,
+                available: self.max_buffer_size,
+            });
+        }
+        Ok(needed)
+    }
+
+    /// Get the last error message from the native library
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_possible_wrap,
+        clippy::cast_sign_loss
+    )]
+    fn get_last_error(&self) -> Option<String> {
+        let mut buffer = vec![0u8; 1024];
+        let result =
+            unsafe { (self.lib.get_last_error)(buffer.as_mut_ptr(), buffer.len() as c_int) };
+
+        if return_codes::is_success(result) && result > 0 {
+            let len = result as usize;
+            String::from_utf8(buffer[..len].to_vec()).ok()
+        } else {
+            None
+        }
+    }
+}
+
+impl KqlEngine for NativeEngine {
+    fn validate_syntax(&self, query: &str) -> Result<ValidationResult, Error> {
+        self.check_syntax(query)
+    }
+
+    fn validate_with_schema(
+        &self,
+        query: &str,
+        schema: &Schema,
+    ) -> Result<ValidationResult, Error> {
+        self.check_with_schema(query, schema)
+    }
+
+    fn get_completions(
+        &self,
+        query: &str,
+        cursor_position: usize,
+        schema: Option<&Schema>,
+    ) -> Result<CompletionResult, Error> {
+        self.complete(query, cursor_position, schema)
+    }
+
+    fn get_classifications(&self, query: &str) -> Result<ClassificationResult, Error> {
+        self.classify(query)
+    }
+}
+
+/// Pass spans classified in one call on to `on_batch` in sorted batches
+fn pass_in_batches(mut spans: Vec<ClassifiedSpan>, on_batch: &mut dyn FnMut(&[ClassifiedSpan])) {
+    spans.sort_by_key(|span| span.start);
+    for batch in spans.chunks(CLASSIFY_BATCH_SPANS) {
+        on_batch(batch);
+    }
+}
+
+/// State of a [`KqlValidator::classify_chunks`] call, shared with
+/// [`receive_batch`] through the native context pointer
+struct BatchStream<'a> {
+    /// Set when the query's native offsets need converting
+    index: Option<LineIndex>,
+    on_batch: &'a mut dyn FnMut(&[ClassifiedSpan]),
+    /// Why the stream was stopped early
+    error: Option<Error>,
+    /// Panic from `on_batch`, resumed once the native call has returned
+    panic: Option<Box<dyn Any + Send>>,
+}
+
+impl BatchStream<'_> {
+    /// Decode one native batch and pass it on
+    fn receive(&mut self, json: &[u8]) -> Result<(), Error> {
+        let mut batch: ClassificationResult = serde_json::from_slice(json)?;
+        if let Some(index) = &self.index {
+            batch.utf16_to_chars(index);
+        }
+        batch.spans.sort_by_key(|span| span.start);
+        if !batch.spans.is_empty() {
+            (self.on_batch)(&batch.spans);
+        }
+        Ok(())
+    }
+}
+
+/// Receives classification batches from `kql_classify_chunks`
+extern "C" fn receive_batch(context: *mut c_void, batch: *const u8, len: c_int) -> c_int {
+    let Ok(len) = usize::try_from(len) else {
+        return 1;
+    };
+    if context.is_null() || batch.is_null() {
+        return 1;
+    }
+    // SAFETY: The context is the BatchStream classify_chunks passed in,
+    // alive and otherwise unused for the duration of the native call, and
+    // the native library passes `len` bytes that stay valid for this call.
+    let (stream, json) = unsafe {
+        (
+            &mut *context.cast::<BatchStream<'_>>(),
+            std::slice::from_raw_parts(batch, len),
+        )
+    };
+    // A panicking `on_batch` must not unwind into the native library
+    match panic::catch_unwind(AssertUnwindSafe(|| stream.receive(json))) {
+        Ok(Ok(())) => 0,
+        Ok(Err(e)) => {
+            stream.error = Some(e);
+            1
+        }
+        Err(payload) => {
+            stream.panic = Some(payload);
+            1
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codes::KustoDiagnosticCode;
+    use crate::engine::FeatureKind;
+    use crate::format::OperatorCase;
+    use crate::joins::JoinOperator;
+    use crate::kql_type::KqlType;
+    use crate::types::{DiagnosticCategory, DiagnosticSeverity};
+
+    // These tests require the native library to be available
+    // They are ignored by default and can be run with:
+    // cargo test --features test-native -- --ignored
+
+    #[test]
+    #[ignore = "requires native library"]
+    fn test_validate_syntax_valid() {
+        let validator = KqlValidator::new().expect("Failed to create validator");
+        let result = validator
+            .validate_syntax("T | take 10")
+            .expect("Validation failed");
+        assert!(result.is_valid());
+    }
+
+    #[test]
+    fn test_from_path_missing_library() {
+        let dir = std::env::temp_dir().join("kql-no-library-here");
+        match KqlValidator::from_path(&dir) {
+            Err(Error::LibraryNotFound { searched_paths }) => {
+                assert_eq!(searched_paths, [dir.clone(), dir.join(loader::LIB_NAME)]);
+            }
+            _ => panic!("expected LibraryNotFound"),
+        }
+    }
+
+    #[test]
+    #[ignore = "requires native library"]
+    fn test_from_path_shares_instances() {
+        let global = KqlValidator::new().expect("Failed to create validator");
+        let path = global.native.lib.path().to_path_buf();
+        let local = KqlValidator::from_path(&path).expect("Failed to load from path");
+        assert!(Arc::ptr_eq(&global.native.lib, &local.native.lib));
+        assert!(local.validate_syntax("T | take 10").unwrap().is_valid());
+    }
+
+    #[test]
+    #[ignore = "requires native library"]
+    fn test_unload_and_reload() {
+        let before = KqlValidator::new().expect("Failed to create validator");
+        assert!(loader::unload());
+        assert!(!loader::is_loaded());
+
+        // The old validator keeps working, and a new one revives its library
+        assert!(before.validate_syntax("T | take 10").unwrap().is_valid());
+        let after = KqlValidator::new().expect("Failed to reload validator");
+        assert!(Arc::ptr_eq(&before.native.lib, &after.native.lib));
+        assert!(after.validate_syntax("T | take 10").unwrap().is_valid());
+    }
+
+    #[test]
+    #[ignore = "requires native library"]
+    fn test_validate_syntax_invalid() {
+        let validator = KqlValidator::new().expect("Failed to create validator");
+        let result = validator
+            .validate_syntax("T | invalid_operator")
+            .expect("Validation failed");
+        assert!(!result.is_valid());
+        assert!(result.has_errors());
+    }
+
+    #[test]
+    #[ignore = "requires native library"]
+    fn test_validate_with_schema() {
+        let validator = KqlValidator::new().expect("Failed to create validator");
+
+        let schema = Schema::new().table(
+            crate::schema::Table::new("SecurityEvent")
+                .with_column("TimeGenerated", "datetime")
+                .with_column("Account", "string"),
+        );
+
+        let result = validator
+            .validate_with_schema("SecurityEvent | project TimeGenerated, Account", &schema)
+            .expect("Validation failed");
+        assert!(result.is_valid());
+    }
+
+    #[test]
+    #[ignore = "requires native library"]
+    fn test_validate_with_schema_unknown_column() {
+        let validator = KqlValidator::new().expect("Failed to create validator");
+
+        let schema = Schema::new().table(
+            crate::schema::Table::new("SecurityEvent").with_column("TimeGenerated", "datetime"),
+        );
+
+        let result = validator
+            .validate_with_schema("SecurityEvent | project UnknownColumn", &schema)
+            .expect("Validation failed");
+        assert!(!result.is_valid());
+    }
+
+    #[test]
+    #[ignore = "requires native library"]
+    fn test_tabular_functions_as_sources() {
+        use crate::schema::{Function, Table};
+
+        let validator = KqlValidator::new().expect("Failed to create validator");
+        let schema = Schema::new()
+            .table(Table::new("Events").with_column("Level", "int"))
+            .function(
+                Function::tabular("Errors", "(*)")
+                    .param("min", "int")
+                    .body("Events | where Level >= min"),
+            )
+            .function(Function::tabular("Counts", "(Level:int, Count:long)"));
+
+        for query in ["Errors(3) | project Level", "Counts() | where Count > 10"] {
+            let result = validator
+                .validate_with_schema(query, &schema)
+                .expect("Validation failed");
+            assert!(result.is_valid(), "{query}: {:?}", result.diagnostics());
+        }
+        let result = validator
+            .validate_with_schema("Counts() | project Missing", &schema)
+            .expect("Validation failed");
+        assert!(!result.is_valid());
+    }
+
+    #[cfg(feature = "asim")]
+    #[test]
+    #[ignore = "requires native library"]
+    fn test_asim_parsers() {
+        let validator = KqlValidator::new().expect("Failed to create validator");
+        let schema = crate::asim::schema();
+        for query in [
+            "imDns(starttime=ago(1h), responsecodename='NXDOMAIN') | summarize count() by SrcIpAddr",
+            "_Im_Authentication() | where EventResult == 'Failure' | project TargetUsername, SrcIpAddr",
+            "ASimNetworkSessionLogs | where DstPortNumber == 3389",
+        ] {
+            let result = validator
+                .validate_with_schema(query, &schema)
+                .expect("Validation failed");
+            assert!(result.is_valid(), "{query}: {:?}", result.diagnostics());
+        }
+    }
+
+    #[test]
+    #[ignore = "requires native library"]
+    fn test_validate_schema_bodies() {
+        use crate::schema::{Function, Table};
+
+        let validator = KqlValidator::new().expect("Failed to create validator");
+        let schema = Schema::new()
+            .table(Table::new("Events").with_column("Level", "int"))
+            .function(Function::tabular("Errors", "(*)").body("{ Events | where Level > 2 }"))
+            .function(Function::tabular("Broken", "(*)").body("{ Events | where }"))
+            .function(Function::new("Empty", "long").body("{}"));
+
+        let issues = validator
+            .validate_schema(&schema)
+            .expect("Schema validation failed");
+        let functions: Vec<_> = issues
+            .iter()
+            .map(|issue| match issue {
+                SchemaIssue::InvalidFunctionBody { function, .. } => function.as_str(),
+                other => panic!("unexpected issue: {other}"),
+            })
+            .collect();
+        assert_eq!(functions, ["Empty", "Broken"]);
+    }
+
+    #[test]
+    #[ignore = "requires native library"]
+    fn test_log_analytics_scoped_table() {
+        let validator = KqlValidator::new()
+            .expect("Failed to create validator")
+            .with_dialect(Dialect::LogAnalytics);
+
+        let schema = Schema::new().scope(
+            crate::schema::ResourceScope::workspace("contoso-prod").table(
+                crate::schema::Table::new("SigninLogs").with_column("UserPrincipalName", "string"),
+            ),
+        );
+
+        let result = validator
+            .validate_with_schema(
+                "workspace('contoso-prod').SigninLogs | project UserPrincipalName",
+                &schema,
+            )
+            .expect("Validation failed");
+        assert!(result.is_valid(), "{:?}", result.diagnostics);
+
+        let result = validator
+            .validate_with_schema("workspace('contoso-prod').NoSuchTable", &schema)
+            .expect("Validation failed");
+        assert!(!result.is_valid());
+
+        // resource() requires an Azure resource ID
+        let result = validator
+            .validate_syntax("resource('not-an-id').AzureActivity")
+            .expect("Validation failed");
+        assert!(!result.is_valid());
+    }
+
+    #[test]
+    #[ignore = "requires native library"]
+    #[cfg(feature = "sentinel")]
+    fn test_sentinel_functions() {
+        use crate::sentinel::{self, Watchlist};
+
+        let validator = KqlValidator::new().expect("Failed to create validator");
+        let mut schema = sentinel::schema_with_watchlists(&[
+            Watchlist::new("HighValueAssets").with_column("Hostname", "string")
+        ]);
+        schema.add_table(Table::new("SecurityEvent").with_column("Computer", "string"));
+
+        let result = validator
+            .validate_with_schema(
+                "SecurityEvent | where Computer in ((_GetWatchlist('HighValueAssets') | project Hostname)) | extend UserType = _ASIM_GetUserType(Computer, '')",
+                &schema,
+            )
+            .expect("Validation failed");
+        assert!(result.is_valid(), "{:?}", result.diagnostics);
+    }
+
+    #[test]
+    #[ignore = "requires native library"]
+    fn test_scopes_without_dialect() {
+        let validator = KqlValidator::new().expect("Failed to create validator");
+        let schema = Schema::new()
+            .table(Table::new("SigninLogs").with_column("UserPrincipalName", "string"))
+            .scope(
+                crate::schema::ResourceScope::workspace("contoso-prod")
+                    .table(Table::new("SigninLogs").with_column("UserPrincipalName", "string")),
+            )
+            .scope(
+                crate::schema::ResourceScope::app("contoso-web")
+                    .table(Table::new("requests").with_column("url", "string")),
+            );
+
+        let result = validator
+            .validate_with_schema(
+                "union SigninLogs, workspace('contoso-prod').SigninLogs | project UserPrincipalName",
+                &schema,
+            )
+            .expect("Validation failed");
+        assert!(result.is_valid(), "{:?}", result.diagnostics);
+
+        let query = "app('contoso-web').";
+        let result = validator
+            .get_completions(query, query.len(), Some(&schema))
+            .expect("Completion failed");
+        assert!(result.items.iter().any(|item| item.label == "requests"));
+    }
+
+    #[test]
+    #[ignore = "requires native library"]
+    fn test_log_analytics_adx_proxy() {
+        let validator = KqlValidator::new()
+            .expect("Failed to create validator")
+            .with_dialect(Dialect::LogAnalytics);
+
+        let schema =
+            Schema::new()
+                .scope(crate::schema::ResourceScope::adx("help/Samples").table(
+                    crate::schema::Table::new("StormEvents").with_column("State", "string"),
+                ));
+
+        // Fully-qualified URL resolves against the short-name scope
+        let result = validator
+            .validate_with_schema(
+                "adx('https://help.kusto.windows.net/Samples').StormEvents | where State == 'TEXAS'",
+                &schema,
+            )
+            .expect("Validation failed");
+        assert!(result.is_valid(), "{:?}", result.diagnostics);
+
+        // Unknown remote database degrades to warnings
+        let result = validator
+            .validate_with_schema("adx('other/Db').Anything | where Foo == 1", &schema)
+            .expect("Validation failed");
+        assert!(result.is_valid(), "{:?}", result.diagnostics);
+        assert!(result.has_warnings());
+    }
+
+    #[test]
+    #[ignore = "requires native library"]
+    fn test_data_collection_rule_dialect() {
+        let validator = KqlValidator::new()
+            .expect("Failed to create validator")
+            .with_dialect(Dialect::DataCollectionRule);
+
+        let schema = Schema::new().table(
+            crate::schema::Table::new("source")
+                .with_column("TimeGenerated", "datetime")
+                .with_column("RawData", "string"),
+        );
+
+        let result = validator
+            .validate_with_schema(
+                "source | extend Level = extract('level=(\\w+)', 1, RawData) | project-away RawData",
+                &schema,
+            )
+            .expect("Validation failed");
+        assert!(result.is_valid(), "{:?}", result.diagnostics);
+
+        // summarize is not supported in transformations
+        let result = validator
+            .validate_with_schema(
+                "source | summarize count() by bin(TimeGenerated, 1h)",
+                &schema,
+            )
+            .expect("Validation failed");
+        assert!(!result.is_valid());
+    }
+
+    #[test]
+    #[ignore = "requires native library"]
+    #[cfg(feature = "resource-graph")]
+    fn test_resource_graph_dialect() {
+        let validator = KqlValidator::new()
+            .expect("Failed to create validator")
+            .with_dialect(Dialect::ResourceGraph);
+        let schema = crate::resource_graph::schema();
+
+        let result = validator
+            .validate_with_schema(
+                "resources | where type =~ 'microsoft.compute/virtualmachines' | project name, location",
+                &schema,
+            )
+            .expect("Validation failed");
+        assert!(result.is_valid(), "{:?}", result.diagnostics);
+
+        let result = validator
+            .validate_with_schema(
+                "Resources | join kind=leftanti (ResourceContainers) on subscriptionId",
+                &schema,
+            )
+            .expect("Validation failed");
+        assert!(result
+            .diagnostics
+            .iter()
+            .any(|d| d.code.as_deref() == Some("ARG002")));
+
+        // Plugins are not supported
+        let result = validator
+            .validate_with_schema("resources | evaluate bag_unpack(tags)", &schema)
+            .expect("Validation failed");
+        assert!(!result.is_valid());
+    }
+
+    #[test]
+    #[ignore = "requires native library"]
+    fn test_feature_profile() {
+        let validator = KqlValidator::new()
+            .expect("Failed to create validator")
+            .with_profile(
+                FeatureProfile::new("dashboard")
+                    .allow_operators(["where", "summarize", "render"])
+                    .max_pipeline_depth(2),
+            );
+
+        let result = validator
+            .validate_syntax("T | where x > 1 | summarize count()")
+            .expect("Validation failed");
+        assert!(result.is_valid(), "{:?}", result.diagnostics);
+
+        let result = validator
+            .validate_syntax("T | join (U) on Id")
+            .expect("Validation failed");
+        assert!(!result.is_valid());
+
+        let result = validator
+            .validate_syntax("T | where x > 1 | where y > 1 | summarize count()")
+            .expect("Validation failed");
+        assert!(!result.is_valid());
+    }
+
+    #[test]
+    #[ignore = "requires native library"]
+    fn test_engine_feature_level_pinning() {
+        let validator = KqlValidator::new()
+            .expect("Failed to create validator")
+            .with_engine_feature_level(EngineFeatureLevel::new(2022, 6));
+
+        let result = validator
+            .validate_syntax("print has_ipv4('10.0.0.1 connected', '10.0.0.1')")
+            .expect("Validation failed");
+        assert!(result.is_valid(), "{:?}", result.diagnostics);
+
+        let result = validator
+            .validate_syntax("print bag_zip(dynamic(['a']), dynamic([1]))")
+            .expect("Validation failed");
+        assert!(result.is_valid(), "{:?}", result.diagnostics);
+        assert!(result
+            .warnings()
+            .any(|d| d.code.as_deref() == Some("EL001")));
+
+        let validator = validator.with_feature_availability(FeatureAvailability::new(
+            "bag_zip",
+            FeatureKind::Function,
+            EngineFeatureLevel::new(2023, 2),
+        ));
+        let result = validator
+            .validate_syntax("print bag_zip(dynamic(['a']), dynamic([1]))")
+            .expect("Validation failed");
+        assert!(!result.is_valid());
+        assert!(result.errors().any(|d| d.code.as_deref() == Some("EL001")));
+    }
+
+    #[test]
+    #[ignore = "requires native library"]
+    fn test_service_limits() {
+        let validator = KqlValidator::new()
+            .expect("Failed to create validator")
+            .with_service_limits(ServiceLimits::default().max_summarize_keys(2));
+
+        let result = validator
+            .validate_syntax("T | summarize count() by a, b, c")
+            .expect("Validation failed");
+        let limit = result
+            .errors()
+            .find(|d| d.code.as_deref() == Some("SL003"))
+            .expect("Expected summarize key limit violation");
+        assert_eq!(limit.category, Some(DiagnosticCategory::ServiceLimit));
+    }
+
+    #[test]
+    #[ignore = "requires native library"]
+    fn test_case_sensitivity_advisory() {
+        let validator = KqlValidator::new()
+            .expect("Failed to create validator")
+            .with_case_sensitivity_advisory(true);
+
+        let query = "T | where Id =~ '3f2504e0-4f89-11d3-9a0c-0305e82c3301'";
+        let result = validator.validate_syntax(query).expect("Validation failed");
+        let hint = result
+            .diagnostics()
+            .iter()
+            .find(|d| d.code.as_deref() == Some("CS001"))
+            .expect("Expected case-sensitivity hint");
+        assert_eq!(hint.severity, DiagnosticSeverity::Hint);
+
+        let edit = &hint.fixes[0].edits[0];
+        assert_eq!(&query[edit.start..edit.end], "=~");
+        assert_eq!(edit.new_text, "==");
+    }
+
+    #[test]
+    #[ignore = "requires native library"]
+    fn test_get_completions_with_options() {
+        let validator = KqlValidator::new().expect("Failed to create validator");
+        let options = CompletionOptions::default()
+            .include_punctuation(false)
+            .max_items(5);
+
+        let result = validator
+            .get_completions_with_options("T | ", 4, None, &options)
+            .expect("Completion failed");
+        assert!(result.items.len() <= 5);
+        assert!(result
+            .items
+            .iter()
+            .all(|i| i.kind != crate::completion::CompletionKind::Punctuation));
+
+        let schema = Schema::new().table(
+            Table::new("SigninLogs")
+                .with_column("TimeGenerated", "datetime")
+                .with_column("UserPrincipalName", "string"),
+        );
+        let options = CompletionOptions::default()
+            .matching(crate::completion::CompletionMatch::Fuzzy)
+            .ranked(true)
+            .max_items(3);
+        let query = "SigninLogs | project tgen";
+        let result = validator
+            .get_completions_with_options(query, query.len(), Some(&schema), &options)
+            .expect("Completion failed");
+        assert_eq!(result.items[0].label, "TimeGenerated");
+        assert!(result.items.len() <= 3);
+
+        let options = CompletionOptions::default()
+            .only_kinds([crate::completion::CompletionKind::Column])
+            .ranked(true);
+        let query = "SigninLogs | project ";
+        let result = validator
+            .get_completions_with_options(query, query.len(), Some(&schema), &options)
+            .expect("Completion failed");
+        let labels: Vec<_> = result.items.iter().map(|i| i.label.as_str()).collect();
+        assert_eq!(labels, ["TimeGenerated", "UserPrincipalName"]);
+    }
+
+    #[test]
+    #[ignore = "requires native library"]
+    fn test_get_document_structure() {
+        let validator = KqlValidator::new().expect("Failed to create validator");
+        let query = "// Helpers\n// for tests\nlet f = (x: long) {\n    x + 1\n};\nT | take 1";
+
+        let structure = validator
+            .get_document_structure(query)
+            .expect("Structure failed");
+        let statements: Vec<_> = structure.statements.iter().map(|n| n.kind).collect();
+        assert_eq!(
+            statements,
+            [
+                crate::StructureKind::CommentBlock,
+                crate::StructureKind::LetStatement,
+                crate::StructureKind::QueryStatement,
+            ]
+        );
+        let names: Vec<_> = structure
+            .declarations()
+            .filter_map(|n| n.name.as_deref())
+            .collect();
+        assert_eq!(names, ["f", "x"]);
+        assert!(structure
+            .foldable()
+            .any(|n| n.kind == crate::StructureKind::FunctionBody));
+    }
+
+    #[test]
+    #[ignore = "requires native library"]
+    fn test_did_you_mean() {
+        let validator = KqlValidator::new().expect("Failed to create validator");
+        let schema = Schema::new().table(
+            crate::schema::Table::new("SigninLogs").with_column("UserPrincipalName", "string"),
+        );
+
+        let result = validator
+            .validate_with_schema("SignInLogs | project UserPrincipleName", &schema)
+            .expect("Validation failed");
+        let titles: Vec<_> = result.fixes().map(|f| f.title.as_str()).collect();
+        assert!(titles.contains(&"Did you mean `SigninLogs`?"));
+
+        // Re-validated against the schema the fixes came from
+        let fixes: Vec<_> = result.fixes().cloned().collect();
+        let outcome = validator
+            .apply_fixes(
+                "SignInLogs | project UserPrincipleName",
+                &fixes,
+                Some(&schema),
+            )
+            .expect("Applying fixes failed");
+        assert_eq!(outcome.query, "SigninLogs | project UserPrincipalName");
+        assert!(outcome.validation.is_valid());
+    }
+
+    #[test]
+    #[ignore = "requires native library"]
+    fn test_related_spans() {
+        let validator = KqlValidator::new().expect("Failed to create validator");
+        let query = "let f = (a: long) { a + 1 };\nprint f";
+
+        let result = validator
+            .validate_with_schema(query, &Schema::new())
+            .expect("Validation failed");
+        let diagnostic = result
+            .diagnostics
+            .iter()
+            .find(|d| d.start == query.rfind('f').unwrap())
+            .expect("Expected a diagnostic on the function reference");
+        let related: Vec<_> = diagnostic
+            .related_spans
+            .iter()
+            .map(|r| (r.start, r.end))
+            .collect();
+        assert_eq!(related, [(4, 5)]);
+    }
+
+    #[test]
+    #[ignore = "requires native library"]
+    fn test_diagnostics_config() {
+        use crate::DiagnosticsConfig;
+
+        let query = "T | where Ip has '10.0.0.1'";
+        let validator = |config: DiagnosticsConfig| {
+            KqlValidator::new()
+                .expect("Failed to create validator")
+                .with_case_sensitivity_advisory(true)
+                .with_diagnostics_config(config)
+        };
+
+        let promoted =
+            validator(DiagnosticsConfig::new().severity("CS001", DiagnosticSeverity::Error))
+                .validate_syntax(query)
+                .expect("Validation failed");
+        assert!(!promoted.is_valid());
+
+        let suppressed = validator(DiagnosticsConfig::new().suppress("CS001"))
+            .validate_syntax(query)
+            .expect("Validation failed");
+        assert!(suppressed.is_valid());
+        assert!(suppressed.diagnostics.is_empty());
+    }
+
+    #[test]
+    #[ignore = "requires native library"]
+    fn test_utf16_offsets() {
+        let validator = KqlValidator::new()
+            .unwrap()
+            .with_offset_encoding(OffsetEncoding::Utf16);
+        let query = "print '😀' | take x";
+
+        let result = validator.validate_syntax(query).unwrap();
+        let diagnostic = &result.diagnostics[0];
+        assert_eq!((diagnostic.start, diagnostic.column), (18, 19));
+
+        // The cursor is read in UTF-16 code units too
+        let completions = validator.get_completions(query, 16, None).unwrap();
+        assert!(completions.items.iter().all(|item| item.edit_start <= 16));
+
+        // By default the emoji counts once
+        let result = KqlValidator::new().unwrap().validate_syntax(query).unwrap();
+        let diagnostic = &result.diagnostics[0];
+        assert_eq!((diagnostic.start, diagnostic.column), (17, 18));
+    }
+
+    #[test]
+    #[ignore = "requires native library"]
+    fn test_with_engine() {
+        /// Fails every call, to show which calls reach the engine
+        struct Unavailable;
+
+        fn unavailable<T>() -> Result<T, Error> {
+            Err(Error::Internal {
+                message: "unavailable".to_string(),
+            })
+        }
+
+        impl KqlEngine for Unavailable {
+            fn validate_syntax(&self, _query: &str) -> Result<ValidationResult, Error> {
+                unavailable()
+            }
+
+            fn validate_with_schema(
+                &self,
+                _query: &str,
+                _schema: &Schema,
+            ) -> Result<ValidationResult, Error> {
+                unavailable()
+            }
+
+            fn get_completions(
+                &self,
+                _query: &str,
+                _cursor_position: usize,
+                _schema: Option<&Schema>,
+            ) -> Result<CompletionResult, Error> {
+                unavailable()
+            }
+
+            fn get_classifications(&self, _query: &str) -> Result<ClassificationResult, Error> {
+                unavailable()
+            }
+        }
+
+        let validator = KqlValidator::new().unwrap().with_engine(Unavailable);
+        assert!(validator.validate_syntax("T").is_err());
+        assert!(validator.validate_many(&["T"]).is_err());
+        assert!(validator.get_completions("T | ", 4, None).is_err());
+        assert!(validator.get_classifications("T").is_err());
+        assert!(validator.get_syntax_tree("T").is_ok());
+
+        // The wrapping validator's offset encoding applies to engine results
+        let validator = KqlValidator::new()
+            .unwrap()
+            .with_offset_encoding(OffsetEncoding::Utf16)
+            .with_engine(KqlValidator::new().unwrap());
+        let result = validator.validate_syntax("print '😀' | take x").unwrap();
+        assert_eq!(result.diagnostics[0].start, 18);
+    }
+
+    #[test]
+    #[ignore = "requires native library"]
+    fn test_document_edits() {
+        use crate::Document;
+
+        let validator = KqlValidator::new().expect("Failed to create validator");
+        let schema =
+            Schema::new().table(Table::new("SecurityEvent").with_column("EventID", "long"));
+        let handle = validator.register_schema(&schema).unwrap();
+        let mut document =
+            Document::open_with_handle(&validator, "SecurityEvent | where", &handle).unwrap();
+        drop(handle);
+        assert!(!document.diagnostics().unwrap().is_valid());
+
+        document
+            .apply_edit(21..21, " EventID == 1\n\nSecurityEvent | where Foo == 1")
+            .unwrap();
+        assert_eq!(
+            document.text(),
+            "SecurityEvent | where EventID == 1\n\nSecurityEvent | where Foo == 1"
+        );
+        let result = document.diagnostics().unwrap();
+        assert_eq!(result.diagnostics.len(), 1);
+        assert_eq!(
+            (result.diagnostics[0].start, result.diagnostics[0].line),
+            (57, 3)
+        );
+
+        let completions = document.completions(58).unwrap();
+        assert!(completions.items.iter().any(|item| item.label == "EventID"));
+
+        assert!(document.apply_edit(10..200, "").is_err());
+        assert_eq!(document.text().len(), 66);
+    }
+
+    #[test]
+    #[ignore = "requires native library"]
+    fn test_large_result_retry() {
+        let validator = KqlValidator::new().expect("Failed to create validator");
+        // Thousands of diagnostics overflow the default 64KB buffer
+        let query = "T | where\n\n".repeat(5_000);
+
+        let result = validator.validate_syntax(&query).unwrap();
+        assert!(result.diagnostics.len() >= 5_000);
+    }
+
+    #[test]
+    #[ignore = "requires native library"]
+    fn test_native_results() {
+        let validator = KqlValidator::new()
+            .expect("Failed to create validator")
+            .with_native_results(true);
+        let query = "T | where x == 1 | project a, b, c\n".repeat(50_000);
+
+        let classifications = validator.get_classifications(&query).unwrap();
+        assert!(classifications.spans.len() > 100_000);
+        assert!(validator.validate_syntax("T | take 10").unwrap().is_valid());
+    }
+
+    #[test]
+    #[ignore = "requires native library"]
+    fn test_result_chunks_past_max_buffer_size() {
+        let validator = KqlValidator::new()
+            .expect("Failed to create validator")
+            .with_max_buffer_size(64 * 1024);
+        let query = "T | where x == 1 | project a, b, c\n".repeat(20_000);
+
+        let classifications = validator.get_classifications(&query).unwrap();
+        assert!(classifications.spans.len() > 100_000);
+    }
+
+    #[test]
+    #[cfg(feature = "cbor")]
+    fn test_cbor_results_decode_like_json() {
+        let json = r#"{"valid":false,"diagnostics":[{"message":"Unknown name","severity":"Error","start":4,"end":9,"line":1,"column":5,"code":"KS142","relatedSpans":[{"message":"Declared here","start":0,"end":1}]}]}"#;
+        let value: serde_json::Value = serde_json::from_str(json).unwrap();
+        let mut cbor = Vec::new();
+        ciborium::into_writer(&value, &mut cbor).unwrap();
+
+        let from_json: ValidationResult =
+            ResultFormat::Json.decode(json.as_bytes()).unwrap().unwrap();
+        let from_cbor: ValidationResult = ciborium::from_reader(cbor.as_slice()).unwrap();
+        assert_eq!(
+            serde_json::to_value(&from_cbor).unwrap(),
+            serde_json::to_value(&from_json).unwrap()
+        );
+        assert!(ResultFormat::Json
+            .decode::<ValidationResult>(&[])
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_validator_is_send_and_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<KqlValidator>();
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    #[ignore = "requires native library"]
+    fn test_validate_all_par() {
+        let validator = KqlValidator::new().expect("Failed to create validator");
+        let queries: Vec<&str> = (0..500)
+            .map(|i| {
+                if i % 7 == 0 {
+                    "T | where"
+                } else {
+                    "T | take 10"
+                }
+            })
+            .collect();
+
+        let results = validator.validate_all_par(&queries, None).unwrap();
+        assert_eq!(results.len(), queries.len());
+        for (i, result) in results.iter().enumerate() {
+            assert_eq!(result.is_valid(), i % 7 != 0);
+        }
+    }
+
+    #[test]
+    #[ignore = "requires native library"]
+    fn test_run_cancellable() {
+        use crate::CancellationToken;
+        use std::time::Duration;
+
+        let validator = KqlValidator::new().expect("Failed to create validator");
+
+        let token = CancellationToken::with_timeout(Duration::from_secs(60));
+        let result = validator
+            .run_cancellable(&token, |v| v.validate_syntax("T | take 10"))
+            .unwrap();
+        assert!(result.is_valid());
+
+        token.cancel();
+        let cancelled = validator.run_cancellable(&token, |v| v.validate_syntax("T | take 10"));
+        assert!(matches!(cancelled, Err(Error::Cancelled)));
+
+        // A deadline stops a call that is still running
+        let query = "T | where x == 1 and y == 2".repeat(20_000);
+        let token = CancellationToken::with_timeout(Duration::from_millis(1));
+        let timed_out = validator.run_cancellable(&token, move |v| v.validate_syntax(&query));
+        assert!(matches!(timed_out, Err(Error::TimedOut)));
+    }
+
+    #[test]
+    #[ignore = "requires native library"]
+    fn test_lint() {
+        let validator = KqlValidator::new().expect("Failed to create validator");
+        let linter = Linter::builtin();
+
+        let codes: Vec<_> = validator
+            .lint("search * | take 10", &linter)
+            .expect("Lint failed")
+            .into_iter()
+            .filter_map(|d| d.code)
+            .collect();
+        assert_eq!(codes, ["LNT001"]);
+
+        let clean = validator
+            .lint("SigninLogs | where TimeGenerated > ago(1h)", &linter)
+            .expect("Lint failed");
+        assert!(clean.is_empty());
+    }
+
+    #[test]
+    #[ignore = "requires native library"]
+    fn test_get_document_symbols() {
+        use crate::DocumentSymbolKind;
+
+        let validator = KqlValidator::new().expect("Failed to create validator");
+        let query = "let f = (x: long) { T | where A > x };\nlet y = 1;\nT | where A > y | take 10";
+
+        let symbols = validator
+            .get_document_symbols(query)
+            .expect("Outline failed");
+        let outline: Vec<_> = symbols
+            .iter()
+            .map(|s| {
+                let children: Vec<_> = s.children.iter().map(|c| c.name.as_str()).collect();
+                (s.kind, s.name.as_str(), children)
+            })
+            .collect();
+        assert_eq!(
+            outline,
+            [
+                (DocumentSymbolKind::Function, "f", vec!["x", "where"]),
+                (DocumentSymbolKind::Variable, "y", vec![]),
+                (DocumentSymbolKind::Query, "T", vec!["where", "take"]),
+            ]
+        );
+        let take = &symbols[2].children[1];
+        assert_eq!(&query[take.selection_start..take.selection_end], "take");
+    }
+
+    #[test]
+    #[ignore = "requires native library"]
+    fn test_get_bracket_pairs() {
+        let validator = KqlValidator::new().expect("Failed to create validator");
+        let query = "print x = (1 + (2 * 3)), s = '(' // )\n| extend y = pack_array(1)[0]";
+
+        let pairs = validator
+            .get_bracket_pairs(query)
+            .expect("Bracket matching failed");
+        let pairs: Vec<_> = pairs.iter().map(|p| (p.open, p.close, p.depth)).collect();
+        assert_eq!(pairs, [(10, 22, 0), (15, 21, 1), (61, 63, 0), (64, 66, 0)]);
+    }
+
+    #[test]
+    #[ignore = "requires native library"]
+    fn test_get_syntax_tree() {
+        struct Tokens(String);
+        impl crate::SyntaxVisitor for Tokens {
+            fn enter(&mut self, node: &crate::SyntaxNode) -> bool {
+                if let Some(text) = &node.text {
+                    self.0.push_str(text);
+                }
+                true
+            }
+        }
+
+        let validator = KqlValidator::new().expect("Failed to create validator");
+        let query = "T | summarize n = count() by State";
+
+        let tree = validator
+            .get_syntax_tree(query)
+            .expect("Syntax tree failed");
+        assert_eq!(tree.root.kind, "QueryBlock");
+
+        let mut tokens = Tokens(String::new());
+        tree.walk(&mut tokens);
+        assert_eq!(tokens.0, query.replace(' ', ""));
+
+        let summarize = tree
+            .find_all(crate::NodeKind::SummarizeOperator)
+            .next()
+            .expect("summarize operator");
+        let count = tree.node_at(18).expect("node at count");
+        assert_eq!(count.text.as_deref(), Some("count"));
+        assert!(tree
+            .ancestors(count)
+            .any(|node| std::ptr::eq(node, summarize)));
+    }
+
+    #[test]
+    #[ignore = "requires native library"]
+    fn test_get_enclosing_ranges() {
+        use crate::EnclosingRangeKind;
+
+        let validator = KqlValidator::new().expect("Failed to create validator");
+        let query = "T | where A > 10 | take 5";
+
+        let ranges = validator
+            .get_enclosing_ranges(query, 14)
+            .expect("Enclosing ranges failed");
+        let texts: Vec<_> = ranges.iter().map(|r| &query[r.start..r.end]).collect();
+        assert_eq!(
+            texts,
+            ["10", "A > 10", "where A > 10", "T | where A > 10", query]
+        );
+        assert_eq!(ranges[0].kind, EnclosingRangeKind::Token);
+        assert_eq!(ranges[2].kind, EnclosingRangeKind::Stage);
+    }
+
+    #[test]
+    #[ignore = "requires native library"]
+    fn test_get_folding_ranges() {
+        use crate::FoldingRangeKind;
+
+        let validator = KqlValidator::new().expect("Failed to create validator");
+        let query = "// Helpers\n// for tests\nlet f = (x: long) {\n    x + 1\n};\n\
+                     T\n| join (\n    U\n    | take 1\n) on Id";
+
+        let ranges: Vec<_> = validator
+            .get_folding_ranges(query)
+            .expect("Folding ranges failed")
+            .iter()
+            .map(|r| (r.start_line, r.end_line, r.kind))
+            .collect();
+        assert_eq!(
+            ranges,
+            [
+                (1, 2, FoldingRangeKind::Comment),
+                (3, 5, FoldingRangeKind::Region),
+                (6, 10, FoldingRangeKind::Region),
+                (7, 10, FoldingRangeKind::Region),
+            ]
+        );
+    }
+
+    #[test]
+    #[ignore = "requires native library"]
+    fn test_sample_paths_validation() {
+        let validator = KqlValidator::new().expect("Failed to create validator");
+        let schema = Schema::new().table(
+            crate::schema::Table::new("SigninLogs")
+                .column(crate::schema::Column::dynamic("Properties"))
+                .with_samples([serde_json::json!({"Properties": {"user": {"name": "alice"}}})]),
+        );
+
+        let result = validator
+            .validate_with_schema(
+                "SigninLogs | project parse_json(Properties).user.name",
+                &schema,
+            )
+            .expect("Validation failed");
+        assert!(result.diagnostics.is_empty(), "{:?}", result.diagnostics);
+
+        let result = validator
+            .validate_with_schema("SigninLogs | project Properties.user.nmae", &schema)
+            .expect("Validation failed");
+        assert!(result
+            .warnings()
+            .any(|d| d.code.as_deref() == Some("DS001")));
+    }
+
+    #[test]
+    #[ignore = "requires native library"]
+    fn test_query_parameters() {
+        let schema = Schema::new()
+            .table(Table::new("SecurityEvent").with_column("TimeGenerated", "datetime"))
+            .query_parameter(Parameter::new("_startTime", "datetime"));
+        let query = "SecurityEvent | where TimeGenerated between (_startTime .. _endTime)";
+
+        let validator = KqlValidator::new().expect("Failed to create validator");
+        let result = validator
+            .validate_with_schema(query, &schema)
+            .expect("Validation failed");
+        assert!(!result.is_valid());
+
+        let validator = validator.with_query_parameter(Parameter::new("_endTime", "datetime"));
+        let result = validator
+            .validate_with_schema(query, &schema)
+            .expect("Validation failed");
+        assert!(result.is_valid(), "{:?}", result.diagnostics);
+    }
+
+    #[test]
+    #[ignore = "requires native library"]
+    fn test_dynamic_fields() {
+        let validator = KqlValidator::new().expect("Failed to create validator");
+        let schema = Schema::new().table(
+            crate::schema::Table::new("SigninLogs").column(
+                crate::schema::Column::dynamic("Properties")
+                    .with_fields([("ipAddress", "string"), ("location.city", "string")]),
+            ),
+        );
+
+        let query = "SigninLogs | project tostring(Properties.";
+        let result = validator
+            .get_completions(query, query.len(), Some(&schema))
+            .expect("Completion failed");
+        assert!(result.items.iter().any(|item| item.label == "ipAddress"));
+        assert!(result.items.iter().any(|item| item.label == "location"));
+
+        let result = validator
+            .validate_with_schema(
+                "SigninLogs | where Properties.location.city == 'Oslo' | project Properties.ipAddress",
+                &schema,
+            )
+            .expect("Validation failed");
+        assert!(result.diagnostics.is_empty(), "{:?}", result.diagnostics);
+
+        let result = validator
+            .validate_with_schema("SigninLogs | project Properties.ipAdress", &schema)
+            .expect("Validation failed");
+        assert!(result
+            .warnings()
+            .any(|d| d.code.as_deref() == Some("DS002")));
+    }
+
+    #[test]
+    #[ignore = "requires native library"]
+    fn test_validate_command() {
+        let validator = KqlValidator::new().expect("Failed to create validator");
 
-        // Validate sizes fit in c_int
-        let query_len = c_int::try_from(query_bytes.len()).map_err(|_| Error::Internal {
-            message: format!("Query too large: {} bytes", query_bytes.len()),
-        })?;
-        let cursor_pos = c_int::try_from(cursor_position).map_err(|_| Error::Internal {
-            message: format!("Cursor position too large: {cursor_position}"),
-        })?;
+        for command in [
+            ".create table Logs (Timestamp: datetime, Message: string)",
+            ".show database schema",
+            ".set-or-append Logs <| print Timestamp = now(), Message = 'x'",
+        ] {
+            let result = validator
+                .validate_command(command)
+                .expect("Command validation failed");
+            assert!(result.is_valid(), "{command}: {:?}", result.diagnostics);
+        }
 
-        self.call_ffi_json(|buffer| {
-            // SAFETY: See validate_syntax for safety invariants.
-            // schema_ptr may be null (handled by FFI), schema_len is 0 in that case.
-            #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
-            unsafe {
-                let (schema_ptr, schema_len) = match &schema_json {
-                    Some(json) => (json.as_ptr(), json.len() as c_int),
-                    None => (std::ptr::null(), 0),
-                };
+        let result = validator.validate_command("Logs | take 10").unwrap();
+        assert!(result
+            .diagnostics
+            .iter()
+            .any(|d| d.code.as_deref() == Some("KC001")));
 
-                completions_fn(
-                    query_bytes.as_ptr(),
-                    query_len,
-                    cursor_pos,
-                    schema_ptr,
-                    schema_len,
-                    buffer.as_mut_ptr(),
-                    buffer.len() as c_int,
-                )
-            }
-        })
+        let strict = KqlValidator::new()
+            .expect("Failed to create validator")
+            .with_query_kind(QueryKind::Command);
+        assert!(!strict.validate_syntax("Logs | take 10").unwrap().is_valid());
     }
 
-    /// Call an FFI function with automatic buffer retry on overflow
-    #[allow(clippy::cast_sign_loss)]
-    fn call_ffi_with_retry<F>(&self, mut ffi_call: F) -> Result<ValidationResult, Error>
-    where
-        F: FnMut(&mut Vec<u8>) -> c_int,
-    {
-        let mut buffer = vec![0u8; DEFAULT_BUFFER_SIZE];
-        let mut result = ffi_call(&mut buffer);
-
-        // Handle buffer too small - retry with larger buffer
-        if return_codes::is_buffer_too_small(result) {
-            // Double the buffer size and retry
-            let new_size = buffer.len() * 2;
-            if new_size > MAX_BUFFER_SIZE {
-                return Err(Error::BufferTooSmall {
-                    needed: new_size,
-                    available: MAX_BUFFER_SIZE,
-                });
-            }
-            buffer.resize(new_size, 0);
-            result = ffi_call(&mut buffer);
+    #[test]
+    #[ignore = "requires native library"]
+    fn test_validate_script() {
+        let validator = KqlValidator::new().expect("Failed to create validator");
 
-            // If still too small, give up
-            if return_codes::is_buffer_too_small(result) {
-                return Err(Error::BufferTooSmall {
-                    needed: 0, // Unknown
-                    available: buffer.len(),
-                });
-            }
-        }
+        let text = "StormEvents | take 10\n\n\nStormEvents\n| where";
+        let script = validator
+            .validate_script(text)
+            .expect("Script validation failed");
 
-        // Check for other errors
-        if !return_codes::is_success(result) {
-            let error_msg = self.get_last_error().unwrap_or_default();
-            return Err(Error::from_native_code(result, &error_msg));
-        }
+        assert_eq!(script.blocks.len(), 2);
+        assert!(script.blocks[0].result.is_valid());
+        assert!(!script.is_valid());
 
-        // Parse JSON result
-        if result == 0 {
-            // Empty result means valid query
-            return Ok(ValidationResult::valid());
-        }
+        let error = script.diagnostics().next().expect("Expected a diagnostic");
+        assert_eq!(error.line, 5);
+        assert_eq!(script.block_at(error.start).unwrap().start_line, 4);
+    }
 
-        let json_len = result as usize;
-        let json_str = std::str::from_utf8(&buffer[..json_len])?;
+    #[test]
+    #[ignore = "requires native library"]
+    fn test_validate_many() {
+        let validator = KqlValidator::new().expect("Failed to create validator");
+        let schema = Schema::new()
+            .table(crate::schema::Table::new("SecurityEvent").with_column("EventID", "long"));
 
-        log::trace!("FFI returned JSON: {json_str}");
+        let results = validator
+            .validate_many_with_schema(
+                &[
+                    "SecurityEvent | where EventID == 4625",
+                    "SecurityEvent | where Missing == 1",
+                    "SecurityEvent | where",
+                ],
+                &schema,
+            )
+            .expect("Batch validation failed");
 
-        let validation_result: ValidationResult = serde_json::from_str(json_str)?;
-        Ok(validation_result)
+        let valid: Vec<_> = results.iter().map(ValidationResult::is_valid).collect();
+        assert_eq!(valid, [true, false, false]);
     }
 
-    /// Call an FFI function and deserialize JSON result to a generic type
-    #[allow(clippy::cast_sign_loss)]
-    fn call_ffi_json<T, F>(&self, mut ffi_call: F) -> Result<T, Error>
-    where
-        T: for<'de> serde::Deserialize<'de> + Default,
-        F: FnMut(&mut Vec<u8>) -> c_int,
-    {
-        let mut buffer = vec![0u8; DEFAULT_BUFFER_SIZE];
-        let mut result = ffi_call(&mut buffer);
-
-        // Handle buffer too small - retry with larger buffer
-        if return_codes::is_buffer_too_small(result) {
-            let new_size = buffer.len() * 2;
-            if new_size > MAX_BUFFER_SIZE {
-                return Err(Error::BufferTooSmall {
-                    needed: new_size,
-                    available: MAX_BUFFER_SIZE,
-                });
-            }
-            buffer.resize(new_size, 0);
-            result = ffi_call(&mut buffer);
+    #[test]
+    #[ignore = "requires native library"]
+    fn test_bound_validator() {
+        let schema = Schema::new().table(
+            Table::new("SecurityEvent")
+                .with_column("Account", "string")
+                .with_column("EventID", "long"),
+        );
+        let bound = KqlValidator::new()
+            .expect("Failed to create validator")
+            .with_schema(schema)
+            .expect("Failed to bind schema");
 
-            if return_codes::is_buffer_too_small(result) {
-                return Err(Error::BufferTooSmall {
-                    needed: 0,
-                    available: buffer.len(),
-                });
-            }
-        }
+        assert!(bound
+            .validate("SecurityEvent | where EventID == 4625")
+            .unwrap()
+            .is_valid());
+        assert!(!bound
+            .validate("SecurityEvent | where Missing == 1")
+            .unwrap()
+            .is_valid());
 
-        // Check for errors
-        if !return_codes::is_success(result) {
-            let error_msg = self.get_last_error().unwrap_or_default();
-            return Err(Error::from_native_code(result, &error_msg));
-        }
+        let completions = bound
+            .complete("SecurityEvent | where ", 22)
+            .expect("Completion failed");
+        assert!(completions.items.iter().any(|i| i.label == "EventID"));
+    }
 
-        // Parse JSON result
-        if result == 0 {
-            return Ok(T::default());
-        }
+    #[test]
+    #[ignore = "requires native library"]
+    fn test_schema_handle() {
+        let validator = KqlValidator::new().expect("Failed to create validator");
+        let schema =
+            Schema::new().table(Table::new("SecurityEvent").with_column("EventID", "long"));
 
-        let json_len = result as usize;
-        let json_str = std::str::from_utf8(&buffer[..json_len])?;
+        let handle = validator
+            .register_schema(&schema)
+            .expect("Failed to register schema");
+        assert!(handle.id() > 0);
 
-        log::trace!("FFI returned JSON: {json_str}");
+        let other = KqlValidator::new().expect("Failed to create validator");
+        assert!(other
+            .validate_with_handle("SecurityEvent | where EventID == 4625", &handle)
+            .unwrap()
+            .is_valid());
+        let completions = validator
+            .get_completions_with_handle("SecurityEvent | where ", 22, &handle)
+            .unwrap();
+        assert!(completions.items.iter().any(|i| i.label == "EventID"));
 
-        let parsed_result: T = serde_json::from_str(json_str)?;
-        Ok(parsed_result)
+        let second = validator.register_schema(&schema).unwrap();
+        assert_ne!(second.id(), handle.id());
     }
 
-    /// Get the last error message from the native library
-    #[allow(
-        clippy::cast_possible_truncation,
-        clippy::cast_possible_wrap,
-        clippy::cast_sign_loss
-    )]
-    fn get_last_error(&self) -> Option<String> {
-        let mut buffer = vec![0u8; 1024];
-        let result =
-            unsafe { (self.lib.get_last_error)(buffer.as_mut_ptr(), buffer.len() as c_int) };
+    #[test]
+    #[ignore = "requires native library"]
+    fn test_analyze() {
+        let validator = KqlValidator::new().expect("Failed to create validator");
+        let schema = Schema::new()
+            .table(
+                Table::new("SecurityEvent")
+                    .with_column("Account", "string")
+                    .with_column("EventID", "long"),
+            )
+            .table(Table::new("SigninLogs").with_column("UserPrincipalName", "string"));
 
-        if return_codes::is_success(result) && result > 0 {
-            let len = result as usize;
-            String::from_utf8(buffer[..len].to_vec()).ok()
-        } else {
-            None
-        }
+        let analysis = validator
+            .analyze(
+                "SecurityEvent | where EventID == 4625 | join (SigninLogs) on $left.Account == $right.UserPrincipalName\n\
+                 | union cluster('help').database('Samples').StormEvents",
+                &schema,
+            )
+            .expect("Analysis failed");
+
+        assert_eq!(
+            analysis.tables.iter().collect::<Vec<_>>(),
+            ["SecurityEvent", "SigninLogs"]
+        );
+        assert_eq!(
+            analysis.columns_of("SecurityEvent").collect::<Vec<_>>(),
+            ["Account", "EventID"]
+        );
+        assert_eq!(
+            analysis.columns_of("SigninLogs").collect::<Vec<_>>(),
+            ["UserPrincipalName"]
+        );
+        assert!(analysis.clusters.contains("help"));
+        assert!(analysis.databases.contains("Samples"));
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    #[test]
+    #[ignore = "requires native library"]
+    fn test_analyze_pipeline() {
+        let validator = KqlValidator::new().expect("Failed to create validator");
+        let schema = Schema::new().table(
+            Table::new("SecurityEvent")
+                .with_column("Account", "string")
+                .with_column("EventID", "long"),
+        );
+        let query = "let n = 10;\nSecurityEvent\n| where EventID == 4625\n| summarize Failures = count() by Account\n| take n";
 
-    // These tests require the native library to be available
-    // They are ignored by default and can be run with:
-    // cargo test --features test-native -- --ignored
+        let pipeline = validator
+            .analyze_pipeline(query, &schema)
+            .expect("Pipeline analysis failed");
+        let kinds: Vec<_> = pipeline.stages.iter().map(|s| s.kind.as_str()).collect();
+        assert_eq!(
+            kinds,
+            [
+                "NameReference",
+                "FilterOperator",
+                "SummarizeOperator",
+                "TakeOperator"
+            ]
+        );
+        assert_eq!(pipeline.stages[1].text, "where EventID == 4625");
+        let columns = |index: usize| {
+            pipeline.stages[index]
+                .output
+                .as_ref()
+                .expect("tabular stage")
+                .columns
+                .len()
+        };
+        assert_eq!((columns(0), columns(1), columns(2)), (2, 2, 2));
+        assert_eq!(pipeline.prefix(2), "SecurityEvent\n| where EventID == 4625");
+    }
 
     #[test]
     #[ignore = "requires native library"]
-    fn test_validate_syntax_valid() {
+    fn test_analyze_joins() {
+        let validator = KqlValidator::new().expect("Failed to create validator");
+        let schema = Schema::new()
+            .table(
+                Table::new("SigninLogs")
+                    .with_column("UserId", "string")
+                    .with_column("CorrelationId", "guid"),
+            )
+            .table(
+                Table::new("AuditLogs")
+                    .with_column("UserId", "long")
+                    .with_column("CorrelationId", "guid"),
+            );
+        let query = "SigninLogs\n| join kind=inner (AuditLogs) on UserId, CorrelationId\n| lookup AuditLogs on CorrelationId\n| union AuditLogs";
+
+        let analysis = validator
+            .analyze_joins(query, &schema)
+            .expect("Join analysis failed");
+        let operators: Vec<_> = analysis.operations.iter().map(|o| o.operator).collect();
+        assert_eq!(
+            operators,
+            [
+                JoinOperator::Join,
+                JoinOperator::Lookup,
+                JoinOperator::Union
+            ]
+        );
+        let join = &analysis.operations[0];
+        assert_eq!(join.kind, "inner");
+        assert_eq!(join.sources, ["SigninLogs", "(AuditLogs)"]);
+        assert_eq!(join.keys[0].left_type, Some(KqlType::String));
+        assert_eq!(join.keys[0].right_type, Some(KqlType::Long));
+        assert_eq!(analysis.operations[1].kind, "leftouter");
+        assert_eq!(analysis.operations[2].kind, "outer");
+
+        let codes: Vec<_> = analysis
+            .diagnostics
+            .iter()
+            .filter_map(Diagnostic::parsed_code)
+            .collect();
+        assert_eq!(
+            codes,
+            [
+                KustoDiagnosticCode::JoinKeyTypeMismatch,
+                KustoDiagnosticCode::HighCardinalityJoinKey
+            ]
+        );
+    }
+
+    #[test]
+    #[ignore = "requires native library"]
+    fn test_infer_result_schema() {
         let validator = KqlValidator::new().expect("Failed to create validator");
+        let schema = Schema::new().table(
+            Table::new("SecurityEvent")
+                .with_column("Account", "string")
+                .with_column("EventID", "long")
+                .with_column("TimeGenerated", "datetime"),
+        );
+
         let result = validator
-            .validate_syntax("T | take 10")
-            .expect("Validation failed");
-        assert!(result.is_valid());
+            .infer_result_schema(
+                "SecurityEvent | where EventID == 4625 | summarize Failures = count(), Last = max(TimeGenerated) by Account",
+                &schema,
+            )
+            .expect("Inference failed");
+        let columns: Vec<_> = result
+            .columns
+            .iter()
+            .map(|c| (c.name.as_str(), c.data_type.clone()))
+            .collect();
+        assert_eq!(
+            columns,
+            [
+                ("Account", KqlType::String),
+                ("Failures", KqlType::Long),
+                ("Last", KqlType::DateTime)
+            ]
+        );
+
+        assert!(validator.infer_result_schema("print 1", &schema).is_ok());
+        assert!(validator
+            .infer_result_schema("let x = 1; x", &schema)
+            .is_err());
     }
 
     #[test]
     #[ignore = "requires native library"]
-    fn test_validate_syntax_invalid() {
+    fn test_format() {
+        let validator = KqlValidator::new().expect("Failed to create validator");
+
+        let formatted = validator
+            .format(
+                "SecurityEvent | Where EventID == 4625 | take 10",
+                &FormatOptions::default(),
+            )
+            .expect("Format failed");
+        assert_eq!(
+            formatted,
+            "SecurityEvent\n| where EventID == 4625\n| take 10"
+        );
+        assert!(validator.validate_syntax(&formatted).unwrap().is_valid());
+
+        let preserved = validator
+            .format(
+                "T | Where x == 1",
+                &FormatOptions::default().operator_case(OperatorCase::Preserve),
+            )
+            .expect("Format failed");
+        assert!(preserved.contains("Where"));
+    }
+
+    #[test]
+    #[ignore = "requires native library"]
+    fn test_get_semantic_classifications() {
+        use crate::classification::ClassificationKind;
+
         let validator = KqlValidator::new().expect("Failed to create validator");
+        let schema = Schema::new()
+            .table(Table::new("SecurityEvent").with_column("EventID", "long"))
+            .function(crate::schema::Function::new("FailedLogons", "long"));
+        let query = "SecurityEvent | where EventID == FailedLogons()";
+
         let result = validator
-            .validate_syntax("T | invalid_operator")
-            .expect("Validation failed");
-        assert!(!result.is_valid());
-        assert!(result.has_errors());
+            .get_semantic_classifications(query, &schema)
+            .expect("Classification failed");
+        let kind_of = |text: &str| {
+            let start = query.find(text).unwrap();
+            result
+                .spans
+                .iter()
+                .find(|s| s.start == start)
+                .map(|s| s.kind)
+        };
+        assert_eq!(kind_of("SecurityEvent"), Some(ClassificationKind::Table));
+        assert_eq!(kind_of("EventID"), Some(ClassificationKind::Column));
+        assert_eq!(
+            kind_of("FailedLogons"),
+            Some(ClassificationKind::ScalarFunction)
+        );
     }
 
     #[test]
     #[ignore = "requires native library"]
-    fn test_validate_with_schema() {
+    fn test_get_analyzer_diagnostics() {
         let validator = KqlValidator::new().expect("Failed to create validator");
+        let schema = Schema::new()
+            .table(crate::schema::Table::new("SigninLogs").with_column("AppDisplayName", "string"));
+
+        let diagnostics = validator
+            .get_analyzer_diagnostics(
+                "SigninLogs | where AppDisplayName contains \"Office\"",
+                &schema,
+            )
+            .expect("Analysis failed");
+        assert!(!diagnostics.is_empty());
+        assert!(diagnostics
+            .iter()
+            .all(|d| d.category == Some(DiagnosticCategory::Analyzer)));
 
+        let clean = validator
+            .get_analyzer_diagnostics("SigninLogs | where AppDisplayName has \"Office\"", &schema)
+            .expect("Analysis failed");
+        assert!(clean.is_empty());
+    }
+
+    #[test]
+    #[ignore = "requires native library"]
+    fn test_let_completions() {
+        use crate::completion::CompletionKind;
+
+        let validator = KqlValidator::new().expect("Failed to create validator");
         let schema = Schema::new().table(
-            crate::schema::Table::new("SecurityEvent")
+            Table::new("SigninLogs")
                 .with_column("TimeGenerated", "datetime")
-                .with_column("Account", "string"),
+                .with_column("UserPrincipalName", "string"),
         );
+        let query = "let threshold = 10;\nlet Recent = SigninLogs | project TimeGenerated;\nlet twice = (x: long) { x * 2 };\nRecent | where ";
+        let result = validator
+            .get_completions(query, query.len(), Some(&schema))
+            .unwrap();
+        let detail = |label: &str| {
+            let item = result
+                .items
+                .iter()
+                .find(|i| i.label.starts_with(label))
+                .unwrap_or_else(|| panic!("Expected {label} to be completed"));
+            assert_eq!(item.kind, CompletionKind::LetVariable);
+            item.detail.clone().unwrap_or_default()
+        };
+        assert_eq!(detail("threshold"), "long");
+        assert_eq!(detail("twice"), "twice(x: long)");
 
+        let query = "let Recent = SigninLogs | project TimeGenerated;\nRec";
         let result = validator
-            .validate_with_schema("SecurityEvent | project TimeGenerated, Account", &schema)
-            .expect("Validation failed");
-        assert!(result.is_valid());
+            .get_completions(query, query.len(), Some(&schema))
+            .unwrap();
+        let recent = result.items.iter().find(|i| i.label == "Recent").unwrap();
+        assert_eq!(recent.kind, CompletionKind::LetVariable);
+        assert_eq!(recent.detail.as_deref(), Some("(TimeGenerated: datetime)"));
     }
 
     #[test]
     #[ignore = "requires native library"]
-    fn test_validate_with_schema_unknown_column() {
+    fn test_expected_type_completions() {
         let validator = KqlValidator::new().expect("Failed to create validator");
-
         let schema = Schema::new().table(
-            crate::schema::Table::new("SecurityEvent").with_column("TimeGenerated", "datetime"),
+            Table::new("SigninLogs")
+                .with_column("UserPrincipalName", "string")
+                .with_column("TimeGenerated", "datetime")
+                .with_column("CreatedDateTime", "datetime"),
         );
 
+        let query = "SigninLogs | where TimeGenerated > ";
         let result = validator
-            .validate_with_schema("SecurityEvent | project UnknownColumn", &schema)
-            .expect("Validation failed");
-        assert!(!result.is_valid());
+            .get_completions(query, query.len(), Some(&schema))
+            .unwrap();
+        assert_eq!(result.expected_type, Some(KqlType::DateTime));
+        let matching: Vec<_> = result
+            .items
+            .iter()
+            .take_while(|i| i.matches_expected_type)
+            .map(|i| i.label.as_str())
+            .collect();
+        assert!(matching.contains(&"CreatedDateTime"));
+        assert!(matching.iter().any(|l| l.starts_with("ago")));
+        assert!(!matching.contains(&"UserPrincipalName"));
+        assert!(result
+            .items
+            .iter()
+            .skip(matching.len())
+            .all(|i| !i.matches_expected_type));
+
+        let query = "SigninLogs | where ";
+        let result = validator
+            .get_completions(query, query.len(), Some(&schema))
+            .unwrap();
+        assert_eq!(result.expected_type, None);
+    }
+
+    #[test]
+    #[ignore = "requires native library"]
+    fn test_completion_context() {
+        use crate::completion::CompletionTrigger;
+
+        let validator = KqlValidator::new().expect("Failed to create validator");
+        let schema = Schema::new().table(
+            Table::new("SigninLogs")
+                .with_column("TimeGenerated", "datetime")
+                .with_column("UserPrincipalName", "string"),
+        );
+        let context = |query: &str, cursor: usize| {
+            validator
+                .get_completions(query, cursor, Some(&schema))
+                .unwrap()
+                .context
+                .expect("Expected a completion context")
+        };
+
+        let query = "SigninLogs | ";
+        assert_eq!(
+            context(query, query.len()).trigger,
+            CompletionTrigger::AfterPipe
+        );
+        let query = "SigninLogs | summarize count() by Us";
+        let by = context(query, query.len());
+        assert_eq!(by.trigger, CompletionTrigger::ByClause);
+        assert_eq!(&query[by.edit_start..by.edit_end], "Us");
+        let query = "SigninLogs | extend x = strcat(";
+        assert_eq!(
+            context(query, query.len()).trigger,
+            CompletionTrigger::FunctionArguments
+        );
+
+        // The replaced range covers the rest of the word
+        let query = "SigninLogs | project TimeGen";
+        let middle = context(query, query.find("Gen").unwrap());
+        assert_eq!(&query[middle.edit_start..middle.edit_end], "TimeGen");
+        assert_eq!(middle.trigger, CompletionTrigger::Other);
+    }
+
+    #[test]
+    #[ignore = "requires native library"]
+    fn test_resolve_completion() {
+        let validator = KqlValidator::new().expect("Failed to create validator");
+        let schema = Schema::new().table(
+            crate::schema::Table::new("SigninLogs")
+                .with_column("TimeGenerated", "datetime")
+                .description("Microsoft Entra sign-ins"),
+        );
+
+        let query = "SigninLogs | where TimeGenerated > ag";
+        let completions = validator
+            .get_completions(query, query.len(), Some(&schema))
+            .unwrap();
+        let ago = completions
+            .items
+            .iter()
+            .find(|i| i.label.starts_with("ago"))
+            .expect("ago should be completed");
+        assert_eq!(ago.documentation, None);
+        let resolved = validator.resolve_completion(ago, Some(&schema)).unwrap();
+        assert_eq!(resolved.label, ago.label);
+        assert_eq!(resolved.edit_start, ago.edit_start);
+        assert!(resolved.detail.unwrap().starts_with("ago("));
+        assert!(resolved.documentation.unwrap().contains("```kql"));
+
+        let query = "Sign";
+        let completions = validator
+            .get_completions(query, query.len(), Some(&schema))
+            .unwrap();
+        let table = completions
+            .items
+            .iter()
+            .find(|i| i.label == "SigninLogs")
+            .unwrap();
+        let resolved = validator.resolve_completion(table, Some(&schema)).unwrap();
+        let documentation = resolved.documentation.unwrap();
+        assert!(documentation.contains("TimeGenerated: datetime"));
+        assert!(documentation.contains("Microsoft Entra sign-ins"));
+    }
+
+    #[test]
+    #[ignore = "requires native library"]
+    fn test_get_expression_type() {
+        let validator = KqlValidator::new().expect("Failed to create validator");
+        let schema = Schema::new().table(
+            crate::schema::Table::new("SigninLogs")
+                .with_column("TimeGenerated", "datetime")
+                .with_column("UserPrincipalName", "string"),
+        );
+        let query = "SigninLogs | where TimeGenerated > ago(1h) | project UserPrincipalName";
+        let at = |needle: &str| query.find(needle).unwrap() + 1;
+
+        let column = validator
+            .get_expression_type(query, at("TimeGenerated"), &schema)
+            .unwrap()
+            .unwrap();
+        assert_eq!(column.scalar_name(), Some("datetime"));
+        assert_eq!(&query[column.start..column.end], "TimeGenerated");
+
+        let project = validator
+            .get_expression_type(query, at("project"), &schema)
+            .unwrap()
+            .unwrap();
+        let columns: Vec<_> = project
+            .columns()
+            .unwrap()
+            .iter()
+            .map(|c| (c.name.as_str(), c.data_type.clone()))
+            .collect();
+        assert_eq!(columns, [("UserPrincipalName", KqlType::String)]);
+
+        assert_eq!(
+            validator.get_expression_type("   ", 1, &schema).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    #[ignore = "requires native library"]
+    fn test_get_inlay_hints() {
+        use crate::InlayHintKind;
+
+        let validator = KqlValidator::new().expect("Failed to create validator");
+        let schema =
+            Schema::new().table(crate::schema::Table::new("T").with_column("Name", "string"));
+        let query = "let n = 5;\nT | extend Part = substring(Name, n) | project Part";
+
+        let hints = validator
+            .get_inlay_hints(query, &schema, 0..query.len())
+            .expect("Inlay hints failed");
+        let hints: Vec<_> = hints
+            .iter()
+            .map(|h| (h.kind, h.position, h.label.as_str()))
+            .collect();
+        assert_eq!(hints[0], (InlayHintKind::Type, 5, ": long"));
+        let parameters = hints
+            .iter()
+            .filter(|h| h.0 == InlayHintKind::Parameter)
+            .count();
+        assert_eq!(parameters, 2);
+        let columns: Vec<_> = hints
+            .iter()
+            .filter(|h| h.0 == InlayHintKind::ColumnCount)
+            .map(|h| h.2)
+            .collect();
+        assert_eq!(columns, ["2 columns", "1 column"]);
+
+        let first_line = validator.get_inlay_hints(query, &schema, 0..10).unwrap();
+        assert_eq!(first_line.len(), 1);
+    }
+
+    #[test]
+    #[ignore = "requires native library"]
+    fn test_get_definition() {
+        use crate::navigation::DefinitionKind;
+
+        let validator = KqlValidator::new().expect("Failed to create validator");
+        let query = "let threshold = 5;\n\
+                     let over = (T: (Count: long), limit: long) { T | where Count > limit };\n\
+                     print Count = 10 | invoke over(threshold)";
+
+        let at = |needle: &str| query.rfind(needle).unwrap() + 1;
+        let span = |needle: &str| {
+            let start = query.find(needle).unwrap();
+            (start, start + needle.len())
+        };
+
+        let variable = validator
+            .get_definition(query, at("threshold"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(variable.kind, DefinitionKind::Variable);
+        assert_eq!((variable.start, variable.end), span("threshold"));
+
+        let function = validator
+            .get_definition(query, at("over"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(function.kind, DefinitionKind::Function);
+        assert_eq!((function.start, function.end), span("over"));
+
+        let parameter = validator
+            .get_definition(query, at("limit }"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(parameter.kind, DefinitionKind::Parameter);
+        assert_eq!((parameter.start, parameter.end), span("limit"));
+
+        assert_eq!(validator.get_definition(query, at("print")).unwrap(), None);
     }
 
     #[test]
@@ -491,6 +5413,126 @@ mod tests {
         }
     }
 
+    #[test]
+    #[ignore = "requires native library"]
+    fn test_classify_chunks() {
+        let validator = KqlValidator::new().expect("Failed to create validator");
+        let query = "T | where x in (1, 2, 3)\n".repeat(10_000);
+
+        let mut streamed = Vec::new();
+        let mut batches = 0;
+        validator
+            .classify_chunks(&query, |batch| {
+                batches += 1;
+                streamed.extend(batch.iter().map(|s| (s.start, s.length, s.kind)));
+            })
+            .expect("Classification failed");
+        assert!(batches > 1);
+
+        let mut whole: Vec<_> = validator
+            .get_classifications(&query)
+            .expect("Classification failed")
+            .spans
+            .iter()
+            .map(|s| (s.start, s.length, s.kind))
+            .collect();
+        whole.sort_by_key(|s| s.0);
+        assert_eq!(streamed, whole);
+    }
+
+    #[test]
+    fn test_classify_batches() {
+        use crate::ClassificationKind;
+
+        // Whole-query fallback: sorted, then split into batches
+        let spans = (0..CLASSIFY_BATCH_SPANS + 10)
+            .rev()
+            .map(|start| ClassifiedSpan {
+                start,
+                length: 1,
+                kind: ClassificationKind::PlainText,
+            })
+            .collect();
+        let mut batches: Vec<Vec<usize>> = Vec::new();
+        pass_in_batches(spans, &mut |batch| {
+            batches.push(batch.iter().map(|s| s.start).collect());
+        });
+        assert_eq!(batches.len(), 2);
+        assert_eq!(batches[1].len(), 10);
+        assert!(batches.concat().windows(2).all(|w| w[0] < w[1]));
+
+        // Streamed batches arrive with native UTF-16 offsets
+        let query = "print '😀' | take 1";
+        let mut received = Vec::new();
+        let mut on_batch = |batch: &[ClassifiedSpan]| {
+            received.extend(batch.iter().map(|s| (s.start, s.length, s.kind)));
+        };
+        let mut stream = BatchStream {
+            index: positions::native_index(query),
+            on_batch: &mut on_batch,
+            error: None,
+            panic: None,
+        };
+        let context: *mut c_void = ptr::addr_of_mut!(stream).cast();
+        let send = |json: &str| {
+            receive_batch(context, json.as_ptr(), c_int::try_from(json.len()).unwrap())
+        };
+        let batch = r#"{"spans":[
+            {"start":13,"length":4,"kind":"QueryOperator"},
+            {"start":0,"length":5,"kind":"PlainText"}]}"#;
+        assert_eq!(send(batch), 0);
+        assert_eq!(send(r#"{"spans":[]}"#), 0);
+        assert_eq!(send("not json"), 1);
+        assert!(matches!(stream.error, Some(Error::Json(_))));
+        drop(stream);
+        assert_eq!(
+            received,
+            [
+                (0, 5, ClassificationKind::PlainText),
+                (12, 4, ClassificationKind::QueryOperator)
+            ]
+        );
+
+        // A panic in the handler stops the stream instead of unwinding
+        let mut on_batch = |_: &[ClassifiedSpan]| panic!("handler failed");
+        let mut stream = BatchStream {
+            index: None,
+            on_batch: &mut on_batch,
+            error: None,
+            panic: None,
+        };
+        let json = r#"{"spans":[{"start":0,"length":1,"kind":"PlainText"}]}"#;
+        let stop = receive_batch(
+            ptr::addr_of_mut!(stream).cast(),
+            json.as_ptr(),
+            c_int::try_from(json.len()).unwrap(),
+        );
+        assert_eq!(stop, 1);
+        assert!(stream.panic.is_some());
+    }
+
+    #[test]
+    #[ignore = "requires native library"]
+    fn test_get_classifications_contiguous() {
+        use crate::ClassificationKind;
+
+        let validator = KqlValidator::new().expect("Failed to create validator");
+        let query = "SecurityEvent  | where x > 'é' // done\n| take 10";
+        let options = ClassificationOptions::default().contiguous(true);
+
+        let result = validator
+            .get_classifications_with_options(query, &options)
+            .expect("Classification failed");
+        let mut position = 0;
+        for span in &result.spans {
+            assert_eq!(span.start, position);
+            assert!(span.length > 0);
+            position += span.length;
+        }
+        assert_eq!(position, query.chars().count());
+        assert_eq!(result.spans[1].kind, ClassificationKind::PlainText);
+    }
+
     #[test]
     #[ignore = "requires native library"]
     fn test_get_completions_after_pipe() {
@@ -0,0 +1,340 @@
+//! WebAssembly backend (experimental)
+//!
+//! Enabled by the `wasm` feature. [`WasmValidator`] runs a WASI build of the
+//! language service under wasmtime instead of loading the native library,
+//! so validation works where the .NET runtime cannot be installed or native
+//! libraries cannot be loaded. The module is built from `dotnet/wasi`:
+//!
+//! ```text
+//! dotnet workload install wasi-experimental
+//! dotnet publish dotnet/wasi -c Release
+//! ```
+//!
+//! The module speaks the [`worker`](crate::worker) protocol on its stdin
+//! and stdout. Every call runs a fresh instance, paying the .NET runtime's
+//! startup each time, so validate many queries at once with
+//! [`validate_many`](WasmValidator::validate_many) where possible. The
+//! instance is sandboxed: it sees no files, environment or network.
+
+use crate::backend::KqlEngine;
+use crate::classification::ClassificationResult;
+use crate::completion::CompletionResult;
+use crate::error::Error;
+use crate::schema::Schema;
+use crate::spelling;
+use crate::types::ValidationResult;
+use crate::worker::{read_frame, write_frame, Request, Response};
+use serde::de::DeserializeOwned;
+use std::borrow::Cow;
+use std::path::Path;
+use wasmtime::{Engine, Linker, Module, Store};
+use wasmtime_wasi::pipe::{MemoryInputPipe, MemoryOutputPipe};
+use wasmtime_wasi::preview1::{self, WasiP1Ctx};
+use wasmtime_wasi::{I32Exit, WasiCtxBuilder};
+
+/// Environment variable naming the WebAssembly module
+pub const WASM_PATH_ENV: &str = "KQL_LANGUAGE_TOOLS_WASM";
+
+/// Most output one run may write before it is cut off
+const MAX_OUTPUT: usize = 256 * 1024 * 1024;
+
+/// A validator running the language service as WebAssembly
+///
+/// The module is compiled once, when the validator is created.
+///
+/// # Example
+///
+/// ```no_run
+/// use kql_language_tools::wasm::WasmValidator;
+///
+/// let validator = WasmValidator::from_file("KqlLanguageWasi.wasm")?;
+/// let results = validator.validate_many(&["T | take 10", "T | where"])?;
+/// assert!(results[0].is_valid());
+/// # Ok::<(), kql_language_tools::Error>(())
+/// ```
+pub struct WasmValidator {
+    engine: Engine,
+    module: Module,
+    linker: Linker<WasiP1Ctx>,
+}
+
+impl std::fmt::Debug for WasmValidator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WasmValidator")
+            .field("module", &self.module.name())
+            .finish_non_exhaustive()
+    }
+}
+
+impl WasmValidator {
+    /// Load the module named by the `KQL_LANGUAGE_TOOLS_WASM` environment
+    /// variable
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the variable is not set or the module fails to
+    /// load.
+    pub fn new() -> Result<Self, Error> {
+        let path = std::env::var_os(WASM_PATH_ENV)
+            .ok_or_else(|| wasm_error(format!("{WASM_PATH_ENV} is not set")))?;
+        Self::from_file(path)
+    }
+
+    /// Load the module from a `.wasm` file
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read or is not a valid
+    /// module.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let bytes = std::fs::read(path)
+            .map_err(|e| wasm_error(format!("Failed to read {}: {e}", path.display())))?;
+        Self::from_bytes(&bytes)
+    }
+
+    /// Load the module from its binary or text format
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the bytes are not a valid module.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        let engine = Engine::default();
+        let module = Module::new(&engine, bytes).map_err(wasm_error)?;
+        let mut linker = Linker::new(&engine);
+        preview1::add_to_linker_sync(&mut linker, |ctx| ctx).map_err(wasm_error)?;
+        Ok(Self {
+            engine,
+            module,
+            linker,
+        })
+    }
+
+    /// Validate a query for syntax errors
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the module fails.
+    pub fn validate_syntax(&self, query: &str) -> Result<ValidationResult, Error> {
+        self.call(Request::ValidateSyntax {
+            query: Cow::Borrowed(query),
+        })
+    }
+
+    /// Validate a query against a schema
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the module fails.
+    pub fn validate_with_schema(
+        &self,
+        query: &str,
+        schema: &Schema,
+    ) -> Result<ValidationResult, Error> {
+        schema.check_types()?;
+        let mut result: ValidationResult = self.call(Request::ValidateWithSchema {
+            query: Cow::Borrowed(query),
+            schema: Cow::Borrowed(schema),
+        })?;
+        spelling::suggest_names(query, schema, &mut result);
+        Ok(result)
+    }
+
+    /// Validate several queries for syntax errors in one run of the module
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the module fails on any of the queries.
+    pub fn validate_many(&self, queries: &[&str]) -> Result<Vec<ValidationResult>, Error> {
+        let requests: Vec<_> = queries
+            .iter()
+            .map(|query| Request::ValidateSyntax {
+                query: Cow::Borrowed(*query),
+            })
+            .collect();
+        self.run(&requests)?.into_iter().map(decode).collect()
+    }
+
+    /// Get completions at a cursor position
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the module fails.
+    pub fn get_completions(
+        &self,
+        query: &str,
+        cursor_position: usize,
+        schema: Option<&Schema>,
+    ) -> Result<CompletionResult, Error> {
+        if let Some(schema) = schema {
+            schema.check_types()?;
+        }
+        self.call(Request::GetCompletions {
+            query: Cow::Borrowed(query),
+            cursor: cursor_position,
+            schema: schema.map(Cow::Borrowed),
+        })
+    }
+
+    /// Get syntax classifications for highlighting
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the module fails.
+    pub fn get_classifications(&self, query: &str) -> Result<ClassificationResult, Error> {
+        self.call(Request::GetClassifications {
+            query: Cow::Borrowed(query),
+        })
+    }
+
+    /// Run one request and decode its result
+    fn call<T: DeserializeOwned>(&self, request: Request<'_>) -> Result<T, Error> {
+        let response = self
+            .run(&[request])?
+            .pop()
+            .expect("run returns one response per request");
+        decode(response)
+    }
+
+    /// Run a fresh instance over `requests`, returning one response each
+    fn run(&self, requests: &[Request<'_>]) -> Result<Vec<Response>, Error> {
+        let mut input = Vec::new();
+        for request in requests {
+            write_frame(&mut input, &serde_json::to_vec(request)?).map_err(wasm_error)?;
+        }
+        let stdout = MemoryOutputPipe::new(MAX_OUTPUT);
+        let ctx = WasiCtxBuilder::new()
+            .stdin(MemoryInputPipe::new(input))
+            .stdout(stdout.clone())
+            .build_p1();
+
+        let mut store = Store::new(&self.engine, ctx);
+        let instance = self
+            .linker
+            .instantiate(&mut store, &self.module)
+            .map_err(wasm_error)?;
+        let start = instance
+            .get_typed_func::<(), ()>(&mut store, "_start")
+            .map_err(wasm_error)?;
+        if let Err(e) = start.call(&mut store, ()) {
+            match e.downcast_ref::<I32Exit>() {
+                Some(I32Exit(0)) => {}
+                Some(I32Exit(code)) => {
+                    return Err(wasm_error(format!("module exited with {code}")))
+                }
+                None => return Err(wasm_error(e)),
+            }
+        }
+        drop(store);
+
+        let output = stdout.contents();
+        let mut reader = &output[..];
+        let mut responses = Vec::with_capacity(requests.len());
+        // The whole output is in memory, so no frame can be longer than it
+        while let Some(frame) = read_frame(&mut reader, output.len()).map_err(wasm_error)? {
+            responses.push(serde_json::from_slice(&frame)?);
+        }
+        if responses.len() != requests.len() {
+            return Err(wasm_error(format!(
+                "module answered {} of {} requests",
+                responses.len(),
+                requests.len()
+            )));
+        }
+        Ok(responses)
+    }
+}
+
+impl KqlEngine for WasmValidator {
+    fn validate_syntax(&self, query: &str) -> Result<ValidationResult, Error> {
+        WasmValidator::validate_syntax(self, query)
+    }
+
+    fn validate_with_schema(
+        &self,
+        query: &str,
+        schema: &Schema,
+    ) -> Result<ValidationResult, Error> {
+        WasmValidator::validate_with_schema(self, query, schema)
+    }
+
+    fn get_completions(
+        &self,
+        query: &str,
+        cursor_position: usize,
+        schema: Option<&Schema>,
+    ) -> Result<CompletionResult, Error> {
+        WasmValidator::get_completions(self, query, cursor_position, schema)
+    }
+
+    fn get_classifications(&self, query: &str) -> Result<ClassificationResult, Error> {
+        WasmValidator::get_classifications(self, query)
+    }
+}
+
+/// Decode a response into its result, or the error it reports
+fn decode<T: DeserializeOwned>(response: Response) -> Result<T, Error> {
+    if response.ok {
+        Ok(serde_json::from_value(response.result.unwrap_or_default())?)
+    } else {
+        Err(wasm_error(
+            response.error.as_deref().unwrap_or("module call failed"),
+        ))
+    }
+}
+
+fn wasm_error(message: impl std::fmt::Display) -> Error {
+    Error::Wasm {
+        message: message.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A WASI command answering every request with a valid result
+    ///
+    /// It reads and discards stdin one frame header and payload at a time,
+    /// writing the canned response after each.
+    const ECHO_VALID: &str = r#"
+        (module
+          (import "wasi_snapshot_preview1" "fd_read"
+            (func $fd_read (param i32 i32 i32 i32) (result i32)))
+          (import "wasi_snapshot_preview1" "fd_write"
+            (func $fd_write (param i32 i32 i32 i32) (result i32)))
+          (memory (export "memory") 1)
+          ;; iovec for reads at 0, read count at 8, scratch at 1024
+          ;; iovec for the response at 16, response frame at 64
+          (data (i32.const 64) "\34\00\00\00{\"ok\":true,\"result\":{\"valid\":true,\"diagnostics\":[]}}")
+          (func $read (param $len i32) (result i32)
+            (i32.store (i32.const 0) (i32.const 1024))
+            (i32.store (i32.const 4) (local.get $len))
+            (drop (call $fd_read (i32.const 0) (i32.const 0) (i32.const 1) (i32.const 8)))
+            (i32.load (i32.const 8)))
+          (func (export "_start")
+            (loop $next
+              (if (i32.eq (call $read (i32.const 4)) (i32.const 4))
+                (then
+                  (drop (call $read (i32.load (i32.const 1024))))
+                  (i32.store (i32.const 16) (i32.const 64))
+                  (i32.store (i32.const 20) (i32.const 56))
+                  (drop (call $fd_write (i32.const 1) (i32.const 16) (i32.const 1) (i32.const 8)))
+                  (br $next))))))
+    "#;
+
+    #[test]
+    fn test_run_module() {
+        let validator = WasmValidator::from_bytes(ECHO_VALID.as_bytes()).unwrap();
+        assert!(validator.validate_syntax("T | take 10").unwrap().is_valid());
+        let results = validator.validate_many(&["T", "U", "V"]).unwrap();
+        assert_eq!(results.len(), 3);
+
+        // A module exiting without answering is an error
+        let silent = WasmValidator::from_bytes(br#"(module (func (export "_start")))"#).unwrap();
+        assert!(matches!(
+            silent.validate_syntax("T"),
+            Err(Error::Wasm { message }) if message.contains("0 of 1")
+        ));
+    }
+}
@@ -0,0 +1,1038 @@
+//! Out-of-process validation
+//!
+//! Enabled by the `worker` feature. [`WorkerValidator`] runs the native
+//! library in a `kql-worker` child process instead of loading it into the
+//! calling process, so a crash or out-of-memory abort inside the .NET
+//! component kills only the child. The call that was running fails with
+//! [`Error::Worker`], and the next call starts a fresh process. A worker
+//! that does not answer within the call timeout is killed the same way.
+//! [`KqlWorkerPool`] spreads calls across several worker processes.
+//!
+//! A [`KqlValidator`] is switched to a worker with
+//! [`with_worker`](KqlValidator::with_worker): validation, completion and
+//! classification then run in the worker, with the validator's settings.
+//!
+//! ```no_run
+//! use kql_language_tools::worker::WorkerValidator;
+//! use kql_language_tools::{Dialect, KqlValidator};
+//!
+//! fn open_validator(isolated: bool) -> Result<KqlValidator, kql_language_tools::Error> {
+//!     let validator = KqlValidator::new()?.with_dialect(Dialect::LogAnalytics);
+//!     if isolated {
+//!         validator.with_worker(WorkerValidator::builder())
+//!     } else {
+//!         Ok(validator)
+//!     }
+//! }
+//! ```
+//!
+//! The two processes exchange frames over the child's stdin and stdout:
+//! a little-endian `u32` byte length followed by that many bytes of JSON.
+//! Each request frame is answered by exactly one response frame. A response
+//! longer than the [frame limit](WorkerBuilder::max_frame_size), such as
+//! the output of a crashing runtime, is treated as the worker dying:
+//!
+//! ```text
+//! -> {"op":"validate_syntax","query":"T | take 10"}
+//! <- {"ok":true,"result":{"valid":true,"diagnostics":[]}}
+//! ```
+//!
+//! The `kql-worker` binary runs [`serve_stdio`]:
+//!
+//! ```text
+//! cargo install kql-language-tools --features worker --bin kql-worker
+//! ```
+
+use crate::analysis::QueryAnalysis;
+use crate::backend::KqlEngine;
+use crate::classification::ClassificationResult;
+use crate::completion::CompletionResult;
+use crate::dialect::Dialect;
+use crate::error::Error;
+use crate::format::FormatOptions;
+use crate::options::ValidationOptions;
+use crate::positions::OffsetEncoding;
+use crate::schema::Schema;
+use crate::types::ValidationResult;
+use crate::validator::KqlValidator;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::sync::{Mutex, MutexGuard, PoisonError, TryLockError};
+use std::thread;
+use std::time::Duration;
+
+/// Environment variable naming the worker executable
+pub const WORKER_PATH_ENV: &str = "KQL_WORKER_PATH";
+
+/// File name of the worker executable
+const WORKER_EXE: &str = "kql-worker";
+
+/// How long a call waits for the worker's answer by default
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Largest response frame accepted from a worker by default
+const DEFAULT_MAX_FRAME_SIZE: usize = 64 * 1024 * 1024;
+
+/// A request frame sent to the worker
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub(crate) enum Request<'a> {
+    /// Set the options of subsequent requests; sent once after spawning
+    Configure {
+        options: Cow<'a, ValidationOptions>,
+        offsets: OffsetEncoding,
+    },
+    /// Check that the worker is responsive
+    Ping,
+    ValidateSyntax {
+        query: Cow<'a, str>,
+    },
+    ValidateWithSchema {
+        query: Cow<'a, str>,
+        schema: Cow<'a, Schema>,
+    },
+    GetCompletions {
+        query: Cow<'a, str>,
+        cursor: usize,
+        schema: Option<Cow<'a, Schema>>,
+    },
+    GetClassifications {
+        query: Cow<'a, str>,
+    },
+    Format {
+        query: Cow<'a, str>,
+        options: FormatOptions,
+    },
+    Analyze {
+        query: Cow<'a, str>,
+        schema: Cow<'a, Schema>,
+    },
+}
+
+/// A response frame sent back by the worker
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct Response {
+    pub(crate) ok: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) result: Option<serde_json::Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) error: Option<String>,
+}
+
+/// Write one length-prefixed frame
+pub(crate) fn write_frame(writer: &mut impl Write, payload: &[u8]) -> io::Result<()> {
+    let len = u32::try_from(payload.len())
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "frame too large"))?;
+    writer.write_all(&len.to_le_bytes())?;
+    writer.write_all(payload)?;
+    writer.flush()
+}
+
+/// Read one length-prefixed frame of at most `max_len` bytes, or `None` at
+/// end of stream
+pub(crate) fn read_frame(reader: &mut impl Read, max_len: usize) -> io::Result<Option<Vec<u8>>> {
+    let mut len = [0u8; 4];
+    match reader.read_exact(&mut len) {
+        Ok(()) => {}
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e),
+    }
+    let len = u32::from_le_bytes(len) as usize;
+    if len > max_len {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("frame of {len} bytes exceeds the {max_len} byte limit"),
+        ));
+    }
+    let mut payload = vec![0u8; len];
+    reader.read_exact(&mut payload)?;
+    Ok(Some(payload))
+}
+
+fn worker_error(message: impl std::fmt::Display) -> Error {
+    Error::Worker {
+        message: message.to_string(),
+    }
+}
+
+/// Builder for a [`WorkerValidator`]
+///
+/// Spawn it directly, or pass it to [`KqlValidator::with_worker`] to run a
+/// validator's calls in the worker with the validator's settings.
+///
+/// The worker executable is taken from [`program`](Self::program), then the
+/// `KQL_WORKER_PATH` environment variable, then a `kql-worker` executable
+/// next to the current one, and finally `kql-worker` on `PATH`.
+#[derive(Debug, Clone)]
+pub struct WorkerBuilder {
+    program: Option<PathBuf>,
+    options: ValidationOptions,
+    offsets: OffsetEncoding,
+    timeout: Duration,
+    max_frame_size: usize,
+}
+
+impl Default for WorkerBuilder {
+    fn default() -> Self {
+        Self {
+            program: None,
+            options: ValidationOptions::default(),
+            offsets: OffsetEncoding::default(),
+            timeout: DEFAULT_TIMEOUT,
+            max_frame_size: DEFAULT_MAX_FRAME_SIZE,
+        }
+    }
+}
+
+impl WorkerBuilder {
+    /// Set the worker executable to run
+    #[must_use]
+    pub fn program(mut self, path: impl Into<PathBuf>) -> Self {
+        self.program = Some(path.into());
+        self
+    }
+
+    /// Set the dialect queries are validated against
+    #[must_use]
+    pub fn dialect(mut self, dialect: Dialect) -> Self {
+        self.options.dialect = dialect;
+        self
+    }
+
+    /// Set the unit of offsets passed to and returned from the worker
+    ///
+    /// See [`KqlValidator::with_offset_encoding`].
+    #[must_use]
+    pub fn offset_encoding(mut self, encoding: OffsetEncoding) -> Self {
+        self.offsets = encoding;
+        self
+    }
+
+    /// Use all of a validator's analysis options
+    pub(crate) fn options(mut self, options: ValidationOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Set how long a call waits for the worker to answer
+    ///
+    /// A worker that takes longer is killed, the call fails with
+    /// [`Error::TimedOut`], and the next call starts a new worker. Defaults
+    /// to 30 seconds; [`Duration::MAX`] waits forever.
+    #[must_use]
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Set the largest response accepted from the worker (default 64MB)
+    ///
+    /// A longer response is taken as a sign the worker is broken, such as a
+    /// crashing runtime writing to its output: the call fails with
+    /// [`Error::Worker`] and the next call starts a new worker.
+    #[must_use]
+    pub fn max_frame_size(mut self, bytes: usize) -> Self {
+        self.max_frame_size = bytes;
+        self
+    }
+
+    /// Start the worker process
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the worker cannot be started or fails to load
+    /// the native library.
+    pub fn spawn(self) -> Result<WorkerValidator, Error> {
+        let mut validator = WorkerValidator {
+            program: self.program.unwrap_or_else(default_program),
+            options: self.options,
+            offsets: self.offsets,
+            timeout: self.timeout,
+            max_frame_size: self.max_frame_size,
+            process: Mutex::new(None),
+        };
+        let process = WorkerProcess::spawn(&validator)?;
+        validator.process = Mutex::new(Some(process));
+        Ok(validator)
+    }
+
+    /// Start a pool of `size` worker processes
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if any worker cannot be started or fails to load
+    /// the native library.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is zero.
+    pub fn spawn_pool(self, size: usize) -> Result<KqlWorkerPool, Error> {
+        assert!(size > 0, "a worker pool needs at least one worker");
+        let workers = (0..size)
+            .map(|_| {
+                self.clone().spawn().map(|validator| PoolSlot {
+                    validator,
+                    in_flight: AtomicUsize::new(0),
+                })
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(KqlWorkerPool { workers })
+    }
+}
+
+/// Locate the worker executable when none was configured
+fn default_program() -> PathBuf {
+    if let Some(path) = std::env::var_os(WORKER_PATH_ENV) {
+        return PathBuf::from(path);
+    }
+    let exe = format!("{WORKER_EXE}{}", std::env::consts::EXE_SUFFIX);
+    std::env::current_exe()
+        .ok()
+        .and_then(|current| current.parent().map(|dir| dir.join(&exe)))
+        .filter(|candidate| candidate.is_file())
+        .unwrap_or_else(|| PathBuf::from(exe))
+}
+
+/// A frame read from the worker's output, or why none could be read
+type Frame = io::Result<Option<Vec<u8>>>;
+
+/// A running worker process
+struct WorkerProcess {
+    child: Child,
+    stdin: BufWriter<ChildStdin>,
+    /// Frames read from the worker's output by a reader thread, so waiting
+    /// for an answer can time out
+    responses: Receiver<Frame>,
+    timeout: Duration,
+    /// Set once a frame could not be exchanged; the process is then discarded
+    broken: bool,
+}
+
+impl WorkerProcess {
+    fn spawn(worker: &WorkerValidator) -> Result<Self, Error> {
+        let program = &worker.program;
+        log::debug!("Starting KQL worker {}", program.display());
+        let mut child = Command::new(program)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .spawn()
+            .map_err(|e| worker_error(format!("failed to start {}: {e}", program.display())))?;
+        let (Some(stdin), Some(stdout)) = (child.stdin.take(), child.stdout.take()) else {
+            return Err(worker_error("worker stdio was not captured"));
+        };
+        let mut process = Self {
+            child,
+            stdin: BufWriter::new(stdin),
+            responses: read_frames(stdout, worker.max_frame_size)?,
+            timeout: worker.timeout,
+            broken: false,
+        };
+        process.call::<()>(&Request::Configure {
+            options: Cow::Borrowed(&worker.options),
+            offsets: worker.offsets,
+        })?;
+        Ok(process)
+    }
+
+    /// Send a request and wait for its response
+    fn call<T: DeserializeOwned>(&mut self, request: &Request<'_>) -> Result<T, Error> {
+        let payload = serde_json::to_vec(request)?;
+        write_frame(&mut self.stdin, &payload).map_err(|e| self.exited(&e.to_string()))?;
+        let response = match self.responses.recv_timeout(self.timeout) {
+            Ok(frame) => frame.map_err(|e| self.exited(&e.to_string()))?,
+            Err(RecvTimeoutError::Timeout) => {
+                log::warn!("KQL worker did not answer within {:?}", self.timeout);
+                // Dropping the process kills it
+                self.broken = true;
+                return Err(Error::TimedOut);
+            }
+            Err(RecvTimeoutError::Disconnected) => None,
+        }
+        .ok_or_else(|| self.exited("worker closed its output"))?;
+        let response: Response = serde_json::from_slice(&response)?;
+        if response.ok {
+            let result = response.result.unwrap_or_default();
+            Ok(serde_json::from_value(result)?)
+        } else {
+            Err(worker_error(
+                response.error.as_deref().unwrap_or("worker call failed"),
+            ))
+        }
+    }
+
+    /// Describe a broken connection, including the exit status if the worker died
+    fn exited(&mut self, reason: &str) -> Error {
+        self.broken = true;
+        match self.child.try_wait() {
+            Ok(Some(status)) => worker_error(format!("worker exited ({status}): {reason}")),
+            _ => worker_error(reason),
+        }
+    }
+}
+
+/// Read frames from the worker's output on a thread until it closes
+fn read_frames(stdout: ChildStdout, max_len: usize) -> Result<Receiver<Frame>, Error> {
+    let (sender, receiver) = mpsc::channel();
+    let mut stdout = BufReader::new(stdout);
+    thread::Builder::new()
+        .name("kql-worker-reader".to_string())
+        .spawn(move || loop {
+            let frame = read_frame(&mut stdout, max_len);
+            let done = !matches!(frame, Ok(Some(_)));
+            if sender.send(frame).is_err() || done {
+                break;
+            }
+        })
+        .map_err(|e| worker_error(format!("failed to start reader thread: {e}")))?;
+    Ok(receiver)
+}
+
+impl Drop for WorkerProcess {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// A validator running the native library in a child process
+///
+/// Calls are sent to the worker one at a time. If the worker dies, the
+/// call fails with [`Error::Worker`] and the next call respawns it; the
+/// failed call is not retried, since the same query would likely crash
+/// the new worker too. A worker that does not answer within the
+/// [timeout](WorkerBuilder::timeout) is killed and the call fails with
+/// [`Error::TimedOut`].
+///
+/// # Example
+///
+/// ```no_run
+/// use kql_language_tools::worker::WorkerValidator;
+///
+/// let validator = WorkerValidator::builder().spawn()?;
+/// let result = validator.validate_syntax("SecurityEvent | take 10")?;
+/// assert!(result.is_valid());
+/// # Ok::<(), kql_language_tools::Error>(())
+/// ```
+pub struct WorkerValidator {
+    program: PathBuf,
+    options: ValidationOptions,
+    offsets: OffsetEncoding,
+    timeout: Duration,
+    max_frame_size: usize,
+    process: Mutex<Option<WorkerProcess>>,
+}
+
+impl WorkerValidator {
+    /// Create a builder for a worker validator
+    #[must_use]
+    pub fn builder() -> WorkerBuilder {
+        WorkerBuilder::default()
+    }
+
+    /// Get the worker executable
+    #[must_use]
+    pub fn program(&self) -> &Path {
+        &self.program
+    }
+
+    /// Get the dialect queries are validated against
+    #[must_use]
+    pub fn dialect(&self) -> Dialect {
+        self.options.dialect
+    }
+
+    /// Get the unit of offsets passed to and returned from the worker
+    #[must_use]
+    pub fn offset_encoding(&self) -> OffsetEncoding {
+        self.offsets
+    }
+
+    /// Get how long a call waits for the worker to answer
+    #[must_use]
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    /// Validate a query for syntax errors
+    ///
+    /// See [`KqlValidator::validate_syntax`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the worker or the native call fails.
+    pub fn validate_syntax(&self, query: &str) -> Result<ValidationResult, Error> {
+        self.call(&Request::ValidateSyntax {
+            query: query.into(),
+        })
+    }
+
+    /// Validate a query against a schema
+    ///
+    /// See [`KqlValidator::validate_with_schema`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the worker or the native call fails.
+    pub fn validate_with_schema(
+        &self,
+        query: &str,
+        schema: &Schema,
+    ) -> Result<ValidationResult, Error> {
+        self.call(&Request::ValidateWithSchema {
+            query: query.into(),
+            schema: Cow::Borrowed(schema),
+        })
+    }
+
+    /// Get completions at a cursor position
+    ///
+    /// See [`KqlValidator::get_completions`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the worker or the native call fails.
+    pub fn get_completions(
+        &self,
+        query: &str,
+        cursor_position: usize,
+        schema: Option<&Schema>,
+    ) -> Result<CompletionResult, Error> {
+        self.call(&Request::GetCompletions {
+            query: query.into(),
+            cursor: cursor_position,
+            schema: schema.map(Cow::Borrowed),
+        })
+    }
+
+    /// Get syntax classifications for highlighting
+    ///
+    /// See [`KqlValidator::get_classifications`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the worker or the native call fails.
+    pub fn get_classifications(&self, query: &str) -> Result<ClassificationResult, Error> {
+        self.call(&Request::GetClassifications {
+            query: query.into(),
+        })
+    }
+
+    /// Format a query
+    ///
+    /// See [`KqlValidator::format`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the worker or the native call fails.
+    pub fn format(&self, query: &str, options: &FormatOptions) -> Result<String, Error> {
+        self.call(&Request::Format {
+            query: query.into(),
+            options: *options,
+        })
+    }
+
+    /// Analyze the tables, columns and functions a query references
+    ///
+    /// See [`KqlValidator::analyze`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the worker or the native call fails.
+    pub fn analyze(&self, query: &str, schema: &Schema) -> Result<QueryAnalysis, Error> {
+        self.call(&Request::Analyze {
+            query: query.into(),
+            schema: Cow::Borrowed(schema),
+        })
+    }
+
+    /// Check that the worker responds, restarting it if it had died
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the worker cannot be restarted or does not
+    /// answer.
+    pub fn ping(&self) -> Result<(), Error> {
+        self.call(&Request::Ping)
+    }
+
+    /// Ping the worker unless a call is running, returning `None` if so
+    fn try_ping(&self) -> Option<Result<(), Error>> {
+        let mut guard = match self.process.try_lock() {
+            Ok(guard) => guard,
+            Err(TryLockError::Poisoned(poisoned)) => poisoned.into_inner(),
+            Err(TryLockError::WouldBlock) => return None,
+        };
+        Some(self.call_locked(&mut guard, &Request::Ping))
+    }
+
+    /// Send a request, starting a new worker if the previous one died
+    fn call<T: DeserializeOwned>(&self, request: &Request<'_>) -> Result<T, Error> {
+        let mut guard = self.process.lock().unwrap_or_else(PoisonError::into_inner);
+        self.call_locked(&mut guard, request)
+    }
+
+    fn call_locked<T: DeserializeOwned>(
+        &self,
+        guard: &mut MutexGuard<'_, Option<WorkerProcess>>,
+        request: &Request<'_>,
+    ) -> Result<T, Error> {
+        let process = if let Some(process) = guard.take() {
+            guard.insert(process)
+        } else {
+            log::info!("Restarting KQL worker {}", self.program.display());
+            guard.insert(WorkerProcess::spawn(self)?)
+        };
+        let result = process.call(request);
+        if process.broken {
+            **guard = None;
+        }
+        result
+    }
+}
+
+impl KqlEngine for WorkerValidator {
+    fn validate_syntax(&self, query: &str) -> Result<ValidationResult, Error> {
+        WorkerValidator::validate_syntax(self, query)
+    }
+
+    fn validate_with_schema(
+        &self,
+        query: &str,
+        schema: &Schema,
+    ) -> Result<ValidationResult, Error> {
+        WorkerValidator::validate_with_schema(self, query, schema)
+    }
+
+    fn get_completions(
+        &self,
+        query: &str,
+        cursor_position: usize,
+        schema: Option<&Schema>,
+    ) -> Result<CompletionResult, Error> {
+        WorkerValidator::get_completions(self, query, cursor_position, schema)
+    }
+
+    fn get_classifications(&self, query: &str) -> Result<ClassificationResult, Error> {
+        WorkerValidator::get_classifications(self, query)
+    }
+}
+
+/// A worker process and the number of calls sent to it
+struct PoolSlot {
+    validator: WorkerValidator,
+    in_flight: AtomicUsize,
+}
+
+/// A pool of worker processes for concurrent calls
+///
+/// Each call goes to the worker with the fewest calls in flight, so a slow
+/// query holds up only one worker. Like a single [`WorkerValidator`], a
+/// worker that dies fails its call and is restarted on its next one;
+/// [`check_health`](Self::check_health) restarts dead or unresponsive
+/// workers ahead of time.
+///
+/// # Example
+///
+/// ```no_run
+/// use kql_language_tools::worker::KqlWorkerPool;
+///
+/// let pool = KqlWorkerPool::new(4)?;
+/// std::thread::scope(|scope| {
+///     for query in ["T | take 10", "T | where"] {
+///         let pool = &pool;
+///         scope.spawn(move || pool.validate_syntax(query));
+///     }
+/// });
+/// # Ok::<(), kql_language_tools::Error>(())
+/// ```
+pub struct KqlWorkerPool {
+    workers: Vec<PoolSlot>,
+}
+
+impl KqlWorkerPool {
+    /// Start a pool of `size` workers with the default configuration
+    ///
+    /// Use [`WorkerBuilder::spawn_pool`] to configure the workers.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if any worker cannot be started or fails to load
+    /// the native library.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is zero.
+    pub fn new(size: usize) -> Result<Self, Error> {
+        WorkerValidator::builder().spawn_pool(size)
+    }
+
+    /// Get the number of workers
+    #[must_use]
+    pub fn size(&self) -> usize {
+        self.workers.len()
+    }
+
+    /// Ping every idle worker, restarting those that died or do not answer
+    ///
+    /// Returns the number of workers that are healthy afterwards. Busy
+    /// workers are skipped without waiting and counted as healthy; a call
+    /// that hangs is stopped by the worker's
+    /// [timeout](WorkerBuilder::timeout).
+    #[allow(clippy::must_use_candidate)]
+    pub fn check_health(&self) -> usize {
+        self.workers
+            .iter()
+            .filter(|slot| match slot.validator.try_ping() {
+                None | Some(Ok(())) => true,
+                Some(Err(e)) => {
+                    log::warn!("KQL worker failed its health check: {e}");
+                    slot.validator.try_ping().map_or(true, |ping| ping.is_ok())
+                }
+            })
+            .count()
+    }
+
+    /// Validate a query for syntax errors
+    ///
+    /// See [`KqlValidator::validate_syntax`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the worker or the native call fails.
+    pub fn validate_syntax(&self, query: &str) -> Result<ValidationResult, Error> {
+        self.dispatch(|worker| worker.validate_syntax(query))
+    }
+
+    /// Validate a query against a schema
+    ///
+    /// See [`KqlValidator::validate_with_schema`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the worker or the native call fails.
+    pub fn validate_with_schema(
+        &self,
+        query: &str,
+        schema: &Schema,
+    ) -> Result<ValidationResult, Error> {
+        self.dispatch(|worker| worker.validate_with_schema(query, schema))
+    }
+
+    /// Get completions at a cursor position
+    ///
+    /// See [`KqlValidator::get_completions`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the worker or the native call fails.
+    pub fn get_completions(
+        &self,
+        query: &str,
+        cursor_position: usize,
+        schema: Option<&Schema>,
+    ) -> Result<CompletionResult, Error> {
+        self.dispatch(|worker| worker.get_completions(query, cursor_position, schema))
+    }
+
+    /// Get syntax classifications for highlighting
+    ///
+    /// See [`KqlValidator::get_classifications`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the worker or the native call fails.
+    pub fn get_classifications(&self, query: &str) -> Result<ClassificationResult, Error> {
+        self.dispatch(|worker| worker.get_classifications(query))
+    }
+
+    /// Format a query
+    ///
+    /// See [`KqlValidator::format`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the worker or the native call fails.
+    pub fn format(&self, query: &str, options: &FormatOptions) -> Result<String, Error> {
+        self.dispatch(|worker| worker.format(query, options))
+    }
+
+    /// Analyze the tables, columns and functions a query references
+    ///
+    /// See [`KqlValidator::analyze`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the worker or the native call fails.
+    pub fn analyze(&self, query: &str, schema: &Schema) -> Result<QueryAnalysis, Error> {
+        self.dispatch(|worker| worker.analyze(query, schema))
+    }
+
+    /// Run `call` on the least busy worker
+    fn dispatch<T>(
+        &self,
+        call: impl FnOnce(&WorkerValidator) -> Result<T, Error>,
+    ) -> Result<T, Error> {
+        let index = least_loaded(
+            self.workers
+                .iter()
+                .map(|slot| slot.in_flight.load(Ordering::Relaxed)),
+        );
+        let slot = &self.workers[index];
+        slot.in_flight.fetch_add(1, Ordering::Relaxed);
+        let result = call(&slot.validator);
+        slot.in_flight.fetch_sub(1, Ordering::Relaxed);
+        result
+    }
+}
+
+impl KqlEngine for KqlWorkerPool {
+    fn validate_syntax(&self, query: &str) -> Result<ValidationResult, Error> {
+        KqlWorkerPool::validate_syntax(self, query)
+    }
+
+    fn validate_with_schema(
+        &self,
+        query: &str,
+        schema: &Schema,
+    ) -> Result<ValidationResult, Error> {
+        KqlWorkerPool::validate_with_schema(self, query, schema)
+    }
+
+    fn get_completions(
+        &self,
+        query: &str,
+        cursor_position: usize,
+        schema: Option<&Schema>,
+    ) -> Result<CompletionResult, Error> {
+        KqlWorkerPool::get_completions(self, query, cursor_position, schema)
+    }
+
+    fn get_classifications(&self, query: &str) -> Result<ClassificationResult, Error> {
+        KqlWorkerPool::get_classifications(self, query)
+    }
+}
+
+/// Index of the smallest load, preferring the first on ties
+fn least_loaded(loads: impl Iterator<Item = usize>) -> usize {
+    loads
+        .enumerate()
+        .min_by_key(|&(_, load)| load)
+        .map_or(0, |(index, _)| index)
+}
+
+/// Serve worker requests over stdin and stdout until stdin closes
+///
+/// # Errors
+///
+/// Returns an error if the native library cannot be loaded or the
+/// streams fail.
+pub fn serve_stdio() -> Result<(), Error> {
+    serve(io::stdin().lock(), io::stdout().lock())
+}
+
+/// Serve worker requests read from `reader`, writing responses to `writer`
+///
+/// Returns once `reader` reaches end of stream.
+///
+/// # Errors
+///
+/// Returns an error if the native library cannot be loaded or the
+/// streams fail.
+pub fn serve(reader: impl Read, writer: impl Write) -> Result<(), Error> {
+    let mut reader = BufReader::new(reader);
+    let mut writer = BufWriter::new(writer);
+    let mut validator = KqlValidator::new()?;
+    // Requests come from the parent process, which is trusted
+    while let Some(frame) = read_frame(&mut reader, usize::MAX).map_err(worker_error)? {
+        let response = match serde_json::from_slice::<Request<'_>>(&frame) {
+            Ok(request) => handle(&mut validator, request),
+            Err(e) => Err(Error::from(e)),
+        };
+        let response = match response {
+            Ok(result) => Response {
+                ok: true,
+                result: Some(result),
+                error: None,
+            },
+            Err(e) => Response {
+                ok: false,
+                result: None,
+                error: Some(e.to_string()),
+            },
+        };
+        write_frame(&mut writer, &serde_json::to_vec(&response)?).map_err(worker_error)?;
+    }
+    Ok(())
+}
+
+/// Run one request against the worker's validator
+fn handle(validator: &mut KqlValidator, request: Request<'_>) -> Result<serde_json::Value, Error> {
+    let result = match request {
+        Request::Configure { options, offsets } => {
+            *validator = validator
+                .clone()
+                .with_options(options.into_owned())
+                .with_offset_encoding(offsets);
+            serde_json::Value::Null
+        }
+        Request::Ping => serde_json::Value::Null,
+        Request::ValidateSyntax { query } => {
+            serde_json::to_value(validator.validate_syntax(&query)?)?
+        }
+        Request::ValidateWithSchema { query, schema } => {
+            serde_json::to_value(validator.validate_with_schema(&query, &schema)?)?
+        }
+        Request::GetCompletions {
+            query,
+            cursor,
+            schema,
+        } => serde_json::to_value(validator.get_completions(&query, cursor, schema.as_deref())?)?,
+        Request::GetClassifications { query } => {
+            serde_json::to_value(validator.get_classifications(&query)?)?
+        }
+        Request::Format { query, options } => {
+            serde_json::to_value(validator.format(&query, &options)?)?
+        }
+        Request::Analyze { query, schema } => {
+            serde_json::to_value(validator.analyze(&query, &schema)?)?
+        }
+    };
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::{EngineFeatureLevel, EnginePin};
+    use crate::kind::QueryKind;
+    use crate::limits::ServiceLimits;
+    use crate::profile::FeatureProfile;
+    use crate::schema::Parameter;
+
+    #[test]
+    fn test_frame_roundtrip() {
+        let mut stream = Vec::new();
+        write_frame(&mut stream, br#"{"op":"validate_syntax","query":"T"}"#).unwrap();
+        write_frame(&mut stream, b"").unwrap();
+        assert_eq!(&stream[..4], &36u32.to_le_bytes());
+
+        let mut reader = stream.as_slice();
+        let first = read_frame(&mut reader, 64).unwrap().unwrap();
+        let request: Request<'_> = serde_json::from_slice(&first).unwrap();
+        assert!(matches!(request, Request::ValidateSyntax { query } if query == "T"));
+        assert_eq!(read_frame(&mut reader, 64).unwrap(), Some(Vec::new()));
+        assert_eq!(read_frame(&mut reader, 64).unwrap(), None);
+
+        // A truncated payload is an error, not end of stream
+        let mut truncated: &[u8] = &[10, 0, 0, 0, b'{'];
+        assert!(read_frame(&mut truncated, 64).is_err());
+
+        // So is a frame over the limit, before its payload is allocated
+        let mut oversized: &[u8] = &[0xff, 0xff, 0xff, 0xff];
+        let err = read_frame(&mut oversized, 64).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_configure_carries_options() {
+        let options = ValidationOptions {
+            dialect: Dialect::LogAnalytics,
+            kind: QueryKind::Command,
+            profiles: vec![FeatureProfile::new("alert-rule").allow_operators(["where"])],
+            engine: Some(EnginePin::new(EngineFeatureLevel::new(2023, 1), &[])),
+            limits: Some(ServiceLimits::default()),
+            query_parameters: vec![Parameter::new("since", "datetime")],
+            case_sensitivity_advisory: true,
+            cancellation_id: None,
+        };
+        let request = Request::Configure {
+            options: Cow::Borrowed(&options),
+            offsets: OffsetEncoding::Utf16,
+        };
+
+        let json = serde_json::to_vec(&request).unwrap();
+        let Request::Configure {
+            options: received,
+            offsets,
+        } = serde_json::from_slice(&json).unwrap()
+        else {
+            panic!("not a configure request");
+        };
+        assert_eq!(*received, options);
+        assert_eq!(offsets, OffsetEncoding::Utf16);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_unresponsive_worker_times_out() {
+        use std::os::unix::fs::PermissionsExt;
+        use std::time::Instant;
+
+        // A "worker" that never answers its configure request
+        let program = std::env::temp_dir().join(format!("kql-hung-worker-{}", std::process::id()));
+        std::fs::write(&program, "#!/bin/sh\nexec sleep 30\n").unwrap();
+        std::fs::set_permissions(&program, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let started = Instant::now();
+        let result = WorkerValidator::builder()
+            .program(&program)
+            .timeout(Duration::from_millis(200))
+            .spawn();
+        let _ = std::fs::remove_file(&program);
+        assert!(matches!(result, Err(Error::TimedOut)));
+        assert!(started.elapsed() < Duration::from_secs(10));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_oversized_response_fails_the_worker() {
+        use std::os::unix::fs::PermissionsExt;
+
+        // A "worker" announcing a 4 GiB response, like a runtime writing a
+        // crash report to its output
+        let program = std::env::temp_dir().join(format!("kql-noisy-worker-{}", std::process::id()));
+        std::fs::write(
+            &program,
+            "#!/bin/sh\nprintf '\\377\\377\\377\\377'\nexec sleep 30\n",
+        )
+        .unwrap();
+        std::fs::set_permissions(&program, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let result = WorkerValidator::builder().program(&program).spawn();
+        let _ = std::fs::remove_file(&program);
+        assert!(matches!(result, Err(Error::Worker { message }) if message.contains("byte limit")));
+    }
+
+    #[test]
+    fn test_least_loaded() {
+        assert_eq!(least_loaded([2, 0, 1, 0].into_iter()), 1);
+        assert_eq!(least_loaded([3, 3].into_iter()), 0);
+        assert_eq!(least_loaded(std::iter::empty()), 0);
+    }
+
+    #[test]
+    fn test_response_errors() {
+        let ok: Response =
+            serde_json::from_str(r#"{"ok":true,"result":{"valid":true,"diagnostics":[]}}"#)
+                .unwrap();
+        let result: ValidationResult = serde_json::from_value(ok.result.unwrap()).unwrap();
+        assert!(result.is_valid());
+
+        let failed = Response {
+            ok: false,
+            result: None,
+            error: Some("Formatting not supported".to_string()),
+        };
+        assert_eq!(
+            serde_json::to_string(&failed).unwrap(),
+            r#"{"ok":false,"error":"Formatting not supported"}"#
+        );
+    }
+}